@@ -1,3 +1,6 @@
 mod ring_buffer;
 
 pub use ring_buffer::RingBufferedIterator;
+
+#[cfg(test)]
+mod tests;
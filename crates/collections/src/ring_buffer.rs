@@ -92,8 +92,8 @@ impl<T, const N: usize> RingBuffer<T, N> {
             self.is_full.set(true);
         }
 
-        let index = self.wrap_add(self.end.get(), 1);
-        self.end.set(index);
+        let index = self.end.get();
+        self.end.set(self.wrap_add(index, 1));
 
         // safety: no shared references to the items in the buffer are held outside of the buffer.
         // Only copies of the items are returned, expect for `enqueue` and `dequeue`. Which transfers
@@ -142,6 +142,10 @@ impl<I: Iterator, const N: usize> RingBufferedIterator<I, N> {
         self.peek_nth(0)
     }
 
+    /// The item `n` places ahead, `peek_nth(0)` being the one returned by the next call of
+    /// `next`. Returns `None` if the iterator ends before or `n` is not less than the capacity
+    /// `N`. Looking further ahead, e.g. with a lookahead configured per parser, is deferred
+    /// until the parser has a grammar which needs it.
     pub fn peek_nth(&self, n: usize) -> Option<I::Item> {
         if n >= self.buffer.capacity() {
             return None;
@@ -0,0 +1,12 @@
+use crate::RingBufferedIterator;
+
+#[test]
+fn peek_and_next() {
+    let mut iter: RingBufferedIterator<_, 4> = RingBufferedIterator::new(1..=6);
+    assert_eq!((iter.peek(), iter.peek_nth(2), iter.peek_nth(3)), (Some(1), Some(3), Some(4)));
+    // beyond the capacity of the buffer
+    assert_eq!(iter.peek_nth(4), None);
+    assert_eq!(iter.by_ref().take(3).collect::<Vec<_>>(), [1, 2, 3]);
+    assert_eq!(iter.peek_nth(3), None);
+    assert_eq!(iter.collect::<Vec<_>>(), [4, 5, 6]);
+}
@@ -0,0 +1,134 @@
+//! On-disk cache of lexed files, so unchanged files of a large project are not lexed again on
+//! every run.
+//!
+//! Entries are keyed by a hash of the content and the [`LexerProfile`] of a file, so an entry
+//! never has to be invalidated: a changed file simply has a different key. Every entry is checked
+//! against the text it is used for, a corrupt or colliding entry is lexed again.
+//!
+//! Only the tokens are cached. Definitions, diagnostics and the other analysis results borrow the
+//! source text and are recomputed from the cached tokens.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use parser::{LexedStr, LexedTokens, LexerProfile, SourceKind};
+
+use crate::{parse::analyze, ParseOptions, ParseResult};
+
+/// Directory of the cache relative to the root of a project.
+pub const CACHE_DIR: &str = ".neotex/cache";
+
+/// A cache directory holding the tokens of previously lexed files.
+///
+/// ```
+/// use neotex_core::{cache::ParseCache, ParseOptions};
+///
+/// let dir = std::env::temp_dir().join(format!("neotex-cache-{}", std::process::id()));
+/// let cache = ParseCache::in_project(&dir);
+/// let source = r"\newcommand{\R}{\mathbb{R}} $\R$";
+///
+/// let first = cache.parse(source, ParseOptions::default());
+/// assert_eq!(std::fs::read_dir(dir.join(".neotex/cache"))?.count(), 1);
+/// let second = cache.parse(source, ParseOptions::default());
+/// assert_eq!(first.tokens().syntax_tokens().collect::<Vec<_>>(), second.tokens().syntax_tokens().collect::<Vec<_>>());
+/// assert!(second.definitions().get(r"\R").is_some());
+///
+/// cache.clear()?;
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCache {
+    dir: PathBuf,
+}
+
+impl ParseCache {
+    /// A cache storing its entries in `dir`, which is created on first use.
+    pub fn new(dir: impl Into<PathBuf>) -> ParseCache {
+        ParseCache { dir: dir.into() }
+    }
+
+    /// The cache of the project rooted at the directory `root`, see [`CACHE_DIR`].
+    pub fn in_project(root: &Path) -> ParseCache {
+        ParseCache::new(root.join(CACHE_DIR))
+    }
+
+    /// The directory of the entries.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Like [`parse_with_options`](crate::parse_with_options), but takes the tokens from the cache
+    /// if `source` has been lexed before and stores them otherwise.
+    ///
+    /// The cache is an optimization only: entries which cannot be read or written are ignored.
+    pub fn parse<'source>(
+        &self,
+        source: &'source str,
+        options: ParseOptions,
+    ) -> ParseResult<'source> {
+        analyze(source, options, |source, profile, kind| self.lex(source, profile, kind), None)
+    }
+
+    /// The tokens of `source` lexed as a file of the given kind, either from the cache or freshly
+    /// lexed and stored.
+    pub fn lex<'source>(
+        &self,
+        source: &'source str,
+        profile: LexerProfile,
+        kind: SourceKind,
+    ) -> LexedStr<'source> {
+        let path = self.dir.join(key(source, profile, kind));
+        let cached = fs::read(&path).ok().and_then(|bytes| LexedTokens::from_bytes(&bytes));
+        if let Some(lexed) = cached
+            .filter(|tokens| tokens.profile() == profile)
+            .and_then(|tokens| LexedStr::from_tokens(source, tokens))
+        {
+            return lexed;
+        }
+
+        let lexed = LexedStr::with_source_kind(source, profile, kind);
+        let _ = self.store(&path, &lexed.to_tokens());
+        lexed
+    }
+
+    /// Removes all entries.
+    pub fn clear(&self) -> io::Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Writes an entry atomically, so a concurrent run never reads half of it.
+    fn store(&self, path: &Path, tokens: &LexedTokens) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let temporary = path.with_extension(format!("tmp{}", std::process::id()));
+        fs::write(&temporary, tokens.to_bytes())?;
+        fs::rename(&temporary, path)
+    }
+}
+
+/// File name of the entry of `source`: the 64-bit FNV-1a hash of the profile, the source kind and
+/// the content, followed by the length of the content.
+fn key(source: &str, profile: LexerProfile, kind: SourceKind) -> String {
+    let profile = match profile {
+        LexerProfile::Latex => 0,
+        LexerProfile::NeoTex => 1,
+        LexerProfile::ExpL3 => 2,
+    };
+    let kind = match kind {
+        SourceKind::Document => 0,
+        SourceKind::Package => 1,
+        SourceKind::Class => 2,
+    };
+    let hash = [profile, kind]
+        .into_iter()
+        .chain(source.bytes())
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{hash:016x}-{}.tokens", source.len())
+}
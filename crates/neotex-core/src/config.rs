@@ -0,0 +1,436 @@
+//! Project configuration read from a `neotex.toml`.
+//!
+//! The file is looked up in the directory of a document and its ancestors, so a single file at
+//! the project root configures all documents below it:
+//!
+//! ```toml
+//! profile = "latex"
+//! search-paths = ["chapters", "../shared"]
+//! path-case = "insensitive"
+//! analysis = "text-only"
+//! document = true
+//!
+//! [lints]
+//! dollar-math = true
+//! double-space = false
+//! shell-escape = "error"
+//! overused-phrase = true
+//! phrase-window = 50
+//!
+//! [stages]
+//! expansion = false
+//!
+//! [format]
+//! indent = 4
+//! line-width = 100
+//!
+//! [format.environments]
+//! align = { align = true }
+//! itemize = { hanging-items = true }
+//! code = { verbatim = true }
+//!
+//! [preamble]
+//! order = ["fonts", "math", "other", "references"]
+//!
+//! [commands]
+//! "\\todo" = "[]{}"
+//!
+//! [conditionals]
+//! draft = true
+//! ```
+//!
+//! Every key is optional, missing keys keep their defaults. Unknown keys are an error, so typos
+//! do not go unnoticed.
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use parser::{
+    analysis::{
+        CommandStub, LintConfig, BRACKET_AFTER_LINE_BREAK, DEPRECATED_PACKAGE, DOLLAR_MATH,
+        DOUBLE_SPACE, OVERUSED_PHRASE, REPEATED_WORD, SHELL_ESCAPE, TIE_BEFORE_REFERENCE,
+    },
+    diagnostics::{Severity, Stage, Stages},
+    expansion::ConditionalFlags,
+    format::{EnvironmentRule, FormatOptions, Indent, PackageCategory, PreambleOptions},
+    LexerProfile, SourceKind,
+};
+use toml::{Table, Value};
+
+use crate::{AnalysisProfile, ParseOptions, PathCase};
+
+/// Name of the configuration file.
+pub const CONFIG_FILE: &str = "neotex.toml";
+
+/// The settings of a `neotex.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    /// Lexer profile of all documents, `None` picks it by file extension
+    pub profile: Option<LexerProfile>,
+    /// Directories searched for included files which are not found next to the including file
+    pub search_paths: Vec<PathBuf>,
+    /// How the names of included files are matched
+    pub path_case: PathCase,
+    /// Which parts of the documents are analyzed
+    pub analysis: AnalysisProfile,
+    /// Whether every source has to have a `document` environment, `None` requires one of the
+    /// sources with a `\documentclass`
+    pub document: Option<bool>,
+    /// The lint rules which are run
+    pub lints: LintConfig,
+    /// The analysis stages whose diagnostics are reported
+    pub stages: Stages,
+    /// Options of the formatter
+    pub format: FormatOptions,
+    /// Order of the package groups of an organized preamble
+    pub preamble: PreambleOptions,
+    /// Argument signatures of commands defined outside the project, e.g. in a class file
+    pub commands: Vec<CommandStub>,
+    /// Values of `\newif` flags like `draft` for `\ifdraft`, used to expand conditionals
+    pub conditionals: ConditionalFlags,
+}
+
+/// Error returned when a configuration file cannot be read or is invalid.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file could not be read
+    Io(PathBuf, io::Error),
+    /// The file is not valid TOML or contains an unknown key or a value of the wrong type
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "cannot read {}: {err}", path.display()),
+            ConfigError::Invalid(path, message) => write!(f, "{}: {message}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io(_, err) => Some(err),
+            ConfigError::Invalid(..) => None,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the `neotex.toml` of `dir` or of the closest ancestor containing one, together with
+    /// its path. Returns `None` if there is none.
+    ///
+    /// ```
+    /// use neotex_core::{config::Config, project::Project};
+    ///
+    /// let dir = std::env::temp_dir().join(format!("neotex-config-{}", std::process::id()));
+    /// std::fs::create_dir_all(dir.join("src"))?;
+    /// std::fs::create_dir_all(dir.join("shared"))?;
+    /// std::fs::write(dir.join("neotex.toml"), "search-paths = ['shared']")?;
+    /// std::fs::write(dir.join("src/main.tex"), r"\input{macros}")?;
+    /// std::fs::write(dir.join("shared/macros.tex"), "")?;
+    ///
+    /// let (path, config) = Config::discover(&dir.join("src"))?.unwrap();
+    /// assert_eq!(path, dir.join("neotex.toml"));
+    /// let main = dir.join("src/main.tex");
+    /// let project = Project::load_with_search_paths(&main, config.parse_options(&main), &config.search_paths)?;
+    /// assert_eq!(project.files()[1].path, dir.join("shared/macros.tex"));
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn discover(dir: &Path) -> Result<Option<(PathBuf, Config)>, ConfigError> {
+        let Some(path) =
+            dir.ancestors().map(|dir| dir.join(CONFIG_FILE)).find(|path| path.is_file())
+        else {
+            return Ok(None);
+        };
+        let config = Config::load(&path)?;
+        Ok(Some((path, config)))
+    }
+
+    /// Loads the configuration file at `path`. Search paths are resolved against its directory.
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        let text = fs::read_to_string(path).map_err(|err| ConfigError::Io(path.into(), err))?;
+        let mut config =
+            Config::parse(&text).map_err(|message| ConfigError::Invalid(path.into(), message))?;
+
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for search_path in &mut config.search_paths {
+            *search_path = dir.join(&*search_path);
+        }
+        Ok(config)
+    }
+
+    /// Parses the content of a configuration file.
+    ///
+    /// ```
+    /// use neotex_core::{
+    ///     config::Config,
+    ///     format::{Indent, PackageCategory},
+    ///     LexerProfile,
+    /// };
+    ///
+    /// let config = Config::parse("profile = 'latex'\n[lints]\ndouble-space = false\n[format]\nindent = 'tab'")?;
+    /// assert_eq!(config.profile, Some(LexerProfile::Latex));
+    /// assert!(!config.lints.double_space && config.lints.tie_before_reference);
+    /// assert_eq!(config.format.indent, Indent::Tab);
+    ///
+    /// let config = Config::parse(r#"commands = { '\todo' = "[]{}" }"#)?;
+    /// assert_eq!((config.commands[0].arg_count, config.commands[0].optional), (2, true));
+    /// let config = Config::parse("path-case = 'insensitive'")?;
+    /// assert_eq!(config.path_case, neotex_core::PathCase::Insensitive);
+    /// let config = Config::parse("analysis = 'text-only'")?;
+    /// assert_eq!(config.analysis, neotex_core::AnalysisProfile::TextOnly);
+    /// let config = Config::parse("stages.expansion = false")?;
+    /// assert!(!config.stages.is_enabled(neotex_core::diagnostics::Stage::Expansion));
+    /// let config = Config::parse("lints.shell-escape = 'error'")?;
+    /// assert_eq!(config.lints.shell_escape, Some(neotex_core::diagnostics::Severity::Error));
+    /// let config = Config::parse("[lints]\noverused-phrase = true\nphrase-window = 50")?;
+    /// assert!(config.lints.overused_phrase && config.lints.phrase_window == 50);
+    /// let config = Config::parse("document = false")?;
+    /// assert_eq!(config.document, Some(false));
+    /// let config = Config::parse("conditionals.draft = true")?;
+    /// assert_eq!(config.conditionals.get("draft"), Some(true));
+    /// let config = Config::parse("preamble.order = ['references', 'math']")?;
+    /// assert_eq!(config.preamble.order, [PackageCategory::References, PackageCategory::Math]);
+    /// let config = Config::parse("[format.environments]\nalign = { align = true }")?;
+    /// assert!(config.format.environment("align").align && config.format.environment("align").indent);
+    ///
+    /// let err = Config::parse("[lints]\ndouble-spaces = false").unwrap_err();
+    /// assert_eq!(err, "unknown key 'double-spaces' in [lints]");
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let table: Table =
+            text.parse().map_err(|err: toml::de::Error| err.message().to_string())?;
+        let mut config = Config::default();
+
+        for (key, value) in &table {
+            match key.as_str() {
+                "profile" => {
+                    let profile = match string(key, value)? {
+                        "latex" => LexerProfile::Latex,
+                        "neotex" => LexerProfile::NeoTex,
+                        "expl3" => LexerProfile::ExpL3,
+                        other => return Err(format!("unknown profile '{other}'")),
+                    };
+                    config.profile = Some(profile);
+                }
+                "search-paths" => {
+                    let Value::Array(paths) = value else {
+                        return Err(format!("'{key}' has to be an array of paths"));
+                    };
+                    for path in paths {
+                        config.search_paths.push(string(key, path)?.into());
+                    }
+                }
+                "path-case" => {
+                    config.path_case = match string(key, value)? {
+                        "exact" => PathCase::Exact,
+                        "insensitive" => PathCase::Insensitive,
+                        other => return Err(format!("unknown path case '{other}'")),
+                    };
+                }
+                "analysis" => {
+                    config.analysis = match string(key, value)? {
+                        "full" => AnalysisProfile::Full,
+                        "text-only" => AnalysisProfile::TextOnly,
+                        other => return Err(format!("unknown analysis '{other}'")),
+                    };
+                }
+                "document" => config.document = Some(boolean(key, value)?),
+                "lints" => parse_lints(section(key, value)?, &mut config.lints)?,
+                "stages" => {
+                    for (name, value) in section(key, value)? {
+                        let stage = Stage::from_name(name)
+                            .ok_or_else(|| format!("unknown stage '{name}' in [stages]"))?;
+                        match boolean(name, value)? {
+                            true => config.stages.enable(stage),
+                            false => config.stages.disable(stage),
+                        }
+                    }
+                }
+                "format" => parse_format(section(key, value)?, &mut config.format)?,
+                "preamble" => parse_preamble(section(key, value)?, &mut config.preamble)?,
+                "commands" => parse_commands(section(key, value)?, &mut config.commands)?,
+                "conditionals" => {
+                    for (name, value) in section(key, value)? {
+                        config.conditionals.set(name.as_str(), boolean(name, value)?);
+                    }
+                }
+                _ => return Err(format!("unknown key '{key}'")),
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// The options for parsing the document at `path`: the configured profile or the one of the
+    /// file extension, together with the rest of the configuration. `.sty` and `.cls` files are
+    /// parsed as packages and classes.
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use neotex_core::{config::Config, SourceKind};
+    ///
+    /// let options = Config::default().parse_options(Path::new("mymacros.sty"));
+    /// assert_eq!(options.source_kind, Some(SourceKind::Package));
+    /// ```
+    pub fn parse_options(&self, path: &Path) -> ParseOptions {
+        let profile = self.profile.unwrap_or_else(|| profile(path));
+        let mut options = ParseOptions::new(profile)
+            .with_lints(self.lints)
+            .with_stages(self.stages)
+            .with_commands(self.commands.clone())
+            .with_conditionals(self.conditionals.clone())
+            .with_path_case(self.path_case)
+            .with_analysis(self.analysis);
+        let kind = path.extension().and_then(|ext| SourceKind::from_extension(ext.to_str()?));
+        if let Some(kind) = kind {
+            options = options.with_source_kind(kind);
+        }
+        match self.document {
+            Some(document) => options.with_document(document),
+            None => options,
+        }
+    }
+}
+
+/// NeoTeX extensions are only enabled for `.ntex` files, plain LaTeX must lex unchanged.
+pub fn profile(path: &Path) -> LexerProfile {
+    match path.extension() {
+        Some(ext) if ext == "ntex" => LexerProfile::NeoTex,
+        _ => LexerProfile::Latex,
+    }
+}
+
+fn parse_lints(table: &Table, lints: &mut LintConfig) -> Result<(), String> {
+    for (key, value) in table {
+        // the rule is enabled with the default severity or with an explicit one
+        if key == SHELL_ESCAPE {
+            lints.shell_escape = match value {
+                Value::Boolean(enabled) => enabled.then_some(Severity::Warning),
+                Value::String(severity) if severity == "error" => Some(Severity::Error),
+                Value::String(severity) if severity == "warning" => Some(Severity::Warning),
+                Value::String(severity) if severity == "info" => Some(Severity::Info),
+                _ => {
+                    return Err(format!(
+                        "'{key}' has to be true, false, \"error\", \"warning\" or \"info\""
+                    ))
+                }
+            };
+            continue;
+        }
+        if key == "phrase-window" {
+            lints.phrase_window = integer(key, value)?;
+            continue;
+        }
+        let enabled = match key.as_str() {
+            DOLLAR_MATH => &mut lints.dollar_math,
+            TIE_BEFORE_REFERENCE => &mut lints.tie_before_reference,
+            DOUBLE_SPACE => &mut lints.double_space,
+            DEPRECATED_PACKAGE => &mut lints.deprecated_package,
+            BRACKET_AFTER_LINE_BREAK => &mut lints.bracket_after_line_break,
+            REPEATED_WORD => &mut lints.repeated_word,
+            OVERUSED_PHRASE => &mut lints.overused_phrase,
+            _ => return Err(format!("unknown key '{key}' in [lints]")),
+        };
+        *enabled = boolean(key, value)?;
+    }
+    Ok(())
+}
+
+fn parse_format(table: &Table, format: &mut FormatOptions) -> Result<(), String> {
+    for (key, value) in table {
+        match key.as_str() {
+            "indent" => {
+                format.indent = match value {
+                    Value::String(tab) if tab == "tab" => Indent::Tab,
+                    value => Indent::Spaces(integer(key, value)?),
+                }
+            }
+            "line-width" => format.line_width = Some(integer(key, value)?),
+            "indent-document" => format.indent_document = boolean(key, value)?,
+            "math-spacing" => format.math_spacing = boolean(key, value)?,
+            "environments" => {
+                for (name, value) in section(key, value)? {
+                    let rule = parse_environment(name, section(name, value)?, format)?;
+                    format.environments.insert(name.clone(), rule);
+                }
+            }
+            _ => return Err(format!("unknown key '{key}' in [format]")),
+        }
+    }
+    Ok(())
+}
+
+/// The rule of the environment `name`, keys which are not set keep the default rule.
+fn parse_environment(
+    name: &str,
+    table: &Table,
+    format: &FormatOptions,
+) -> Result<EnvironmentRule, String> {
+    let mut rule = format.environment(name);
+    for (key, value) in table {
+        match key.as_str() {
+            "indent" => rule.indent = boolean(key, value)?,
+            "verbatim" => rule.verbatim = boolean(key, value)?,
+            "align" => rule.align = boolean(key, value)?,
+            "hanging-items" => rule.hanging_items = boolean(key, value)?,
+            _ => return Err(format!("unknown key '{key}' in [format.environments.{name}]")),
+        }
+    }
+    Ok(rule)
+}
+
+fn parse_preamble(table: &Table, preamble: &mut PreambleOptions) -> Result<(), String> {
+    for (key, value) in table {
+        if key != "order" {
+            return Err(format!("unknown key '{key}' in [preamble]"));
+        }
+        let Value::Array(names) = value else {
+            return Err(format!("'{key}' has to be an array of package categories"));
+        };
+        preamble.order.clear();
+        for name in names {
+            let name = string(key, name)?;
+            let category = PackageCategory::from_name(name)
+                .ok_or_else(|| format!("unknown package category '{name}'"))?;
+            preamble.order.push(category);
+        }
+    }
+    Ok(())
+}
+
+fn parse_commands(table: &Table, commands: &mut Vec<CommandStub>) -> Result<(), String> {
+    for (name, value) in table {
+        if !name.starts_with('\\') {
+            return Err(format!("command '{name}' has to start with a backslash"));
+        }
+        let stub = CommandStub::parse(name.as_str(), string(name, value)?);
+        commands.push(
+            stub.ok_or_else(|| format!("'{name}' has to be an argument list like '[]{{}}'"))?,
+        );
+    }
+    Ok(())
+}
+
+fn section<'a>(key: &str, value: &'a Value) -> Result<&'a Table, String> {
+    value.as_table().ok_or_else(|| format!("'{key}' has to be a table"))
+}
+
+fn string<'a>(key: &str, value: &'a Value) -> Result<&'a str, String> {
+    value.as_str().ok_or_else(|| format!("'{key}' has to be a string"))
+}
+
+fn boolean(key: &str, value: &Value) -> Result<bool, String> {
+    value.as_bool().ok_or_else(|| format!("'{key}' has to be true or false"))
+}
+
+fn integer(key: &str, value: &Value) -> Result<usize, String> {
+    let integer = value.as_integer().and_then(|integer| usize::try_from(integer).ok());
+    integer.ok_or_else(|| format!("'{key}' has to be a positive integer"))
+}
@@ -0,0 +1,183 @@
+#![doc(
+    html_logo_url = "https://gist.githubusercontent.com/leonfuss/9247909d6237cb406439944fe22405a4/raw/02853019c2e0187bfb518dba1052ed5338c144c8/logo.svg"
+)]
+#![doc(
+    html_favicon_url = "https://gist.githubusercontent.com/leonfuss/fa44f11267796b352edaa121675cd6f2/raw/ae237aa701a5d087c89af313299858f5e1c1144f/favicon.svg"
+)]
+#![deny(missing_docs)]
+
+//! **Part of [NeoTeX](../neotex/index.html)**
+//!
+//! The public API of NeoTeX. Tools built on top of NeoTeX, including the `neotex` CLI, should
+//! only depend on this crate. The internal crates are free to change their layout, everything
+//! re-exported here is kept stable.
+//!
+//! Most users only need [`parse`], which runs every pass on a source text, and the [`prelude`]:
+//!
+//! ```
+//! use neotex_core::prelude::*;
+//!
+//! let result = parse(r"\newcommand{\R}{\mathbb{R}}");
+//! assert!(result.definitions().get(r"\R").is_some());
+//! assert!(result.diagnostics().is_empty());
+//! ```
+//!
+//! # Features
+//!
+//! Every feature only adds to the API, so an embedder can turn off the default features and pick
+//! the subsystems it needs:
+//!
+//! - `config` (default): the `config` module reading a `neotex.toml`, which pulls in a TOML
+//!   parser.
+//! - `lint` (default): the style rules of `analysis::lint`, run by [`parse`] and reported by
+//!   `ParseResult::lints`. Without it [`ParseOptions::lints`] is ignored.
+//! - `spellcheck`: `analysis::spell_check` and the `Dictionary` it checks the words of the text
+//!   against.
+//! - `serde`: `Serialize` for exported data like
+//!   [`ExportedDefinition`](crate::expansion::ExportedDefinition), the
+//!   [`Report`](crate::report::Report) and the [`Usage`](crate::usage::Usage).
+//!
+//! The language server is the separate `neotex-lsp` crate built on top of this one.
+
+pub mod cache;
+#[cfg(feature = "config")]
+pub mod config;
+mod options;
+mod parse;
+pub mod project;
+pub mod report;
+mod shared;
+mod snippets;
+mod symbols;
+pub mod usage;
+
+pub use options::{
+    AnalysisProfile, Limit, LimitExceeded, Limits, ParseOptions, PathCase, LIMIT_EXCEEDED,
+};
+pub use parse::{parse, parse_with_options, parse_with_profile, ParseResult};
+pub use shared::SharedParse;
+pub use snippets::{analyze_snippets, analyze_snippets_with_options, SnippetResult};
+
+pub use parser::{
+    CompactLexedStr, LexedBuffer, LexedStr, LexedTokens, LexerProfile, SourceKind, SourceText,
+    SpannedToken, SyntaxKind, SyntaxKindSet, TokenAtOffset, TokenEmitter, Trivia,
+};
+
+/// Named ranges which follow the edits of a document.
+pub mod anchors {
+    pub use parser::anchors::{map_offset, map_range, Anchors, Bias};
+}
+
+/// Semantic analysis passes working on the whole document.
+pub mod analysis {
+    pub use parser::analysis::{
+        check_arguments, check_references, completions, fuzzy_match, graphics, hover, name_at,
+        shell_escapes, signature_at, symbols, text_runs, word_count, word_frequencies, words,
+        CommandStub, Completion, CompletionKind, CompletionOrigin, Completions, Graphic,
+        GraphicKind, HoverInfo, LintConfig, Parameter, ProseWord, ResolvedSignature, SectionWords,
+        ShellEscape, ShellEscapeKind, SignatureOrigin, Symbol, SymbolKind, TextRun, WordCount,
+        WordCountOptions, WriteStream, BRACKET_AFTER_LINE_BREAK, DEPRECATED_PACKAGE, DOLLAR_MATH,
+        DOUBLE_SPACE, DUPLICATE_LABEL, GRAPHICS_COMMANDS, GRAPHICS_ENVIRONMENTS, MIN_WORD_LEN,
+        MISSING_ARGUMENT, OVERUSED_PHRASE, REPEATED_WORD, SHELL_ESCAPE, SHELL_ESCAPE_PACKAGES,
+        STOP_WORDS, TEXT_COMMANDS, TIE_BEFORE_REFERENCE, UNDEFINED_REFERENCE, UNKNOWN_WORD,
+        UNUSED_LABEL,
+    };
+    #[cfg(feature = "lint")]
+    pub use parser::analysis::{lint, Lint};
+    #[cfg(feature = "spellcheck")]
+    pub use parser::analysis::{spell_check, Dictionary};
+
+    pub use crate::symbols::{goto_definition, workspace_symbols, DefinitionSite, WorkspaceSymbol};
+}
+
+/// Typed view of the structure of a document.
+pub mod ast {
+    pub use parser::ast::{
+        alignment, content_model, missing_document, Alignment, AstNode, Branch, Cell,
+        CommandDefinition, Conditional, ContentModel, Document, DocumentBody, Environment,
+        EnvironmentDefinition, MathBlock, MissingPackage, NodeKind, OverlaySpec, Preamble, Row,
+        SlideRange, SyntaxNode, TreeSink, COLUMN_COUNT, CONTENT_AFTER_DOCUMENT, MISSING_DOCUMENT,
+        MISSING_ITEM, MISSING_PACKAGE, OVERLAY_COMMANDS, PARAGRAPH_BREAK, UNBALANCED_BRACE,
+        UNCLOSED_AT_EOF,
+    };
+}
+
+/// Characters written by their code, e.g. `^^e9` or `\char"E9`.
+pub mod charcode {
+    pub use parser::charcode::{char_codes, CharCode, CharCodeError, Notation, INVALID_CHAR_CODE};
+}
+
+/// Diagnostics reported by the analysis passes.
+pub mod diagnostics {
+    pub use parser::diagnostics::{
+        explain, fixes, snippet, truncate, Diagnostic, DiagnosticSink, Explanation, Related,
+        Severity, SnippetLine, Stage, Stages, ELLIPSIS, EXPLANATIONS, UNUSED_SUPPRESSION,
+    };
+}
+
+/// Typed values of TeX dimensions and glue.
+pub mod dimension {
+    pub use parser::dimension::{Dimension, DimensionError, GlueComponent, Infinity, Length, Unit};
+}
+
+/// Resolution and expansion of user macro definitions.
+pub mod expansion {
+    pub use parser::expansion::{
+        conditionals, join_ranges, resolve, CacheStats, ConditionalBlock, ConditionalFlags,
+        DefPattern, DefinitionKind, ExpansionArgs, ExpansionCache, ExpansionStep, ExpansionStore,
+        ExpansionStoreItem, ExpansionTrace, ExportedDefinition, ParagraphInArgument, PreviewError,
+        Recursion, RegisterItem, RegisterKind, Registers, RenameError, ResolverError,
+        ResolverErrorKind, Shadowing, TextEdit, PARAGRAPH_IN_ARGUMENT, RECURSIVE_DEFINITION,
+        SHADOWED_COMMAND,
+    };
+}
+
+/// References between the files of a project.
+pub mod files {
+    pub use parser::files::{
+        document_class, file_references, main_file, rename_file, FileEdit, FileReference,
+        GRAPHIC_EXTENSIONS, SUBFILE_CLASSES,
+    };
+}
+
+/// Index of the citations, labels and references of a document.
+pub mod index {
+    pub use parser::index::{DocumentIndex, IndexEntry, IndexKind};
+}
+
+/// Formatter reprinting the source text with normalized whitespace.
+pub mod format {
+    pub use parser::format::{
+        format, organize_preamble, EnvironmentRule, FormatOptions, Indent, PackageCategory,
+        PreambleError, PreambleOptions,
+    };
+}
+
+/// Outline of the sectioning commands of a document.
+pub mod outline {
+    pub use parser::outline::{Outline, Section, SectionLevel};
+}
+
+/// Signatures of the standard commands of LaTeX and common packages.
+pub mod registry {
+    pub use parser::registry::{
+        command, commands, environment, environments, BuiltinCommand, BuiltinEnvironment,
+    };
+}
+
+/// Classification of tokens for semantic highlighting.
+pub mod semantic {
+    pub use parser::semantic::{
+        encode, semantic_tokens, SemanticModifier, SemanticToken, SemanticTokenType,
+    };
+}
+
+/// The most commonly used types and entry points.
+pub mod prelude {
+    pub use crate::{
+        diagnostics::{Diagnostic, DiagnosticSink, Severity},
+        expansion::{self, ExpansionStore},
+        parse, parse_with_options, parse_with_profile, LexedStr, LexerProfile, Limits,
+        ParseOptions, ParseResult, SpannedToken, SyntaxKind,
+    };
+}
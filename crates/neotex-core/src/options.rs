@@ -0,0 +1,271 @@
+use std::{fmt, ops::Range};
+
+use parser::{
+    analysis::{CommandStub, LintConfig},
+    diagnostics::{Diagnostic, Severity, Stages},
+    expansion::ConditionalFlags,
+    LexerProfile, SourceKind,
+};
+
+/// Rule id of the diagnostic reported when a [`Limit`] is exceeded.
+pub const LIMIT_EXCEEDED: &str = "limit-exceeded";
+
+/// Options of [`parse_with_options`](crate::parse_with_options) and
+/// [`Project::load`](crate::project::Project::load).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ParseOptions {
+    /// The dialect of the source
+    pub profile: LexerProfile,
+    /// The kind of file the source is, `None` detects it from a `\ProvidesPackage` or
+    /// `\ProvidesClass`
+    pub source_kind: Option<SourceKind>,
+    /// Bounds on the resources spent on a source
+    pub limits: Limits,
+    /// The lint rules which are run
+    pub lints: LintConfig,
+    /// The stages whose diagnostics are reported
+    pub stages: Stages,
+    /// Signatures of commands which are neither defined in the source nor known to the registry
+    pub commands: Vec<CommandStub>,
+    /// Values of the `\newif` flags, which select the branch of a conditional during expansion
+    pub conditionals: ConditionalFlags,
+    /// How the names of included files are matched
+    pub path_case: PathCase,
+    /// Which parts of the documents are analyzed
+    pub analysis: AnalysisProfile,
+    /// Whether the source has to have a `document` environment, `None` requires one if it has a
+    /// `\documentclass`
+    pub document: Option<bool>,
+}
+
+impl ParseOptions {
+    /// Options for the given dialect without any limits.
+    pub fn new(profile: LexerProfile) -> ParseOptions {
+        ParseOptions {
+            profile,
+            source_kind: None,
+            limits: Limits::default(),
+            lints: LintConfig::default(),
+            stages: Stages::default(),
+            commands: Vec::new(),
+            conditionals: ConditionalFlags::new(),
+            path_case: PathCase::default(),
+            analysis: AnalysisProfile::default(),
+            document: None,
+        }
+    }
+
+    /// Sets the kind of file the source is, e.g. [`SourceKind::Package`] for a `.sty` file.
+    pub fn with_source_kind(mut self, source_kind: SourceKind) -> ParseOptions {
+        self.source_kind = Some(source_kind);
+        self
+    }
+
+    /// Replaces the limits.
+    pub fn with_limits(mut self, limits: Limits) -> ParseOptions {
+        self.limits = limits;
+        self
+    }
+
+    /// Replaces the enabled lint rules.
+    pub fn with_lints(mut self, lints: LintConfig) -> ParseOptions {
+        self.lints = lints;
+        self
+    }
+
+    /// Replaces the stages whose diagnostics are reported.
+    pub fn with_stages(mut self, stages: Stages) -> ParseOptions {
+        self.stages = stages;
+        self
+    }
+
+    /// Replaces the signatures of unknown commands.
+    pub fn with_commands(mut self, commands: Vec<CommandStub>) -> ParseOptions {
+        self.commands = commands;
+        self
+    }
+
+    /// Replaces the values of the conditional flags.
+    pub fn with_conditionals(mut self, conditionals: ConditionalFlags) -> ParseOptions {
+        self.conditionals = conditionals;
+        self
+    }
+
+    /// Replaces how the names of included files are matched.
+    pub fn with_path_case(mut self, path_case: PathCase) -> ParseOptions {
+        self.path_case = path_case;
+        self
+    }
+
+    /// Replaces which parts of the documents are analyzed.
+    pub fn with_analysis(mut self, analysis: AnalysisProfile) -> ParseOptions {
+        self.analysis = analysis;
+        self
+    }
+
+    /// Sets whether the source has to have a `document` environment, e.g. `false` for a class
+    /// file with an example `\documentclass` in a comment.
+    pub fn with_document(mut self, document: bool) -> ParseOptions {
+        self.document = Some(document);
+        self
+    }
+}
+
+/// How [`Project::load`](crate::project::Project::load) matches the names of included files.
+///
+/// The policy is the same on every platform, so a project loads the same files everywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathCase {
+    /// Names have to match exactly, like on Linux
+    #[default]
+    Exact,
+    /// Names differing in case match if there is no exact match, like on macOS and Windows
+    Insensitive,
+}
+
+/// Which parts of the documents are analyzed.
+///
+/// ```
+/// use neotex_core::{parse_with_options, AnalysisProfile, ParseOptions};
+///
+/// let source = r"\begin{tikzpicture}\draw (0,0) -- \unknown{;\end{tikzpicture} Text.  More";
+/// let full = parse_with_options(source, ParseOptions::default());
+/// let options = ParseOptions::default().with_analysis(AnalysisProfile::TextOnly);
+/// let text = parse_with_options(source, options);
+///
+/// let rules = |result: &neotex_core::ParseResult<'_>| {
+///     result.diagnostics().iter().map(|diagnostic| diagnostic.rule).collect::<Vec<_>>()
+/// };
+/// assert!(rules(&full).contains(&"unbalanced-brace"));
+/// assert_eq!(rules(&text), ["double-space"]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnalysisProfile {
+    /// Everything
+    #[default]
+    Full,
+    /// Prose and structure only, for tools like style checks which do not care about pictures.
+    /// [`graphics`](crate::analysis::graphics) are opaque placeholders: nothing inside of them
+    /// is reported and pictures included with `\input`, e.g. `plot.pgf`, are not loaded by a
+    /// [`Project`](crate::project::Project).
+    TextOnly,
+}
+
+/// Bounds on the memory and time spent on a source.
+///
+/// The default is unlimited, which is fine for the files of a user. Services analyzing untrusted
+/// input, e.g. a web playground, should start from [`Limits::untrusted`]. Exceeding a limit is
+/// reported as a [`LIMIT_EXCEEDED`] diagnostic and the affected analysis is skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum size of a single file in bytes, larger files are not lexed
+    pub max_file_size: usize,
+    /// Maximum number of tokens of a single file, larger files are not analyzed
+    pub max_tokens: usize,
+    /// Maximum size of the expanded text of a file in bytes
+    pub max_expansion_output: usize,
+    /// Maximum nesting of `\input` and `\include`, the root file has depth 0
+    pub max_include_depth: usize,
+    /// Maximum number of files included by a single file
+    pub max_includes: usize,
+}
+
+impl Limits {
+    /// Limits suitable for untrusted input: 1 MiB per file, 250k tokens, 4 MiB of expanded text
+    /// and at most 64 files included up to 8 levels deep.
+    pub const fn untrusted() -> Limits {
+        Limits {
+            max_file_size: 1 << 20,
+            max_tokens: 250_000,
+            max_expansion_output: 4 << 20,
+            max_include_depth: 8,
+            max_includes: 64,
+        }
+    }
+
+    /// Returns an error if `value` exceeds the maximum of `limit`.
+    pub fn check(&self, limit: Limit, value: usize) -> Result<(), LimitExceeded> {
+        let max = match limit {
+            Limit::FileSize => self.max_file_size,
+            Limit::Tokens => self.max_tokens,
+            Limit::ExpansionOutput => self.max_expansion_output,
+            Limit::IncludeDepth => self.max_include_depth,
+            Limit::Includes => self.max_includes,
+        };
+        match value > max {
+            true => Err(LimitExceeded { limit, max, range: 0..0 }),
+            false => Ok(()),
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            max_file_size: usize::MAX,
+            max_tokens: usize::MAX,
+            max_expansion_output: usize::MAX,
+            max_include_depth: usize::MAX,
+            max_includes: usize::MAX,
+        }
+    }
+}
+
+/// One of the [`Limits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Limit {
+    /// [`Limits::max_file_size`]
+    FileSize,
+    /// [`Limits::max_tokens`]
+    Tokens,
+    /// [`Limits::max_expansion_output`]
+    ExpansionOutput,
+    /// [`Limits::max_include_depth`]
+    IncludeDepth,
+    /// [`Limits::max_includes`]
+    Includes,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Limit::FileSize => "file size",
+            Limit::Tokens => "number of tokens",
+            Limit::ExpansionOutput => "expanded text size",
+            Limit::IncludeDepth => "include depth",
+            Limit::Includes => "number of included files",
+        })
+    }
+}
+
+/// A [`Limit`] which was exceeded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitExceeded {
+    /// The exceeded limit
+    pub limit: Limit,
+    /// The configured maximum
+    pub max: usize,
+    /// Byte range the limit was exceeded at, empty at the start for whole files
+    pub range: Range<usize>,
+}
+
+impl LimitExceeded {
+    /// Moves the error to `range`, e.g. the `\input` exceeding the include depth.
+    pub fn at(mut self, range: Range<usize>) -> LimitExceeded {
+        self.range = range;
+        self
+    }
+
+    /// Converts the error into an error [`Diagnostic`].
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic::new(LIMIT_EXCEEDED, Severity::Error, self.range.clone(), self.to_string())
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} exceeds the limit of {}", self.limit, self.max)
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
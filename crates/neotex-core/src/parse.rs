@@ -0,0 +1,331 @@
+use parser::{
+    analysis::{self, CommandStub, Completions, HoverInfo, ResolvedSignature},
+    ast::{missing_document, Document, MISSING_PACKAGE},
+    charcode,
+    diagnostics::{Diagnostic, DiagnosticSink, Severity},
+    expansion::{self, ExpansionCache, ExpansionStore},
+    index::DocumentIndex,
+    outline::Outline,
+    semantic::{self, SemanticToken},
+    LexedStr, LexerProfile, SourceKind,
+};
+
+#[cfg(feature = "lint")]
+use parser::analysis::Lint;
+
+use crate::{snippets::Preamble, AnalysisProfile, Limit, LimitExceeded, Limits, ParseOptions};
+
+/// Lexes `source` with the default [`LexerProfile`] and runs all analysis passes on it.
+///
+/// ```
+/// let result = neotex_core::parse(r"\newcommand{\R}{\mathbb{R}} \newcommand{\broken}");
+///
+/// assert!(result.definitions().get(r"\R").is_some());
+/// assert_eq!(result.diagnostics()[0].rule, "missing-body");
+/// assert!(result.has_errors());
+/// ```
+pub fn parse(source: &str) -> ParseResult<'_> {
+    parse_with_profile(source, LexerProfile::default())
+}
+
+/// Lexes `source` as the given dialect and runs all analysis passes on it.
+pub fn parse_with_profile(source: &str, profile: LexerProfile) -> ParseResult<'_> {
+    parse_with_options(source, ParseOptions::new(profile))
+}
+
+/// Lexes `source` and runs all analysis passes on it, staying within the [`Limits`] of
+/// `options`.
+///
+/// Sources exceeding the file size or token limit are not analyzed, the result only contains a
+/// [`LIMIT_EXCEEDED`](crate::LIMIT_EXCEEDED) diagnostic.
+///
+/// ```
+/// use neotex_core::{parse_with_options, Limits, ParseOptions};
+///
+/// let limits = Limits { max_tokens: 8, ..Limits::untrusted() };
+/// let result = parse_with_options(r"\newcommand{\R}{\mathbb{R}}", ParseOptions::default().with_limits(limits));
+///
+/// assert_eq!(result.diagnostics()[0].rule, "limit-exceeded");
+/// assert!(result.definitions().get(r"\R").is_none());
+/// ```
+pub fn parse_with_options(source: &str, options: ParseOptions) -> ParseResult<'_> {
+    analyze(source, options, LexedStr::with_source_kind, None)
+}
+
+/// Runs all analysis passes on `source`, which is lexed by `lex`. The definitions and packages of
+/// `preamble` are available to `source`, see [`analyze_snippets`](crate::analyze_snippets).
+pub(crate) fn analyze<'source>(
+    source: &'source str,
+    options: ParseOptions,
+    lex: impl FnOnce(&'source str, LexerProfile, SourceKind) -> LexedStr<'source>,
+    preamble: Option<&Preamble<'source>>,
+) -> ParseResult<'source> {
+    let limits = options.limits;
+    if let Err(err) = limits.check(Limit::FileSize, source.len()) {
+        return ParseResult::skipped(
+            LexedStr::with_profile(&source[..0], options.profile),
+            options,
+            err,
+        );
+    }
+    let kind = options.source_kind.unwrap_or_else(|| SourceKind::detect(source));
+    let tokens = lex(source, options.profile, kind);
+    if let Err(err) = limits.check(Limit::Tokens, tokens.len()) {
+        return ParseResult::skipped(tokens, options, err);
+    }
+
+    let mut definitions = expansion::resolve(&tokens);
+    if let Some(preamble) = preamble {
+        definitions.inherit(&preamble.definitions);
+    }
+    definitions.set_flags(options.conditionals.clone());
+
+    let mut sink = DiagnosticSink::new(&tokens).with_stages(options.stages);
+    for error in definitions.errors() {
+        sink.push(error.to_diagnostic(&tokens));
+    }
+    for shadowing in definitions.shadowed_commands(&tokens) {
+        sink.push(shadowing.to_diagnostic());
+    }
+    for recursion in definitions.recursions(&tokens) {
+        sink.push(recursion.to_diagnostic());
+    }
+    for paragraph in definitions.paragraphs_in_arguments(&tokens) {
+        sink.push(paragraph.to_diagnostic());
+    }
+    let document = Document::new(&tokens);
+    // commands of packages loaded by the preamble are not missing
+    let loaded: Vec<_> = match preamble {
+        Some(preamble) => document
+            .missing_packages()
+            .into_iter()
+            .filter(|missing| preamble.packages.contains(&missing.package))
+            .map(|missing| missing.uses[0].clone())
+            .collect(),
+        None => Vec::new(),
+    };
+    for diagnostic in document.diagnostics() {
+        if diagnostic.rule == MISSING_PACKAGE && loaded.contains(&diagnostic.range) {
+            continue;
+        }
+        sink.push(diagnostic.clone());
+    }
+    if let Some(diagnostic) = missing_document(&document, options.document) {
+        sink.push(diagnostic);
+    }
+    for diagnostic in analysis::check_references(&document) {
+        sink.push(diagnostic);
+    }
+    for diagnostic in analysis::check_arguments(&document, &definitions, &options.commands) {
+        sink.push(diagnostic);
+    }
+    for diagnostic in charcode::char_codes(&tokens).iter().filter_map(|code| code.to_diagnostic()) {
+        sink.push(diagnostic);
+    }
+    #[cfg(feature = "lint")]
+    let mut lints = analysis::lint(&document, &options.lints);
+    #[cfg(feature = "lint")]
+    for lint in &lints {
+        sink.push(lint.to_diagnostic());
+    }
+    let mut diagnostics = sink.finish();
+    // pictures are placeholders for text-only tools, nothing inside of them is reported
+    if options.analysis == AnalysisProfile::TextOnly {
+        let graphics: Vec<_> =
+            analysis::graphics(&document).into_iter().map(|graphic| graphic.range).collect();
+        let opaque = |range: &std::ops::Range<usize>| {
+            graphics.iter().any(|graphic| graphic.contains(&range.start))
+        };
+        diagnostics.retain(|diagnostic| !opaque(&diagnostic.range));
+        #[cfg(feature = "lint")]
+        lints.retain(|lint| !opaque(&lint.range));
+    }
+    let index = DocumentIndex::new(&tokens);
+    let outline = Outline::new(&tokens);
+
+    ParseResult {
+        tokens,
+        definitions,
+        index,
+        outline,
+        #[cfg(feature = "lint")]
+        lints,
+        diagnostics,
+        limits,
+        commands: options.commands,
+    }
+}
+
+/// The result of [`parse`]: the tokens of a source text together with everything the analysis
+/// passes found out about it.
+///
+/// New passes add new accessors, so the fields are kept private.
+#[derive(Debug)]
+pub struct ParseResult<'source> {
+    tokens: LexedStr<'source>,
+    definitions: ExpansionStore<'source>,
+    index: DocumentIndex<'source>,
+    outline: Outline<'source>,
+    #[cfg(feature = "lint")]
+    lints: Vec<Lint>,
+    diagnostics: Vec<Diagnostic>,
+    limits: Limits,
+    commands: Vec<CommandStub>,
+}
+
+impl<'source> ParseResult<'source> {
+    /// The result of a source which was not analyzed because it exceeds a limit.
+    fn skipped(tokens: LexedStr<'source>, options: ParseOptions, err: LimitExceeded) -> Self {
+        ParseResult {
+            tokens,
+            definitions: ExpansionStore::default(),
+            index: DocumentIndex::default(),
+            outline: Outline::default(),
+            #[cfg(feature = "lint")]
+            lints: Vec::new(),
+            diagnostics: vec![err.to_diagnostic()],
+            limits: options.limits,
+            commands: options.commands,
+        }
+    }
+
+    /// The source text.
+    pub fn source(&self) -> &'source str {
+        self.tokens.as_str()
+    }
+
+    /// The tokens of the source text.
+    pub fn tokens(&self) -> &LexedStr<'source> {
+        &self.tokens
+    }
+
+    /// All macro and environment definitions.
+    pub fn definitions(&self) -> &ExpansionStore<'source> {
+        &self.definitions
+    }
+
+    /// Citations, labels and references.
+    pub fn index(&self) -> &DocumentIndex<'source> {
+        &self.index
+    }
+
+    /// The sectioning commands nested by level.
+    pub fn outline(&self) -> &Outline<'source> {
+        &self.outline
+    }
+
+    /// Findings of the enabled lint rules together with their fixes, see
+    /// [`lint`](analysis::lint). They are part of the [`diagnostics`](Self::diagnostics) as well.
+    ///
+    /// ```
+    /// let result = neotex_core::parse(r"\usepackage{epsfig} see Table \ref{tab}.  Then");
+    ///
+    /// let rules: Vec<_> = result.lints().iter().map(|lint| lint.rule).collect();
+    /// assert_eq!(rules, ["tie-before-reference", "double-space", "deprecated-package"]);
+    /// assert_eq!(result.lints()[2].fix[0].new_text, "graphicx");
+    /// ```
+    #[cfg(feature = "lint")]
+    pub fn lints(&self) -> &[Lint] {
+        &self.lints
+    }
+
+    /// Command or environment names completing the partial name at the byte `offset`, see
+    /// [`completions`](analysis::completions).
+    ///
+    /// ```
+    /// let result = neotex_core::parse(r"\newcommand{\Real}{\mathbb{R}} $\Re");
+    ///
+    /// let completions = result.completions(result.source().len()).unwrap();
+    /// let names: Vec<_> = completions.items.iter().map(|item| item.name).collect();
+    /// assert_eq!(names, [r"\Re", r"\Real", r"\RequirePackage"]);
+    /// ```
+    pub fn completions(&self, offset: usize) -> Option<Completions<'source>> {
+        let document = Document::new(&self.tokens);
+        analysis::completions(&document, &self.definitions, offset)
+    }
+
+    /// The signature of the innermost command call whose arguments contain the byte `offset`,
+    /// including the stubs of the [`ParseOptions`], see [`signature_at`](analysis::signature_at).
+    ///
+    /// ```
+    /// let result = neotex_core::parse(r"\newcommand{\pair}[2]{(#1, #2)} $\pair{a}{");
+    ///
+    /// let signature = result.signature_at(result.source().len()).unwrap();
+    /// assert_eq!((signature.label.as_str(), signature.active), (r"\pair{#1}{#2}", Some(1)));
+    /// ```
+    pub fn signature_at(&self, offset: usize) -> Option<ResolvedSignature<'source>> {
+        let document = Document::new(&self.tokens);
+        analysis::signature_at(&document, &self.definitions, &self.commands, offset)
+    }
+
+    /// The definition of the user command at the byte `offset` together with its `%%`
+    /// documentation, see [`hover`](analysis::hover).
+    ///
+    /// ```
+    /// let result = neotex_core::parse("%% The reals\n\\newcommand{\\R}{\\mathbb{R}} $\\R$");
+    ///
+    /// let info = result.hover(result.source().len() - 1).unwrap();
+    /// assert_eq!((info.signature.as_str(), info.body), (r"\R", r"\mathbb{R}"));
+    /// assert_eq!(info.documentation.as_deref(), Some("The reals"));
+    /// ```
+    pub fn hover(&self, offset: usize) -> Option<HoverInfo<'source>> {
+        let document = Document::new(&self.tokens);
+        analysis::hover(&document, &self.definitions, offset)
+    }
+
+    /// The classified tokens for semantic highlighting, see
+    /// [`semantic_tokens`](crate::semantic::semantic_tokens).
+    ///
+    /// ```
+    /// use neotex_core::semantic::SemanticTokenType;
+    ///
+    /// let result = neotex_core::parse(r"\newcommand{\R}{\mathbb{R}} % reals");
+    /// let kinds: Vec<_> = result.semantic_tokens().iter().map(|token| token.kind).collect();
+    /// assert_eq!(kinds[0], SemanticTokenType::Keyword);
+    /// assert_eq!(kinds.last(), Some(&SemanticTokenType::Comment));
+    /// ```
+    pub fn semantic_tokens(&self) -> Vec<SemanticToken> {
+        semantic::semantic_tokens(&self.tokens, &self.definitions)
+    }
+
+    /// The source text with every call of a user command expanded once.
+    ///
+    /// Fails if the text grows beyond [`Limits::max_expansion_output`].
+    pub fn expanded_text(&self) -> Result<String, LimitExceeded> {
+        self.expanded_text_with_cache(&mut ExpansionCache::new())
+    }
+
+    /// Like [`expanded_text`](Self::expanded_text), but reuses the expansions in `cache`, e.g.
+    /// of the result before the last edit of the source.
+    ///
+    /// ```
+    /// use neotex_core::{expansion::ExpansionCache, parse};
+    ///
+    /// let mut cache = ExpansionCache::new();
+    /// parse(r"\newcommand{\R}{\mathbb{R}} $\R$").expanded_text_with_cache(&mut cache)?;
+    /// let edited = parse(r"\newcommand{\R}{\mathbb{R}} $x \in \R$");
+    /// assert_eq!(edited.expanded_text_with_cache(&mut cache)?, edited.expanded_text()?);
+    /// assert_eq!(cache.stats().hit_rate(), 0.5);
+    /// # Ok::<(), neotex_core::LimitExceeded>(())
+    /// ```
+    pub fn expanded_text_with_cache(
+        &self,
+        cache: &mut ExpansionCache,
+    ) -> Result<String, LimitExceeded> {
+        let max = self.limits.max_expansion_output;
+        let range = 0..self.tokens.len();
+        self.tokens.text_with_cached_expansion(range, &self.definitions, max, cache).ok_or(
+            LimitExceeded { limit: Limit::ExpansionOutput, max, range: 0..self.source().len() },
+        )
+    }
+
+    /// Diagnostics of all passes, comment suppressions are already applied.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns `true` if any diagnostic is an error.
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+}
@@ -0,0 +1,430 @@
+//! Loading of a root file together with all files it includes.
+//!
+//! A [`Project`] follows the `\input`s of a document within the include [`Limits`] of its
+//! [`ParseOptions`], so a malicious document cannot make the loader read an unbounded number of
+//! files.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use parser::{
+    diagnostics::{Diagnostic, Severity},
+    files::{document_class, file_references, main_file, FileReference, SUBFILE_CLASSES},
+    LexedStr,
+};
+
+use crate::{
+    cache::ParseCache, parse::analyze, parse_with_options, snippets::Preamble, AnalysisProfile,
+    Limit, LimitExceeded, Limits, ParseOptions, ParseResult, PathCase,
+};
+
+/// Rule id of the diagnostic reported for includes which resolve to different files depending on
+/// whether the file system is case-sensitive.
+pub const AMBIGUOUS_PATH: &str = "ambiguous-path";
+
+/// Number of parent directories [`Project::find_root`] searches above the directory of a subfile.
+pub const ROOT_SEARCH_DEPTH: usize = 2;
+
+/// A file of a [`Project`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectFile {
+    /// Path of the file
+    pub path: PathBuf,
+    /// Content of the file, empty if it exceeds [`Limits::max_file_size`]
+    pub source: String,
+    /// Number of includes between the root file and this file
+    pub depth: usize,
+    /// Problems found while loading the file, e.g. includes exceeding the limits
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// A root file together with all files it includes.
+#[derive(Debug, Clone)]
+pub struct Project {
+    options: ParseOptions,
+    files: Vec<ProjectFile>,
+}
+
+impl Project {
+    /// Loads `root` and all files included with `\input`, `\include` or `\subfile`, breadth
+    /// first and each file once.
+    ///
+    /// Included paths are resolved against the directory of `root`, just like TeX does:
+    /// 1. `.` and `..` are removed from the path, absolute paths are kept.
+    /// 2. A path without an extension gets `.tex`. A path with another extension is tried with
+    ///    `.tex` appended first, e.g. `chapter.v2.tex` before `chapter.v2`.
+    /// 3. The directory of `root` is tried before the search paths. A name matching exactly in
+    ///    any of them wins over one differing in case, which only matches with
+    ///    [`PathCase::Insensitive`]. Like the `subfiles` package does, the includes of a file
+    ///    with `\documentclass{subfiles}` are tried in its own directory first.
+    ///
+    /// Includes whose file differs between case-sensitive and case-insensitive file systems are
+    /// reported as [`AMBIGUOUS_PATH`] warnings of the including file. Missing
+    /// files are skipped, only an unreadable root is an error. Includes exceeding
+    /// [`Limits::max_include_depth`] or [`Limits::max_includes`] are reported as diagnostics of
+    /// the including file and not followed.
+    ///
+    /// ```
+    /// use neotex_core::{project::Project, Limits, ParseOptions};
+    ///
+    /// let dir = std::env::temp_dir().join(format!("neotex-project-{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir)?;
+    /// std::fs::write(dir.join("main.tex"), r"\input{a} \input{b}")?;
+    /// std::fs::write(dir.join("a.tex"), r"\input{main}")?;
+    ///
+    /// let limits = Limits { max_includes: 1, ..Limits::default() };
+    /// let project = Project::load(dir.join("main.tex"), ParseOptions::default().with_limits(limits))?;
+    /// let files: Vec<_> = project.files().iter().map(|file| file.path.file_name().unwrap()).collect();
+    /// assert_eq!(files, ["main.tex", "a.tex"]);
+    /// assert_eq!(project.root().diagnostics[0].rule, "limit-exceeded");
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn load(root: impl AsRef<Path>, options: ParseOptions) -> io::Result<Project> {
+        Project::load_with_search_paths(root, options, &[])
+    }
+
+    /// Like [`Project::load`], but included files which do not exist next to the root are looked
+    /// up in `search_paths` in order, e.g. the search paths of a [`Config`](crate::config::Config).
+    pub fn load_with_search_paths(
+        root: impl AsRef<Path>,
+        options: ParseOptions,
+        search_paths: &[PathBuf],
+    ) -> io::Result<Project> {
+        let root = root.as_ref();
+        let dir = root.parent().unwrap_or(Path::new(""));
+        let limits = options.limits;
+
+        let mut files = Vec::new();
+        let mut seen = HashSet::from([root.with_extension("tex")]);
+        let mut queue = VecDeque::from([(root.to_path_buf(), 0)]);
+
+        while let Some((path, depth)) = queue.pop_front() {
+            let mut file =
+                ProjectFile { path, source: String::new(), depth, diagnostics: Vec::new() };
+            match read(&file.path, &limits) {
+                Ok(Ok(source)) => file.source = source,
+                Ok(Err(err)) => file.diagnostics.push(err.to_diagnostic()),
+                Err(err) if depth == 0 => return Err(err),
+                Err(_) => continue,
+            }
+
+            let lexed = LexedStr::with_profile(&file.source, options.profile);
+            let own = file.path.parent().unwrap_or(Path::new(""));
+            let mut paths = search_paths.to_vec();
+            let base = match own != dir && document_class(&lexed) == Some("subfiles") {
+                true => {
+                    paths.insert(0, dir.to_path_buf());
+                    own
+                }
+                false => dir,
+            };
+            // pictures exported as TeX code may be large and contain no prose
+            let references = file_references(&lexed).into_iter().filter(|reference| {
+                options.analysis == AnalysisProfile::Full || !reference.is_graphic()
+            });
+            for (count, reference) in references.enumerate() {
+                let range = reference.range.clone();
+                let checked = limits
+                    .check(Limit::IncludeDepth, depth + 1)
+                    .and_then(|()| limits.check(Limit::Includes, count + 1));
+                match checked {
+                    Err(err) if err.limit == Limit::Includes => {
+                        file.diagnostics.push(err.at(range).to_diagnostic());
+                        break;
+                    }
+                    Err(err) => file.diagnostics.push(err.at(range).to_diagnostic()),
+                    Ok(()) => {
+                        let (path, diagnostic) =
+                            resolve(&reference, base, &paths, options.path_case);
+                        file.diagnostics.extend(
+                            diagnostic.filter(|diagnostic| options.stages.reports(diagnostic)),
+                        );
+                        if let Some(path) = path.filter(|path| seen.insert(path.clone())) {
+                            queue.push_back((path, depth + 1));
+                        }
+                    }
+                }
+            }
+
+            files.push(file);
+        }
+
+        Ok(Project { options, files })
+    }
+
+    /// The file the project was loaded from.
+    pub fn root(&self) -> &ProjectFile {
+        &self.files[0]
+    }
+
+    /// The options the project was loaded with.
+    pub(crate) fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    /// All files in the order they were loaded, starting with the root.
+    pub fn files(&self) -> &[ProjectFile] {
+        &self.files
+    }
+
+    /// Parses every file with the options the project was loaded with.
+    pub fn parse(&self) -> impl Iterator<Item = (&ProjectFile, ParseResult<'_>)> {
+        self.files.iter().map(|file| (file, parse_with_options(&file.source, self.options.clone())))
+    }
+
+    /// Parses the file at `path`, which has to be one of the files of the project, with the
+    /// definitions and packages of the other files available, so e.g. a chapter sees the
+    /// commands defined in the preamble of the root. `None` if `path` is no file of the project.
+    ///
+    /// ```
+    /// use neotex_core::{project::Project, ParseOptions};
+    ///
+    /// let dir = std::env::temp_dir().join(format!("neotex-parse-file-{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir)?;
+    /// std::fs::write(dir.join("main.tex"), r"\newcommand{\R}{\mathbb{R}} \input{intro}")?;
+    /// std::fs::write(dir.join("intro.tex"), r"\section{Intro} $\R$")?;
+    ///
+    /// let project = Project::load(dir.join("main.tex"), ParseOptions::default())?;
+    /// let result = project.parse_file(&dir.join("intro.tex")).unwrap();
+    /// assert!(result.definitions().get(r"\R").is_some());
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn parse_file(&self, path: &Path) -> Option<ParseResult<'_>> {
+        let file = self.files.iter().find(|file| same_file(&file.path, path))?;
+        let mut context = Preamble::default();
+        for other in self.files.iter().filter(|other| !std::ptr::eq(*other, file)) {
+            let preamble = Preamble::new(&other.source, &self.options);
+            context.definitions.inherit(&preamble.definitions);
+            context.packages.extend(preamble.packages);
+        }
+        Some(analyze(
+            &file.source,
+            self.options.clone(),
+            LexedStr::with_source_kind,
+            Some(&context),
+        ))
+    }
+
+    /// The root file of the project containing `file`, `file` itself if it is a root or no root
+    /// including it is found.
+    ///
+    /// A `% !TEX root = ../main.tex` comment or the [`main_file`](crate::files::main_file) of a
+    /// `\documentclass[../main.tex]{subfiles}` names the root explicitly, relative to the
+    /// directory of `file`. Otherwise a file without `\documentclass` or with one of the
+    /// [`SUBFILE_CLASSES`](crate::files::SUBFILE_CLASSES) is taken for a subfile, and the `.tex` files with any other
+    /// `\documentclass` in its directory and up to [`ROOT_SEARCH_DEPTH`] parent directories are
+    /// searched for one whose project includes it, nearest first.
+    ///
+    /// ```
+    /// use neotex_core::{project::Project, ParseOptions};
+    ///
+    /// let dir = std::env::temp_dir().join(format!("neotex-find-root-{}", std::process::id()));
+    /// std::fs::create_dir_all(dir.join("chapters"))?;
+    /// std::fs::write(dir.join("main.tex"), r"\documentclass{book} \input{chapters/intro}")?;
+    /// std::fs::write(dir.join("chapters/intro.tex"), r"\section{Intro}")?;
+    ///
+    /// let options = ParseOptions::default();
+    /// let root = Project::find_root(dir.join("chapters/intro.tex"), &options)?;
+    /// assert_eq!(root, dir.join("main.tex"));
+    /// assert_eq!(Project::find_root(dir.join("main.tex"), &options)?, dir.join("main.tex"));
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn find_root(file: impl AsRef<Path>, options: &ParseOptions) -> io::Result<PathBuf> {
+        let file = file.as_ref();
+        let dir = file.parent().unwrap_or(Path::new(""));
+        let source = fs::read_to_string(file)?;
+        let lexed = LexedStr::with_profile(&source, options.profile);
+        let named = root_directive(&source).or_else(|| main_file(&lexed).map(|main| main.path));
+        if let Some(root) = named {
+            let mut root = dir.join(root);
+            if root.extension().is_none() {
+                root.set_extension("tex");
+            }
+            if root.is_file() {
+                return Ok(root);
+            }
+        }
+        if is_root(&lexed) {
+            return Ok(file.to_path_buf());
+        }
+
+        // a relative path has no parents to search otherwise
+        let dir = match dir.is_relative() {
+            true => std::env::current_dir()?.join(dir),
+            false => dir.to_path_buf(),
+        };
+        for dir in dir.ancestors().take(ROOT_SEARCH_DEPTH + 1) {
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            let mut candidates: Vec<_> = entries
+                .filter_map(|entry| Some(dir.join(entry.ok()?.file_name())))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "tex"))
+                .filter(|path| !same_file(path, file))
+                .collect();
+            candidates.sort();
+
+            for candidate in candidates {
+                let Ok(source) = fs::read_to_string(&candidate) else {
+                    continue;
+                };
+                if !source.contains("\\documentclass")
+                    || !is_root(&LexedStr::with_profile(&source, options.profile))
+                {
+                    continue;
+                }
+                let Ok(project) = Project::load(&candidate, options.clone()) else {
+                    continue;
+                };
+                if project.files.iter().any(|included| same_file(&included.path, file)) {
+                    return Ok(candidate);
+                }
+            }
+        }
+        Ok(file.to_path_buf())
+    }
+
+    /// Like [`Project::parse`], but files lexed in an earlier run are taken from `cache`.
+    pub fn parse_with_cache<'a>(
+        &'a self,
+        cache: &'a ParseCache,
+    ) -> impl Iterator<Item = (&'a ProjectFile, ParseResult<'a>)> {
+        self.files.iter().map(|file| (file, cache.parse(&file.source, self.options.clone())))
+    }
+}
+
+/// Resolves `reference` as described in [`Project::load`]. Returns the file to load, if any, and
+/// an [`AMBIGUOUS_PATH`] diagnostic if it depends on the case sensitivity of the file system.
+fn resolve(
+    reference: &FileReference<'_>,
+    dir: &Path,
+    search_paths: &[PathBuf],
+    path_case: PathCase,
+) -> (Option<PathBuf>, Option<Diagnostic>) {
+    let with_extension = Path::new(reference.path).extension().is_some_and(|ext| ext != "tex");
+    let candidates: Vec<_> = std::iter::once(dir)
+        .chain(search_paths.iter().map(PathBuf::as_path))
+        .flat_map(|dir| {
+            let path = reference.resolve(dir);
+            let mut tex = path.clone().into_os_string();
+            tex.push(".tex");
+            with_extension.then(|| PathBuf::from(tex)).into_iter().chain([path])
+        })
+        .collect();
+
+    let names = |found: &[PathBuf]| {
+        let names: Vec<_> = found
+            .iter()
+            .map(|path| format!("'{}'", path.file_name().unwrap_or_default().to_string_lossy()))
+            .collect();
+        names.join(", ")
+    };
+    let warning = |message: String| {
+        Diagnostic::new(AMBIGUOUS_PATH, Severity::Warning, reference.range.clone(), message)
+    };
+
+    let mut differing = None;
+    for candidate in &candidates {
+        let found = matching_case_insensitive(candidate);
+        if found.contains(candidate) {
+            let others: Vec<_> = found.into_iter().filter(|path| path != candidate).collect();
+            let message = format!(
+                "'{}' also matches {} on case-insensitive file systems",
+                reference.path,
+                names(&others)
+            );
+            return (Some(candidate.clone()), (!others.is_empty()).then(|| warning(message)));
+        }
+        if differing.is_none() && !found.is_empty() {
+            differing = Some(found);
+        }
+    }
+
+    let Some(found) = differing else {
+        return (None, None);
+    };
+    match path_case {
+        PathCase::Exact => {
+            let message = format!(
+                "'{}' is not found, but matches {} on case-insensitive file systems",
+                reference.path,
+                names(&found)
+            );
+            (None, Some(warning(message)))
+        }
+        PathCase::Insensitive => {
+            let message = format!(
+                "'{}' matches {} only on case-insensitive file systems",
+                reference.path,
+                names(&found)
+            );
+            (found.into_iter().next(), Some(warning(message)))
+        }
+    }
+}
+
+/// The files in the directory of `path` whose name equals its name ignoring case, sorted. The
+/// directory is listed, since a case-insensitive file system would find any spelling.
+fn matching_case_insensitive(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let name = name.to_string_lossy().to_lowercase();
+    let listed = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let Ok(entries) = fs::read_dir(listed) else {
+        return Vec::new();
+    };
+
+    let mut found: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().to_lowercase() == name)
+        .map(|entry| path.with_file_name(entry.file_name()))
+        .filter(|path| path.is_file())
+        .collect();
+    found.sort();
+    found
+}
+
+/// Reads the file at `path` unless it is larger than the file size limit.
+fn read(path: &Path, limits: &Limits) -> io::Result<Result<String, LimitExceeded>> {
+    let size = fs::metadata(path)?.len();
+    if let Err(err) = limits.check(Limit::FileSize, usize::try_from(size).unwrap_or(usize::MAX)) {
+        return Ok(Err(err));
+    }
+    fs::read_to_string(path).map(Ok)
+}
+
+/// The path of a `% !TEX root = <path>` comment, the spelling and spacing of `!TEX root` as
+/// accepted by TeXShop and the LaTeX Workshop.
+fn root_directive(source: &str) -> Option<&str> {
+    source.lines().find_map(|line| {
+        let comment = line.trim_start().strip_prefix('%')?.trim_start_matches('%').trim_start();
+        let rest = comment.strip_prefix('!')?.trim_start();
+        let (key, value) = rest.split_once('=')?;
+        let key: Vec<_> = key.split_whitespace().collect();
+        let matches = key.len() == 2
+            && key[0].eq_ignore_ascii_case("tex")
+            && key[1].eq_ignore_ascii_case("root");
+        (matches && !value.trim().is_empty()).then(|| value.trim())
+    })
+}
+
+/// Whether `lexed` compiles on its own, i.e. has a `\documentclass` which is none of the
+/// [`SUBFILE_CLASSES`].
+fn is_root(lexed: &LexedStr<'_>) -> bool {
+    document_class(lexed).is_some_and(|class| !SUBFILE_CLASSES.contains(&class))
+}
+
+/// Whether `a` and `b` are the same file, comparing the paths themselves if either does not exist.
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
@@ -0,0 +1,370 @@
+//! A summary of the state of a whole project, e.g. for a CI artifact.
+//!
+//! [`Report::new`] collects the statistics, the most frequent words, open todos, unknown commands,
+//! unused definitions, constructs needing shell escape and diagnostics of all files of a
+//! [`Project`]. With the `serde` feature enabled the report serializes to JSON, the
+//! `neotex report` command renders it as HTML as well.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use parser::{
+    analysis::{shell_escapes, symbols, word_frequencies, words, SymbolKind},
+    ast::Document,
+    diagnostics::{Diagnostic, Severity},
+    registry,
+    semantic::{semantic_tokens, SemanticModifier, SemanticTokenType},
+    LexedStr, SyntaxKind,
+};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::project::Project;
+
+/// A position in a file of the project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Location<'a> {
+    /// The file
+    pub file: &'a Path,
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column, counted in bytes
+    pub column: usize,
+}
+
+/// Counts over all files of a project.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Stats {
+    /// Number of files
+    pub files: usize,
+    /// Number of lines
+    pub lines: usize,
+    /// Number of words outside of comments and command names
+    pub words: usize,
+    /// Number of sectioning commands
+    pub sections: usize,
+    /// Number of command and environment definitions
+    pub definitions: usize,
+    /// Number of labels
+    pub labels: usize,
+    /// Number of error diagnostics
+    pub errors: usize,
+    /// Number of warning diagnostics
+    pub warnings: usize,
+}
+
+/// Number of [`WordFrequency`] entries of a [`Report`].
+pub const FREQUENT_WORDS: usize = 20;
+
+/// How often a word is used in the project, see
+/// [`word_frequencies`](crate::analysis::word_frequencies).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct WordFrequency {
+    /// The word in lowercase
+    pub word: String,
+    /// Number of uses
+    pub count: usize,
+}
+
+/// A `TODO` or `FIXME` comment or a call of `\todo`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Todo<'a> {
+    /// Where the todo is
+    pub location: Location<'a>,
+    /// The comment without the `%` or the argument of `\todo`
+    pub text: &'a str,
+}
+
+/// A command which is neither defined in the project, declared by a stub of its options nor a
+/// standard command of the [`registry`](crate::registry).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct UnknownCommand<'a> {
+    /// Name including the leading backslash
+    pub name: &'a str,
+    /// Number of calls in the project
+    pub uses: usize,
+    /// The first call
+    pub first: Location<'a>,
+}
+
+/// A command definition whose command is never used in the project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DeadDefinition<'a> {
+    /// Name including the leading backslash
+    pub name: &'a str,
+    /// Start of the definition
+    pub location: Location<'a>,
+}
+
+/// A construct which only works with `--shell-escape`, see
+/// [`shell_escapes`](crate::analysis::shell_escapes).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ShellEscapeUse<'a> {
+    /// Start of the construct
+    pub location: Location<'a>,
+    /// Short description, e.g. `\write18` or `the package 'minted'`
+    pub construct: String,
+    /// The shell command if it is known
+    pub command: Option<&'a str>,
+}
+
+/// A diagnostic of a file of the project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ReportDiagnostic<'a> {
+    /// Start of the diagnostic
+    pub location: Location<'a>,
+    /// Severity of the diagnostic
+    pub severity: Severity,
+    /// Code of the rule, see [`explain`](crate::diagnostics::explain)
+    pub code: Option<&'static str>,
+    /// Rule id, e.g. `missing-body`
+    pub rule: &'static str,
+    /// Human readable description
+    pub message: String,
+}
+
+/// Everything [`Report::new`] found out about a project, see the [module](self) documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Report<'a> {
+    /// The root file of the project
+    pub root: &'a Path,
+    /// Counts over all files
+    pub stats: Stats,
+    /// The [`FREQUENT_WORDS`] most used words of the text, most used first
+    pub frequent_words: Vec<WordFrequency>,
+    /// All todos in the order of the files and their position
+    pub todos: Vec<Todo<'a>>,
+    /// Unknown commands, most used first
+    pub unknown_commands: Vec<UnknownCommand<'a>>,
+    /// Definitions of commands which are never used, in the order of the files
+    pub dead_definitions: Vec<DeadDefinition<'a>>,
+    /// Constructs which need `--shell-escape`, in the order of the files
+    pub shell_escape: Vec<ShellEscapeUse<'a>>,
+    /// Diagnostics of all files, including the problems found while loading them
+    pub diagnostics: Vec<ReportDiagnostic<'a>>,
+}
+
+impl<'a> Report<'a> {
+    /// Collects the report of all files of `project`.
+    ///
+    /// ```
+    /// use neotex_core::{project::Project, report::Report, ParseOptions};
+    ///
+    /// let dir = std::env::temp_dir().join(format!("neotex-report-{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir)?;
+    /// std::fs::write(dir.join("main.tex"), "\\input{macros}\n% TODO: cite\n$\\R \\foo$ \\todo{proof}\\write18{ls}")?;
+    /// std::fs::write(dir.join("macros.tex"), r"\newcommand{\R}{\mathbb{R}} \newcommand{\N}{\mathbb{N}}")?;
+    ///
+    /// let project = Project::load(dir.join("main.tex"), ParseOptions::default())?;
+    /// let report = Report::new(&project);
+    /// assert_eq!((report.stats.files, report.stats.definitions), (2, 2));
+    ///
+    /// let todos: Vec<_> = report.todos.iter().map(|todo| (todo.location.line, todo.text)).collect();
+    /// assert_eq!(todos, [(2, "TODO: cite"), (3, "proof")]);
+    /// assert_eq!(report.unknown_commands[0].name, r"\foo");
+    /// assert_eq!((report.frequent_words[0].word.as_str(), report.frequent_words[0].count), ("proof", 1));
+    /// assert_eq!(report.dead_definitions[0].name, r"\N");
+    /// assert_eq!((report.shell_escape[0].construct.as_str(), report.shell_escape[0].command), (r"\write18", Some("ls")));
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn new(project: &'a Project) -> Report<'a> {
+        let results: Vec<_> = project.parse().collect();
+        let stubs = &project.options().commands;
+        let mut report = Report {
+            root: &project.root().path,
+            stats: Stats { files: results.len(), ..Stats::default() },
+            frequent_words: Vec::new(),
+            todos: Vec::new(),
+            unknown_commands: Vec::new(),
+            dead_definitions: Vec::new(),
+            shell_escape: Vec::new(),
+            diagnostics: Vec::new(),
+        };
+
+        let mut defined = HashSet::new();
+        let mut used = HashSet::new();
+        let mut calls: Vec<(&'a str, Location<'a>)> = Vec::new();
+        let mut prose = Vec::new();
+        for (file, result) in &results {
+            let lexed = result.tokens();
+            let lines = Lines::new(&file.path, &file.source);
+            report.stats.lines += file.source.lines().count();
+            report.stats.sections += result.outline().iter().count();
+            report.stats.labels += symbols(lexed, result.definitions())
+                .iter()
+                .filter(|symbol| symbol.kind == SymbolKind::Label)
+                .count();
+
+            for item in
+                result.definitions().all_commands().chain(result.definitions().environments())
+            {
+                if !item.range.is_empty() {
+                    report.stats.definitions += 1;
+                }
+                defined.insert(item.name);
+            }
+            for token in semantic_tokens(lexed, result.definitions()) {
+                let name = &file.source[token.range.clone()];
+                if token.kind == SemanticTokenType::Macro
+                    && !token.has(SemanticModifier::Definition)
+                {
+                    used.insert(name);
+                    calls.push((name, lines.location(token.range.start)));
+                }
+            }
+
+            for idx in 0..lexed.len() {
+                match lexed.kind(idx) {
+                    SyntaxKind::Word | SyntaxKind::AWord => report.stats.words += 1,
+                    SyntaxKind::Comment | SyntaxKind::AComment => {
+                        let text = lexed.text(idx).trim_start_matches('%').trim();
+                        if text.starts_with("TODO") || text.starts_with("FIXME") {
+                            let location = lines.location(lexed.text_start(idx));
+                            report.todos.push(Todo { location, text });
+                        }
+                    }
+                    SyntaxKind::Command if lexed.text(idx) == "\\todo" => {
+                        if let Some(text) = argument(lexed, idx) {
+                            let location = lines.location(lexed.text_start(idx));
+                            report.todos.push(Todo { location, text: text.trim() });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            let document = Document::new(lexed);
+            prose.extend(words(&document));
+            for escape in shell_escapes(&document) {
+                if escape.requires_shell_escape() {
+                    report.shell_escape.push(ShellEscapeUse {
+                        location: lines.location(escape.range.start),
+                        construct: escape.construct(),
+                        command: escape.text,
+                    });
+                }
+            }
+
+            let found = file.diagnostics.iter().chain(result.diagnostics());
+            for diagnostic in found {
+                match diagnostic.severity {
+                    Severity::Error => report.stats.errors += 1,
+                    Severity::Warning => report.stats.warnings += 1,
+                    Severity::Info => {}
+                }
+                report.diagnostics.push(ReportDiagnostic::new(diagnostic, &lines));
+            }
+        }
+
+        for (file, result) in &results {
+            let lines = Lines::new(&file.path, &file.source);
+            for symbol in symbols(result.tokens(), result.definitions()) {
+                if symbol.kind == SymbolKind::Command && !used.contains(symbol.name) {
+                    let location = lines.location(symbol.range.start);
+                    report.dead_definitions.push(DeadDefinition { name: symbol.name, location });
+                }
+            }
+        }
+
+        // control symbols like `\,` are primitives, only unknown control words are reported
+        let known = |name: &str| {
+            defined.contains(name)
+                || !name[1..].starts_with(|c: char| c.is_alphabetic() || c == '@')
+                || stubs.iter().any(|stub| stub.name == name)
+                || registry::command(name).is_some()
+        };
+        let mut unknown: HashMap<&str, UnknownCommand<'a>> = HashMap::new();
+        for (name, location) in calls {
+            if known(name) {
+                continue;
+            }
+            unknown.entry(name).and_modify(|command| command.uses += 1).or_insert(UnknownCommand {
+                name,
+                uses: 1,
+                first: location,
+            });
+        }
+        report.unknown_commands = unknown.into_values().collect();
+        report.unknown_commands.sort_by(|a, b| b.uses.cmp(&a.uses).then(a.name.cmp(b.name)));
+        report.frequent_words = word_frequencies(&prose)
+            .into_iter()
+            .take(FREQUENT_WORDS)
+            .map(|(word, count)| WordFrequency { word, count })
+            .collect();
+
+        report
+    }
+}
+
+impl<'a> ReportDiagnostic<'a> {
+    fn new(diagnostic: &Diagnostic, lines: &Lines<'a>) -> ReportDiagnostic<'a> {
+        ReportDiagnostic {
+            location: lines.location(diagnostic.range.start),
+            severity: diagnostic.severity,
+            code: diagnostic.code(),
+            rule: diagnostic.rule,
+            message: diagnostic.message.clone(),
+        }
+    }
+}
+
+/// The content of the brace group following the command at `idx` and its optional argument.
+fn argument<'source>(lexed: &LexedStr<'source>, idx: usize) -> Option<&'source str> {
+    let skip = |pos: usize| lexed.next_non_trivia(pos);
+    let mut open = skip(idx + 1)?;
+    if lexed.kind(open) == SyntaxKind::OpenBracket {
+        let close = (open..lexed.len()).find(|&pos| lexed.kind(pos) == SyntaxKind::CloseBracket)?;
+        open = skip(close + 1)?;
+    }
+    if lexed.kind(open) != SyntaxKind::OpenBrace {
+        return None;
+    }
+
+    let mut depth = 0;
+    for pos in open..lexed.len() {
+        match lexed.kind(pos) {
+            SyntaxKind::OpenBrace => depth += 1,
+            SyntaxKind::CloseBrace => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(
+                        &lexed.as_str()[lexed.text_start(open + 1)..lexed.text_start(pos)],
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Converts byte offsets of a file into [`Location`]s.
+struct Lines<'a> {
+    file: &'a Path,
+    starts: Vec<usize>,
+}
+
+impl<'a> Lines<'a> {
+    fn new(file: &'a Path, source: &str) -> Lines<'a> {
+        let starts =
+            std::iter::once(0).chain(source.match_indices('\n').map(|(idx, _)| idx + 1)).collect();
+        Lines { file, starts }
+    }
+
+    fn location(&self, offset: usize) -> Location<'a> {
+        let line = self.starts.partition_point(|&start| start <= offset);
+        Location { file: self.file, line, column: offset - self.starts[line - 1] + 1 }
+    }
+}
@@ -0,0 +1,85 @@
+use std::{fmt, sync::Arc};
+
+use parser::{LexedStr, LexedTokens, SourceKind, SourceText};
+
+use crate::{parse::analyze, Limit, ParseOptions, ParseResult};
+
+/// The analysis of a source which owns its source text.
+///
+/// A [`ParseResult`] borrows the source, which ties it to the scope that loaded the text. A
+/// `SharedParse` keeps its [`SourceText`] alive itself, so it can be cached, cloned cheaply and
+/// sent to other threads.
+///
+/// Only the tokens are kept, like in a [`ParseCache`](crate::cache::ParseCache). The other
+/// analysis results borrow the source text and [`result`](SharedParse::result) recomputes them
+/// from the tokens without lexing the text again.
+///
+/// ```
+/// use neotex_core::{Limits, ParseOptions, SharedParse};
+///
+/// let parsed = {
+///     let text = String::from(r"\newcommand{\R}{\mathbb{R}}");
+///     SharedParse::new(text, ParseOptions::default())
+/// };
+///
+/// let handle = std::thread::spawn(move || parsed.result().definitions().get(r"\R").is_some());
+/// assert!(handle.join().unwrap());
+///
+/// let limits = Limits { max_file_size: 8, ..Limits::default() };
+/// let large = SharedParse::new(r"\section{Long}", ParseOptions::default().with_limits(limits));
+/// assert_eq!(large.result().diagnostics()[0].rule, "limit-exceeded");
+/// ```
+#[derive(Clone)]
+pub struct SharedParse {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    text: SourceText,
+    options: ParseOptions,
+    tokens: LexedTokens,
+}
+
+impl SharedParse {
+    /// Lexes `text` with `options`.
+    pub fn new(text: impl Into<SourceText>, options: ParseOptions) -> SharedParse {
+        let text = text.into();
+        // a source exceeding the file size limit is not lexed, see `parse_with_options`
+        let source = match options.limits.check(Limit::FileSize, text.len()) {
+            Ok(()) => &text[..],
+            Err(_) => "",
+        };
+        let kind = options.source_kind.unwrap_or_else(|| SourceKind::detect(source));
+        let tokens = LexedStr::with_source_kind(source, options.profile, kind).into_tokens();
+        SharedParse { inner: Arc::new(Inner { text, options, tokens }) }
+    }
+
+    /// The source text.
+    pub fn text(&self) -> &SourceText {
+        &self.inner.text
+    }
+
+    /// The options the source is analyzed with.
+    pub fn options(&self) -> &ParseOptions {
+        &self.inner.options
+    }
+
+    /// Runs all analysis passes on the lexed source text.
+    pub fn result(&self) -> ParseResult<'_> {
+        let Inner { text, options, tokens } = &*self.inner;
+        let lex = |source, profile, kind| {
+            LexedStr::from_tokens(source, tokens.clone())
+                .unwrap_or_else(|| LexedStr::with_source_kind(source, profile, kind))
+        };
+        analyze(text, options.clone(), lex, None)
+    }
+}
+
+impl fmt::Debug for SharedParse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedParse")
+            .field("text", &self.inner.text)
+            .field("options", &self.inner.options)
+            .finish()
+    }
+}
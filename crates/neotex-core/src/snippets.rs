@@ -0,0 +1,72 @@
+use parser::{ast::Document, expansion::ExpansionStore, LexedStr};
+
+use crate::{parse::analyze, Limit, ParseOptions, ParseResult};
+
+/// The analysis of a single snippet of [`analyze_snippets`]. Its definitions include those of
+/// the shared preamble.
+pub type SnippetResult<'source> = ParseResult<'source>;
+
+/// What the snippets of [`analyze_snippets`] take from the shared preamble.
+#[derive(Debug, Default)]
+pub(crate) struct Preamble<'source> {
+    /// Definitions inherited by every snippet which does not define the name itself
+    pub(crate) definitions: ExpansionStore<'source>,
+    /// Packages loaded by the preamble, uses of their commands are not reported as missing
+    pub(crate) packages: Vec<&'source str>,
+}
+
+impl<'source> Preamble<'source> {
+    /// Lexes and resolves `source` once, nothing if it exceeds the limits of `options`.
+    pub(crate) fn new(source: &'source str, options: &ParseOptions) -> Preamble<'source> {
+        let limits = options.limits;
+        if limits.check(Limit::FileSize, source.len()).is_err() {
+            return Preamble::default();
+        }
+        let lexed = LexedStr::with_profile(source, options.profile);
+        if limits.check(Limit::Tokens, lexed.len()).is_err() {
+            return Preamble::default();
+        }
+        let packages = Document::new(&lexed).loaded_packages();
+        Preamble { definitions: parser::expansion::resolve(&lexed), packages }
+    }
+}
+
+/// Analyzes many independent snippets, e.g. the cells of a notebook, which share the definitions
+/// and packages of `shared_preamble`.
+///
+/// The preamble is lexed and resolved only once. Every snippet is analyzed on its own, but with
+/// the commands and environments of the preamble it does not define itself, and without reporting
+/// commands of packages the preamble loads as missing. Diagnostics of the preamble itself are not
+/// reported, [`parse`](crate::parse) it to get them.
+///
+/// ```
+/// let preamble = r"\usepackage{amssymb} \newcommand{\R}{\mathbb{R}}";
+/// let snippets = [r"$\mathbb{N} \subset \R$", r"\newcommand{\R}{r} $\R$"];
+/// let results = neotex_core::analyze_snippets(&snippets, preamble);
+///
+/// assert_eq!(results[0].expanded_text().unwrap(), r"$\mathbb{N} \subset \mathbb{R}$");
+/// assert_eq!(results[1].expanded_text().unwrap(), r"\newcommand{\R}{r} $r$");
+/// assert!(results.iter().all(|result| result.diagnostics().is_empty()));
+/// ```
+pub fn analyze_snippets<'source>(
+    snippets: &[&'source str],
+    shared_preamble: &'source str,
+) -> Vec<SnippetResult<'source>> {
+    analyze_snippets_with_options(snippets, shared_preamble, ParseOptions::default())
+}
+
+/// Like [`analyze_snippets`], but analyzes the preamble and the snippets with `options`. A
+/// preamble exceeding the [`Limits`](crate::Limits) of `options` is ignored.
+pub fn analyze_snippets_with_options<'source>(
+    snippets: &[&'source str],
+    shared_preamble: &'source str,
+    options: ParseOptions,
+) -> Vec<SnippetResult<'source>> {
+    let preamble = Preamble::new(shared_preamble, &options);
+    snippets
+        .iter()
+        .map(|snippet| {
+            analyze(snippet, options.clone(), LexedStr::with_source_kind, Some(&preamble))
+        })
+        .collect()
+}
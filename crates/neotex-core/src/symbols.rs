@@ -0,0 +1,141 @@
+use std::{ops::Range, path::Path};
+
+use parser::{
+    analysis::{fuzzy_match, name_at, symbols, CommandStub, Symbol, SymbolKind},
+    registry,
+};
+
+use crate::project::Project;
+
+/// A definition found by [`workspace_symbols`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceSymbol<'a> {
+    /// The file containing the definition
+    pub file: &'a Path,
+    /// The definition, its range is a byte range of the file
+    pub symbol: Symbol<'a>,
+}
+
+/// All command, environment, label, counter and length definitions of the files of `project`
+/// whose names match `query` fuzzily, see [`fuzzy_match`](crate::analysis::fuzzy_match).
+///
+/// The best matches come first, equally good ones in the order of the files and the definitions
+/// in them. An empty query returns every definition.
+///
+/// ```
+/// use neotex_core::{analysis::{workspace_symbols, SymbolKind}, project::Project, ParseOptions};
+///
+/// let dir = std::env::temp_dir().join(format!("neotex-symbols-{}", std::process::id()));
+/// std::fs::create_dir_all(&dir)?;
+/// std::fs::write(dir.join("main.tex"), r"\input{macros} \section{Reals}\label{sec:reals}")?;
+/// std::fs::write(dir.join("macros.tex"), r"\newcommand{\R}{\mathbb{R}} \newcounter{real}")?;
+///
+/// let project = Project::load(dir.join("main.tex"), ParseOptions::default())?;
+/// let found: Vec<_> = workspace_symbols(&project, "re")
+///     .into_iter()
+///     .map(|found| (found.file.file_name().unwrap(), found.symbol.kind, found.symbol.name))
+///     .collect();
+/// assert_eq!(
+///     found,
+///     [("macros.tex".as_ref(), SymbolKind::Counter, "real"), ("main.tex".as_ref(), SymbolKind::Label, "sec:reals")]
+/// );
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn workspace_symbols<'a>(project: &'a Project, query: &str) -> Vec<WorkspaceSymbol<'a>> {
+    let mut found = Vec::new();
+    for (file, result) in project.parse() {
+        for symbol in symbols(result.tokens(), result.definitions()) {
+            if let Some(cost) = fuzzy_match(query, symbol.name) {
+                found.push((cost, WorkspaceSymbol { file: &file.path, symbol }));
+            }
+        }
+    }
+    // the sort is stable, so equal matches stay in file and source order
+    found.sort_by_key(|(cost, _)| *cost);
+    found.into_iter().map(|(_, symbol)| symbol).collect()
+}
+
+/// Where [`goto_definition`] found a definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefinitionSite<'a> {
+    /// A definition in a file of the project
+    File {
+        /// The file containing the definition
+        file: &'a Path,
+        /// Byte range of the whole definition, of the key for labels
+        range: Range<usize>,
+    },
+    /// A command declared by a stub of the [`ParseOptions`](crate::ParseOptions) of the project
+    Stub(&'a CommandStub),
+    /// A standard command or environment, provided by the package or the kernel if `None`
+    Builtin {
+        /// The package providing the name, `None` for the LaTeX kernel
+        package: Option<&'static str>,
+    },
+}
+
+/// The definitions of the command, environment or label at the byte `offset` of `file`, which
+/// has to be one of the files of `project`.
+///
+/// Every definition in the files of `project` is a candidate, so a redefined name has several,
+/// in the order of the files and the definitions in them. Only names the project does not define
+/// are looked up in the stubs of its options and then in the standard commands and environments.
+///
+/// ```
+/// use neotex_core::{analysis::{goto_definition, DefinitionSite}, project::Project, ParseOptions};
+///
+/// let dir = std::env::temp_dir().join(format!("neotex-goto-{}", std::process::id()));
+/// std::fs::create_dir_all(&dir)?;
+/// std::fs::write(dir.join("main.tex"), r"\input{macros} $\R$ \frac{1}{2}")?;
+/// std::fs::write(dir.join("macros.tex"), r"\newcommand{\R}{\mathbb{R}}")?;
+///
+/// let project = Project::load(dir.join("main.tex"), ParseOptions::default())?;
+/// let main = &project.root().path;
+/// let found = goto_definition(&project, main, 18);
+/// assert_eq!(found, [DefinitionSite::File { file: &dir.join("macros.tex"), range: 0..27 }]);
+/// assert_eq!(goto_definition(&project, main, 23), [DefinitionSite::Builtin { package: None }]);
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn goto_definition<'a>(
+    project: &'a Project,
+    file: &Path,
+    offset: usize,
+) -> Vec<DefinitionSite<'a>> {
+    let results: Vec<_> = project.parse().collect();
+    let Some((_, result)) = results.iter().find(|(candidate, _)| candidate.path == file) else {
+        return Vec::new();
+    };
+    let Some(target) = name_at(result.tokens(), offset) else {
+        return Vec::new();
+    };
+
+    let mut found = Vec::new();
+    for (file, result) in &results {
+        let definitions = symbols(result.tokens(), result.definitions())
+            .into_iter()
+            .filter(|symbol| symbol.kind == target.kind && symbol.name == target.name)
+            .map(|symbol| DefinitionSite::File { file: &file.path, range: symbol.range });
+        found.extend(definitions);
+    }
+    if !found.is_empty() {
+        return found;
+    }
+
+    let stubs = &project.options().commands;
+    match target.kind {
+        SymbolKind::Command => match stubs.iter().find(|stub| stub.name == target.name) {
+            Some(stub) => vec![DefinitionSite::Stub(stub)],
+            None => registry::command(target.name)
+                .map(|builtin| DefinitionSite::Builtin { package: builtin.package })
+                .into_iter()
+                .collect(),
+        },
+        SymbolKind::Environment => registry::environment(target.name)
+            .map(|builtin| DefinitionSite::Builtin { package: builtin.package })
+            .into_iter()
+            .collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,194 @@
+//! How often a project uses which commands, environments and packages, e.g. to check the
+//! restrictions of a journal on packages or to feed a dashboard.
+//!
+//! [`Usage::new`] counts over all files of a [`Project`], so the chapters included by `\input`
+//! and `\include` are part of the counts. With the `serde` feature enabled the usage serializes
+//! to JSON, the `neotex stats` command renders it as text and CSV as well.
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashSet},
+};
+
+use parser::{
+    ast::{content_model, AstNode, Document, Environment, NodeKind, SyntaxNode},
+    registry,
+    semantic::{semantic_tokens, SemanticModifier, SemanticTokenType},
+    SyntaxKind,
+};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::project::Project;
+
+/// Number of uses of a command or an environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Count<'a> {
+    /// Name of the command including the leading backslash, or of the environment
+    pub name: &'a str,
+    /// Number of uses in the project
+    pub count: usize,
+}
+
+/// The calls of the commands of a package, see [`registry`](crate::registry).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct PackageUsage {
+    /// Name of the package, `None` for the LaTeX kernel
+    pub package: Option<&'static str>,
+    /// Number of calls of commands of the package
+    pub calls: usize,
+    /// The distinct commands called, ordered by name
+    pub commands: Vec<&'static str>,
+}
+
+/// The counts of [`Usage::new`], see the [module](self) documentation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Usage<'a> {
+    /// Number of files
+    pub files: usize,
+    /// Number of lines
+    pub lines: usize,
+    /// Number of `Word` and `AWord` tokens, which includes the words of math and of arguments
+    /// like the names of environments
+    pub words: usize,
+    /// Number of lines with a comment
+    pub comment_lines: usize,
+    /// Share of the lines with a comment, between 0 and 1
+    pub comment_density: f64,
+    /// Number of math regions, inline and display math as well as math environments like
+    /// `align`. Math nested in another one, e.g. in the `\text` of an equation, is not counted.
+    pub math: usize,
+    /// Calls of every command, most used first. Definitions are not counted as calls.
+    pub commands: Vec<Count<'a>>,
+    /// Uses of every environment, most used first
+    pub environments: Vec<Count<'a>>,
+    /// Calls of standard commands by package, the kernel first and then most called first.
+    /// Commands defined in the project are not attributed to a package.
+    pub packages: Vec<PackageUsage>,
+}
+
+impl<'a> Usage<'a> {
+    /// Counts over all files of `project`.
+    ///
+    /// ```
+    /// use neotex_core::{project::Project, usage::Usage, ParseOptions};
+    ///
+    /// let dir = std::env::temp_dir().join(format!("neotex-usage-{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir)?;
+    /// std::fs::write(dir.join("main.tex"), "\\input{intro}\n% draft\n\\begin{align}\\frac12\\end{align}")?;
+    /// std::fs::write(dir.join("intro.tex"), "Let $\\mathbb{R}$ be the reals, \\emph{see} $x$.")?;
+    ///
+    /// let project = Project::load(dir.join("main.tex"), ParseOptions::default())?;
+    /// let usage = Usage::new(&project);
+    /// assert_eq!((usage.files, usage.words, usage.math, usage.comment_lines), (2, 10, 3, 1));
+    /// assert_eq!((usage.environments[0].name, usage.environments[0].count), ("align", 1));
+    ///
+    /// let packages: Vec<_> = usage.packages.iter().map(|usage| (usage.package, usage.calls)).collect();
+    /// assert_eq!(packages, [(None, 1), (Some("amssymb"), 1)]);
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn new(project: &'a Project) -> Usage<'a> {
+        let mut usage = Usage {
+            files: 0,
+            lines: 0,
+            words: 0,
+            comment_lines: 0,
+            comment_density: 0.0,
+            math: 0,
+            commands: Vec::new(),
+            environments: Vec::new(),
+            packages: Vec::new(),
+        };
+        let mut commands: BTreeMap<&'a str, usize> = BTreeMap::new();
+        let mut environments: BTreeMap<&'a str, usize> = BTreeMap::new();
+        let mut defined = HashSet::new();
+
+        for (file, result) in project.parse() {
+            let lexed = result.tokens();
+            usage.files += 1;
+            usage.lines += file.source.lines().count();
+
+            let mut commented = None;
+            for idx in 0..lexed.len() {
+                match lexed.kind(idx) {
+                    SyntaxKind::Word | SyntaxKind::AWord => usage.words += 1,
+                    SyntaxKind::Comment | SyntaxKind::AComment => {
+                        let line = file.source[..lexed.text_start(idx)].matches('\n').count();
+                        if commented.replace(line) != Some(line) {
+                            usage.comment_lines += 1;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            defined.extend(result.definitions().all_commands().map(|item| item.name));
+            for token in semantic_tokens(lexed, result.definitions()) {
+                if token.kind == SemanticTokenType::Macro
+                    && !token.has(SemanticModifier::Definition)
+                {
+                    *commands.entry(&file.source[token.range]).or_default() += 1;
+                }
+            }
+
+            let document = Document::new(lexed);
+            for node in document.descendants() {
+                if let Some(environment) = Environment::cast(node) {
+                    *environments.entry(environment.name()).or_default() += 1;
+                }
+                if is_math(node)
+                    && !std::iter::successors(node.parent(), |n| n.parent()).any(is_math)
+                {
+                    usage.math += 1;
+                }
+            }
+        }
+        if usage.lines > 0 {
+            usage.comment_density = usage.comment_lines as f64 / usage.lines as f64;
+        }
+
+        let mut packages: BTreeMap<Option<&'static str>, PackageUsage> = BTreeMap::new();
+        for (&name, &count) in &commands {
+            let Some(command) = registry::command(name).filter(|_| !defined.contains(name)) else {
+                continue;
+            };
+            let package = command.package;
+            let entry = packages.entry(package).or_insert(PackageUsage {
+                package,
+                calls: 0,
+                commands: Vec::new(),
+            });
+            entry.calls += count;
+            entry.commands.push(command.name);
+        }
+        usage.packages = packages.into_values().collect();
+        usage.packages.sort_by_key(|usage| (usage.package.is_some(), Reverse(usage.calls)));
+        usage.commands = by_count(commands);
+        usage.environments = by_count(environments);
+        usage
+    }
+}
+
+/// Whether `node` is inline or display math or a math environment.
+fn is_math(node: SyntaxNode<'_, '_>) -> bool {
+    match node.kind() {
+        NodeKind::MathBlock => true,
+        NodeKind::Environment => Environment::cast(node)
+            .and_then(|environment| content_model(environment.name()))
+            .is_some_and(|model| model.math),
+        _ => false,
+    }
+}
+
+/// The counts ordered by count, most first, and then by name.
+fn by_count(counts: BTreeMap<&str, usize>) -> Vec<Count<'_>> {
+    let mut counts: Vec<_> =
+        counts.into_iter().map(|(name, count)| Count { name, count }).collect();
+    // stable, so equal counts stay ordered by name
+    counts.sort_by_key(|count| Reverse(count.count));
+    counts
+}
@@ -0,0 +1,33 @@
+//! The example of every explanation has to be reported under its rule, the fixed example not.
+#![cfg(feature = "lint")]
+
+use neotex_core::{
+    analysis::LintConfig,
+    diagnostics::{explain, EXPLANATIONS},
+    parse_with_options, ParseOptions,
+};
+
+#[test]
+fn examples_trigger_their_rule() {
+    let rules = |source| {
+        let options = ParseOptions::default().with_lints(LintConfig::all());
+        let result = parse_with_options(source, options);
+        result.diagnostics().iter().map(|diagnostic| diagnostic.rule).collect::<Vec<_>>()
+    };
+
+    for explanation in EXPLANATIONS {
+        let (Some(example), Some(fixed)) = (explanation.example, explanation.fixed) else {
+            continue;
+        };
+        let rule = explanation.rule;
+        assert!(rules(example).contains(&rule), "{} is not reported for {example:?}", rule);
+        assert!(!rules(fixed).contains(&rule), "{} is reported for {fixed:?}", rule);
+    }
+}
+
+#[test]
+fn diagnostics_have_codes() {
+    let result = neotex_core::parse(r"\newcommand{\R}");
+    let code = result.diagnostics()[0].code().unwrap();
+    assert_eq!(explain(code).unwrap().rule, result.diagnostics()[0].rule);
+}
@@ -0,0 +1,36 @@
+//! Every combination of the features has to build on its own.
+
+use std::process::Command;
+
+const FEATURES: &[&str] = &["config", "lint", "serde", "spellcheck"];
+
+#[test]
+fn feature_combinations() {
+    for mask in 0..1 << FEATURES.len() {
+        let features: Vec<_> = (0..FEATURES.len())
+            .filter(|bit| mask & (1 << bit) != 0)
+            .map(|bit| FEATURES[bit])
+            .collect();
+        let output = Command::new(env!("CARGO"))
+            .args([
+                "check",
+                "--package",
+                "neotex-core",
+                "--lib",
+                "--no-default-features",
+                "--quiet",
+            ])
+            .args(["--features", &features.join(",")])
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            // a separate target directory avoids waiting for the lock held by the running build
+            .env("CARGO_TARGET_DIR", concat!(env!("CARGO_TARGET_TMPDIR"), "/features"))
+            .output()
+            .expect("failed to run cargo");
+
+        assert!(
+            output.status.success(),
+            "features {features:?}: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! Go-to-definition follows the `\input`s of a project: a name used in one file is looked up in
+//! every file, then in the stubs of the options and finally in the registry.
+
+use std::path::{Path, PathBuf};
+
+use neotex_core::{
+    analysis::{goto_definition, CommandStub, DefinitionSite},
+    project::Project,
+    ParseOptions,
+};
+
+const MAIN: &str = r"\input{macros}
+\begin{theorem} $\R$ \todo{x} \end{theorem}
+\ref{thm:main} \begin{tabular}{c} \end{tabular} \nothing";
+
+const MACROS: &str = r"\newcommand{\R}{\mathbb{R}}
+\newenvironment{theorem}{}{}
+\renewcommand{\R}{\mathbf{R}}\label{thm:main}";
+
+/// Writes the project into a fresh directory and loads it.
+fn project(name: &str) -> (PathBuf, Project) {
+    let dir = std::env::temp_dir().join(format!("neotex-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("main.tex"), MAIN).unwrap();
+    std::fs::write(dir.join("macros.tex"), MACROS).unwrap();
+
+    let stubs = vec![CommandStub::parse("\\todo", "{}").unwrap()];
+    let options = ParseOptions::default().with_commands(stubs);
+    let project = Project::load(dir.join("main.tex"), options).unwrap();
+    (dir, project)
+}
+
+#[test]
+fn goto_definition_across_files() {
+    let (dir, project) = project("goto");
+    let main = dir.join("main.tex");
+    let macros = dir.join("macros.tex");
+    let goto = |at: &str| goto_definition(&project, &main, MAIN.find(at).unwrap() + 1);
+    let text = |site: &DefinitionSite<'_>| match site {
+        DefinitionSite::File { file, range } => {
+            assert_eq!(*file, Path::new(&macros));
+            &MACROS[range.clone()]
+        }
+        site => panic!("expected a definition in a file, got {site:?}"),
+    };
+
+    // a redefined command has a candidate for every definition
+    let found = goto("\\R$");
+    let texts: Vec<_> = found.iter().map(text).collect();
+    assert_eq!(texts, [r"\newcommand{\R}{\mathbb{R}}", r"\renewcommand{\R}{\mathbf{R}}"]);
+
+    assert_eq!(
+        goto("theorem}").iter().map(text).collect::<Vec<_>>(),
+        [r"\newenvironment{theorem}{}{}"]
+    );
+    assert_eq!(goto("thm:main").iter().map(text).collect::<Vec<_>>(), ["thm:main"]);
+
+    assert!(matches!(goto("\\todo")[..], [DefinitionSite::Stub(stub)] if stub.name == "\\todo"));
+    assert_eq!(goto("tabular"), [DefinitionSite::Builtin { package: None }]);
+    assert_eq!(goto("\\nothing"), []);
+    // files outside of the project have no definitions
+    assert_eq!(goto_definition(&project, &dir.join("other.tex"), 1), []);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn root_of_a_chapter() {
+    let dir = std::env::temp_dir().join(format!("neotex-root-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("chapters")).unwrap();
+    std::fs::write(dir.join("main.tex"), "\\documentclass{book}\n\\input{chapters/intro}").unwrap();
+    std::fs::write(dir.join("other.tex"), "\\documentclass{article}").unwrap();
+    std::fs::write(dir.join("chapters/intro.tex"), "$\\R$").unwrap();
+    std::fs::write(dir.join("chapters/appendix.tex"), "% !TEX root = ../other.tex\n").unwrap();
+
+    let options = ParseOptions::default();
+    let intro = dir.join("chapters/intro.tex");
+    assert_eq!(Project::find_root(&intro, &options).unwrap(), dir.join("main.tex"));
+    let appendix = dir.join("chapters/appendix.tex");
+    let root = Project::find_root(&appendix, &options).unwrap();
+    assert_eq!(root, dir.join("chapters/../other.tex"));
+    let main = dir.join("main.tex");
+    assert_eq!(Project::find_root(&main, &options).unwrap(), main);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn root_of_a_subfile() {
+    let dir = std::env::temp_dir().join(format!("neotex-subfiles-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("chapters/figures")).unwrap();
+    let main = "\\documentclass{book}\n\\usepackage{subfiles}\n\\newcommand{\\R}{\\mathbb{R}}\n\
+                \\begin{document}\\subfile{chapters/intro}\\input{chapters/figures/plot}\\end{document}";
+    std::fs::write(dir.join("main.tex"), main).unwrap();
+    let intro = "\\documentclass[../main]{subfiles}\n\
+                 \\begin{document}$\\R$ \\subfile{details}\\end{document}";
+    std::fs::write(dir.join("chapters/intro.tex"), intro).unwrap();
+    std::fs::write(dir.join("chapters/details.tex"), "\\documentclass[../main]{subfiles}").unwrap();
+    std::fs::write(dir.join("chapters/figures/plot.tex"), "\\documentclass{standalone}").unwrap();
+
+    let options = ParseOptions::default();
+    let intro = dir.join("chapters/intro.tex");
+    let root = Project::find_root(&intro, &options).unwrap();
+    assert_eq!(root, dir.join("chapters/../main.tex"));
+    let plot = dir.join("chapters/figures/plot.tex");
+    assert_eq!(Project::find_root(&plot, &options).unwrap(), dir.join("main.tex"));
+
+    // the nested subfile is found next to the including one
+    let project = Project::load(&root, options).unwrap();
+    let files: Vec<_> = project.files().iter().map(|file| file.path.file_name().unwrap()).collect();
+    assert_eq!(files, ["main.tex", "intro.tex", "plot.tex", "details.tex"]);
+    let result = project.parse_file(&intro).unwrap();
+    assert!(result.diagnostics().is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,106 @@
+//! Includes resolve to the same files on every platform, see `Project::load`.
+
+use std::path::{Path, PathBuf};
+
+use neotex_core::{project::Project, AnalysisProfile, ParseOptions, PathCase};
+
+/// Writes `files` into a fresh directory and returns it.
+fn write(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("neotex-{name}-{}", std::process::id()));
+    for (path, content) in files {
+        std::fs::create_dir_all(dir.join(path).parent().unwrap()).unwrap();
+        std::fs::write(dir.join(path), content).unwrap();
+    }
+    dir
+}
+
+/// The names of the loaded files and the messages of the diagnostics of the root.
+fn load(dir: &Path, options: ParseOptions, search_paths: &[PathBuf]) -> (Vec<String>, Vec<String>) {
+    let project =
+        Project::load_with_search_paths(dir.join("main.tex"), options, search_paths).unwrap();
+    let files = project.files()[1..]
+        .iter()
+        .map(|file| file.path.strip_prefix(dir).unwrap().to_string_lossy().replace('\\', "/"))
+        .collect();
+    let messages = project.root().diagnostics.iter().map(|diagnostic| {
+        assert_eq!(diagnostic.rule, "ambiguous-path");
+        diagnostic.message.clone()
+    });
+    (files, messages.collect())
+}
+
+#[test]
+fn order_of_candidates() {
+    let dir = write(
+        "resolution-order",
+        &[
+            ("main.tex", r"\input{./a/../chapter.v2} \input{shared} \input{plain.tex}"),
+            ("chapter.v2.tex", ""),
+            ("chapter.v2", ""),
+            ("plain.tex", ""),
+            ("lib/shared.tex", ""),
+        ],
+    );
+
+    let (files, messages) = load(&dir, ParseOptions::default(), &[dir.join("lib")]);
+    assert_eq!(files, ["chapter.v2.tex", "lib/shared.tex", "plain.tex"]);
+    assert!(messages.is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn case_mismatch() {
+    let dir = write(
+        "resolution-case",
+        &[
+            ("main.tex", r"\input{Chapter1} \input{lib}"),
+            ("chapter1.tex", ""),
+            ("shared/lib.tex", ""),
+            ("shared/LIB.tex", ""),
+        ],
+    );
+
+    // an exact match in a search path wins over a case-insensitive one next to the root
+    let options = ParseOptions::default();
+    let (files, messages) = load(&dir, options.clone(), &[dir.join("shared")]);
+    assert_eq!(files, ["shared/lib.tex"]);
+    assert_eq!(
+        messages,
+        [
+            "'Chapter1' is not found, but matches 'chapter1.tex' on case-insensitive file systems",
+            "'lib' also matches 'LIB.tex' on case-insensitive file systems",
+        ]
+    );
+
+    let options = options.with_path_case(PathCase::Insensitive);
+    let (files, messages) = load(&dir, options, &[dir.join("shared")]);
+    assert_eq!(files, ["chapter1.tex", "shared/lib.tex"]);
+    assert_eq!(
+        messages[0],
+        "'Chapter1' matches 'chapter1.tex' only on case-insensitive file systems"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn text_only_skips_pictures() {
+    let dir = write(
+        "resolution-text-only",
+        &[
+            ("main.tex", r"\input{plot.pgf} \input{figures/chart.tikz} \input{intro}"),
+            ("plot.pgf", ""),
+            ("figures/chart.tikz", ""),
+            ("intro.tex", ""),
+        ],
+    );
+
+    let (files, _) = load(&dir, ParseOptions::default(), &[]);
+    assert_eq!(files, ["plot.pgf", "figures/chart.tikz", "intro.tex"]);
+    let options = ParseOptions::default().with_analysis(AnalysisProfile::TextOnly);
+    let (files, _) = load(&dir, options, &[]);
+    assert_eq!(files, ["intro.tex"]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,92 @@
+//! Expanding the user commands of a document and parsing the result again has to keep its
+//! structure: the same outline, the same labels and the same math once the macros inside it are
+//! expanded.
+//!
+//! There is no lowering of NeoTeX to LaTeX yet. Once there is, its output has to pass the same
+//! checks.
+
+use std::collections::BTreeSet;
+
+use neotex_core::{
+    ast::{AstNode, Document, MathBlock},
+    index::IndexKind,
+    outline::SectionLevel,
+    parse_with_profile, LexerProfile, ParseResult,
+};
+
+const DOCUMENTS: &[(&str, LexerProfile)] = &[
+    (
+        r"\newcommand{\R}{\mathbb{R}}
+\newcommand{\norm}[1]{\left\lVert #1 \right\rVert}
+\newcommand{\thm}[2][Theorem]{\textbf{#1.} #2}
+\section{Spaces over $\R$}\label{sec:spaces}
+The norm $\norm{x}$ of $x \in \R^n$ is
+\begin{equation}\label{eq:norm}
+  \norm{x} = \sqrt{\sum_i x_i^2}
+\end{equation}
+\subsection*{Remarks}
+\thm{See \eqref{eq:norm}.}
+\thm[Lemma]{Every $\norm{\cdot}$ is continuous.}\label{lem:continuous}",
+        LexerProfile::Latex,
+    ),
+    (
+        r"\def\pair(#1,#2){\langle #1, #2 \rangle}
+\newcommand{\set}[1]{\{#1\}}
+\chapter{Pairs}\label{ch:pairs}
+\[ \pair(a,b) \in \set{\pair(x,y)} \]
+\section{Sets}
+$\set{a, b}$ and \ref{ch:pairs}",
+        LexerProfile::NeoTex,
+    ),
+];
+
+fn outline(result: &ParseResult<'_>) -> Vec<(SectionLevel, bool)> {
+    result.outline().iter().map(|section| (section.level, section.starred)).collect()
+}
+
+fn labels<'a>(result: &'a ParseResult<'_>) -> BTreeSet<&'a str> {
+    result.index().entries_of(IndexKind::Label).map(|entry| entry.key).collect()
+}
+
+/// The content of every math block once all user commands are expanded, without whitespace.
+fn math(source: &str, profile: LexerProfile) -> Vec<String> {
+    let mut text = source.to_string();
+    // arguments of a call may contain calls themselves, so expand until nothing changes
+    loop {
+        let result = parse_with_profile(&text, profile);
+        let expanded = result.expanded_text().unwrap();
+        if expanded == text {
+            let document = Document::new(result.tokens());
+            return document
+                .descendants()
+                .filter_map(MathBlock::cast)
+                .map(|math| math.body().split_whitespace().collect())
+                .collect();
+        }
+        text = expanded;
+    }
+}
+
+#[test]
+fn expansion_keeps_structure() {
+    for &(source, profile) in DOCUMENTS {
+        let original = parse_with_profile(source, profile);
+        assert!(!original.has_errors(), "{:?}", original.diagnostics());
+
+        let expanded = original.expanded_text().unwrap();
+        let reparsed = parse_with_profile(&expanded, profile);
+        assert!(!reparsed.has_errors(), "{expanded}\n{:?}", reparsed.diagnostics());
+
+        assert_eq!(outline(&original), outline(&reparsed), "{expanded}");
+        assert_eq!(labels(&original), labels(&reparsed), "{expanded}");
+        assert_eq!(math(source, profile), math(&expanded, profile), "{expanded}");
+        assert!(!outline(&original).is_empty() && !math(source, profile).is_empty());
+    }
+}
+
+#[test]
+fn expansion_is_idempotent_without_definitions() {
+    let source = r"\section{A}\label{a} $x^2$ \[ \frac{1}{2} \] \ref{a}";
+    let result = parse_with_profile(source, LexerProfile::Latex);
+    assert_eq!(result.expanded_text().unwrap(), source);
+}
@@ -0,0 +1,325 @@
+//! Conversion of parse results into protocol responses.
+
+use std::iter::Peekable;
+
+use lsp_types::{
+    DiagnosticRelatedInformation, DiagnosticSeverity, DocumentSymbol, Documentation, Hover,
+    HoverContents, Location, MarkupContent, MarkupKind, NumberOrString, ParameterInformation,
+    ParameterLabel, Position, SemanticToken, SemanticTokenModifier, SemanticTokenType,
+    SemanticTokens, SemanticTokensEdit, SemanticTokensLegend, SignatureHelp, SignatureInformation,
+    SymbolKind, Url,
+};
+use neotex_core::{
+    analysis::{HoverInfo, SignatureOrigin},
+    diagnostics::{Diagnostic, Severity},
+    expansion::{DefinitionKind, ExpansionStoreItem},
+    index::IndexKind,
+    outline::Section,
+    registry, semantic, ParseResult, SyntaxKind, TokenAtOffset,
+};
+
+use crate::line_index::LineIndex;
+
+/// All diagnostics of `result`, the document at `uri`.
+pub fn diagnostics(
+    uri: &Url,
+    result: &ParseResult<'_>,
+    index: &LineIndex,
+) -> Vec<lsp_types::Diagnostic> {
+    let source = result.source();
+    let convert = |diagnostic: &Diagnostic| {
+        let related = diagnostic.related.iter().map(|related| DiagnosticRelatedInformation {
+            location: Location::new(uri.clone(), index.range(source, related.range.clone())),
+            message: related.message.clone(),
+        });
+
+        lsp_types::Diagnostic {
+            range: index.range(source, diagnostic.range.clone()),
+            severity: Some(match diagnostic.severity {
+                Severity::Error => DiagnosticSeverity::ERROR,
+                Severity::Warning => DiagnosticSeverity::WARNING,
+                Severity::Info => DiagnosticSeverity::INFORMATION,
+            }),
+            code: Some(NumberOrString::String(diagnostic.rule.to_string())),
+            source: Some("neotex".to_string()),
+            message: diagnostic.message.clone(),
+            related_information: Some(related.collect())
+                .filter(|related: &Vec<_>| !related.is_empty()),
+            ..Default::default()
+        }
+    };
+
+    result.diagnostics().iter().map(convert).collect()
+}
+
+/// The outline of the document, with the command and environment definitions and labels nested
+/// in the section containing them.
+pub fn document_symbols(result: &ParseResult<'_>, index: &LineIndex) -> Vec<DocumentSymbol> {
+    let source = result.source();
+    let tokens = result.tokens();
+    let mut symbols = Vec::new();
+
+    let definitions = result.definitions();
+    let mut items: Vec<_> = definitions.commands().chain(definitions.environments()).collect();
+    items.sort_by_key(|item| item.range.start);
+    for item in items {
+        let range = tokens.text_start(item.range.start)..tokens.text_start(item.range.end);
+        let kind = match item.kind {
+            DefinitionKind::Environment => SymbolKind::CLASS,
+            DefinitionKind::Command | DefinitionKind::Def => SymbolKind::FUNCTION,
+        };
+        let detail = match item.kind {
+            DefinitionKind::Environment => None,
+            _ => Some(item.signature()),
+        };
+        let start = range.start;
+        let symbol = symbol(item.name.to_string(), detail, kind, index.range(source, range));
+        symbols.push((start, symbol));
+    }
+
+    for label in result.index().entries_of(IndexKind::Label) {
+        let range = index.range(source, label.range.clone());
+        symbols
+            .push((label.range.start, symbol(label.key.to_string(), None, SymbolKind::KEY, range)));
+    }
+
+    symbols.sort_by_key(|(start, _)| *start);
+    let mut symbols = symbols.into_iter().peekable();
+    nest(result.outline().sections(), &mut symbols, usize::MAX, source, index)
+}
+
+/// Symbols for `sections` and the `symbols` starting before `end`, each placed into the innermost
+/// section containing it.
+fn nest(
+    sections: &[Section<'_>],
+    symbols: &mut Peekable<impl Iterator<Item = (usize, DocumentSymbol)>>,
+    end: usize,
+    source: &str,
+    index: &LineIndex,
+) -> Vec<DocumentSymbol> {
+    let mut nested = Vec::new();
+    for section in sections {
+        nested.extend(take_before(symbols, section.range.start));
+        let children = nest(&section.children, symbols, section.range.end, source, index);
+        nested.push(section_symbol(section, children, source, index));
+    }
+    nested.extend(take_before(symbols, end));
+    nested
+}
+
+fn take_before<'a>(
+    symbols: &'a mut Peekable<impl Iterator<Item = (usize, DocumentSymbol)>>,
+    end: usize,
+) -> impl Iterator<Item = DocumentSymbol> + 'a {
+    std::iter::from_fn(move || symbols.next_if(|(start, _)| *start < end)).map(|(_, symbol)| symbol)
+}
+
+#[allow(deprecated)]
+fn section_symbol(
+    section: &Section<'_>,
+    children: Vec<DocumentSymbol>,
+    source: &str,
+    index: &LineIndex,
+) -> DocumentSymbol {
+    let command = section.level.command();
+    let detail = if section.starred { format!("{command}*") } else { command.to_string() };
+    // clients reject symbols with an empty name
+    let name = match section.title {
+        "" => detail.clone(),
+        title => title.to_string(),
+    };
+    DocumentSymbol {
+        name,
+        detail: Some(detail),
+        kind: SymbolKind::MODULE,
+        tags: None,
+        deprecated: None,
+        range: index.range(source, section.range.clone()),
+        selection_range: index.range(source, section.heading.clone()),
+        children: Some(children),
+    }
+}
+
+#[allow(deprecated)]
+fn symbol(
+    name: String,
+    detail: Option<String>,
+    kind: SymbolKind,
+    range: lsp_types::Range,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        name,
+        detail,
+        kind,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range: range,
+        children: None,
+    }
+}
+
+/// Signature, body and expansion of the command at `position`.
+pub fn hover(result: &ParseResult<'_>, index: &LineIndex, position: Position) -> Option<Hover> {
+    let source = result.source();
+    let tokens = result.tokens();
+
+    let idx = match tokens.token_at_offset(index.offset(source, position)) {
+        TokenAtOffset::None => return None,
+        TokenAtOffset::Single(idx) => idx,
+        // the cursor right behind a command name still points at the command
+        TokenAtOffset::Between(left, right) => match tokens.kind(right) {
+            SyntaxKind::Command => right,
+            _ => left,
+        },
+    };
+    if tokens.kind(idx) != SyntaxKind::Command {
+        return None;
+    }
+
+    let value = match result.hover(tokens.text_start(idx)) {
+        Some(info) => {
+            // the definition in effect at a use, none at the definition itself
+            let call = result
+                .definitions()
+                .get_at(info.name, idx)
+                .map(|item| (item, &source[info.call.end..]));
+            definition_hover(&info, call)
+        }
+        None => {
+            let builtin = registry::command(tokens.text(idx))?;
+            let origin = match builtin.package {
+                Some(package) => format!("provided by `{package}`"),
+                None => "provided by the LaTeX kernel".to_string(),
+            };
+            format!("```latex\n{builtin}\n```\n{origin}")
+        }
+    };
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent { kind: MarkupKind::Markdown, value }),
+        range: Some(index.range(source, tokens.text_range(idx))),
+    })
+}
+
+/// Markdown describing the definition of `info`, including its expansion if `call` holds the
+/// definition in effect at a use and the text following it.
+fn definition_hover(info: &HoverInfo<'_>, call: Option<(&ExpansionStoreItem<'_>, &str)>) -> String {
+    let mut value = format!("```latex\n{}\n```\n", info.signature);
+    if let Some(documentation) = &info.documentation {
+        value.push_str(&format!("{documentation}\n\n"));
+    }
+    value.push_str(&format!("defined as\n```latex\n{}\n```", info.body));
+
+    if let Some((item, Ok((expansion, _)))) =
+        call.map(|(item, call)| (item, item.expand_call(call)))
+    {
+        if expansion == item.body {
+            return value;
+        }
+        value.push_str(&format!("\nexpands to\n```latex\n{expansion}\n```"));
+    }
+    value
+}
+
+/// The signature of the command whose arguments are being typed at `position`.
+pub fn signature_help(
+    result: &ParseResult<'_>,
+    index: &LineIndex,
+    position: Position,
+) -> Option<SignatureHelp> {
+    let signature = result.signature_at(index.offset(result.source(), position))?;
+
+    // parameter offsets are counted in UTF-16 code units of the label
+    let utf16 = |offset: usize| signature.label[..offset].encode_utf16().count() as u32;
+    let parameters = signature
+        .parameters
+        .iter()
+        .map(|parameter| ParameterInformation {
+            label: ParameterLabel::LabelOffsets([
+                utf16(parameter.label.start),
+                utf16(parameter.label.end),
+            ]),
+            documentation: None,
+        })
+        .collect();
+
+    let value = match (signature.origin, signature.documentation) {
+        (_, Some(documentation)) => documentation,
+        (SignatureOrigin::Document, None) => "defined in this document".to_string(),
+        (SignatureOrigin::Stub, None) => "declared in the configuration".to_string(),
+        (SignatureOrigin::Kernel, None) => "provided by the LaTeX kernel".to_string(),
+        (SignatureOrigin::Package(package), None) => format!("provided by `{package}`"),
+    };
+
+    Some(SignatureHelp {
+        signatures: vec![SignatureInformation {
+            label: signature.label,
+            documentation: Some(Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            })),
+            parameters: Some(parameters),
+            active_parameter: signature.active.map(|active| active as u32),
+        }],
+        active_signature: Some(0),
+        active_parameter: signature.active.map(|active| active as u32),
+    })
+}
+
+/// The token types and modifiers the indices of [`semantic_tokens`] refer to.
+pub fn semantic_tokens_legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: semantic::SemanticTokenType::ALL
+            .iter()
+            .map(|kind| SemanticTokenType::new(kind.as_str()))
+            .collect(),
+        token_modifiers: semantic::SemanticModifier::ALL
+            .iter()
+            .map(|modifier| SemanticTokenModifier::new(modifier.as_str()))
+            .collect(),
+    }
+}
+
+/// The classified tokens of the whole document, encoded relative to each other.
+pub fn semantic_tokens(result: &ParseResult<'_>) -> SemanticTokens {
+    let data = semantic::encode(result.source(), &result.semantic_tokens());
+    let data = data
+        .chunks_exact(5)
+        .map(|token| lsp_types::SemanticToken {
+            delta_line: token[0],
+            delta_start: token[1],
+            length: token[2],
+            token_type: token[3],
+            token_modifiers_bitset: token[4],
+        })
+        .collect();
+    SemanticTokens { result_id: None, data }
+}
+
+/// The edit turning the semantic tokens `old` into `new`, `None` if they are equal.
+///
+/// Only the tokens between the common prefix and suffix are replaced, which is typically a few
+/// tokens around the last change. Positions count the integers of the encoding, five per token.
+pub fn semantic_tokens_edit(
+    old: &[SemanticToken],
+    new: &[SemanticToken],
+) -> Option<SemanticTokensEdit> {
+    let prefix = old.iter().zip(new).take_while(|(old, new)| old == new).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let deleted = old.len() - prefix - suffix;
+    let inserted = &new[prefix..new.len() - suffix];
+    if deleted == 0 && inserted.is_empty() {
+        return None;
+    }
+    Some(SemanticTokensEdit {
+        start: 5 * prefix as u32,
+        delete_count: 5 * deleted as u32,
+        data: (!inserted.is_empty()).then(|| inserted.to_vec()),
+    })
+}
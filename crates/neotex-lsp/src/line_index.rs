@@ -0,0 +1,49 @@
+use lsp_types::Position;
+
+/// Converts between byte offsets and the line/UTF-16 column positions of the protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    /// Byte offset of the start of every line
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> LineIndex {
+        let line_starts =
+            std::iter::once(0).chain(text.match_indices('\n').map(|(idx, _)| idx + 1)).collect();
+        LineIndex { line_starts }
+    }
+
+    /// Byte offset of `position` in `text`. Positions past the end of a line are clamped to the
+    /// end of the line, positions past the last line to the end of the text.
+    pub fn offset(&self, text: &str, position: Position) -> usize {
+        let Some(&start) = self.line_starts.get(position.line as usize) else {
+            return text.len();
+        };
+        let end =
+            self.line_starts.get(position.line as usize + 1).map_or(text.len(), |end| end - 1);
+
+        let mut column = 0;
+        for (idx, c) in text[start..end].char_indices() {
+            if column >= position.character as usize {
+                return start + idx;
+            }
+            column += c.len_utf16();
+        }
+        end
+    }
+
+    /// Position of the byte `offset` in `text`.
+    pub fn position(&self, text: &str, offset: usize) -> Position {
+        let offset = offset.min(text.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+        let character = text[start..offset].chars().map(char::len_utf16).sum::<usize>();
+        Position::new(line as u32, character as u32)
+    }
+
+    /// Range of the bytes `range` in `text`.
+    pub fn range(&self, text: &str, range: std::ops::Range<usize>) -> lsp_types::Range {
+        lsp_types::Range::new(self.position(text, range.start), self.position(text, range.end))
+    }
+}
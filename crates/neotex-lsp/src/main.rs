@@ -0,0 +1,46 @@
+#![doc(
+    html_logo_url = "https://gist.githubusercontent.com/leonfuss/9247909d6237cb406439944fe22405a4/raw/02853019c2e0187bfb518dba1052ed5338c144c8/logo.svg"
+)]
+#![doc(
+    html_favicon_url = "https://gist.githubusercontent.com/leonfuss/fa44f11267796b352edaa121675cd6f2/raw/ae237aa701a5d087c89af313299858f5e1c1144f/favicon.svg"
+)]
+#![deny(missing_docs)]
+
+//! **Part of [NeoTeX](../neotex/index.html)**
+//!
+//! Language server for LaTeX and NeoTeX, speaking the Language Server Protocol over stdio.
+//!
+//! Every change of a document is applied to the stored text, which is then run through the
+//! [`neotex_core`] pipeline again. The server provides
+//! * diagnostics of all analysis passes
+//! * document symbols for definitions and labels
+//! * hover with the signature and expansion of the command under the cursor
+//! * semantic tokens, sent as the changes since the last response if the client supports it
+
+use std::error::Error;
+
+use lsp_server::Connection;
+
+mod handlers;
+mod line_index;
+mod server;
+
+type Result<R> = std::result::Result<R, Box<dyn Error + Send + Sync>>;
+
+fn main() -> Result<()> {
+    // stdout carries the protocol, so logs go to stderr
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(tracing::Level::INFO)
+        .init();
+
+    let (connection, io_threads) = Connection::stdio();
+    server::run(connection)?;
+    io_threads.join()?;
+
+    tracing::info!("shut down");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
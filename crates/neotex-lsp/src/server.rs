@@ -0,0 +1,310 @@
+//! The main loop dispatching the messages of the client.
+
+use std::collections::HashMap;
+
+use lsp_server::{
+    Connection, ErrorCode, ExtractError, Message, Notification, Request, RequestId, Response,
+};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+        PublishDiagnostics,
+    },
+    request::{
+        DocumentSymbolRequest, HoverRequest, Request as _, SemanticTokensFullDeltaRequest,
+        SemanticTokensFullRequest, SignatureHelpRequest,
+    },
+    DocumentSymbolResponse, HoverProviderCapability, OneOf, PublishDiagnosticsParams,
+    SemanticTokens, SemanticTokensDelta, SemanticTokensFullDeltaResult, SemanticTokensFullOptions,
+    SemanticTokensOptions, SemanticTokensResult, SemanticTokensServerCapabilities,
+    ServerCapabilities, SignatureHelpOptions, TextDocumentContentChangeEvent,
+    TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+use neotex_core::{config::Config, ParseOptions, ParseResult, SharedParse};
+
+use crate::{handlers, line_index::LineIndex, Result};
+
+/// An open document together with the analysis of its current version.
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub text: String,
+    pub version: i32,
+    pub options: ParseOptions,
+    parsed: SharedParse,
+    index: LineIndex,
+    /// The semantic tokens of the last response, a delta request refers to them by their result
+    /// id
+    semantic_tokens: SemanticTokens,
+    /// Number of semantic token responses, the result id of the next one
+    results: u64,
+}
+
+impl Document {
+    pub fn new(uri: &Url, text: String, version: i32) -> Document {
+        let options = parse_options(uri);
+        let parsed = SharedParse::new(text.as_str(), options.clone());
+        let index = LineIndex::new(&text);
+        Document {
+            text,
+            version,
+            options,
+            parsed,
+            index,
+            semantic_tokens: SemanticTokens::default(),
+            results: 0,
+        }
+    }
+
+    /// Applies the changes of a new version and analyzes it.
+    pub fn update(&mut self, version: i32, changes: Vec<TextDocumentContentChangeEvent>) {
+        for change in changes {
+            self.apply(change);
+        }
+        self.version = version;
+        self.parsed = SharedParse::new(self.text.as_str(), self.options.clone());
+        self.index = LineIndex::new(&self.text);
+    }
+
+    /// Applies an incremental change, or replaces the whole text if the change has no range. The
+    /// analysis is only updated by [`update`](Self::update).
+    pub fn apply(&mut self, change: TextDocumentContentChangeEvent) {
+        let Some(range) = change.range else {
+            self.text = change.text;
+            return;
+        };
+
+        let index = LineIndex::new(&self.text);
+        let start = index.offset(&self.text, range.start);
+        let end = index.offset(&self.text, range.end).max(start);
+        self.text.replace_range(start..end, &change.text);
+    }
+
+    /// Calls `f` with the analysis of the current version.
+    pub fn analyze<R>(&self, f: impl FnOnce(&ParseResult<'_>, &LineIndex) -> R) -> R {
+        f(&self.parsed.result(), &self.index)
+    }
+
+    /// The semantic tokens of the current version with a new result id.
+    pub fn semantic_tokens(&mut self) -> SemanticTokens {
+        let tokens = self.analyze(|result, _| handlers::semantic_tokens(result));
+        self.respond_with(tokens)
+    }
+
+    /// The changes of the semantic tokens since the response with `previous_result_id`, or all
+    /// tokens if it is not the last response.
+    pub fn semantic_tokens_delta(
+        &mut self,
+        previous_result_id: &str,
+    ) -> SemanticTokensFullDeltaResult {
+        let previous = std::mem::take(&mut self.semantic_tokens);
+        let tokens = self.semantic_tokens();
+        if previous.result_id.as_deref() != Some(previous_result_id) {
+            return SemanticTokensFullDeltaResult::Tokens(tokens);
+        }
+        let edits = handlers::semantic_tokens_edit(&previous.data, &tokens.data);
+        let result_id = tokens.result_id;
+        SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+            result_id,
+            edits: edits.into_iter().collect(),
+        })
+    }
+
+    /// Remembers `tokens` as the last response and gives them the next result id.
+    fn respond_with(&mut self, mut tokens: SemanticTokens) -> SemanticTokens {
+        self.results += 1;
+        tokens.result_id = Some(self.results.to_string());
+        self.semantic_tokens = tokens.clone();
+        tokens
+    }
+}
+
+/// The options of the `neotex.toml` next to the document or in one of its parent directories.
+/// An invalid configuration is logged and the defaults are used instead.
+fn parse_options(uri: &Url) -> ParseOptions {
+    let path = uri.to_file_path().unwrap_or_else(|()| uri.path().into());
+    let config = match path.parent().map(Config::discover) {
+        Some(Ok(Some((_, config)))) => config,
+        Some(Err(err)) => {
+            tracing::warn!("{err}");
+            Config::default()
+        }
+        _ => Config::default(),
+    };
+    config.parse_options(&path)
+}
+
+/// Runs the server until the client requests a shutdown.
+pub fn run(connection: Connection) -> Result<()> {
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::INCREMENTAL,
+        )),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        signature_help_provider: Some(SignatureHelpOptions {
+            trigger_characters: Some(vec!["{".to_string(), "[".to_string()]),
+            retrigger_characters: Some(vec!["}".to_string(), "]".to_string()]),
+            work_done_progress_options: Default::default(),
+        }),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+            SemanticTokensOptions {
+                legend: handlers::semantic_tokens_legend(),
+                full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                ..Default::default()
+            },
+        )),
+        ..Default::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+    tracing::info!("initialized");
+
+    let mut server = Server { connection: &connection, documents: HashMap::new() };
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) if connection.handle_shutdown(&request)? => break,
+            Message::Request(request) => server.request(request)?,
+            Message::Notification(notification) => server.notification(notification)?,
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+struct Server<'a> {
+    connection: &'a Connection,
+    documents: HashMap<Url, Document>,
+}
+
+impl Server<'_> {
+    /// Answers `request`, with an error if its parameters are malformed.
+    fn request(&mut self, request: Request) -> Result<()> {
+        let id = request.id.clone();
+        let response = self.respond(request).unwrap_or_else(|err| {
+            tracing::warn!("{err}");
+            Response::new_err(id, ErrorCode::InvalidParams as i32, err.to_string())
+        });
+        self.connection.sender.send(response.into())?;
+        Ok(())
+    }
+
+    fn respond(
+        &mut self,
+        request: Request,
+    ) -> std::result::Result<Response, ExtractError<Request>> {
+        let response = match request.method.as_str() {
+            HoverRequest::METHOD => {
+                let (id, params) = extract::<HoverRequest>(request)?;
+                let position = params.text_document_position_params;
+                let hover = self.documents.get(&position.text_document.uri).and_then(|document| {
+                    document
+                        .analyze(|result, index| handlers::hover(result, index, position.position))
+                });
+                Response::new_ok(id, hover)
+            }
+            SignatureHelpRequest::METHOD => {
+                let (id, params) = extract::<SignatureHelpRequest>(request)?;
+                let position = params.text_document_position_params;
+                let help = self.documents.get(&position.text_document.uri).and_then(|document| {
+                    document.analyze(|result, index| {
+                        handlers::signature_help(result, index, position.position)
+                    })
+                });
+                Response::new_ok(id, help)
+            }
+            DocumentSymbolRequest::METHOD => {
+                let (id, params) = extract::<DocumentSymbolRequest>(request)?;
+                let symbols = self.documents.get(&params.text_document.uri).map(|document| {
+                    DocumentSymbolResponse::Nested(document.analyze(handlers::document_symbols))
+                });
+                Response::new_ok(id, symbols)
+            }
+            SemanticTokensFullRequest::METHOD => {
+                let (id, params) = extract::<SemanticTokensFullRequest>(request)?;
+                let tokens = self
+                    .documents
+                    .get_mut(&params.text_document.uri)
+                    .map(|document| SemanticTokensResult::Tokens(document.semantic_tokens()));
+                Response::new_ok(id, tokens)
+            }
+            SemanticTokensFullDeltaRequest::METHOD => {
+                let (id, params) = extract::<SemanticTokensFullDeltaRequest>(request)?;
+                let delta = self
+                    .documents
+                    .get_mut(&params.text_document.uri)
+                    .map(|document| document.semantic_tokens_delta(&params.previous_result_id));
+                Response::new_ok(id, delta)
+            }
+            method => {
+                tracing::warn!("unsupported request {method}");
+                let code = ErrorCode::MethodNotFound as i32;
+                Response::new_err(request.id, code, format!("unsupported request {method}"))
+            }
+        };
+        Ok(response)
+    }
+
+    fn notification(&mut self, notification: Notification) -> Result<()> {
+        let uri = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Some(params) = params::<DidOpenTextDocument>(notification) else {
+                    return Ok(());
+                };
+                let item = params.text_document;
+                let document = Document::new(&item.uri, item.text, item.version);
+                self.documents.insert(item.uri.clone(), document);
+                item.uri
+            }
+            DidChangeTextDocument::METHOD => {
+                let Some(params) = params::<DidChangeTextDocument>(notification) else {
+                    return Ok(());
+                };
+                let uri = params.text_document.uri;
+                let Some(document) = self.documents.get_mut(&uri) else {
+                    return Ok(());
+                };
+                document.update(params.text_document.version, params.content_changes);
+                uri
+            }
+            DidCloseTextDocument::METHOD => {
+                let Some(params) = params::<DidCloseTextDocument>(notification) else {
+                    return Ok(());
+                };
+                self.documents.remove(&params.text_document.uri);
+                params.text_document.uri
+            }
+            _ => return Ok(()),
+        };
+
+        self.publish_diagnostics(uri)
+    }
+
+    /// Sends the diagnostics of the document at `uri`, or clears them if it was closed.
+    fn publish_diagnostics(&self, uri: Url) -> Result<()> {
+        let (diagnostics, version) = match self.documents.get(&uri) {
+            Some(document) => (
+                document.analyze(|result, index| handlers::diagnostics(&uri, result, index)),
+                Some(document.version),
+            ),
+            None => (Vec::new(), None),
+        };
+
+        let params = PublishDiagnosticsParams { uri, diagnostics, version };
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        self.connection.sender.send(notification.into())?;
+        Ok(())
+    }
+}
+
+fn extract<R: lsp_types::request::Request>(
+    request: Request,
+) -> std::result::Result<(RequestId, R::Params), ExtractError<Request>> {
+    request.extract(R::METHOD)
+}
+
+/// The parameters of `notification`, or `None` if they are malformed. There is no response to a
+/// notification, so the error is only logged.
+fn params<N: lsp_types::notification::Notification>(
+    notification: Notification,
+) -> Option<N::Params> {
+    notification.extract(N::METHOD).map_err(|err| tracing::warn!("{err}")).ok()
+}
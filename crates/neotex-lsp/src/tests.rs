@@ -0,0 +1,279 @@
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId};
+use lsp_types::{
+    DiagnosticSeverity, Documentation, HoverContents, NumberOrString, ParameterLabel, Position,
+    Range, SemanticToken, SemanticTokensDelta, SemanticTokensFullDeltaResult, SymbolKind,
+    TextDocumentContentChangeEvent, Url,
+};
+use neotex_core::parse;
+
+use crate::{handlers, line_index::LineIndex, server, server::Document};
+
+#[test]
+fn line_index() {
+    let text = "a𝔸b\n\nc";
+    let index = LineIndex::new(text);
+
+    // '𝔸' takes two UTF-16 code units
+    assert_eq!(index.offset(text, Position::new(0, 3)), 5);
+    assert_eq!(index.position(text, 5), Position::new(0, 3));
+    assert_eq!(index.position(text, 7), Position::new(1, 0));
+    assert_eq!(index.offset(text, Position::new(0, 99)), 6);
+    assert_eq!(index.offset(text, Position::new(2, 1)), 9);
+    assert_eq!(index.offset(text, Position::new(9, 0)), text.len());
+}
+
+#[test]
+fn incremental_changes() {
+    let uri = Url::parse("file:///main.tex").unwrap();
+    let mut document = Document::new(&uri, "\\section{A}\n$x$".to_string(), 1);
+
+    let change = |range, text: &str| TextDocumentContentChangeEvent {
+        range: Some(range),
+        range_length: None,
+        text: text.to_string(),
+    };
+    document.apply(change(Range::new(Position::new(1, 1), Position::new(1, 2)), "y^2"));
+    document.apply(change(Range::new(Position::new(0, 9), Position::new(0, 9)), "Intro "));
+    assert_eq!(document.text, "\\section{Intro A}\n$y^2$");
+
+    let replace = TextDocumentContentChangeEvent {
+        range: None,
+        range_length: None,
+        text: "\\label{a}".into(),
+    };
+    document.update(2, vec![replace]);
+    assert_eq!((document.text.as_str(), document.version), ("\\label{a}", 2));
+    document.analyze(|result, _| assert_eq!(result.index().entries().len(), 1));
+}
+
+#[test]
+fn diagnostics_and_symbols() {
+    let uri = Url::parse("file:///main.tex").unwrap();
+    let text = "\\newcommand{\\R}[1]{\\mathbb{#1}}\n\\label{a}\\label{a} \\ref{a}\n\\newenvironment{proof}{}{}";
+    let result = parse(text);
+    let index = LineIndex::new(text);
+
+    let diagnostics = handlers::diagnostics(&uri, &result, &index);
+    let duplicate = &diagnostics[0];
+    assert_eq!(duplicate.code, Some(NumberOrString::String("duplicate-label".into())));
+    assert_eq!(duplicate.severity, Some(DiagnosticSeverity::WARNING));
+    assert_eq!(duplicate.range, Range::new(Position::new(1, 16), Position::new(1, 17)));
+    let related = duplicate.related_information.as_ref().unwrap();
+    assert_eq!(related[0].location.range.start, Position::new(1, 7));
+
+    let symbols: Vec<_> = handlers::document_symbols(&result, &index)
+        .into_iter()
+        .map(|symbol| (symbol.name, symbol.detail, symbol.kind))
+        .collect();
+    assert_eq!(
+        symbols,
+        [
+            ("\\R".to_string(), Some("\\R{#1}".to_string()), SymbolKind::FUNCTION),
+            ("a".to_string(), None, SymbolKind::KEY),
+            ("a".to_string(), None, SymbolKind::KEY),
+            ("proof".to_string(), None, SymbolKind::CLASS),
+        ]
+    );
+}
+
+#[test]
+fn outline_symbols() {
+    let text = "\\newcommand{\\R}{}\n\\section{Intro}\\label{a}\n\\subsection*{}\\label{b}\n\\section{End}";
+    let result = parse(text);
+    let index = LineIndex::new(text);
+
+    let symbols = handlers::document_symbols(&result, &index);
+    let names = |symbols: &[lsp_types::DocumentSymbol]| -> Vec<_> {
+        symbols.iter().map(|symbol| (symbol.name.clone(), symbol.kind)).collect()
+    };
+    assert_eq!(
+        names(&symbols),
+        [
+            ("\\R".to_string(), SymbolKind::FUNCTION),
+            ("Intro".to_string(), SymbolKind::MODULE),
+            ("End".to_string(), SymbolKind::MODULE),
+        ]
+    );
+
+    let intro = &symbols[1];
+    assert_eq!(intro.range, Range::new(Position::new(1, 0), Position::new(3, 0)));
+    assert_eq!(intro.selection_range, Range::new(Position::new(1, 0), Position::new(1, 15)));
+    let children = intro.children.as_deref().unwrap();
+    assert_eq!(
+        names(children),
+        [("a".to_string(), SymbolKind::KEY), ("\\subsection*".to_string(), SymbolKind::MODULE)]
+    );
+    assert_eq!(
+        names(children[1].children.as_deref().unwrap()),
+        [("b".to_string(), SymbolKind::KEY)]
+    );
+}
+
+#[test]
+fn hover() {
+    let text = "%% Blackboard bold\n\\newcommand{\\R}[1]{\\mathbb{#1}}\n$\\R{N} \\frac{1}{2} x$";
+    let result = parse(text);
+    let index = LineIndex::new(text);
+    let markdown = |position| match handlers::hover(&result, &index, position)?.contents {
+        HoverContents::Markup(content) => Some(content.value),
+        _ => None,
+    };
+
+    let call = markdown(Position::new(2, 3)).unwrap();
+    assert!(call.starts_with("```latex\n\\R{#1}\n```\nBlackboard bold\n\ndefined as"));
+    assert!(call.ends_with("expands to\n```latex\n\\mathbb{N}\n```"));
+
+    // no expansion at the definition itself
+    let definition = markdown(Position::new(1, 13)).unwrap();
+    assert!(definition.ends_with("defined as\n```latex\n\\mathbb{#1}\n```"));
+
+    let builtin = markdown(Position::new(2, 9)).unwrap();
+    assert_eq!(builtin, "```latex\n\\frac{#1}{#2}\n```\nprovided by the LaTeX kernel");
+    assert_eq!(markdown(Position::new(2, 19)), None);
+}
+
+#[test]
+fn signature_help() {
+    let text =
+        "% Real numbers to the power\n\\newcommand{\\R}[1][n]{\\mathbb{R}^#1}\n$\\R[3] \\frac{1}{";
+    let result = parse(text);
+    let index = LineIndex::new(text);
+    let help = |position| handlers::signature_help(&result, &index, position);
+    let documentation = |signature: &lsp_types::SignatureInformation| match &signature.documentation
+    {
+        Some(Documentation::MarkupContent(content)) => content.value.clone(),
+        _ => String::new(),
+    };
+
+    let frac = help(Position::new(2, 16)).unwrap();
+    assert_eq!(frac.active_parameter, Some(1));
+    let signature = &frac.signatures[0];
+    assert_eq!(signature.label, "\\frac{#1}{#2}");
+    assert_eq!(documentation(signature), "provided by the LaTeX kernel");
+    let labels: Vec<_> =
+        signature.parameters.as_ref().unwrap().iter().map(|p| p.label.clone()).collect();
+    assert_eq!(
+        labels,
+        [ParameterLabel::LabelOffsets([5, 9]), ParameterLabel::LabelOffsets([9, 13])]
+    );
+
+    let user = help(Position::new(2, 4)).unwrap();
+    assert_eq!((user.signatures[0].label.as_str(), user.active_parameter), ("\\R[#1]", Some(0)));
+    assert_eq!(documentation(&user.signatures[0]), "Real numbers to the power");
+    assert_eq!(help(Position::new(2, 0)), None);
+}
+
+#[test]
+fn semantic_tokens() {
+    let result = parse("\\newcommand{\\R}{x}\n$\\R$");
+    let tokens: Vec<_> = handlers::semantic_tokens(&result)
+        .data
+        .iter()
+        .map(|token| {
+            (
+                token.delta_line,
+                token.delta_start,
+                token.length,
+                token.token_type,
+                token.token_modifiers_bitset,
+            )
+        })
+        .collect();
+    assert_eq!(
+        tokens,
+        [(0, 0, 11, 4, 0), (0, 12, 2, 0, 1), (1, 0, 1, 7, 0), (0, 1, 2, 0, 0), (0, 2, 1, 7, 0)]
+    );
+
+    let legend = handlers::semantic_tokens_legend();
+    assert_eq!(legend.token_types[4].as_str(), "keyword");
+    assert_eq!(legend.token_modifiers[0].as_str(), "definition");
+}
+
+#[test]
+fn semantic_tokens_delta() {
+    let uri = Url::parse("file:///main.tex").unwrap();
+    let mut document = Document::new(&uri, "\\newcommand{\\R}{x}\n$\\R$".to_string(), 1);
+    let full = document.semantic_tokens();
+    assert_eq!((full.result_id.as_deref(), full.data.len()), (Some("1"), 5));
+
+    let insert = TextDocumentContentChangeEvent {
+        range: Some(Range::new(Position::new(1, 0), Position::new(1, 0))),
+        range_length: None,
+        text: "$\\R$ ".to_string(),
+    };
+    document.update(2, vec![insert]);
+    let SemanticTokensFullDeltaResult::TokensDelta(delta) = document.semantic_tokens_delta("1")
+    else {
+        panic!("expected a delta");
+    };
+    // the three tokens of the inserted math follow the unchanged ones
+    assert_eq!(delta.result_id.as_deref(), Some("2"));
+    let edits: Vec<_> = delta
+        .edits
+        .iter()
+        .map(|edit| (edit.start, edit.delete_count, edit.data.as_ref().map(Vec::len)))
+        .collect();
+    assert_eq!(edits, [(25, 0, Some(3))]);
+
+    let unchanged = SemanticTokensDelta { result_id: Some("3".into()), edits: Vec::new() };
+    assert_eq!(document.semantic_tokens_delta("2"), unchanged.into());
+    // an outdated result id gets all tokens
+    let outdated = document.semantic_tokens_delta("2");
+    let SemanticTokensFullDeltaResult::Tokens(tokens) = outdated else {
+        panic!("expected all tokens");
+    };
+    assert_eq!((tokens.result_id.as_deref(), tokens.data.len()), (Some("4"), 8));
+
+    let token = |length| SemanticToken { length, ..SemanticToken::default() };
+    let edit = handlers::semantic_tokens_edit(
+        &[token(1), token(2), token(3)],
+        &[token(1), token(4), token(3)],
+    );
+    assert_eq!(
+        edit.map(|edit| (edit.start, edit.delete_count, edit.data)),
+        Some((5, 5, Some(vec![token(4)])))
+    );
+    assert_eq!(handlers::semantic_tokens_edit(&[token(1)], &[token(1)]), None);
+}
+
+#[test]
+fn malformed_params() {
+    let (connection, client) = Connection::memory();
+    let server = std::thread::spawn(move || server::run(connection).unwrap());
+    let request = |id: i32, method: &str, params: serde_json::Value| {
+        let request = Request::new(RequestId::from(id), method.to_string(), params);
+        client.sender.send(request.into()).unwrap();
+        match client.receiver.recv().unwrap() {
+            Message::Response(response) => response,
+            message => panic!("expected a response, got {message:?}"),
+        }
+    };
+    let notify = |method: &str, params: serde_json::Value| {
+        let notification = Notification::new(method.to_string(), params);
+        client.sender.send(notification.into()).unwrap();
+    };
+    let initialize = serde_json::json!({ "capabilities": {} });
+    assert!(request(1, "initialize", initialize).error.is_none());
+    notify("initialized", serde_json::json!({}));
+
+    // the server answers a request with malformed parameters and keeps running
+    let error = request(2, "textDocument/hover", serde_json::json!({ "position": 1 })).error;
+    assert_eq!(error.map(|error| error.code), Some(ErrorCode::InvalidParams as i32));
+
+    // a malformed notification is ignored, the next one is handled
+    notify("textDocument/didOpen", serde_json::json!({ "textDocument": 1 }));
+    let item = serde_json::json!({
+        "uri": "file:///doc.tex", "languageId": "latex", "version": 1, "text": "\\undefined"
+    });
+    notify("textDocument/didOpen", serde_json::json!({ "textDocument": item }));
+    match client.receiver.recv().unwrap() {
+        Message::Notification(notification) => {
+            assert_eq!(notification.method, "textDocument/publishDiagnostics")
+        }
+        message => panic!("expected the diagnostics, got {message:?}"),
+    }
+
+    assert!(request(3, "shutdown", serde_json::Value::Null).error.is_none());
+    notify("exit", serde_json::Value::Null);
+    server.join().unwrap();
+}
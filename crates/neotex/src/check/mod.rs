@@ -0,0 +1,56 @@
+//! `neotex check`: prints the diagnostics of files and applies their fixes with `--fix`.
+//!
+//! Fixing takes the fixes of all diagnostics which do not overlap, see [`fixes`], applies them
+//! and analyzes the text again, since a skipped fix may apply to the new text. This repeats until
+//! no fix is left, at most [`MAX_ROUNDS`] times. A fixed text is only accepted if it has no more
+//! errors than the original.
+
+use neotex_core::{
+    diagnostics::{fixes, Severity},
+    parse_with_options, ParseOptions,
+};
+
+use crate::{output, Result};
+
+/// Maximum number of times the fixes are applied to a file.
+pub const MAX_ROUNDS: usize = 8;
+
+/// A text with the fixes of its diagnostics applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixed {
+    /// The fixed text
+    pub text: String,
+    /// Number of applied edits
+    pub edits: usize,
+}
+
+/// Applies the fixes of the diagnostics of `source`, analyzed with `options`. Fails if the
+/// fixed text has more errors than `source`.
+pub fn fix(source: &str, options: &ParseOptions) -> Result<Fixed> {
+    let errors = |text: &str| {
+        let result = parse_with_options(text, options.clone());
+        let diagnostics = result.diagnostics();
+        let errors = diagnostics.iter().filter(|diag| diag.severity == Severity::Error).count();
+        (errors, fixes(diagnostics))
+    };
+
+    let (before, mut edits) = errors(source);
+    let mut fixed = Fixed { text: source.to_string(), edits: 0 };
+    let mut after = before;
+    for _ in 0..MAX_ROUNDS {
+        if edits.is_empty() {
+            break;
+        }
+        fixed.text = output::apply_edits(&fixed.text, &edits);
+        fixed.edits += edits.len();
+        (after, edits) = errors(&fixed.text);
+    }
+
+    match after > before {
+        true => Err(format!("the fixes would add {} errors", after - before).into()),
+        false => Ok(fixed),
+    }
+}
+
+#[cfg(test)]
+mod tests;
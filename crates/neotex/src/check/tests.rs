@@ -0,0 +1,16 @@
+use neotex_core::{analysis::LintConfig, ParseOptions};
+
+use super::fix;
+
+#[test]
+fn fixes_until_clean() {
+    let options = ParseOptions::default().with_lints(LintConfig::all());
+    let source = "\\begin{document}\n$$x^2$$ and \\textbf{a {b\n\\end{document}\n";
+    let fixed = fix(source, &options).unwrap();
+    assert_eq!(fixed.text, "\\begin{document}\n\\[x^2\\] and \\textbf{a {b\n}}\\end{document}\n");
+    assert_eq!(fixed.edits, 3);
+
+    // repeated words are only reported
+    let source = "\\begin{document}\nthe the end\n\\end{document}\n";
+    assert_eq!(fix(source, &options).unwrap().text, source);
+}
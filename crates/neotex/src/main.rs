@@ -11,10 +11,26 @@
 //!
 //!
 //! # Crates
+//! * [neotex-core](../neotex_core/index.html)
 //! * [lexer](../lexer/index.html)
 //! * [parser](../parser/index.html)
 
-use std::{error::Error, path::PathBuf};
+use std::{
+    error::Error,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use neotex_core::{analysis, config::Config, diagnostics, prelude::*, project::Project};
+
+mod check;
+mod output;
+mod report;
+mod serve;
+mod stats;
+mod watch;
+
+use output::{FileChange, Output, WriteMode};
 
 extern crate tracing;
 
@@ -30,30 +46,482 @@ fn main() -> Result<()> {
         // sets this to be the default, global collector for this application.
         .init();
 
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    let mut out = Output::from_args(&mut args)?;
     if let Some(s) = args.get(1) {
         match s.as_str() {
-            "tokens" if args.get(2).is_some() => token_stream(args.get(2).unwrap())?,
+            "tokens" if args.get(2).is_some() => token_stream(args.get(2).unwrap(), &mut out)?,
+            "defs" => match &args[2..] {
+                [flag, format, path] if flag == "--format" => definitions(path, format, &mut out)?,
+                [path] => definitions(path, "text", &mut out)?,
+                _ => output::status("usage: neotex defs [--format text|json] <file>"),
+            },
+            "check" => check(&args[2..], &mut out)?,
+            "rename" => rename(args[2..].to_vec(), &mut out)?,
+            "fmt" => fmt(args[2..].to_vec(), &mut out)?,
+            "organize-preamble" => organize_preamble(args[2..].to_vec(), &mut out)?,
+            "expand" => expand(&args[2..], &mut out)?,
+            "explain" => explain(&args[2..], &mut out)?,
+            "outline" => match &args[2..] {
+                [path] => outline(path, &mut out)?,
+                _ => output::status("usage: neotex outline <file>"),
+            },
+            "report" => match &args[2..] {
+                [flag, format, path] if flag == "--format" => report(path, format, &mut out)?,
+                [path] => report(path, "html", &mut out)?,
+                _ => output::status("usage: neotex report [--format html|json] <file>"),
+            },
+            "serve" => match &args[2..] {
+                [flag] if flag == "--simple" => serve::run(std::io::stdin().lock(), &mut out)?,
+                _ => output::status("usage: neotex serve --simple"),
+            },
+            "stats" => match &args[2..] {
+                [flag, format, path] if flag == "--format" => stats(path, format, &mut out)?,
+                [path] => stats(path, "text", &mut out)?,
+                _ => output::status("usage: neotex stats [--format text|json|csv] <file>"),
+            },
+            "symbols" => match &args[2..] {
+                [path] => symbols(path, "", &mut out)?,
+                [path, query] => symbols(path, query, &mut out)?,
+                _ => output::status("usage: neotex symbols <file> [query]"),
+            },
+            "watch" => watch(&args[2..], &mut out)?,
+
+            s => output::status(format_args!("called unknown {s} or with false argument count")),
+        }
+    }
+    out.finish()
+}
+
+/// Prints the diagnostics of files, e.g. `neotex check --fix main.tex`. With `--fix` the fixes of
+/// the diagnostics are applied to the files first. Fails if a file has errors.
+fn check(args: &[String], out: &mut Output) -> Result<()> {
+    let fix = args.first().is_some_and(|arg| arg == "--fix");
+    let paths = &args[usize::from(fix)..];
+    if paths.is_empty() {
+        output::status("usage: neotex check [--fix] <file>...");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for path in paths.iter().map(PathBuf::from) {
+        let mut src = std::fs::read_to_string(&path)?;
+        let options = config(&path)?.parse_options(&path);
+        if fix {
+            let fixed =
+                check::fix(&src, &options).map_err(|err| format!("{}: {err}", path.display()))?;
+            let change = FileChange::new(&path, src, fixed.text.clone());
+            output::apply(&[change], WriteMode::Write { backup: false }, out)?;
+            src = fixed.text;
+        }
+
+        // the fixed text is analyzed again, so only what is left is reported
+        let result = parse_with_options(&src, options);
+        match result.diagnostics().is_empty() {
+            true => writeln!(out, "{}: no problems", path.display())?,
+            false => writeln!(out, "{}:", path.display())?,
+        }
+        for diagnostic in result.diagnostics() {
+            writeln!(out, "  {}", output::diagnostic(&src, diagnostic, out.color()))?;
+        }
+        failed += usize::from(result.has_errors());
+    }
+    match failed {
+        0 => Ok(()),
+        1 => Err("1 file has errors".into()),
+        n => Err(format!("{n} files have errors").into()),
+    }
+}
+
+/// Checks a document whenever it changes. Every `--disable <stage>` drops the diagnostics of a
+/// stage, e.g. `neotex watch --disable expansion main.tex` while drafting.
+fn watch(mut args: &[String], out: &mut Output) -> Result<()> {
+    let mut disabled = Vec::new();
+    while let [flag, name, rest @ ..] = args {
+        if flag != "--disable" {
+            break;
+        }
+        let names = || diagnostics::Stage::ALL.map(|stage| stage.name()).join(", ");
+        let stage = diagnostics::Stage::from_name(name)
+            .ok_or_else(|| format!("unknown stage '{name}', expected one of {}", names()))?;
+        disabled.push(stage);
+        args = rest;
+    }
+    match args {
+        [path] => watch::run(&root(Path::new(path))?, disabled, out),
+        _ => {
+            output::status("usage: neotex watch [--disable <stage>]... <file>");
+            Ok(())
+        }
+    }
+}
+
+/// The `neotex.toml` of the file at `path` or of one of its parent directories, the defaults if
+/// there is none.
+fn config(path: &Path) -> Result<Config> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => std::env::current_dir()?,
+    };
+    Ok(Config::discover(&dir)?.map(|(_, config)| config).unwrap_or_default())
+}
+
+/// Names the root file of the project of every file, instead of looking for it with
+/// [`Project::find_root`].
+const ROOT_VARIABLE: &str = "NEOTEX_ROOT";
+
+/// The root file of the project containing the file at `path`.
+fn root(path: &Path) -> Result<PathBuf> {
+    match std::env::var_os(ROOT_VARIABLE) {
+        Some(root) => Ok(PathBuf::from(root)),
+        None => Ok(Project::find_root(path, &config(path)?.parse_options(path))?),
+    }
+}
+
+/// The project containing the file at `path`, loaded from its [`root`].
+fn project(path: &Path) -> Result<Project> {
+    let root = root(path)?;
+    let config = config(&root)?;
+    Ok(Project::load_with_search_paths(&root, config.parse_options(&root), &config.search_paths)?)
+}
+
+fn token_stream(path: &str, out: &mut Output) -> Result<()> {
+    let path = PathBuf::from(path);
+    output::status(format_args!("reading {path:?}..."));
+    let src = std::fs::read_to_string(&path)?;
+    let options = config(&path)?.parse_options(&path);
+
+    output::status("parsing input...");
+    // within the project, so the definitions of the other files are known
+    let project = project(&path)?;
+    let result = match project.parse_file(&path) {
+        Some(result) => result,
+        None => parse_with_options(&src, options),
+    };
 
-            s => println!("called unknown {s} or with false argument count"),
+    for token in result.tokens().spanned_tokens() {
+        writeln!(out, "{:?} {:?} - {:?}", token.kind, token.range, token.text)?;
+    }
+
+    writeln!(out, "{:?}", result.definitions())?;
+
+    for diagnostic in result.diagnostics() {
+        writeln!(
+            out,
+            "{:?} [{}] {:?}: {}",
+            diagnostic.severity, diagnostic.rule, diagnostic.range, diagnostic.message
+        )?;
+        for line in diagnostics::snippet(&src, diagnostic.range.clone(), 1, 100) {
+            writeln!(out, "{:>5} | {}", line.number, line.text)?;
         }
     }
+
     Ok(())
 }
 
-fn token_stream(path: &str) -> Result<()> {
+/// Prints all definitions of a file, `json` is meant to be consumed by other tools.
+fn definitions(path: &str, format: &str, out: &mut Output) -> Result<()> {
     let path = PathBuf::from(path);
-    println!("reading {path:?}...",);
-    let src = std::fs::read_to_string(path)?;
+    let src = std::fs::read_to_string(&path)?;
+    let result = parse_with_options(&src, config(&path)?.parse_options(&path));
+    let definitions = result.definitions().export(result.tokens());
 
-    println!("lexing input...");
+    match format {
+        "json" => writeln!(out, "{}", serde_json::to_string_pretty(&definitions)?)?,
+        "text" => {
+            for definition in definitions {
+                writeln!(
+                    out,
+                    "{:?} {} [{}] {:?}",
+                    definition.kind, definition.name, definition.arg_count, definition.body
+                )?;
+            }
+        }
+        format => return Err(format!("unknown format '{format}', expected text or json").into()),
+    }
 
-    // println!("{lexed:?}");
+    Ok(())
+}
+
+/// Prints a file with all user commands expanded. `neotex expand --trace main.tex 12:5` prints
+/// every substitution of the call at line 12, column 5 instead.
+fn expand(args: &[String], out: &mut Output) -> Result<()> {
+    use neotex_core::expansion::ExpansionTrace;
 
-    println!("resolving macors...");
-    // let store = parser::expansion::resolve(&lexed);
+    let (trace, args) = match args {
+        [flag, args @ ..] if flag == "--trace" => (true, args),
+        args => (false, args),
+    };
+    let (path, position) = match args {
+        [path] => (PathBuf::from(path), None),
+        [path, position] => (PathBuf::from(path), Some(position)),
+        _ => {
+            output::status("usage: neotex expand [--trace] <file> [<line>:<column>]");
+            return Ok(());
+        }
+    };
+    let src = std::fs::read_to_string(&path)?;
+    // within the project, so the commands defined in the preamble of the root are expanded too
+    let project = project(&path)?;
+    let result = match project.parse_file(&path) {
+        Some(result) => result,
+        None => parse_with_options(&src, config(&path)?.parse_options(&path)),
+    };
+    let lexed = result.tokens();
 
-    // println!("{store:?}");
+    let range = match position {
+        Some(position) => {
+            let call = position_offset(&src, position)
+                .and_then(|offset| lexed.token_at_offset(offset).right_biased())
+                .filter(|&idx| result.definitions().get(lexed.text(idx)).is_some());
+            let idx = call.ok_or_else(|| format!("no call of a user command at {position}"))?;
+            idx..idx + 1
+        }
+        None => 0..lexed.len(),
+    };
 
+    let mut steps = ExpansionTrace::new();
+    let expanded = lexed.text_with_traced_expansion(range, result.definitions(), &mut steps);
+    if !trace {
+        writeln!(out, "{expanded}")?;
+        return Ok(());
+    }
+
+    for step in steps.steps() {
+        let indent = "  ".repeat(step.depth);
+        match step.depth {
+            0 => {
+                let line = src[..step.range.start].matches('\n').count() + 1;
+                writeln!(out, "{indent}{} (line {line})", step.call)?;
+            }
+            _ => writeln!(out, "{indent}{}", step.call)?,
+        }
+        for (idx, argument) in step.arguments.iter().enumerate() {
+            writeln!(out, "{indent}  #{} = {argument}", idx + 1)?;
+        }
+        writeln!(out, "{indent}  -> {}", step.output)?;
+    }
+    if steps.is_truncated() {
+        writeln!(out, "(stopped after {} steps or at depth {})", steps.max_steps, steps.max_depth)?;
+    }
+    writeln!(out, "{expanded}")?;
     Ok(())
 }
+
+/// Prints the explanation of a diagnostic code like `E0004` or a rule id like `missing-body`, or a
+/// list of all codes without an argument. `json` is meant to be consumed by editors.
+fn explain(args: &[String], out: &mut Output) -> Result<()> {
+    use neotex_core::diagnostics::EXPLANATIONS;
+
+    let (format, args) = match args {
+        [flag, format, args @ ..] if flag == "--format" => (format.as_str(), args),
+        args => ("text", args),
+    };
+    let explanation = match args {
+        [] => None,
+        [code] => Some(
+            diagnostics::explain(code)
+                .ok_or_else(|| format!("unknown diagnostic code '{code}'"))?,
+        ),
+        _ => {
+            output::status("usage: neotex explain [--format text|json] [<code>]");
+            return Ok(());
+        }
+    };
+
+    match (format, explanation) {
+        ("json", Some(explanation)) => {
+            writeln!(out, "{}", serde_json::to_string_pretty(explanation)?)?
+        }
+        ("json", None) => writeln!(out, "{}", serde_json::to_string_pretty(EXPLANATIONS)?)?,
+        ("text", Some(explanation)) => {
+            writeln!(out, "{} [{}]: {}", explanation.code, explanation.rule, explanation.summary)?;
+            writeln!(out, "\n{}", explanation.description)?;
+            if let (Some(example), Some(fixed)) = (explanation.example, explanation.fixed) {
+                let indent = |text: &str| text.replace('\n', "\n    ");
+                writeln!(
+                    out,
+                    "\nExample:\n\n    {}\n\nFixed:\n\n    {}",
+                    indent(example),
+                    indent(fixed)
+                )?;
+            }
+        }
+        ("text", None) => {
+            for explanation in EXPLANATIONS {
+                writeln!(
+                    out,
+                    "{} {:<26} {}",
+                    explanation.code, explanation.rule, explanation.summary
+                )?;
+            }
+        }
+        (format, _) => {
+            return Err(format!("unknown format '{format}', expected text or json").into())
+        }
+    }
+    Ok(())
+}
+
+/// Byte offset of a 1-based `<line>:<column>` position, the column counted in bytes.
+fn position_offset(src: &str, position: &str) -> Option<usize> {
+    let (line, column) = position.split_once(':')?;
+    let (line, column) = (line.parse::<usize>().ok()?, column.parse::<usize>().ok()?);
+    let start: usize = src.split_inclusive('\n').take(line.checked_sub(1)?).map(str::len).sum();
+    let offset = start + column.checked_sub(1)?;
+    (offset <= src.len()).then_some(offset)
+}
+
+/// Prints the sectioning commands of a file indented by nesting depth, together with their line.
+fn outline(path: &str, out: &mut Output) -> Result<()> {
+    let path = PathBuf::from(path);
+    let src = std::fs::read_to_string(&path)?;
+    let result = parse_with_options(&src, config(&path)?.parse_options(&path));
+
+    let mut sections: Vec<_> = result.outline().sections().iter().rev().map(|s| (0, s)).collect();
+    while let Some((depth, section)) = sections.pop() {
+        let line = src[..section.heading.start].matches('\n').count() + 1;
+        let star = if section.starred { "*" } else { "" };
+        let indent = "  ".repeat(depth);
+        writeln!(out, "{indent}{}{star} {} (line {line})", section.level.command(), section.title)?;
+        sections.extend(section.children.iter().rev().map(|child| (depth + 1, child)));
+    }
+
+    Ok(())
+}
+
+/// Prints the definitions of the project of a file whose names match `query`, best matches first,
+/// e.g. `neotex symbols main.tex eqlem`.
+fn symbols(path: &str, query: &str, out: &mut Output) -> Result<()> {
+    let project = project(Path::new(path))?;
+
+    for found in analysis::workspace_symbols(&project, query) {
+        let source = &project.files().iter().find(|file| file.path == found.file).unwrap().source;
+        let line = source[..found.symbol.range.start].matches('\n').count() + 1;
+        let kind = format!("{:?}", found.symbol.kind).to_lowercase();
+        writeln!(out, "{kind:<11} {} {}:{line}", found.symbol.name, found.file.display())?;
+    }
+
+    Ok(())
+}
+
+/// Prints a report of the project of a file, e.g. `neotex report main.tex > report.html`.
+/// `json` is meant to be consumed by other tools.
+fn report(path: &str, format: &str, out: &mut Output) -> Result<()> {
+    let project = project(Path::new(path))?;
+    let report = neotex_core::report::Report::new(&project);
+
+    match format {
+        "html" => write!(out, "{}", report::html(&report))?,
+        "json" => writeln!(out, "{}", serde_json::to_string_pretty(&report)?)?,
+        format => return Err(format!("unknown format '{format}', expected html or json").into()),
+    }
+    Ok(())
+}
+
+/// Prints how often the project of a file uses which commands, environments and packages, e.g.
+/// `neotex stats --format csv main.tex > usage.csv`.
+fn stats(path: &str, format: &str, out: &mut Output) -> Result<()> {
+    let project = project(Path::new(path))?;
+    let usage = neotex_core::usage::Usage::new(&project);
+
+    match format {
+        "text" => write!(out, "{}", stats::text(&usage))?,
+        "json" => writeln!(out, "{}", serde_json::to_string_pretty(&usage)?)?,
+        "csv" => write!(out, "{}", stats::csv(&usage))?,
+        format => {
+            return Err(format!("unknown format '{format}', expected text, json or csv").into())
+        }
+    }
+    Ok(())
+}
+
+/// Renames a command and all of its uses, e.g. `neotex rename --diff main.tex '\R' '\Reals'`.
+fn rename(mut args: Vec<String>, out: &mut Output) -> Result<()> {
+    let mode = WriteMode::from_args(&mut args)?;
+    let [path, old, new] = &args[..] else {
+        output::status(
+            "usage: neotex rename [--dry-run | --diff | --write [--backup]] <file> <old> <new>",
+        );
+        return Ok(());
+    };
+
+    let path = PathBuf::from(path);
+    let src = std::fs::read_to_string(&path)?;
+    let result = parse_with_options(&src, config(&path)?.parse_options(&path));
+    let edits = result.definitions().rename_command(result.tokens(), old, new)?;
+
+    let change = FileChange::new(path, src.clone(), output::apply_edits(&src, &edits));
+    output::apply(&[change], mode, out)
+}
+
+/// Formats files, e.g. `neotex fmt --width 100 --write main.tex chapter.tex`.
+fn fmt(mut args: Vec<String>, out: &mut Output) -> Result<()> {
+    use neotex_core::format::Indent;
+
+    let mode = WriteMode::from_args(&mut args)?;
+    // flags override the `[format]` section of the configuration of every file
+    let (mut width, mut indent) = (None, None);
+    let (mut indent_document, mut no_math_spacing) = (false, false);
+    let mut paths = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut number = || -> Result<usize> {
+            let value = args.next().ok_or_else(|| format!("missing value of '{arg}'"))?;
+            Ok(value.parse().map_err(|_| format!("invalid value '{value}' of '{arg}'"))?)
+        };
+        match arg.as_str() {
+            "--width" => width = Some(number()?),
+            "--indent" => indent = Some(Indent::Spaces(number()?)),
+            "--tabs" => indent = Some(Indent::Tab),
+            "--indent-document" => indent_document = true,
+            "--no-math-spacing" => no_math_spacing = true,
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    if paths.is_empty() {
+        output::status(
+            "usage: neotex fmt [--dry-run | --diff | --write [--backup]] [--width <n>] \
+             [--indent <n> | --tabs] [--indent-document] [--no-math-spacing] <file>...",
+        );
+        return Ok(());
+    }
+
+    let mut changes = Vec::new();
+    for path in paths {
+        let src = std::fs::read_to_string(&path)?;
+        let config = config(&path)?;
+        let mut options = config.format.clone();
+        options.line_width = width.or(options.line_width);
+        options.indent = indent.unwrap_or(options.indent);
+        options.indent_document |= indent_document;
+        options.math_spacing &= !no_math_spacing;
+
+        let result = parse_with_options(&src, config.parse_options(&path));
+        let formatted = neotex_core::format::format(result.tokens(), &options);
+        changes.push(FileChange::new(path, src, formatted));
+    }
+    output::apply(&changes, mode, out)
+}
+
+/// Orders the preambles of files, e.g. `neotex organize-preamble --diff main.tex`.
+fn organize_preamble(mut args: Vec<String>, out: &mut Output) -> Result<()> {
+    let mode = WriteMode::from_args(&mut args)?;
+    if args.is_empty() {
+        output::status(
+            "usage: neotex organize-preamble [--dry-run | --diff | --write [--backup]] <file>...",
+        );
+        return Ok(());
+    }
+
+    let mut changes = Vec::new();
+    for path in args.into_iter().map(PathBuf::from) {
+        let src = std::fs::read_to_string(&path)?;
+        let config = config(&path)?;
+        let result = parse_with_options(&src, config.parse_options(&path));
+        let organized = neotex_core::format::organize_preamble(result.tokens(), &config.preamble)
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+        changes.push(FileChange::new(path, src, organized));
+    }
+    output::apply(&changes, mode, out)
+}
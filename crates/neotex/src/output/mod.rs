@@ -0,0 +1,267 @@
+//! Output layer shared by all commands.
+//!
+//! The result of a command goes to an [`Output`], which is stdout or the file named by
+//! `--output <file>`, so machine formats like JSON stay clean. Messages for the user, e.g. usage
+//! and progress, go to stderr with [`status`].
+//!
+//! Commands which modify files compute the new content of every file as a [`FileChange`] and
+//! hand them to [`apply`], which depending on the [`WriteMode`] lists the files, prints a unified
+//! diff or replaces the files atomically.
+
+use std::{
+    ffi::OsString,
+    fmt::Display,
+    fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+
+use neotex_core::{
+    diagnostics::{Diagnostic, Severity},
+    expansion::TextEdit,
+};
+use similar::TextDiff;
+
+use crate::Result;
+
+/// Where a command writes its result.
+pub struct Output {
+    out: Box<dyn Write>,
+    /// The file written instead of stdout
+    path: Option<PathBuf>,
+    color: bool,
+}
+
+impl Output {
+    /// Writes to `out`, e.g. a pipe to an editor, with colors if `color` is set.
+    pub fn new(out: impl Write + 'static, color: bool) -> Output {
+        Output { out: Box::new(out), path: None, color }
+    }
+
+    /// Writes to stdout, with colors if it is a terminal and the environment allows them, see
+    /// [`use_color`].
+    pub fn stdout() -> Output {
+        let color = use_color(io::stdout().is_terminal(), |name| std::env::var_os(name));
+        Output::new(io::stdout(), color)
+    }
+
+    /// Writes to the file `path` without colors, replacing its content.
+    pub fn file(path: impl Into<PathBuf>) -> Result<Output> {
+        let path = path.into();
+        let file = fs::File::create(&path)
+            .map_err(|err| format!("cannot write '{}': {err}", path.display()))?;
+        Ok(Output { out: Box::new(io::BufWriter::new(file)), path: Some(path), color: false })
+    }
+
+    /// Removes `--output <file>` from `args` and writes to the file, to stdout without it.
+    pub fn from_args(args: &mut Vec<String>) -> Result<Output> {
+        let Some(pos) = args.iter().position(|arg| arg == "--output") else {
+            return Ok(Output::stdout());
+        };
+        if pos + 1 == args.len() {
+            return Err("missing file of '--output'".into());
+        }
+        let path = args.remove(pos + 1);
+        args.remove(pos);
+        Output::file(path)
+    }
+
+    /// Whether the output may contain ANSI colors.
+    pub fn color(&self) -> bool {
+        self.color
+    }
+
+    /// Flushes the output and tells the user where it went if it is a file.
+    pub fn finish(mut self) -> Result<()> {
+        self.out.flush()?;
+        if let Some(path) = &self.path {
+            status(format_args!("wrote {}", path.display()));
+        }
+        Ok(())
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Whether to color output to a terminal (`is_terminal`) or a pipe, following the conventions of
+/// `NO_COLOR` and `CLICOLOR`: a non-empty `NO_COLOR` disables colors, `CLICOLOR_FORCE` other than
+/// `0` forces them even in a pipe, and `CLICOLOR=0` disables them.
+pub fn use_color(is_terminal: bool, var: impl Fn(&str) -> Option<OsString>) -> bool {
+    let set = |name: &str| var(name).is_some_and(|value| !value.is_empty());
+    let zero = |name: &str| var(name).is_some_and(|value| value == "0");
+    if set("NO_COLOR") {
+        return false;
+    }
+    if set("CLICOLOR_FORCE") && !zero("CLICOLOR_FORCE") {
+        return true;
+    }
+    is_terminal && !zero("CLICOLOR")
+}
+
+/// Prints a message for the user to stderr, where it never mixes with the result of a command.
+pub fn status(message: impl Display) {
+    // nothing sensible is left to do if stderr is gone
+    let _ = writeln!(io::stderr(), "{message}");
+}
+
+/// The name of `severity`, in bold red, yellow or blue if `color` is set.
+pub fn severity(severity: Severity, color: bool) -> String {
+    let (name, code) = match severity {
+        Severity::Error => ("error", 31),
+        Severity::Warning => ("warning", 33),
+        Severity::Info => ("info", 34),
+    };
+    match color {
+        true => format!("\x1b[1;{code}m{name}\x1b[0m"),
+        false => name.to_string(),
+    }
+}
+
+/// `line:column: severity [rule] message` with a 1-based line and column.
+pub fn diagnostic(source: &str, diagnostic: &Diagnostic, color: bool) -> String {
+    let before = &source[..diagnostic.range.start.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |idx| idx + 1) + 1;
+    let severity = severity(diagnostic.severity, color);
+    format!("{line}:{column}: {severity} [{}] {}", diagnostic.rule, diagnostic.message)
+}
+
+/// How a command applies its changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteMode {
+    /// List the files which would change, the default
+    #[default]
+    DryRun,
+    /// Print a unified diff of every change
+    Diff,
+    /// Replace the files, optionally keeping the original as `<file>.bak`
+    Write {
+        /// Keep a backup of the original file
+        backup: bool,
+    },
+}
+
+impl WriteMode {
+    /// Removes `--dry-run`, `--diff`, `--write` and `--backup` from `args`.
+    pub fn from_args(args: &mut Vec<String>) -> Result<WriteMode> {
+        let mut flag = |name: &str| {
+            let found = args.iter().any(|arg| arg == name);
+            args.retain(|arg| arg != name);
+            found
+        };
+        let (dry_run, diff, write, backup) =
+            (flag("--dry-run"), flag("--diff"), flag("--write"), flag("--backup"));
+
+        match (dry_run, diff, write, backup) {
+            (_, false, false, false) => Ok(WriteMode::DryRun),
+            (false, true, false, false) => Ok(WriteMode::Diff),
+            (false, false, true, backup) => Ok(WriteMode::Write { backup }),
+            (_, _, false, true) => Err("--backup can only be used together with --write".into()),
+            _ => Err("--dry-run, --diff and --write are mutually exclusive".into()),
+        }
+    }
+}
+
+/// The new content of a single file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    /// The changed file
+    pub path: PathBuf,
+    /// Content before the change
+    pub original: String,
+    /// Content after the change
+    pub updated: String,
+}
+
+impl FileChange {
+    /// Creates a change of `path` from `original` to `updated`.
+    pub fn new(path: impl Into<PathBuf>, original: String, updated: String) -> FileChange {
+        FileChange { path: path.into(), original, updated }
+    }
+
+    /// Returns `true` if the content is unchanged.
+    pub fn is_unchanged(&self) -> bool {
+        self.original == self.updated
+    }
+
+    /// Unified diff of the change with three lines of context.
+    pub fn diff(&self) -> String {
+        let path = self.path.display().to_string();
+        TextDiff::from_lines(&self.original, &self.updated)
+            .unified_diff()
+            .header(&format!("a/{path}"), &format!("b/{path}"))
+            .to_string()
+    }
+}
+
+/// Applies `changes` as requested by `mode` and reports them to `out`. Unchanged files are
+/// skipped.
+pub fn apply(changes: &[FileChange], mode: WriteMode, out: &mut impl Write) -> Result<()> {
+    for change in changes.iter().filter(|change| !change.is_unchanged()) {
+        match mode {
+            WriteMode::DryRun => writeln!(out, "would change {}", change.path.display())?,
+            WriteMode::Diff => write!(out, "{}", change.diff())?,
+            WriteMode::Write { backup } => {
+                write_atomic(&change.path, &change.updated, backup)?;
+                writeln!(out, "changed {}", change.path.display())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the content of `path` without leaving a partially written file behind: the content is
+/// written to a temporary file next to it, which is then renamed.
+fn write_atomic(path: &Path, contents: &str, backup: bool) -> io::Result<()> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".neotex-tmp");
+    let tmp = path.with_file_name(name);
+
+    let result = (|| {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&tmp, metadata.permissions())?;
+        }
+
+        if backup {
+            let mut name = path.file_name().unwrap_or_default().to_os_string();
+            name.push(".bak");
+            fs::copy(path, path.with_file_name(name))?;
+        }
+        fs::rename(&tmp, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Applies non-overlapping `edits` to `text`.
+pub fn apply_edits(text: &str, edits: &[TextEdit]) -> String {
+    let mut edits: Vec<_> = edits.iter().collect();
+    edits.sort_by_key(|edit| edit.range.start);
+
+    let mut result = String::with_capacity(text.len());
+    let mut end = 0;
+    for edit in edits {
+        result.push_str(&text[end..edit.range.start]);
+        result.push_str(&edit.new_text);
+        end = edit.range.end;
+    }
+    result.push_str(&text[end..]);
+    result
+}
+
+#[cfg(test)]
+mod tests;
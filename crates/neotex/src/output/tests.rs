@@ -0,0 +1,97 @@
+use std::{ffi::OsString, fs, io::Write};
+
+use neotex_core::{diagnostics::Severity, expansion::TextEdit};
+
+use super::{apply, apply_edits, severity, use_color, FileChange, Output, WriteMode};
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn write_mode_flags() {
+    let mut rest = args(&["--diff", "main.tex", "\\a"]);
+    assert_eq!(WriteMode::from_args(&mut rest).unwrap(), WriteMode::Diff);
+    assert_eq!(rest, args(&["main.tex", "\\a"]));
+
+    assert_eq!(WriteMode::from_args(&mut args(&[])).unwrap(), WriteMode::DryRun);
+    assert_eq!(
+        WriteMode::from_args(&mut args(&["--backup", "--write"])).unwrap(),
+        WriteMode::Write { backup: true }
+    );
+    assert!(WriteMode::from_args(&mut args(&["--diff", "--write"])).is_err());
+    assert!(WriteMode::from_args(&mut args(&["--backup"])).is_err());
+}
+
+#[test]
+fn edits_and_diff() {
+    let text = "a \\old\nb\nc \\old\n";
+    let edits = [
+        TextEdit { range: 11..15, new_text: "\\new".into() },
+        TextEdit { range: 2..6, new_text: "\\new".into() },
+    ];
+    let updated = apply_edits(text, &edits);
+    assert_eq!(updated, "a \\new\nb\nc \\new\n");
+
+    let change = FileChange::new("main.tex", text.into(), updated);
+    assert_eq!(
+        change.diff(),
+        "--- a/main.tex\n+++ b/main.tex\n@@ -1,3 +1,3 @@\n-a \\old\n+a \\new\n b\n-c \\old\n+c \\new\n"
+    );
+
+    let mut out = Vec::new();
+    apply(&[change], WriteMode::DryRun, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "would change main.tex\n");
+}
+
+#[test]
+fn atomic_write_with_backup() {
+    let dir = std::env::temp_dir().join(format!("neotex-output-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("main.tex");
+    fs::write(&path, "old").unwrap();
+
+    let change = FileChange::new(&path, "old".into(), "new".into());
+    apply(&[change], WriteMode::Write { backup: true }, &mut Vec::new()).unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    assert_eq!(fs::read_to_string(dir.join("main.tex.bak")).unwrap(), "old");
+    assert!(!dir.join("main.tex.neotex-tmp").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn color_detection() {
+    let env = |vars: &'static [(&'static str, &'static str)]| {
+        move |name: &str| {
+            vars.iter().find(|(var, _)| *var == name).map(|(_, value)| OsString::from(value))
+        }
+    };
+    assert!(use_color(true, env(&[])));
+    assert!(!use_color(false, env(&[])));
+    assert!(!use_color(true, env(&[("NO_COLOR", "1")])));
+    assert!(use_color(true, env(&[("NO_COLOR", "")])));
+    assert!(!use_color(true, env(&[("CLICOLOR", "0")])));
+    assert!(use_color(false, env(&[("CLICOLOR_FORCE", "1")])));
+    assert!(!use_color(false, env(&[("CLICOLOR_FORCE", "0")])));
+    assert!(!use_color(false, env(&[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")])));
+
+    assert_eq!(severity(Severity::Warning, false), "warning");
+    assert_eq!(severity(Severity::Error, true), "\x1b[1;31merror\x1b[0m");
+}
+
+#[test]
+fn output_file() {
+    let path = std::env::temp_dir().join(format!("neotex-output-file-{}", std::process::id()));
+    let mut rest = args(&["report", "--output", path.to_str().unwrap(), "main.tex"]);
+    let mut out = Output::from_args(&mut rest).unwrap();
+    assert_eq!(rest, args(&["report", "main.tex"]));
+    assert!(!out.color());
+
+    writeln!(out, "{{}}").unwrap();
+    out.finish().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{}\n");
+    fs::remove_file(&path).unwrap();
+
+    assert!(Output::from_args(&mut args(&["report", "--output"])).is_err());
+}
@@ -0,0 +1,170 @@
+//! `neotex report`: renders the [`Report`] of a project as a single self-contained HTML page.
+//!
+//! The page has no external resources, so it can be stored as a CI artifact or sent around as is.
+
+use std::fmt::Write;
+
+use neotex_core::{
+    diagnostics::Severity,
+    report::{Location, Report},
+};
+
+const STYLE: &str = "body{font:15px/1.5 system-ui,sans-serif;margin:2em auto;max-width:70em;\
+padding:0 1em;color:#222}h1{font-size:1.6em}h2{font-size:1.2em;margin-top:2em;\
+border-bottom:1px solid #ddd}table{border-collapse:collapse;width:100%}\
+th,td{text-align:left;padding:.25em .75em .25em 0;vertical-align:top}\
+.loc,.num{white-space:nowrap;color:#666}code{font-size:.95em}.stats td{padding-right:2em}\
+.error{color:#b00020}.warning{color:#a15c00}.info{color:#1f5fa8}p.none{color:#666}";
+
+/// The report as an HTML document.
+pub fn html(report: &Report<'_>) -> String {
+    let root = escape(&report.root.display().to_string());
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>NeoTeX report: {root}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>NeoTeX report: <code>{root}</code></h1>\n"
+    );
+
+    let stats = &report.stats;
+    page.push_str("<h2>Statistics</h2>\n<table class=\"stats\">\n");
+    for (name, value) in [
+        ("Files", stats.files),
+        ("Lines", stats.lines),
+        ("Words", stats.words),
+        ("Sections", stats.sections),
+        ("Definitions", stats.definitions),
+        ("Labels", stats.labels),
+        ("Errors", stats.errors),
+        ("Warnings", stats.warnings),
+    ] {
+        let _ = writeln!(page, "<tr><th>{name}</th><td class=\"num\">{value}</td></tr>");
+    }
+    page.push_str("</table>\n");
+
+    let words = report
+        .frequent_words
+        .iter()
+        .map(|frequency| [escape(&frequency.word), frequency.count.to_string()])
+        .collect();
+    section(&mut page, "Frequent words", &["Word", "Uses"], words);
+
+    let todos =
+        report.todos.iter().map(|todo| [location(&todo.location), escape(todo.text)]).collect();
+    section(&mut page, "Todos", &["Location", "Text"], todos);
+
+    let unknown = report
+        .unknown_commands
+        .iter()
+        .map(|command| {
+            [
+                format!("<code>{}</code>", escape(command.name)),
+                command.uses.to_string(),
+                location(&command.first),
+            ]
+        })
+        .collect();
+    section(&mut page, "Unknown commands", &["Command", "Uses", "First use"], unknown);
+
+    let dead = report
+        .dead_definitions
+        .iter()
+        .map(|dead| [format!("<code>{}</code>", escape(dead.name)), location(&dead.location)])
+        .collect();
+    section(&mut page, "Unused definitions", &["Command", "Location"], dead);
+
+    let shell_escape = report
+        .shell_escape
+        .iter()
+        .map(|found| {
+            let command = found.command.map(|command| format!("<code>{}</code>", escape(command)));
+            [
+                location(&found.location),
+                format!("<code>{}</code>", escape(&found.construct)),
+                command.unwrap_or_default(),
+            ]
+        })
+        .collect();
+    section(&mut page, "Shell escape", &["Location", "Construct", "Command"], shell_escape);
+
+    let diagnostics = report
+        .diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let severity = match diagnostic.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Info => "info",
+            };
+            let rule = match diagnostic.code {
+                Some(code) => format!("{code} {}", diagnostic.rule),
+                None => diagnostic.rule.to_string(),
+            };
+            [
+                location(&diagnostic.location),
+                format!("<span class=\"{severity}\">{severity}</span>"),
+                format!("<code>{}</code>", escape(&rule)),
+                escape(&diagnostic.message),
+            ]
+        })
+        .collect();
+    section(&mut page, "Diagnostics", &["Location", "Severity", "Rule", "Message"], diagnostics);
+
+    page.push_str("</body>\n</html>\n");
+    page
+}
+
+/// Appends a heading and a table of `rows`, or a note if there are none.
+fn section<const N: usize>(
+    page: &mut String,
+    title: &str,
+    header: &[&str; N],
+    rows: Vec<[String; N]>,
+) {
+    let _ = writeln!(page, "<h2>{title} ({})</h2>", rows.len());
+    if rows.is_empty() {
+        page.push_str("<p class=\"none\">None</p>\n");
+        return;
+    }
+
+    page.push_str("<table>\n<tr>");
+    for name in header {
+        let _ = write!(page, "<th>{name}</th>");
+    }
+    page.push_str("</tr>\n");
+    for row in rows {
+        page.push_str("<tr>");
+        for cell in row {
+            let _ = write!(page, "<td>{cell}</td>");
+        }
+        page.push_str("</tr>\n");
+    }
+    page.push_str("</table>\n");
+}
+
+fn location(location: &Location<'_>) -> String {
+    format!(
+        "<span class=\"loc\">{}:{}:{}</span>",
+        escape(&location.file.display().to_string()),
+        location.line,
+        location.column
+    )
+}
+
+/// `text` with the characters special to HTML replaced by entities.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests;
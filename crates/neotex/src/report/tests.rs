@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use neotex_core::{
+    diagnostics::Severity,
+    report::{Location, Report, ReportDiagnostic, ShellEscapeUse, Stats, Todo, WordFrequency},
+};
+
+use super::html;
+
+#[test]
+fn html_page() {
+    let file = Path::new("chapters/<intro>.tex");
+    let location = |line| Location { file, line, column: 3 };
+    let report = Report {
+        root: Path::new("main.tex"),
+        stats: Stats { files: 2, words: 120, ..Stats::default() },
+        frequent_words: vec![WordFrequency { word: "graph".to_string(), count: 7 }],
+        todos: vec![Todo { location: location(4), text: "TODO: a < b & c" }],
+        unknown_commands: Vec::new(),
+        dead_definitions: Vec::new(),
+        shell_escape: vec![ShellEscapeUse {
+            location: location(9),
+            construct: "\\write18".to_string(),
+            command: Some("make > out.tex"),
+        }],
+        diagnostics: vec![ReportDiagnostic {
+            location: location(7),
+            severity: Severity::Error,
+            code: Some("E0004"),
+            rule: "missing-body",
+            message: "missing body of '\\x'".to_string(),
+        }],
+    };
+    let page = html(&report);
+
+    assert!(page.starts_with("<!DOCTYPE html>"));
+    assert!(page.contains("<title>NeoTeX report: main.tex</title>"));
+    assert!(page.contains("<tr><th>Words</th><td class=\"num\">120</td></tr>"));
+    assert!(page.contains(
+        "<td><span class=\"loc\">chapters/&lt;intro&gt;.tex:4:3</span></td>\
+         <td>TODO: a &lt; b &amp; c</td>"
+    ));
+    assert!(page.contains("<td>graph</td><td>7</td>"));
+    assert!(page.contains("<h2>Unknown commands (0)</h2>\n<p class=\"none\">None</p>"));
+    assert!(page.contains("<td><code>\\write18</code></td><td><code>make &gt; out.tex</code></td>"));
+    assert!(page.contains(
+        "<td><span class=\"error\">error</span></td><td><code>E0004 missing-body</code></td>\
+         <td>missing body of &#39;\\x&#39;</td>"
+    ));
+    // self-contained, no external resources
+    assert!(!page.contains("href=") && !page.contains("src="));
+}
@@ -0,0 +1,204 @@
+//! `neotex serve --simple`: a minimal JSON-RPC 2.0 server for scripts and bots which do not want
+//! to speak the whole language server protocol.
+//!
+//! Every line of the input is one request, every response is written as one line. Paths are
+//! resolved like by the other commands. Opened documents shadow the file on disk until they are
+//! closed, all other files are read from disk on every request. The methods are
+//! * `open` with `path` and an optional `text`, the content of the file by default
+//! * `close` with `path`
+//! * `tokens`, `diagnostics` and `outline` with `path`
+//! * `shutdown`, which ends the server after its response
+//!
+//! Positions are byte offsets, diagnostics additionally have a 1-based line and column like the
+//! output of `neotex watch`.
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use neotex_core::{outline::Section, parse_with_options, ParseResult, SharedParse};
+use serde_json::{json, Value};
+
+use crate::Result;
+
+/// The request could not be parsed as JSON.
+const PARSE_ERROR: i64 = -32700;
+/// The request is no JSON-RPC request object.
+const INVALID_REQUEST: i64 = -32600;
+/// The method is unknown.
+const METHOD_NOT_FOUND: i64 = -32601;
+/// The parameters are missing or of the wrong type.
+const INVALID_PARAMS: i64 = -32602;
+/// The request was valid but could not be served, e.g. because the file is unreadable.
+const SERVER_ERROR: i64 = -32000;
+
+/// An error response, the code and message of the JSON-RPC error object.
+type Error = (i64, String);
+
+/// The open documents of a session.
+#[derive(Debug, Default)]
+pub struct Server {
+    documents: HashMap<PathBuf, SharedParse>,
+    shutdown: bool,
+}
+
+impl Server {
+    /// Handles a single request and returns the response, `None` for notifications, i.e.
+    /// requests without an `id`.
+    pub fn handle(&mut self, line: &str) -> Option<Value> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(err) => return Some(response(Value::Null, Err((PARSE_ERROR, err.to_string())))),
+        };
+        let id = request.get("id").cloned();
+        let result = match request.get("method").and_then(Value::as_str) {
+            Some(method) => self.call(method, request.get("params").unwrap_or(&Value::Null)),
+            None => Err((INVALID_REQUEST, "missing method".to_string())),
+        };
+        id.map(|id| response(id, result))
+    }
+
+    /// Whether a `shutdown` request was handled.
+    pub fn is_shut_down(&self) -> bool {
+        self.shutdown
+    }
+
+    fn call(&mut self, method: &str, params: &Value) -> std::result::Result<Value, Error> {
+        match method {
+            "open" => {
+                let path = path(params)?;
+                let text = match params.get("text") {
+                    None => read(&path)?,
+                    Some(Value::String(text)) => text.clone(),
+                    Some(_) => return Err((INVALID_PARAMS, "text is no string".to_string())),
+                };
+                let options = crate::config(&path).map_err(server_error)?.parse_options(&path);
+                self.documents.insert(path, SharedParse::new(text, options));
+                Ok(Value::Null)
+            }
+            "close" => {
+                self.documents.remove(&path(params)?);
+                Ok(Value::Null)
+            }
+            "tokens" => self.analyze(params, tokens),
+            "diagnostics" => self.analyze(params, diagnostics),
+            "outline" => self.analyze(params, |result| {
+                Value::Array(result.outline().sections().iter().map(section).collect())
+            }),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            method => Err((METHOD_NOT_FOUND, format!("unknown method '{method}'"))),
+        }
+    }
+
+    /// Calls `f` with the analysis of the open document at the `path` parameter, or of the file on
+    /// disk if it is not open.
+    fn analyze(
+        &self,
+        params: &Value,
+        f: impl FnOnce(&ParseResult<'_>) -> Value,
+    ) -> std::result::Result<Value, Error> {
+        let path = path(params)?;
+        match self.documents.get(&path) {
+            Some(parsed) => Ok(f(&parsed.result())),
+            None => {
+                let options = crate::config(&path).map_err(server_error)?.parse_options(&path);
+                Ok(f(&parse_with_options(&read(&path)?, options)))
+            }
+        }
+    }
+}
+
+/// Serves the requests of `input` until it ends or a `shutdown` request was handled.
+pub fn run(input: impl BufRead, out: &mut impl Write) -> Result<()> {
+    let mut server = Server::default();
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle(&line) {
+            writeln!(out, "{response}")?;
+            out.flush()?;
+        }
+        if server.is_shut_down() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn response(id: Value, result: std::result::Result<Value, Error>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => {
+            json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+        }
+    }
+}
+
+fn server_error(err: impl ToString) -> Error {
+    (SERVER_ERROR, err.to_string())
+}
+
+/// The `path` parameter.
+fn path(params: &Value) -> std::result::Result<PathBuf, Error> {
+    match params.get("path").and_then(Value::as_str) {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Err((INVALID_PARAMS, "missing path".to_string())),
+    }
+}
+
+fn read(path: &Path) -> std::result::Result<String, Error> {
+    std::fs::read_to_string(path).map_err(|err| server_error(format!("{}: {err}", path.display())))
+}
+
+fn tokens(result: &ParseResult<'_>) -> Value {
+    let tokens = result.tokens().spanned_tokens().map(|token| {
+        json!({
+            "kind": format!("{:?}", token.kind),
+            "start": token.range.start,
+            "end": token.range.end,
+            "text": token.text,
+        })
+    });
+    Value::Array(tokens.collect())
+}
+
+fn diagnostics(result: &ParseResult<'_>) -> Value {
+    let source = result.source();
+    let diagnostics = result.diagnostics().iter().map(|diagnostic| {
+        let before = &source[..diagnostic.range.start.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |idx| idx + 1) + 1;
+        json!({
+            "rule": diagnostic.rule,
+            "code": diagnostic.code(),
+            "severity": diagnostic.severity,
+            "message": diagnostic.message,
+            "start": diagnostic.range.start,
+            "end": diagnostic.range.end,
+            "line": line,
+            "column": column,
+        })
+    });
+    Value::Array(diagnostics.collect())
+}
+
+fn section(section: &Section<'_>) -> Value {
+    json!({
+        "level": section.level.command(),
+        "starred": section.starred,
+        "title": section.title,
+        "start": section.heading.start,
+        "end": section.range.end,
+        "children": section.children.iter().map(self::section).collect::<Vec<_>>(),
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,83 @@
+use serde_json::{json, Value};
+
+use super::{run, Server};
+
+/// Sends `method` with `params` and returns the result, or the error object.
+fn call(server: &mut Server, method: &str, params: Value) -> Value {
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+    let response = server.handle(&request.to_string()).unwrap();
+    assert_eq!(response["id"], 1);
+    response.get("result").unwrap_or(&response["error"]).clone()
+}
+
+#[test]
+fn open_documents_shadow_the_disk() {
+    let dir = std::env::temp_dir().join(format!("neotex-serve-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("main.tex");
+    std::fs::write(&path, "\\section{Disk}\n\\newcommand{\\broken}").unwrap();
+    let path = path.to_str().unwrap();
+    let mut server = Server::default();
+
+    let outline = call(&mut server, "outline", json!({ "path": path }));
+    assert_eq!(outline[0]["title"], "Disk");
+    let diagnostics = call(&mut server, "diagnostics", json!({ "path": path }));
+    assert_eq!(diagnostics[0]["rule"], "missing-body");
+    assert_eq!(
+        (&diagnostics[0]["line"], &diagnostics[0]["severity"]),
+        (&json!(2), &json!("error"))
+    );
+
+    let text = "\\section{Open}\\subsection{Nested}";
+    assert_eq!(call(&mut server, "open", json!({ "path": path, "text": text })), Value::Null);
+    let outline = call(&mut server, "outline", json!({ "path": path }));
+    assert_eq!(outline[0]["title"], "Open");
+    assert_eq!(outline[0]["children"][0]["level"], "\\subsection");
+    assert_eq!(call(&mut server, "diagnostics", json!({ "path": path })), json!([]));
+    let tokens = call(&mut server, "tokens", json!({ "path": path }));
+    assert_eq!(tokens[0], json!({ "kind": "Command", "start": 0, "end": 8, "text": "\\section" }));
+
+    call(&mut server, "close", json!({ "path": path }));
+    let outline = call(&mut server, "outline", json!({ "path": path }));
+    assert_eq!(outline[0]["title"], "Disk");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn errors() {
+    let mut server = Server::default();
+    assert_eq!(call(&mut server, "hover", json!({}))["code"], -32601);
+    assert_eq!(call(&mut server, "tokens", json!({}))["code"], -32602);
+    let missing = call(&mut server, "tokens", json!({ "path": "/nonexistent/main.tex" }));
+    assert_eq!(missing["code"], -32000);
+    assert_eq!(server.handle("{").unwrap()["error"]["code"], -32700);
+    // notifications get no response
+    assert_eq!(server.handle(r#"{"jsonrpc": "2.0", "method": "close", "params": {}}"#), None);
+}
+
+#[test]
+fn lines_until_shutdown() {
+    let input = [
+        r#"{"jsonrpc": "2.0", "id": 1, "method": "open", "params": {"path": "a.tex", "text": "x"}}"#,
+        "",
+        r#"{"jsonrpc": "2.0", "id": 2, "method": "shutdown"}"#,
+        r#"{"jsonrpc": "2.0", "id": 3, "method": "outline", "params": {"path": "a.tex"}}"#,
+    ]
+    .join("\n");
+    let mut out = Vec::new();
+    run(input.as_bytes(), &mut out).unwrap();
+
+    let responses: Vec<Value> = out
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).unwrap())
+        .collect();
+    assert_eq!(
+        responses,
+        [
+            json!({ "jsonrpc": "2.0", "id": 1, "result": null }),
+            json!({ "jsonrpc": "2.0", "id": 2, "result": null }),
+        ]
+    );
+}
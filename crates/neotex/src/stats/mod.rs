@@ -0,0 +1,72 @@
+//! `neotex stats`: renders the [`Usage`] of a project as text or CSV, JSON is serialized
+//! directly.
+//!
+//! The CSV has one `kind,name,count` row per value, so it loads into a spreadsheet as is: the
+//! `summary` rows hold the totals, followed by the `command`, `environment` and `package` rows.
+
+use std::fmt::Write;
+
+use neotex_core::usage::Usage;
+
+/// Number of commands and environments listed by [`text`].
+const TOP: usize = 10;
+
+/// A short summary with the most used commands and environments.
+pub fn text(usage: &Usage<'_>) -> String {
+    let mut text = String::new();
+    for (name, value) in summary(usage) {
+        let _ = writeln!(text, "{name:<16}{value}");
+    }
+    for (title, counts) in [("commands", &usage.commands), ("environments", &usage.environments)] {
+        let _ = writeln!(text, "\ntop {title}:");
+        for count in counts.iter().take(TOP) {
+            let _ = writeln!(text, "  {:<24}{}", count.name, count.count);
+        }
+    }
+    let _ = writeln!(text, "\npackages:");
+    for package in &usage.packages {
+        let name = package.package.unwrap_or("(kernel)");
+        let _ = writeln!(text, "  {name:<24}{} calls of {}", package.calls, package.commands.len());
+    }
+    text
+}
+
+/// All counts as `kind,name,count` rows with a header.
+pub fn csv(usage: &Usage<'_>) -> String {
+    let mut csv = String::from("kind,name,count\n");
+    for (name, value) in summary(usage) {
+        let _ = writeln!(csv, "summary,{name},{value}");
+    }
+    for count in &usage.commands {
+        let _ = writeln!(csv, "command,{},{}", field(count.name), count.count);
+    }
+    for count in &usage.environments {
+        let _ = writeln!(csv, "environment,{},{}", field(count.name), count.count);
+    }
+    for package in &usage.packages {
+        let _ = writeln!(csv, "package,{},{}", package.package.unwrap_or(""), package.calls);
+    }
+    csv
+}
+
+fn summary(usage: &Usage<'_>) -> [(&'static str, String); 6] {
+    [
+        ("files", usage.files.to_string()),
+        ("lines", usage.lines.to_string()),
+        ("words", usage.words.to_string()),
+        ("math", usage.math.to_string()),
+        ("comment_lines", usage.comment_lines.to_string()),
+        ("comment_density", format!("{:.3}", usage.comment_density)),
+    ]
+}
+
+/// `name` quoted if it contains a comma, a quote or a line break, e.g. the command `\,`.
+fn field(name: &str) -> String {
+    match name.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", name.replace('"', "\"\"")),
+        false => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,27 @@
+use std::fs;
+
+use neotex_core::{project::Project, usage::Usage, ParseOptions};
+
+use super::{csv, text};
+
+#[test]
+fn csv_rows() {
+    let dir = std::env::temp_dir().join(format!("neotex-stats-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("main.tex"), "\\input{part}\n% note\n$a\\,b$ \\frac{1}{2} \\frac{3}{4}\n")
+        .unwrap();
+    fs::write(dir.join("part.tex"), "\\begin{itemize}\\item \"x\"\\end{itemize}\n").unwrap();
+
+    let project = Project::load(dir.join("main.tex"), ParseOptions::default()).unwrap();
+    let usage = Usage::new(&project);
+    let csv = csv(&usage);
+    assert!(csv.starts_with("kind,name,count\nsummary,files,2\nsummary,lines,4\n"), "{csv}");
+    assert!(csv.contains("summary,comment_density,0.250\n"), "{csv}");
+    assert!(csv.contains("command,\\frac,2\n") && csv.contains("command,\"\\,\",1\n"), "{csv}");
+    assert!(csv.contains("environment,itemize,1\n") && csv.contains("package,,"), "{csv}");
+
+    let text = text(&usage);
+    assert!(text.starts_with("files           2\n"), "{text}");
+    assert!(text.contains("\ntop environments:\n  itemize                 1\n"), "{text}");
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,152 @@
+//! `neotex watch`: re-checks a document and the files it includes whenever one of them changes.
+//!
+//! The watcher observes the directories of all files of the [`Project`] instead of the files
+//! themselves, so files replaced by an editor and newly created includes are noticed as well.
+//! After every check the set of files is refreshed, so inputs added to or removed from the
+//! document start or stop being watched. Tokens of unchanged files are taken from the
+//! [`ParseCache`] of the directory of the document.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use neotex_core::{cache::ParseCache, config::CONFIG_FILE, diagnostics::Stage, project::Project};
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::{output, output::Output, Result};
+
+/// Time to wait for further events after a change, editors often write a file in several steps.
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// The state of a watched document between two checks.
+#[derive(Debug)]
+pub struct Session {
+    root: PathBuf,
+    cache: ParseCache,
+    /// Stages whose diagnostics are not reported, in addition to the ones of the configuration
+    disabled: Vec<Stage>,
+    /// The printed diagnostics of every file of the last check
+    reported: BTreeMap<PathBuf, Vec<String>>,
+    /// Whether the severities are colored
+    color: bool,
+}
+
+impl Session {
+    /// A session for the document at `root` which has not been checked yet.
+    pub fn new(root: impl Into<PathBuf>) -> Session {
+        let root = root.into();
+        let cache = ParseCache::in_project(&directory(&root).unwrap_or_default());
+        Session { root, cache, disabled: Vec::new(), reported: BTreeMap::new(), color: false }
+    }
+
+    /// Does not report the diagnostics of the `disabled` stages, e.g. of the expansion while
+    /// drafting.
+    pub fn with_disabled(mut self, disabled: Vec<Stage>) -> Session {
+        self.disabled = disabled;
+        self
+    }
+
+    /// Colors the severities of the diagnostics, see [`Output::color`].
+    pub fn with_color(mut self, color: bool) -> Session {
+        self.color = color;
+        self
+    }
+
+    /// Loads and analyzes the document and its includes, and prints the diagnostics of every file
+    /// whose diagnostics differ from the last check. Returns the files of the project.
+    pub fn check(&mut self, out: &mut impl Write) -> Result<BTreeSet<PathBuf>> {
+        let config = crate::config(&self.root)?;
+        let mut options = config.parse_options(&self.root);
+        for stage in &self.disabled {
+            options.stages.disable(*stage);
+        }
+        let project = Project::load_with_search_paths(&self.root, options, &config.search_paths)?;
+
+        let mut reported = BTreeMap::new();
+        for (file, result) in project.parse_with_cache(&self.cache) {
+            let lines = file
+                .diagnostics
+                .iter()
+                .chain(result.diagnostics())
+                .map(|diagnostic| output::diagnostic(&file.source, diagnostic, self.color))
+                .collect();
+            reported.insert(file.path.clone(), lines);
+        }
+
+        for (path, lines) in &reported {
+            if self.reported.get(path) == Some(lines) {
+                continue;
+            }
+            match lines.is_empty() {
+                true => writeln!(out, "{}: no problems", path.display())?,
+                false => writeln!(out, "{}:", path.display())?,
+            }
+            for line in lines {
+                writeln!(out, "  {line}")?;
+            }
+        }
+        for path in self.reported.keys().filter(|path| !reported.contains_key(*path)) {
+            writeln!(out, "{}: no longer included", path.display())?;
+        }
+
+        self.reported = reported;
+        Ok(self.reported.keys().cloned().collect())
+    }
+}
+
+/// Watches the document at `root` until the process is terminated, without reporting the
+/// diagnostics of the `disabled` stages.
+pub fn run(root: &Path, disabled: Vec<Stage>, out: &mut Output) -> Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    let mut session = Session::new(root).with_disabled(disabled).with_color(out.color());
+    let mut watched = BTreeSet::new();
+
+    loop {
+        let files = session.check(out)?;
+        out.flush()?;
+
+        let dirs: BTreeSet<_> = files.iter().filter_map(|path| directory(path)).collect();
+        for dir in watched.difference(&dirs) {
+            // the directory may have been deleted, which already ended the watch
+            let _ = watcher.unwatch(dir);
+        }
+        for dir in dirs.difference(&watched) {
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        }
+        watched = dirs;
+
+        // wait for a change of a project file, a new `.tex` file or the configuration
+        loop {
+            let event = receiver.recv()??;
+            let kind = event.kind;
+            let changed =
+                matches!(kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_));
+            if changed && event.paths.iter().any(|path| files.contains(path) || is_source(path)) {
+                break;
+            }
+        }
+        while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+    }
+}
+
+/// The directory containing `path`, the current directory for a bare file name.
+fn directory(path: &Path) -> Option<PathBuf> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => Some(dir.to_path_buf()),
+        _ => std::env::current_dir().ok(),
+    }
+}
+
+/// Whether a change of `path` may change the result of a check.
+fn is_source(path: &Path) -> bool {
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    matches!(extension, Some("tex" | "ntex")) || path.ends_with(CONFIG_FILE)
+}
+
+#[cfg(test)]
+mod tests;
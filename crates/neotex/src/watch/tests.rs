@@ -0,0 +1,54 @@
+use std::fs;
+
+use neotex_core::diagnostics::Stage;
+
+use super::Session;
+
+#[test]
+fn incremental_checks() {
+    let dir = std::env::temp_dir().join(format!("neotex-watch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (main, chapter) = (dir.join("main.tex"), dir.join("chapter.tex"));
+    fs::write(&main, "\\input{chapter}\n").unwrap();
+    fs::write(&chapter, "text\n\\newcommand{\\broken}").unwrap();
+
+    let mut session = Session::new(&main);
+    let mut check = || {
+        let mut out = Vec::new();
+        let files = session.check(&mut out).unwrap();
+        (files.len(), String::from_utf8(out).unwrap().replace(&*dir.to_string_lossy(), "<dir>"))
+    };
+
+    let (files, out) = check();
+    assert_eq!(files, 2);
+    assert!(out.contains("<dir>/chapter.tex:\n  2:20: error [missing-body]"), "{out}");
+    assert!(
+        out.starts_with("<dir>/chapter.tex:") && out.ends_with("<dir>/main.tex: no problems\n")
+    );
+
+    // nothing changed, nothing is printed
+    assert_eq!(check(), (2, String::new()));
+
+    fs::write(&chapter, "text\n").unwrap();
+    assert_eq!(check(), (2, "<dir>/chapter.tex: no problems\n".to_string()));
+
+    fs::write(&main, "no inputs\n").unwrap();
+    assert_eq!(check(), (1, "<dir>/chapter.tex: no longer included\n".to_string()));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn disabled_stages() {
+    let dir = std::env::temp_dir().join(format!("neotex-watch-stages-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let main = dir.join("main.tex");
+    fs::write(&main, "\\newcommand{\\broken}").unwrap();
+
+    let mut session = Session::new(&main).with_disabled(vec![Stage::Expansion]);
+    let mut out = Vec::new();
+    session.check(&mut out).unwrap();
+    assert!(String::from_utf8(out).unwrap().ends_with("main.tex: no problems\n"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
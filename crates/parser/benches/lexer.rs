@@ -0,0 +1,87 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use parser::{chars, LexedStr};
+
+const DOCUMENT: &str = r#"\documentclass[11pt]{article}
+\usepackage{amsmath}
+
+% A short comment before the definitions
+\newcommand{\R}[1]{\mathbb{#1}}
+\let e = "interpolation";
+
+\begin{document}
+\section{Introduction}
+Lorem ipsum dolor sit amet, consectetur adipiscing elit. Sed do eiusmod tempor
+incididunt ut labore et dolore magna aliqua: $a_1 + b^2 <= 12.5e-3$.
+
+\begin{align}
+    f(x) &= \int_0^1 x^2 \, dx \\
+    g(x) &= \R{x} -> \frac{1}{2}
+\end{align}
+\end{document}
+"#;
+
+fn document(repeat: usize) -> String {
+    DOCUMENT.repeat(repeat)
+}
+
+fn lex(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lex");
+
+    for repeat in [1, 100, 1000] {
+        let input = document(repeat);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(repeat), &input, |b, input| {
+            b.iter(|| LexedStr::new(black_box(input)))
+        });
+    }
+
+    group.finish();
+}
+
+/// Mostly indentation and blank lines, as found in generated or heavily aligned documents.
+fn whitespace_document(repeat: usize) -> String {
+    "\t    a  &  b \\\\  \r\n\n        \u{00A0} c\n".repeat(repeat)
+}
+
+/// The previous `matches!` based predicate, kept as a baseline for [`chars::is_whitespace`].
+fn is_whitespace_match(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0009}'
+            | '\u{0020}'
+            | '\u{00A0}'
+            | '\u{1680}'
+            | '\u{202F}'
+            | '\u{3000}'
+            | '\u{200E}'
+            | '\u{200F}'
+    )
+}
+
+fn char_classes(c: &mut Criterion) {
+    let input = whitespace_document(1000);
+    let mut group = c.benchmark_group("char_classes");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+
+    group.bench_function("match", |b| {
+        b.iter(|| black_box(&input).chars().filter(|&c| is_whitespace_match(c)).count())
+    });
+    group.bench_function("table", |b| {
+        b.iter(|| black_box(&input).chars().filter(|&c| chars::is_whitespace(c)).count())
+    });
+    group.bench_function("lex_whitespace", |b| b.iter(|| LexedStr::new(black_box(&input))));
+
+    group.finish();
+}
+
+fn spanned_tokens(c: &mut Criterion) {
+    let input = document(1000);
+    let lexed = LexedStr::new(&input);
+
+    c.bench_function("spanned_tokens", |b| {
+        b.iter(|| lexed.spanned_tokens().filter(|token| !token.is_trivia()).count())
+    });
+}
+
+criterion_group!(benches, lex, char_classes, spanned_tokens);
+criterion_main!(benches);
@@ -0,0 +1,21 @@
+//! Lexing must never panic or loop forever and always cover the whole input.
+//!
+//! Run with `cargo +nightly fuzz run lex` from `crates/parser`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use parser::{CompactLexedStr, LexedStr, LexerProfile};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    for profile in [LexerProfile::Latex, LexerProfile::NeoTex, LexerProfile::ExpL3] {
+        let lexed = LexedStr::with_profile(source, profile);
+        assert_eq!(lexed.reconstruct(), source);
+        assert!(lexed.spanned_tokens().all(|token| !token.text.is_empty()));
+        assert_eq!(CompactLexedStr::new(&lexed).reconstruct(), source);
+    }
+});
@@ -0,0 +1,126 @@
+use super::signatures::{group, resolve, CommandStub};
+use crate::{
+    ast::Document,
+    diagnostics::{Diagnostic, Severity},
+    expansion::{takes_argument, ExpansionStore},
+    SyntaxKind,
+};
+
+/// Rule id of the diagnostic reported for calls with fewer arguments than their command takes.
+pub const MISSING_ARGUMENT: &str = "missing-argument";
+
+/// Commands whose star takes the place of their first argument, like `\DeclareOption*{code}`
+/// declares the code of all undeclared options.
+const STAR_FOR_ARGUMENT: &[&str] = &["\\DeclareOption"];
+
+/// Checks that the calls of `document` have all the mandatory arguments of their commands.
+///
+/// Arguments are collected greedily like TeX does, each one is a brace group or a single
+/// character or command. A chain of brace groups like `\frac{a}{b}` ends at the first token
+/// which is separated from the previous group by a space though, so the `x` of `\frac{a} x` is
+/// not swallowed as the second argument like by TeX, which is rarely intended. The argument is
+/// reported as missing instead, a warning since TeX accepts it. An argument cut off by the end of
+/// a group, a paragraph or the document is an error.
+///
+/// Signatures are resolved like by [`signature_at`](super::signature_at). Calls inside of
+/// definitions are skipped, since their arguments may be provided by the caller, and so are
+/// commands with delimited parameters.
+pub fn check_arguments<'source>(
+    document: &Document<'_, 'source>,
+    store: &ExpansionStore<'source>,
+    stubs: &[CommandStub],
+) -> Vec<Diagnostic> {
+    let lexed = document.lexed();
+    let definitions: Vec<_> =
+        store.all_commands().chain(store.environments()).map(|item| item.range.clone()).collect();
+    let mut diagnostics = Vec::new();
+
+    for idx in 0..lexed.len() {
+        if lexed.kind(idx) != SyntaxKind::Command
+            || definitions.iter().any(|definition| definition.contains(&idx))
+        {
+            continue;
+        }
+        let Some(signature) = resolve(lexed.text(idx), idx, store, stubs) else {
+            continue;
+        };
+        if signature.item.is_some_and(|item| item.pattern.is_some()) {
+            continue;
+        }
+
+        // the star of a variant like `\section*` takes no argument, user commands only have
+        // variants if they dispatch with `\@ifstar`
+        let mut pos = idx + 1;
+        let mut arg = 0;
+        let variants = signature.item.map_or(true, |item| item.star_variants().is_some());
+        if pos < lexed.len() && lexed.kind(pos) == SyntaxKind::Star && variants {
+            pos += 1;
+            if STAR_FOR_ARGUMENT.contains(&lexed.text(idx)) {
+                arg = 1;
+            }
+        }
+        let mut braced = false;
+        while arg < signature.arg_count {
+            let next = (pos..lexed.len())
+                .find(|&next| {
+                    !matches!(
+                        lexed.kind(next),
+                        SyntaxKind::Whitespace
+                            | SyntaxKind::Newline
+                            | SyntaxKind::Comment
+                            | SyntaxKind::AComment
+                    )
+                })
+                .unwrap_or(lexed.len());
+            let kind = (next < lexed.len()).then(|| lexed.kind(next));
+            let optional = signature.optional && arg == 0;
+            // whether TeX would take the token as the argument
+            let takes = kind.is_some_and(|kind| kind != SyntaxKind::Break && takes_argument(kind));
+            let swallowed = takes && braced && next > pos;
+
+            match kind {
+                Some(SyntaxKind::OpenBracket) if optional => {
+                    pos = group(lexed, next, SyntaxKind::OpenBracket, SyntaxKind::CloseBracket).0;
+                }
+                _ if optional => {}
+                Some(SyntaxKind::OpenBrace) => {
+                    pos = group(lexed, next, SyntaxKind::OpenBrace, SyntaxKind::CloseBrace).0;
+                    braced = true;
+                }
+                Some(kind) if takes && !swallowed => {
+                    // every character of a word or number is an argument of its own
+                    let chars = match kind {
+                        SyntaxKind::Command => 1,
+                        _ => lexed.text(next).chars().count(),
+                    };
+                    arg += chars.clamp(1, signature.arg_count - arg) - 1;
+                    (pos, braced) = (next + 1, false);
+                }
+                _ => {
+                    let call = lexed.text_start(idx)..lexed.text_range(pos - 1).end;
+                    let number = arg + 1;
+                    let severity = if swallowed { Severity::Warning } else { Severity::Error };
+                    let message = format!("missing argument {number} of '{}'", signature.name);
+                    let mut diagnostic = Diagnostic::new(MISSING_ARGUMENT, severity, call, message);
+                    if swallowed {
+                        diagnostic = diagnostic.with_related(
+                            lexed.text_range(next),
+                            format!("TeX takes this as argument {number}"),
+                        );
+                    }
+                    // inherited definitions have no position in this document
+                    if let Some(item) = signature.item.filter(|item| !item.range.is_empty()) {
+                        let definition = lexed.text_start(item.range.start)
+                            ..lexed.text_range(item.range.end - 1).end;
+                        diagnostic = diagnostic.with_related(definition, "defined here");
+                    }
+                    diagnostics.push(diagnostic);
+                    break;
+                }
+            }
+            arg += 1;
+        }
+    }
+
+    diagnostics
+}
@@ -0,0 +1,119 @@
+use std::{fmt::Display, ops::Range};
+
+use crate::{ast::Document, expansion::ExpansionStore, registry};
+
+/// Whether a [`Completion`] is a command or an environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompletionKind {
+    /// A command, completed after a backslash
+    Command,
+    /// An environment, completed inside the braces of `\begin{` or `\end{`
+    Environment,
+}
+
+/// Where the candidate of a [`Completion`] comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompletionOrigin {
+    /// Defined in the document
+    Document,
+    /// Provided by the LaTeX kernel
+    Kernel,
+    /// Provided by a package loaded with `\usepackage`
+    Package(&'static str),
+}
+
+/// A single completion candidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion<'source> {
+    /// Command or environment
+    pub kind: CompletionKind,
+    /// Name of the command including the backslash or of the environment
+    pub name: &'source str,
+    /// Argument signature for snippet generation, e.g. `\sqrt[#1]{#2}` or `minipage[#1]{#2}`
+    pub signature: String,
+    /// Where the candidate is defined
+    pub origin: CompletionOrigin,
+}
+
+/// The candidates for completing the name at an offset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completions<'source> {
+    /// Byte range of the partial name the candidates replace, including the backslash of commands
+    pub range: Range<usize>,
+    /// Candidates starting with the partial name, ordered by name
+    pub items: Vec<Completion<'source>>,
+}
+
+/// Candidates for the name at the byte `offset` of `document`.
+///
+/// After a backslash the candidates are the commands defined in `store`, the kernel commands and
+/// the commands of the packages loaded by the document. Inside the braces of `\begin` and `\end`
+/// they are the environments of the same sources. Definitions of the document take precedence
+/// over standard ones of the same name. Returns `None` if `offset` is not inside a name.
+pub fn completions<'source>(
+    document: &Document<'_, 'source>,
+    store: &ExpansionStore<'source>,
+    offset: usize,
+) -> Option<Completions<'source>> {
+    let text = document.lexed().as_str();
+    let (kind, range) = context(text, offset)?;
+    let prefix = &text[range.clone()];
+
+    let loaded = document.loaded_packages();
+    let origin = |package: Option<&'static str>| match package {
+        None => Some(CompletionOrigin::Kernel),
+        Some(package) if loaded.contains(&package) => Some(CompletionOrigin::Package(package)),
+        Some(_) => None,
+    };
+
+    let (definitions, builtins): (Vec<_>, Vec<(_, _, &dyn Display)>) = match kind {
+        CompletionKind::Command => (
+            store.commands().collect(),
+            registry::commands().iter().map(|cmd| (cmd.name, cmd.package, cmd as _)).collect(),
+        ),
+        CompletionKind::Environment => (
+            store.environments().collect(),
+            registry::environments().iter().map(|env| (env.name, env.package, env as _)).collect(),
+        ),
+    };
+
+    let mut items: Vec<_> = definitions
+        .into_iter()
+        .filter(|item| item.name.starts_with(prefix))
+        .map(|item| Completion {
+            kind,
+            name: item.name,
+            signature: item.signature(),
+            origin: CompletionOrigin::Document,
+        })
+        .collect();
+    for (name, package, signature) in builtins {
+        if !name.starts_with(prefix) || items.iter().any(|item| item.name == name) {
+            continue;
+        }
+        if let Some(origin) = origin(package) {
+            items.push(Completion { kind, name, signature: signature.to_string(), origin });
+        }
+    }
+
+    items.sort_by(|a, b| a.name.cmp(b.name));
+    Some(Completions { range, items })
+}
+
+/// The kind of name ending at `offset` and its byte range, including the backslash of a command.
+fn context(text: &str, offset: usize) -> Option<(CompletionKind, Range<usize>)> {
+    let before = text.get(..offset)?;
+
+    let start = before.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '*').len();
+    if before[..start].ends_with("\\begin{") || before[..start].ends_with("\\end{") {
+        return Some((CompletionKind::Environment, start..offset));
+    }
+
+    let start = before.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == '@').len();
+    let backslashes = before[..start].len() - before[..start].trim_end_matches('\\').len();
+    // an even number of backslashes are line breaks, e.g. `\\foo`
+    match backslashes % 2 {
+        1 => Some((CompletionKind::Command, start - 1..offset)),
+        _ => None,
+    }
+}
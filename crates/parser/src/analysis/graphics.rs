@@ -0,0 +1,121 @@
+use std::ops::Range;
+
+use super::signatures::group;
+use crate::{
+    ast::{AstNode, Document, Environment},
+    files::{is_graphic, FILE_COMMANDS},
+    SyntaxKind,
+};
+
+/// Commands including an image or another document as a picture.
+pub const GRAPHICS_COMMANDS: &[&str] = &[
+    "\\includegraphics",
+    "\\includeinkscape",
+    "\\includepdf",
+    "\\includestandalone",
+    "\\includesvg",
+];
+
+/// Environments drawing a picture.
+pub const GRAPHICS_ENVIRONMENTS: &[&str] =
+    &["picture", "pgfpicture", "pspicture", "tikzcd", "tikzpicture"];
+
+/// What a [`Graphic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicKind {
+    /// A command from [`GRAPHICS_COMMANDS`]
+    Command,
+    /// An environment from [`GRAPHICS_ENVIRONMENTS`]
+    Environment,
+    /// An `\input` of a picture exported as TeX code, see
+    /// [`FileReference::is_graphic`](crate::files::FileReference::is_graphic)
+    Input,
+}
+
+/// A picture in a document, which text-only tools treat as an opaque placeholder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graphic<'source> {
+    /// What includes or draws the picture
+    pub kind: GraphicKind,
+    /// Byte range of the whole construct, e.g. `\includegraphics[width=3cm]{plot}`
+    pub range: Range<usize>,
+    /// The included file as written in the source, `None` for environments and if the argument
+    /// is missing
+    pub path: Option<&'source str>,
+}
+
+/// All pictures of `document` in source order. Pictures nested in another one, e.g. an
+/// `\includegraphics` inside of a `tikzpicture`, are part of the outer one.
+///
+/// ```
+/// use parser::{analysis::{graphics, GraphicKind}, ast::Document, LexedStr};
+///
+/// let source = "\\includegraphics[width=3cm]{plot} and \\input{chart.pgf}\n\
+///               \\begin{tikzpicture}\\node{\\includegraphics{logo}};\\end{tikzpicture}";
+/// let lexed = LexedStr::new(source);
+/// let found = graphics(&Document::new(&lexed));
+///
+/// let ranges: Vec<_> = found.iter().map(|graphic| (graphic.kind, &source[graphic.range.clone()])).collect();
+/// assert_eq!(ranges[0], (GraphicKind::Command, "\\includegraphics[width=3cm]{plot}"));
+/// assert_eq!(ranges[1], (GraphicKind::Input, "\\input{chart.pgf}"));
+/// assert_eq!(ranges[2].0, GraphicKind::Environment);
+/// assert_eq!((found.len(), found[0].path), (3, Some("plot")));
+/// ```
+pub fn graphics<'source>(document: &Document<'_, 'source>) -> Vec<Graphic<'source>> {
+    let lexed = document.lexed();
+    let mut found: Vec<_> = document
+        .descendants()
+        .filter_map(Environment::cast)
+        .filter(|environment| GRAPHICS_ENVIRONMENTS.contains(&environment.name()))
+        .map(|environment| Graphic {
+            kind: GraphicKind::Environment,
+            range: environment.syntax().text_range(),
+            path: None,
+        })
+        .collect();
+
+    for idx in 0..lexed.len() {
+        let command = lexed.text(idx);
+        let kind = match lexed.kind(idx) {
+            SyntaxKind::Command if GRAPHICS_COMMANDS.contains(&command) => GraphicKind::Command,
+            SyntaxKind::Command | SyntaxKind::FileInput if FILE_COMMANDS.contains(&command) => {
+                GraphicKind::Input
+            }
+            _ => continue,
+        };
+        let skip = |pos: usize| lexed.next_non_trivia(pos).unwrap_or(lexed.len());
+
+        // a star and an optional argument are skipped, e.g. of `\includegraphics*[3cm]{x}`
+        let mut pos = skip(idx + 1);
+        if pos < lexed.len() && lexed.kind(pos) == SyntaxKind::Star {
+            pos = skip(pos + 1);
+        }
+        if pos < lexed.len() && lexed.kind(pos) == SyntaxKind::OpenBracket {
+            pos = skip(group(lexed, pos, SyntaxKind::OpenBracket, SyntaxKind::CloseBracket).0);
+        }
+        let (end, path) = match pos < lexed.len() && lexed.kind(pos) == SyntaxKind::OpenBrace {
+            true => {
+                let (end, closed) =
+                    group(lexed, pos, SyntaxKind::OpenBrace, SyntaxKind::CloseBrace);
+                let content =
+                    lexed.text_start(pos + 1)..lexed.text_start(end - usize::from(closed));
+                (end, Some(lexed.as_str()[content].trim()))
+            }
+            false => (idx + 1, None),
+        };
+        if kind == GraphicKind::Input && !path.is_some_and(is_graphic) {
+            continue;
+        }
+        let range = lexed.text_start(idx)..lexed.text_range(end - 1).end;
+        found.push(Graphic { kind, range, path });
+    }
+
+    found.sort_by_key(|graphic| (graphic.range.start, std::cmp::Reverse(graphic.range.end)));
+    let mut outer = 0;
+    found.retain(|graphic| {
+        let nested = graphic.range.end <= outer;
+        outer = outer.max(graphic.range.end);
+        !nested
+    });
+    found
+}
@@ -0,0 +1,89 @@
+use std::ops::Range;
+
+use super::signatures::doc_comment;
+use crate::{
+    ast::Document,
+    expansion::{DefinitionKind, ExpansionStore},
+    LexedStr, SyntaxKind, TokenAtOffset,
+};
+
+/// What is known about a command of the document, see [`hover`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HoverInfo<'source> {
+    /// Name of the command including the leading backslash
+    pub name: &'source str,
+    /// Byte range of the command token the offset points at
+    pub call: Range<usize>,
+    /// Byte range of the whole definition, empty if it is inherited from another source
+    pub definition: Range<usize>,
+    /// Whether the command is defined with e.g. `\newcommand` or with `\def`
+    pub kind: DefinitionKind,
+    /// The argument signature, e.g. `\vect[#1]{#2}`
+    pub signature: String,
+    /// The replacement text as written in the definition
+    pub body: &'source str,
+    /// The annotated comment lines (`%%`) directly above the definition, without the `%%`
+    pub documentation: Option<String>,
+}
+
+/// The definition of the command at the byte `offset` of `document`, if the document defines it.
+/// Of a redefined command it is the definition in effect at the offset.
+///
+/// An offset right behind a command name still points at the command, so the information is
+/// available while the cursor is at the end of a name that was just typed.
+///
+/// ```
+/// use parser::{analysis::hover, ast::Document, expansion, LexedStr};
+///
+/// let source = "%% The real numbers\n\\newcommand{\\R}{\\mathbb{R}}\n$x \\in \\R$";
+/// let lexed = LexedStr::new(source);
+/// let info = hover(&Document::new(&lexed), &expansion::resolve(&lexed), source.len() - 1).unwrap();
+///
+/// assert_eq!((info.name, info.body), ("\\R", "\\mathbb{R}"));
+/// assert_eq!(&source[info.definition], "\\newcommand{\\R}{\\mathbb{R}}");
+/// assert_eq!(info.documentation.as_deref(), Some("The real numbers"));
+/// ```
+pub fn hover<'source>(
+    document: &Document<'_, 'source>,
+    definitions: &ExpansionStore<'source>,
+    offset: usize,
+) -> Option<HoverInfo<'source>> {
+    let lexed = document.lexed();
+    let idx = command_at(lexed, offset)?;
+    // a command of a definition itself refers to that definition
+    let name = lexed.text(idx);
+    let item = definitions.get_at(name, idx).or_else(|| {
+        definitions.definitions_of(name).iter().find(|item| item.range.contains(&idx))
+    })?;
+    // inherited definitions have no position in this document
+    let (definition, documentation) = match item.range.is_empty() {
+        true => (0..0, None),
+        false => (
+            lexed.text_start(item.range.start)..lexed.text_range(item.range.end - 1).end,
+            doc_comment(lexed, item.range.start, &[SyntaxKind::AComment]),
+        ),
+    };
+    Some(HoverInfo {
+        name: item.name,
+        call: lexed.text_range(idx),
+        definition,
+        kind: item.kind,
+        signature: item.signature(),
+        body: item.body,
+        documentation,
+    })
+}
+
+/// The `Command` token at the byte `offset`, preferring the one ending at `offset` over anything
+/// but a command starting there.
+pub(super) fn command_at(lexed: &LexedStr<'_>, offset: usize) -> Option<usize> {
+    let idx = match lexed.token_at_offset(offset) {
+        TokenAtOffset::None => return None,
+        TokenAtOffset::Single(idx) => idx,
+        TokenAtOffset::Between(left, right) => match lexed.kind(right) {
+            SyntaxKind::Command => right,
+            _ => left,
+        },
+    };
+    (lexed.kind(idx) == SyntaxKind::Command).then_some(idx)
+}
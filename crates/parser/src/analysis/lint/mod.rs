@@ -0,0 +1,106 @@
+use crate::diagnostics::Severity;
+
+/// Rule id of the lint reported for math delimited by `$` or `$$`.
+pub const DOLLAR_MATH: &str = "dollar-math";
+
+/// Rule id of the lint reported for a breakable space in front of a reference.
+pub const TIE_BEFORE_REFERENCE: &str = "tie-before-reference";
+
+/// Rule id of the lint reported for more than one space after a period.
+pub const DOUBLE_SPACE: &str = "double-space";
+
+/// Rule id of the lint reported for packages which have been superseded.
+pub const DEPRECATED_PACKAGE: &str = "deprecated-package";
+
+/// Rule id of the lint reported for a `[` at the start of a line following an unknown command.
+pub const BRACKET_AFTER_LINE_BREAK: &str = "bracket-after-line-break";
+
+/// Rule id of the lint reported for constructs which need `--shell-escape`, see
+/// [`shell_escapes`](crate::analysis::shell_escapes).
+pub const SHELL_ESCAPE: &str = "shell-escape";
+
+/// Rule id of the lint reported for a word following itself, e.g. `the the`.
+pub const REPEATED_WORD: &str = "repeated-word";
+
+/// Rule id of the lint reported for a word or a phrase of two words used again and again within
+/// a few sentences, see [`LintConfig::phrase_window`].
+pub const OVERUSED_PHRASE: &str = "overused-phrase";
+
+/// Which lint rules are run by [`lint`](crate::analysis::lint).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LintConfig {
+    /// [`DOLLAR_MATH`], off by default since `$` is the common style
+    pub dollar_math: bool,
+    /// [`TIE_BEFORE_REFERENCE`]
+    pub tie_before_reference: bool,
+    /// [`DOUBLE_SPACE`]
+    pub double_space: bool,
+    /// [`DEPRECATED_PACKAGE`]
+    pub deprecated_package: bool,
+    /// [`BRACKET_AFTER_LINE_BREAK`]
+    pub bracket_after_line_break: bool,
+    /// Severity of [`SHELL_ESCAPE`], `None` disables the rule
+    pub shell_escape: Option<Severity>,
+    /// [`REPEATED_WORD`]
+    pub repeated_word: bool,
+    /// [`OVERUSED_PHRASE`], off by default since technical terms are repeated on purpose
+    pub overused_phrase: bool,
+    /// Number of consecutive words in which a phrase used three times is reported as
+    /// [`OVERUSED_PHRASE`]
+    pub phrase_window: usize,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        LintConfig {
+            dollar_math: false,
+            tie_before_reference: true,
+            double_space: true,
+            deprecated_package: true,
+            bracket_after_line_break: true,
+            shell_escape: Some(Severity::Warning),
+            repeated_word: true,
+            overused_phrase: false,
+            phrase_window: 100,
+        }
+    }
+}
+
+impl LintConfig {
+    /// Enables every rule.
+    pub fn all() -> LintConfig {
+        LintConfig {
+            dollar_math: true,
+            tie_before_reference: true,
+            double_space: true,
+            deprecated_package: true,
+            bracket_after_line_break: true,
+            shell_escape: Some(Severity::Warning),
+            repeated_word: true,
+            overused_phrase: true,
+            ..LintConfig::default()
+        }
+    }
+
+    /// Disables every rule.
+    pub fn none() -> LintConfig {
+        LintConfig {
+            dollar_math: false,
+            tie_before_reference: false,
+            double_space: false,
+            deprecated_package: false,
+            bracket_after_line_break: false,
+            shell_escape: None,
+            repeated_word: false,
+            overused_phrase: false,
+            ..LintConfig::default()
+        }
+    }
+}
+
+// the configuration is needed to read config files, the rules only with the `lint` feature
+#[cfg(feature = "lint")]
+mod rules;
+
+#[cfg(feature = "lint")]
+pub use rules::{lint, Lint};
@@ -0,0 +1,347 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    ops::Range,
+};
+
+use super::{
+    LintConfig, BRACKET_AFTER_LINE_BREAK, DEPRECATED_PACKAGE, DOLLAR_MATH, DOUBLE_SPACE,
+    OVERUSED_PHRASE, REPEATED_WORD, SHELL_ESCAPE, TIE_BEFORE_REFERENCE,
+};
+use crate::{
+    analysis::{shell_escapes, words, ProseWord},
+    ast::{AstNode, CommandDefinition, Document, Environment, MathBlock, NodeKind},
+    diagnostics::{Diagnostic, Severity},
+    expansion::{TextEdit, VERBATIM_ENVIRONMENTS},
+    registry, LexedStr, SyntaxKind,
+};
+
+/// Commands which should be tied to the preceding word with `~`.
+const REFERENCE_COMMANDS: &[&str] = &["\\ref", "\\eqref", "\\pageref", "\\autoref", "\\cite"];
+
+/// Deprecated packages together with their replacement.
+const DEPRECATED_PACKAGES: &[(&str, &str)] = &[
+    ("a4", "geometry"),
+    ("a4wide", "geometry"),
+    ("caption2", "caption"),
+    ("doublespace", "setspace"),
+    ("epsfig", "graphicx"),
+    ("fancyheadings", "fancyhdr"),
+    ("isolatin1", "inputenc"),
+    ("palatino", "mathpazo"),
+    ("scrpage2", "scrlayer-scrpage"),
+    ("subfigure", "subcaption"),
+    ("t1enc", "fontenc"),
+    ("times", "mathptmx"),
+];
+
+/// A style finding of [`lint`] together with the edits fixing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// Rule id, e.g. [`DOUBLE_SPACE`]
+    pub rule: &'static str,
+    /// Byte range of the finding
+    pub range: Range<usize>,
+    /// Severity of the reported diagnostic
+    pub severity: Severity,
+    /// Human readable description
+    pub message: String,
+    /// Edits fixing the finding, empty if there is no automatic fix
+    pub fix: Vec<TextEdit>,
+}
+
+impl Lint {
+    /// Converts the finding into a [`Diagnostic`]. The fix is attached unless it may change the
+    /// meaning: a repeated word can be on purpose, e.g. `had had`, the replacement of a
+    /// deprecated package may not understand the same options and a bracket after a line break
+    /// may be meant as the optional argument.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic =
+            Diagnostic::new(self.rule, self.severity, self.range.clone(), self.message.clone());
+        match self.rule {
+            REPEATED_WORD | DEPRECATED_PACKAGE | BRACKET_AFTER_LINE_BREAK => diagnostic,
+            _ => diagnostic.with_fix(self.fix.iter().cloned()),
+        }
+    }
+}
+
+/// Runs the rules enabled in `config` over `document`. The findings are ordered by rule and then
+/// by their position.
+///
+/// Spacing rules only look at text, math, verbatim environments and definitions are skipped.
+pub fn lint(document: &Document<'_, '_>, config: &LintConfig) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    if config.dollar_math {
+        dollar_math(document, &mut lints);
+    }
+    if config.tie_before_reference || config.double_space {
+        spacing(document, config, &mut lints);
+    }
+    if config.deprecated_package {
+        deprecated_packages(document, &mut lints);
+    }
+    if config.bracket_after_line_break {
+        brackets_after_line_breaks(document, &mut lints);
+    }
+    if let Some(severity) = config.shell_escape {
+        shell_escape(document, severity, &mut lints);
+    }
+    if config.repeated_word || config.overused_phrase {
+        let words = words(document);
+        if config.repeated_word {
+            repeated_words(document.lexed(), &words, &mut lints);
+        }
+        if config.overused_phrase {
+            overused_phrases(&words, config.phrase_window, &mut lints);
+        }
+    }
+    lints
+}
+
+fn dollar_math(document: &Document<'_, '_>, lints: &mut Vec<Lint>) {
+    let lexed = document.lexed();
+    for math in document.descendants().filter_map(MathBlock::cast) {
+        let (open, close) = match math.delimiter() {
+            "$" => ("\\(", "\\)"),
+            "$$" => ("\\[", "\\]"),
+            _ => continue,
+        };
+        if !math.is_closed() {
+            continue;
+        }
+
+        let tokens = math.syntax().token_range();
+        let message = format!("use {open}...{close} instead of {0}...{0}", math.delimiter());
+        let edit = |idx: usize, new_text: &str| TextEdit {
+            range: lexed.text_range(idx),
+            new_text: new_text.to_string(),
+        };
+        lints.push(Lint {
+            rule: DOLLAR_MATH,
+            range: math.syntax().text_range(),
+            severity: Severity::Info,
+            message,
+            fix: vec![edit(tokens.start, open), edit(tokens.end - 1, close)],
+        });
+    }
+}
+
+fn spacing(document: &Document<'_, '_>, config: &LintConfig, lints: &mut Vec<Lint>) {
+    let lexed = document.lexed();
+    let prose = words::prose(document);
+    let space = |range: Range<usize>, new_text: &str| TextEdit { range, new_text: new_text.into() };
+
+    let mut ties = Vec::new();
+    let mut spaces = Vec::new();
+    let whitespace = (1..lexed.len().saturating_sub(1))
+        .filter(|&idx| prose[idx] && lexed.kind(idx) == SyntaxKind::Whitespace);
+    for idx in whitespace {
+        let (prev, next) = (lexed.kind(idx - 1), lexed.kind(idx + 1));
+        let range = lexed.text_range(idx);
+
+        if next == SyntaxKind::Command
+            && REFERENCE_COMMANDS.contains(&lexed.text(idx + 1))
+            && matches!(prev, SyntaxKind::Word | SyntaxKind::AWord | SyntaxKind::Number)
+        {
+            let message =
+                format!("use '~' in front of '{}' to keep it on the line", lexed.text(idx + 1));
+            ties.push(Lint {
+                rule: TIE_BEFORE_REFERENCE,
+                severity: Severity::Info,
+                message,
+                fix: vec![space(range.clone(), "~")],
+                range,
+            });
+            continue;
+        }
+
+        let trailing = matches!(
+            next,
+            SyntaxKind::Newline | SyntaxKind::Break | SyntaxKind::Comment | SyntaxKind::AComment
+        );
+        if prev == SyntaxKind::Dot && range.len() > 1 && !trailing {
+            let message = "more than one space after a period".to_string();
+            spaces.push(Lint {
+                rule: DOUBLE_SPACE,
+                severity: Severity::Info,
+                message,
+                fix: vec![space(range.clone(), " ")],
+                range,
+            });
+        }
+    }
+
+    if config.tie_before_reference {
+        lints.extend(ties);
+    }
+    if config.double_space {
+        lints.extend(spaces);
+    }
+}
+
+fn deprecated_packages(document: &Document<'_, '_>, lints: &mut Vec<Lint>) {
+    for (name, range) in document.package_names() {
+        let Some((_, replacement)) = DEPRECATED_PACKAGES.iter().find(|(old, _)| *old == name)
+        else {
+            continue;
+        };
+        lints.push(Lint {
+            rule: DEPRECATED_PACKAGE,
+            range: range.clone(),
+            severity: Severity::Warning,
+            message: format!("the package '{name}' is deprecated, use '{replacement}' instead"),
+            fix: vec![TextEdit { range, new_text: replacement.to_string() }],
+        });
+    }
+}
+
+/// A `[` at the start of a line is read as an optional argument by commands which take one. For
+/// commands neither in the [`registry`] nor defined in the document this is unknown, `\\` is one
+/// of them. The fix wraps the bracket in braces.
+fn brackets_after_line_breaks(document: &Document<'_, '_>, lints: &mut Vec<Lint>) {
+    let lexed = document.lexed();
+    let mut skipped = vec![false; lexed.len()];
+    let mut defined = Vec::new();
+    for node in document.descendants() {
+        let skip = match node.kind() {
+            NodeKind::CommandDefinition => {
+                defined.push(CommandDefinition::cast(node).unwrap().name());
+                true
+            }
+            NodeKind::EnvironmentDefinition => true,
+            NodeKind::Environment => {
+                VERBATIM_ENVIRONMENTS.contains(&Environment::cast(node).unwrap().name())
+            }
+            _ => false,
+        };
+        if skip {
+            skipped[node.token_range()].fill(true);
+        }
+    }
+
+    for idx in (0..lexed.len()).filter(|&idx| lexed.kind(idx) == SyntaxKind::Command) {
+        let name = lexed.text(idx);
+        // the segments of a NeoTeX path like `\a::b` are commands without a backslash
+        let Some(word) = name.strip_prefix('\\') else {
+            continue;
+        };
+        let word = word.chars().all(char::is_alphabetic);
+        if skipped[idx]
+            || !(word || name == "\\\\")
+            || registry::command(name).is_some()
+            || defined.contains(&name)
+        {
+            continue;
+        }
+
+        // a comment swallows its line break, so lines of comments may be in between
+        let mut next = idx + 1;
+        let mut line_break = false;
+        while next < lexed.len() {
+            match lexed.kind(next) {
+                SyntaxKind::Whitespace | SyntaxKind::Comment | SyntaxKind::AComment => {}
+                SyntaxKind::Newline => line_break = true,
+                _ => break,
+            }
+            next += 1;
+        }
+        if !line_break || next == lexed.len() || lexed.kind(next) != SyntaxKind::OpenBracket {
+            continue;
+        }
+
+        let range = lexed.text_range(next);
+        lints.push(Lint {
+            rule: BRACKET_AFTER_LINE_BREAK,
+            range: range.clone(),
+            severity: Severity::Info,
+            message: format!(
+                "'[' at the start of the line may be read as an optional argument of '{name}', \
+                 write '{{[}}' for a literal bracket"
+            ),
+            fix: vec![TextEdit { range, new_text: "{[}".to_string() }],
+        });
+    }
+}
+
+fn shell_escape(document: &Document<'_, '_>, severity: Severity, lints: &mut Vec<Lint>) {
+    for escape in
+        shell_escapes(document).into_iter().filter(|escape| escape.requires_shell_escape())
+    {
+        lints.push(Lint {
+            rule: SHELL_ESCAPE,
+            message: format!(
+                "{} runs external programs and needs '--shell-escape'",
+                escape.construct()
+            ),
+            range: escape.range,
+            severity,
+            fix: Vec::new(),
+        });
+    }
+}
+
+fn repeated_words(lexed: &LexedStr<'_>, words: &[ProseWord<'_>], lints: &mut Vec<Lint>) {
+    for pair in words.windows(2) {
+        let (prev, word) = (&pair[0], &pair[1]);
+        let adjacent = (prev.token + 1..word.token)
+            .all(|idx| matches!(lexed.kind(idx), SyntaxKind::Whitespace | SyntaxKind::Newline));
+        if adjacent && prev.token + 1 < word.token && prev.text.eq_ignore_ascii_case(word.text) {
+            lints.push(Lint {
+                rule: REPEATED_WORD,
+                range: word.range.clone(),
+                severity: Severity::Warning,
+                message: format!("'{}' is repeated", word.text),
+                fix: vec![TextEdit {
+                    range: prev.range.end..word.range.end,
+                    new_text: String::new(),
+                }],
+            });
+        }
+    }
+}
+
+/// Number of uses within the window from which a phrase is overused.
+const OVERUSE_COUNT: usize = 3;
+
+/// Reports phrases of one or two words with a content word among them which are used
+/// [`OVERUSE_COUNT`] times within `window` consecutive words. A phrase is reported again only
+/// once its last report is out of the window, a single word not inside of a reported phrase.
+fn overused_phrases(words: &[ProseWord<'_>], window: usize, lints: &mut Vec<Lint>) {
+    let mut found: Vec<Lint> = Vec::new();
+    for len in [2, 1] {
+        let mut uses: HashMap<String, VecDeque<usize>> = HashMap::new();
+        let mut reported: HashMap<String, usize> = HashMap::new();
+        for (end, phrase) in words.windows(len).enumerate() {
+            if !phrase.iter().any(ProseWord::is_content_word) {
+                continue;
+            }
+            let key: Vec<_> = phrase.iter().map(|word| word.text.to_lowercase()).collect();
+            let key = key.join(" ");
+            let positions = uses.entry(key.clone()).or_default();
+            positions.push_back(end);
+            while positions.front().is_some_and(|&start| end - start >= window) {
+                positions.pop_front();
+            }
+
+            let range = phrase[0].range.start..phrase[len - 1].range.end;
+            let repeated = reported.get(&key).is_some_and(|&last| end - last < window);
+            let nested = found
+                .iter()
+                .any(|lint| lint.range.start <= range.start && range.end <= lint.range.end);
+            if positions.len() >= OVERUSE_COUNT && !repeated && !nested {
+                reported.insert(key.clone(), end);
+                found.push(Lint {
+                    rule: OVERUSED_PHRASE,
+                    range,
+                    severity: Severity::Info,
+                    message: format!(
+                        "'{key}' is used {} times within {window} words",
+                        positions.len()
+                    ),
+                    fix: Vec::new(),
+                });
+            }
+        }
+    }
+    found.sort_by_key(|lint| lint.range.start);
+    lints.extend(found);
+}
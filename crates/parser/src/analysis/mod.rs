@@ -0,0 +1,42 @@
+//! Semantic analysis passes working on the whole document.
+//!
+//! Unlike the checks of the [`ast`](crate::ast), which look at a single node, these passes relate
+//! different parts of the document to each other, e.g. a `\ref` to the `\label` it points to.
+
+mod arguments;
+mod completions;
+mod graphics;
+mod hover;
+mod lint;
+mod references;
+mod shell_escape;
+mod signatures;
+mod spelling;
+mod symbols;
+mod word_count;
+mod words;
+
+pub use arguments::{check_arguments, MISSING_ARGUMENT};
+pub use completions::{completions, Completion, CompletionKind, CompletionOrigin, Completions};
+pub use graphics::{graphics, Graphic, GraphicKind, GRAPHICS_COMMANDS, GRAPHICS_ENVIRONMENTS};
+pub use hover::{hover, HoverInfo};
+#[cfg(feature = "lint")]
+pub use lint::{lint, Lint};
+pub use lint::{
+    LintConfig, BRACKET_AFTER_LINE_BREAK, DEPRECATED_PACKAGE, DOLLAR_MATH, DOUBLE_SPACE,
+    OVERUSED_PHRASE, REPEATED_WORD, SHELL_ESCAPE, TIE_BEFORE_REFERENCE,
+};
+pub use references::{check_references, DUPLICATE_LABEL, UNDEFINED_REFERENCE, UNUSED_LABEL};
+pub use shell_escape::{
+    shell_escapes, ShellEscape, ShellEscapeKind, WriteStream, SHELL_ESCAPE_PACKAGES,
+};
+pub use signatures::{signature_at, CommandStub, Parameter, ResolvedSignature, SignatureOrigin};
+#[cfg(feature = "spellcheck")]
+pub use spelling::{spell_check, Dictionary};
+pub use spelling::{text_runs, TextRun, UNKNOWN_WORD};
+pub use symbols::{fuzzy_match, name_at, symbols, Symbol, SymbolKind};
+pub use word_count::{word_count, SectionWords, WordCount, WordCountOptions, TEXT_COMMANDS};
+pub use words::{word_frequencies, words, ProseWord, MIN_WORD_LEN, STOP_WORDS};
+
+#[cfg(test)]
+mod tests;
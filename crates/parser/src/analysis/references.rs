@@ -0,0 +1,74 @@
+use rustc_hash::FxHashMap;
+
+use crate::{
+    ast::Document,
+    diagnostics::{Diagnostic, Severity},
+    index::{DocumentIndex, IndexEntry, IndexKind},
+};
+
+/// Rule id of the diagnostic reported for references to labels which are never defined.
+pub const UNDEFINED_REFERENCE: &str = "undefined-reference";
+
+/// Rule id of the diagnostic reported for labels which are defined more than once.
+pub const DUPLICATE_LABEL: &str = "duplicate-label";
+
+/// Rule id of the diagnostic reported for labels which are never referenced.
+pub const UNUSED_LABEL: &str = "unused-label";
+
+/// Checks the `\ref` like references of `document` against its `\label`s.
+///
+/// Reports references to undefined labels, every redefinition of a label together with its first
+/// definition and the references it makes ambiguous, and labels which are never referenced. All
+/// findings are warnings, since the labels may be defined or used in another file.
+pub fn check_references(document: &Document<'_, '_>) -> Vec<Diagnostic> {
+    let index = DocumentIndex::new(document.lexed());
+    let mut labels: FxHashMap<&str, Vec<&IndexEntry<'_>>> = FxHashMap::default();
+    let mut references: FxHashMap<&str, Vec<&IndexEntry<'_>>> = FxHashMap::default();
+    for entry in index.entries_of(IndexKind::Label) {
+        labels.entry(entry.key).or_default().push(entry);
+    }
+    for entry in index.entries_of(IndexKind::Reference) {
+        references.entry(entry.key).or_default().push(entry);
+    }
+
+    let mut diagnostics = Vec::new();
+    for entry in index.entries() {
+        let key = entry.key;
+        let uses = references.get(key).map_or(&[][..], Vec::as_slice);
+        let definitions = labels.get(key).map_or(&[][..], Vec::as_slice);
+
+        let diagnostic = match entry.kind {
+            IndexKind::Reference if definitions.is_empty() => {
+                let message = format!("reference to undefined label '{key}'");
+                Diagnostic::new(
+                    UNDEFINED_REFERENCE,
+                    Severity::Warning,
+                    entry.range.clone(),
+                    message,
+                )
+            }
+            IndexKind::Label if definitions[0].range != entry.range => {
+                let message = format!("label '{key}' is already defined");
+                let diagnostic = Diagnostic::new(
+                    DUPLICATE_LABEL,
+                    Severity::Warning,
+                    entry.range.clone(),
+                    message,
+                )
+                .with_related(definitions[0].range.clone(), "first defined here");
+                uses.iter().fold(diagnostic, |diagnostic, use_| {
+                    diagnostic.with_related(use_.range.clone(), "referenced here")
+                })
+            }
+            // only the first definition is reported, the others are duplicates
+            IndexKind::Label if uses.is_empty() => {
+                let message = format!("label '{key}' is never referenced");
+                Diagnostic::new(UNUSED_LABEL, Severity::Warning, entry.range.clone(), message)
+            }
+            _ => continue,
+        };
+        diagnostics.push(diagnostic);
+    }
+
+    diagnostics
+}
@@ -0,0 +1,255 @@
+use std::ops::Range;
+
+use crate::{
+    ast::{AstNode, Document, Environment},
+    expansion::VERBATIM_ENVIRONMENTS,
+    LexedStr, SyntaxKind,
+};
+
+/// Packages which run external programs while compiling and therefore need `--shell-escape`.
+pub const SHELL_ESCAPE_PACKAGES: &[&str] = &["auto-pst-pdf", "gnuplottex", "minted", "svg"];
+
+/// The stream a `\write` writes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteStream<'source> {
+    /// Stream 18, whose text is run as a shell command
+    Shell,
+    /// A negative stream, which only writes to the log file
+    Log,
+    /// A stream above 15 other than 18, which writes to the terminal and the log file
+    Terminal,
+    /// An explicit stream number between 0 and 15, open or not
+    Number(u8),
+    /// A stream allocated with `\newwrite`, e.g. `\out`
+    Named(&'source str),
+}
+
+/// What a [`ShellEscape`] does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellEscapeKind<'source> {
+    /// `\write`, possibly preceded by `\immediate`
+    Write(WriteStream<'source>),
+    /// `\ShellEscape` of the `shellesc` package
+    ShellEscape,
+    /// `\input|"command"`, which reads the output of a command
+    PipeInput,
+    /// `\special`, which passes raw text to the driver
+    Special,
+    /// A package from [`SHELL_ESCAPE_PACKAGES`]
+    Package(&'source str),
+}
+
+/// A construct running a shell command or writing raw output, see [`shell_escapes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellEscape<'source> {
+    /// What the construct does
+    pub kind: ShellEscapeKind<'source>,
+    /// Byte range of the construct, including a preceding `\immediate`
+    pub range: Range<usize>,
+    /// The written text, the shell command or the text passed to the driver. `None` for
+    /// packages and if it cannot be determined.
+    pub text: Option<&'source str>,
+}
+
+impl ShellEscape<'_> {
+    /// Returns `true` if the construct only works with `--shell-escape`. Restricted shell escape,
+    /// the default of TeX Live, allows a few programs only and is not taken into account.
+    pub fn requires_shell_escape(&self) -> bool {
+        !matches!(
+            self.kind,
+            ShellEscapeKind::Special
+                | ShellEscapeKind::Write(
+                    WriteStream::Log
+                        | WriteStream::Terminal
+                        | WriteStream::Number(_)
+                        | WriteStream::Named(_)
+                )
+        )
+    }
+
+    /// Short description of the construct, e.g. `\write18` or the package `minted`.
+    pub fn construct(&self) -> String {
+        match self.kind {
+            ShellEscapeKind::Write(WriteStream::Shell) => "\\write18".to_string(),
+            ShellEscapeKind::Write(WriteStream::Named(name)) => format!("\\write{name}"),
+            ShellEscapeKind::Write(_) => "\\write".to_string(),
+            ShellEscapeKind::ShellEscape => "\\ShellEscape".to_string(),
+            ShellEscapeKind::PipeInput => "\\input|".to_string(),
+            ShellEscapeKind::Special => "\\special".to_string(),
+            ShellEscapeKind::Package(name) => format!("the package '{name}'"),
+        }
+    }
+}
+
+/// All constructs of `document` which run shell commands, write to streams or pass raw text to
+/// the driver, in source order. The content of verbatim environments is skipped.
+///
+/// ```
+/// use parser::{
+///     analysis::{shell_escapes, ShellEscapeKind, WriteStream},
+///     ast::Document,
+///     LexedStr,
+/// };
+///
+/// let lexed = LexedStr::new("\\usepackage{minted}\n\\immediate\\write18{make plots}\\write\\out{x}");
+/// let found = shell_escapes(&Document::new(&lexed));
+///
+/// assert_eq!(found[0].kind, ShellEscapeKind::Package("minted"));
+/// assert_eq!(found[1].kind, ShellEscapeKind::Write(WriteStream::Shell));
+/// assert_eq!(found[1].text, Some("make plots"));
+/// assert_eq!(&lexed.as_str()[found[1].range.clone()], "\\immediate\\write18{make plots}");
+/// assert_eq!(found[2].kind, ShellEscapeKind::Write(WriteStream::Named("\\out")));
+/// assert!(found[1].requires_shell_escape() && !found[2].requires_shell_escape());
+/// ```
+pub fn shell_escapes<'source>(document: &Document<'_, 'source>) -> Vec<ShellEscape<'source>> {
+    let lexed = document.lexed();
+    let mut verbatim = vec![false; lexed.len()];
+    for environment in document.descendants().filter_map(Environment::cast) {
+        if VERBATIM_ENVIRONMENTS.contains(&environment.name()) {
+            verbatim[environment.syntax().token_range()].fill(true);
+        }
+    }
+
+    let mut found: Vec<_> = document
+        .package_names()
+        .into_iter()
+        .filter(|(name, _)| SHELL_ESCAPE_PACKAGES.contains(name))
+        .map(|(name, range)| ShellEscape {
+            kind: ShellEscapeKind::Package(name),
+            range,
+            text: None,
+        })
+        .collect();
+    for idx in (0..lexed.len()).filter(|&idx| !verbatim[idx]) {
+        let escape = match (lexed.kind(idx), lexed.text(idx)) {
+            (SyntaxKind::Command, name) if name.starts_with("\\write") => write(lexed, idx),
+            (SyntaxKind::Command, "\\ShellEscape") => {
+                let (text, end) = group(lexed, skip(lexed, idx + 1));
+                Some((ShellEscapeKind::ShellEscape, text, end))
+            }
+            (SyntaxKind::Command, "\\special") => {
+                let (text, end) = group(lexed, skip(lexed, idx + 1));
+                Some((ShellEscapeKind::Special, text, end))
+            }
+            (SyntaxKind::FileInput, "\\input") => pipe(lexed, idx),
+            _ => None,
+        };
+        let Some((kind, text, end)) = escape else {
+            continue;
+        };
+
+        // `\immediate` belongs to the construct
+        let mut start = idx;
+        let before = lexed.prev_non_trivia(idx);
+        if let Some(before) = before.filter(|&pos| lexed.text(pos) == "\\immediate") {
+            start = before;
+        }
+        let range = lexed.text_start(start)..lexed.text_range(end - 1).end;
+        found.push(ShellEscape { kind, range, text });
+    }
+    found.sort_by_key(|escape| escape.range.start);
+    found
+}
+
+/// `\write` at `idx` with its stream and text, and the index after them. The lexer keeps digits
+/// in command names, so `\write18` is a single token.
+fn write<'source>(
+    lexed: &LexedStr<'source>,
+    idx: usize,
+) -> Option<(ShellEscapeKind<'source>, Option<&'source str>, usize)> {
+    let number = |text: &str| {
+        Some(match text.parse::<u32>().ok()? {
+            18 => WriteStream::Shell,
+            number @ 0..=15 => WriteStream::Number(number as u8),
+            _ => WriteStream::Terminal,
+        })
+    };
+    let (stream, pos) = match &lexed.text(idx)["\\write".len()..] {
+        "" => {
+            let mut pos = skip(lexed, idx + 1);
+            let negative = pos < lexed.len() && lexed.kind(pos) == SyntaxKind::Minus;
+            if negative {
+                pos = skip(lexed, pos + 1);
+            }
+            if pos == lexed.len() {
+                return None;
+            }
+            let stream = match lexed.kind(pos) {
+                SyntaxKind::Number if negative => WriteStream::Log,
+                SyntaxKind::Number => number(lexed.text(pos))?,
+                SyntaxKind::Command if !negative => WriteStream::Named(lexed.text(pos)),
+                _ => return None,
+            };
+            (stream, pos)
+        }
+        digits => (number(digits)?, idx),
+    };
+    let (text, end) = group(lexed, skip(lexed, pos + 1));
+    Some((ShellEscapeKind::Write(stream), text, end.max(pos + 1)))
+}
+
+/// `\input|"command"` or `\input{|"command"}` at `idx`.
+fn pipe<'source>(
+    lexed: &LexedStr<'source>,
+    idx: usize,
+) -> Option<(ShellEscapeKind<'source>, Option<&'source str>, usize)> {
+    let mut pos = skip(lexed, idx + 1);
+    let braced = pos < lexed.len() && lexed.kind(pos) == SyntaxKind::OpenBrace;
+    if braced {
+        pos += 1;
+    }
+    if pos == lexed.len() || lexed.kind(pos) != SyntaxKind::Pipe {
+        return None;
+    }
+
+    // the command is quoted or extends to the next space
+    let quoted = pos + 1 < lexed.len() && lexed.kind(pos + 1) == SyntaxKind::DoubleApostrophe;
+    let (start, close) = match quoted {
+        true => {
+            let close = (pos + 2..lexed.len())
+                .find(|&close| lexed.kind(close) == SyntaxKind::DoubleApostrophe)?;
+            (pos + 2, close)
+        }
+        false => {
+            let close = (pos + 1..lexed.len())
+                .find(|&close| {
+                    lexed.kind(close).is_resolver_trivia()
+                        || lexed.kind(close) == SyntaxKind::CloseBrace
+                })
+                .unwrap_or(lexed.len());
+            (pos + 1, close)
+        }
+    };
+    let text = &lexed.as_str()[lexed.text_start(start)..lexed.text_start(close)];
+    let mut end = if quoted { close + 1 } else { close };
+    if braced && end < lexed.len() && lexed.kind(end) == SyntaxKind::CloseBrace {
+        end += 1;
+    }
+    Some((ShellEscapeKind::PipeInput, Some(text), end))
+}
+
+/// The first token at or after `pos` which is no whitespace or comment.
+fn skip(lexed: &LexedStr<'_>, pos: usize) -> usize {
+    lexed.next_non_trivia(pos).unwrap_or(lexed.len())
+}
+
+/// The content of the brace group opened at `open` and the index after it. Without a group the
+/// content is unknown and the index is `open`.
+fn group<'source>(lexed: &LexedStr<'source>, open: usize) -> (Option<&'source str>, usize) {
+    if open == lexed.len() || lexed.kind(open) != SyntaxKind::OpenBrace {
+        return (None, open);
+    }
+    let mut depth = 0usize;
+    for pos in open..lexed.len() {
+        match lexed.kind(pos) {
+            SyntaxKind::OpenBrace => depth += 1,
+            SyntaxKind::CloseBrace if depth == 1 => {
+                let text = &lexed.as_str()[lexed.text_start(open + 1)..lexed.text_start(pos)];
+                return (Some(text), pos + 1);
+            }
+            SyntaxKind::CloseBrace => depth -= 1,
+            _ => {}
+        }
+    }
+    (None, lexed.len())
+}
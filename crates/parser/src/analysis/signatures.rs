@@ -0,0 +1,293 @@
+use std::ops::Range;
+
+use crate::{
+    ast::Document,
+    expansion::{self, ExpansionStore, ExpansionStoreItem},
+    registry, LexedStr, SyntaxKind, Trivia,
+};
+
+/// The argument signature of a command which is neither defined in the document nor part of the
+/// [`registry`], e.g. from a package the registry does not know. Usually declared in the
+/// configuration of a project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandStub {
+    /// Name including the leading backslash
+    pub name: String,
+    /// Total number of arguments, including the optional one
+    pub arg_count: usize,
+    /// Whether the first argument is optional
+    pub optional: bool,
+}
+
+impl CommandStub {
+    /// Parses an argument specification like `[]{}{}`, where only the first argument may be
+    /// optional. Returns `None` for anything else.
+    pub fn parse(name: impl Into<String>, spec: &str) -> Option<CommandStub> {
+        let spec: String = spec.chars().filter(|c| !c.is_whitespace()).collect();
+        let optional = spec.starts_with("[]");
+        let mandatory = spec.strip_prefix("[]").unwrap_or(&spec);
+        if mandatory.len() % 2 != 0 || !mandatory.as_bytes().chunks(2).all(|arg| arg == b"{}") {
+            return None;
+        }
+        let arg_count = mandatory.len() / 2 + usize::from(optional);
+        Some(CommandStub { name: name.into(), arg_count, optional })
+    }
+}
+
+/// Where the signature of a [`ResolvedSignature`] comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignatureOrigin {
+    /// Defined in the document
+    Document,
+    /// Declared by a [`CommandStub`]
+    Stub,
+    /// Provided by the LaTeX kernel
+    Kernel,
+    /// Provided by the package of the [`registry`]
+    Package(&'static str),
+}
+
+/// A parameter of a [`ResolvedSignature`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    /// Whether the argument is optional
+    pub optional: bool,
+    /// Byte range of the parameter in the label, e.g. `[#1]` of `\sqrt[#1]{#2}`
+    pub label: Range<usize>,
+}
+
+/// The arguments a command takes at the position of a call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedSignature<'source> {
+    /// Name of the called command including the leading backslash
+    pub name: &'source str,
+    /// Byte range of the command name of the call
+    pub call: Range<usize>,
+    /// The formatted signature, e.g. `\sqrt[#1]{#2}`
+    pub label: String,
+    /// The parameters in order
+    pub parameters: Vec<Parameter>,
+    /// Index of the parameter the offset falls into, `None` if it cannot be determined, e.g.
+    /// for the delimited parameters of a `\def`
+    pub active: Option<usize>,
+    /// Where the signature is defined
+    pub origin: SignatureOrigin,
+    /// The comment lines directly above the definition in the document, without the `%`
+    pub documentation: Option<String>,
+}
+
+/// The signature of the innermost command call whose arguments contain the byte `offset` of
+/// `document`, e.g. `\frac` with the active parameter 1 at the end of `\frac{a}{b`.
+///
+/// Definitions of the document take precedence over `stubs`, which in turn take precedence over
+/// the [`registry`]. An offset right after the arguments present so far counts as the next
+/// missing argument, so the signature is available while a call is being typed.
+pub fn signature_at<'source>(
+    document: &Document<'_, 'source>,
+    store: &ExpansionStore<'source>,
+    stubs: &[CommandStub],
+    offset: usize,
+) -> Option<ResolvedSignature<'source>> {
+    let lexed = document.lexed();
+    let before = lexed.token_at_offset(offset).left_biased()?;
+
+    (0..=before)
+        .rev()
+        .filter(|&idx| lexed.kind(idx) == SyntaxKind::Command)
+        .filter(|&idx| lexed.text_range(idx).end <= offset)
+        .find_map(|idx| {
+            let signature = resolve(lexed.text(idx), idx, store, stubs)?;
+            signature.at(lexed, idx, offset)
+        })
+}
+
+/// The signature of the command `name` before it is located at a call.
+pub(super) struct Signature<'store, 'source> {
+    pub(super) name: &'source str,
+    pub(super) arg_count: usize,
+    pub(super) optional: bool,
+    pub(super) item: Option<&'store ExpansionStoreItem<'source>>,
+    origin: SignatureOrigin,
+}
+
+/// The signature of the command `name` called at the token `idx`.
+pub(super) fn resolve<'store, 'source>(
+    name: &'source str,
+    idx: usize,
+    store: &'store ExpansionStore<'source>,
+    stubs: &[CommandStub],
+) -> Option<Signature<'store, 'source>> {
+    let signature = if let Some(item) = store.get_at(name, idx) {
+        Signature {
+            name,
+            arg_count: item.args.count,
+            optional: item.args.default.is_some(),
+            item: Some(item),
+            origin: SignatureOrigin::Document,
+        }
+    } else if let Some(stub) = stubs.iter().find(|stub| stub.name == name) {
+        let (arg_count, optional) = (stub.arg_count, stub.optional);
+        Signature { name, arg_count, optional, item: None, origin: SignatureOrigin::Stub }
+    } else {
+        let builtin = registry::command(name)?;
+        let origin = builtin.package.map_or(SignatureOrigin::Kernel, SignatureOrigin::Package);
+        let (arg_count, optional) = (builtin.arg_count, builtin.optional);
+        Signature { name, arg_count, optional, item: None, origin }
+    };
+    (signature.arg_count > 0).then_some(signature)
+}
+
+impl<'source> Signature<'_, 'source> {
+    /// Locates `offset` in the arguments of the call at the token `idx`.
+    fn at(
+        &self,
+        lexed: &LexedStr<'source>,
+        idx: usize,
+        offset: usize,
+    ) -> Option<ResolvedSignature<'source>> {
+        let active = match self.item.filter(|item| item.pattern.is_some()) {
+            // delimited parameters are only known once the whole call is typed
+            Some(item) => {
+                let (_, _, end) = expansion::collect_at(item, lexed, idx + 1).ok()?;
+                if offset > lexed.text_start(end.min(lexed.len())) {
+                    return None;
+                }
+                None
+            }
+            None => Some(self.active(lexed, idx, offset)?),
+        };
+
+        let (label, parameters) = self.label();
+        Some(ResolvedSignature {
+            name: self.name,
+            call: lexed.text_range(idx),
+            label,
+            parameters,
+            active,
+            origin: self.origin,
+            documentation: self.item.and_then(|item| {
+                doc_comment(lexed, item.range.start, &[SyntaxKind::Comment, SyntaxKind::AComment])
+            }),
+        })
+    }
+
+    /// Index of the argument containing `offset`, or of the first missing argument if `offset`
+    /// directly follows the arguments present so far.
+    fn active(&self, lexed: &LexedStr<'_>, idx: usize, offset: usize) -> Option<usize> {
+        let mut pos = idx + 1;
+        for arg in 0..self.arg_count {
+            pos = (pos..lexed.len())
+                .find(|&pos| !lexed.kind(pos).is_trivia(Trivia::PARSE))
+                .unwrap_or(lexed.len());
+            let start = lexed.text_start(pos);
+            let optional = self.optional && arg == 0;
+
+            let kind = (pos < lexed.len()).then(|| lexed.kind(pos));
+            let (end, closed) = match kind {
+                Some(SyntaxKind::OpenBracket) if optional => {
+                    group(lexed, pos, SyntaxKind::OpenBracket, SyntaxKind::CloseBracket)
+                }
+                Some(SyntaxKind::OpenBrace) if !optional => {
+                    group(lexed, pos, SyntaxKind::OpenBrace, SyntaxKind::CloseBrace)
+                }
+                // an omitted optional argument may still be typed
+                _ if optional => {
+                    if offset <= start {
+                        return Some(arg);
+                    }
+                    continue;
+                }
+                Some(kind) if !kind.is_closing_delimiter() && kind != SyntaxKind::Break => {
+                    (pos + 1, true)
+                }
+                _ => return (offset <= start).then_some(arg),
+            };
+
+            let end_offset = lexed.text_start(end);
+            if offset < end_offset || (!closed && offset == end_offset) {
+                return Some(arg);
+            }
+            pos = end;
+        }
+        None
+    }
+
+    /// The formatted signature and the ranges of its parameters.
+    fn label(&self) -> (String, Vec<Parameter>) {
+        let mut label = String::from(self.name);
+        let mut parameters = Vec::with_capacity(self.arg_count);
+
+        if let Some(pattern) = self.item.and_then(|item| item.pattern.as_ref()) {
+            label.push_str(pattern.prefix);
+            for (arg, delimiter) in pattern.delimiters.iter().enumerate() {
+                let start = label.len();
+                label.push_str(&format!("#{}", arg + 1));
+                parameters.push(Parameter { optional: false, label: start..label.len() });
+                label.push_str(delimiter);
+            }
+            return (label, parameters);
+        }
+
+        for arg in 0..self.arg_count {
+            let optional = self.optional && arg == 0;
+            let start = label.len();
+            match optional {
+                true => label.push_str(&format!("[#{}]", arg + 1)),
+                false => label.push_str(&format!("{{#{}}}", arg + 1)),
+            }
+            parameters.push(Parameter { optional, label: start..label.len() });
+        }
+        (label, parameters)
+    }
+}
+
+/// The token after the group opened at `open` and whether it is closed. An unclosed group extends
+/// to the end of the document.
+pub(super) fn group(
+    lexed: &LexedStr<'_>,
+    open: usize,
+    left: SyntaxKind,
+    right: SyntaxKind,
+) -> (usize, bool) {
+    let mut depth = 0usize;
+    for idx in open..lexed.len() {
+        match lexed.kind(idx) {
+            kind if kind == left => depth += 1,
+            kind if kind == right => {
+                depth -= 1;
+                if depth == 0 {
+                    return (idx + 1, true);
+                }
+            }
+            _ => {}
+        }
+    }
+    (lexed.len(), false)
+}
+
+/// The comment lines of one of the `kinds` directly above the token `start`, joined by newlines.
+pub(super) fn doc_comment(
+    lexed: &LexedStr<'_>,
+    start: usize,
+    kinds: &[SyntaxKind],
+) -> Option<String> {
+    let mut lines = Vec::new();
+    let mut idx = start;
+    loop {
+        while idx > 0 && lexed.kind(idx - 1) == SyntaxKind::Whitespace {
+            idx -= 1;
+        }
+        if idx < 2 || lexed.kind(idx - 1) != SyntaxKind::Newline {
+            break;
+        }
+        let comment = idx - 2;
+        if !kinds.contains(&lexed.kind(comment)) {
+            break;
+        }
+        let text = lexed.text(comment).trim_start_matches('%');
+        lines.push(text.strip_prefix(' ').unwrap_or(text).trim_end());
+        idx = comment;
+    }
+    lines.reverse();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
@@ -0,0 +1,165 @@
+#[cfg(feature = "spellcheck")]
+use std::collections::HashSet;
+use std::ops::Range;
+
+use super::words::words;
+#[cfg(feature = "spellcheck")]
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::{ast::Document, Trivia};
+
+/// Rule id of the diagnostics of `spell_check` for words missing from its dictionary, available
+/// with the `spellcheck` feature.
+pub const UNKNOWN_WORD: &str = "unknown-word";
+
+/// Words of the text following each other, see [`text_runs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextRun<'source> {
+    /// The source text from the first up to the last word, including the whitespace between
+    pub text: &'source str,
+    /// Byte range of `text`
+    pub range: Range<usize>,
+}
+
+/// The runs of natural language of `document` in source order, e.g. to pass them to a spell or
+/// grammar checker.
+///
+/// A run consists of the [`words`] following each other with only whitespace and single newlines
+/// in between. Everything else ends a run: punctuation, commands, math, comments and paragraph
+/// breaks. The ranges of the runs map the findings of a checker back to the source.
+///
+/// ```
+/// use parser::{analysis::text_runs, ast::Document, LexedStr};
+///
+/// let lexed = LexedStr::new("Let $x$ be a real\nnumber, see \\ref{eq:x}. % todo\nThe end");
+/// let document = Document::new(&lexed);
+/// let runs: Vec<_> = text_runs(&document).map(|run| run.text).collect();
+/// assert_eq!(runs, ["Let", "be a real\nnumber", "see", "The end"]);
+/// ```
+pub fn text_runs<'lexed, 'source>(
+    document: &Document<'lexed, 'source>,
+) -> impl Iterator<Item = TextRun<'source>> + 'lexed {
+    let lexed = document.lexed();
+    let mut words = words(document).into_iter().peekable();
+    std::iter::from_fn(move || {
+        let first = words.next()?;
+        let mut last = first.token;
+        let mut end = first.range.end;
+        while let Some(next) = words.next_if(|next| {
+            (last + 1..next.token).all(|idx| lexed.kind(idx).is_trivia(Trivia::PARSE))
+        }) {
+            (last, end) = (next.token, next.range.end);
+        }
+        let range = first.range.start..end;
+        Some(TextRun { text: &lexed.as_str()[range.clone()], range })
+    })
+}
+
+/// The known words of [`spell_check`].
+#[cfg(feature = "spellcheck")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dictionary {
+    words: HashSet<String>,
+}
+
+#[cfg(feature = "spellcheck")]
+impl Dictionary {
+    /// An empty dictionary.
+    pub fn new() -> Dictionary {
+        Dictionary::default()
+    }
+
+    /// Reads a word list with one word per line, like the `.dic` files of Hunspell: the affix
+    /// flags following a `/`, a first line with the number of words and lines starting with `#`
+    /// are ignored.
+    ///
+    /// ```
+    /// use parser::analysis::Dictionary;
+    ///
+    /// let dictionary = Dictionary::from_word_list("3\nproof/S\ntheorem\n# names\nEuler\n");
+    /// assert!(dictionary.contains("proof") && dictionary.contains("Theorem"));
+    /// assert!(dictionary.contains("Euler") && !dictionary.contains("euler"));
+    /// assert!(!dictionary.contains("proofs") && !dictionary.contains("3"));
+    /// ```
+    pub fn from_word_list(list: &str) -> Dictionary {
+        let mut dictionary = Dictionary::new();
+        for line in list.lines().map(str::trim) {
+            let word = line.split('/').next().unwrap_or_default();
+            let count = word.bytes().all(|byte| byte.is_ascii_digit());
+            if !count && !word.starts_with('#') {
+                dictionary.insert(word);
+            }
+        }
+        dictionary
+    }
+
+    /// Adds `word`, e.g. a name the user marked as correct.
+    pub fn insert(&mut self, word: &str) {
+        self.words.insert(word.to_string());
+    }
+
+    /// Whether `word` is known. A word written with capitals is also known if its lowercase form
+    /// is, like a word at the start of a sentence, but not the other way round.
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(word) || self.words.contains(&word.to_lowercase())
+    }
+
+    /// Number of known words.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Returns `true` if no word is known.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+#[cfg(feature = "spellcheck")]
+impl<'a> Extend<&'a str> for Dictionary {
+    fn extend<T: IntoIterator<Item = &'a str>>(&mut self, words: T) {
+        for word in words {
+            self.insert(word);
+        }
+    }
+}
+
+#[cfg(feature = "spellcheck")]
+impl<'a> FromIterator<&'a str> for Dictionary {
+    fn from_iter<T: IntoIterator<Item = &'a str>>(words: T) -> Dictionary {
+        let mut dictionary = Dictionary::new();
+        dictionary.extend(words);
+        dictionary
+    }
+}
+
+/// Reports [`UNKNOWN_WORD`] for every word of the text of `document` which is not in
+/// `dictionary`. The words are those of [`words`], acronyms written in capitals only like `GPU`
+/// are not checked.
+///
+/// ```
+/// use parser::{analysis::{spell_check, Dictionary}, ast::Document, LexedStr};
+///
+/// let dictionary: Dictionary = ["this", "is", "the", "proof"].into_iter().collect();
+/// let lexed = LexedStr::new(r"This is teh \emph{proof} of $\lambda$ by GPU.");
+/// let diagnostics = spell_check(&Document::new(&lexed), &dictionary);
+/// let messages: Vec<_> = diagnostics.iter().map(|diagnostic| diagnostic.message.as_str()).collect();
+/// assert_eq!(messages, ["unknown word 'teh'", "unknown word 'of'", "unknown word 'by'"]);
+/// ```
+#[cfg(feature = "spellcheck")]
+pub fn spell_check(document: &Document<'_, '_>, dictionary: &Dictionary) -> Vec<Diagnostic> {
+    words(document)
+        .into_iter()
+        .filter(|word| word.text.chars().nth(1).is_none() || !is_acronym(word.text))
+        .filter(|word| !dictionary.contains(word.text))
+        .map(|word| {
+            let message = format!("unknown word '{}'", word.text);
+            Diagnostic::new(UNKNOWN_WORD, Severity::Warning, word.range, message)
+        })
+        .collect()
+}
+
+/// Whether `word` is written in capitals only.
+#[cfg(feature = "spellcheck")]
+fn is_acronym(word: &str) -> bool {
+    word.chars().all(|c| !c.is_alphabetic() || c.is_uppercase())
+}
@@ -0,0 +1,166 @@
+use std::ops::Range;
+
+use super::hover::command_at;
+use crate::{
+    ast::environment_name,
+    expansion::{DefinitionKind, ExpansionStore, RegisterKind},
+    index::{DocumentIndex, IndexKind},
+    LexedStr, SyntaxKind,
+};
+
+/// What a [`Symbol`] defines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    /// A command defined with e.g. `\newcommand` or `\def`
+    Command,
+    /// An environment defined with `\newenvironment`
+    Environment,
+    /// A label defined with `\label`
+    Label,
+    /// A counter declared with `\newcounter`
+    Counter,
+    /// A length declared with `\newlength`
+    Length,
+}
+
+/// A definition of a document which can be searched for by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol<'source> {
+    /// What the symbol defines
+    pub kind: SymbolKind,
+    /// Name as written in the source, commands and lengths including the backslash
+    pub name: &'source str,
+    /// Byte range of the whole definition, of the key for labels
+    pub range: Range<usize>,
+}
+
+/// All command, environment, label, counter and length definitions of `lexed` in source order,
+/// including every redefinition of a command. `definitions` are the resolved definitions of
+/// `lexed`, inherited ones are left out.
+pub fn symbols<'source>(
+    lexed: &LexedStr<'source>,
+    definitions: &ExpansionStore<'source>,
+) -> Vec<Symbol<'source>> {
+    let span = |range: &Range<usize>| {
+        lexed.text_start(range.start)..lexed.text_range(range.end.saturating_sub(1)).end
+    };
+    let mut symbols = Vec::new();
+
+    for item in definitions.all_commands().chain(definitions.environments()) {
+        if item.range.is_empty() {
+            continue;
+        }
+        let kind = match item.kind {
+            DefinitionKind::Environment => SymbolKind::Environment,
+            DefinitionKind::Command | DefinitionKind::Def => SymbolKind::Command,
+        };
+        symbols.push(Symbol { kind, name: item.name, range: span(&item.range) });
+    }
+    for item in definitions.registers().items().iter().filter(|item| item.declaration) {
+        let kind = match item.kind {
+            RegisterKind::Counter => SymbolKind::Counter,
+            RegisterKind::Length => SymbolKind::Length,
+        };
+        symbols.push(Symbol { kind, name: item.name, range: span(&item.range) });
+    }
+    for label in DocumentIndex::new(lexed).entries_of(IndexKind::Label) {
+        symbols.push(Symbol {
+            kind: SymbolKind::Label,
+            name: label.key,
+            range: label.range.clone(),
+        });
+    }
+
+    symbols.sort_by_key(|symbol| symbol.range.start);
+    symbols
+}
+
+/// The command, environment or label name at the byte `offset` of `lexed`, e.g. `proof` of
+/// `\begin{proof}` or `eq:1` of `\ref{eq:1}`, as the [`Symbol`] it refers to. The range is the
+/// byte range of the name at `offset`.
+///
+/// ```
+/// use parser::{analysis::{name_at, SymbolKind}, LexedStr};
+///
+/// let lexed = LexedStr::new(r"\begin{proof} \R \ref{eq:1}");
+/// let name = |offset| name_at(&lexed, offset).map(|symbol| (symbol.kind, symbol.name));
+///
+/// assert_eq!(name(9), Some((SymbolKind::Environment, "proof")));
+/// assert_eq!(name(16), Some((SymbolKind::Command, r"\R")));
+/// assert_eq!(name(24), Some((SymbolKind::Label, "eq:1")));
+/// assert_eq!(name(13), None);
+/// ```
+pub fn name_at<'source>(lexed: &LexedStr<'source>, offset: usize) -> Option<Symbol<'source>> {
+    let index = DocumentIndex::new(lexed);
+    let label = index.entries().iter().find(|entry| {
+        matches!(entry.kind, IndexKind::Label | IndexKind::Reference)
+            && entry.range.start <= offset
+            && offset <= entry.range.end
+    });
+    if let Some(label) = label {
+        return Some(Symbol {
+            kind: SymbolKind::Label,
+            name: label.key,
+            range: label.range.clone(),
+        });
+    }
+    if let Some(idx) = command_at(lexed, offset) {
+        let (name, range) = (lexed.text(idx), lexed.text_range(idx));
+        return Some(Symbol { kind: SymbolKind::Command, name, range });
+    }
+
+    // the name of `\begin{name}` or `\end{name}` containing the offset
+    let idx = lexed.token_at_offset(offset).left_biased()?;
+    let open = (0..=idx)
+        .rev()
+        .find(|&open| matches!(lexed.kind(open), SyntaxKind::OpenBrace | SyntaxKind::CloseBrace))?;
+    let keyword = lexed.prev_non_trivia(open)?;
+    if lexed.kind(open) != SyntaxKind::OpenBrace
+        || !matches!(lexed.kind(keyword), SyntaxKind::BeginGroup | SyntaxKind::EndGroup)
+    {
+        return None;
+    }
+    let (name, end) = environment_name(lexed, open).filter(|(name, _)| !name.is_empty())?;
+    let start = lexed.text_start(open + 1);
+    let start = start + lexed.as_str()[start..].len() - lexed.as_str()[start..].trim_start().len();
+    let range = start..start + name.len();
+    (offset >= range.start && offset <= range.end && idx < end).then_some(Symbol {
+        kind: SymbolKind::Environment,
+        name,
+        range,
+    })
+}
+
+/// Matches `query` fuzzily against `name`: all characters of the query have to appear in the
+/// name in order, ignoring case and a leading backslash of either.
+///
+/// Returns the cost of the match, lower is better. It counts the characters of the name skipped
+/// before and in between the matched ones, so prefixes and contiguous matches come first. An
+/// empty query matches every name at cost zero.
+///
+/// ```
+/// use parser::analysis::fuzzy_match;
+///
+/// assert_eq!(fuzzy_match("sec", "section"), Some(0));
+/// assert_eq!(fuzzy_match(r"\R", r"\Real"), Some(0));
+/// assert_eq!(fuzzy_match("eql", "eq:lemma"), Some(1));
+/// assert_eq!(fuzzy_match("lem", "eq:lemma"), Some(3));
+/// assert_eq!(fuzzy_match("ab", "ba"), None);
+/// ```
+pub fn fuzzy_match(query: &str, name: &str) -> Option<usize> {
+    let query = query.strip_prefix('\\').unwrap_or(query);
+    let name = name.strip_prefix('\\').unwrap_or(name);
+
+    let mut cost = 0;
+    let mut chars = name.chars();
+    for wanted in query.chars() {
+        loop {
+            let c = chars.next()?;
+            if c.to_lowercase().eq(wanted.to_lowercase()) {
+                break;
+            }
+            cost += 1;
+        }
+    }
+    Some(cost)
+}
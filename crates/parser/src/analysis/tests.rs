@@ -0,0 +1,522 @@
+use super::{
+    check_arguments, check_references, completions, fuzzy_match, hover, name_at, shell_escapes,
+    signature_at, symbols, text_runs, word_count, word_frequencies, words, CommandStub,
+    CompletionKind, CompletionOrigin, ShellEscapeKind, SignatureOrigin, SymbolKind,
+    WordCountOptions, WriteStream, DUPLICATE_LABEL, MISSING_ARGUMENT, UNDEFINED_REFERENCE,
+    UNUSED_LABEL,
+};
+#[cfg(feature = "lint")]
+use super::{
+    lint, LintConfig, BRACKET_AFTER_LINE_BREAK, DEPRECATED_PACKAGE, DOLLAR_MATH, DOUBLE_SPACE,
+    OVERUSED_PHRASE, REPEATED_WORD, SHELL_ESCAPE, TIE_BEFORE_REFERENCE,
+};
+#[cfg(feature = "spellcheck")]
+use super::{spell_check, Dictionary, UNKNOWN_WORD};
+use crate::{
+    ast::Document,
+    diagnostics::Severity,
+    expansion::{self, DefinitionKind},
+    LexedStr,
+};
+
+#[test]
+fn references() {
+    let input = "\\section{A}\\label{sec:a}\n\\begin{equation}x\\label{eq:x}\\end{equation}\n\
+                 \\label{sec:a} see \\ref{sec:a}, \\cref{eq:x,eq:y} and \\pageref{fig}";
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+
+    let diagnostics = check_references(&document);
+    let found: Vec<_> = diagnostics
+        .iter()
+        .map(|diag| (diag.rule, diag.message.as_str(), diag.range.start))
+        .collect();
+    let second = input.rfind("\\label{sec:a}").unwrap() + 7;
+    assert_eq!(
+        found,
+        [
+            (DUPLICATE_LABEL, "label 'sec:a' is already defined", second),
+            (
+                UNDEFINED_REFERENCE,
+                "reference to undefined label 'eq:y'",
+                input.find("eq:y").unwrap()
+            ),
+            (UNDEFINED_REFERENCE, "reference to undefined label 'fig'", input.find("fig").unwrap()),
+        ]
+    );
+
+    let related: Vec<_> = diagnostics[0]
+        .related
+        .iter()
+        .map(|related| {
+            (&input[related.range.clone()], related.range.start, related.message.as_str())
+        })
+        .collect();
+    assert_eq!(
+        related,
+        [
+            ("sec:a", 18, "first defined here"),
+            ("sec:a", input.find("\\ref{").unwrap() + 5, "referenced here"),
+        ]
+    );
+
+    let lexed = LexedStr::new("\\label{unused}");
+    let diagnostics = check_references(&Document::new(&lexed));
+    assert_eq!((diagnostics[0].rule, diagnostics[0].range.clone()), (UNUSED_LABEL, 7..13));
+
+    // a duplicate is reported even if the label is never referenced
+    let lexed = LexedStr::new("\\label{a}\\label{a}");
+    let diagnostics = check_references(&Document::new(&lexed));
+    let found: Vec<_> = diagnostics.iter().map(|diag| (diag.rule, diag.range.clone())).collect();
+    assert_eq!(found, [(UNUSED_LABEL, 7..8), (DUPLICATE_LABEL, 16..17)]);
+}
+
+#[test]
+fn completion_candidates() {
+    let input = "\\usepackage{amsmath}\\newcommand{\\frac}[3]{}\\newcommand{\\fr}{}\n\
+                 \\newenvironment{proof}{}{} $\\fr x \\mathb\\\\fr$ \\begin{pr}\\end{ali";
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+    let store = expansion::resolve(&lexed);
+    let complete = |at: &str| {
+        let offset = input.find(at).unwrap() + at.len();
+        completions(&document, &store, offset)
+    };
+
+    let commands = complete("$\\fr").unwrap();
+    assert_eq!(&input[commands.range.clone()], "\\fr");
+    let found: Vec<_> = commands
+        .items
+        .iter()
+        .map(|item| (item.kind, item.name, item.signature.as_str(), item.origin))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (CompletionKind::Command, "\\fr", "\\fr", CompletionOrigin::Document),
+            (CompletionKind::Command, "\\frac", "\\frac{#1}{#2}{#3}", CompletionOrigin::Document),
+        ]
+    );
+
+    // `\mathbb` is provided by `amssymb`, which is not loaded
+    let names: Vec<_> = complete("\\mathb").unwrap().items.iter().map(|item| item.name).collect();
+    assert_eq!(names, ["\\mathbf"]);
+    // after a line break
+    assert_eq!(complete("\\\\fr"), None);
+    assert_eq!(complete("$\\fr x"), None);
+
+    let environments = complete("\\begin{pr").unwrap();
+    let names: Vec<_> = environments.items.iter().map(|item| (item.name, item.origin)).collect();
+    assert_eq!(names, [("proof", CompletionOrigin::Document)]);
+    let environments = complete("\\end{ali").unwrap();
+    let names: Vec<_> = environments.items.iter().map(|item| (item.name, item.origin)).collect();
+    assert_eq!(
+        names,
+        [
+            ("align", CompletionOrigin::Package("amsmath")),
+            ("align*", CompletionOrigin::Package("amsmath")),
+            ("alignat", CompletionOrigin::Package("amsmath")),
+            ("alignat*", CompletionOrigin::Package("amsmath")),
+        ]
+    );
+}
+
+#[cfg(feature = "lint")]
+#[test]
+fn lints() {
+    let input = "\\usepackage{amsmath, subfigure}\n\\newcommand{\\x}{a.  b}\n\
+                 See Figure \\ref{fig}.  Next $a.  b$ and $$c$$. Done.   \n\
+                 \\begin{verbatim}x.  y\\end{verbatim} as in~\\cite{key}.";
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+
+    let lints = lint(&document, &LintConfig::all());
+    let found: Vec<_> = lints.iter().map(|lint| (lint.rule, &input[lint.range.clone()])).collect();
+    assert_eq!(
+        found,
+        [
+            (DOLLAR_MATH, "$a.  b$"),
+            (DOLLAR_MATH, "$$c$$"),
+            (TIE_BEFORE_REFERENCE, " "),
+            (DOUBLE_SPACE, "  "),
+            (DEPRECATED_PACKAGE, "subfigure"),
+        ]
+    );
+
+    let mut fixed = input.to_string();
+    let mut edits: Vec<_> = lints.iter().flat_map(|lint| lint.fix.iter()).collect();
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+    for edit in edits {
+        fixed.replace_range(edit.range.clone(), &edit.new_text);
+    }
+    let lexed = LexedStr::new(&fixed);
+    assert!(lint(&Document::new(&lexed), &LintConfig::all()).is_empty());
+    assert!(fixed.contains("{amsmath, subcaption}") && fixed.contains("\\(a.  b\\) and \\[c\\]"));
+
+    let config = LintConfig { deprecated_package: false, ..LintConfig::default() };
+    let rules: Vec<_> = lint(&document, &config).iter().map(|lint| lint.rule).collect();
+    assert_eq!(rules, [TIE_BEFORE_REFERENCE, DOUBLE_SPACE]);
+    assert!(lint(&document, &LintConfig::none()).is_empty());
+}
+
+#[cfg(feature = "lint")]
+#[test]
+fn brackets_after_line_breaks() {
+    let input = "\\newcommand{\\opt}[1][x]{#1}\\newcommand{\\plain}{y}\n\
+                 \\begin{tabular}{l}\n  a \\\\\n  [0, 1] \\\\[2pt]\n\\end{tabular}\n\
+                 \\todo % note\n[a] \\todo\n\n[b] \\todo [c] \\opt\n[d] \\plain\n[e] \\item\n[f]\n\
+                 \\begin{verbatim}\\x\n[g]\\end{verbatim}";
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+
+    let config = LintConfig { bracket_after_line_break: true, ..LintConfig::none() };
+    let lints = lint(&document, &config);
+    let found: Vec<_> = lints.iter().map(|lint| (lint.rule, lint.range.start)).collect();
+    assert_eq!(
+        found,
+        [
+            (BRACKET_AFTER_LINE_BREAK, input.find("[0, 1]").unwrap()),
+            (BRACKET_AFTER_LINE_BREAK, input.find("[a]").unwrap()),
+        ]
+    );
+    assert_eq!(
+        lints[1].message,
+        "'[' at the start of the line may be read as an optional argument of '\\todo', write \
+         '{[}' for a literal bracket"
+    );
+    assert_eq!(lints[0].fix[0].new_text, "{[}");
+    // only a suggestion, the bracket may be meant as the argument
+    assert!(lints[0].to_diagnostic().fix.is_empty());
+
+    // the bracket starts the next row instead of being the spacing of `\\`
+    let rows: Vec<_> = document
+        .descendants()
+        .filter(|node| node.kind() == crate::ast::NodeKind::Row)
+        .map(|row| row.text().trim())
+        .collect();
+    assert_eq!(rows, ["a \\\\", "[0, 1] \\\\[2pt]"]);
+
+    // the segments of a path are commands without a backslash
+    for input in ["\\subfile::é-\\subfile#1", "\\let\\fi::日本", "\\a::b\n[c]"] {
+        let lexed = LexedStr::new(input);
+        assert!(lint(&Document::new(&lexed), &config).is_empty(), "{input:?}");
+    }
+}
+
+#[test]
+fn shell_escape_constructs() {
+    let input =
+        "\\usepackage{graphicx,minted}\n\\newwrite\\out\n\\immediate \\write18{pdflatex -v}\n\
+                 \\write\\out{x}\\write-1{log}\\write16{term}\\write 3{y}\n\
+                 \\input|\"date\" \\input{|ls} \\input{file} \\ShellEscape{rm a}\\special{pdf: x}\n\
+                 \\begin{verbatim}\\write18{no}\\end{verbatim}";
+    let lexed = LexedStr::new(input);
+    let found = shell_escapes(&Document::new(&lexed));
+    let kinds: Vec<_> = found
+        .iter()
+        .map(|escape| (escape.kind, &input[escape.range.clone()], escape.text))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            (ShellEscapeKind::Package("minted"), "minted", None),
+            (
+                ShellEscapeKind::Write(WriteStream::Shell),
+                "\\immediate \\write18{pdflatex -v}",
+                Some("pdflatex -v")
+            ),
+            (ShellEscapeKind::Write(WriteStream::Named("\\out")), "\\write\\out{x}", Some("x")),
+            (ShellEscapeKind::Write(WriteStream::Log), "\\write-1{log}", Some("log")),
+            (ShellEscapeKind::Write(WriteStream::Terminal), "\\write16{term}", Some("term")),
+            (ShellEscapeKind::Write(WriteStream::Number(3)), "\\write 3{y}", Some("y")),
+            (ShellEscapeKind::PipeInput, "\\input|\"date\"", Some("date")),
+            (ShellEscapeKind::PipeInput, "\\input{|ls}", Some("ls")),
+            (ShellEscapeKind::ShellEscape, "\\ShellEscape{rm a}", Some("rm a")),
+            (ShellEscapeKind::Special, "\\special{pdf: x}", Some("pdf: x")),
+        ]
+    );
+    let required: Vec<_> = found.iter().map(|escape| escape.requires_shell_escape()).collect();
+    assert_eq!(required, [true, true, false, false, false, false, true, true, true, false]);
+}
+
+#[cfg(feature = "lint")]
+#[test]
+fn shell_escape_lint() {
+    let input = "\\usepackage{minted}\\immediate\\write18{ls}\\write\\out{x}";
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+
+    let config = LintConfig { shell_escape: Some(Severity::Error), ..LintConfig::none() };
+    let lints = lint(&document, &config);
+    let found: Vec<_> = lints.iter().map(|lint| (lint.rule, &input[lint.range.clone()])).collect();
+    assert_eq!(found, [(SHELL_ESCAPE, "minted"), (SHELL_ESCAPE, "\\immediate\\write18{ls}")]);
+    assert_eq!(lints[1].message, "\\write18 runs external programs and needs '--shell-escape'");
+    assert_eq!(lints[1].to_diagnostic().severity, Severity::Error);
+    assert_eq!(lint(&document, &LintConfig::default())[0].severity, Severity::Warning);
+}
+
+#[test]
+fn prose_words() {
+    let input = "\\documentclass{article}\\title{Preamble words}\n\\begin{document}\n\
+                 See \\ref{fig:graph} and \\input{graph}.\\begin{itemize}\\item Graph $graph$\
+                 \\end{itemize}\\begin{verbatim}graph\\end{verbatim}\\end{document}";
+    let lexed = LexedStr::new(input);
+    let found = words(&Document::new(&lexed));
+
+    let texts: Vec<_> = found.iter().map(|word| word.text).collect();
+    assert_eq!(texts, ["See", "and", "Graph"]);
+    assert_eq!(&input[found[2].range.clone()], "Graph");
+    assert!(!found[1].is_content_word() && found[2].is_content_word());
+    assert_eq!(word_frequencies(&found), [("graph".to_string(), 1)]);
+}
+
+#[test]
+fn natural_language_runs() {
+    let input = "\\documentclass{article}\n\\begin{document}\nThe  graph \\cite{west} has\n\
+                 many edges\n\nand $n$ vertices\\footnote{See \\ref{fig:graph} later}.\\end{document}";
+    let lexed = LexedStr::new(input);
+    let runs: Vec<_> = text_runs(&Document::new(&lexed)).collect();
+
+    let texts: Vec<_> = runs.iter().map(|run| run.text).collect();
+    assert_eq!(texts, ["The  graph", "has\nmany edges", "and", "vertices", "See", "later"]);
+    assert!(runs.iter().all(|run| &input[run.range.clone()] == run.text));
+}
+
+#[cfg(feature = "spellcheck")]
+#[test]
+fn spelling() {
+    let mut dictionary = Dictionary::from_word_list("the\ngraph/S\nhas\nedges\n");
+    let lexed = LexedStr::new("The grpah has edges. The graph has vertices of NP.");
+    let document = Document::new(&lexed);
+
+    let unknown = |dictionary: &Dictionary| -> Vec<_> {
+        let diagnostics = spell_check(&document, dictionary);
+        assert!(diagnostics.iter().all(|diagnostic| diagnostic.rule == UNKNOWN_WORD));
+        diagnostics.into_iter().map(|diagnostic| &lexed.as_str()[diagnostic.range]).collect()
+    };
+    assert_eq!(unknown(&dictionary), ["grpah", "vertices", "of"]);
+    assert_eq!(spell_check(&document, &dictionary)[0].range, 4..9);
+
+    dictionary.extend(["vertices", "of"]);
+    assert_eq!(unknown(&dictionary), ["grpah"]);
+    assert_eq!(dictionary.len(), 6);
+}
+
+#[test]
+fn word_counts() {
+    let input = "\\documentclass{article}\\title{Preamble words}\n\\begin{document}\n\
+                 \\section*{Graphs} See \\cite[p.~3]{west} and \\textbf{\\emph{bold} words}.\n\
+                 \\begin{tabular}{ll} a & b \\\\ \\end{tabular}\\todo{Check this}\n\
+                 \\subsection{Trees} $x$ is {\\bf the} root\\footnote{A note.}\\end{document}";
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+
+    let count = word_count(&document, 0..input.len(), &WordCountOptions::default());
+    assert_eq!(count.words, 13);
+    let sections: Vec<_> =
+        count.sections.iter().map(|section| (section.title, section.words)).collect();
+    assert_eq!(sections, [("Graphs", 13), ("Trees", 6)]);
+
+    let mut options = WordCountOptions::default();
+    options.text_commands.push("\\todo".to_string());
+    let trees = count.sections[1].range.clone();
+    assert_eq!(word_count(&document, 0..input.len(), &options).words, 15);
+    assert_eq!(word_count(&document, trees.clone(), &options).words, 6);
+    assert_eq!(word_count(&document, trees.end..input.len(), &options).sections, []);
+}
+
+#[cfg(feature = "lint")]
+#[test]
+fn word_repetitions() {
+    let input = "The the proof is\nis done, done. Graph theory studies graph theory: a graph \
+                 theory result. $x x$";
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+
+    let config = LintConfig { repeated_word: true, ..LintConfig::none() };
+    let lints = lint(&document, &config);
+    let found: Vec<_> = lints.iter().map(|lint| (lint.rule, &input[lint.range.clone()])).collect();
+    assert_eq!(found, [(REPEATED_WORD, "the"), (REPEATED_WORD, "is")]);
+    assert_eq!(lints[0].message, "'the' is repeated");
+    assert_eq!(&input[lints[1].fix[0].range.clone()], "\nis");
+
+    let config = LintConfig { overused_phrase: true, phrase_window: 12, ..LintConfig::none() };
+    let lints = lint(&document, &config);
+    let found: Vec<_> = lints.iter().map(|lint| (lint.rule, &input[lint.range.clone()])).collect();
+    assert_eq!(found, [(OVERUSED_PHRASE, "graph theory")]);
+    assert_eq!(lints[0].message, "'graph theory' is used 3 times within 12 words");
+
+    // the window is too small for the third use
+    let config = LintConfig { phrase_window: 6, ..config };
+    assert!(lint(&document, &config).is_empty());
+}
+
+#[test]
+fn signatures() {
+    let input = "% A vector\n%  with index\n\\newcommand{\\vect}[2][n]{#1#2}\\def\\pair(#1,#2){}\n\
+                 \\frac{a}{b} \\vect[m]{v} \\vect \\todo{a} \\pair(a,b) $\\frac{\\sqrt{x}}{b$";
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+    let store = expansion::resolve(&lexed);
+    let stubs = [CommandStub::parse("\\todo", "[]{}").unwrap()];
+    let signature = |at: &str| {
+        let offset = input.find(at).unwrap() + at.len();
+        signature_at(&document, &store, &stubs, offset)
+            .map(|signature| (signature.name, signature.active, signature.origin))
+    };
+
+    let frac = signature_at(&document, &store, &stubs, input.find("{b$").unwrap() + 2).unwrap();
+    assert_eq!(
+        (frac.name, frac.label.as_str(), frac.active),
+        ("\\frac", "\\frac{#1}{#2}", Some(1))
+    );
+    assert_eq!(&frac.label[frac.parameters[1].label.clone()], "{#2}");
+    assert_eq!(signature("$\\frac{\\sqrt{"), Some(("\\sqrt", Some(1), SignatureOrigin::Kernel)));
+    assert_eq!(signature("$\\frac{\\sqrt{x}"), Some(("\\frac", Some(0), SignatureOrigin::Kernel)));
+    assert_eq!(signature("$\\frac{\\sqrt{x}}"), Some(("\\frac", Some(1), SignatureOrigin::Kernel)));
+    // a closed call does not extend beyond its last argument
+    assert_eq!(signature("\\frac{a}{b}"), None);
+
+    assert_eq!(signature("\\vect[m"), Some(("\\vect", Some(0), SignatureOrigin::Document)));
+    assert_eq!(signature("\\vect[m]{"), Some(("\\vect", Some(1), SignatureOrigin::Document)));
+    assert_eq!(
+        signature("\\vect[m]{v} \\vect"),
+        Some(("\\vect", Some(0), SignatureOrigin::Document))
+    );
+    assert_eq!(signature("\\todo{"), Some(("\\todo", Some(1), SignatureOrigin::Stub)));
+
+    let pair = signature_at(&document, &store, &stubs, input.find("(a,b)").unwrap() + 2).unwrap();
+    assert_eq!((pair.label.as_str(), pair.active), ("\\pair(#1,#2)", None));
+    assert_eq!(pair.documentation, None);
+    let vect = signature_at(&document, &store, &stubs, input.find("[m").unwrap()).unwrap();
+    assert_eq!(vect.documentation.as_deref(), Some("A vector\n with index"));
+
+    assert_eq!(CommandStub::parse("\\x", "{}[]"), None);
+    assert_eq!(CommandStub::parse("\\x", "[] {}").map(|stub| stub.arg_count), Some(2));
+}
+
+#[test]
+fn missing_arguments() {
+    let input = "\\newcommand{\\pair}[2]{(#1, #2)}\\def\\x#1.{#1}\n\
+                 \\pair{a}{b} \\pair{a} b \\frac12 \\frac{1}\\pi \\section*{A} \\x y.\n\
+                 {\\frac{a}} \\todo{c} \\pair {a}\n\n{b}";
+    let lexed = LexedStr::new(input);
+    let store = expansion::resolve(&lexed);
+    let stubs = [CommandStub::parse("\\todo", "{}{}").unwrap()];
+    let diagnostics = check_arguments(&Document::new(&lexed), &store, &stubs);
+
+    let found: Vec<_> = diagnostics
+        .iter()
+        .map(|diag| (diag.rule, diag.severity, diag.message.as_str(), &input[diag.range.clone()]))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (MISSING_ARGUMENT, Severity::Warning, "missing argument 2 of '\\pair'", "\\pair{a}"),
+            (MISSING_ARGUMENT, Severity::Error, "missing argument 2 of '\\frac'", "\\frac{a}"),
+            (MISSING_ARGUMENT, Severity::Warning, "missing argument 2 of '\\todo'", "\\todo{c}"),
+            (MISSING_ARGUMENT, Severity::Error, "missing argument 2 of '\\pair'", "\\pair {a}"),
+        ]
+    );
+
+    let related: Vec<_> = diagnostics[0]
+        .related
+        .iter()
+        .map(|related| (&input[related.range.clone()], related.message.as_str()))
+        .collect();
+    assert_eq!(
+        related,
+        [
+            ("b", "TeX takes this as argument 2"),
+            ("\\newcommand{\\pair}[2]{(#1, #2)}", "defined here"),
+        ]
+    );
+    // neither the kernel nor a stub has a definition to point at
+    assert!(diagnostics[1].related.is_empty());
+    assert_eq!(diagnostics[2].related[0].message, "TeX takes this as argument 2");
+    assert_eq!(diagnostics[2].related.len(), 1);
+
+    // `\begin` is no argument, like for the `Invocation` of the syntax tree
+    let input = "\\newcommand{\\pair}[2]{}\\pair{a}\\begin{itemize}\\end{itemize}";
+    let lexed = LexedStr::new(input);
+    let store = expansion::resolve(&lexed);
+    let diagnostics = check_arguments(&Document::new(&lexed), &store, &[]);
+    let found: Vec<_> =
+        diagnostics.iter().map(|diag| (diag.severity, &input[diag.range.clone()])).collect();
+    assert_eq!(found, [(Severity::Error, "\\pair{a}")]);
+
+    // the star of `\DeclareOption*` replaces the name of the option
+    let input =
+        r"\DeclareOption{draft}{}\DeclareOption*{\PassOptionsToClass{\CurrentOption}{article}}";
+    let lexed = LexedStr::new(input);
+    let store = expansion::resolve(&lexed);
+    assert!(check_arguments(&Document::new(&lexed), &store, &[]).is_empty());
+}
+
+#[test]
+fn document_symbols() {
+    let source = "\\newlength{\\gap}\\setlength{\\gap}{1em}\n\\newenvironment{note}{}{}\n\\label{eq:1}\\newcommand{\\R}{\\mathbb{R}}";
+    let lexed = LexedStr::new(source);
+    let found = symbols(&lexed, &expansion::resolve(&lexed));
+
+    let names: Vec<_> = found.iter().map(|symbol| (symbol.kind, symbol.name)).collect();
+    assert_eq!(
+        names,
+        [
+            (SymbolKind::Length, "\\gap"),
+            (SymbolKind::Environment, "note"),
+            (SymbolKind::Label, "eq:1"),
+            (SymbolKind::Command, "\\R")
+        ]
+    );
+    assert_eq!(&source[found[0].range.clone()], "\\newlength{\\gap}");
+    assert_eq!(&source[found[2].range.clone()], "eq:1");
+    assert_eq!(&source[found[3].range.clone()], "\\newcommand{\\R}{\\mathbb{R}}");
+
+    assert_eq!(fuzzy_match("", "\\R"), Some(0));
+    assert_eq!(fuzzy_match("GAP", "\\gap"), Some(0));
+    assert_eq!(fuzzy_match("e1", "eq:1"), Some(2));
+    assert_eq!(fuzzy_match("gaps", "\\gap"), None);
+}
+
+#[test]
+fn hover_info() {
+    let input = "% plain comment\n\\def\\x{a}\n%% Vectors\n%%  in bold\n\\newcommand{\\vect}[1]{\\mathbf{#1}}\n\\x \\vect{v} \\frac";
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+    let store = expansion::resolve(&lexed);
+    let info = |at: &str| hover(&document, &store, input.rfind(at).unwrap() + at.len());
+
+    let vect = info("\\vect").unwrap();
+    assert_eq!(
+        (vect.name, vect.signature.as_str(), vect.body),
+        ("\\vect", "\\vect{#1}", "\\mathbf{#1}")
+    );
+    assert_eq!(&input[vect.call.clone()], "\\vect");
+    assert_eq!(&input[vect.definition.clone()], "\\newcommand{\\vect}[1]{\\mathbf{#1}}");
+    assert_eq!(vect.documentation.as_deref(), Some("Vectors\n in bold"));
+
+    // plain comments are not documentation
+    let x = info("\\x").unwrap();
+    assert_eq!((x.kind, x.documentation), (DefinitionKind::Def, None));
+    // builtin commands are not defined in the document
+    assert_eq!(info("\\frac"), None);
+    assert_eq!(info("{v"), None);
+}
+
+#[test]
+fn names_at_offsets() {
+    let input = "\\begin{ proof } \\R \\eqref{eq:1} \\end{proof} {proof}";
+    let lexed = LexedStr::new(input);
+    let name =
+        |offset: usize| name_at(&lexed, offset).map(|symbol| (symbol.kind, &input[symbol.range]));
+
+    assert_eq!(name(8), Some((SymbolKind::Environment, "proof")));
+    assert_eq!(name(13), Some((SymbolKind::Environment, "proof")));
+    assert_eq!(name(7), None);
+    // the cursor right behind a command name still points at it
+    assert_eq!(name(18), Some((SymbolKind::Command, "\\R")));
+    assert_eq!(name(19 + "\\eqref{".len()), Some((SymbolKind::Label, "eq:1")));
+    assert_eq!(name(input.find("proof}").unwrap() + 1), Some((SymbolKind::Environment, "proof")));
+    assert_eq!(name(input.len() - 2), None);
+}
@@ -0,0 +1,199 @@
+use std::ops::Range;
+
+use super::words::prose;
+use crate::{
+    ast::{environment_name, AstNode, Document},
+    outline::{Outline, SectionLevel},
+    SyntaxKind, Trivia,
+};
+
+/// Commands whose arguments are part of the text, counted by [`word_count`] by default.
+pub const TEXT_COMMANDS: &[&str] = &[
+    "\\emph",
+    "\\textbf",
+    "\\textit",
+    "\\textsl",
+    "\\textsc",
+    "\\texttt",
+    "\\textrm",
+    "\\textsf",
+    "\\textup",
+    "\\textmd",
+    "\\underline",
+    "\\mbox",
+    "\\footnote",
+    "\\caption",
+    "\\item",
+    "\\part",
+    "\\chapter",
+    "\\section",
+    "\\subsection",
+    "\\subsubsection",
+    "\\paragraph",
+    "\\subparagraph",
+];
+
+/// What [`word_count`] counts as text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordCountOptions {
+    /// Names of the commands including the leading backslash whose arguments are counted, e.g.
+    /// a `\todo` to count notes as well. The arguments of all other commands, like the key of a
+    /// `\label`, are left out.
+    pub text_commands: Vec<String>,
+}
+
+impl Default for WordCountOptions {
+    fn default() -> Self {
+        WordCountOptions {
+            text_commands: TEXT_COMMANDS.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+}
+
+/// The words of a section counted by [`word_count`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionWords<'source> {
+    /// The level of the sectioning command
+    pub level: SectionLevel,
+    /// The title of the section, see [`Section::title`](crate::outline::Section::title)
+    pub title: &'source str,
+    /// Byte range of the whole section
+    pub range: Range<usize>,
+    /// Number of words of the section including its heading and its subsections
+    pub words: usize,
+}
+
+/// The result of [`word_count`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WordCount<'source> {
+    /// Number of words in the counted range
+    pub words: usize,
+    /// The sections overlapping the counted range in source order, each followed by its
+    /// subsections, with the words of the section inside of the range
+    pub sections: Vec<SectionWords<'source>>,
+}
+
+/// Counts the words of the text of `document` starting in the byte range `range`.
+///
+/// Like a reader would count: only words of the `document` environment are counted, comments,
+/// math, verbatim environments, definitions and command names are left out. Words in the
+/// arguments of commands only count for the [`text_commands`](WordCountOptions::text_commands),
+/// so `\emph{very}` is a word while `\label{very}` and the `ll` of `\begin{tabular}{ll}` are
+/// not. Words are counted by token, so `well-known` counts twice.
+///
+/// ```
+/// use parser::{analysis::{word_count, WordCountOptions}, ast::Document, LexedStr};
+///
+/// let source = r"\begin{document}\section{Intro}\label{sec:intro}Let $x$ be \emph{positive}. % todo
+/// \subsection{Case} Then \ref{sec:intro} holds.\end{document}";
+/// let lexed = LexedStr::new(source);
+/// let document = Document::new(&lexed);
+///
+/// let count = word_count(&document, 0..source.len(), &WordCountOptions::default());
+/// assert_eq!(count.words, 7);
+/// let sections: Vec<_> = count.sections.iter().map(|section| (section.title, section.words)).collect();
+/// assert_eq!(sections, [("Intro", 7), ("Case", 3)]);
+/// ```
+pub fn word_count<'source>(
+    document: &Document<'_, 'source>,
+    range: Range<usize>,
+    options: &WordCountOptions,
+) -> WordCount<'source> {
+    let lexed = document.lexed();
+    let mut counted = text(document, options);
+    match document.body() {
+        Some(body) => {
+            let tokens = body.syntax().token_range();
+            counted[..tokens.start].fill(false);
+            counted[tokens.end..].fill(false);
+        }
+        None => counted.fill(false),
+    }
+
+    let words: Vec<usize> = (0..lexed.len())
+        .filter(|&idx| {
+            counted[idx] && matches!(lexed.kind(idx), SyntaxKind::Word | SyntaxKind::AWord)
+        })
+        .map(|idx| lexed.text_start(idx))
+        .filter(|start| range.contains(start))
+        .collect();
+    let within =
+        |section: &Range<usize>| words.iter().filter(|start| section.contains(start)).count();
+
+    let sections = Outline::new(lexed)
+        .iter()
+        .filter(|section| section.range.start < range.end && range.start < section.range.end)
+        .map(|section| SectionWords {
+            level: section.level,
+            title: section.title,
+            range: section.range.clone(),
+            words: within(&section.range),
+        })
+        .collect();
+    WordCount { words: words.len(), sections }
+}
+
+/// A brace group or an optional argument enclosing the current token, see [`text`].
+struct Group {
+    /// The kind of the token closing the group
+    close: SyntaxKind,
+    /// Whether the group is an argument of a command
+    argument: bool,
+    /// Whether the tokens of the group are text, i.e. it and all enclosing arguments are
+    /// arguments of text commands
+    text: bool,
+}
+
+/// Whether every token is part of the text, which excludes the tokens of [`prose`] and the
+/// arguments of commands which are not text commands.
+fn text(document: &Document<'_, '_>, options: &WordCountOptions) -> Vec<bool> {
+    let lexed = document.lexed();
+    let mut text = prose(document);
+    let mut groups: Vec<Group> = Vec::new();
+    // the command whose arguments may follow and whether it is a text command
+    let mut command: Option<bool> = None;
+    let mut idx = 0;
+    while idx < lexed.len() {
+        let outer = groups.last().map_or(true, |group| group.text);
+        let kind = lexed.kind(idx);
+        match kind {
+            SyntaxKind::BeginGroup | SyntaxKind::EndGroup => {
+                let after = environment_name(lexed, idx + 1).map_or(idx + 1, |(_, after)| after);
+                text[idx..after].fill(false);
+                // arguments of an environment, e.g. the columns of a `tabular`, are no text
+                command = (kind == SyntaxKind::BeginGroup).then_some(false);
+                idx = after;
+                continue;
+            }
+            SyntaxKind::OpenBrace | SyntaxKind::OpenBracket => {
+                let argument = command.is_some();
+                if argument || kind == SyntaxKind::OpenBrace {
+                    let close = match kind {
+                        SyntaxKind::OpenBrace => SyntaxKind::CloseBrace,
+                        _ => SyntaxKind::CloseBracket,
+                    };
+                    let text = outer && command.unwrap_or(true);
+                    groups.push(Group { close, argument, text });
+                }
+                command = None;
+            }
+            SyntaxKind::CloseBrace | SyntaxKind::CloseBracket
+                if groups.last().is_some_and(|group| group.close == kind) =>
+            {
+                let group = groups.pop().unwrap();
+                // further arguments of the same command may follow
+                command = group.argument.then_some(group.text);
+            }
+            SyntaxKind::Star if command.is_some() => {}
+            kind if kind.is_trivia(Trivia::PARSE) => {}
+            _ if lexed.text(idx).starts_with('\\') => {
+                let name = lexed.text(idx);
+                command = Some(options.text_commands.iter().any(|text| text == name));
+            }
+            _ => command = None,
+        }
+        text[idx] &= outer;
+        idx += 1;
+    }
+    text
+}
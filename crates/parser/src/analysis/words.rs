@@ -0,0 +1,134 @@
+use std::{collections::HashMap, ops::Range};
+
+use super::graphics;
+use crate::{
+    ast::{content_model, environment_name, AstNode, Document, Environment, NodeKind},
+    expansion::VERBATIM_ENVIRONMENTS,
+    files::file_references,
+    index::DocumentIndex,
+    SyntaxKind,
+};
+
+/// Minimal number of characters of a word counted by [`word_frequencies`].
+pub const MIN_WORD_LEN: usize = 4;
+
+/// Common English words which are long enough to be counted, but say nothing about the style of
+/// a text. They are left out of [`word_frequencies`] and the `overused-phrase` lint.
+pub const STOP_WORDS: &[&str] = &[
+    "about", "also", "been", "being", "from", "have", "into", "more", "most", "only", "other",
+    "some", "such", "than", "that", "their", "them", "then", "there", "these", "they", "this",
+    "those", "what", "when", "where", "which", "while", "will", "with", "would", "were",
+];
+
+/// A word of the text of a document, see [`words`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProseWord<'source> {
+    /// The word as written in the source
+    pub text: &'source str,
+    /// Byte range of the word
+    pub range: Range<usize>,
+    /// Index of the token of the word
+    pub token: usize,
+}
+
+impl ProseWord<'_> {
+    /// Whether the word is counted by [`word_frequencies`], i.e. it has at least
+    /// [`MIN_WORD_LEN`] characters and is none of the [`STOP_WORDS`].
+    pub fn is_content_word(&self) -> bool {
+        self.text.chars().count() >= MIN_WORD_LEN
+            && !STOP_WORDS.iter().any(|stop| stop.eq_ignore_ascii_case(self.text))
+    }
+}
+
+/// The words of the text of `document` in source order, which is the content of the `document`
+/// environment if there is one.
+///
+/// Command names, math, verbatim environments, definitions and pictures are left out, as are
+/// keys and names in arguments, e.g. of `\label{sec:intro}`, `\input{intro}` or
+/// `\begin{itemize}`.
+///
+/// ```
+/// use parser::{analysis::words, ast::Document, LexedStr};
+///
+/// let lexed = LexedStr::new(r"\section{Intro}\label{sec:intro} Let $x$ be \emph{positive}.");
+/// let found: Vec<_> = words(&Document::new(&lexed)).iter().map(|word| word.text).collect();
+/// assert_eq!(found, ["Intro", "Let", "be", "positive"]);
+/// ```
+pub fn words<'source>(document: &Document<'_, 'source>) -> Vec<ProseWord<'source>> {
+    let lexed = document.lexed();
+    let mut prose = prose(document);
+    if let Some(body) = document.body() {
+        let tokens = body.syntax().token_range();
+        prose[..tokens.start].fill(false);
+        prose[tokens.end..].fill(false);
+    }
+
+    let mut keys: Vec<_> =
+        DocumentIndex::new(lexed).entries().iter().map(|entry| entry.range.clone()).collect();
+    keys.extend(file_references(lexed).into_iter().map(|reference| reference.range));
+    keys.extend(graphics(document).into_iter().map(|graphic| graphic.range));
+
+    let mut found = Vec::new();
+    let mut idx = 0;
+    while idx < lexed.len() {
+        match lexed.kind(idx) {
+            SyntaxKind::BeginGroup | SyntaxKind::EndGroup => {
+                idx = environment_name(lexed, idx + 1).map_or(idx + 1, |(_, after)| after);
+                continue;
+            }
+            SyntaxKind::Word | SyntaxKind::AWord if prose[idx] => {
+                let range = lexed.text_range(idx);
+                if !keys.iter().any(|key| key.contains(&range.start)) {
+                    found.push(ProseWord { text: lexed.text(idx), range, token: idx });
+                }
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+    found
+}
+
+/// How often each [content word](ProseWord::is_content_word) of `words` is used, compared
+/// ignoring case. The words are lowercase and ordered by their count, most used first, and then
+/// alphabetically.
+///
+/// ```
+/// use parser::{analysis::{word_frequencies, words}, ast::Document, LexedStr};
+///
+/// let lexed = LexedStr::new("Graphs have edges. The edges of graphs with loops. Edges!");
+/// let frequencies = word_frequencies(&words(&Document::new(&lexed)));
+/// assert_eq!(frequencies[..2], [("edges".to_string(), 3), ("graphs".to_string(), 2)]);
+/// ```
+pub fn word_frequencies(words: &[ProseWord<'_>]) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for word in words.iter().filter(|word| word.is_content_word()) {
+        *counts.entry(word.text.to_lowercase()).or_default() += 1;
+    }
+
+    let mut frequencies: Vec<_> = counts.into_iter().collect();
+    frequencies.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    frequencies
+}
+
+/// Whether every token is part of the text, as opposed to math, verbatim content or definitions.
+pub(crate) fn prose(document: &Document<'_, '_>) -> Vec<bool> {
+    let mut prose = vec![true; document.lexed().len()];
+    for node in document.descendants() {
+        let code = match node.kind() {
+            NodeKind::MathBlock | NodeKind::CommandDefinition | NodeKind::EnvironmentDefinition => {
+                true
+            }
+            NodeKind::Environment => {
+                let name = Environment::cast(node).unwrap().name();
+                VERBATIM_ENVIRONMENTS.contains(&name)
+                    || content_model(name).is_some_and(|model| model.math)
+            }
+            _ => false,
+        };
+        if code {
+            prose[node.token_range()].fill(false);
+        }
+    }
+    prose
+}
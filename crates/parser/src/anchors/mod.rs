@@ -0,0 +1,151 @@
+//! Named ranges of a document which follow its edits, e.g. the span a reviewer comment refers
+//! to.
+//!
+//! A tool registers a range under a name in [`Anchors`] and passes every edit of the text to
+//! [`Anchors::apply`], which moves the ranges so they keep covering the same text. Only the
+//! offsets are tracked, so the anchors of a document are cheap to keep next to it. With the
+//! `serde` feature enabled they serialize and deserialize, to be stored between sessions.
+//!
+//! ```
+//! use parser::{anchors::Anchors, expansion::TextEdit};
+//!
+//! let mut text = String::from("Let $x$ be real.");
+//! let mut anchors = Anchors::new();
+//! anchors.insert("reviewer comment #12", 4..7);
+//!
+//! let edit = TextEdit { range: 0..3, new_text: "Now let".to_string() };
+//! text.replace_range(edit.range.clone(), &edit.new_text);
+//! anchors.apply(&edit);
+//! assert_eq!(&text[anchors.get("reviewer comment #12").unwrap()], "$x$");
+//! ```
+
+use std::{collections::BTreeMap, ops::Range};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::expansion::TextEdit;
+
+/// Which side of an edit an offset inside of the replaced text moves to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bias {
+    /// The start of the replacement
+    Before,
+    /// The end of the replacement
+    After,
+}
+
+/// The offset `offset` of a text after replacing `edit.range` with `edit.new_text`. Offsets in
+/// front of the edit stay, offsets following it move by the change of the length, and offsets
+/// inside of the replaced text or at its boundaries move to the side given by `bias`.
+pub fn map_offset(offset: usize, edit: &TextEdit, bias: Bias) -> usize {
+    let Range { start, end } = edit.range;
+    if offset < start {
+        offset
+    } else if offset > end {
+        offset - (end - start) + edit.new_text.len()
+    } else {
+        match bias {
+            Bias::Before => start,
+            Bias::After => start + edit.new_text.len(),
+        }
+    }
+}
+
+/// The range `range` of a text after applying `edit`, see [`Anchors::apply`].
+pub fn map_range(range: &Range<usize>, edit: &TextEdit) -> Range<usize> {
+    let start = map_offset(range.start, edit, Bias::After);
+    let end = map_offset(range.end, edit, Bias::Before);
+    start..end.max(start)
+}
+
+/// Named byte ranges of a text, ordered by name. See the [module](self) documentation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Anchors {
+    ranges: BTreeMap<String, Range<usize>>,
+}
+
+impl Anchors {
+    /// No anchors.
+    pub fn new() -> Anchors {
+        Anchors::default()
+    }
+
+    /// Anchors `range` under `name`. Returns the range previously anchored under the name.
+    pub fn insert(&mut self, name: impl Into<String>, range: Range<usize>) -> Option<Range<usize>> {
+        self.ranges.insert(name.into(), range)
+    }
+
+    /// Removes the anchor `name` and returns its range.
+    pub fn remove(&mut self, name: &str) -> Option<Range<usize>> {
+        self.ranges.remove(name)
+    }
+
+    /// The current range of the anchor `name`.
+    pub fn get(&self, name: &str) -> Option<Range<usize>> {
+        self.ranges.get(name).cloned()
+    }
+
+    /// All anchors ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Range<usize>)> {
+        self.ranges.iter().map(|(name, range)| (name.as_str(), range.clone()))
+    }
+
+    /// The anchors whose range contains `offset` or ends at it, e.g. the comments at the cursor.
+    pub fn at(&self, offset: usize) -> impl Iterator<Item = (&str, Range<usize>)> {
+        self.iter().filter(move |(_, range)| range.start <= offset && offset <= range.end)
+    }
+
+    /// Number of anchors.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Returns `true` if there are no anchors.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Moves the anchors along with `edit`, so they keep covering the same text.
+    ///
+    /// Text inserted inside of a range becomes part of it, text inserted at its start or end
+    /// does not. A range whose text is partly replaced shrinks to the remaining text, and one
+    /// whose text is replaced completely collapses to an empty range behind the replacement.
+    pub fn apply(&mut self, edit: &TextEdit) {
+        for range in self.ranges.values_mut() {
+            *range = map_range(range, edit);
+        }
+    }
+
+    /// Like [`apply`](Self::apply) for non-overlapping `edits` of the same text, like the edits of
+    /// a rename, which are applied together.
+    pub fn apply_all(&mut self, edits: &[TextEdit]) {
+        let mut edits: Vec<_> = edits.iter().collect();
+        // from the end, so the ranges of the remaining edits are still valid
+        edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+        for edit in edits {
+            self.apply(edit);
+        }
+    }
+
+    /// Removes the anchors which do not fit `text`, e.g. after restoring them for a file which
+    /// was changed outside of the tool. Returns the names of the removed anchors.
+    pub fn retain_valid(&mut self, text: &str) -> Vec<String> {
+        let fits =
+            |range: &Range<usize>| range.start <= range.end && text.get(range.clone()).is_some();
+        let invalid: Vec<String> = self
+            .ranges
+            .iter()
+            .filter(|(_, range)| !fits(range))
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in &invalid {
+            self.ranges.remove(name);
+        }
+        invalid
+    }
+}
+
+#[cfg(test)]
+mod tests;
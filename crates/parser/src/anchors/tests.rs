@@ -0,0 +1,71 @@
+use super::{map_offset, Anchors, Bias};
+use crate::expansion::TextEdit;
+
+fn edit(range: std::ops::Range<usize>, new_text: &str) -> TextEdit {
+    TextEdit { range, new_text: new_text.to_string() }
+}
+
+#[test]
+fn offsets() {
+    let replace = edit(4..8, "ab");
+    assert_eq!(map_offset(3, &replace, Bias::After), 3);
+    assert_eq!(map_offset(10, &replace, Bias::Before), 8);
+    assert_eq!(map_offset(6, &replace, Bias::Before), 4);
+    assert_eq!(map_offset(6, &replace, Bias::After), 6);
+    assert_eq!(map_offset(4, &edit(4..4, "xyz"), Bias::After), 7);
+}
+
+#[test]
+fn ranges_follow_edits() {
+    let text = "one two three four";
+    let mut anchors = Anchors::new();
+    anchors.insert("two", 4..7);
+    anchors.insert("two three", 4..13);
+    anchors.insert("three", 8..13);
+    anchors.insert("cursor", 18..18);
+
+    let edits = [edit(0..3, "1"), edit(4..4, ">"), edit(7..8, " and "), edit(10..13, "ree!")];
+    let mut updated = text.to_string();
+    for edit in edits.iter().rev() {
+        updated.replace_range(edit.range.clone(), &edit.new_text);
+    }
+    anchors.apply_all(&edits);
+    assert_eq!(updated, "1 >two and three! four");
+
+    let text = |name: &str| &updated[anchors.get(name).unwrap()];
+    // insertions at the boundaries stay outside, the ones inside become part of the range
+    assert_eq!(text("two"), "two");
+    // partly replaced, the replacement is not part of the range
+    assert_eq!(text("two three"), "two and th");
+    assert_eq!(text("three"), "th");
+    assert_eq!(anchors.get("cursor"), Some(22..22));
+    assert_eq!(anchors.at(5).map(|(name, _)| name).collect::<Vec<_>>(), ["two", "two three"]);
+
+    // replaced completely
+    anchors.apply(&edit(3..20, "x"));
+    assert_eq!(anchors.get("two"), Some(4..4));
+    assert_eq!(anchors.get("cursor"), Some(6..6));
+}
+
+#[test]
+fn restore() {
+    let mut anchors = Anchors::new();
+    anchors.insert("start", 0..2);
+    anchors.insert("past the end", 3..9);
+    anchors.insert("inside a character", 1..3);
+    assert_eq!(anchors.remove("missing"), None);
+
+    let removed = anchors.retain_valid("abäd");
+    assert_eq!(removed, ["inside a character", "past the end"]);
+    assert_eq!(anchors.iter().collect::<Vec<_>>(), [("start", 0..2)]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialized() {
+    let mut anchors = Anchors::new();
+    anchors.insert("comment #12", 4..7);
+    let json = serde_json::to_string(&anchors).unwrap();
+    assert_eq!(json, r#"{"ranges":{"comment #12":{"start":4,"end":7}}}"#);
+    assert_eq!(serde_json::from_str::<Anchors>(&json).unwrap(), anchors);
+}
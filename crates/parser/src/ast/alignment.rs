@@ -0,0 +1,233 @@
+use std::ops::Range;
+
+use super::{AstNode, Cell, Document, Environment, NodeKind, Row};
+use crate::{
+    diagnostics::{Diagnostic, Severity},
+    LexedStr, SyntaxKind,
+};
+
+/// Rule id of the diagnostic reported for rows with an unexpected number of cells.
+pub const COLUMN_COUNT: &str = "column-count";
+
+/// An environment whose content is split into rows at `\\` and into cells at `&`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alignment {
+    /// Number of mandatory arguments in front of the first row, e.g. the column spec of `tabular`
+    pub args: usize,
+    /// Index of the argument holding the column spec
+    pub spec: Option<usize>,
+    /// All rows need the same number of cells, used for environments without a column spec
+    pub uniform: bool,
+}
+
+const fn spec(args: usize, spec: usize) -> Alignment {
+    Alignment { args, spec: Some(spec), uniform: false }
+}
+
+const ALIGN: Alignment = Alignment { args: 0, spec: None, uniform: false };
+const MATRIX: Alignment = Alignment { args: 0, spec: None, uniform: true };
+
+/// Alignment environments of LaTeX and common packages.
+const ALIGNMENTS: &[(&str, Alignment)] = &[
+    ("tabular", spec(1, 0)),
+    ("tabular*", spec(2, 1)),
+    ("tabularx", spec(2, 1)),
+    ("longtable", spec(1, 0)),
+    ("array", spec(1, 0)),
+    ("align", ALIGN),
+    ("align*", ALIGN),
+    ("alignat", Alignment { args: 1, ..ALIGN }),
+    ("alignat*", Alignment { args: 1, ..ALIGN }),
+    ("aligned", ALIGN),
+    ("flalign", ALIGN),
+    ("flalign*", ALIGN),
+    ("gather", ALIGN),
+    ("gather*", ALIGN),
+    ("split", ALIGN),
+    ("cases", MATRIX),
+    ("matrix", MATRIX),
+    ("pmatrix", MATRIX),
+    ("bmatrix", MATRIX),
+    ("Bmatrix", MATRIX),
+    ("vmatrix", MATRIX),
+    ("Vmatrix", MATRIX),
+    ("smallmatrix", MATRIX),
+];
+
+/// Returns the alignment of the environment `name`, `None` if it is not split into rows.
+pub fn alignment(name: &str) -> Option<&'static Alignment> {
+    ALIGNMENTS.iter().find(|(environment, _)| *environment == name).map(|(_, alignment)| alignment)
+}
+
+/// Token ranges of the content of `count` brace groups following the token `idx`, together with
+/// the index after the last group. Missing groups are empty.
+pub(super) fn arguments(
+    lexed: &LexedStr<'_>,
+    mut idx: usize,
+    count: usize,
+) -> (Vec<Range<usize>>, usize) {
+    let mut groups = Vec::with_capacity(count);
+    for _ in 0..count {
+        let open = lexed.next_non_trivia(idx);
+        let Some(open) = open.filter(|&open| lexed.kind(open) == SyntaxKind::OpenBrace) else {
+            groups.push(idx..idx);
+            continue;
+        };
+
+        let mut depth = 0usize;
+        let close = (open + 1..lexed.len()).find(|&close| match lexed.kind(close) {
+            SyntaxKind::OpenBrace => {
+                depth += 1;
+                false
+            }
+            SyntaxKind::CloseBrace if depth == 0 => true,
+            SyntaxKind::CloseBrace => {
+                depth -= 1;
+                false
+            }
+            _ => false,
+        });
+        let close = close.unwrap_or(lexed.len());
+        groups.push(open + 1..close);
+        idx = (close + 1).min(lexed.len());
+    }
+    (groups, idx)
+}
+
+/// Number of columns declared by a column spec like `|l|c|p{3cm}|`.
+pub(super) fn spec_columns(spec: &str) -> usize {
+    let mut columns = 0;
+    let mut rest = spec;
+
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        match c {
+            'l' | 'c' | 'r' | 'X' | 'S' => columns += 1,
+            'p' | 'm' | 'b' => {
+                columns += 1;
+                rest = group(rest).map_or(rest, |(_, rest)| rest);
+            }
+            '@' | '!' | '>' | '<' => rest = group(rest).map_or(rest, |(_, rest)| rest),
+            // `*{3}{l}` repeats the spec
+            '*' => {
+                let Some((count, after)) = group(rest) else { continue };
+                let Some((repeated, after)) = group(after) else { continue };
+                columns += count.trim().parse::<usize>().unwrap_or(1) * spec_columns(repeated);
+                rest = after;
+            }
+            _ => {}
+        }
+    }
+
+    columns
+}
+
+/// Splits the brace group at the start of `text` into its content and the remaining text.
+fn group(text: &str) -> Option<(&str, &str)> {
+    let rest = text.trim_start().strip_prefix('{')?;
+    let mut depth = 0usize;
+    for (idx, c) in rest.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some((&rest[..idx], &rest[idx + 1..])),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Checks the number of cells of every row of the alignment environments of `document`.
+pub(super) fn check(document: &Document<'_, '_>) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for environment in document.descendants().filter_map(Environment::cast) {
+        let Some(alignment) = alignment(environment.name()) else {
+            continue;
+        };
+        let rows: Vec<_> = environment.rows().filter(|row| !row.is_blank()).collect();
+
+        let (expected, source) = match (environment.columns(), rows.first()) {
+            (Some(columns), _) => (columns, format!("'{}' declares", environment.name())),
+            (None, Some(first)) if alignment.uniform => {
+                (first.column_count(), "the first row has".to_string())
+            }
+            _ => continue,
+        };
+
+        // the columns of a row containing a conditional depend on the branch which is taken
+        let rows = rows.into_iter().filter(|row| {
+            !row.syntax().descendants().any(|node| node.kind() == NodeKind::Conditional)
+        });
+        for row in rows {
+            let found = row.column_count();
+            // extra columns are an error in TeX, uneven rows of a matrix are only suspicious
+            let severity = match alignment.uniform {
+                true if found != expected => Severity::Warning,
+                false if found > expected => Severity::Error,
+                _ => continue,
+            };
+            let message = format!("row has {found} columns but {source} {expected}");
+            let range = row.syntax().text_range();
+            diagnostics.push(Diagnostic::new(COLUMN_COUNT, severity, range, message));
+        }
+    }
+
+    diagnostics
+}
+
+/// Number of columns spanned by `cell`, `\multicolumn{n}` spans `n` columns.
+pub(super) fn cell_columns(cell: &Cell<'_, '_>) -> usize {
+    let node = cell.syntax();
+    let lexed = node.document.lexed;
+    let content = node.data().content.clone();
+
+    // the cell may start with rules like `\hline`
+    let multicolumn = content
+        .clone()
+        .find(|&idx| lexed.kind(idx) == SyntaxKind::Command && lexed.text(idx) == "\\multicolumn");
+    let Some(multicolumn) = multicolumn else {
+        return 1;
+    };
+
+    let (groups, _) = arguments(lexed, multicolumn + 1, 1);
+    let span = &groups[0];
+    lexed.as_str()[lexed.text_start(span.start)..lexed.text_start(span.end)]
+        .trim()
+        .parse()
+        .unwrap_or(1)
+}
+
+impl Row<'_, '_> {
+    /// Rows consisting only of trivia and commands like `\hline`, e.g. after the last `\\`.
+    pub(super) fn is_blank(&self) -> bool {
+        let lexed = self.syntax().document.lexed;
+        self.syntax().children().count() <= 1
+            && self.syntax().token_range().all(|idx| {
+                let kind = lexed.kind(idx);
+                kind.is_resolver_trivia() || kind == SyntaxKind::Command
+            })
+    }
+}
+
+impl NodeKind {
+    /// Returns `true` for the rows and cells of alignment environments.
+    pub(super) fn is_alignment(&self) -> bool {
+        matches!(self, NodeKind::Row | NodeKind::Cell)
+    }
+
+    /// Returns `true` for conditionals and their branches.
+    pub(super) fn is_conditional(&self) -> bool {
+        matches!(self, NodeKind::Conditional | NodeKind::Branch)
+    }
+
+    /// Returns `true` for invocations and their arguments.
+    pub(super) fn is_invocation(&self) -> bool {
+        matches!(self, NodeKind::Invocation | NodeKind::Argument)
+    }
+
+    /// Returns `true` for the preamble and the body of a document.
+    pub(super) fn is_structure(&self) -> bool {
+        matches!(self, NodeKind::Preamble | NodeKind::DocumentBody)
+    }
+}
@@ -0,0 +1,132 @@
+use super::{AstNode, Document, Environment, NodeKind, SyntaxNode};
+use crate::{
+    diagnostics::{Diagnostic, Severity},
+    SyntaxKind,
+};
+
+/// Rule id of the diagnostic reported for text in a list before the first `\item`.
+pub const MISSING_ITEM: &str = "missing-item";
+
+/// Rule id of the diagnostic reported for paragraph breaks where they are not allowed.
+pub const PARAGRAPH_BREAK: &str = "paragraph-break";
+
+/// Restrictions on the content of an environment.
+///
+/// Only the direct content is checked, nested environments have their own content model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentModel {
+    /// Text has to be preceded by an `\item`
+    pub requires_item: bool,
+    /// Blank lines are allowed outside of brace groups
+    pub allows_paragraphs: bool,
+    /// The content is typeset in math mode
+    pub math: bool,
+}
+
+const LIST: ContentModel =
+    ContentModel { requires_item: true, allows_paragraphs: true, math: false };
+const TABULAR: ContentModel =
+    ContentModel { allows_paragraphs: false, requires_item: false, ..LIST };
+const ARRAY: ContentModel = ContentModel { math: true, ..TABULAR };
+pub(super) const MATH: ContentModel = ARRAY;
+
+/// Content models of the restricted standard environments. Math blocks use the model of `math`.
+const CONTENT_MODELS: &[(&str, ContentModel)] = &[
+    ("itemize", LIST),
+    ("enumerate", LIST),
+    ("description", LIST),
+    ("tabular", TABULAR),
+    ("tabular*", TABULAR),
+    ("tabularx", TABULAR),
+    ("array", ARRAY),
+    ("math", MATH),
+    ("displaymath", MATH),
+    ("equation", MATH),
+    ("equation*", MATH),
+    ("align", MATH),
+    ("align*", MATH),
+    ("alignat", MATH),
+    ("alignat*", MATH),
+    ("flalign", MATH),
+    ("flalign*", MATH),
+    ("gather", MATH),
+    ("gather*", MATH),
+    ("multline", MATH),
+    ("multline*", MATH),
+];
+
+/// Returns the content model of the environment `name`, `None` if its content is unrestricted.
+pub fn content_model(name: &str) -> Option<&'static ContentModel> {
+    CONTENT_MODELS.iter().find(|(environment, _)| *environment == name).map(|(_, model)| model)
+}
+
+/// Checks every node of `document` against its content model.
+pub(super) fn check(document: &Document<'_, '_>) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for node in document.descendants() {
+        let (name, model) = match node.kind() {
+            NodeKind::MathBlock => ("math", &MATH),
+            NodeKind::Environment => {
+                let name = Environment::cast(node).unwrap().name();
+                match content_model(name) {
+                    Some(model) => (name, model),
+                    None => continue,
+                }
+            }
+            _ => continue,
+        };
+        check_node(node, name, model, &mut diagnostics);
+    }
+
+    diagnostics
+}
+
+fn check_node(
+    node: SyntaxNode<'_, '_>,
+    name: &str,
+    model: &ContentModel,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let lexed = node.document.lexed;
+    let mut children = node.content_children().into_iter().peekable();
+    let mut missing_item = model.requires_item;
+    let mut depth = 0usize;
+
+    let content = node.data().content.clone();
+    let mut idx = content.start;
+    while idx < content.end {
+        if let Some(child) = children.next_if(|child| child.token_range().start == idx) {
+            if missing_item && child.kind() == NodeKind::MathBlock {
+                diagnostics.push(missing_item_diagnostic(child.text_range(), name));
+                missing_item = false;
+            }
+            idx = child.token_range().end.max(idx + 1);
+            continue;
+        }
+
+        match lexed.kind(idx) {
+            SyntaxKind::Break if depth == 0 && !model.allows_paragraphs => {
+                let message = format!("paragraph breaks are not allowed inside of '{name}'");
+                let range = lexed.text_range(idx);
+                diagnostics.push(Diagnostic::new(PARAGRAPH_BREAK, Severity::Error, range, message));
+            }
+            SyntaxKind::OpenBrace | SyntaxKind::OpenBracket => depth += 1,
+            SyntaxKind::CloseBrace | SyntaxKind::CloseBracket => depth = depth.saturating_sub(1),
+            SyntaxKind::Command if lexed.text(idx) == "\\item" => missing_item = false,
+            // commands and their arguments may configure the list before the first item
+            SyntaxKind::Command => {}
+            kind if missing_item && depth == 0 && !kind.is_resolver_trivia() => {
+                diagnostics.push(missing_item_diagnostic(lexed.text_range(idx), name));
+                missing_item = false;
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+}
+
+fn missing_item_diagnostic(range: std::ops::Range<usize>, name: &str) -> Diagnostic {
+    let message = format!("text inside of '{name}' has to follow an \\item");
+    Diagnostic::new(MISSING_ITEM, Severity::Error, range, message)
+}
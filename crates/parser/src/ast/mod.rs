@@ -0,0 +1,738 @@
+//! Typed view of the structure of a document.
+//!
+//! A [`Document`] groups the tokens of a [`LexedStr`] into a tree of definitions, environments
+//! and math blocks. The untyped [`SyntaxNode`]s of the tree are cast into typed wrappers like
+//! [`Environment`], whose accessors spare consumers from matching on raw [`SyntaxKind`]s:
+//!
+//! ```
+//! use parser::{ast::{AstNode, Document, Environment, MathBlock}, LexedStr};
+//!
+//! let lexed = LexedStr::new(r"\begin{proof} $x^2$ \end{proof}");
+//! let document = Document::new(&lexed);
+//!
+//! let proof = document.descendants().find_map(Environment::cast).unwrap();
+//! assert_eq!(proof.name(), "proof");
+//! assert_eq!(proof.math_blocks().next().unwrap().body(), "x^2");
+//! ```
+
+mod alignment;
+mod content_model;
+mod nodes;
+mod overlay;
+mod packages;
+mod recovery;
+mod sink;
+mod structure;
+
+pub use alignment::{alignment, Alignment, COLUMN_COUNT};
+pub use content_model::{content_model, ContentModel, MISSING_ITEM, PARAGRAPH_BREAK};
+pub use nodes::{
+    Argument, Branch, Cell, CommandDefinition, Conditional, DocumentBody, Environment,
+    EnvironmentDefinition, Invocation, MathBlock, OverlaySpec, Preamble, Row,
+};
+pub(crate) use overlay::overlay_spec;
+pub use overlay::{SlideRange, OVERLAY_COMMANDS};
+pub use packages::{MissingPackage, MISSING_PACKAGE};
+pub use recovery::{UNBALANCED_BRACE, UNCLOSED_AT_EOF};
+pub use sink::TreeSink;
+pub use structure::{missing_document, CONTENT_AFTER_DOCUMENT, MISSING_DOCUMENT};
+
+use std::{collections::BTreeMap, fmt, ops::Range};
+
+use crate::{
+    diagnostics::Diagnostic,
+    expansion::{
+        self, ConditionalBlock, ConditionalFlags, ExpansionStoreItem, VERBATIM_ENVIRONMENTS,
+    },
+    LexedStr, SyntaxKind,
+};
+
+/// The kind of a [`SyntaxNode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    /// `\newcommand` or `\def`
+    CommandDefinition,
+    /// `\newenvironment`
+    EnvironmentDefinition,
+    /// `\begin{name} ... \end{name}`
+    Environment,
+    /// `$...$`, `$$...$$`, `\(...\)` or `\[...\]`
+    MathBlock,
+    /// A row of an alignment environment, terminated by `\\`
+    Row,
+    /// A cell of a [`NodeKind::Row`], terminated by `&`
+    Cell,
+    /// `<...>` after a beamer command or `\begin{name}`, e.g. `\item<2->`
+    OverlaySpec,
+    /// A TeX conditional from `\if...` to `\fi`, e.g. `\ifdefined\x ... \else ... \fi`
+    Conditional,
+    /// A branch of a [`NodeKind::Conditional`], preceded by the test, an `\or` or the `\else`
+    Branch,
+    /// A `}` without a matching `{`
+    Error,
+    /// Everything in front of the `document` environment
+    Preamble,
+    /// The `document` environment
+    DocumentBody,
+    /// A call of a command defined with arguments or starred variants earlier in the source,
+    /// e.g. `\pair{a}{b}`
+    Invocation,
+    /// An argument of an [`NodeKind::Invocation`]: an optional one in brackets, a brace group or
+    /// a single token
+    Argument,
+}
+
+/// A typed wrapper around a [`SyntaxNode`] of a specific [`NodeKind`].
+pub trait AstNode<'doc, 'source>: Sized {
+    /// Returns `true` if nodes of `kind` can be cast to `Self`.
+    fn can_cast(kind: NodeKind) -> bool;
+
+    /// Casts `node` to `Self` if it has a matching kind.
+    fn cast(node: SyntaxNode<'doc, 'source>) -> Option<Self>;
+
+    /// The underlying untyped node.
+    fn syntax(&self) -> SyntaxNode<'doc, 'source>;
+}
+
+/// The tree of a single document.
+///
+/// Definitions which cannot be resolved are not part of the tree, see
+/// [`expansion::resolve`] for their errors. The bodies of definitions and the content of
+/// verbatim environments are not searched for nested nodes.
+///
+/// The content of alignment environments like `tabular` is split into rows and cells. While
+/// building the tree, environments are checked against their [`ContentModel`] and rows against
+/// the columns of their [`Alignment`].
+///
+/// Conditionals are split into their branches, see [`expansion::conditionals`] for the commands
+/// which are recognized. An `&` inside of a conditional does not end a cell.
+///
+/// Calls of commands defined with arguments or starred variants earlier in the source become
+/// [`Invocation`]s, whose arguments are collected like by the expansion. Nodes opened inside of an argument end with it.
+///
+/// Nodes which are still open at the end of a truncated source are closed there and flagged as
+/// [recovered](SyntaxNode::is_recovered), a single [`UNCLOSED_AT_EOF`] diagnostic lists them
+/// together with the unclosed braces. Braces are matched within their environment or math
+/// block: a `}` without a `{` becomes a [`NodeKind::Error`] node and braces still open at the end
+/// of the environment are closed there, both reported as [`UNBALANCED_BRACE`].
+///
+/// The top level is split at the first top level `document` environment into a [`Preamble`]
+/// and a [`DocumentBody`] wrapping the environment, text after it is reported as
+/// [`CONTENT_AFTER_DOCUMENT`]. See [`missing_document`] for documents without one.
+#[derive(Debug)]
+pub struct Document<'lexed, 'source> {
+    lexed: &'lexed LexedStr<'source>,
+    nodes: Vec<NodeData<'source>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug)]
+struct NodeData<'source> {
+    kind: NodeKind,
+    /// Token range of the whole node
+    range: Range<usize>,
+    /// Token range in between the delimiters of the node
+    content: Range<usize>,
+    closed: bool,
+    /// The node was still open at the end of the source
+    recovered: bool,
+    /// Brace depth at the start of the node
+    depth: usize,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    definition: Option<ExpansionStoreItem<'source>>,
+}
+
+impl<'lexed, 'source> Document<'lexed, 'source> {
+    /// Builds the tree of `lexed`.
+    pub fn new(lexed: &'lexed LexedStr<'source>) -> Document<'lexed, 'source> {
+        let conditionals = expansion::conditionals(lexed, &ConditionalFlags::new());
+        let mut markers = BTreeMap::new();
+        for (block, conditional) in conditionals.iter().enumerate() {
+            let fi = conditional.closed.then(|| conditional.range.end - 1);
+            let separators = conditional.ors.iter().chain(&conditional.else_branch).chain(&fi);
+            for &idx in std::iter::once(&conditional.range.start).chain(separators) {
+                markers.insert(idx, block);
+            }
+        }
+        Builder {
+            lexed,
+            nodes: Vec::new(),
+            open: Vec::new(),
+            braces: Vec::new(),
+            conditionals,
+            markers,
+            diagnostics: Vec::new(),
+            commands: BTreeMap::new(),
+            limit: lexed.len(),
+        }
+        .build()
+    }
+
+    /// The tokens the tree was built from.
+    pub fn lexed(&self) -> &'lexed LexedStr<'source> {
+        self.lexed
+    }
+
+    /// Violations of the content models of the environments.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// The top level nodes.
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode<'_, 'source>> {
+        self.descendants().filter(|node| node.data().parent.is_none())
+    }
+
+    /// All nodes in the order they start in the source.
+    pub fn descendants(&self) -> impl Iterator<Item = SyntaxNode<'_, 'source>> {
+        (0..self.nodes.len()).map(move |idx| SyntaxNode { document: self, idx })
+    }
+}
+
+/// A node of a [`Document`].
+#[derive(Clone, Copy)]
+pub struct SyntaxNode<'doc, 'source> {
+    document: &'doc Document<'doc, 'source>,
+    idx: usize,
+}
+
+impl<'doc, 'source> SyntaxNode<'doc, 'source> {
+    fn data(&self) -> &'doc NodeData<'source> {
+        &self.document.nodes[self.idx]
+    }
+
+    fn node(&self, idx: usize) -> SyntaxNode<'doc, 'source> {
+        SyntaxNode { document: self.document, idx }
+    }
+
+    fn slice(&self, range: &Range<usize>) -> &'source str {
+        let lexed = self.document.lexed;
+        &lexed.as_str()[lexed.text_start(range.start)..lexed.text_start(range.end)]
+    }
+
+    /// The kind of the node.
+    pub fn kind(&self) -> NodeKind {
+        self.data().kind
+    }
+
+    /// Token range of the node in the [`LexedStr`] of the document.
+    pub fn token_range(&self) -> Range<usize> {
+        self.data().range.clone()
+    }
+
+    /// Token range in between the delimiters of the node, e.g. the body of an environment.
+    pub fn content_token_range(&self) -> Range<usize> {
+        self.data().content.clone()
+    }
+
+    /// Byte range of the node in the source text.
+    pub fn text_range(&self) -> Range<usize> {
+        let range = &self.data().range;
+        let lexed = self.document.lexed;
+        lexed.text_start(range.start)..lexed.text_start(range.end)
+    }
+
+    /// Source text of the whole node.
+    pub fn text(&self) -> &'source str {
+        self.slice(&self.data().range)
+    }
+
+    /// Source text in between the delimiters of the node, e.g. the body of an environment.
+    pub fn content(&self) -> &'source str {
+        self.slice(&self.data().content)
+    }
+
+    /// Returns `false` if the end of the node is missing. An unclosed node extends to the end of
+    /// its parent.
+    pub fn is_closed(&self) -> bool {
+        self.data().closed
+    }
+
+    /// Returns `true` if the node was still open at the end of the source, e.g. of a truncated
+    /// file. Such a node is not [closed](Self::is_closed) and extends to the end of the source.
+    pub fn is_recovered(&self) -> bool {
+        self.data().recovered
+    }
+
+    /// The node containing this node.
+    pub fn parent(&self) -> Option<SyntaxNode<'doc, 'source>> {
+        self.data().parent.map(|idx| self.node(idx))
+    }
+
+    /// Children with the rows and cells of alignments, the branches of conditionals and the
+    /// arguments of invocations replaced by their content.
+    fn content_children(&self) -> Vec<SyntaxNode<'doc, 'source>> {
+        let mut children = Vec::new();
+        for child in self.children() {
+            let kind = child.kind();
+            match kind.is_alignment() || kind.is_conditional() || kind.is_invocation() {
+                true => children.extend(child.content_children()),
+                false => children.push(child),
+            }
+        }
+        children
+    }
+
+    /// The nodes directly contained in this node.
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode<'doc, 'source>> + 'doc {
+        let document = self.document;
+        self.data().children.iter().map(move |&idx| SyntaxNode { document, idx })
+    }
+
+    /// All nodes contained in this node in the order they start in the source.
+    pub fn descendants(&self) -> impl Iterator<Item = SyntaxNode<'doc, 'source>> + 'doc {
+        // nodes are stored in source order, so the descendants directly follow the node
+        let document = self.document;
+        let end = self.data().range.end;
+        (self.idx + 1..document.nodes.len())
+            .take_while(move |&idx| document.nodes[idx].range.start < end)
+            .map(move |idx| SyntaxNode { document, idx })
+    }
+}
+
+impl fmt::Debug for SyntaxNode<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}@{:?}", self.kind(), self.text_range())
+    }
+}
+
+impl PartialEq for SyntaxNode<'_, '_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.document, other.document) && self.idx == other.idx
+    }
+}
+
+impl Eq for SyntaxNode<'_, '_> {}
+
+struct Builder<'lexed, 'source> {
+    lexed: &'lexed LexedStr<'source>,
+    nodes: Vec<NodeData<'source>>,
+    /// Environments, math blocks, rows and cells which are not closed yet
+    open: Vec<usize>,
+    /// Token indices of the open braces
+    braces: Vec<usize>,
+    conditionals: Vec<ConditionalBlock>,
+    /// The conditional of every conditional command, `\else`, `\or` and `\fi`, by token index
+    markers: BTreeMap<usize, usize>,
+    /// Unbalanced braces found while building
+    diagnostics: Vec<Diagnostic>,
+    /// The commands defined with arguments so far by name
+    commands: BTreeMap<&'source str, ExpansionStoreItem<'source>>,
+    /// End of the innermost argument being built, invocations must not extend beyond it
+    limit: usize,
+}
+
+impl<'lexed, 'source> Builder<'lexed, 'source> {
+    fn build(mut self) -> Document<'lexed, 'source> {
+        let lexed = self.lexed;
+        let mut idx = 0;
+
+        while idx < lexed.len() {
+            idx = self.step(idx);
+        }
+
+        for node in std::mem::take(&mut self.open) {
+            let node = &mut self.nodes[node];
+            node.range.end = lexed.len();
+            node.content.end = lexed.len();
+            node.recovered = true;
+        }
+
+        let mut document = Document { lexed, nodes: self.nodes, diagnostics: self.diagnostics };
+        let trailing = structure::split(&mut document);
+        document.diagnostics.extend(trailing);
+        document.diagnostics.extend(content_model::check(&document));
+        document.diagnostics.extend(alignment::check(&document));
+        document.diagnostics.extend(recovery::check(&document, &self.braces));
+        let missing = document.missing_packages();
+        document.diagnostics.extend(missing.iter().map(MissingPackage::to_diagnostic));
+        document
+    }
+
+    /// Handles the token at `idx` and returns the index of the next unhandled token.
+    fn step(&mut self, idx: usize) -> usize {
+        let lexed = self.lexed;
+        match lexed.kind(idx) {
+            _ if self.closes_math(idx) => {
+                let math = self.open.pop().unwrap();
+                self.close_braces(math, idx..idx + 1);
+                self.close(math, idx, idx + 1);
+                idx + 1
+            }
+            kind if kind.is_definition_keyword() => self.definition(idx),
+            SyntaxKind::Command if self.markers.contains_key(&idx) => self.conditional(idx),
+            SyntaxKind::BeginGroup => self.begin(idx),
+            SyntaxKind::EndGroup => self.end(idx),
+            SyntaxKind::MathDelimiter => self.math(idx),
+            SyntaxKind::Command if matches!(lexed.text(idx), "\\(" | "\\[") => self.math(idx),
+            SyntaxKind::Command if OVERLAY_COMMANDS.contains(&lexed.text(idx)) => {
+                self.overlay(idx + 1)
+            }
+            SyntaxKind::Ampersand if self.in_cell() => self.next_cell(idx),
+            SyntaxKind::Command
+                if self.in_cell() && matches!(lexed.text(idx), "\\\\" | "\\tabularnewline") =>
+            {
+                self.next_row(idx)
+            }
+            SyntaxKind::Command if self.commands.contains_key(lexed.text(idx)) => {
+                self.invocation(idx)
+            }
+            SyntaxKind::OpenBrace => {
+                self.braces.push(idx);
+                idx + 1
+            }
+            SyntaxKind::CloseBrace => self.close_brace(idx),
+            _ => idx + 1,
+        }
+    }
+
+    fn definition(&mut self, idx: usize) -> usize {
+        let Ok(item) = expansion::resolve_at(self.lexed, idx) else {
+            return idx + 1;
+        };
+
+        let kind = match item.kind {
+            expansion::DefinitionKind::Environment => NodeKind::EnvironmentDefinition,
+            _ => NodeKind::CommandDefinition,
+        };
+        let end = item.range.end;
+        if kind == NodeKind::CommandDefinition {
+            // a redefinition without arguments or starred variant ends the invocations of the
+            // command
+            let takes = item.args.count > 0 || item.pattern.is_some();
+            match takes || item.star_variants().is_some() {
+                true => self.commands.insert(item.name, item.clone()),
+                false => self.commands.remove(item.name),
+            };
+        }
+        let node = self.push(kind, idx, idx);
+        self.nodes[node].definition = Some(item);
+        self.close(node, end, end);
+        end
+    }
+
+    /// A call of a command defined with arguments. Nodes which are still open at the end of an
+    /// argument end with it, an argument ended by an enclosing node ends the invocation as well.
+    /// A call inside of an argument whose own arguments extend beyond it is no invocation, e.g.
+    /// the `\pair` taken as argument by `\box\pair{a}{b}`.
+    fn invocation(&mut self, idx: usize) -> usize {
+        let item = self.commands[self.lexed.text(idx)].clone();
+        let arguments = expansion::arguments_at(&item, self.lexed, idx + 1);
+        if arguments.end > self.limit {
+            return idx + 1;
+        }
+        let invocation = self.push(NodeKind::Invocation, idx, idx + 1);
+        self.nodes[invocation].definition = Some(item);
+        self.open.push(invocation);
+
+        let mut end = arguments.end;
+        for arg in &arguments.args {
+            let node = self.push(NodeKind::Argument, arg.range.start, arg.content.start);
+            self.open.push(node);
+            let limit = std::mem::replace(&mut self.limit, arg.content.end);
+            let mut pos = arg.content.start;
+            while pos < arg.content.end {
+                pos = self.step(pos);
+            }
+            self.limit = limit;
+            end = end.max(pos);
+            let Some(level) = self.open.iter().rposition(|&open| open == node) else {
+                return end;
+            };
+            for inner in self.open.split_off(level + 1) {
+                self.nodes[inner].range.end = arg.content.end;
+                self.nodes[inner].content.end = arg.content.end;
+            }
+            self.open.pop();
+            self.close(node, arg.content.end, arg.range.end);
+        }
+
+        // the star selecting the starred variant follows the content
+        self.open.pop();
+        let content_end = arguments.end - usize::from(arguments.starred);
+        self.close(invocation, content_end, arguments.end);
+        self.nodes[invocation].closed = arguments.error.is_none();
+        end
+    }
+
+    /// A conditional command, `\else`, `\or` or `\fi`. Nodes which are still open at the end of
+    /// a branch end with it.
+    fn conditional(&mut self, idx: usize) -> usize {
+        let block = &self.conditionals[self.markers[&idx]];
+        let (start, test_end) = (block.range.start, block.test.end);
+        if idx == start {
+            let node = self.push(NodeKind::Conditional, idx, test_end);
+            self.open.push(node);
+            let branch = self.push(NodeKind::Branch, test_end, test_end);
+            self.open.push(branch);
+            return test_end;
+        }
+
+        let Some(pos) = self.open.iter().rposition(|&node| {
+            self.nodes[node].kind == NodeKind::Conditional && self.nodes[node].range.start == start
+        }) else {
+            return idx + 1;
+        };
+        let conditional = self.open[pos];
+        for node in self.open.split_off(pos + 1) {
+            match self.nodes[node].parent == Some(conditional) {
+                true => self.close(node, idx, idx),
+                false => {
+                    self.nodes[node].range.end = idx;
+                    self.nodes[node].content.end = idx;
+                }
+            }
+        }
+
+        match self.lexed.text(idx) {
+            "\\fi" => {
+                self.close(conditional, idx, idx + 1);
+                self.open.pop();
+            }
+            _ => {
+                let branch = self.push(NodeKind::Branch, idx, idx + 1);
+                self.open.push(branch);
+            }
+        }
+        idx + 1
+    }
+
+    /// `\begin{name}`, the content of verbatim environments is skipped.
+    fn begin(&mut self, idx: usize) -> usize {
+        let Some((name, start)) = environment_name(self.lexed, idx + 1) else {
+            return idx + 1;
+        };
+
+        let node = self.push(NodeKind::Environment, idx, start);
+        if let Some(alignment) = alignment(name) {
+            self.open.push(node);
+            let (_, rows) = alignment::arguments(self.lexed, start, alignment.args);
+            self.open_row(rows);
+            return rows;
+        }
+        if !VERBATIM_ENVIRONMENTS.contains(&name) {
+            self.open.push(node);
+            return self.overlay(start);
+        }
+
+        let end = (start..self.lexed.len()).find_map(|end| {
+            let (end_name, after) = match self.lexed.kind(end) {
+                SyntaxKind::EndGroup => environment_name(self.lexed, end + 1)?,
+                _ => return None,
+            };
+            (end_name == name).then_some((end, after))
+        });
+        match end {
+            Some((end, after)) => {
+                self.close(node, end, after);
+                after
+            }
+            None => {
+                self.open.push(node);
+                self.lexed.len()
+            }
+        }
+    }
+
+    /// `\end{name}` closes the innermost open environment `name` and every node opened after it.
+    fn end(&mut self, idx: usize) -> usize {
+        let Some((name, after)) = environment_name(self.lexed, idx + 1) else {
+            return idx + 1;
+        };
+        let Some(pos) = self.open.iter().rposition(|&node| {
+            self.nodes[node].kind == NodeKind::Environment
+                && environment_name(self.lexed, self.nodes[node].range.start + 1)
+                    .is_some_and(|(open, _)| open == name)
+        }) else {
+            return idx + 1;
+        };
+
+        let environment = self.open[pos];
+        for node in self.open.split_off(pos + 1) {
+            // the last row of an alignment ends with the environment
+            let row = match self.nodes[node].kind {
+                NodeKind::Row => Some(node),
+                NodeKind::Cell => self.nodes[node].parent,
+                _ => None,
+            };
+            match row.and_then(|row| self.nodes[row].parent) == Some(environment) {
+                true => self.close(node, idx, idx),
+                false => {
+                    self.nodes[node].range.end = idx;
+                    self.nodes[node].content.end = idx;
+                }
+            }
+        }
+        self.remove_trailing_row(environment);
+        self.close_braces(environment, idx..after);
+        self.close(environment, idx, after);
+        self.open.pop();
+        after
+    }
+
+    /// A `}` closes the innermost brace opened in the current environment or math block. Without
+    /// one it becomes an error node.
+    fn close_brace(&mut self, idx: usize) -> usize {
+        let scope = self.open.iter().rev().find(|&&node| {
+            matches!(self.nodes[node].kind, NodeKind::Environment | NodeKind::MathBlock)
+        });
+        if self.braces.len() > scope.map_or(0, |&node| self.nodes[node].depth) {
+            self.braces.pop();
+            return idx + 1;
+        }
+
+        let node = self.push(NodeKind::Error, idx, idx);
+        self.close(node, idx, idx + 1);
+        self.diagnostics.push(recovery::unmatched_brace(self.lexed, idx));
+        idx + 1
+    }
+
+    /// Closes the braces opened inside of `node`, which ends with the tokens `end`.
+    fn close_braces(&mut self, node: usize, end: Range<usize>) {
+        let depth = self.nodes[node].depth;
+        if self.braces.len() > depth {
+            let braces = self.braces.split_off(depth);
+            self.diagnostics.push(recovery::unclosed_braces(self.lexed, &braces, end));
+        }
+    }
+
+    fn in_cell(&self) -> bool {
+        self.open.last().is_some_and(|&node| {
+            self.nodes[node].kind == NodeKind::Cell && self.nodes[node].depth == self.braces.len()
+        })
+    }
+
+    fn open_row(&mut self, idx: usize) {
+        let row = self.push(NodeKind::Row, idx, idx);
+        self.open.push(row);
+        let cell = self.push(NodeKind::Cell, idx, idx);
+        self.open.push(cell);
+    }
+
+    /// `&` ends the current cell.
+    fn next_cell(&mut self, idx: usize) -> usize {
+        let cell = self.open.pop().unwrap();
+        self.close(cell, idx, idx);
+        let cell = self.push(NodeKind::Cell, idx + 1, idx + 1);
+        self.open.push(cell);
+        idx + 1
+    }
+
+    /// `\\` ends the current row, including its optional argument, e.g. `\\[2pt]`. A `[` on the
+    /// next line is left to the next row.
+    fn next_row(&mut self, idx: usize) -> usize {
+        let mut after = idx + 1;
+        let next =
+            (after..self.lexed.len()).find(|&next| self.lexed.kind(next) != SyntaxKind::Whitespace);
+        if let Some(open) = next.filter(|&open| self.lexed.kind(open) == SyntaxKind::OpenBracket) {
+            if let Some(close) = (open..self.lexed.len())
+                .find(|&close| self.lexed.kind(close) == SyntaxKind::CloseBracket)
+            {
+                after = close + 1;
+            }
+        }
+
+        let cell = self.open.pop().unwrap();
+        self.close(cell, idx, idx);
+        let row = self.open.pop().unwrap();
+        self.close(row, idx, after);
+        self.open_row(after);
+        after
+    }
+
+    /// Drops the empty row following the final `\\` of `environment`.
+    fn remove_trailing_row(&mut self, environment: usize) {
+        let rows = &self.nodes[environment].children;
+        let [.., _, row] = rows[..] else {
+            return;
+        };
+        let [cell] = self.nodes[row].children[..] else {
+            return;
+        };
+        let blank = self.nodes[cell].children.is_empty()
+            && self.nodes[cell].range.clone().all(|idx| self.lexed.kind(idx).is_resolver_trivia());
+        if blank && cell + 1 == self.nodes.len() {
+            self.nodes.truncate(row);
+            self.nodes[environment].children.pop();
+        }
+    }
+
+    /// An overlay specification like `<2->` at `idx`, which is not recognized inside of math.
+    fn overlay(&mut self, idx: usize) -> usize {
+        let in_math = self.open.iter().any(|&node| self.nodes[node].kind == NodeKind::MathBlock);
+        let Some(range) = overlay::overlay_spec(self.lexed, idx).filter(|_| !in_math) else {
+            return idx;
+        };
+        let node = self.push(NodeKind::OverlaySpec, range.start, range.start + 1);
+        self.close(node, range.end - 1, range.end);
+        range.end
+    }
+
+    fn math(&mut self, idx: usize) -> usize {
+        let node = self.push(NodeKind::MathBlock, idx, idx + 1);
+        self.open.push(node);
+        idx + 1
+    }
+
+    fn closes_math(&self, idx: usize) -> bool {
+        let Some(&node) = self.open.last() else {
+            return false;
+        };
+        if self.nodes[node].kind != NodeKind::MathBlock {
+            return false;
+        }
+
+        let (open, close) = (self.lexed.text(self.nodes[node].range.start), self.lexed.text(idx));
+        match open {
+            "\\(" => close == "\\)",
+            "\\[" => close == "\\]",
+            _ => self.lexed.kind(idx) == SyntaxKind::MathDelimiter && close == open,
+        }
+    }
+
+    fn push(&mut self, kind: NodeKind, start: usize, content: usize) -> usize {
+        let parent = self.open.last().copied();
+        let node = self.nodes.len();
+        self.nodes.push(NodeData {
+            kind,
+            range: start..start,
+            content: content..content,
+            closed: false,
+            recovered: false,
+            depth: self.braces.len(),
+            parent,
+            children: Vec::new(),
+            definition: None,
+        });
+        if let Some(parent) = parent {
+            self.nodes[parent].children.push(node);
+        }
+        node
+    }
+
+    /// Closes `node` with the end delimiter spanning the tokens `end..after`.
+    fn close(&mut self, node: usize, end: usize, after: usize) {
+        let node = &mut self.nodes[node];
+        node.content.end = end;
+        node.range.end = after;
+        node.closed = true;
+    }
+}
+
+/// The trimmed name in `{name}` following the token at `idx` and the index after the group.
+pub(crate) fn environment_name<'source>(
+    lexed: &LexedStr<'source>,
+    idx: usize,
+) -> Option<(&'source str, usize)> {
+    let open = lexed.next_non_trivia(idx)?;
+    if lexed.kind(open) != SyntaxKind::OpenBrace {
+        return None;
+    }
+    let close = (open..lexed.len()).find(|&close| lexed.kind(close) == SyntaxKind::CloseBrace)?;
+    let name = lexed.as_str()[lexed.text_start(open + 1)..lexed.text_start(close)].trim();
+    Some((name, close + 1))
+}
+
+#[cfg(test)]
+mod tests;
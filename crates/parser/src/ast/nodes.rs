@@ -0,0 +1,359 @@
+use super::{alignment, AstNode, NodeKind, SyntaxNode};
+use crate::expansion::{DefinitionKind, ExpansionStoreItem};
+
+macro_rules! ast_node {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct $name<'doc, 'source>(SyntaxNode<'doc, 'source>);
+
+        impl<'doc, 'source> AstNode<'doc, 'source> for $name<'doc, 'source> {
+            fn can_cast(kind: NodeKind) -> bool {
+                kind == NodeKind::$name
+            }
+
+            fn cast(node: SyntaxNode<'doc, 'source>) -> Option<Self> {
+                Self::can_cast(node.kind()).then_some($name(node))
+            }
+
+            fn syntax(&self) -> SyntaxNode<'doc, 'source> {
+                self.0
+            }
+        }
+    };
+}
+
+ast_node! {
+    /// A command definition, e.g. `\newcommand{\R}{\mathbb{R}}` or `\def\pair(#1,#2){...}`.
+    CommandDefinition
+}
+
+ast_node! {
+    /// An environment definition, e.g. `\newenvironment{proof}{...}{...}`.
+    EnvironmentDefinition
+}
+
+ast_node! {
+    /// An environment, e.g. `\begin{proof} ... \end{proof}`.
+    Environment
+}
+
+ast_node! {
+    /// Inline or display math delimited by `$`, `$$`, `\(...\)` or `\[...\]`.
+    MathBlock
+}
+
+ast_node! {
+    /// A row of an alignment environment like `tabular`, terminated by `\\`.
+    Row
+}
+
+ast_node! {
+    /// A cell of a [`Row`], terminated by `&`.
+    Cell
+}
+
+ast_node! {
+    /// A TeX conditional, e.g. `\ifdefined\x ... \else ... \fi`.
+    Conditional
+}
+
+ast_node! {
+    /// A branch of a [`Conditional`].
+    Branch
+}
+
+ast_node! {
+    /// Everything in front of `\begin{document}`, e.g. the `\documentclass` and the packages.
+    Preamble
+}
+
+ast_node! {
+    /// The `document` environment, see [`Document::body`](super::Document::body).
+    DocumentBody
+}
+
+ast_node! {
+    /// A call of a command defined with arguments, e.g. `\pair[x]{a}{b}`.
+    Invocation
+}
+
+ast_node! {
+    /// An argument of an [`Invocation`], e.g. `[x]` or `{a}`.
+    Argument
+}
+
+ast_node! {
+    /// A beamer overlay specification, e.g. `<2->` of `\item<2->` or `\begin{block}<3>`.
+    OverlaySpec
+}
+
+impl<'doc, 'source> CommandDefinition<'doc, 'source> {
+    /// The resolved definition.
+    pub fn definition(&self) -> &'doc ExpansionStoreItem<'source> {
+        self.0.data().definition.as_ref().expect("definition nodes are always resolved")
+    }
+
+    /// `\newcommand` or `\def`.
+    pub fn definition_kind(&self) -> DefinitionKind {
+        self.definition().kind
+    }
+
+    /// Name of the command including the leading backslash.
+    pub fn name(&self) -> &'source str {
+        self.definition().name
+    }
+
+    /// Total number of arguments, including the optional one.
+    pub fn arg_count(&self) -> usize {
+        self.definition().args.count
+    }
+
+    /// Default value of the optional first argument.
+    pub fn default_arg(&self) -> Option<&'source str> {
+        self.definition().args.default
+    }
+
+    /// Replacement text without the surrounding braces.
+    pub fn body(&self) -> &'source str {
+        self.definition().body
+    }
+
+    /// Whether the starred form like `\newcommand*` was used.
+    pub fn is_starred(&self) -> bool {
+        self.definition().starred
+    }
+}
+
+impl<'doc, 'source> EnvironmentDefinition<'doc, 'source> {
+    /// The resolved definition.
+    pub fn definition(&self) -> &'doc ExpansionStoreItem<'source> {
+        self.0.data().definition.as_ref().expect("definition nodes are always resolved")
+    }
+
+    /// Name of the environment.
+    pub fn name(&self) -> &'source str {
+        self.definition().name
+    }
+
+    /// Total number of arguments, including the optional one.
+    pub fn arg_count(&self) -> usize {
+        self.definition().args.count
+    }
+
+    /// Default value of the optional first argument.
+    pub fn default_arg(&self) -> Option<&'source str> {
+        self.definition().args.default
+    }
+
+    /// Code inserted at `\begin`.
+    pub fn begin_body(&self) -> &'source str {
+        self.definition().body
+    }
+
+    /// Code inserted at `\end`.
+    pub fn end_body(&self) -> &'source str {
+        self.definition().end_body.unwrap_or_default()
+    }
+}
+
+impl<'doc, 'source> Environment<'doc, 'source> {
+    /// Name of the environment, e.g. `proof`.
+    pub fn name(&self) -> &'source str {
+        let start = self.0.token_range().start;
+        super::environment_name(self.0.document.lexed, start + 1).map_or("", |(name, _)| name)
+    }
+
+    /// Source text in between `\begin{name}` and `\end{name}`.
+    pub fn body(&self) -> &'source str {
+        self.0.content()
+    }
+
+    /// Returns `false` if `\end{name}` is missing.
+    pub fn is_closed(&self) -> bool {
+        self.0.is_closed()
+    }
+
+    /// Environments directly nested in this environment.
+    ///
+    /// Environments inside of the cells of an alignment are included.
+    pub fn environments(&self) -> impl Iterator<Item = Environment<'doc, 'source>> + 'doc {
+        self.0.content_children().into_iter().filter_map(Environment::cast)
+    }
+
+    /// Math blocks directly contained in this environment.
+    ///
+    /// Math blocks inside of the cells of an alignment are included.
+    pub fn math_blocks(&self) -> impl Iterator<Item = MathBlock<'doc, 'source>> + 'doc {
+        self.0.content_children().into_iter().filter_map(MathBlock::cast)
+    }
+
+    /// Rows of an alignment environment like `tabular`, empty for other environments.
+    pub fn rows(&self) -> impl Iterator<Item = Row<'doc, 'source>> + 'doc {
+        self.0.children().filter_map(Row::cast)
+    }
+
+    /// Number of columns declared by the column spec, e.g. 3 for `\begin{tabular}{l|cc}`.
+    pub fn columns(&self) -> Option<usize> {
+        let spec = alignment::alignment(self.name())?.spec?;
+        let lexed = self.0.document.lexed;
+        let (_, start) = super::environment_name(lexed, self.0.token_range().start + 1)?;
+
+        let (groups, _) = alignment::arguments(lexed, start, spec + 1);
+        let spec = &groups[spec];
+        let spec = &lexed.as_str()[lexed.text_start(spec.start)..lexed.text_start(spec.end)];
+        Some(alignment::spec_columns(spec))
+    }
+}
+
+impl<'doc, 'source> Row<'doc, 'source> {
+    /// The cells of the row.
+    pub fn cells(&self) -> impl Iterator<Item = Cell<'doc, 'source>> + 'doc {
+        self.0.children().filter_map(Cell::cast)
+    }
+
+    /// Number of columns of the row, a `\multicolumn` counts as all columns it spans.
+    pub fn column_count(&self) -> usize {
+        self.cells().map(|cell| cell.column_span()).sum()
+    }
+}
+
+impl<'doc, 'source> Cell<'doc, 'source> {
+    /// Source text of the cell without the separating `&` or `\\`.
+    pub fn body(&self) -> &'source str {
+        self.0.content()
+    }
+
+    /// Number of columns spanned by the cell, `\multicolumn{n}` spans `n` columns.
+    pub fn column_span(&self) -> usize {
+        alignment::cell_columns(self)
+    }
+}
+
+impl<'doc, 'source> MathBlock<'doc, 'source> {
+    /// The opening delimiter, e.g. `$` or `\[`.
+    pub fn delimiter(&self) -> &'source str {
+        self.0.document.lexed.text(self.0.token_range().start)
+    }
+
+    /// Returns `true` for display math delimited by `$$` or `\[...\]`.
+    pub fn is_display(&self) -> bool {
+        matches!(self.delimiter(), "$$" | "\\[")
+    }
+
+    /// Source text in between the delimiters.
+    pub fn body(&self) -> &'source str {
+        self.0.content()
+    }
+
+    /// Returns `false` if the closing delimiter is missing.
+    pub fn is_closed(&self) -> bool {
+        self.0.is_closed()
+    }
+}
+
+impl<'doc, 'source> Conditional<'doc, 'source> {
+    /// The conditional command, e.g. `\ifdefined` or `\ifdraft`.
+    pub fn name(&self) -> &'source str {
+        self.0.document.lexed.text(self.0.token_range().start)
+    }
+
+    /// Source text of the test, e.g. `\x` of `\ifdefined\x`. Empty for flags like `\ifdraft`.
+    pub fn test(&self) -> &'source str {
+        let lexed = self.0.document.lexed;
+        let (start, end) = (self.0.token_range().start + 1, self.0.content_token_range().start);
+        lexed.as_str()[lexed.text_start(start)..lexed.text_start(end)].trim()
+    }
+
+    /// The branches in source order, the last one follows the `\else` if there is one.
+    pub fn branches(&self) -> impl Iterator<Item = Branch<'doc, 'source>> + 'doc {
+        self.0.children().filter_map(Branch::cast)
+    }
+
+    /// Returns `false` if the `\fi` is missing.
+    pub fn is_closed(&self) -> bool {
+        self.0.is_closed()
+    }
+}
+
+impl<'doc, 'source> Branch<'doc, 'source> {
+    /// Source text of the branch without the preceding `\else` or `\or`.
+    pub fn body(&self) -> &'source str {
+        self.0.content()
+    }
+
+    /// Returns `true` for the branch following the `\else`.
+    pub fn is_else(&self) -> bool {
+        let range = self.0.token_range();
+        range.start < self.0.content_token_range().start
+            && self.0.document.lexed.text(range.start) == "\\else"
+    }
+}
+
+impl<'doc, 'source> Invocation<'doc, 'source> {
+    /// The definition of the called command.
+    pub fn definition(&self) -> &'doc ExpansionStoreItem<'source> {
+        self.0.data().definition.as_ref().expect("invocations are calls of a definition")
+    }
+
+    /// Name of the command including the leading backslash.
+    pub fn name(&self) -> &'source str {
+        self.definition().name
+    }
+
+    /// Whether the call is followed by a star selecting the starred variant of the command, see
+    /// [`star_variants`](ExpansionStoreItem::star_variants). The star is part of the node, but
+    /// not of its content.
+    ///
+    /// ```
+    /// use parser::{ast::{AstNode, Document, Invocation}, LexedStr};
+    ///
+    /// let lexed = LexedStr::new(r"\newcommand{\name}{\@ifstar{a}{b}}\newcommand{\x}[1]{#1} \name* \name \x*");
+    /// let document = Document::new(&lexed);
+    /// let calls: Vec<_> = document.descendants().filter_map(Invocation::cast).collect();
+    ///
+    /// let starred: Vec<_> = calls.iter().map(|call| (call.syntax().text(), call.is_starred())).collect();
+    /// // the star is the argument of `\x`, which has no starred variant
+    /// assert_eq!(starred, [(r"\name*", true), (r"\name", false), (r"\x*", false)]);
+    /// ```
+    pub fn is_starred(&self) -> bool {
+        self.0.content_token_range().end < self.0.token_range().end
+    }
+
+    /// The arguments in source order, the optional one is omitted if the call has none.
+    pub fn arguments(&self) -> impl Iterator<Item = Argument<'doc, 'source>> + 'doc {
+        self.0.children().filter_map(Argument::cast)
+    }
+
+    /// The number of the first required argument which is missing, counting the optional one
+    /// even if it is omitted. `None` if the call is complete.
+    ///
+    /// ```
+    /// use parser::{ast::{AstNode, Document, Invocation}, LexedStr};
+    ///
+    /// let lexed = LexedStr::new(r"\newcommand{\pair}[3][x]{(#2, #3)} \pair{a}{b} \pair[y]{a}");
+    /// let document = Document::new(&lexed);
+    /// let calls: Vec<_> = document.descendants().filter_map(Invocation::cast).collect();
+    ///
+    /// let args: Vec<_> = calls[0].arguments().map(|arg| arg.syntax().content()).collect();
+    /// assert_eq!(args, ["a", "b"]);
+    /// assert_eq!(calls[0].missing_argument(), None);
+    /// assert_eq!(calls[1].missing_argument(), Some(3));
+    /// ```
+    pub fn missing_argument(&self) -> Option<usize> {
+        if self.0.is_closed() {
+            return None;
+        }
+        let omitted = self.definition().args.default.is_some()
+            && self.arguments().next().map_or(true, |arg| !arg.is_optional());
+        Some(self.arguments().count() + usize::from(omitted) + 1)
+    }
+}
+
+impl Argument<'_, '_> {
+    /// Returns `true` for the optional argument in brackets.
+    pub fn is_optional(&self) -> bool {
+        let lexed = self.0.document.lexed;
+        lexed.kind(self.0.token_range().start) == crate::SyntaxKind::OpenBracket
+    }
+}
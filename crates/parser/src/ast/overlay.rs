@@ -0,0 +1,128 @@
+use std::ops::Range;
+
+use super::{AstNode, Environment, OverlaySpec};
+use crate::{LexedStr, SyntaxKind};
+
+/// Beamer commands which accept an overlay specification directly after their name, e.g.
+/// `\only<2>{...}` or `\item<3->`. Environments accept one after `\begin{name}`.
+pub const OVERLAY_COMMANDS: &[&str] = &[
+    "\\item",
+    "\\only",
+    "\\onslide",
+    "\\uncover",
+    "\\visible",
+    "\\invisible",
+    "\\alt",
+    "\\temporal",
+    "\\alert",
+    "\\structure",
+    "\\action",
+    "\\note",
+    "\\pause",
+    "\\textbf",
+    "\\textit",
+    "\\textsl",
+    "\\textrm",
+    "\\textsf",
+    "\\texttt",
+    "\\emph",
+    "\\color",
+    "\\includegraphics",
+    "\\hyperlink",
+    "\\label",
+    "\\frametitle",
+    "\\framesubtitle",
+    "\\frame",
+];
+
+/// A range of slides of an [`OverlaySpec`], e.g. `2-4`, `3-` or `-2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlideRange {
+    /// First slide, `None` if the range is open towards the first slide
+    pub start: Option<usize>,
+    /// Last slide, `None` if the range is open towards the last slide
+    pub end: Option<usize>,
+}
+
+impl<'doc, 'source> OverlaySpec<'doc, 'source> {
+    /// Source text in between `<` and `>`, e.g. `2-` for `<2->`.
+    pub fn spec(&self) -> &'source str {
+        let text = self.syntax().text();
+        &text[1..text.len() - 1]
+    }
+
+    /// Returns `true` if the specification counts relative to the previous one with `+` or `.`,
+    /// e.g. `<+->`. Its slides can only be known by counting all incremental specifications of
+    /// the frame.
+    pub fn is_incremental(&self) -> bool {
+        self.spec().contains(['+', '.'])
+    }
+
+    /// The absolute slide ranges of the specification. Mode prefixes like `beamer:` or `alert@`
+    /// are skipped, as are incremental parts like `+-`.
+    pub fn slides(&self) -> Vec<SlideRange> {
+        let slide = |text: &str| text.trim().parse::<usize>().ok();
+
+        let mut slides = Vec::new();
+        for part in self.spec().split([',', '|']) {
+            let part = part.rsplit([':', '@']).next().unwrap_or_default();
+            if part.contains(['+', '.', '(']) {
+                continue;
+            }
+            let range = match part.split_once('-') {
+                Some((start, end)) => SlideRange { start: slide(start), end: slide(end) },
+                None => match slide(part) {
+                    Some(slide) => SlideRange { start: Some(slide), end: Some(slide) },
+                    None => continue,
+                },
+            };
+            slides.push(range);
+        }
+        slides
+    }
+
+    /// The highest slide number mentioned by the specification.
+    pub fn last_slide(&self) -> Option<usize> {
+        self.slides().iter().flat_map(|range| [range.start, range.end]).flatten().max()
+    }
+}
+
+impl<'doc, 'source> Environment<'doc, 'source> {
+    /// The overlay specification directly following `\begin{name}`, e.g. `<2->` of
+    /// `\begin{block}<2->{Title}`.
+    pub fn overlay(&self) -> Option<OverlaySpec<'doc, 'source>> {
+        let lexed = self.syntax().document.lexed;
+        let start = self.syntax().content_token_range().start;
+        let spec = self.syntax().children().next().and_then(OverlaySpec::cast)?;
+        let leading = start..spec.syntax().token_range().start;
+        leading.into_iter().all(|idx| lexed.kind(idx) == SyntaxKind::Whitespace).then_some(spec)
+    }
+
+    /// All overlay specifications inside of the environment, including its own, in source order.
+    pub fn overlays(&self) -> impl Iterator<Item = OverlaySpec<'doc, 'source>> + 'doc {
+        self.syntax().descendants().filter_map(OverlaySpec::cast)
+    }
+}
+
+/// Token range of the overlay specification at the token `idx`, which may be preceded by spaces.
+/// The specification has to end on the same line and may only contain slide numbers, modes and
+/// the characters of the overlay syntax.
+pub(crate) fn overlay_spec(lexed: &LexedStr<'_>, idx: usize) -> Option<Range<usize>> {
+    let start = (idx..lexed.len()).find(|&idx| lexed.kind(idx) != SyntaxKind::Whitespace)?;
+    if !lexed.text(start).starts_with('<') {
+        return None;
+    }
+
+    let text = &lexed.as_str()[lexed.text_start(start)..];
+    let close = text.find('>')?;
+    let spec = &text[1..close];
+    let valid = |c: char| c.is_ascii_alphanumeric() || " +-|,.:@()".contains(c);
+    if spec.trim().is_empty() || !spec.chars().all(valid) {
+        return None;
+    }
+
+    // the `>` may be part of a longer token like `->`, but has to end it
+    let close = lexed.text_start(start) + close + 1;
+    let end = (start..lexed.len()).find(|&idx| lexed.text_range(idx).end >= close)?;
+    (lexed.text_range(end).end == close).then_some(start..end + 1)
+}
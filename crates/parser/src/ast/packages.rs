@@ -0,0 +1,184 @@
+use std::ops::Range;
+
+use super::{content_model, AstNode, Document, Environment, NodeKind, SyntaxNode};
+use crate::{
+    diagnostics::{Diagnostic, Severity},
+    expansion::TextEdit,
+    registry, LexedStr, SyntaxKind,
+};
+
+/// Rule id of the diagnostic reported for math symbols whose package is not loaded.
+pub const MISSING_PACKAGE: &str = "missing-package";
+
+/// Packages which load other packages themselves.
+const IMPLIED_PACKAGES: &[(&str, &[&str])] = &[("mathtools", &["amsmath"])];
+
+/// A package providing commands used in math, which is not loaded by the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingPackage<'source> {
+    /// Name of the package, e.g. `amssymb`
+    pub package: &'static str,
+    /// The first command requiring the package
+    pub command: &'source str,
+    /// Byte ranges of all commands requiring the package
+    pub uses: Vec<Range<usize>>,
+    /// Inserts the `\usepackage` line after the last package of the preamble
+    pub fix: TextEdit,
+}
+
+impl MissingPackage<'_> {
+    /// Converts the finding into a [`Diagnostic`] on the first use.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let message = format!("'{}' requires the package '{}'", self.command, self.package);
+        Diagnostic::new(MISSING_PACKAGE, Severity::Warning, self.uses[0].clone(), message)
+            .with_fix([self.fix.clone()])
+    }
+}
+
+impl<'source> Document<'_, 'source> {
+    /// Packages providing the commands used in math according to the [`registry`], together
+    /// with the byte ranges of the uses. Packages are ordered by their first use.
+    pub fn required_packages(&self) -> Vec<(&'static str, Vec<Range<usize>>)> {
+        let mut packages: Vec<(&'static str, Vec<Range<usize>>)> = Vec::new();
+
+        for idx in math_commands(self) {
+            let Some(package) = registry::command(self.lexed.text(idx)).and_then(|cmd| cmd.package)
+            else {
+                continue;
+            };
+            let range = self.lexed.text_range(idx);
+            match packages.iter_mut().find(|(required, _)| *required == package) {
+                Some((_, uses)) => uses.push(range),
+                None => packages.push((package, vec![range])),
+            }
+        }
+
+        packages
+    }
+
+    /// Packages loaded with `\usepackage`, including the packages they load themselves.
+    pub fn loaded_packages(&self) -> Vec<&'source str> {
+        let mut packages = Vec::new();
+        for (name, _) in self.package_names() {
+            packages.push(name);
+            if let Some((_, implied)) = IMPLIED_PACKAGES.iter().find(|(pkg, _)| *pkg == name) {
+                packages.extend(implied.iter().copied());
+            }
+        }
+        packages
+    }
+
+    /// The package names listed by `\usepackage` together with their byte ranges.
+    pub(crate) fn package_names(&self) -> Vec<(&'source str, Range<usize>)> {
+        let text = self.lexed.as_str();
+        let mut names = Vec::new();
+        for idx in 0..self.lexed.len() {
+            if self.lexed.kind(idx) != SyntaxKind::UsePackage {
+                continue;
+            }
+            let Some((list, _)) = use_package(self.lexed, idx) else {
+                continue;
+            };
+            let mut start = list.start;
+            for name in text[list].split(',') {
+                let trimmed = name.trim();
+                let offset = start + (name.len() - name.trim_start().len());
+                if !trimmed.is_empty() {
+                    names.push((trimmed, offset..offset + trimmed.len()));
+                }
+                start += name.len() + 1;
+            }
+        }
+        names
+    }
+
+    /// Packages required by math content which are not loaded.
+    pub fn missing_packages(&self) -> Vec<MissingPackage<'source>> {
+        let loaded = self.loaded_packages();
+        let (offset, new_line) = insert_position(self.lexed);
+
+        self.required_packages()
+            .into_iter()
+            .filter(|(package, _)| !loaded.contains(package))
+            .map(|(package, uses)| {
+                let line = format!("\\usepackage{{{package}}}");
+                let new_text = match new_line {
+                    true => format!("\n{line}\n"),
+                    false => format!("{line}\n"),
+                };
+                MissingPackage {
+                    package,
+                    command: &self.lexed.as_str()[uses[0].clone()],
+                    uses,
+                    fix: TextEdit { range: offset..offset, new_text },
+                }
+            })
+            .collect()
+    }
+}
+
+/// Token indices of all commands typeset in math mode, in source order.
+fn math_commands(document: &Document<'_, '_>) -> Vec<usize> {
+    let mut commands = Vec::new();
+
+    for node in document.descendants() {
+        // nested math is covered by the outermost math node
+        if !is_math(node) || std::iter::successors(node.parent(), |n| n.parent()).any(is_math) {
+            continue;
+        }
+        let content = node.data().content.clone();
+        commands.extend(content.filter(|&idx| document.lexed.kind(idx) == SyntaxKind::Command));
+    }
+
+    commands
+}
+
+fn is_math(node: SyntaxNode<'_, '_>) -> bool {
+    match node.kind() {
+        NodeKind::MathBlock => true,
+        NodeKind::Environment => Environment::cast(node)
+            .and_then(|environment| content_model(environment.name()))
+            .is_some_and(|model| model.math),
+        _ => false,
+    }
+}
+
+/// Byte range of the package list of `\usepackage[options]{list}` at `idx` and the index after
+/// it.
+fn use_package(lexed: &LexedStr<'_>, idx: usize) -> Option<(Range<usize>, usize)> {
+    let next = |from: usize| lexed.next_non_trivia(from);
+
+    let mut open = next(idx + 1)?;
+    if lexed.kind(open) == SyntaxKind::OpenBracket {
+        let close = (open..lexed.len()).find(|&idx| lexed.kind(idx) == SyntaxKind::CloseBracket)?;
+        open = next(close + 1)?;
+    }
+    if lexed.kind(open) != SyntaxKind::OpenBrace {
+        return None;
+    }
+    let close = (open..lexed.len()).find(|&idx| lexed.kind(idx) == SyntaxKind::CloseBrace)?;
+    Some((lexed.text_start(open + 1)..lexed.text_start(close), close + 1))
+}
+
+/// Offset at the start of the line following the last `\usepackage` or the `\documentclass`,
+/// and whether the package has to be put on a line of its own because that line is the last one.
+fn insert_position(lexed: &LexedStr<'_>) -> (usize, bool) {
+    let last = (0..lexed.len()).rev().find_map(|idx| match lexed.kind(idx) {
+        SyntaxKind::UsePackage => use_package(lexed, idx).map(|(_, after)| after),
+        _ => None,
+    });
+    let class = || {
+        let idx = (0..lexed.len()).find(|&idx| lexed.kind(idx) == SyntaxKind::DocClass)?;
+        use_package(lexed, idx).map(|(_, after)| after)
+    };
+    let Some(after) = last.or_else(class) else {
+        return (0, false);
+    };
+
+    let text = lexed.as_str();
+    let end = lexed.text_start(after);
+    match text[end..].find('\n') {
+        Some(newline) => (end + newline + 1, false),
+        None => (end, true),
+    }
+}
@@ -0,0 +1,105 @@
+use std::ops::Range;
+
+use super::{AstNode, Conditional, Document, Environment, MathBlock, NodeKind, SyntaxNode};
+use crate::{
+    diagnostics::{Diagnostic, Severity},
+    expansion::TextEdit,
+    LexedStr, SyntaxKind,
+};
+
+/// Rule id of the diagnostic listing everything which is still open at the end of the source.
+pub const UNCLOSED_AT_EOF: &str = "unclosed-at-eof";
+
+/// Rule id of the diagnostics reported for a `}` without a matching `{` and for braces which
+/// are still open at the end of their environment or math block.
+pub const UNBALANCED_BRACE: &str = "unbalanced-brace";
+
+/// A single diagnostic at the end of the source listing the recovered nodes and the unclosed
+/// braces at `groups`, in the order they were opened. Rows, cells and branches are part of the
+/// environment or conditional they belong to and are not listed.
+///
+/// If only braces are open, the fix appends the missing `}`s, on a new line after a comment.
+pub(super) fn check(document: &Document<'_, '_>, groups: &[usize]) -> Option<Diagnostic> {
+    let lexed = document.lexed();
+    let mut open: Vec<(Range<usize>, String)> = document
+        .descendants()
+        .filter(|node| node.is_recovered())
+        .filter(|node| !node.kind().is_alignment() && !node.kind().is_structure())
+        .filter(|node| node.kind() != NodeKind::Branch)
+        .map(|node| (opening_range(&node), opening(&node)))
+        .collect();
+    open.extend(groups.iter().map(|&idx| (lexed.text_range(idx), "{".to_string())));
+    open.sort_by_key(|(range, _)| range.start);
+    if open.is_empty() {
+        return None;
+    }
+
+    let names: Vec<_> = open.iter().map(|(_, name)| format!("'{name}'")).collect();
+    let message = format!("the file ends with {} still open: {}", names.len(), names.join(", "));
+    let end = lexed.as_str().len();
+    let mut diagnostic = Diagnostic::new(UNCLOSED_AT_EOF, Severity::Error, end..end, message);
+    if open.iter().all(|(_, name)| name == "{") {
+        let last = lexed.len().checked_sub(1).map(|idx| lexed.kind(idx));
+        let mut new_text = "}".repeat(open.len());
+        if matches!(last, Some(SyntaxKind::Comment | SyntaxKind::AComment)) {
+            new_text.insert(0, '\n');
+        }
+        diagnostic = diagnostic.with_fix([TextEdit { range: end..end, new_text }]);
+    }
+    Some(open.into_iter().fold(diagnostic, |diagnostic, (range, name)| {
+        diagnostic.with_related(range, format!("'{name}' opened here"))
+    }))
+}
+
+/// The `}` at `idx`, which closes no brace.
+pub(super) fn unmatched_brace(lexed: &LexedStr<'_>, idx: usize) -> Diagnostic {
+    let message = "'}' without a matching '{'";
+    Diagnostic::new(UNBALANCED_BRACE, Severity::Error, lexed.text_range(idx), message)
+}
+
+/// The `braces` which are still open at the tokens `end` closing their environment or math block.
+/// The fix closes them right in front of `end`.
+pub(super) fn unclosed_braces(
+    lexed: &LexedStr<'_>,
+    braces: &[usize],
+    end: Range<usize>,
+) -> Diagnostic {
+    let range = lexed.text_start(end.start)..lexed.text_start(end.end);
+    let groups = match braces.len() {
+        1 => "a brace group".to_string(),
+        n => format!("{n} brace groups"),
+    };
+    let message = format!("'{}' leaves {groups} open", &lexed.as_str()[range.clone()]);
+    let fix = TextEdit { range: range.start..range.start, new_text: "}".repeat(braces.len()) };
+    let diagnostic =
+        Diagnostic::new(UNBALANCED_BRACE, Severity::Error, range, message).with_fix([fix]);
+    braces.iter().fold(diagnostic, |diagnostic, &idx| {
+        diagnostic.with_related(lexed.text_range(idx), "'{' opened here")
+    })
+}
+
+/// The text opening `node`, e.g. `\begin{itemize}` or `$`.
+fn opening(node: &SyntaxNode<'_, '_>) -> String {
+    if let Some(environment) = Environment::cast(*node) {
+        return format!("\\begin{{{}}}", environment.name());
+    }
+    if let Some(math) = MathBlock::cast(*node) {
+        return math.delimiter().to_string();
+    }
+    if let Some(conditional) = Conditional::cast(*node) {
+        return conditional.name().to_string();
+    }
+    let lexed = node.document.lexed;
+    lexed.text(node.token_range().start).to_string()
+}
+
+/// Byte range of the text opening `node`, including the name of an environment.
+fn opening_range(node: &SyntaxNode<'_, '_>) -> Range<usize> {
+    let lexed = node.document.lexed;
+    let start = node.token_range().start;
+    let end = match node.kind() {
+        NodeKind::Environment => super::environment_name(lexed, start + 1).map(|(_, end)| end),
+        _ => None,
+    };
+    lexed.text_start(start)..lexed.text_start(end.unwrap_or(start + 1))
+}
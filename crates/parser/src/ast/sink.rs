@@ -0,0 +1,92 @@
+use super::{Document, NodeKind};
+use crate::{diagnostics::Diagnostic, SyntaxKind};
+
+/// Receives the tree of a [`Document`] as a flat stream of events, see [`Document::replay`].
+///
+/// The events allow building other tree representations, e.g. a rowan green tree, or visiting
+/// the document in a single pass without walking the [`SyntaxNode`](super::SyntaxNode)s.
+pub trait TreeSink {
+    /// The next token of the source, spanning `len` bytes.
+    fn token(&mut self, kind: SyntaxKind, len: usize);
+
+    /// Opens a node, which contains all following tokens and nodes until its
+    /// [`finish_node`](TreeSink::finish_node).
+    fn start_node(&mut self, kind: NodeKind);
+
+    /// Closes the innermost open node.
+    fn finish_node(&mut self);
+
+    /// A diagnostic of the document, reported in front of the first token starting at or after
+    /// the start of its range.
+    fn error(&mut self, diagnostic: &Diagnostic);
+}
+
+impl Document<'_, '_> {
+    /// Replays the tree into `sink`. Every token is passed exactly once and in source order, so
+    /// the lengths of the tokens add up to the length of the source. Nodes are properly nested
+    /// and recovered nodes are finished at the end of the source.
+    ///
+    /// ```
+    /// use parser::{ast::{Document, NodeKind, TreeSink}, diagnostics::Diagnostic, LexedStr, SyntaxKind};
+    ///
+    /// #[derive(Default)]
+    /// struct Outline(String);
+    ///
+    /// impl TreeSink for Outline {
+    ///     fn token(&mut self, _: SyntaxKind, _: usize) {}
+    ///     fn start_node(&mut self, kind: NodeKind) {
+    ///         self.0.push_str(&format!("({kind:?}"));
+    ///     }
+    ///     fn finish_node(&mut self) {
+    ///         self.0.push(')');
+    ///     }
+    ///     fn error(&mut self, diagnostic: &Diagnostic) {
+    ///         self.0.push_str(&format!("[{}]", diagnostic.rule));
+    ///     }
+    /// }
+    ///
+    /// let lexed = LexedStr::new(r"\begin{proof} $x$ \end{proof}");
+    /// let mut outline = Outline::default();
+    /// Document::new(&lexed).replay(&mut outline);
+    /// assert_eq!(outline.0, "(Environment(MathBlock))");
+    /// ```
+    pub fn replay(&self, sink: &mut impl TreeSink) {
+        let lexed = self.lexed;
+        let mut errors: Vec<_> = self.diagnostics.iter().collect();
+        errors.sort_by_key(|diagnostic| diagnostic.range.start);
+        let mut errors = errors.into_iter().peekable();
+
+        let mut next = 0;
+        let mut tokens = |end: usize, sink: &mut dyn TreeSink| {
+            while next < end {
+                let start = lexed.text_start(next);
+                while let Some(error) = errors.next_if(|error| error.range.start <= start) {
+                    sink.error(error);
+                }
+                sink.token(lexed.kind(next), lexed.text_range(next).len());
+                next += 1;
+            }
+        };
+
+        // nodes are stored in preorder, every node is preceded by its parent
+        let mut open: Vec<usize> = Vec::new();
+        for (idx, node) in self.nodes.iter().enumerate() {
+            while open.last().is_some_and(|&top| Some(top) != node.parent) {
+                let top = open.pop().unwrap();
+                tokens(self.nodes[top].range.end, sink);
+                sink.finish_node();
+            }
+            tokens(node.range.start, sink);
+            sink.start_node(node.kind);
+            open.push(idx);
+        }
+        while let Some(top) = open.pop() {
+            tokens(self.nodes[top].range.end, sink);
+            sink.finish_node();
+        }
+        tokens(lexed.len(), sink);
+        for error in errors {
+            sink.error(error);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use super::{AstNode, Document, DocumentBody, Environment, NodeData, NodeKind, Preamble};
+use crate::{
+    diagnostics::{Diagnostic, Severity},
+    SyntaxKind,
+};
+
+/// Rule id of the diagnostic reported for text after `\end{document}`, which TeX ignores.
+pub const CONTENT_AFTER_DOCUMENT: &str = "content-after-document";
+
+/// Rule id of the diagnostic reported for a document without a `document` environment.
+pub const MISSING_DOCUMENT: &str = "missing-document";
+
+impl<'source> Document<'_, 'source> {
+    /// Everything in front of `\begin{document}`, `None` without a top level `document`
+    /// environment.
+    pub fn preamble(&self) -> Option<Preamble<'_, 'source>> {
+        self.children().find_map(Preamble::cast)
+    }
+
+    /// The `document` environment, `None` if there is none at the top level.
+    pub fn body(&self) -> Option<DocumentBody<'_, 'source>> {
+        self.children().find_map(DocumentBody::cast)
+    }
+}
+
+impl<'doc, 'source> DocumentBody<'doc, 'source> {
+    /// The `document` environment itself.
+    pub fn environment(&self) -> Environment<'doc, 'source> {
+        self.syntax()
+            .children()
+            .find_map(Environment::cast)
+            .expect("the body wraps the environment")
+    }
+}
+
+/// Splits the top level of `document` at its first `document` environment: the nodes in front
+/// of it are moved into a [`NodeKind::Preamble`], the environment into a
+/// [`NodeKind::DocumentBody`]. Nodes following it stay at the top level. Returns the
+/// [`CONTENT_AFTER_DOCUMENT`] diagnostic if there is any text following it.
+pub(super) fn split(document: &mut Document<'_, '_>) -> Option<Diagnostic> {
+    let lexed = document.lexed;
+    let environment = document.nodes.iter().position(|node| {
+        node.kind == NodeKind::Environment
+            && node.parent.is_none()
+            && super::environment_name(lexed, node.range.start + 1)
+                .is_some_and(|(name, _)| name == "document")
+    })?;
+
+    // the preamble is inserted in front of all nodes and the body in front of the environment
+    let shift = |idx: usize| if idx < environment { idx + 1 } else { idx + 2 };
+    for node in &mut document.nodes {
+        node.parent = node.parent.map(shift);
+        node.children.iter_mut().for_each(|child| *child = shift(*child));
+    }
+    let mut preamble = Vec::new();
+    for (idx, node) in document.nodes[..environment].iter_mut().enumerate() {
+        if node.parent.is_none() {
+            node.parent = Some(0);
+            preamble.push(shift(idx));
+        }
+    }
+    document.nodes[environment].parent = Some(environment + 1);
+
+    let data = &document.nodes[environment];
+    let (start, end) = (data.range.start, data.range.end);
+    let body = NodeData {
+        kind: NodeKind::DocumentBody,
+        range: data.range.clone(),
+        content: data.content.clone(),
+        closed: data.closed,
+        recovered: data.recovered,
+        depth: 0,
+        parent: None,
+        children: vec![environment + 2],
+        definition: None,
+    };
+    let preamble = NodeData {
+        kind: NodeKind::Preamble,
+        range: 0..start,
+        content: 0..start,
+        closed: true,
+        recovered: false,
+        depth: 0,
+        parent: None,
+        children: preamble,
+        definition: None,
+    };
+    document.nodes.insert(environment, body);
+    document.nodes.insert(0, preamble);
+
+    let mut trailing = (end..lexed.len())
+        .filter(|&idx| !lexed.kind(idx).is_resolver_trivia() && lexed.kind(idx) != SyntaxKind::Eof);
+    let first = trailing.next()?;
+    let last = trailing.next_back().unwrap_or(first);
+    let range = lexed.text_start(first)..lexed.text_range(last).end;
+    let message = "text after '\\end{document}' is ignored";
+    Some(Diagnostic::new(CONTENT_AFTER_DOCUMENT, Severity::Warning, range, message))
+}
+
+/// Reports a `document` without a top level `document` environment if it is `required`. Pass
+/// `None` to require one if the source has a `\documentclass`, so neither subfiles nor class and
+/// package files are reported.
+///
+/// ```
+/// use parser::{ast::{missing_document, Document}, LexedStr};
+///
+/// let lexed = LexedStr::new("\\documentclass{article}\n\\section{Intro}");
+/// let document = Document::new(&lexed);
+/// assert_eq!(missing_document(&document, None).unwrap().rule, "missing-document");
+///
+/// let lexed = LexedStr::new("\\ProvidesPackage{geometry}");
+/// let document = Document::new(&lexed);
+/// assert!(missing_document(&document, None).is_none());
+/// assert!(missing_document(&document, Some(true)).is_some());
+/// ```
+pub fn missing_document(document: &Document<'_, '_>, required: Option<bool>) -> Option<Diagnostic> {
+    let lexed = document.lexed();
+    let class = (0..lexed.len()).find(|&idx| lexed.kind(idx) == SyntaxKind::DocClass);
+    if document.body().is_some() || !required.unwrap_or(class.is_some()) {
+        return None;
+    }
+
+    let end = lexed.as_str().len();
+    let range = class.map_or(end..end, |idx| lexed.text_range(idx));
+    let message = "the document has no 'document' environment";
+    Some(Diagnostic::new(MISSING_DOCUMENT, Severity::Error, range, message))
+}
@@ -0,0 +1,506 @@
+use super::TreeSink;
+use super::{
+    content_model, AstNode, CommandDefinition, Conditional, Document, Environment,
+    EnvironmentDefinition, Invocation, MathBlock, NodeKind, OverlaySpec, SlideRange, COLUMN_COUNT,
+    CONTENT_AFTER_DOCUMENT, MISSING_ITEM, MISSING_PACKAGE, PARAGRAPH_BREAK, UNBALANCED_BRACE,
+    UNCLOSED_AT_EOF,
+};
+use crate::{
+    diagnostics::{Diagnostic, Severity},
+    expansion::DefinitionKind,
+    LexedStr, SyntaxKind,
+};
+
+#[test]
+fn typed_accessors() {
+    let lexed = LexedStr::new(
+        "\\newcommand{\\vect}[2][n]{#2_1, \\dots, #2_#1}\n\
+         \\def\\pair(#1,#2){\\begin{x}}\n\
+         \\newenvironment{proof}{\\textbf{Proof.}}{\\qed}",
+    );
+    let document = Document::new(&lexed);
+
+    let commands: Vec<_> = document.descendants().filter_map(CommandDefinition::cast).collect();
+    assert_eq!(commands.len(), 2);
+    assert_eq!((commands[0].name(), commands[0].arg_count()), ("\\vect", 2));
+    assert_eq!(commands[0].default_arg(), Some("n"));
+    assert_eq!(commands[0].body(), "#2_1, \\dots, #2_#1");
+    assert_eq!(commands[1].definition_kind(), DefinitionKind::Def);
+
+    let proof = document.descendants().find_map(EnvironmentDefinition::cast).unwrap();
+    assert_eq!(
+        (proof.name(), proof.begin_body(), proof.end_body()),
+        ("proof", "\\textbf{Proof.}", "\\qed")
+    );
+
+    // definition bodies are not part of the document content
+    assert_eq!(document.descendants().filter_map(Environment::cast).count(), 0);
+}
+
+#[test]
+fn nested_environments() {
+    let lexed = LexedStr::new(
+        "\\begin{theorem} $a$ \\begin{align} x &= \\(y\\) \\end{align} \\[z\\] \\end {theorem}",
+    );
+    let document = Document::new(&lexed);
+
+    let theorem = document.children().find_map(Environment::cast).unwrap();
+    assert!(theorem.is_closed());
+    assert_eq!(theorem.name(), "theorem");
+    assert!(theorem.body().starts_with(" $a$") && theorem.body().ends_with("\\] "));
+
+    let align: Vec<_> = theorem.environments().collect();
+    assert_eq!(align.len(), 1);
+    assert_eq!(align[0].body(), " x &= \\(y\\) ");
+    assert_eq!(align[0].syntax().parent(), Some(theorem.syntax()));
+
+    let math: Vec<_> = theorem.math_blocks().map(|math| (math.body(), math.is_display())).collect();
+    assert_eq!(math, [("a", false), ("z", true)]);
+    // the content of align is split into a row with two cells
+    assert_eq!(theorem.syntax().descendants().count(), 7);
+    assert_eq!(theorem.syntax().text(), lexed.as_str());
+}
+
+#[test]
+fn unclosed_nodes() {
+    let lexed = LexedStr::new("\\begin{a} \\begin{b} $x \\end{a} \\end{c} $$y");
+    let document = Document::new(&lexed);
+
+    let kinds: Vec<_> =
+        document.descendants().map(|node| (node.kind(), node.is_closed())).collect();
+    assert_eq!(
+        kinds,
+        [
+            (NodeKind::Environment, true),
+            (NodeKind::Environment, false),
+            (NodeKind::MathBlock, false),
+            (NodeKind::MathBlock, false),
+        ]
+    );
+
+    let b = document.descendants().filter_map(Environment::cast).nth(1).unwrap();
+    assert_eq!(b.body(), " $x ");
+    let display = document.descendants().filter_map(MathBlock::cast).last().unwrap();
+    assert_eq!((display.delimiter(), display.body()), ("$$", "y"));
+}
+
+#[test]
+fn verbatim_content_is_skipped() {
+    let lexed = LexedStr::new("\\begin{verbatim}$\\begin{a}\\end{verbatim} $b$");
+    let document = Document::new(&lexed);
+
+    let kinds: Vec<_> = document.children().map(|node| node.kind()).collect();
+    assert_eq!(kinds, [NodeKind::Environment, NodeKind::MathBlock]);
+}
+
+fn content_diagnostics(input: &str) -> Vec<(&'static str, &str)> {
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+    document.diagnostics().iter().map(|diag| (diag.rule, &input[diag.range.clone()])).collect()
+}
+
+#[test]
+fn content_models() {
+    let input =
+        "\\begin{itemize}\\setlength{\\itemsep}{0pt} stray \\item ok\n\n\\item fine\\end{itemize}";
+    assert_eq!(content_diagnostics(input), [(MISSING_ITEM, "stray")]);
+
+    let input = "\\begin{enumerate} $x$ \\begin{itemize}\\item a\\end{itemize}\\end{enumerate}";
+    assert_eq!(content_diagnostics(input), [(MISSING_ITEM, "$x$")]);
+
+    let input = "\\begin{tabular}{ll} a & \\parbox{2cm}{b\n\nc} \\\\\n\n d\\end{tabular} $a\n\nb$";
+    assert_eq!(content_diagnostics(input), [(PARAGRAPH_BREAK, "\n\n"), (PARAGRAPH_BREAK, "\n\n")]);
+
+    assert!(content_model("proof").is_none());
+    assert!(content_diagnostics("\\begin{proof} text\n\n more \\end{proof}").is_empty());
+}
+
+#[test]
+fn overlay_specs() {
+    let input = "\\begin{frame}<2->{Title}\n\\begin{itemize}<+->\n\\item<1-3,5> a\n\\item <-2| alert@4> b\n\
+                 \\end{itemize}\n\\only<beamer:3>{c} $a<b> c$\\end{frame}";
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+    assert!(document.diagnostics().is_empty());
+
+    let frame = document.descendants().find_map(Environment::cast).unwrap();
+    assert_eq!(frame.overlay().unwrap().spec(), "2-");
+    let specs: Vec<_> = frame.overlays().map(|spec| spec.spec()).collect();
+    assert_eq!(specs, ["2-", "+-", "1-3,5", "-2| alert@4", "beamer:3"]);
+
+    let itemize = frame.environments().next().unwrap();
+    assert!(itemize.overlay().unwrap().is_incremental());
+    let slides = |spec: &OverlaySpec<'_, '_>| (spec.slides(), spec.last_slide());
+    let specs: Vec<_> = itemize.overlays().skip(1).map(|spec| slides(&spec)).collect();
+    let range = |start, end| SlideRange { start, end };
+    assert_eq!(
+        specs,
+        [
+            (vec![range(Some(1), Some(3)), range(Some(5), Some(5))], Some(5)),
+            (vec![range(None, Some(2)), range(Some(4), Some(4))], Some(4)),
+        ]
+    );
+    assert!(itemize.overlays().skip(1).all(|spec| !spec.is_incremental()));
+}
+
+#[test]
+fn alignment_rows_and_cells() {
+    let lexed = LexedStr::new(
+        "\\begin{tabular}{|l|*{2}{c}|} a & {b & c} & $d$ \\\\[2pt]\n\\hline\n\
+         \\multicolumn{2}{c}{e} & f \\\\\n\\end{tabular}",
+    );
+    let document = Document::new(&lexed);
+    let tabular = document.descendants().find_map(Environment::cast).unwrap();
+    assert_eq!(tabular.columns(), Some(3));
+
+    let rows: Vec<Vec<_>> =
+        tabular.rows().map(|row| row.cells().map(|cell| cell.body().trim()).collect()).collect();
+    assert_eq!(rows, [vec!["a", "{b & c}", "$d$"], vec!["\\hline\n\\multicolumn{2}{c}{e}", "f"]]);
+
+    let row = tabular.rows().nth(1).unwrap();
+    assert_eq!(row.column_count(), 3);
+    assert!(row.syntax().is_closed());
+    assert_eq!(tabular.math_blocks().next().unwrap().body(), "d");
+    assert!(document.diagnostics().is_empty());
+}
+
+#[test]
+fn truncated_source() {
+    let input =
+        "\\begin{document}\n\\begin{itemize}\n\\item {\\bf a} $x\\begin{tabular}{l} b \\\\ c & d\n\
+                 \\end{document}\n\\newif\\ifdraft\\ifdraft{\\emph{";
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+
+    let recovered: Vec<_> = document
+        .descendants()
+        .filter(|node| node.is_recovered())
+        .map(|node| (node.kind(), node.text_range().end))
+        .collect();
+    assert_eq!(recovered, [(NodeKind::Conditional, input.len()), (NodeKind::Branch, input.len())]);
+    // the environments opened before `\end{document}` end with it
+    let itemize = document.descendants().filter_map(Environment::cast).nth(1).unwrap();
+    assert!(!itemize.is_closed() && !itemize.syntax().is_recovered());
+
+    let diagnostics: Vec<_> =
+        document.diagnostics().iter().filter(|diag| diag.rule == UNCLOSED_AT_EOF).collect();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(diagnostics[0].range, input.len()..input.len());
+    assert_eq!(diagnostics[0].message, "the file ends with 3 still open: '\\ifdraft', '{', '{'");
+    let related: Vec<_> =
+        diagnostics[0].related.iter().map(|related| &input[related.range.clone()]).collect();
+    assert_eq!(related, ["\\ifdraft", "{", "{"]);
+
+    let lexed = LexedStr::new("\\begin{document}\n\\begin{proof}\n$x");
+    let document = Document::new(&lexed);
+    let diagnostic = document.diagnostics().iter().find(|diag| diag.rule == UNCLOSED_AT_EOF);
+    assert_eq!(
+        diagnostic.unwrap().message,
+        "the file ends with 3 still open: '\\begin{document}', '\\begin{proof}', '$'"
+    );
+    assert_eq!(&lexed.as_str()[diagnostic.unwrap().related[1].range.clone()], "\\begin{proof}");
+    assert!(Document::new(&LexedStr::new("\\begin{x}{}\\end{x}")).diagnostics().is_empty());
+}
+
+#[test]
+fn column_counts() {
+    let input = "\\begin{tabular}{ll} a & b & c \\\\ d \\end{tabular}\n\
+                 $\\begin{pmatrix} 1 & 2 \\\\ 3 \\end{pmatrix}$\n\
+                 \\begin{align} a &= b \\\\ c &= d & e \\end{align}";
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+
+    let diagnostics: Vec<_> = document
+        .diagnostics()
+        .iter()
+        .map(|diag| (diag.rule, diag.severity, diag.message.as_str(), &input[diag.range.clone()]))
+        .collect();
+    assert_eq!(
+        diagnostics,
+        [
+            (
+                COLUMN_COUNT,
+                Severity::Error,
+                "row has 3 columns but 'tabular' declares 2",
+                " a & b & c \\\\"
+            ),
+            (COLUMN_COUNT, Severity::Warning, "row has 1 columns but the first row has 2", " 3 "),
+        ]
+    );
+}
+
+#[test]
+fn conditional_nodes() {
+    let input = "\\newif\\ifdraft\\begin{itemize}\\ifdefined\\x \\item $a$\\else\\begin{enumerate}\\fi\\end{itemize}\n\
+                 \\begin{tabular}{ll} a & \\ifdraft b & c\\else b\\fi \\\\ \\end{tabular}\\iftrue x";
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+
+    let conditionals: Vec<_> = document.descendants().filter_map(Conditional::cast).collect();
+    let names: Vec<_> = conditionals.iter().map(|cond| (cond.name(), cond.test())).collect();
+    assert_eq!(names, [("\\ifdefined", "\\x"), ("\\ifdraft", ""), ("\\iftrue", "")]);
+    let branches: Vec<_> =
+        conditionals[0].branches().map(|branch| (branch.body().trim(), branch.is_else())).collect();
+    assert_eq!(branches, [("\\item $a$", false), ("\\begin{enumerate}", true)]);
+    assert!(conditionals[0].is_closed() && !conditionals[2].is_closed());
+
+    // the environment opened in a branch ends with it
+    let enumerate = document.descendants().filter_map(Environment::cast).nth(1).unwrap();
+    assert_eq!((enumerate.name(), enumerate.is_closed()), ("enumerate", false));
+    let itemize = document.descendants().find_map(Environment::cast).unwrap();
+    assert!(itemize.is_closed());
+    assert_eq!(itemize.math_blocks().next().unwrap().body(), "a");
+
+    let tabular = document.descendants().filter_map(Environment::cast).nth(2).unwrap();
+    let rows: Vec<Vec<_>> =
+        tabular.rows().map(|row| row.cells().map(|cell| cell.body().trim()).collect()).collect();
+    assert_eq!(rows, [vec!["a", "\\ifdraft b & c\\else b\\fi"]]);
+    assert!(!document.diagnostics().iter().any(|diag| diag.rule == COLUMN_COUNT));
+}
+
+#[test]
+fn required_packages() {
+    let input = "\\documentclass{article}\n\\usepackage[utf8]{inputenc}\n\\usepackage{mathtools}\n\
+                 \\begin{document}\n$\\mathbb{R} \\text{a} \\mathbb{N}$ \\mathscr{x}\n\
+                 \\begin{equation}\\mathscr{L}\\end{equation}\\end{document}";
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+
+    let required: Vec<_> = document
+        .required_packages()
+        .into_iter()
+        .map(|(package, uses)| (package, uses.into_iter().map(|use_| &input[use_]).collect()))
+        .collect();
+    assert_eq!(
+        required,
+        [
+            ("amssymb", vec!["\\mathbb", "\\mathbb"]),
+            ("amsmath", vec!["\\text"]),
+            ("mathrsfs", vec!["\\mathscr"])
+        ]
+    );
+    assert_eq!(document.loaded_packages(), ["inputenc", "mathtools", "amsmath"]);
+
+    let missing = document.missing_packages();
+    let packages: Vec<_> = missing.iter().map(|missing| missing.package).collect();
+    assert_eq!(packages, ["amssymb", "mathrsfs"]);
+    assert_eq!(missing[0].fix.new_text, "\\usepackage{amssymb}\n");
+    assert_eq!(&input[missing[0].fix.range.start..][..16], "\\begin{document}");
+
+    let rules: Vec<_> = document.diagnostics().iter().map(|diag| diag.rule).collect();
+    assert_eq!(rules, [MISSING_PACKAGE, MISSING_PACKAGE]);
+
+    let lexed = LexedStr::new("\\usepackage{amsmath}");
+    let fix = &Document::new(&lexed).missing_packages();
+    assert!(fix.is_empty());
+    let lexed = LexedStr::new("\\documentclass{article}$\\mathbb{R}$");
+    let fix = &Document::new(&lexed).missing_packages()[0].fix;
+    assert_eq!((fix.range.start, fix.new_text.as_str()), (23, "\n\\usepackage{amssymb}\n"));
+}
+
+#[test]
+fn unbalanced_braces() {
+    /// Text, message and related offsets of a diagnostic.
+    type Reported<'a> = (&'a str, String, Vec<usize>);
+
+    /// The start and parent of the error nodes and the unbalanced brace diagnostics.
+    fn braces(input: &str) -> (Vec<(usize, Option<NodeKind>)>, Vec<Reported<'_>>) {
+        let lexed = LexedStr::new(input);
+        let document = Document::new(&lexed);
+        let errors: Vec<_> = document
+            .descendants()
+            .filter(|node| node.kind() == NodeKind::Error)
+            .map(|node| (node.text_range().start, node.parent().map(|parent| parent.kind())))
+            .collect();
+        let diagnostics: Vec<_> = document
+            .diagnostics()
+            .iter()
+            .filter(|diag| diag.rule == UNBALANCED_BRACE)
+            .map(|diag| {
+                let related = diag.related.iter().map(|related| related.range.start).collect();
+                (&input[diag.range.clone()], diag.message.clone(), related)
+            })
+            .collect();
+        (errors, diagnostics)
+    }
+
+    let (errors, diagnostics) = braces("a } b");
+    assert_eq!(errors, [(2, None)]);
+    assert_eq!(diagnostics, [("}", "'}' without a matching '{'".to_string(), vec![])]);
+
+    // a brace opened outside of the environment is not closed inside of it
+    let (errors, diagnostics) = braces("{ \\begin{proof} } \\end{proof} }");
+    assert_eq!(errors, [(16, Some(NodeKind::Environment))]);
+    assert_eq!(diagnostics.len(), 1);
+
+    let (errors, diagnostics) = braces("\\begin{proof} {a \\end{proof} b $ {x {y $");
+    assert_eq!(errors, []);
+    assert_eq!(
+        diagnostics,
+        [
+            ("\\end{proof}", "'\\end{proof}' leaves a brace group open".to_string(), vec![14]),
+            ("$", "'$' leaves 2 brace groups open".to_string(), vec![33, 36]),
+        ]
+    );
+
+    // the rest of the document is parsed as usual
+    let input =
+        "\\begin{itemize} \\item {a \\end{itemize} \\begin{tabular}{ll} a & b \\\\ \\end{tabular}";
+    let lexed = LexedStr::new(input);
+    let document = Document::new(&lexed);
+    let tabular = document.descendants().filter_map(Environment::cast).nth(1).unwrap();
+    assert_eq!(tabular.rows().next().unwrap().cells().count(), 2);
+    assert!(tabular.is_closed());
+}
+
+/// Renders the replayed tree as `(Kind text)` with diagnostics as `[rule]`.
+struct SExpr<'source> {
+    source: &'source str,
+    offset: usize,
+    out: String,
+}
+
+impl TreeSink for SExpr<'_> {
+    fn token(&mut self, _: SyntaxKind, len: usize) {
+        self.out.push_str(&self.source[self.offset..self.offset + len]);
+        self.offset += len;
+    }
+
+    fn start_node(&mut self, kind: NodeKind) {
+        self.out.push_str(&format!("({kind:?} "));
+    }
+
+    fn finish_node(&mut self) {
+        self.out.push(')');
+    }
+
+    fn error(&mut self, diagnostic: &Diagnostic) {
+        self.out.push_str(&format!("[{}]", diagnostic.rule));
+    }
+}
+
+#[test]
+fn preamble_and_body() {
+    let source = "\\newcommand{\\R}{x}$a$\n\\begin{document}$b$\\end{document}\n% done\nText $c$";
+    let lexed = LexedStr::new(source);
+    let document = Document::new(&lexed);
+
+    let kinds: Vec<_> = document.children().map(|node| node.kind()).collect();
+    assert_eq!(kinds, [NodeKind::Preamble, NodeKind::DocumentBody, NodeKind::MathBlock]);
+    let preamble = document.preamble().unwrap();
+    assert_eq!(preamble.syntax().text(), "\\newcommand{\\R}{x}$a$\n");
+    let kinds: Vec<_> = preamble.syntax().children().map(|node| node.kind()).collect();
+    assert_eq!(kinds, [NodeKind::CommandDefinition, NodeKind::MathBlock]);
+
+    let body = document.body().unwrap();
+    assert_eq!(body.environment().math_blocks().next().unwrap().body(), "b");
+    assert_eq!(body.syntax().text(), "\\begin{document}$b$\\end{document}");
+    // the tree is still in source order and every node knows its parent
+    for node in document.descendants() {
+        let parent = node.parent().map(|parent| parent.token_range());
+        assert!(parent.map_or(true, |parent| parent.contains(&node.token_range().start)));
+    }
+    let diagnostics: Vec<_> = document
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| (diagnostic.rule, &source[diagnostic.range.clone()]))
+        .collect();
+    assert_eq!(diagnostics, [(CONTENT_AFTER_DOCUMENT, "Text $c$")]);
+
+    // only a document environment at the top level splits the document
+    let lexed = LexedStr::new("\\begin{frame}\\begin{document}\\end{document}\\end{frame}");
+    assert!(Document::new(&lexed).preamble().is_none());
+    let lexed = LexedStr::new("\\begin{document}\n\\end{document}\n% only comments\n");
+    let document = Document::new(&lexed);
+    assert!(document.preamble().unwrap().syntax().text().is_empty());
+    assert!(document.diagnostics().is_empty());
+}
+
+#[test]
+fn invocations() {
+    let source = "\\box{a} \\newcommand{\\box}[2][red]{#1#2}\\def\\pair(#1,#2){}\n\
+                  \\box[blue]{$x$} \\box \\pair(\\box{\\begin{proof}}, b) \\pair(a, {b}) \\box{\\end{proof}}";
+    let lexed = LexedStr::new(source);
+    let document = Document::new(&lexed);
+    let calls: Vec<_> = document.descendants().filter_map(Invocation::cast).collect();
+
+    // calls in front of the definition are not recognized
+    let texts: Vec<_> = calls.iter().map(|call| call.syntax().text()).collect();
+    assert_eq!(
+        texts,
+        [
+            "\\box[blue]{$x$}",
+            "\\box \\pair",
+            "\\box{\\begin{proof}}",
+            "\\pair(a, {b})",
+            "\\box{\\end{proof}}"
+        ]
+    );
+    let args: Vec<_> =
+        calls[0].arguments().map(|arg| (arg.is_optional(), arg.syntax().text())).collect();
+    assert_eq!(args, [(true, "[blue]"), (false, "{$x$}")]);
+    let math = calls[0].syntax().descendants().find_map(MathBlock::cast).unwrap();
+    assert_eq!(math.syntax().parent().unwrap().kind(), NodeKind::Argument);
+    // a single token is an argument as well, the arguments of `\pair` follow the call of `\box`
+    assert_eq!(calls[1].arguments().next().unwrap().syntax().text(), "\\pair");
+    assert_eq!(calls[1].syntax().descendants().count(), 1);
+
+    // environments opened inside of an argument end with it
+    let proof = calls[2].syntax().descendants().find_map(Environment::cast).unwrap();
+    assert!(!proof.syntax().is_closed());
+    assert_eq!(proof.syntax().text(), "\\begin{proof}");
+    let args: Vec<_> = calls[3].arguments().map(|arg| arg.syntax().content()).collect();
+    assert_eq!(args, ["a", "b"]);
+    assert_eq!(calls[4].missing_argument(), None);
+    for node in document.descendants() {
+        let parent = node.parent().map(|parent| parent.token_range());
+        assert!(parent.map_or(true, |parent| parent.end >= node.token_range().end), "{node:?}");
+    }
+
+    let lexed = LexedStr::new("\\newcommand{\\pair}[2]{} \\pair{a}");
+    let document = Document::new(&lexed);
+    let call = document.descendants().find_map(Invocation::cast).unwrap();
+    assert!(!call.syntax().is_closed());
+    assert_eq!((call.name(), call.missing_argument()), ("\\pair", Some(2)));
+
+    // `\begin` is no argument, the environment stays open up to the end of the file
+    let source = "\\newcommand{\\pair}[2]{} \\pair{a}\n\\begin{itemize}\\item b";
+    let lexed = LexedStr::new(source);
+    let document = Document::new(&lexed);
+    let call = document.descendants().find_map(Invocation::cast).unwrap();
+    assert_eq!((call.syntax().text(), call.missing_argument()), ("\\pair{a}", Some(2)));
+    let itemize = document.descendants().find_map(Environment::cast).unwrap();
+    assert_eq!(itemize.syntax().text_range().end, source.len());
+    let diagnostic = document.diagnostics().iter().find(|diag| diag.rule == UNCLOSED_AT_EOF);
+    assert_eq!(diagnostic.unwrap().message, "the file ends with 1 still open: '\\begin{itemize}'");
+}
+
+#[test]
+fn replay() {
+    let replay = |input: &str| {
+        let lexed = LexedStr::new(input);
+        let mut sink = SExpr { source: input, offset: 0, out: String::new() };
+        Document::new(&lexed).replay(&mut sink);
+        assert_eq!(sink.offset, input.len());
+        sink.out
+    };
+
+    assert_eq!(replay("a $x$ b"), "a (MathBlock $x$) b");
+    assert_eq!(
+        replay("\\begin{tabular}{ll} a & b \\\\ \\end{tabular}"),
+        "(Environment \\begin{tabular}{ll}(Row (Cell  a )&(Cell  b )\\\\) \\end{tabular})"
+    );
+    assert_eq!(
+        replay("\\iftrue $x$\\else y\\fi z"),
+        "(Conditional \\iftrue(Branch  (MathBlock $x$))(Branch \\else y)\\fi) z"
+    );
+    assert_eq!(
+        replay("\\begin{itemize} x \\item y \\end{itemize}"),
+        "(Environment \\begin{itemize} [missing-item]x \\item y \\end{itemize})"
+    );
+    assert_eq!(
+        replay("\\begin{proof}\n$x"),
+        "(Environment \\begin{proof}\n(MathBlock $x))[unclosed-at-eof]"
+    );
+}
@@ -0,0 +1,229 @@
+//! Characters written by their code instead of literally.
+//!
+//! TeX reads `^^` followed by two lowercase hex digits as the character with that code, and `^^`
+//! followed by any other ASCII character as the character 64 positions away, e.g. `^^M` for a
+//! carriage return. XeTeX and LuaTeX extend this to `^^^^` with four and `^^^^^^` with six hex
+//! digits. `\char` typesets the character with a given number, which is decimal or prefixed with
+//! `'` for octal, `"` for hexadecimal or `` ` `` for the code of a character, e.g. ``\char`\%``.
+//! LaTeX's `\symbol{...}` takes the same number in braces.
+//!
+//! [`char_codes`] finds and decodes all of them, so text extraction and search can see the
+//! intended characters, see [`LexedStr::decoded_text`].
+
+use std::ops::Range;
+
+use thiserror::Error;
+
+use crate::{
+    diagnostics::{Diagnostic, Severity},
+    LexedStr, SyntaxKind,
+};
+
+/// Rule id of the diagnostic reported for character codes which do not denote a character.
+pub const INVALID_CHAR_CODE: &str = "invalid-char-code";
+
+/// How a [`CharCode`] is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Notation {
+    /// `^^M`, `^^e9` or `^^^^03b1`
+    Caret,
+    /// `\char"E9`
+    Char,
+    /// `\symbol{233}`
+    Symbol,
+}
+
+/// Why a [`CharCode`] does not denote a character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum CharCodeError {
+    /// No number follows, e.g. `\char"` or `\symbol{}`
+    #[error("expected a character code")]
+    MissingCode,
+    /// The number is larger than `0x10FFFF` or a surrogate
+    #[error("{0} is not a valid character code")]
+    Invalid(u64),
+    /// `^^` is followed by a character outside of ASCII or nothing at all
+    #[error("'^^' has to be followed by an ASCII character or two lowercase hex digits")]
+    InvalidCaret,
+}
+
+/// A character written by its code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharCode<'source> {
+    /// How the character is written
+    pub notation: Notation,
+    /// The whole specification, e.g. `\char"E9`
+    pub text: &'source str,
+    /// Byte range of `text` in the source
+    pub range: Range<usize>,
+    /// The decoded character
+    pub value: Result<char, CharCodeError>,
+}
+
+impl CharCode<'_> {
+    /// Converts an invalid code into an error [`Diagnostic`], `None` for a valid one.
+    pub fn to_diagnostic(&self) -> Option<Diagnostic> {
+        let err = self.value.err()?;
+        let message = format!("'{}': {err}", self.text);
+        Some(Diagnostic::new(INVALID_CHAR_CODE, Severity::Error, self.range.clone(), message))
+    }
+}
+
+/// All characters of `lexed` written in `^^` notation or with `\char` or `\symbol`, in source
+/// order.
+///
+/// Only literal numbers are decoded. A register or parameter like `\char\count0` or `\char#1` is
+/// no character code of the source.
+///
+/// ```
+/// use parser::{charcode::char_codes, LexedStr};
+///
+/// let lexed = LexedStr::new(r#"^^e9t^^e9 \char"E9 \symbol{`\%} \char#1"#);
+/// let values: Vec<_> = char_codes(&lexed).into_iter().map(|code| code.value.unwrap()).collect();
+/// assert_eq!(values, ['é', 'é', 'é', '%']);
+/// ```
+pub fn char_codes<'source>(lexed: &LexedStr<'source>) -> Vec<CharCode<'source>> {
+    let source = lexed.as_str();
+    let mut codes = Vec::new();
+    let mut idx = 0;
+
+    while idx < lexed.len() {
+        let start = lexed.text_start(idx);
+        let found = match lexed.kind(idx) {
+            SyntaxKind::Carret
+                if idx + 1 < lexed.len() && lexed.kind(idx + 1) == SyntaxKind::Carret =>
+            {
+                let (len, value) = caret(&source[start..]);
+                Some((Notation::Caret, start + len, value))
+            }
+            // digits may be part of the command name, e.g. `\char65` in the NeoTeX profile
+            SyntaxKind::Command
+                if lexed
+                    .text(idx)
+                    .strip_prefix("\\char")
+                    .is_some_and(|rest| rest.bytes().all(|b| b.is_ascii_digit())) =>
+            {
+                let spec = start + "\\char".len();
+                let skipped = source[spec..].len() - source[spec..].trim_start().len();
+                number(&source[spec + skipped..])
+                    .map(|(len, value)| (Notation::Char, spec + skipped + len, value))
+            }
+            SyntaxKind::Command if lexed.text(idx) == "\\symbol" => symbol(lexed, idx),
+            _ => None,
+        };
+
+        let Some((notation, end, value)) = found else {
+            idx += 1;
+            continue;
+        };
+        codes.push(CharCode { notation, text: &source[start..end], range: start..end, value });
+        while idx < lexed.len() && lexed.text_start(idx) < end {
+            idx += 1;
+        }
+    }
+
+    codes
+}
+
+/// Length and value of the `^^` notation at the start of `text`.
+fn caret(text: &str) -> (usize, Result<char, CharCodeError>) {
+    let hex =
+        |digits: &str| digits.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+    for carets in [6, 4] {
+        let digits = text.get(carets..carets + carets);
+        let prefix = text.as_bytes().get(..carets);
+        if prefix.is_some_and(|prefix| prefix.iter().all(|&b| b == b'^')) && digits.is_some_and(hex)
+        {
+            let code = u64::from_str_radix(digits.unwrap(), 16).unwrap_or(u64::MAX);
+            return (2 * carets, character(code));
+        }
+    }
+    if let Some(digits) = text.get(2..4).filter(|digits| hex(digits)) {
+        let code = u64::from_str_radix(digits, 16).unwrap_or(u64::MAX);
+        return (4, character(code));
+    }
+    match text[2..].chars().next() {
+        Some(c) if c.is_ascii() => (3, character(u64::from(c as u8 ^ 0x40))),
+        Some(c) => (2 + c.len_utf8(), Err(CharCodeError::InvalidCaret)),
+        None => (2, Err(CharCodeError::InvalidCaret)),
+    }
+}
+
+/// The argument of the `\symbol` at the token `idx`, `None` if it is not a literal number.
+fn symbol(
+    lexed: &LexedStr<'_>,
+    idx: usize,
+) -> Option<(Notation, usize, Result<char, CharCodeError>)> {
+    let open = lexed.next_non_trivia(idx + 1)?;
+    let close = (open..lexed.len()).find(|&close| lexed.kind(close) == SyntaxKind::CloseBrace)?;
+    if lexed.kind(open) != SyntaxKind::OpenBrace {
+        return None;
+    }
+    let arg = &lexed.as_str()[lexed.text_start(open + 1)..lexed.text_start(close)];
+    let value = match number(arg.trim()) {
+        Some((len, value)) if len == arg.trim().len() => value,
+        Some(_) => Err(CharCodeError::MissingCode),
+        None if arg.trim().is_empty() => Err(CharCodeError::MissingCode),
+        // e.g. `\symbol{\value{c}}`
+        None => return None,
+    };
+    Some((Notation::Symbol, lexed.text_range(close).end, value))
+}
+
+/// Length and value of the TeX number at the start of `text`, `None` if it is a register or a
+/// parameter rather than a literal.
+fn number(text: &str) -> Option<(usize, Result<char, CharCodeError>)> {
+    let digits = |text: &str, radix: u32| {
+        let len = text.find(|c: char| !c.is_digit(radix)).unwrap_or(text.len());
+        (len, u64::from_str_radix(&text[..len], radix).ok())
+    };
+    let (prefix, radix) = match text.chars().next()? {
+        '\'' => (1, 8),
+        '"' => (1, 16),
+        '`' => {
+            // the character itself or an escaped one, e.g. `\%`
+            let rest = &text[1..];
+            let escaped = rest.strip_prefix('\\').unwrap_or(rest);
+            return match escaped.chars().next() {
+                Some(c) => Some((text.len() - escaped.len() + c.len_utf8(), Ok(c))),
+                None => Some((1, Err(CharCodeError::MissingCode))),
+            };
+        }
+        '\\' | '#' => return None,
+        _ => (0, 10),
+    };
+
+    match digits(&text[prefix..], radix) {
+        (0, _) => Some((prefix, Err(CharCodeError::MissingCode))),
+        (len, code) => Some((prefix + len, character(code.unwrap_or(u64::MAX)))),
+    }
+}
+
+fn character(code: u64) -> Result<char, CharCodeError> {
+    u32::try_from(code).ok().and_then(char::from_u32).ok_or(CharCodeError::Invalid(code))
+}
+
+impl LexedStr<'_> {
+    /// Source text of the token range `range` with every valid [`CharCode`] replaced by its
+    /// character, e.g. `caf^^e9` becomes `café`. Codes only partly inside of `range` are kept.
+    pub fn decoded_text(&self, range: Range<usize>) -> String {
+        let (start, end) = (self.text_start(range.start), self.text_start(range.end));
+        let mut text = String::with_capacity(end - start);
+        let mut pos = start;
+        for code in char_codes(self) {
+            let Ok(c) = code.value else {
+                continue;
+            };
+            if code.range.start >= pos && code.range.end <= end {
+                text.push_str(&self.as_str()[pos..code.range.start]);
+                text.push(c);
+                pos = code.range.end;
+            }
+        }
+        text.push_str(&self.as_str()[pos..end]);
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests;
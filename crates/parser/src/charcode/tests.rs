@@ -0,0 +1,74 @@
+use super::{char_codes, CharCodeError, Notation, INVALID_CHAR_CODE};
+use crate::LexedStr;
+
+fn decode(input: &str) -> Vec<(Notation, &str, Result<char, CharCodeError>)> {
+    let lexed = LexedStr::new(input);
+    char_codes(&lexed).into_iter().map(|code| (code.notation, code.text, code.value)).collect()
+}
+
+#[test]
+fn caret_notation() {
+    assert_eq!(
+        decode("^^M^^41^^e9x^^^^03b1^^^^^^01f600"),
+        [
+            (Notation::Caret, "^^M", Ok('\r')),
+            (Notation::Caret, "^^41", Ok('A')),
+            (Notation::Caret, "^^e9", Ok('é')),
+            (Notation::Caret, "^^^^03b1", Ok('α')),
+            (Notation::Caret, "^^^^^^01f600", Ok('😀')),
+        ]
+    );
+    // uppercase hex digits are not read as a code, `^^E` is the character 64 below `E`
+    assert_eq!(decode("^^E9"), [(Notation::Caret, "^^E", Ok('\u{5}'))]);
+    assert_eq!(decode("^^?"), [(Notation::Caret, "^^?", Ok('\u{7f}'))]);
+    assert_eq!(decode("^^ü"), [(Notation::Caret, "^^ü", Err(CharCodeError::InvalidCaret))]);
+    assert_eq!(decode("x^2 ^ ^"), []);
+
+    // multibyte text following the carets is no code and must not be split
+    assert_eq!(decode("^^zé"), [(Notation::Caret, "^^z", Ok(':'))]);
+    assert_eq!(decode("^^e9日本"), [(Notation::Caret, "^^e9", Ok('é'))]);
+    assert_eq!(decode("^^日本"), [(Notation::Caret, "^^日", Err(CharCodeError::InvalidCaret))]);
+    assert_eq!(decode("^^^é").len(), 1);
+    assert_eq!(decode("^^^^日").len(), 1);
+}
+
+#[test]
+fn char_and_symbol() {
+    assert_eq!(
+        decode("\\char65 \\char'101\\char\"41 \\char`A \\char`\\% \\symbol{ \"3B1 }"),
+        [
+            (Notation::Char, "\\char65", Ok('A')),
+            (Notation::Char, "\\char'101", Ok('A')),
+            (Notation::Char, "\\char\"41", Ok('A')),
+            (Notation::Char, "\\char`A", Ok('A')),
+            (Notation::Char, "\\char`\\%", Ok('%')),
+            (Notation::Symbol, "\\symbol{ \"3B1 }", Ok('α')),
+        ]
+    );
+    // registers and parameters are not literal codes
+    assert_eq!(decode("\\char\\count0 \\char#1 \\symbol{\\value{c}} \\symbol"), []);
+
+    assert_eq!(
+        decode("\\char\"D800 \\char\"110000 \\char\" \\symbol{} \\symbol{12a}"),
+        [
+            (Notation::Char, "\\char\"D800", Err(CharCodeError::Invalid(0xD800))),
+            (Notation::Char, "\\char\"110000", Err(CharCodeError::Invalid(0x110000))),
+            (Notation::Char, "\\char\"", Err(CharCodeError::MissingCode)),
+            (Notation::Symbol, "\\symbol{}", Err(CharCodeError::MissingCode)),
+            (Notation::Symbol, "\\symbol{12a}", Err(CharCodeError::MissingCode)),
+        ]
+    );
+}
+
+#[test]
+fn decoded_text() {
+    let lexed = LexedStr::new("caf^^e9 \\char\"110000 na\\symbol{239}ve");
+    assert_eq!(lexed.decoded_text(0..lexed.len()), "café \\char\"110000 naïve");
+    assert_eq!(lexed.decoded_text(2..lexed.len()), "^e9 \\char\"110000 naïve");
+
+    let codes = char_codes(&lexed);
+    let diagnostics: Vec<_> = codes.iter().filter_map(|code| code.to_diagnostic()).collect();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!((diagnostics[0].rule, diagnostics[0].range.clone()), (INVALID_CHAR_CODE, 8..20));
+    assert_eq!(diagnostics[0].message, "'\\char\"110000': 1114112 is not a valid character code");
+}
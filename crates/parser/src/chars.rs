@@ -0,0 +1,62 @@
+//! Character classes used by the lexer.
+//!
+//! The predicates run for almost every character of a document. Characters below `U+0100` are
+//! classified by a single lookup in [`LATIN1_CLASSES`], only the few remaining Unicode
+//! characters of each class are matched explicitly.
+
+const WHITESPACE: u8 = 1 << 0;
+const NEWLINE: u8 = 1 << 1;
+
+/// Class bits of all characters below `U+0100`.
+static LATIN1_CLASSES: [u8; 256] = {
+    let mut table = [0; 256];
+
+    table[0x09] = WHITESPACE; // \t
+    table[0x20] = WHITESPACE; // space
+    table[0xA0] = WHITESPACE; // no-break space
+
+    table[0x0A] = NEWLINE; // \n
+    table[0x0B] = NEWLINE; // vertical tab
+    table[0x0C] = NEWLINE; // form feed
+    table[0x0D] = NEWLINE; // \r
+    table[0x85] = NEWLINE; // NEXT LINE from latin1
+
+    table
+};
+
+#[inline]
+fn latin1_class(c: char) -> Option<u8> {
+    LATIN1_CLASSES.get(c as usize).copied()
+}
+
+/// Returns `true` for horizontal whitespace, including the Unicode spaces and bidi markers.
+#[inline]
+pub fn is_whitespace(c: char) -> bool {
+    match latin1_class(c) {
+        Some(class) => class & WHITESPACE != 0,
+        None => matches!(
+            c,
+            '\u{1680}' // ogham space mark
+                | '\u{202F}' // narrow no-break space
+                | '\u{3000}' // ideographic space
+
+                // Bidi markers
+                | '\u{200E}' // LEFT-TO-RIGHT MARK
+                | '\u{200F}' // RIGHT-TO-LEFT MARK
+        ),
+    }
+}
+
+/// Returns `true` for characters which end a line. `\r\n` is a single newline made of two
+/// newline characters.
+#[inline]
+pub fn is_newline(c: char) -> bool {
+    match latin1_class(c) {
+        Some(class) => class & NEWLINE != 0,
+        None => matches!(
+            c,
+            '\u{2028}' // LINE SEPARATOR
+                | '\u{2029}' // PARAGRAPH SEPARATOR
+        ),
+    }
+}
@@ -0,0 +1,430 @@
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use super::{Diagnostic, Stage};
+
+/// Extended documentation of a diagnostic rule, e.g. for `neotex explain` or the hover of an
+/// editor.
+///
+/// With the `serde` feature enabled it serializes to e.g.
+///
+/// ```json
+/// {
+///   "code": "E0004",
+///   "rule": "missing-body",
+///   "stage": "expansion",
+///   "summary": "a definition without replacement text",
+///   "description": "...",
+///   "example": "\\newcommand{\\R}",
+///   "fixed": "\\newcommand{\\R}{\\mathbb{R}}"
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Explanation {
+    /// Stable code of the rule, e.g. `E0004`. Codes are never reused, even if the rule is
+    /// removed.
+    pub code: &'static str,
+    /// Rule id of the diagnostics, e.g. `missing-body`
+    pub rule: &'static str,
+    /// The stage reporting the diagnostics
+    pub stage: Stage,
+    /// Single line summary
+    pub summary: &'static str,
+    /// Why the diagnostic is reported and how to fix it
+    pub description: &'static str,
+    /// Source reported by the rule, `None` if it does not depend on the source alone
+    pub example: Option<&'static str>,
+    /// The example with the problem fixed
+    pub fixed: Option<&'static str>,
+}
+
+/// The explanations of all rules ordered by code.
+pub const EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: "E0001",
+        rule: "missing-name",
+        stage: Stage::Expansion,
+        summary: "a definition without the name of the defined command",
+        description: "\\newcommand, \\def and their variants are followed by the command they \
+                      define, environment definitions by the name of the environment in braces. \
+                      The definition is ignored until the name is added.",
+        example: Some(r"\newcommand{}{x}"),
+        fixed: Some(r"\newcommand{\x}{x}"),
+    },
+    Explanation {
+        code: "E0002",
+        rule: "invalid-arg-count",
+        stage: Stage::Expansion,
+        summary: "an argument count which is not a number between 1 and 9",
+        description: "The optional argument in brackets after the name declares the number of \
+                      arguments, which TeX limits to nine. Omit it for a command without \
+                      arguments.",
+        example: Some(r"\newcommand{\pair}[x]{(#1, #2)}"),
+        fixed: Some(r"\newcommand{\pair}[2]{(#1, #2)}"),
+    },
+    Explanation {
+        code: "E0003",
+        rule: "unclosed-group",
+        stage: Stage::Expansion,
+        summary: "a definition whose braces are not closed",
+        description: "The name or the replacement text of a definition opens a brace group \
+                      which is never closed, so the definition would swallow the rest of the \
+                      file. Add the missing closing brace.",
+        example: Some(r"\newcommand{\R}{\mathbb{R}"),
+        fixed: Some(r"\newcommand{\R}{\mathbb{R}}"),
+    },
+    Explanation {
+        code: "E0004",
+        rule: "missing-body",
+        stage: Stage::Expansion,
+        summary: "a definition without replacement text",
+        description: "Every definition needs the text its command is replaced with, in braces \
+                      after the name and the arguments. Use an empty group for a command which \
+                      expands to nothing.",
+        example: Some(r"\newcommand{\R}"),
+        fixed: Some(r"\newcommand{\R}{\mathbb{R}}"),
+    },
+    Explanation {
+        code: "E0005",
+        rule: "invalid-parameter-number",
+        stage: Stage::Expansion,
+        summary: "parameters of a \\def which are not numbered #1, #2 and so on",
+        description: "TeX requires the parameters in the parameter text of a \\def to be \
+                      numbered consecutively starting at #1.",
+        example: Some(r"\def\pair(#1,#3){(#1, #3)}"),
+        fixed: Some(r"\def\pair(#1,#2){(#1, #2)}"),
+    },
+    Explanation {
+        code: "E0006",
+        rule: "paragraph-in-argument",
+        stage: Stage::Expansion,
+        summary: "a paragraph break in the argument of a short command",
+        description: "Commands defined with \\newcommand* or \\def without \\long do not accept \
+                      blank lines or \\par in their arguments, TeX stops with 'Runaway \
+                      argument'. Remove the break or define the command without the star.",
+        example: Some("\\newcommand*{\\note}[1]{\\textit{#1}}\n\\note{First\n\nSecond}"),
+        fixed: Some("\\newcommand{\\note}[1]{\\textit{#1}}\n\\note{First\n\nSecond}"),
+    },
+    Explanation {
+        code: "E0007",
+        rule: "shadowed-command",
+        stage: Stage::Expansion,
+        summary: "a definition replacing a standard command",
+        description: "Redefining a command of LaTeX or a common package changes every use of it, \
+                      including the uses in packages. Pick a new name unless the redefinition \
+                      is intended.",
+        example: Some(r"\renewcommand{\vec}[1]{\mathbf{#1}}"),
+        fixed: Some(r"\newcommand{\bvec}[1]{\mathbf{#1}}"),
+    },
+    Explanation {
+        code: "E0008",
+        rule: "missing-item",
+        stage: Stage::Structure,
+        summary: "text in a list before the first \\item",
+        description: "The content of itemize, enumerate and description has to start with an \
+                      \\item, LaTeX stops with 'Something's wrong--perhaps a missing \\item'.",
+        example: Some("\\begin{itemize}\n  First\n  \\item Second\n\\end{itemize}"),
+        fixed: Some("\\begin{itemize}\n  \\item First\n  \\item Second\n\\end{itemize}"),
+    },
+    Explanation {
+        code: "E0009",
+        rule: "paragraph-break",
+        stage: Stage::Structure,
+        summary: "a blank line where paragraphs are not allowed",
+        description: "Tables and math environments cannot contain paragraph breaks. Remove the \
+                      blank line, or end the row with \\\\ in a table.",
+        example: Some("\\begin{tabular}{l}\n  a\n\n  b\n\\end{tabular}"),
+        fixed: Some("\\begin{tabular}{l}\n  a \\\\\n  b\n\\end{tabular}"),
+    },
+    Explanation {
+        code: "E0010",
+        rule: "column-count",
+        stage: Stage::Structure,
+        summary: "a row with more or fewer cells than columns",
+        description: "A row of a table has more cells than the column spec declares, which is \
+                      an error, or a row of a matrix has a different number of cells than the \
+                      first row, which is most likely a typo.",
+        example: Some("\\begin{tabular}{ll}\n  a & b & c \\\\\n\\end{tabular}"),
+        fixed: Some("\\begin{tabular}{lll}\n  a & b & c \\\\\n\\end{tabular}"),
+    },
+    Explanation {
+        code: "E0011",
+        rule: "missing-package",
+        stage: Stage::Structure,
+        summary: "a math command whose package is not loaded",
+        description: "The command is provided by a package which the document does not load. \
+                      The fix inserts the \\usepackage after the last package of the preamble.",
+        example: Some(r"\(\mathbb{R}\)"),
+        fixed: Some("\\usepackage{amssymb}\n\\(\\mathbb{R}\\)"),
+    },
+    Explanation {
+        code: "E0012",
+        rule: "unclosed-at-eof",
+        stage: Stage::Structure,
+        summary: "environments, math or groups still open at the end of the file",
+        description: "The file ends before everything opened in it is closed, often because it \
+                      was truncated or \\end{document} is missing. The diagnostic lists every \
+                      open construct in the order it was opened.",
+        example: Some("\\begin{document}\nText"),
+        fixed: Some("\\begin{document}\nText\n\\end{document}"),
+    },
+    Explanation {
+        code: "E0013",
+        rule: "undefined-reference",
+        stage: Stage::Analysis,
+        summary: "a reference to a label which is not defined",
+        description: "No \\label with the referenced key exists in the document, LaTeX prints \
+                      '??' instead of the number.",
+        example: Some(r"\section{Intro} see~\ref{sec:intro}"),
+        fixed: Some(r"\section{Intro}\label{sec:intro} see~\ref{sec:intro}"),
+    },
+    Explanation {
+        code: "E0014",
+        rule: "duplicate-label",
+        stage: Stage::Analysis,
+        summary: "a label key which is defined more than once",
+        description: "References to the key resolve to the last definition only. Give every \
+                      label a unique key.",
+        example: Some("\\section{A}\\label{sec:a}\n\\section{B}\\label{sec:a} see~\\ref{sec:a}"),
+        fixed: Some("\\section{A}\\label{sec:a}\n\\section{B}\\label{sec:b} see~\\ref{sec:a}"),
+    },
+    Explanation {
+        code: "E0015",
+        rule: "unused-label",
+        stage: Stage::Analysis,
+        summary: "a label which is never referenced",
+        description: "Nothing refers to the label, it can be removed unless it is referenced \
+                      from another document.",
+        example: Some(r"\section{Intro}\label{sec:intro}"),
+        fixed: Some(r"\section{Intro}\label{sec:intro} see~\ref{sec:intro}"),
+    },
+    Explanation {
+        code: "E0016",
+        rule: "dollar-math",
+        stage: Stage::Lint,
+        summary: "math delimited by $ or $$",
+        description: "\\(...\\) and \\[...\\] report unbalanced delimiters better than $ and \
+                      $$, and $$ breaks the vertical spacing of LaTeX. The lint is off by \
+                      default.",
+        example: Some(r"$x$"),
+        fixed: Some(r"\(x\)"),
+    },
+    Explanation {
+        code: "E0017",
+        rule: "tie-before-reference",
+        stage: Stage::Lint,
+        summary: "a breakable space in front of a reference",
+        description: "A line break between a word and the following \\ref or \\cite leaves the \
+                      number alone at the start of the line. The tie ~ keeps both together.",
+        example: Some(r"\section{Intro}\label{sec:intro} see \ref{sec:intro}"),
+        fixed: Some(r"\section{Intro}\label{sec:intro} see~\ref{sec:intro}"),
+    },
+    Explanation {
+        code: "E0018",
+        rule: "double-space",
+        stage: Stage::Lint,
+        summary: "more than one space after a period",
+        description: "TeX collapses the spaces anyway, a single space keeps the source tidy.",
+        example: Some("A sentence.  Another one."),
+        fixed: Some("A sentence. Another one."),
+    },
+    Explanation {
+        code: "E0019",
+        rule: "deprecated-package",
+        stage: Stage::Lint,
+        summary: "a package which has been superseded",
+        description: "The package is obsolete and may conflict with current packages. The fix \
+                      replaces it with its successor.",
+        example: Some(r"\usepackage{epsfig}"),
+        fixed: Some(r"\usepackage{graphicx}"),
+    },
+    Explanation {
+        code: "E0020",
+        rule: "unused-suppression",
+        stage: Stage::Lint,
+        summary: "a suppression comment which does not suppress anything",
+        description: "The diagnostic named by the neotex-ignore comment is not reported on its \
+                      line, most likely because the problem has been fixed. Remove the comment.",
+        example: Some("% neotex-ignore-next-line missing-body\n\\newcommand{\\R}{\\mathbb{R}}"),
+        fixed: Some("\\newcommand{\\R}{\\mathbb{R}}"),
+    },
+    Explanation {
+        code: "E0021",
+        rule: "limit-exceeded",
+        stage: Stage::Preparse,
+        summary: "a source exceeding a limit of the parse options",
+        description: "The source is larger or nested deeper than the limits allow, which are \
+                      meant for untrusted input. The affected analysis is skipped. Raise the \
+                      limit if the source is trusted.",
+        example: None,
+        fixed: None,
+    },
+    Explanation {
+        code: "E0022",
+        rule: "bracket-after-line-break",
+        stage: Stage::Lint,
+        summary: "a '[' at the start of a line following an unknown command",
+        description: "Commands with an optional argument skip the line break and read a '[' on \
+                      the next line as its start, e.g. \\\\ in a table or an align environment. \
+                      Whether the command takes one is unknown, wrap the bracket in braces if \
+                      it is meant literally.",
+        example: Some("\\begin{tabular}{l}\n  a \\\\\n  [0, 1]\n\\end{tabular}"),
+        fixed: Some("\\begin{tabular}{l}\n  a \\\\\n  {[}0, 1]\n\\end{tabular}"),
+    },
+    Explanation {
+        code: "E0023",
+        rule: "unbalanced-brace",
+        stage: Stage::Structure,
+        summary: "a '}' without a '{' or a '{' still open at the end of its environment",
+        description: "Braces have to be balanced within every environment and math block. A \
+                      stray '}' is ignored, braces still open at the end of the environment are \
+                      closed there. The related locations point to the unclosed braces.",
+        example: Some(r"\begin{center} \textbf{Title \end{center}"),
+        fixed: Some(r"\begin{center} \textbf{Title} \end{center}"),
+    },
+    Explanation {
+        code: "E0024",
+        rule: "invalid-char-code",
+        stage: Stage::Preparse,
+        summary: "a character code which does not denote a character",
+        description:
+            "\\char, \\symbol and the ^^ notation write a character by its code. The code \
+                      has to be at most \"10FFFF and must not be a surrogate, and ^^ has to be \
+                      followed by an ASCII character or two lowercase hex digits.",
+        example: Some(r#"\char"110000"#),
+        fixed: Some(r#"\char"10FFFF"#),
+    },
+    Explanation {
+        code: "E0025",
+        rule: "missing-argument",
+        stage: Stage::Analysis,
+        summary: "a call with fewer arguments than its command takes",
+        description: "A command takes its arguments from the brace groups following it. TeX \
+                      takes the next token instead if a group is missing, so a chain of groups \
+                      broken by a space swallows the following word or command, which is reported \
+                      as a warning. An argument cut off by a closing brace, a paragraph or the end \
+                      of the document is an error. The related locations point to the token TeX \
+                      would take and to the definition of the command.",
+        example: Some(r"\frac{1} 2"),
+        fixed: Some(r"\frac{1}{2}"),
+    },
+    Explanation {
+        code: "E0026",
+        rule: "ambiguous-path",
+        stage: Stage::Project,
+        summary: "an include whose file depends on the case sensitivity of the file system",
+        description: "Linux file systems tell chapter1.tex and Chapter1.tex apart, the ones of \
+                      macOS and Windows do not, so the include loads a different file or none at \
+                      all depending on where the document is compiled. Spell the path exactly \
+                      like the file name and do not keep files differing only in case.",
+        example: None,
+        fixed: None,
+    },
+    Explanation {
+        code: "E0027",
+        rule: "content-after-document",
+        stage: Stage::Structure,
+        summary: "text after \\end{document}",
+        description: "TeX stops reading the file at \\end{document}, so everything after it \
+                      is ignored. Move the text into the document or turn it into a comment.",
+        example: Some("\\begin{document}\n\\end{document}\nNotes"),
+        fixed: Some("\\begin{document}\n\\end{document}\n% Notes"),
+    },
+    Explanation {
+        code: "E0028",
+        rule: "missing-document",
+        stage: Stage::Structure,
+        summary: "a document without a document environment",
+        description: "A file with a \\documentclass is compiled on its own and needs the text \
+                      in a document environment. Files without one, e.g. chapters, class and \
+                      package files, are only checked if the options require it.",
+        example: Some("\\documentclass{article}\nText"),
+        fixed: Some("\\documentclass{article}\n\\begin{document}\nText\n\\end{document}"),
+    },
+    Explanation {
+        code: "E0029",
+        rule: "shell-escape",
+        stage: Stage::Lint,
+        summary: "a construct which runs external programs",
+        description: "\\write18, \\ShellEscape, \\input|\"command\" and packages like minted run \
+                      programs while compiling and fail unless the engine is started with \
+                      --shell-escape, which allows the document to run any command. Run the \
+                      program once and include its output, or allow the rule for trusted \
+                      documents.",
+        example: Some("\\immediate\\write18{date > today.tex}"),
+        fixed: Some("\\input{today}"),
+    },
+    Explanation {
+        code: "E0030",
+        rule: "recursive-definition",
+        stage: Stage::Expansion,
+        summary: "definitions which call each other endlessly",
+        description: "A command whose body calls the command itself, directly or through other \
+                      definitions, never stops expanding and TeX fails with a full stack. End the \
+                      recursion with a conditional or call the original command saved with \
+                      \\let.",
+        example: Some(r"\renewcommand{\emph}[1]{\textit{\emph{#1}}}"),
+        fixed: Some(r"\let\oldemph\emph \renewcommand{\emph}[1]{\textit{\oldemph{#1}}}"),
+    },
+    Explanation {
+        code: "E0031",
+        rule: "repeated-word",
+        stage: Stage::Lint,
+        summary: "a word following itself",
+        description: "The same word twice in a row, like 'the the', is almost always a typo left \
+                      over from editing a sentence.",
+        example: Some("This is the the proof."),
+        fixed: Some("This is the proof."),
+    },
+    Explanation {
+        code: "E0032",
+        rule: "overused-phrase",
+        stage: Stage::Lint,
+        summary: "a word or phrase used again and again within a few sentences",
+        description: "A word or a phrase of two words used three times within the configured \
+                      window of words, 100 by default, makes a text monotonous. Short and very \
+                      common words are not counted. Vary the wording or restructure the \
+                      sentences.",
+        example: Some("Clearly the sum is finite. Clearly it is positive. Clearly we are done."),
+        fixed: Some("Clearly the sum is finite. It is also positive, so we are done."),
+    },
+    Explanation {
+        code: "E0033",
+        rule: "unknown-word",
+        stage: Stage::Analysis,
+        summary: "a word missing from the dictionary of the spell checker",
+        description: "The word is not in the word list the text is checked against, so it is \
+                      either misspelled or a term the list does not know yet. Fix the spelling \
+                      or add the word to the list.",
+        example: Some("This is teh proof."),
+        fixed: None,
+    },
+];
+
+/// The explanation of a code like `E0004` or a rule id like `missing-body`. Codes are matched
+/// case-insensitively.
+pub fn explain(code: &str) -> Option<&'static Explanation> {
+    EXPLANATIONS
+        .iter()
+        .find(|explanation| explanation.code.eq_ignore_ascii_case(code) || explanation.rule == code)
+}
+
+impl Diagnostic {
+    /// The stable code of the rule, e.g. `E0004` for `missing-body`, see [`explain`].
+    pub fn code(&self) -> Option<&'static str> {
+        EXPLANATIONS
+            .iter()
+            .find(|explanation| explanation.rule == self.rule)
+            .map(|explanation| explanation.code)
+    }
+
+    /// The analysis stage which reported the diagnostic, `None` for rules without an
+    /// [`Explanation`].
+    pub fn stage(&self) -> Option<Stage> {
+        EXPLANATIONS
+            .iter()
+            .find(|explanation| explanation.rule == self.rule)
+            .map(|explanation| explanation.stage)
+    }
+}
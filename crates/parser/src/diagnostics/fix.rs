@@ -0,0 +1,44 @@
+use std::ops::Range;
+
+use super::Diagnostic;
+use crate::expansion::TextEdit;
+
+/// The edits of the fixes of `diagnostics`, ordered by their position.
+///
+/// A fix is only taken as a whole and only if none of its edits overlaps an edit of a fix taken
+/// before, so the edits can be applied together. Two insertions at the same offset overlap as
+/// well, since their order would be arbitrary. Skipped fixes usually apply once the taken ones
+/// are applied and the text is analyzed again.
+///
+/// ```
+/// use parser::{diagnostics::{fixes, Diagnostic, Severity}, expansion::TextEdit};
+///
+/// let insert = |offset: usize, text: &str| TextEdit { range: offset..offset, new_text: text.into() };
+/// let diagnostics = [
+///     Diagnostic::new("a", Severity::Error, 4..4, "").with_fix([insert(4, "}")]),
+///     Diagnostic::new("b", Severity::Error, 0..4, "").with_fix([insert(4, "]"), insert(0, "[")]),
+///     Diagnostic::new("c", Severity::Error, 0..4, ""),
+/// ];
+/// assert_eq!(fixes(&diagnostics), vec![insert(4, "}")]);
+/// ```
+pub fn fixes(diagnostics: &[Diagnostic]) -> Vec<TextEdit> {
+    let mut edits: Vec<TextEdit> = Vec::new();
+    for diagnostic in diagnostics {
+        let conflict = diagnostic.fix.iter().any(|edit| {
+            edits.iter().any(|taken| overlap(&taken.range, &edit.range))
+                || diagnostic
+                    .fix
+                    .iter()
+                    .any(|other| !std::ptr::eq(edit, other) && overlap(&other.range, &edit.range))
+        });
+        if !conflict {
+            edits.extend(diagnostic.fix.iter().cloned());
+        }
+    }
+    edits.sort_by_key(|edit| (edit.range.start, edit.range.end));
+    edits
+}
+
+fn overlap(a: &Range<usize>, b: &Range<usize>) -> bool {
+    a.start == b.start || (a.start < b.end && b.start < a.end)
+}
@@ -0,0 +1,155 @@
+//! Diagnostics reported by the different analysis passes.
+//!
+//! Every pass reports its findings as [`Diagnostic`]s into a [`DiagnosticSink`]. The sink drops
+//! diagnostics which are suppressed by a comment in the source:
+//!
+//! ```tex
+//! % neotex-ignore-next-line missing-body
+//! \newcommand{\broken}
+//! \newcommand{\other}[x]{} % neotex-ignore invalid-arg-count
+//! ```
+//!
+//! Suppressions which never matched a diagnostic are reported as `unused-suppression`. Whole
+//! [`Stage`]s can be disabled, e.g. the warnings of the expansion while drafting, see
+//! [`DiagnosticSink::with_stages`].
+//!
+//! Every rule has a stable code like `E0004`, see [`explain`] for the extended explanations.
+//! [`snippet`] extracts the source lines around a diagnostic for rendering.
+//!
+//! Diagnostics can carry a fix, edits which are safe to apply without review, e.g. the missing
+//! `}` at the end of an environment. [`fixes`] selects the fixes of a whole file.
+
+mod explain;
+mod fix;
+mod snippet;
+mod stage;
+mod suppression;
+
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+pub use explain::{explain, Explanation, EXPLANATIONS};
+pub use fix::fixes;
+pub use snippet::{snippet, truncate, SnippetLine, ELLIPSIS};
+pub use stage::{Stage, Stages};
+use suppression::Suppressions;
+
+use crate::{expansion::TextEdit, LexedStr};
+
+/// Rule id of the diagnostic reported for suppressions which did not suppress anything.
+pub const UNUSED_SUPPRESSION: &str = "unused-suppression";
+
+/// Severity of a [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+pub enum Severity {
+    /// The document is invalid
+    Error,
+    /// The document is valid but most likely not what the author intended
+    Warning,
+    /// Informational note
+    Info,
+}
+
+/// A single finding of an analysis pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Identifier of the rule which reported the diagnostic, e.g. `missing-body`
+    pub rule: &'static str,
+    /// Severity of the diagnostic
+    pub severity: Severity,
+    /// Byte range in the source text the diagnostic refers to
+    pub range: Range<usize>,
+    /// Human readable description
+    pub message: String,
+    /// Other locations involved, e.g. the first definition of a duplicate label
+    pub related: Vec<Related>,
+    /// Edits fixing the diagnostic which are safe to apply without review, empty if there are none
+    pub fix: Vec<TextEdit>,
+}
+
+/// A secondary location of a [`Diagnostic`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Related {
+    /// Byte range in the source text
+    pub range: Range<usize>,
+    /// Describes the role of the location, e.g. `first defined here`
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Creates a new diagnostic.
+    pub fn new(
+        rule: &'static str,
+        severity: Severity,
+        range: Range<usize>,
+        message: impl Into<String>,
+    ) -> Diagnostic {
+        Diagnostic {
+            rule,
+            severity,
+            range,
+            message: message.into(),
+            related: Vec::new(),
+            fix: Vec::new(),
+        }
+    }
+
+    /// Adds a secondary location.
+    pub fn with_related(mut self, range: Range<usize>, message: impl Into<String>) -> Diagnostic {
+        self.related.push(Related { range, message: message.into() });
+        self
+    }
+
+    /// Attaches edits fixing the diagnostic. They are applied by `neotex check --fix`, so only
+    /// fixes which cannot change the meaning of the document belong here.
+    pub fn with_fix(mut self, fix: impl IntoIterator<Item = TextEdit>) -> Diagnostic {
+        self.fix.extend(fix);
+        self
+    }
+}
+
+/// Collects the diagnostics of a single source text and applies comment suppressions.
+#[derive(Debug)]
+pub struct DiagnosticSink {
+    suppressions: Suppressions,
+    stages: Stages,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl DiagnosticSink {
+    /// Creates a sink honoring the suppression comments of `lexed`.
+    pub fn new(lexed: &LexedStr<'_>) -> DiagnosticSink {
+        DiagnosticSink {
+            suppressions: Suppressions::new(lexed),
+            stages: Stages::ALL,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Drops the diagnostics of the stages which are not enabled in `stages`. Suppressions of
+    /// their rules still count as used, so disabling a stage does not report them as unused.
+    pub fn with_stages(mut self, stages: Stages) -> DiagnosticSink {
+        self.stages = stages;
+        self
+    }
+
+    /// Reports a diagnostic unless it is suppressed or its stage is disabled.
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        if !self.suppressions.suppress(&diagnostic) && self.stages.reports(&diagnostic) {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    /// Returns all reported diagnostics followed by one diagnostic for every unused suppression.
+    pub fn finish(self) -> Vec<Diagnostic> {
+        let DiagnosticSink { suppressions, stages, mut diagnostics } = self;
+        diagnostics.extend(suppressions.unused().filter(|diagnostic| stages.reports(diagnostic)));
+        diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests;
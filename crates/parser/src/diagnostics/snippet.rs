@@ -0,0 +1,143 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Marks text removed by [`truncate`] and [`snippet`].
+pub const ELLIPSIS: &str = "…";
+
+/// A line of the source shown by [`snippet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnippetLine {
+    /// 1-based line number in the source
+    pub number: usize,
+    /// Text of the line without the line break, shortened to the requested width
+    pub text: String,
+    /// Byte range in `text` covered by the diagnostic, `None` for context lines
+    pub highlight: Option<Range<usize>>,
+}
+
+/// Truncates `text` to at most `max_width` grapheme clusters, replacing the removed end with
+/// [`ELLIPSIS`]. The cut never splits a grapheme cluster or a control sequence like `\mathbb`,
+/// which is removed completely instead.
+pub fn truncate(text: &str, max_width: usize) -> Cow<'_, str> {
+    let bounds = boundaries(text);
+    if bounds.len() - 1 <= max_width {
+        return Cow::Borrowed(text);
+    }
+    if max_width == 0 {
+        return Cow::Borrowed("");
+    }
+    let end = escape_start(text, bounds[max_width - 1]);
+    Cow::Owned(format!("{}{ELLIPSIS}", &text[..end]))
+}
+
+/// The lines covered by the byte `range` of `source` with `context` lines before and after them.
+/// Lines wider than `max_width` grapheme clusters, but at least three, are shortened around the
+/// start of the highlight with [`ELLIPSIS`] marking the removed text on either side.
+pub fn snippet(
+    source: &str,
+    range: Range<usize>,
+    context: usize,
+    max_width: usize,
+) -> Vec<SnippetLine> {
+    let mut starts = vec![0];
+    starts.extend(source.match_indices('\n').map(|(idx, _)| idx + 1));
+    let line_of = |offset: usize| starts.partition_point(|&start| start <= offset) - 1;
+
+    let first = line_of(range.start);
+    let last = match range.is_empty() {
+        true => first,
+        false => line_of(range.end - 1).max(first),
+    };
+    let lines = first.saturating_sub(context)..(last + context + 1).min(starts.len());
+
+    lines
+        .map(|line| {
+            let start = starts[line];
+            let end = starts.get(line + 1).map_or(source.len(), |&next| next - 1);
+            let text = source[start..end].strip_suffix('\r').unwrap_or(&source[start..end]);
+            let clamp = |offset: usize| offset.clamp(start, start + text.len()) - start;
+            let highlight =
+                (first..=last).contains(&line).then(|| clamp(range.start)..clamp(range.end));
+            let (text, highlight) = excerpt(text, highlight, max_width.max(3));
+            SnippetLine { number: line + 1, text, highlight }
+        })
+        .collect()
+}
+
+/// Shortens `text` to `max_width` grapheme clusters keeping the start of `highlight` visible and
+/// moves the highlight along.
+fn excerpt(
+    text: &str,
+    highlight: Option<Range<usize>>,
+    max_width: usize,
+) -> (String, Option<Range<usize>>) {
+    let bounds = boundaries(text);
+    let width = bounds.len() - 1;
+    if width <= max_width {
+        return (text.to_string(), highlight);
+    }
+
+    // start a third of the width in front of the highlight, but show as much as possible
+    let focus = highlight.as_ref().map_or(0, |highlight| highlight.start);
+    let focus_idx = bounds.partition_point(|&bound| bound < focus).min(width);
+    let mut start = 0;
+    if focus_idx >= max_width / 2 {
+        let idx = (focus_idx - max_width / 3).min(width - (max_width - 1));
+        start = escape_end(text, bounds[idx]).min(focus);
+    }
+
+    let prefix = if start > 0 { ELLIPSIS } else { "" };
+    let remaining = max_width - usize::from(start > 0);
+    let start_idx = bounds.partition_point(|&bound| bound < start);
+    let (end, suffix) = match width - start_idx <= remaining {
+        true => (text.len(), ""),
+        false => (escape_start(text, bounds[start_idx + remaining - 1]).max(start), ELLIPSIS),
+    };
+
+    let shift = |offset: usize| offset.clamp(start, end) - start + prefix.len();
+    let highlight = highlight.map(|highlight| shift(highlight.start)..shift(highlight.end));
+    (format!("{prefix}{}{suffix}", &text[start..end]), highlight)
+}
+
+/// Byte offsets of the grapheme cluster boundaries of `text`, including `0` and `text.len()`.
+fn boundaries(text: &str) -> Vec<usize> {
+    let mut bounds: Vec<_> = text.grapheme_indices(true).map(|(idx, _)| idx).collect();
+    bounds.push(text.len());
+    bounds
+}
+
+/// Moves `offset` in front of the control sequence it splits, if any.
+fn escape_start(text: &str, offset: usize) -> usize {
+    control_sequence(text, offset).map_or(offset, |sequence| sequence.start)
+}
+
+/// Moves `offset` behind the control sequence it splits, if any.
+fn escape_end(text: &str, offset: usize) -> usize {
+    control_sequence(text, offset).map_or(offset, |sequence| sequence.end)
+}
+
+/// The control sequence, e.g. `\alpha` or `\{`, which contains `offset` not at its boundaries.
+fn control_sequence(text: &str, offset: usize) -> Option<Range<usize>> {
+    let bytes = text.as_bytes();
+    let letters = bytes[..offset].iter().rev().take_while(|b| b.is_ascii_alphabetic()).count();
+    let backslash = offset - letters;
+    let escapes = bytes[..backslash].iter().rev().take_while(|&&b| b == b'\\').count();
+    if escapes % 2 == 0 {
+        return None;
+    }
+
+    let start = backslash - 1;
+    match letters {
+        // `\` followed by a single character, e.g. `\{` or `\\`
+        0 => {
+            let len = text[backslash..].chars().next()?.len_utf8();
+            Some(start..backslash + len)
+        }
+        _ => {
+            let rest = bytes[offset..].iter().take_while(|b| b.is_ascii_alphabetic()).count();
+            (rest > 0).then_some(start..offset + rest)
+        }
+    }
+}
@@ -0,0 +1,95 @@
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use super::Diagnostic;
+
+/// The analysis stage a [`Diagnostic`] originates from, see [`Diagnostic::stage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+pub enum Stage {
+    /// Lexing and the checks working on the tokens alone, e.g. of `\catcode` assignments
+    Preparse,
+    /// Resolution and expansion of macro definitions
+    Expansion,
+    /// The structure of the document: groups, environments and their content
+    Structure,
+    /// Semantic checks like references and the arguments of calls
+    Analysis,
+    /// Style rules and unused suppressions
+    Lint,
+    /// Checks across the files of a project, e.g. the resolution of includes
+    Project,
+}
+
+impl Stage {
+    /// All stages in the order they run.
+    pub const ALL: [Stage; 6] = [
+        Stage::Preparse,
+        Stage::Expansion,
+        Stage::Structure,
+        Stage::Analysis,
+        Stage::Lint,
+        Stage::Project,
+    ];
+
+    /// Name of the stage in the configuration and on the command line, e.g. `expansion`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Preparse => "preparse",
+            Stage::Expansion => "expansion",
+            Stage::Structure => "structure",
+            Stage::Analysis => "analysis",
+            Stage::Lint => "lint",
+            Stage::Project => "project",
+        }
+    }
+
+    /// The stage with the given [`name`](Self::name).
+    pub fn from_name(name: &str) -> Option<Stage> {
+        Stage::ALL.into_iter().find(|stage| stage.name() == name)
+    }
+}
+
+/// The stages whose diagnostics are reported, all of them by default.
+///
+/// ```
+/// use parser::diagnostics::{Stage, Stages};
+///
+/// let mut stages = Stages::default();
+/// stages.disable(Stage::Expansion);
+/// assert!(!stages.is_enabled(Stage::Expansion));
+/// assert!(stages.is_enabled(Stage::Preparse));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Stages(u8);
+
+impl Stages {
+    /// All stages enabled.
+    pub const ALL: Stages = Stages((1 << Stage::ALL.len()) - 1);
+
+    /// Returns `true` if the diagnostics of `stage` are reported.
+    pub fn is_enabled(self, stage: Stage) -> bool {
+        self.0 & (1 << stage as u8) != 0
+    }
+
+    /// Reports the diagnostics of `stage`.
+    pub fn enable(&mut self, stage: Stage) {
+        self.0 |= 1 << stage as u8;
+    }
+
+    /// Drops the diagnostics of `stage`.
+    pub fn disable(&mut self, stage: Stage) {
+        self.0 &= !(1 << stage as u8);
+    }
+
+    /// Returns `true` if `diagnostic` is reported. Diagnostics of unknown rules always are.
+    pub fn reports(self, diagnostic: &Diagnostic) -> bool {
+        diagnostic.stage().map_or(true, |stage| self.is_enabled(stage))
+    }
+}
+
+impl Default for Stages {
+    fn default() -> Stages {
+        Stages::ALL
+    }
+}
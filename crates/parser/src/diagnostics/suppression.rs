@@ -0,0 +1,119 @@
+use std::ops::Range;
+
+use super::{Diagnostic, Severity, UNUSED_SUPPRESSION};
+use crate::{LexedStr, SyntaxKind};
+
+const IGNORE_NEXT_LINE: &str = "neotex-ignore-next-line";
+const IGNORE: &str = "neotex-ignore";
+
+/// Suppression comments of a source text.
+#[derive(Debug)]
+pub(super) struct Suppressions {
+    line_starts: Vec<usize>,
+    items: Vec<Suppression>,
+}
+
+/// A single `% neotex-ignore` or `% neotex-ignore-next-line` comment.
+#[derive(Debug)]
+struct Suppression {
+    /// Line the suppression applies to
+    line: usize,
+    /// Byte range of the comment
+    range: Range<usize>,
+    /// Suppressed rules and whether they suppressed a diagnostic. An empty list suppresses all
+    /// rules.
+    rules: Vec<(String, bool)>,
+    used: bool,
+}
+
+impl Suppressions {
+    pub(super) fn new(lexed: &LexedStr<'_>) -> Suppressions {
+        let text = lexed.as_str();
+        let line_starts =
+            std::iter::once(0).chain(text.match_indices('\n').map(|(idx, _)| idx + 1)).collect();
+
+        let mut suppressions = Suppressions { line_starts, items: Vec::new() };
+
+        for token in lexed.spanned_tokens() {
+            if !matches!(token.kind, SyntaxKind::Comment | SyntaxKind::AComment) {
+                continue;
+            }
+
+            let comment = token.text.trim_start_matches('%').trim_start();
+            let line = suppressions.line(token.range.start);
+
+            let (line, rules) = if let Some(rules) = directive(comment, IGNORE_NEXT_LINE) {
+                (line + 1, rules)
+            } else if let Some(rules) = directive(comment, IGNORE) {
+                (line, rules)
+            } else {
+                continue;
+            };
+
+            let rules = rules
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|rule| !rule.is_empty())
+                .map(|rule| (rule.to_string(), false))
+                .collect();
+
+            suppressions.items.push(Suppression { line, range: token.range, rules, used: false });
+        }
+
+        suppressions
+    }
+
+    /// Returns `true` if `diagnostic` is suppressed and marks the matching suppression as used.
+    pub(super) fn suppress(&mut self, diagnostic: &Diagnostic) -> bool {
+        if diagnostic.rule == UNUSED_SUPPRESSION {
+            return false;
+        }
+
+        let line = self.line(diagnostic.range.start);
+        let mut suppressed = false;
+
+        for item in self.items.iter_mut().filter(|item| item.line == line) {
+            if item.rules.is_empty() {
+                item.used = true;
+                suppressed = true;
+            }
+            for (_, used) in item.rules.iter_mut().filter(|(rule, _)| rule == diagnostic.rule) {
+                *used = true;
+                suppressed = true;
+            }
+        }
+
+        suppressed
+    }
+
+    /// Diagnostics for every suppressed rule which never matched a diagnostic.
+    pub(super) fn unused(self) -> impl Iterator<Item = Diagnostic> {
+        self.items.into_iter().flat_map(|item| {
+            let unused: Vec<_> = match item.rules.is_empty() {
+                true if !item.used => vec!["all rules".to_string()],
+                _ => {
+                    item.rules.into_iter().filter(|(_, used)| !used).map(|(rule, _)| rule).collect()
+                }
+            };
+
+            unused.into_iter().map(move |rule| {
+                Diagnostic::new(
+                    UNUSED_SUPPRESSION,
+                    Severity::Warning,
+                    item.range.clone(),
+                    format!("suppression of '{rule}' does not match any diagnostic"),
+                )
+            })
+        })
+    }
+
+    fn line(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset) - 1
+    }
+}
+
+/// Returns the rule list following `directive` at the start of `comment`.
+fn directive<'a>(comment: &'a str, directive: &str) -> Option<&'a str> {
+    comment
+        .strip_prefix(directive)
+        .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
@@ -0,0 +1,220 @@
+use super::{
+    explain, fixes, snippet, truncate, Diagnostic, DiagnosticSink, Severity, SnippetLine, Stage,
+    Stages, EXPLANATIONS, UNUSED_SUPPRESSION,
+};
+use crate::{analysis, ast, charcode, expansion, LexedStr};
+
+fn check(input: &str) -> Vec<(&'static str, usize)> {
+    let lexed = LexedStr::new(input);
+    let store = expansion::resolve(&lexed);
+
+    let mut sink = DiagnosticSink::new(&lexed);
+    for error in store.errors() {
+        sink.push(error.to_diagnostic(&lexed));
+    }
+
+    sink.finish()
+        .into_iter()
+        .map(|diag| (diag.rule, input[..diag.range.start].matches('\n').count()))
+        .collect()
+}
+
+#[test]
+fn unsuppressed() {
+    assert_eq!(check("\\newcommand{\\a}[x]{a}"), vec![("invalid-arg-count", 0)]);
+}
+
+#[test]
+fn ignore_next_line() {
+    let input = "% neotex-ignore-next-line invalid-arg-count\n\\newcommand{\\a}[x]{a}";
+    assert_eq!(check(input), vec![]);
+}
+
+#[test]
+fn ignore_end_of_line() {
+    let input = "\\newcommand{\\a}[x]{a} % neotex-ignore invalid-arg-count, missing-body\n";
+    assert_eq!(check(input), vec![(UNUSED_SUPPRESSION, 0)]);
+}
+
+#[test]
+fn suppression_only_applies_to_its_line() {
+    let input = "% neotex-ignore-next-line invalid-arg-count\n\n\\newcommand{\\a}[x]{a}";
+    assert_eq!(check(input), vec![("invalid-arg-count", 2), (UNUSED_SUPPRESSION, 0)]);
+}
+
+#[test]
+fn ignore_all_rules() {
+    let lexed = LexedStr::new("x % neotex-ignore\ny % neotex-ignore");
+    let mut sink = DiagnosticSink::new(&lexed);
+    sink.push(Diagnostic::new("any-rule", Severity::Warning, 0..1, "x"));
+
+    let diagnostics = sink.finish();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].rule, UNUSED_SUPPRESSION);
+    assert_eq!(diagnostics[0].range.start, 20);
+}
+
+#[test]
+fn disabled_stages() {
+    let input = "\\newcommand{\\a}[x]{a}\n\\newcommand{\\b} % neotex-ignore missing-body";
+    let lexed = LexedStr::new(input);
+    let mut stages = Stages::default();
+    stages.disable(Stage::Expansion);
+    let mut sink = DiagnosticSink::new(&lexed).with_stages(stages);
+    for error in expansion::resolve(&lexed).errors() {
+        sink.push(error.to_diagnostic(&lexed));
+    }
+
+    sink.push(Diagnostic::new(analysis::DOUBLE_SPACE, Severity::Warning, 0..1, "x"));
+
+    // the suppression is used by the diagnostic of its line although that is dropped
+    let rules: Vec<_> = sink.finish().iter().map(|diag| diag.rule).collect();
+    assert_eq!(rules, [analysis::DOUBLE_SPACE]);
+    assert_eq!(Stage::from_name("lint"), Some(Stage::Lint));
+}
+
+#[test]
+fn explanations() {
+    for (idx, explanation) in EXPLANATIONS.iter().enumerate() {
+        assert_eq!(explanation.code, format!("E{:04}", idx + 1));
+    }
+
+    let errors = [
+        expansion::ResolverErrorKind::MissingName,
+        expansion::ResolverErrorKind::InvalidArgCount,
+        expansion::ResolverErrorKind::UnclosedGroup,
+        expansion::ResolverErrorKind::MissingBody,
+        expansion::ResolverErrorKind::InvalidParameterNumber,
+    ];
+    let rules = [
+        expansion::PARAGRAPH_IN_ARGUMENT,
+        expansion::SHADOWED_COMMAND,
+        expansion::RECURSIVE_DEFINITION,
+        ast::MISSING_ITEM,
+        ast::PARAGRAPH_BREAK,
+        ast::COLUMN_COUNT,
+        ast::MISSING_PACKAGE,
+        ast::UNCLOSED_AT_EOF,
+        ast::UNBALANCED_BRACE,
+        ast::CONTENT_AFTER_DOCUMENT,
+        ast::MISSING_DOCUMENT,
+        analysis::UNDEFINED_REFERENCE,
+        analysis::DUPLICATE_LABEL,
+        analysis::UNUSED_LABEL,
+        analysis::DOLLAR_MATH,
+        analysis::TIE_BEFORE_REFERENCE,
+        analysis::DOUBLE_SPACE,
+        analysis::DEPRECATED_PACKAGE,
+        analysis::BRACKET_AFTER_LINE_BREAK,
+        analysis::MISSING_ARGUMENT,
+        analysis::SHELL_ESCAPE,
+        analysis::REPEATED_WORD,
+        analysis::OVERUSED_PHRASE,
+        analysis::UNKNOWN_WORD,
+        charcode::INVALID_CHAR_CODE,
+        UNUSED_SUPPRESSION,
+    ];
+    for rule in errors.iter().map(|error| error.rule()).chain(rules) {
+        assert_eq!(explain(rule).map(|explanation| explanation.rule), Some(rule));
+    }
+
+    assert_eq!(explain("e0004").unwrap().rule, "missing-body");
+    assert_eq!(explain("E9999"), None);
+    let diagnostic = Diagnostic::new(UNUSED_SUPPRESSION, Severity::Warning, 0..0, "");
+    assert_eq!(diagnostic.code(), Some("E0020"));
+    assert_eq!(diagnostic.stage(), Some(Stage::Lint));
+}
+
+#[test]
+fn truncation() {
+    assert_eq!(truncate("short", 5), "short");
+    assert_eq!(truncate("longer text", 6), "longe…");
+    assert_eq!(truncate("text", 0), "");
+
+    // combining accents, flags and emoji sequences count as one
+    assert_eq!(truncate("cafe\u{301} au lait", 5), "cafe\u{301}…");
+    assert_eq!(truncate("cafe\u{301} au lait", 4), "caf…");
+    assert_eq!(truncate("🇩🇪🇫🇷🇮🇹🇪🇸", 3), "🇩🇪🇫🇷…");
+    assert_eq!(truncate("👩‍👩‍👧 family 👨‍👨‍👦", 2), "👩‍👩‍👧…");
+    assert_eq!(truncate("한국어 텍스트", 4), "한국어…");
+    assert_eq!(truncate("日本語のテキスト", 8), "日本語のテキスト");
+
+    // control sequences are removed completely
+    assert_eq!(truncate(r"see \mathbb{R}", 8), "see …");
+    assert_eq!(truncate(r"see \mathbb{R}", 12), r"see \mathbb…");
+    assert_eq!(truncate(r"a \{ b", 4), "a …");
+    assert_eq!(truncate(r"a \\ b", 4), "a …");
+    assert_eq!(truncate(r"a \\b c", 5), r"a \\…");
+    assert_eq!(truncate(r"\ä and more", 2), "…");
+}
+
+#[test]
+fn snippets() {
+    let line = |number: usize, text: &str, highlight: Option<std::ops::Range<usize>>| SnippetLine {
+        number,
+        text: text.to_string(),
+        highlight,
+    };
+
+    let source = "first\r\nsecond ünïcödé\r\nthird\nfourth";
+    let start = source.find("ünï").unwrap();
+    assert_eq!(
+        snippet(source, start..start + "ünï".len(), 0, 80),
+        vec![line(2, "second ünïcödé", Some(7..12))]
+    );
+    assert_eq!(
+        snippet(source, start..source.find("rd").unwrap(), 1, 80),
+        vec![
+            line(1, "first", None),
+            line(2, "second ünïcödé", Some(7..18)),
+            line(3, "third", Some(0..3)),
+            line(4, "fourth", None),
+        ]
+    );
+
+    // empty ranges at the end of the source and at a line break
+    assert_eq!(
+        snippet(source, source.len()..source.len(), 1, 80),
+        vec![line(3, "third", None), line(4, "fourth", Some(6..6))]
+    );
+    let end = source.find('\r').unwrap();
+    assert_eq!(snippet(source, end..end + 1, 0, 80), vec![line(1, "first", Some(5..5))]);
+
+    // long lines are shortened around the highlight
+    let source = format!("{}\\undefined{{x}} {}", "ä".repeat(30), "ö".repeat(30));
+    let start = source.find('\\').unwrap();
+    let lines = snippet(&source, start..start + r"\undefined".len(), 0, 20);
+    let text = format!("…{}\\undefined{{x…", "ä".repeat(6));
+    assert_eq!(lines, vec![line(1, &text, Some(15..25))]);
+    assert_eq!(&lines[0].text[15..25], r"\undefined");
+
+    // the start of an excerpt does not split a control sequence either
+    let source = format!("{}\\alpha + \\beta {}", "x".repeat(30), "y".repeat(30));
+    let start = source.find("beta").unwrap() - 1;
+    let lines = snippet(&source, start..start + 5, 0, 12);
+    assert_eq!(lines, vec![line(1, r"… + \beta y…", Some(6..11))]);
+}
+
+#[test]
+fn fixed_braces() {
+    let fix = |input: &str| {
+        let lexed = LexedStr::new(input);
+        let document = ast::Document::new(&lexed);
+        let mut fixed = input.to_string();
+        for edit in fixes(document.diagnostics()).iter().rev() {
+            fixed.replace_range(edit.range.clone(), &edit.new_text);
+        }
+        let lexed = LexedStr::new(&fixed);
+        assert!(ast::Document::new(&lexed).diagnostics().is_empty(), "{fixed:?}");
+        fixed
+    };
+
+    assert_eq!(fix("\\begin{proof}{a {b\n\\end{proof}"), "\\begin{proof}{a {b\n}}\\end{proof}");
+    assert_eq!(fix("\\textbf{a {b"), "\\textbf{a {b}}");
+    assert_eq!(fix("\\textbf{a % b"), "\\textbf{a % b\n}");
+
+    // closing an environment is left to the author
+    let lexed = LexedStr::new("\\begin{proof}{a");
+    let diagnostics = ast::Document::new(&lexed).diagnostics().to_vec();
+    assert!(diagnostics.iter().all(|diagnostic| diagnostic.fix.is_empty()));
+}
@@ -0,0 +1,274 @@
+//! Typed values of TeX dimensions and glue, e.g. `12.5pt` or `3em plus 1fil minus 2pt`.
+//!
+//! [`Dimension::parse`] combines the number and unit tokens of a literal into a [`Dimension`].
+//! Lengths computed from other lengths like `0.5\textwidth` are not literals and are rejected.
+
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+
+use crate::{LexedStr, SyntaxKind};
+
+/// A unit of a [`Length`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unit {
+    /// Point, 1/72.27 inch
+    Pt,
+    /// Millimeter
+    Mm,
+    /// Centimeter
+    Cm,
+    /// Inch
+    In,
+    /// Height of an `x` in the current font
+    Ex,
+    /// Width of an `M` in the current font
+    Em,
+    /// Big point, 1/72 inch
+    Bp,
+    /// Didot point
+    Dd,
+    /// Cicero, 12 Didot points
+    Cc,
+    /// Scaled point, 1/65536 point
+    Sp,
+    /// New Didot point
+    Nd,
+    /// New cicero, 12 new Didot points
+    Nc,
+    /// Math unit, 1/18 em of the math font
+    Mu,
+}
+
+const UNITS: &[(&str, Unit)] = &[
+    ("pt", Unit::Pt),
+    ("mm", Unit::Mm),
+    ("cm", Unit::Cm),
+    ("in", Unit::In),
+    ("ex", Unit::Ex),
+    ("em", Unit::Em),
+    ("bp", Unit::Bp),
+    ("dd", Unit::Dd),
+    ("cc", Unit::Cc),
+    ("sp", Unit::Sp),
+    ("nd", Unit::Nd),
+    ("nc", Unit::Nc),
+    ("mu", Unit::Mu),
+];
+
+impl Unit {
+    /// Returns the unit written as `name`, e.g. `pt`.
+    pub fn from_name(name: &str) -> Option<Unit> {
+        UNITS.iter().find(|(unit, _)| *unit == name).map(|&(_, unit)| unit)
+    }
+
+    /// The name of the unit, e.g. `pt`.
+    pub fn name(&self) -> &'static str {
+        UNITS.iter().find(|(_, unit)| unit == self).map_or("", |(name, _)| name)
+    }
+
+    /// Size of the unit in points, `None` for the font relative units `em`, `ex` and `mu`.
+    pub fn points(&self) -> Option<f64> {
+        const DD: f64 = 1238.0 / 1157.0;
+        const ND: f64 = 685.0 / 642.0;
+
+        Some(match self {
+            Unit::Pt => 1.0,
+            Unit::Mm => 72.27 / 25.4,
+            Unit::Cm => 72.27 / 2.54,
+            Unit::In => 72.27,
+            Unit::Bp => 72.27 / 72.0,
+            Unit::Dd => DD,
+            Unit::Cc => 12.0 * DD,
+            Unit::Sp => 1.0 / 65536.0,
+            Unit::Nd => ND,
+            Unit::Nc => 12.0 * ND,
+            Unit::Ex | Unit::Em | Unit::Mu => return None,
+        })
+    }
+}
+
+/// A fixed length, e.g. `-1.5cm`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Length {
+    /// The factor of `unit`
+    pub value: f64,
+    /// The unit of the length
+    pub unit: Unit,
+}
+
+impl Length {
+    /// The length in points, `None` for font relative units.
+    pub fn points(&self) -> Option<f64> {
+        Some(self.value * self.unit.points()?)
+    }
+}
+
+/// The order of an infinite glue component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Infinity {
+    /// `fil`
+    Fil,
+    /// `fill`, infinitely larger than `fil`
+    Fill,
+    /// `filll`, infinitely larger than `fill`
+    Filll,
+}
+
+/// The stretch or shrink of a [`Dimension`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GlueComponent {
+    /// A fixed amount, e.g. `plus 2pt`
+    Finite(Length),
+    /// An infinite amount, e.g. `plus 1fill`
+    Infinite(f64, Infinity),
+}
+
+/// A length with optional stretch and shrink components.
+///
+/// Without stretch and shrink this is a plain TeX dimension, otherwise TeX calls it glue.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dimension {
+    /// The natural size
+    pub natural: Length,
+    /// How far the length may grow, `plus ...`
+    pub stretch: Option<GlueComponent>,
+    /// How far the length may shrink, `minus ...`
+    pub shrink: Option<GlueComponent>,
+}
+
+/// Error returned by [`Dimension::parse`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DimensionError {
+    /// A number is missing, e.g. in `pt` or `plus`
+    #[error("expected a number")]
+    MissingNumber,
+    /// The unit is missing or not a TeX unit
+    #[error("expected a unit but found '{0}'")]
+    InvalidUnit(String),
+    /// The dimension is followed by other text
+    #[error("unexpected '{0}' after the dimension")]
+    TrailingInput(String),
+}
+
+impl Dimension {
+    /// Parses a dimension or glue literal like `12.5pt` or `3em plus 1fil minus 2pt`.
+    pub fn parse(text: &str) -> Result<Dimension, DimensionError> {
+        let lexed = LexedStr::new(text);
+        let mut tokens = (0..lexed.len())
+            .filter(|&idx| !lexed.kind(idx).is_resolver_trivia())
+            .map(|idx| (lexed.kind(idx), lexed.text(idx)))
+            .peekable();
+
+        let natural = match component(&mut tokens)? {
+            GlueComponent::Finite(length) => length,
+            GlueComponent::Infinite(_, order) => {
+                return Err(DimensionError::InvalidUnit(order.to_string()))
+            }
+        };
+
+        let mut keyword = |keyword: &str| match tokens.next_if(|&(_, text)| text == keyword) {
+            Some(_) => component(&mut tokens).map(Some),
+            None => Ok(None),
+        };
+        let stretch = keyword("plus")?;
+        let shrink = keyword("minus")?;
+
+        match tokens.next() {
+            Some((_, text)) => Err(DimensionError::TrailingInput(text.to_string())),
+            None => Ok(Dimension { natural, stretch, shrink }),
+        }
+    }
+
+    /// Returns `true` if the dimension has neither stretch nor shrink.
+    pub fn is_rigid(&self) -> bool {
+        self.stretch.is_none() && self.shrink.is_none()
+    }
+}
+
+/// Parses a signed number followed by a unit or an order of infinity.
+fn component<'a>(
+    tokens: &mut std::iter::Peekable<impl Iterator<Item = (SyntaxKind, &'a str)>>,
+) -> Result<GlueComponent, DimensionError> {
+    let mut sign = 1.0;
+    while let Some((kind, _)) =
+        tokens.next_if(|(kind, _)| matches!(kind, SyntaxKind::Minus | SyntaxKind::Plus))
+    {
+        if kind == SyntaxKind::Minus {
+            sign = -sign;
+        }
+    }
+
+    let value =
+        match tokens.next_if(|(kind, _)| matches!(kind, SyntaxKind::Number | SyntaxKind::Float)) {
+            Some((_, number)) => {
+                sign * number
+                    .replace('_', "")
+                    .parse::<f64>()
+                    .map_err(|_| DimensionError::MissingNumber)?
+            }
+            None => return Err(DimensionError::MissingNumber),
+        };
+
+    let unit = tokens.next().map_or("", |(_, text)| text);
+    if let Some(unit) = Unit::from_name(unit) {
+        return Ok(GlueComponent::Finite(Length { value, unit }));
+    }
+    let order = match unit {
+        "fil" => Infinity::Fil,
+        "fill" => Infinity::Fill,
+        "filll" => Infinity::Filll,
+        _ => return Err(DimensionError::InvalidUnit(unit.to_string())),
+    };
+    Ok(GlueComponent::Infinite(value, order))
+}
+
+impl FromStr for Dimension {
+    type Err = DimensionError;
+
+    fn from_str(text: &str) -> Result<Dimension, DimensionError> {
+        Dimension::parse(text)
+    }
+}
+
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.value, self.unit.name())
+    }
+}
+
+impl fmt::Display for Infinity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Infinity::Fil => "fil",
+            Infinity::Fill => "fill",
+            Infinity::Filll => "filll",
+        })
+    }
+}
+
+impl fmt::Display for GlueComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GlueComponent::Finite(length) => length.fmt(f),
+            GlueComponent::Infinite(value, order) => write!(f, "{value}{order}"),
+        }
+    }
+}
+
+/// Formats the dimension as TeX would read it, e.g. `3em plus 1fil`.
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.natural)?;
+        if let Some(stretch) = &self.stretch {
+            write!(f, " plus {stretch}")?;
+        }
+        if let Some(shrink) = &self.shrink {
+            write!(f, " minus {shrink}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
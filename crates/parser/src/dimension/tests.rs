@@ -0,0 +1,36 @@
+use super::{Dimension, DimensionError, GlueComponent, Infinity, Length, Unit};
+
+#[test]
+fn parse_dimensions() {
+    let dimension = Dimension::parse("12.5pt").unwrap();
+    assert_eq!(dimension.natural, Length { value: 12.5, unit: Unit::Pt });
+    assert!(dimension.is_rigid());
+
+    let glue: Dimension = " -3em plus 1fil minus .5pt ".parse().unwrap();
+    assert_eq!(glue.natural, Length { value: -3.0, unit: Unit::Em });
+    assert_eq!(glue.stretch, Some(GlueComponent::Infinite(1.0, Infinity::Fil)));
+    assert_eq!(glue.shrink, Some(GlueComponent::Finite(Length { value: 0.5, unit: Unit::Pt })));
+    assert_eq!(glue.to_string(), "-3em plus 1fil minus 0.5pt");
+
+    let shrink = Dimension::parse("0pt minus 2 fill").unwrap();
+    assert_eq!(
+        (shrink.stretch, shrink.shrink),
+        (None, Some(GlueComponent::Infinite(2.0, Infinity::Fill)))
+    );
+
+    assert_eq!(Dimension::parse("1in").unwrap().natural.points(), Some(72.27));
+    assert_eq!(Dimension::parse("2ex").unwrap().natural.points(), None);
+}
+
+#[test]
+fn invalid_dimensions() {
+    assert_eq!(Dimension::parse("pt"), Err(DimensionError::MissingNumber));
+    assert_eq!(Dimension::parse("3px"), Err(DimensionError::InvalidUnit("px".into())));
+    assert_eq!(Dimension::parse("1fil"), Err(DimensionError::InvalidUnit("fil".into())));
+    assert_eq!(
+        Dimension::parse("0.5\\textwidth"),
+        Err(DimensionError::InvalidUnit("\\textwidth".into()))
+    );
+    assert_eq!(Dimension::parse("1pt plus"), Err(DimensionError::MissingNumber));
+    assert_eq!(Dimension::parse("1pt and"), Err(DimensionError::TrailingInput("and".into())));
+}
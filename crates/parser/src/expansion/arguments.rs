@@ -0,0 +1,270 @@
+use std::ops::Range;
+
+use super::store::{ExpansionStoreItem, PreviewError};
+use crate::{LexedStr, SyntaxKind, Trivia};
+
+/// Collects the arguments of a call to `item` from `input`, the text following the command name.
+///
+/// Undelimited arguments are either a single token or a brace group. Delimited arguments of a
+/// `\def` extend up to the first occurrence of their delimiter outside of braces, like in TeX.
+/// An omitted optional argument is not part of the returned arguments. If `item` has
+/// [`star_variants`](ExpansionStoreItem::star_variants), a star following the arguments selects
+/// the starred one.
+///
+/// Returns the arguments, whether the starred variant is called and the number of bytes of
+/// `input` they span.
+pub(super) fn collect<'a>(
+    item: &ExpansionStoreItem<'_>,
+    input: &'a str,
+) -> Result<(Vec<&'a str>, bool, usize), PreviewError> {
+    let lexed = LexedStr::new(input);
+    let (args, starred, end) = collect_at(item, &lexed, 0)?;
+    Ok((args, starred, lexed.text_start(end.min(lexed.len()))))
+}
+
+/// Like [`collect`], but on the tokens of `lexed` starting at the token `pos`.
+///
+/// Returns the arguments, whether the starred variant is called and the index of the token
+/// following them.
+pub(crate) fn collect_at<'a>(
+    item: &ExpansionStoreItem<'_>,
+    lexed: &LexedStr<'a>,
+    pos: usize,
+) -> Result<(Vec<&'a str>, bool, usize), PreviewError> {
+    let arguments = arguments_at(item, lexed, pos);
+    if let Some(error) = arguments.error {
+        return Err(error);
+    }
+    let text = |range: &Range<usize>| match range.is_empty() {
+        true => "",
+        false => {
+            &lexed.as_str()[lexed.text_start(range.start)..lexed.text_range(range.end - 1).end]
+        }
+    };
+    let args = arguments.args.iter().map(|arg| text(&arg.content)).collect();
+    Ok((args, arguments.starred, arguments.end))
+}
+
+/// A single argument of a call, see [`arguments_at`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CallArgument {
+    /// Whether this is the optional argument in brackets
+    pub(crate) optional: bool,
+    /// Token range of the argument including its brackets or braces
+    pub(crate) range: Range<usize>,
+    /// Token range of the argument without its brackets or braces
+    pub(crate) content: Range<usize>,
+}
+
+/// The arguments of a call collected by [`arguments_at`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CallArguments {
+    /// The arguments found, up to the first missing one
+    pub(crate) args: Vec<CallArgument>,
+    /// Whether the starred variant is called, see
+    /// [`star_variants`](ExpansionStoreItem::star_variants)
+    pub(crate) starred: bool,
+    /// Index of the token following the arguments and the star
+    pub(crate) end: usize,
+    /// Why the remaining arguments are missing
+    pub(crate) error: Option<PreviewError>,
+}
+
+/// The arguments of a call to `item` on the tokens of `lexed` starting at the token `pos`, like
+/// [`collect_at`] but with their token ranges. Collection stops at the first missing argument.
+pub(crate) fn arguments_at(
+    item: &ExpansionStoreItem<'_>,
+    lexed: &LexedStr<'_>,
+    pos: usize,
+) -> CallArguments {
+    let mut cursor = Cursor { lexed, pos };
+    let mut args = Vec::with_capacity(item.args.count);
+    let error = cursor.arguments(item, &mut args).err();
+    let star = cursor.skip_whitespace(cursor.pos);
+    let starred =
+        error.is_none() && cursor.kind(star) == SyntaxKind::Star && item.star_variants().is_some();
+    if starred {
+        cursor.pos = star + 1;
+    }
+    CallArguments { args, starred, end: cursor.pos, error }
+}
+
+/// Whether a token of `kind` can be an undelimited argument on its own. `\begin` and `\end`
+/// are not, TeX would take them but they are surely meant to start or end an environment.
+pub(crate) fn takes_argument(kind: SyntaxKind) -> bool {
+    !kind.is_closing_delimiter()
+        && !matches!(kind, SyntaxKind::Eof | SyntaxKind::BeginGroup | SyntaxKind::EndGroup)
+}
+
+/// Texts of the non whitespace tokens of `text`.
+fn tokens(text: &str) -> Vec<&str> {
+    LexedStr::new(text)
+        .spanned_tokens()
+        .filter(|token| !token.kind.is_trivia(Trivia::PARSE))
+        .map(|token| token.text)
+        .collect()
+}
+
+struct Cursor<'lexed, 'a> {
+    lexed: &'lexed LexedStr<'a>,
+    pos: usize,
+}
+
+impl Cursor<'_, '_> {
+    /// Pushes the arguments of a call to `item` to `args`.
+    fn arguments(
+        &mut self,
+        item: &ExpansionStoreItem<'_>,
+        args: &mut Vec<CallArgument>,
+    ) -> Result<(), PreviewError> {
+        match &item.pattern {
+            Some(pattern) => {
+                let prefix = tokens(pattern.prefix);
+                self.pos = self
+                    .match_at(self.pos, &prefix)
+                    .ok_or_else(|| PreviewError::PatternMismatch(pattern.prefix.to_string()))?;
+
+                for (idx, delimiter) in pattern.delimiters.iter().enumerate() {
+                    let arg = match delimiter.is_empty() {
+                        true => self.undelimited(idx + 1)?,
+                        false => self
+                            .delimited(&tokens(delimiter))
+                            .ok_or_else(|| PreviewError::PatternMismatch(delimiter.to_string()))?,
+                    };
+                    args.push(arg);
+                }
+            }
+            None => {
+                let mut required = item.args.count;
+                if item.args.default.is_some() {
+                    required -= 1;
+                    args.extend(self.optional());
+                }
+
+                for _ in 0..required {
+                    let arg = self.undelimited(args.len() + 1)?;
+                    args.push(arg);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// A brace group or a single token [taking the argument](takes_argument). A paragraph break
+    /// is taken as well, like by TeX, see
+    /// [`paragraphs_in_arguments`](super::ExpansionStore::paragraphs_in_arguments).
+    fn undelimited(&mut self, number: usize) -> Result<CallArgument, PreviewError> {
+        let start = self.skip_whitespace(self.pos);
+
+        match self.kind(start) {
+            SyntaxKind::OpenBrace => {
+                let end = self.group_end(start).ok_or(PreviewError::MissingArgument(number))?;
+                self.pos = end + 1;
+                Ok(CallArgument { optional: false, range: start..end + 1, content: start + 1..end })
+            }
+            kind if !takes_argument(kind) => Err(PreviewError::MissingArgument(number)),
+            _ => {
+                self.pos = start + 1;
+                Ok(CallArgument {
+                    optional: false,
+                    range: start..start + 1,
+                    content: start..start + 1,
+                })
+            }
+        }
+    }
+
+    /// Everything up to `delimiter` outside of braces. A single surrounding brace group is
+    /// removed from the content.
+    fn delimited(&mut self, delimiter: &[&str]) -> Option<CallArgument> {
+        let start = self.skip_whitespace(self.pos);
+        let mut pos = start;
+
+        loop {
+            if let Some(end) = self.match_at(pos, delimiter) {
+                self.pos = end;
+                break;
+            }
+
+            pos = match self.kind(pos) {
+                SyntaxKind::OpenBrace => self.group_end(pos)? + 1,
+                SyntaxKind::Eof | SyntaxKind::CloseBrace => return None,
+                _ => pos + 1,
+            };
+        }
+
+        let mut end = pos;
+        while end > start && self.kind(end - 1).is_trivia(Trivia::PARSE) {
+            end -= 1;
+        }
+
+        let content = match self.kind(start) == SyntaxKind::OpenBrace
+            && self.group_end(start) == Some(end - 1)
+        {
+            true => start + 1..end - 1,
+            false => start..end,
+        };
+        Some(CallArgument { optional: false, range: start..end, content })
+    }
+
+    /// `[...]` if present.
+    fn optional(&mut self) -> Option<CallArgument> {
+        let open = self.skip_whitespace(self.pos);
+        if self.kind(open) != SyntaxKind::OpenBracket {
+            return None;
+        }
+
+        let mut pos = open + 1;
+        loop {
+            pos = match self.kind(pos) {
+                SyntaxKind::CloseBracket => break,
+                SyntaxKind::OpenBrace => self.group_end(pos)? + 1,
+                SyntaxKind::Eof => return None,
+                _ => pos + 1,
+            };
+        }
+
+        self.pos = pos + 1;
+        Some(CallArgument { optional: true, range: open..pos + 1, content: open + 1..pos })
+    }
+
+    /// Returns the position after `expected` if its tokens follow at `pos`.
+    fn match_at(&self, mut pos: usize, expected: &[&str]) -> Option<usize> {
+        for text in expected {
+            pos = self.skip_whitespace(pos);
+            if self.kind(pos) == SyntaxKind::Eof || self.lexed.text(pos) != *text {
+                return None;
+            }
+            pos += 1;
+        }
+        Some(pos)
+    }
+
+    /// Index of the brace closing the group opened at `open`.
+    fn group_end(&self, open: usize) -> Option<usize> {
+        let mut depth = 0usize;
+        for pos in open..self.lexed.len() {
+            match self.kind(pos) {
+                SyntaxKind::OpenBrace => depth += 1,
+                SyntaxKind::CloseBrace if depth == 1 => return Some(pos),
+                SyntaxKind::CloseBrace => depth -= 1,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn skip_whitespace(&self, mut pos: usize) -> usize {
+        while self.kind(pos).is_trivia(Trivia::PARSE) {
+            pos += 1;
+        }
+        pos
+    }
+
+    fn kind(&self, pos: usize) -> SyntaxKind {
+        match pos < self.lexed.len() {
+            true => self.lexed.kind(pos),
+            false => SyntaxKind::Eof,
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use super::{
+    arguments,
+    store::{ExpansionStoreItem, PreviewError},
+};
+use crate::LexedStr;
+
+/// Reuses the expansions of calls across [`LexedStr::text_with_cached_expansion`] calls, e.g.
+/// of the same document before and after an edit.
+///
+/// The expansion of a call only depends on its definition and its arguments, so entries are
+/// keyed by the [`fingerprint`](ExpansionStoreItem::fingerprint) of the definition and the text
+/// of the arguments. An entry is never stale: a changed definition or argument has a different
+/// key. To bound the memory, the cache is cleared once it holds [`max_entries`](Self::max_entries)
+/// entries.
+///
+/// [`LexedStr::text_with_cached_expansion`]: crate::LexedStr::text_with_cached_expansion
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpansionCache {
+    /// Number of entries at which the cache is cleared
+    pub max_entries: usize,
+    entries: HashMap<(u64, u64), Entry>,
+    stats: CacheStats,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    /// The text of the arguments, compared on a hit in case of a hash collision
+    arguments: String,
+    expansion: String,
+}
+
+/// Hits and misses of an [`ExpansionCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// Calls whose expansion was taken from the cache
+    pub hits: usize,
+    /// Calls which were expanded and stored
+    pub misses: usize,
+}
+
+impl CacheStats {
+    /// The share of the calls taken from the cache, between 0 and 1. Zero without any calls.
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            calls => self.hits as f64 / calls as f64,
+        }
+    }
+}
+
+impl ExpansionCache {
+    /// An empty cache holding at most 4096 entries.
+    pub fn new() -> ExpansionCache {
+        ExpansionCache { max_entries: 4096, entries: HashMap::new(), stats: CacheStats::default() }
+    }
+
+    /// Number of cached expansions.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no expansion is cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Hits and misses since the cache was created or the stats were reset.
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Resets the hits and misses, e.g. to measure a single run.
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    /// Removes all entries, the stats are kept.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Like [`ExpansionStoreItem::expand_call`] on the tokens of `lexed` following the command
+    /// name at `pos`, but takes the expansion from the cache if the definition has been called
+    /// with the same arguments before. Returns the expansion and the index of the token
+    /// following the arguments.
+    pub(super) fn expand_call(
+        &mut self,
+        item: &ExpansionStoreItem<'_>,
+        lexed: &LexedStr<'_>,
+        pos: usize,
+    ) -> Result<(String, usize), PreviewError> {
+        let (args, starred, end) = arguments::collect_at(item, lexed, pos)?;
+        let end = end.min(lexed.len());
+        let arguments = &lexed.as_str()[lexed.text_start(pos)..lexed.text_start(end)];
+        let mut hasher = DefaultHasher::new();
+        arguments.hash(&mut hasher);
+        let key = (item.fingerprint(), hasher.finish());
+
+        if let Some(entry) = self.entries.get(&key).filter(|entry| entry.arguments == arguments) {
+            self.stats.hits += 1;
+            return Ok((entry.expansion.clone(), end));
+        }
+        let expansion = item.expand_variant(starred, &args)?;
+        self.stats.misses += 1;
+        if self.entries.len() >= self.max_entries {
+            self.entries.clear();
+        }
+        let entry = Entry { arguments: arguments.to_string(), expansion: expansion.clone() };
+        self.entries.insert(key, entry);
+        Ok((expansion, end))
+    }
+}
+
+impl Default for ExpansionCache {
+    fn default() -> Self {
+        ExpansionCache::new()
+    }
+}
+
+impl ExpansionStoreItem<'_> {
+    /// A hash of everything the expansion of a call depends on besides its arguments: the
+    /// bodies and the parameters, but not the name or the position of the definition. A
+    /// definition which is moved or renamed keeps its fingerprint.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (self.body, self.end_body, self.args.count, self.args.default).hash(&mut hasher);
+        let pattern = self.pattern.as_ref();
+        pattern.map(|pattern| (pattern.prefix, &pattern.delimiters)).hash(&mut hasher);
+        (self.long, self.starred).hash(&mut hasher);
+        hasher.finish()
+    }
+}
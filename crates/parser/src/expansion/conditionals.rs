@@ -0,0 +1,253 @@
+use std::{collections::BTreeMap, ops::Range};
+
+use crate::{LexedStr, SyntaxKind};
+
+/// The extent of the test following a conditional.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Test {
+    /// The given number of tokens, e.g. two for `\ifx\a\b`
+    Tokens(usize),
+    /// Everything up to the next whitespace outside of braces, e.g. `\value{page}>3` of `\ifnum`
+    UpToSpace,
+    /// Everything up to and including `\endcsname`
+    Csname,
+}
+
+/// Conditionals of TeX and its engines together with the extent of their test.
+const PRIMITIVES: &[(&str, Test)] = &[
+    ("\\if", Test::Tokens(2)),
+    ("\\ifcat", Test::Tokens(2)),
+    ("\\ifx", Test::Tokens(2)),
+    ("\\ifdefined", Test::Tokens(1)),
+    ("\\ifcsname", Test::Csname),
+    ("\\ifnum", Test::UpToSpace),
+    ("\\ifdim", Test::UpToSpace),
+    ("\\ifodd", Test::UpToSpace),
+    ("\\ifcase", Test::UpToSpace),
+    ("\\ifvoid", Test::Tokens(1)),
+    ("\\ifhbox", Test::Tokens(1)),
+    ("\\ifvbox", Test::Tokens(1)),
+    ("\\ifeof", Test::Tokens(1)),
+    ("\\iftrue", Test::Tokens(0)),
+    ("\\iffalse", Test::Tokens(0)),
+    ("\\ifvmode", Test::Tokens(0)),
+    ("\\ifhmode", Test::Tokens(0)),
+    ("\\ifmmode", Test::Tokens(0)),
+    ("\\ifinner", Test::Tokens(0)),
+    ("\\ifpdf", Test::Tokens(0)),
+    ("\\ifxetex", Test::Tokens(0)),
+    ("\\ifluatex", Test::Tokens(0)),
+];
+
+/// Values of the flags declared with `\newif`, e.g. `draft` for `\ifdraft`, used to select the
+/// active branch of a conditional during expansion.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ConditionalFlags {
+    flags: BTreeMap<String, bool>,
+}
+
+impl ConditionalFlags {
+    /// No flags, only `\iftrue` and `\iffalse` have a known value.
+    pub fn new() -> ConditionalFlags {
+        ConditionalFlags::default()
+    }
+
+    /// Sets the flag `name`, e.g. `draft` for `\ifdraft`.
+    pub fn set(&mut self, name: impl Into<String>, value: bool) {
+        self.flags.insert(name.into(), value);
+    }
+
+    /// The value of the flag `name`.
+    pub fn get(&self, name: &str) -> Option<bool> {
+        self.flags.get(name).copied()
+    }
+
+    /// Returns `true` if no flag is set.
+    pub fn is_empty(&self) -> bool {
+        self.flags.is_empty()
+    }
+
+    /// Iterator over the flags and their values, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.flags.iter().map(|(name, &value)| (name.as_str(), value))
+    }
+}
+
+/// A TeX conditional like `\ifdefined\x ... \else ... \fi`, paired in the token stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConditionalBlock {
+    /// Token range from the conditional command to the `\fi` inclusive
+    pub range: Range<usize>,
+    /// Token range of the test, e.g. `\x` of `\ifdefined\x`. Empty for flags like `\ifdraft`.
+    pub test: Range<usize>,
+    /// Token indices of the `\or`s of an `\ifcase`
+    pub ors: Vec<usize>,
+    /// Token index of the `\else`
+    pub else_branch: Option<usize>,
+    /// Returns `false` if the `\fi` is missing. The conditional then extends to the end of the
+    /// source.
+    pub closed: bool,
+}
+
+impl ConditionalBlock {
+    /// Token ranges of the branches without their separators: the branch following the test,
+    /// the branches following each `\or` and the branch following the `\else`.
+    pub fn branches(&self) -> Vec<Range<usize>> {
+        let end = self.range.end - usize::from(self.closed);
+        let separators: Vec<_> = self.ors.iter().chain(&self.else_branch).copied().collect();
+
+        let mut start = self.test.end;
+        let mut branches = Vec::with_capacity(separators.len() + 1);
+        for separator in separators {
+            branches.push(start..separator);
+            start = separator + 1;
+        }
+        branches.push(start..end);
+        branches
+    }
+
+    /// The value of the test if it is known without running TeX: `\iftrue`, `\iffalse` and the
+    /// conditionals of the given `flags`.
+    pub fn value(&self, lexed: &LexedStr<'_>, flags: &ConditionalFlags) -> Option<bool> {
+        match lexed.text(self.range.start) {
+            "\\iftrue" => Some(true),
+            "\\iffalse" => Some(false),
+            name => flags.get(name.strip_prefix("\\if")?),
+        }
+    }
+
+    /// The token range of the branch which is taken, if the [`value`](Self::value) is known.
+    /// The range is empty for a false conditional without `\else`.
+    pub fn active_branch(
+        &self,
+        lexed: &LexedStr<'_>,
+        flags: &ConditionalFlags,
+    ) -> Option<Range<usize>> {
+        let branches = self.branches();
+        match self.value(lexed, flags)? {
+            true => branches.first().cloned(),
+            false if self.else_branch.is_some() => branches.last().cloned(),
+            false => Some(self.range.end..self.range.end),
+        }
+    }
+}
+
+/// Pairs the conditionals of `lexed` with their `\else`, `\or` and `\fi`, in the order they
+/// start.
+///
+/// Conditionals are the primitives of TeX, the flags declared with `\newif` in the source and
+/// the flags of `flags`. Other commands starting with `\if` are macros taking arguments, like
+/// `\ifthenelse`, and are not paired.
+pub fn conditionals(lexed: &LexedStr<'_>, flags: &ConditionalFlags) -> Vec<ConditionalBlock> {
+    let mut declared: Vec<&str> = Vec::new();
+    for idx in 0..lexed.len() {
+        if lexed.kind(idx) == SyntaxKind::Command && lexed.text(idx) == "\\newif" {
+            let next = lexed.next_non_trivia(idx + 1);
+            declared
+                .extend(next.map(|next| lexed.text(next)).filter(|name| name.starts_with("\\if")));
+        }
+    }
+    let test = |name: &str| {
+        let primitive = PRIMITIVES.iter().find(|(primitive, _)| *primitive == name);
+        let flag = name.strip_prefix("\\if").is_some_and(|flag| flags.get(flag).is_some());
+        match primitive {
+            Some(&(_, test)) => Some(test),
+            None if flag || declared.contains(&name) => Some(Test::Tokens(0)),
+            None => None,
+        }
+    };
+
+    let mut blocks: Vec<ConditionalBlock> = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    let mut idx = 0;
+    while idx < lexed.len() {
+        if lexed.kind(idx) != SyntaxKind::Command {
+            idx += 1;
+            continue;
+        }
+        let name = lexed.text(idx);
+        match name {
+            "\\else" => {
+                if let Some(&block) = open.last() {
+                    blocks[block].else_branch.get_or_insert(idx);
+                }
+            }
+            "\\or" => {
+                let block = open.last().map(|&block| &mut blocks[block]);
+                if let Some(block) =
+                    block.filter(|block| lexed.text(block.range.start) == "\\ifcase")
+                {
+                    block.ors.push(idx);
+                }
+            }
+            "\\fi" => {
+                if let Some(block) = open.pop() {
+                    blocks[block].range.end = idx + 1;
+                    blocks[block].closed = true;
+                }
+            }
+            // `\newif\ifdraft` declares the flag, it does not start a conditional
+            _ if declared.contains(&name) && is_declaration(lexed, idx) => {}
+            _ => {
+                if let Some(test) = test(name) {
+                    let end = test_end(lexed, idx + 1, test);
+                    open.push(blocks.len());
+                    blocks.push(ConditionalBlock {
+                        range: idx..lexed.len(),
+                        test: idx + 1..end,
+                        ors: Vec::new(),
+                        else_branch: None,
+                        closed: false,
+                    });
+                    idx = end;
+                    continue;
+                }
+            }
+        }
+        idx += 1;
+    }
+
+    blocks
+}
+
+/// Whether the flag at `idx` is the name declared by a `\newif`.
+fn is_declaration(lexed: &LexedStr<'_>, idx: usize) -> bool {
+    let prev = lexed.prev_non_trivia(idx);
+    prev.is_some_and(|prev| lexed.text(prev) == "\\newif")
+}
+
+/// The end of the test of a conditional starting at the token `start`.
+fn test_end(lexed: &LexedStr<'_>, start: usize, test: Test) -> usize {
+    let len = lexed.len();
+    let next = |idx: usize| lexed.next_non_trivia(idx);
+    match test {
+        Test::Tokens(count) => {
+            let mut end = start;
+            for _ in 0..count {
+                match next(end) {
+                    Some(token) => end = token + 1,
+                    None => break,
+                }
+            }
+            end
+        }
+        Test::Csname => {
+            (start..len).find(|&idx| lexed.text(idx) == "\\endcsname").map_or(len, |end| end + 1)
+        }
+        Test::UpToSpace => {
+            let Some(first) = next(start) else {
+                return len;
+            };
+            let mut depth = 0usize;
+            for idx in first..len {
+                match lexed.kind(idx) {
+                    SyntaxKind::OpenBrace => depth += 1,
+                    SyntaxKind::CloseBrace => depth = depth.saturating_sub(1),
+                    kind if depth == 0 && kind.is_resolver_trivia() => return idx,
+                    _ => {}
+                }
+            }
+            len
+        }
+    }
+}
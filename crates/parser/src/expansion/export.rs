@@ -0,0 +1,78 @@
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use super::{DefPattern, DefinitionKind, ExpansionStore};
+use crate::LexedStr;
+
+/// A definition in a form meant for tools outside of NeoTeX.
+///
+/// With the `serde` feature enabled it serializes to e.g.
+///
+/// ```json
+/// {
+///   "name": "\\vect",
+///   "kind": "command",
+///   "arg_count": 2,
+///   "default_arg": "n",
+///   "pattern": null,
+///   "body": "#2_1, \\dots, #2_#1",
+///   "end_body": null,
+///   "long": true,
+///   "starred": false,
+///   "span": { "start": 0, "end": 44 }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ExportedDefinition<'source> {
+    /// Name of the definition, commands include the leading backslash
+    pub name: &'source str,
+    /// Command used to create the definition
+    pub kind: DefinitionKind,
+    /// Total number of arguments, including the optional one
+    pub arg_count: usize,
+    /// Default value of the optional first argument
+    pub default_arg: Option<&'source str>,
+    /// Parameter text of a `\def`
+    pub pattern: Option<DefPattern<'source>>,
+    /// Replacement text, for environments the begin code
+    pub body: &'source str,
+    /// End code of an environment
+    pub end_body: Option<&'source str>,
+    /// Whether the arguments may contain paragraph breaks
+    pub long: bool,
+    /// Whether the definition was made with the starred form of its command
+    pub starred: bool,
+    /// Byte range of the whole definition in the source text
+    pub span: Range<usize>,
+}
+
+impl<'source> ExpansionStore<'source> {
+    /// Exports all commands and environments in the order they appear in the source. Inherited
+    /// definitions come first, commands before environments and each ordered by name.
+    ///
+    /// The store is expected to be resolved from `lexed`.
+    pub fn export(&self, lexed: &LexedStr<'source>) -> Vec<ExportedDefinition<'source>> {
+        let mut definitions: Vec<_> = self
+            .commands()
+            .chain(self.environments())
+            .map(|item| ExportedDefinition {
+                name: item.name,
+                kind: item.kind,
+                arg_count: item.args.count,
+                default_arg: item.args.default,
+                pattern: item.pattern.clone(),
+                body: item.body,
+                end_body: item.end_body,
+                long: item.long,
+                starred: item.starred,
+                span: lexed.text_start(item.range.start)..lexed.text_start(item.range.end),
+            })
+            .collect();
+
+        definitions.sort_by_key(|definition| definition.span.start);
+        definitions
+    }
+}
@@ -0,0 +1,83 @@
+use std::ops::Range;
+
+use super::TextEdit;
+use crate::{LexedStr, SyntaxKind};
+
+/// Pushes the edits joining the lines of the token range `tokens` into one to `edits`, e.g. for a
+/// command call broken across lines.
+///
+/// Every run of whitespace, comments and line breaks between two tokens of the range becomes a
+/// single space, or nothing after a control word, where TeX skips the end of the line anyway.
+/// The comments of the runs would comment out the rest of the joined line, so they are moved to
+/// the end of the line of the last token instead. Paragraph breaks are joined like line breaks.
+///
+/// ```
+/// use parser::{expansion::{join_ranges, TextEdit}, LexedStr};
+///
+/// let source = "\\frac % numerator\n  {a}\n  {b} x\n";
+/// let lexed = LexedStr::new(source);
+/// let mut edits = Vec::new();
+/// join_ranges(&mut edits, &lexed, 0..lexed.len() - 3);
+///
+/// let mut joined = source.to_string();
+/// for edit in edits.iter().rev() {
+///     joined.replace_range(edit.range.clone(), &edit.new_text);
+/// }
+/// assert_eq!(joined, "\\frac{a} {b} x % numerator\n");
+/// ```
+pub fn join_ranges(edits: &mut Vec<TextEdit>, lexed: &LexedStr<'_>, tokens: Range<usize>) {
+    let is_trivia = |idx: usize| lexed.kind(idx).is_resolver_trivia();
+    let Some(first) = (tokens.start..tokens.end).find(|&idx| !is_trivia(idx)) else {
+        return;
+    };
+    let Some(last) = (first..tokens.end).rev().find(|&idx| !is_trivia(idx)) else {
+        return;
+    };
+
+    let mut comments = Vec::new();
+    let mut idx = first + 1;
+    while idx < last {
+        let Some(next) = lexed.next_non_trivia(idx) else {
+            break;
+        };
+        let run = idx..next;
+        if run.clone().any(|idx| lexed.kind(idx) != SyntaxKind::Whitespace) {
+            let moved = run.clone().filter(|&idx| is_comment(lexed.kind(idx)));
+            comments.extend(moved.map(|idx| lexed.text(idx).trim_end()));
+            let separator = match is_control_word(lexed.text(idx - 1)) {
+                true => "",
+                false => " ",
+            };
+            let range = lexed.text_start(run.start)..lexed.text_start(run.end);
+            edits.push(TextEdit { range, new_text: separator.to_string() });
+        }
+        idx = next + 1;
+    }
+    if comments.is_empty() {
+        return;
+    }
+
+    // in front of the line break or the comment ending the line of the last token
+    let end = (last + 1..lexed.len()).find(|&idx| {
+        matches!(lexed.kind(idx), SyntaxKind::Newline | SyntaxKind::Break)
+            || is_comment(lexed.kind(idx))
+    });
+    let mut new_text = format!(" {}", comments.join(" "));
+    if let Some(end) = end.filter(|&end| is_comment(lexed.kind(end))) {
+        if lexed.kind(end - 1) == SyntaxKind::Whitespace {
+            new_text.remove(0);
+        }
+        new_text.push(' ');
+    }
+    let end = end.map_or(lexed.as_str().len(), |end| lexed.text_start(end));
+    edits.push(TextEdit { range: end..end, new_text });
+}
+
+fn is_comment(kind: SyntaxKind) -> bool {
+    matches!(kind, SyntaxKind::Comment | SyntaxKind::AComment)
+}
+
+/// Whether `text` is a command made of letters, after which TeX skips spaces and the line end.
+fn is_control_word(text: &str) -> bool {
+    text.strip_prefix('\\').is_some_and(|name| name.ends_with(|c: char| c.is_alphabetic()))
+}
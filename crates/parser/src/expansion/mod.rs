@@ -0,0 +1,210 @@
+//! Resolution and expansion of user macro definitions.
+//!
+//! [`resolve`] walks a [`LexedStr`] and collects every `\newcommand`, `\def` and
+//! `\newenvironment` definition into an [`ExpansionStore`]. Definitions that cannot be parsed
+//! are skipped and every problem they have is reported as a [`ResolverError`]. Declarations and
+//! assignments of counters and lengths are collected into the [`Registers`] of the store.
+//!
+//! An [`ExpansionCache`] keeps the expansions of calls between analyses of a changing document.
+//! An [`ExpansionTrace`] records every substitution of an expansion for debugging. Definitions
+//! calling each other endlessly are found up front by [`ExpansionStore::recursions`], instead of
+//! only hitting the depth limit of the trace.
+//!
+//! [`conditionals`] pairs TeX conditionals like `\ifdefined` with their `\else` and `\fi`. The
+//! [`ConditionalFlags`] of a store select the branch of a conditional which is expanded.
+
+mod arguments;
+mod cache;
+mod conditionals;
+mod export;
+mod join;
+mod paragraphs;
+mod recursion;
+mod registers;
+mod rename;
+mod resolving;
+mod shadowing;
+mod store;
+mod trace;
+
+pub(crate) use arguments::{arguments_at, collect_at, takes_argument};
+pub use cache::{CacheStats, ExpansionCache};
+pub use conditionals::{conditionals, ConditionalBlock, ConditionalFlags};
+pub use export::ExportedDefinition;
+pub use join::join_ranges;
+pub use paragraphs::{ParagraphInArgument, PARAGRAPH_IN_ARGUMENT};
+pub use recursion::{Recursion, RECURSIVE_DEFINITION};
+pub use registers::{RegisterItem, RegisterKind, Registers};
+pub(crate) use rename::VERBATIM_ENVIRONMENTS;
+pub use rename::{RenameError, TextEdit};
+pub use resolving::{ResolverError, ResolverErrorKind};
+pub use shadowing::{Shadowing, SHADOWED_COMMAND};
+pub use store::{
+    DefPattern, DefinitionKind, ExpansionArgs, ExpansionStore, ExpansionStoreItem, PreviewError,
+};
+pub use trace::{ExpansionStep, ExpansionTrace};
+
+use std::{cell::RefCell, ops::Range};
+
+use crate::{LexedStr, SyntaxKind};
+
+/// Collects all macro and environment definitions of `lexed` into an [`ExpansionStore`].
+pub fn resolve<'source>(lexed: &LexedStr<'source>) -> ExpansionStore<'source> {
+    resolving::Resolver::new(lexed).resolve()
+}
+
+/// Resolves the single definition whose keyword (e.g. `\newcommand`) is the token at `idx`.
+pub(crate) fn resolve_at<'source>(
+    lexed: &LexedStr<'source>,
+    idx: usize,
+) -> Result<ExpansionStoreItem<'source>, Vec<ResolverError<'source>>> {
+    debug_assert!(lexed.kind(idx).is_definition_keyword());
+    resolving::Resolver::at(lexed, idx).definition()
+}
+
+impl<'source> LexedStr<'source> {
+    /// Source text of the token range `range` with every call of a command defined in `store`
+    /// expanded once.
+    ///
+    /// `store` is expected to be resolved from this [`LexedStr`], the definitions themselves are
+    /// left untouched. Every call is expanded with the definition in effect at its position, see
+    /// [`ExpansionStore::get_at`]. Calls whose arguments do not match their definition are kept unchanged.
+    /// Of a conditional whose value is known from the [`ConditionalFlags`] of the store, only
+    /// the active branch is kept.
+    pub fn text_with_expansion(&self, range: Range<usize>, store: &ExpansionStore<'_>) -> String {
+        self.text_with_expansion_limited(range, store, usize::MAX).unwrap_or_default()
+    }
+
+    /// Like [`text_with_expansion`](Self::text_with_expansion), but gives up and returns `None`
+    /// as soon as the text grows beyond `max_len` bytes.
+    ///
+    /// A single line calling a definition with a large body many times can expand to gigabytes,
+    /// so untrusted input should always be expanded with a limit.
+    pub fn text_with_expansion_limited(
+        &self,
+        range: Range<usize>,
+        store: &ExpansionStore<'_>,
+        max_len: usize,
+    ) -> Option<String> {
+        self.text_with_cached_expansion(range, store, max_len, &mut ExpansionCache::new())
+    }
+
+    /// Like [`text_with_expansion_limited`](Self::text_with_expansion_limited), but takes the
+    /// expansions of calls from `cache` and stores the new ones in it. Passing the same cache
+    /// for the text after an edit only expands the calls whose definition or arguments changed.
+    ///
+    /// ```
+    /// use parser::{
+    ///     expansion::{self, ExpansionCache},
+    ///     LexedStr,
+    /// };
+    ///
+    /// let mut cache = ExpansionCache::new();
+    /// let mut expand = |source: &str| {
+    ///     let lexed = LexedStr::new(source);
+    ///     let store = expansion::resolve(&lexed);
+    ///     lexed.text_with_cached_expansion(0..lexed.len(), &store, usize::MAX, &mut cache).unwrap()
+    /// };
+    ///
+    /// expand(r"\newcommand{\R}{\mathbb{R}} $\R$ and $\R$");
+    /// let edited = expand(r"\newcommand{\R}{\mathbb{R}} Now $\R$");
+    /// assert_eq!(edited, r"\newcommand{\R}{\mathbb{R}} Now $\mathbb{R}$");
+    /// assert_eq!((cache.stats().hits, cache.stats().misses), (2, 1));
+    /// ```
+    pub fn text_with_cached_expansion(
+        &self,
+        range: Range<usize>,
+        store: &ExpansionStore<'_>,
+        max_len: usize,
+        cache: &mut ExpansionCache,
+    ) -> Option<String> {
+        let mut definitions = vec![false; self.len()];
+        for item in store.all_commands() {
+            definitions[item.range.start.min(self.len())..item.range.end.min(self.len())]
+                .fill(true);
+        }
+        let conditionals = conditionals(self, store.flags());
+        let len = self.text_start(range.end) - self.text_start(range.start);
+
+        let mut text = String::with_capacity(len.min(max_len));
+        let cache = RefCell::new(cache);
+        let expansion = Expansion { store, definitions, conditionals, max_len, cache };
+        expansion.expand(self, range, &mut text)?;
+        (text.len() <= max_len).then_some(text)
+    }
+}
+
+/// The state of [`LexedStr::text_with_expansion_limited`].
+struct Expansion<'store, 'source> {
+    store: &'store ExpansionStore<'source>,
+    /// Whether every token is part of a definition, which is not expanded
+    definitions: Vec<bool>,
+    conditionals: Vec<ConditionalBlock>,
+    max_len: usize,
+    cache: RefCell<&'store mut ExpansionCache>,
+}
+
+impl Expansion<'_, '_> {
+    /// Appends the expanded text of the token range `range` to `text`.
+    fn expand(&self, lexed: &LexedStr<'_>, range: Range<usize>, text: &mut String) -> Option<()> {
+        let (store, definitions) = (self.store, &self.definitions);
+        let mut idx = range.start;
+
+        while idx < range.end {
+            if text.len() > self.max_len {
+                return None;
+            }
+            let in_definition = definitions.get(idx).copied().unwrap_or(false);
+            let block = self
+                .conditionals
+                .binary_search_by_key(&idx, |block| block.range.start)
+                .ok()
+                .map(|block| &self.conditionals[block])
+                .filter(|_| !in_definition);
+            if let Some(block) = block {
+                let active = block.active_branch(lexed, store.flags());
+                match active.filter(|_| block.closed && block.range.end <= range.end) {
+                    Some(branch) => {
+                        self.expand(lexed, branch, text)?;
+                        idx = block.range.end;
+                    }
+                    // the test is kept as it is, e.g. `\x` of `\ifdefined\x` is not expanded
+                    None => {
+                        let test_end = block.test.end.min(range.end);
+                        text.push_str(
+                            &lexed.as_str()[lexed.text_start(idx)..lexed.text_start(test_end)],
+                        );
+                        idx = test_end;
+                    }
+                }
+                continue;
+            }
+
+            let call = match lexed.kind(idx) {
+                SyntaxKind::Command if !in_definition => {
+                    store.get_at(lexed.text(idx), idx).and_then(|item| {
+                        self.cache.borrow_mut().expand_call(item, lexed, idx + 1).ok()
+                    })
+                }
+                _ => None,
+            };
+
+            match call {
+                // arguments reaching past the expanded range are not collected
+                Some((expansion, resume)) if resume <= range.end => {
+                    text.push_str(&expansion);
+                    idx = resume;
+                }
+                _ => {
+                    text.push_str(lexed.text(idx));
+                    idx += 1;
+                }
+            }
+        }
+
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,94 @@
+use std::ops::Range;
+
+use super::{arguments, ExpansionStore};
+use crate::{
+    diagnostics::{Diagnostic, Severity},
+    LexedStr, SyntaxKind,
+};
+
+/// Rule id of the diagnostic reported for paragraph breaks in arguments of short macros.
+pub const PARAGRAPH_IN_ARGUMENT: &str = "paragraph-in-argument";
+
+/// A blank line or `\par` inside the arguments of a call to a macro which is not `\long`, e.g.
+/// `\name{a\n\nb}` after `\newcommand*{\name}[1]{#1}`. TeX stops with a "Runaway argument" error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParagraphInArgument<'source> {
+    /// Name of the called command including the leading backslash
+    pub name: &'source str,
+    /// Byte range of the paragraph break
+    pub range: Range<usize>,
+    /// Byte range of the command name of the call
+    pub call: Range<usize>,
+    /// Byte range of the definition of the command, empty if it is inherited from another source
+    pub definition: Range<usize>,
+}
+
+impl ParagraphInArgument<'_> {
+    /// Converts the finding into an error [`Diagnostic`] on the paragraph break.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let message = format!(
+            "paragraph ended before the arguments of '{}' were complete, it is not \\long",
+            self.name
+        );
+        let diagnostic =
+            Diagnostic::new(PARAGRAPH_IN_ARGUMENT, Severity::Error, self.range.clone(), message)
+                .with_related(self.call.clone(), "called here");
+        match self.definition.is_empty() {
+            true => diagnostic,
+            false => {
+                diagnostic.with_related(self.definition.clone(), "defined here without \\long")
+            }
+        }
+    }
+}
+
+impl<'source> ExpansionStore<'source> {
+    /// Finds the paragraph breaks inside the arguments of calls to short definitions, in source
+    /// order. Only the first break of every call is reported.
+    ///
+    /// The store is expected to be resolved from `lexed`.
+    pub fn paragraphs_in_arguments(
+        &self,
+        lexed: &LexedStr<'source>,
+    ) -> Vec<ParagraphInArgument<'source>> {
+        let definitions: Vec<_> = self.all_commands().map(|item| item.range.clone()).collect();
+        let mut found = Vec::new();
+
+        for idx in 0..lexed.len() {
+            if lexed.kind(idx) != SyntaxKind::Command
+                || definitions.iter().any(|definition| definition.contains(&idx))
+            {
+                continue;
+            }
+            let Some(item) = self.get_at(lexed.text(idx), idx).filter(|item| !item.long) else {
+                continue;
+            };
+            let Ok((_, _, end)) = arguments::collect_at(item, lexed, idx + 1) else {
+                continue;
+            };
+
+            let paragraph = (idx + 1..end.min(lexed.len())).find(|&arg| {
+                lexed.kind(arg) == SyntaxKind::Break
+                    || (lexed.kind(arg) == SyntaxKind::Command && lexed.text(arg) == "\\par")
+            });
+            if let Some(paragraph) = paragraph {
+                let definition = match item.range.is_empty() {
+                    // inherited from another source, see `ExpansionStore::inherit`
+                    true => 0..0,
+                    false => {
+                        lexed.text_start(item.range.start)
+                            ..lexed.text_range(item.range.end.saturating_sub(1)).end
+                    }
+                };
+                found.push(ParagraphInArgument {
+                    name: item.name,
+                    range: lexed.text_range(paragraph),
+                    call: lexed.text_range(idx),
+                    definition,
+                });
+            }
+        }
+
+        found
+    }
+}
@@ -0,0 +1,127 @@
+use std::{collections::VecDeque, ops::Range};
+
+use rustc_hash::FxHashMap;
+
+use super::{conditionals, rename, ConditionalFlags, ExpansionStore};
+use crate::{
+    diagnostics::{Diagnostic, Severity},
+    LexedStr, SyntaxKind,
+};
+
+/// Rule id of the diagnostic reported for definitions which expand to themselves.
+pub const RECURSIVE_DEFINITION: &str = "recursive-definition";
+
+/// Definitions whose bodies call each other in a cycle, e.g. `\def\a{\b}\def\b{\a}`, so
+/// expanding any of them never ends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recursion<'source> {
+    /// Names of the definitions in the order they call each other, starting with the one
+    /// defined first. The last one calls the first one again.
+    pub cycle: Vec<&'source str>,
+    /// Byte range of the name in the definition of the first one
+    pub range: Range<usize>,
+}
+
+impl Recursion<'_> {
+    /// Returns `true` if the definition calls itself, without any other definition in between.
+    pub fn is_direct(&self) -> bool {
+        self.cycle.len() == 1
+    }
+
+    /// Converts the recursion into a warning on the name of the first definition.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let message = match self.is_direct() {
+            true => format!("'{}' expands to itself", self.cycle[0]),
+            false => {
+                let path: Vec<_> = self.cycle.iter().chain(&self.cycle[..1]).copied().collect();
+                format!("'{}' expands to itself through {}", self.cycle[0], path.join(" -> "))
+            }
+        };
+        Diagnostic::new(RECURSIVE_DEFINITION, Severity::Warning, self.range.clone(), message)
+    }
+}
+
+impl<'source> ExpansionStore<'source> {
+    /// Finds the cycles of the command definitions in effect at the end of the document, in
+    /// source order. Every definition is part of at most one reported cycle, which is the
+    /// shortest one starting at the first definition of the cycle.
+    ///
+    /// Calls inside of a conditional of a body are left out, since the conditional may end the
+    /// recursion like in a TeX loop. Cycles made only of inherited definitions are not reported.
+    /// The store is expected to be resolved from `lexed`.
+    ///
+    /// ```
+    /// use parser::{expansion::resolve, LexedStr};
+    ///
+    /// let lexed = LexedStr::new(r"\def\a{\b}\def\b{x\c}\def\c{\a}\def\d{\ifx\e\d\fi}");
+    /// let recursions = resolve(&lexed).recursions(&lexed);
+    /// assert_eq!(recursions.len(), 1);
+    /// assert_eq!(recursions[0].cycle, [r"\a", r"\b", r"\c"]);
+    /// ```
+    pub fn recursions(&self, lexed: &LexedStr<'source>) -> Vec<Recursion<'source>> {
+        let mut items: Vec<_> = self.commands().collect();
+        items.sort_by_key(|item| (item.range.is_empty(), item.range.start));
+        let calls: FxHashMap<_, _> =
+            items.iter().map(|item| (item.name, self.calls(item.body))).collect();
+
+        let mut found: Vec<Recursion<'source>> = Vec::new();
+        for item in items.iter().filter(|item| !item.range.is_empty()) {
+            if found.iter().any(|recursion| recursion.cycle.contains(&item.name)) {
+                continue;
+            }
+            let Some(cycle) = shortest_cycle(item.name, &calls) else {
+                continue;
+            };
+            let uses = rename::command_uses(lexed, item.range.start, item.name);
+            let name = uses.into_iter().find(|idx| item.range.contains(idx));
+            let range = match name {
+                Some(name) => lexed.text_range(name),
+                None => lexed.text_range(item.range.start),
+            };
+            found.push(Recursion { cycle, range });
+        }
+        found
+    }
+
+    /// Names of the commands of this store called by `body` outside of conditionals.
+    fn calls(&self, body: &'source str) -> Vec<&'source str> {
+        let lexed = LexedStr::new(body);
+        let blocks = conditionals(&lexed, &ConditionalFlags::new());
+        let mut calls: Vec<_> = (0..lexed.len())
+            .filter(|&idx| lexed.kind(idx) == SyntaxKind::Command)
+            .filter(|idx| !blocks.iter().any(|block| block.range.contains(idx)))
+            .map(|idx| lexed.text(idx))
+            .filter(|name| self.get(name).is_some())
+            .collect();
+        calls.sort_unstable();
+        calls.dedup();
+        calls
+    }
+}
+
+/// The shortest path of calls from `start` back to itself, breadth first.
+fn shortest_cycle<'source>(
+    start: &'source str,
+    calls: &FxHashMap<&'source str, Vec<&'source str>>,
+) -> Option<Vec<&'source str>> {
+    let mut callers: FxHashMap<&str, &str> = FxHashMap::default();
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(name) = queue.pop_front() {
+        for &callee in calls.get(name).into_iter().flatten() {
+            if callee == start {
+                let mut cycle = vec![name];
+                while let Some(&caller) = callers.get(cycle[cycle.len() - 1]) {
+                    cycle.push(caller);
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+            if !callers.contains_key(callee) {
+                callers.insert(callee, name);
+                queue.push_back(callee);
+            }
+        }
+    }
+    None
+}
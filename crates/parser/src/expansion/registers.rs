@@ -0,0 +1,125 @@
+use std::ops::Range;
+
+use crate::dimension::Dimension;
+
+/// Whether a register holds an integer or a dimension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegisterKind {
+    /// A LaTeX counter, e.g. `section`
+    Counter,
+    /// A length, e.g. `\parindent`
+    Length,
+}
+
+/// Commands declaring or assigning registers, together with the kind of register and whether
+/// they declare a new one.
+const REGISTER_COMMANDS: &[(&str, RegisterKind, bool)] = &[
+    ("\\newcounter", RegisterKind::Counter, true),
+    ("\\setcounter", RegisterKind::Counter, false),
+    ("\\newlength", RegisterKind::Length, true),
+    ("\\setlength", RegisterKind::Length, false),
+];
+
+/// Returns the kind of register handled by `command` and whether it declares a new one.
+pub(super) fn register_command(command: &str) -> Option<(RegisterKind, bool)> {
+    REGISTER_COMMANDS
+        .iter()
+        .find(|(name, ..)| *name == command)
+        .map(|&(_, kind, declaration)| (kind, declaration))
+}
+
+/// A single declaration or assignment of a register, e.g. `\setlength{\parindent}{1em}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterItem<'source> {
+    /// Name of the counter, or of the length including the leading backslash
+    pub name: &'source str,
+    /// Counter or length
+    pub kind: RegisterKind,
+    /// `true` for `\newcounter` and `\newlength`, `false` for an assignment
+    pub declaration: bool,
+    /// Assigned value without the surrounding braces, `None` for declarations
+    pub value: Option<&'source str>,
+    /// Token range of the whole command in the [`LexedStr`](crate::LexedStr) it was resolved from
+    pub range: Range<usize>,
+}
+
+impl<'source> RegisterItem<'source> {
+    /// The assigned value if it is a literal, e.g. `3` or `1.5em plus 2pt`, but not
+    /// `\value{page}` or `0.5\textwidth`.
+    pub fn literal_value(&self) -> Option<&'source str> {
+        let value = self.value?;
+        let literal = match self.kind {
+            RegisterKind::Counter => value.parse::<i64>().is_ok(),
+            RegisterKind::Length => Dimension::parse(value).is_ok(),
+        };
+        literal.then_some(value)
+    }
+
+    /// The value of a length assignment as typed [`Dimension`].
+    pub fn dimension(&self) -> Option<Dimension> {
+        match self.kind {
+            RegisterKind::Length => Dimension::parse(self.value?).ok(),
+            RegisterKind::Counter => None,
+        }
+    }
+
+    /// The value of a counter assignment as integer.
+    pub fn integer(&self) -> Option<i64> {
+        match self.kind {
+            RegisterKind::Counter => self.literal_value()?.parse().ok(),
+            RegisterKind::Length => None,
+        }
+    }
+}
+
+/// Declarations and assignments of the counters and lengths of a document in source order.
+#[derive(Debug, Clone, Default)]
+pub struct Registers<'source> {
+    items: Vec<RegisterItem<'source>>,
+}
+
+impl<'source> Registers<'source> {
+    pub(super) fn push(&mut self, item: RegisterItem<'source>) {
+        self.items.push(item);
+    }
+
+    /// All declarations and assignments in source order.
+    pub fn items(&self) -> &[RegisterItem<'source>] {
+        &self.items
+    }
+
+    /// All declarations of registers of `kind` in source order, including repeated ones.
+    pub fn declarations(&self, kind: RegisterKind) -> impl Iterator<Item = &RegisterItem<'source>> {
+        self.items.iter().filter(move |item| item.kind == kind && item.declaration)
+    }
+
+    /// The first declaration of the register `name`.
+    pub fn declaration(&self, kind: RegisterKind, name: &str) -> Option<&RegisterItem<'source>> {
+        self.declarations(kind).find(|item| item.name == name)
+    }
+
+    /// All assignments to the register `name` in source order.
+    pub fn assignments<'a>(
+        &'a self,
+        kind: RegisterKind,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a RegisterItem<'source>> {
+        self.items
+            .iter()
+            .filter(move |item| item.kind == kind && !item.declaration && item.name == name)
+    }
+
+    /// Value of the register `name` right after its setup: the first assignment following the
+    /// declaration, or zero if there is none. `None` if the register is not declared in the
+    /// document or the value is not a literal.
+    pub fn initial_value(&self, kind: RegisterKind, name: &str) -> Option<&'source str> {
+        let declaration = self.declaration(kind, name)?;
+        let assignment =
+            self.assignments(kind, name).find(|item| item.range.start > declaration.range.start);
+        match (assignment, kind) {
+            (Some(assignment), _) => assignment.literal_value(),
+            (None, RegisterKind::Counter) => Some("0"),
+            (None, RegisterKind::Length) => Some("0pt"),
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::ops::Range;
+
+use thiserror::Error;
+
+use super::ExpansionStore;
+use crate::{LexedStr, SyntaxKind};
+
+/// Environments whose content is printed as is and never contains commands.
+pub(crate) const VERBATIM_ENVIRONMENTS: &[&str] =
+    &["verbatim", "verbatim*", "Verbatim", "lstlisting", "minted", "comment"];
+
+/// A replacement of a byte range of the source text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Byte range in the source text which is replaced
+    pub range: Range<usize>,
+    /// Replacement text
+    pub new_text: String,
+}
+
+/// Error returned when a command cannot be renamed.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RenameError {
+    /// The command to rename is not defined in the document
+    #[error("no definition found for '{0}'")]
+    UnknownDefinition(String),
+    /// The new name is not a single command
+    #[error("'{0}' is not a valid command name")]
+    InvalidName(String),
+    /// The new name is already taken by another definition
+    #[error("'{0}' is already defined")]
+    AlreadyDefined(String),
+}
+
+impl<'source> ExpansionStore<'source> {
+    /// Renames the command `old` to `new`, e.g. `\R` to `\Reals`.
+    ///
+    /// Returns one edit for the name of the definition and every use of the command in its
+    /// scope. A definition inside of a brace group is only visible up to the end of that group.
+    /// Comments and the content of verbatim environments and `\verb` are left untouched.
+    ///
+    /// The store is expected to be resolved from `lexed`.
+    pub fn rename_command(
+        &self,
+        lexed: &LexedStr<'source>,
+        old: &str,
+        new: &str,
+    ) -> Result<Vec<TextEdit>, RenameError> {
+        let definition =
+            self.get(old).ok_or_else(|| RenameError::UnknownDefinition(old.to_string()))?;
+
+        let name = LexedStr::with_profile(new, lexed.profile());
+        if name.len() != 1 || name.kind(0) != SyntaxKind::Command || !new.starts_with('\\') {
+            return Err(RenameError::InvalidName(new.to_string()));
+        }
+        if self.get(new).is_some() {
+            return Err(RenameError::AlreadyDefined(new.to_string()));
+        }
+
+        let edits = command_uses(lexed, definition.range.start, old)
+            .into_iter()
+            .map(|idx| TextEdit { range: lexed.text_range(idx), new_text: new.to_string() })
+            .collect();
+        Ok(edits)
+    }
+}
+
+/// Token indices of every use of the command `name` in the scope of the definition starting at
+/// the token `start`, including the name in the definition itself.
+pub(super) fn command_uses(lexed: &LexedStr<'_>, start: usize, name: &str) -> Vec<usize> {
+    let scope = scope(lexed, start);
+    let mut uses = Vec::new();
+    let mut idx = scope.start;
+
+    while idx < scope.end {
+        let text = lexed.text(idx);
+        idx = match lexed.kind(idx) {
+            SyntaxKind::BeginGroup => skip_verbatim_environment(lexed, idx),
+            SyntaxKind::Command if text == "\\verb" => skip_verb(lexed, idx + 1),
+            SyntaxKind::Command if text == name => {
+                uses.push(idx);
+                idx + 1
+            }
+            _ => idx + 1,
+        };
+    }
+
+    uses
+}
+
+/// Token range in which a definition starting at `start` is visible.
+fn scope(lexed: &LexedStr<'_>, start: usize) -> Range<usize> {
+    let mut open = Vec::new();
+    for idx in 0..start {
+        match lexed.kind(idx) {
+            SyntaxKind::OpenBrace => open.push(idx),
+            SyntaxKind::CloseBrace => {
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+
+    let Some(&group) = open.last() else {
+        return 0..lexed.len();
+    };
+
+    let mut depth = open.len();
+    for idx in start..lexed.len() {
+        match lexed.kind(idx) {
+            SyntaxKind::OpenBrace => depth += 1,
+            SyntaxKind::CloseBrace if depth == open.len() => return group..idx,
+            SyntaxKind::CloseBrace => depth -= 1,
+            _ => {}
+        }
+    }
+    group..lexed.len()
+}
+
+/// Returns the index after `\end{name}` if `\begin` at `idx` starts a verbatim environment.
+fn skip_verbatim_environment(lexed: &LexedStr<'_>, idx: usize) -> usize {
+    let Some(name) = environment_name(lexed, idx + 1) else {
+        return idx + 1;
+    };
+    if !VERBATIM_ENVIRONMENTS.contains(&name) {
+        return idx + 1;
+    }
+
+    (idx + 1..lexed.len())
+        .find(|&end| {
+            lexed.kind(end) == SyntaxKind::EndGroup
+                && environment_name(lexed, end + 1) == Some(name)
+        })
+        .map_or(lexed.len(), |end| end + 1)
+}
+
+/// The name in `{name}` starting at `idx`.
+fn environment_name<'source>(lexed: &LexedStr<'source>, idx: usize) -> Option<&'source str> {
+    if idx >= lexed.len() || lexed.kind(idx) != SyntaxKind::OpenBrace {
+        return None;
+    }
+    let close = (idx..lexed.len()).find(|&close| lexed.kind(close) == SyntaxKind::CloseBrace)?;
+    Some(lexed.as_str()[lexed.text_start(idx + 1)..lexed.text_start(close)].trim())
+}
+
+/// Returns the index after the argument of `\verb` starting at `idx`. The argument is delimited
+/// by its first character.
+fn skip_verb(lexed: &LexedStr<'_>, mut idx: usize) -> usize {
+    // `\verb*` shows spaces but is otherwise identical
+    if idx < lexed.len() && lexed.kind(idx) == SyntaxKind::Star {
+        idx += 1;
+    }
+
+    let text = lexed.as_str();
+    let start = lexed.text_start(idx);
+    let Some(delimiter) = text[start..].chars().next() else {
+        return idx;
+    };
+
+    let end = text[start + delimiter.len_utf8()..]
+        .find(delimiter)
+        .map_or(text.len(), |end| start + delimiter.len_utf8() + end + delimiter.len_utf8());
+    (idx..lexed.len()).find(|&next| lexed.text_start(next) >= end).unwrap_or(lexed.len())
+}
@@ -0,0 +1,531 @@
+use std::ops::Range;
+
+use thiserror::Error;
+
+use super::registers::{self, RegisterItem, RegisterKind};
+use super::store::{DefPattern, DefinitionKind, ExpansionArgs, ExpansionStore, ExpansionStoreItem};
+use crate::{
+    diagnostics::{Diagnostic, Severity},
+    syntax::{SyntaxKindSet, DEFINITION_KEYWORDS},
+    LexedStr, SyntaxKind,
+};
+
+/// Tokens starting a definition, where resolving picks up again after a broken one.
+const DEFINITION_FIRST: SyntaxKindSet = SyntaxKindSet::from_slice(DEFINITION_KEYWORDS);
+
+/// Tokens ending the parameter text of a `\def`: its body, or the start of the next definition if
+/// the body is missing.
+const PARAMETERS_FOLLOW: SyntaxKindSet = DEFINITION_FIRST.insert(SyntaxKind::OpenBrace);
+
+/// A problem of a definition which could not be resolved.
+///
+/// A single definition can have several independent problems, e.g. a missing name and an invalid
+/// argument count. Each of them is reported as its own error sharing the context of the
+/// definition.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{kind} at token {idx}")]
+pub struct ResolverError<'source> {
+    /// Index of the offending token
+    pub idx: usize,
+    /// What went wrong
+    pub kind: ResolverErrorKind,
+    /// The name of the definition, if it could be read
+    pub name: Option<&'source str>,
+    /// The kind of the definition, `None` for counter and length commands
+    pub definition: Option<DefinitionKind>,
+    /// Token range of the whole definition, from its keyword up to where resolving stopped
+    pub range: Range<usize>,
+    /// Byte range of the offending token, see [`span`](Self::span)
+    pub span: Range<usize>,
+    /// Byte range of the whole definition, the text of [`range`](Self::range)
+    pub definition_span: Range<usize>,
+}
+
+/// The kind of a [`ResolverError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ResolverErrorKind {
+    /// The name of the definition is missing or not a command
+    #[error("expected the name of the definition")]
+    MissingName,
+    /// The argument count is not a number between 1 and 9
+    #[error("expected an argument count between 1 and 9")]
+    InvalidArgCount,
+    /// A group is not closed before the end of the input
+    #[error("unclosed group")]
+    UnclosedGroup,
+    /// The replacement text of the definition is missing
+    #[error("expected the body of the definition")]
+    MissingBody,
+    /// Parameters of a `\def` are not numbered consecutively starting at 1
+    #[error("parameters must be numbered consecutively")]
+    InvalidParameterNumber,
+}
+
+impl ResolverErrorKind {
+    /// Rule id used when the error is reported as a [`Diagnostic`].
+    pub fn rule(&self) -> &'static str {
+        match self {
+            ResolverErrorKind::MissingName => "missing-name",
+            ResolverErrorKind::InvalidArgCount => "invalid-arg-count",
+            ResolverErrorKind::UnclosedGroup => "unclosed-group",
+            ResolverErrorKind::MissingBody => "missing-body",
+            ResolverErrorKind::InvalidParameterNumber => "invalid-parameter-number",
+        }
+    }
+}
+
+impl<'source> ResolverError<'source> {
+    fn new(idx: usize, kind: ResolverErrorKind) -> Self {
+        ResolverError {
+            idx,
+            kind,
+            name: None,
+            definition: None,
+            range: idx..idx + 1,
+            span: 0..0,
+            definition_span: 0..0,
+        }
+    }
+
+    /// Byte range of the offending token in the source text the error was resolved from, e.g.
+    /// for an editor. Empty at the end of the text if the input ended early.
+    ///
+    /// ```
+    /// use parser::{expansion::resolve, LexedStr};
+    ///
+    /// let source = r"\newcommand{\half}[x]{\frac12}";
+    /// let lexed = LexedStr::new(source);
+    /// let store = resolve(&lexed);
+    /// let error = &store.errors()[0];
+    /// assert_eq!(&source[error.span()], "x");
+    /// assert_eq!(&source[error.definition_span.clone()], source);
+    /// ```
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Converts the error into a [`Diagnostic`] spanning the offending token of `lexed`, with the
+    /// whole definition as related information.
+    pub fn to_diagnostic(&self, lexed: &LexedStr<'_>) -> Diagnostic {
+        let text_range = |range| text_range(lexed, range);
+        let message = match self.name {
+            Some(name) => format!("{} in the definition of '{name}'", self.kind),
+            None => self.kind.to_string(),
+        };
+        Diagnostic::new(
+            self.kind.rule(),
+            Severity::Error,
+            text_range(self.idx..self.idx + 1),
+            message,
+        )
+        .with_related(text_range(self.range.clone()), "in this definition")
+    }
+}
+
+/// Byte range of the token range `range` of `lexed`, empty at the end of the text if it starts
+/// past the last token.
+fn text_range(lexed: &LexedStr<'_>, range: Range<usize>) -> Range<usize> {
+    match range.start < lexed.len() {
+        true => lexed.text_start(range.start)..lexed.text_range(range.end.min(lexed.len()) - 1).end,
+        false => lexed.as_str().len()..lexed.as_str().len(),
+    }
+}
+
+type ResolverResult<'source, T> = Result<T, ResolverError<'source>>;
+
+pub(super) struct Resolver<'lexed, 'source> {
+    lexed: &'lexed LexedStr<'source>,
+    pos: usize,
+    store: ExpansionStore<'source>,
+    /// Name of the definition currently being resolved
+    name: Option<&'source str>,
+    /// Problems of the definition currently being resolved which did not stop resolving it
+    problems: Vec<ResolverError<'source>>,
+}
+
+impl<'lexed, 'source> Resolver<'lexed, 'source> {
+    pub(super) fn new(lexed: &'lexed LexedStr<'source>) -> Self {
+        Resolver::at(lexed, 0)
+    }
+
+    pub(super) fn at(lexed: &'lexed LexedStr<'source>, pos: usize) -> Self {
+        Resolver { lexed, pos, store: ExpansionStore::default(), name: None, problems: Vec::new() }
+    }
+
+    pub(super) fn resolve(mut self) -> ExpansionStore<'source> {
+        while !self.eof() {
+            let result = match self.current() {
+                kind if kind.is_definition_keyword() => {
+                    self.definition().map(|item| self.store.insert(item))
+                }
+                SyntaxKind::Command if self.register_command().is_some() => {
+                    let start = self.start();
+                    let result = self.register();
+                    self.finish(start, None, result).map(|item| self.store.push_register(item))
+                }
+                _ => {
+                    self.pos += 1;
+                    continue;
+                }
+            };
+
+            if let Err(errors) = result {
+                // continue after the offending tokens to not get stuck on them, but at the latest
+                // with the next definition among them
+                let start = errors.iter().map(|err| err.range.start).min().unwrap_or(self.pos);
+                let end = errors.iter().fold(self.pos, |pos, err| pos.max(err.range.end));
+                self.pos = (start + 1..end)
+                    .find(|&idx| DEFINITION_FIRST.contains(self.lexed.kind(idx)))
+                    .unwrap_or(end);
+                errors.into_iter().for_each(|err| self.store.push_error(err));
+            }
+        }
+
+        self.store
+    }
+
+    /// Resolves the definition whose keyword is the current token, reporting all of its problems.
+    pub(super) fn definition(
+        &mut self,
+    ) -> Result<ExpansionStoreItem<'source>, Vec<ResolverError<'source>>> {
+        let start = self.start();
+        let (kind, result) = match self.current() {
+            SyntaxKind::NewCommand => (DefinitionKind::Command, self.new_command()),
+            SyntaxKind::Def => (DefinitionKind::Def, self.def()),
+            _ => (DefinitionKind::Environment, self.new_environment()),
+        };
+        self.finish(start, Some(kind), result)
+    }
+
+    /// Starts resolving a new definition at the current token.
+    fn start(&mut self) -> usize {
+        self.name = None;
+        self.problems.clear();
+        self.pos
+    }
+
+    /// Collects the problems of the definition starting at the token `start` into errors
+    /// carrying its context. Any problem discards the definition.
+    fn finish<T>(
+        &mut self,
+        start: usize,
+        definition: Option<DefinitionKind>,
+        result: ResolverResult<'source, T>,
+    ) -> Result<T, Vec<ResolverError<'source>>> {
+        let mut errors = std::mem::take(&mut self.problems);
+        let value = match result {
+            Ok(value) if errors.is_empty() => return Ok(value),
+            Ok(_) => None,
+            Err(err) => Some(err),
+        };
+        errors.extend(value);
+
+        let end = errors.iter().fold(self.pos, |end, err| end.max(err.idx + 1));
+        for err in &mut errors {
+            err.name = self.name;
+            err.definition = definition;
+            err.range = start..end.min(self.lexed.len().max(start + 1));
+            err.span = text_range(self.lexed, err.idx..err.idx + 1);
+            err.definition_span = text_range(self.lexed, err.range.clone());
+        }
+        Err(errors)
+    }
+
+    /// Records a problem which does not stop resolving the current definition.
+    fn report(&mut self, idx: usize, kind: ResolverErrorKind) {
+        self.problems.push(ResolverError::new(idx, kind));
+    }
+
+    /// `\newcommand*{\name}[count][default]{body}`
+    fn new_command(&mut self) -> ResolverResult<'source, ExpansionStoreItem<'source>> {
+        let start = self.bump();
+        // the starred forms define short macros, whose arguments must not contain paragraphs
+        let starred = self.eat(SyntaxKind::Star);
+
+        let name = self.command_name()?;
+        let args = self.args()?;
+        let body = self.body()?;
+
+        Ok(ExpansionStoreItem {
+            name,
+            kind: DefinitionKind::Command,
+            args,
+            body: self.text_of(body),
+            end_body: None,
+            pattern: None,
+            long: !starred,
+            starred,
+            range: start..self.pos,
+        })
+    }
+
+    /// `\def\name<parameter text>{body}`, e.g. `\def\pair(#1,#2){body}`
+    fn def(&mut self) -> ResolverResult<'source, ExpansionStoreItem<'source>> {
+        let start = self.bump();
+
+        let name = self.command_name()?;
+
+        // the parameter text is split at the parameters into the prefix and one delimiter for
+        // every parameter
+        let mut segments = Vec::new();
+        segments.push(self.pos..self.pos);
+        while !self.eof() && !PARAMETERS_FOLLOW.contains(self.current()) {
+            if self.current() == SyntaxKind::SimpleMacroExpansionToken {
+                let number = self.lexed.text(self.pos)[1..].parse::<usize>();
+                if number != Ok(segments.len()) {
+                    self.report(self.pos, ResolverErrorKind::InvalidParameterNumber);
+                }
+                segments.push(self.pos + 1..self.pos + 1);
+            } else {
+                segments.last_mut().unwrap().end = self.pos + 1;
+            }
+            self.pos += 1;
+        }
+        let body = self.body()?;
+
+        let mut segments = segments.into_iter().map(|segment| self.text_of(segment).trim());
+        let prefix = segments.next().unwrap_or_default();
+        let delimiters: Vec<_> = segments.collect();
+
+        Ok(ExpansionStoreItem {
+            name,
+            kind: DefinitionKind::Def,
+            args: ExpansionArgs { count: delimiters.len(), default: None },
+            body: self.text_of(body),
+            end_body: None,
+            pattern: Some(DefPattern { prefix, delimiters }),
+            long: self.is_long(start),
+            starred: false,
+            range: start..self.pos,
+        })
+    }
+
+    /// `\newenvironment*{name}[count][default]{begin}{end}`
+    fn new_environment(&mut self) -> ResolverResult<'source, ExpansionStoreItem<'source>> {
+        let start = self.bump();
+        // the starred forms define short macros, whose arguments must not contain paragraphs
+        let starred = self.eat(SyntaxKind::Star);
+
+        let name = self.group_name()?;
+        let args = self.args()?;
+        let begin = self.body()?;
+        let end = self.body()?;
+
+        Ok(ExpansionStoreItem {
+            name,
+            kind: DefinitionKind::Environment,
+            args,
+            body: self.text_of(begin),
+            end_body: Some(self.text_of(end)),
+            pattern: None,
+            long: !starred,
+            starred,
+            range: start..self.pos,
+        })
+    }
+
+    /// `\newcounter{name}[within]`, `\setcounter{name}{value}`, `\newlength{\name}` or
+    /// `\setlength{\name}{value}`
+    fn register(&mut self) -> ResolverResult<'source, RegisterItem<'source>> {
+        let (kind, declaration) = self.register_command().expect("current token is a register");
+        let start = self.bump();
+
+        let name = match kind {
+            RegisterKind::Counter => self.group_name()?,
+            RegisterKind::Length => self.command_name()?,
+        };
+
+        let value = match declaration {
+            false => {
+                let body = self.body()?;
+                Some(self.text_of(body).trim())
+            }
+            true => {
+                // the optional argument of `\newcounter` names the counter resetting the new one
+                let end = self.pos;
+                self.skip_trivia();
+                if kind == RegisterKind::Counter && self.current() == SyntaxKind::OpenBracket {
+                    self.group()?;
+                } else {
+                    self.pos = end;
+                }
+                None
+            }
+        };
+
+        Ok(RegisterItem { name, kind, declaration, value, range: start..self.pos })
+    }
+
+    /// Whether the prefixes in front of the keyword at `idx` include `\long`, e.g.
+    /// `\long\global\def`.
+    fn is_long(&self, idx: usize) -> bool {
+        (0..idx)
+            .rev()
+            .filter(|&idx| !self.lexed.kind(idx).is_resolver_trivia())
+            .map(|idx| self.lexed.text(idx))
+            .take_while(|text| matches!(*text, "\\long" | "\\global" | "\\protected" | "\\outer"))
+            .any(|text| text == "\\long")
+    }
+
+    fn register_command(&self) -> Option<(RegisterKind, bool)> {
+        registers::register_command(self.lexed.text(self.pos))
+    }
+
+    /// `{name}`
+    ///
+    /// An empty group is reported as a problem and resolving continues with an empty name.
+    fn group_name(&mut self) -> ResolverResult<'source, &'source str> {
+        self.skip_trivia();
+        if self.current() != SyntaxKind::OpenBrace {
+            return Err(self.error(ResolverErrorKind::MissingName));
+        }
+        let open = self.pos;
+        let group = self.group()?;
+        let name = self.text_of(group).trim();
+        if name.is_empty() {
+            self.report(open, ResolverErrorKind::MissingName);
+        } else {
+            self.name = Some(name);
+        }
+        Ok(name)
+    }
+
+    /// `\name` or `{\name}`
+    ///
+    /// A group not containing a single command is reported as a problem and resolving continues
+    /// with an empty name.
+    fn command_name(&mut self) -> ResolverResult<'source, &'source str> {
+        self.skip_trivia();
+
+        if self.current() != SyntaxKind::OpenBrace {
+            if !self.is_command(self.pos) {
+                return Err(self.error(ResolverErrorKind::MissingName));
+            }
+            let name = self.lexed.text(self.bump());
+            self.name = Some(name);
+            return Ok(name);
+        }
+
+        let group = self.group()?;
+        let range = self.trim_trivia(group);
+        if range.len() == 1 && self.is_command(range.start) {
+            let name = self.lexed.text(range.start);
+            self.name = Some(name);
+            return Ok(name);
+        }
+        self.report(range.start, ResolverErrorKind::MissingName);
+        Ok("")
+    }
+
+    /// `[count][default]`
+    fn args(&mut self) -> ResolverResult<'source, ExpansionArgs<'source>> {
+        let mut args = ExpansionArgs::default();
+
+        self.skip_trivia();
+        if self.current() != SyntaxKind::OpenBracket {
+            return Ok(args);
+        }
+
+        let count = self.group()?;
+        match self.text_of(count.clone()).trim().parse() {
+            Ok(count @ 1..=9) => args.count = count,
+            _ => self.report(count.start, ResolverErrorKind::InvalidArgCount),
+        }
+
+        self.skip_trivia();
+        if self.current() == SyntaxKind::OpenBracket {
+            let default = self.group()?;
+            args.default = Some(self.text_of(default));
+        }
+
+        Ok(args)
+    }
+
+    fn body(&mut self) -> ResolverResult<'source, Range<usize>> {
+        self.skip_trivia();
+        if self.current() != SyntaxKind::OpenBrace {
+            return Err(self.error(ResolverErrorKind::MissingBody));
+        }
+        self.group()
+    }
+
+    /// Consumes a brace or bracket group and returns the token range of its content.
+    ///
+    /// Braces have to be balanced inside both kinds of groups. A bracket group is only closed by
+    /// a bracket outside of nested braces, e.g. `[{]}]`.
+    fn group(&mut self) -> ResolverResult<'source, Range<usize>> {
+        let open = self.bump();
+        let close = self.lexed.kind(open).matching_delimiter();
+
+        let mut depth = 0usize;
+        while !self.eof() {
+            match self.current() {
+                kind if Some(kind) == close && depth == 0 => {
+                    self.pos += 1;
+                    return Ok(open + 1..self.pos - 1);
+                }
+                SyntaxKind::OpenBrace => depth += 1,
+                SyntaxKind::CloseBrace => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            self.pos += 1;
+        }
+
+        Err(ResolverError::new(open, ResolverErrorKind::UnclosedGroup))
+    }
+
+    fn trim_trivia(&self, mut range: Range<usize>) -> Range<usize> {
+        while !range.is_empty() && self.lexed.kind(range.start).is_resolver_trivia() {
+            range.start += 1;
+        }
+        while !range.is_empty() && self.lexed.kind(range.end - 1).is_resolver_trivia() {
+            range.end -= 1;
+        }
+        range
+    }
+
+    fn is_command(&self, idx: usize) -> bool {
+        idx < self.lexed.len()
+            && self.lexed.kind(idx) != SyntaxKind::Variable
+            && self.lexed.text(idx).starts_with('\\')
+    }
+
+    fn text_of(&self, range: Range<usize>) -> &'source str {
+        let text = self.lexed.as_str();
+        &text[self.lexed.text_start(range.start)..self.lexed.text_start(range.end)]
+    }
+
+    fn skip_trivia(&mut self) {
+        self.pos = self.lexed.next_non_trivia(self.pos).unwrap_or(self.pos.max(self.lexed.len()));
+    }
+
+    fn eat(&mut self, kind: SyntaxKind) -> bool {
+        self.skip_trivia();
+        let at = self.current() == kind;
+        if at {
+            self.pos += 1;
+        }
+        at
+    }
+
+    fn bump(&mut self) -> usize {
+        self.pos += 1;
+        self.pos - 1
+    }
+
+    fn current(&self) -> SyntaxKind {
+        if self.eof() {
+            SyntaxKind::Eof
+        } else {
+            self.lexed.kind(self.pos)
+        }
+    }
+
+    fn eof(&self) -> bool {
+        self.pos >= self.lexed.len()
+    }
+
+    fn error(&self, kind: ResolverErrorKind) -> ResolverError<'source> {
+        ResolverError::new(self.pos.min(self.lexed.len().saturating_sub(1)), kind)
+    }
+}
@@ -0,0 +1,76 @@
+use std::ops::Range;
+
+use super::{rename, ExpansionStore};
+use crate::{
+    diagnostics::{Diagnostic, Severity},
+    registry::{self, BuiltinCommand},
+    LexedStr,
+};
+
+/// Rule id of the diagnostic reported for redefinitions of standard commands.
+pub const SHADOWED_COMMAND: &str = "shadowed-command";
+
+/// A user definition replacing a standard command, e.g. `\renewcommand{\vec}[1]{\mathbf{#1}}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shadowing<'source> {
+    /// Name of the command including the leading backslash
+    pub name: &'source str,
+    /// The standard command which is replaced
+    pub builtin: &'static BuiltinCommand,
+    /// Signature of the new definition
+    pub signature: String,
+    /// Byte range of the name in the definition
+    pub range: Range<usize>,
+    /// Byte ranges of the uses following the definition, which now get the new meaning
+    pub call_sites: Vec<Range<usize>>,
+}
+
+impl Shadowing<'_> {
+    /// Converts the shadowing into an informational [`Diagnostic`] on the redefined name.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let package = self
+            .builtin
+            .package
+            .map_or("the LaTeX kernel".to_string(), |package| format!("package '{package}'"));
+        let uses = match self.call_sites.len() {
+            1 => "1 use is".to_string(),
+            count => format!("{count} uses are"),
+        };
+        let message = format!(
+            "redefines '{}' of {package}: {} becomes {}, {uses} affected",
+            self.name, self.builtin, self.signature
+        );
+        Diagnostic::new(SHADOWED_COMMAND, Severity::Info, self.range.clone(), message)
+    }
+}
+
+impl<'source> ExpansionStore<'source> {
+    /// Finds all definitions which replace a command of the [`registry`], in source order.
+    ///
+    /// The store is expected to be resolved from `lexed`.
+    pub fn shadowed_commands(&self, lexed: &LexedStr<'source>) -> Vec<Shadowing<'source>> {
+        let mut shadowed: Vec<_> = self
+            .commands()
+            .filter_map(|item| {
+                let builtin = registry::command(item.name)?;
+                let uses = rename::command_uses(lexed, item.range.start, item.name);
+                let name = uses.iter().copied().find(|idx| item.range.contains(idx))?;
+
+                Some(Shadowing {
+                    name: item.name,
+                    builtin,
+                    signature: item.signature(),
+                    range: lexed.text_range(name),
+                    call_sites: uses
+                        .into_iter()
+                        .filter(|&idx| idx >= item.range.end)
+                        .map(|idx| lexed.text_range(idx))
+                        .collect(),
+                })
+            })
+            .collect();
+
+        shadowed.sort_by_key(|shadowing| shadowing.range.start);
+        shadowed
+    }
+}
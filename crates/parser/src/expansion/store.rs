@@ -0,0 +1,404 @@
+use std::{collections::BTreeMap, fmt::Write, ops::Range};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use thiserror::Error;
+
+use super::{arguments, ConditionalFlags, RegisterItem, Registers, ResolverError};
+use crate::{registry, LexedStr, SyntaxKind};
+
+/// All definitions collected from a document.
+///
+/// Commands and environments live in separate namespaces, just like in LaTeX. Commands are keyed
+/// by their name including the leading backslash (e.g. `\R`), environments by their plain name.
+///
+/// Every definition of a name is kept in source order. [`get`](Self::get) returns the last one,
+/// which is in effect at the end of the document, [`get_at`](Self::get_at) the one in effect at a
+/// given token.
+///
+/// The definitions are iterated ordered by their name, so everything derived from a store, down
+/// to its `Debug` output, is the same on every run and platform.
+#[derive(Debug, Default)]
+pub struct ExpansionStore<'source> {
+    commands: BTreeMap<&'source str, Vec<ExpansionStoreItem<'source>>>,
+    environments: BTreeMap<&'source str, Vec<ExpansionStoreItem<'source>>>,
+    registers: Registers<'source>,
+    errors: Vec<ResolverError<'source>>,
+    flags: ConditionalFlags,
+}
+
+impl<'source> ExpansionStore<'source> {
+    pub(super) fn insert(&mut self, item: ExpansionStoreItem<'source>) {
+        let map = match item.kind {
+            DefinitionKind::Environment => &mut self.environments,
+            DefinitionKind::Command | DefinitionKind::Def => &mut self.commands,
+        };
+        map.entry(item.name).or_default().push(item);
+    }
+
+    pub(super) fn push_register(&mut self, item: RegisterItem<'source>) {
+        self.registers.push(item);
+    }
+
+    pub(super) fn push_error(&mut self, error: ResolverError<'source>) {
+        self.errors.push(error);
+    }
+
+    /// Adds the commands and environments of `base` which this store does not define itself, e.g.
+    /// the definitions of a preamble shared by several sources.
+    ///
+    /// The token ranges of the definitions of `base` refer to another source, so the inherited
+    /// definitions get the empty range `0..0`. They are expanded like the definitions of this
+    /// store, but never searched for in its source.
+    pub fn inherit(&mut self, base: &ExpansionStore<'source>) {
+        for item in base.commands().chain(base.environments()) {
+            let map = match item.kind {
+                DefinitionKind::Environment => &mut self.environments,
+                DefinitionKind::Command | DefinitionKind::Def => &mut self.commands,
+            };
+            map.entry(item.name)
+                .or_insert_with(|| vec![ExpansionStoreItem { range: 0..0, ..item.clone() }]);
+        }
+    }
+
+    /// Returns the command definition for `name` (e.g. `\R`), the last one if it is redefined.
+    pub fn get(&self, name: &str) -> Option<&ExpansionStoreItem<'source>> {
+        self.commands.get(name)?.last()
+    }
+
+    /// Returns the definition of the command `name` in effect at the token `idx`: the last one
+    /// which ends before the token. `None` if the command is not defined yet, or `idx` is part of
+    /// its first definition.
+    ///
+    /// Inherited definitions are in effect everywhere, see [`inherit`](Self::inherit).
+    ///
+    /// ```
+    /// use parser::{expansion, LexedStr};
+    ///
+    /// let lexed = LexedStr::new(r"\x \def\x{a} \x \def\x{b} \x");
+    /// let store = expansion::resolve(&lexed);
+    /// let uses: Vec<_> = (0..lexed.len()).filter(|&idx| lexed.text(idx) == r"\x").collect();
+    ///
+    /// let bodies: Vec<_> = uses.iter().map(|&idx| store.get_at(r"\x", idx).map(|item| item.body)).collect();
+    /// assert_eq!(bodies, [None, None, Some("a"), None, Some("b")]);
+    /// assert_eq!(lexed.text_with_expansion(0..lexed.len(), &store), r"\x \def\x{a} a \def\x{b} b");
+    /// ```
+    pub fn get_at(&self, name: &str, idx: usize) -> Option<&ExpansionStoreItem<'source>> {
+        let definitions = self.commands.get(name)?;
+        let effective = definitions.partition_point(|item| item.range.end <= idx);
+        let item = definitions[..effective].last()?;
+        // `idx` inside of a later definition is not a use, definitions are ordered by their start
+        let inside = definitions.get(effective).is_some_and(|next| next.range.start <= idx);
+        (!inside).then_some(item)
+    }
+
+    /// All definitions of the command `name` in source order.
+    pub fn definitions_of(&self, name: &str) -> &[ExpansionStoreItem<'source>] {
+        self.commands.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the environment definition for `name`, the last one if it is redefined.
+    pub fn get_environment(&self, name: &str) -> Option<&ExpansionStoreItem<'source>> {
+        self.environments.get(name)?.last()
+    }
+
+    /// Iterator over the command definitions in effect at the end of the document, one per name,
+    /// ordered by name.
+    pub fn commands(&self) -> impl Iterator<Item = &ExpansionStoreItem<'source>> {
+        self.commands.values().filter_map(|definitions| definitions.last())
+    }
+
+    /// Iterator over all command definitions including the ones replaced by a redefinition,
+    /// ordered by name and then in source order.
+    pub fn all_commands(&self) -> impl Iterator<Item = &ExpansionStoreItem<'source>> {
+        self.commands.values().flatten()
+    }
+
+    /// Iterator over the environment definitions in effect at the end of the document, one per
+    /// name, ordered by name.
+    pub fn environments(&self) -> impl Iterator<Item = &ExpansionStoreItem<'source>> {
+        self.environments.values().filter_map(|definitions| definitions.last())
+    }
+
+    /// Declarations and assignments of counters and lengths.
+    pub fn registers(&self) -> &Registers<'source> {
+        &self.registers
+    }
+
+    /// Errors of definitions which could not be resolved.
+    pub fn errors(&self) -> &[ResolverError<'source>] {
+        &self.errors
+    }
+
+    /// The flags selecting the branches of conditionals during expansion.
+    pub fn flags(&self) -> &ConditionalFlags {
+        &self.flags
+    }
+
+    /// Replaces the flags selecting the branches of conditionals during expansion, see
+    /// [`ConditionalBlock::active_branch`](super::ConditionalBlock::active_branch).
+    pub fn set_flags(&mut self, flags: ConditionalFlags) {
+        self.flags = flags;
+    }
+
+    /// Expands the single command `def_name` with the given arguments.
+    ///
+    /// Only the definition itself is expanded, commands used inside its body are left untouched.
+    /// This allows to preview a definition without analyzing the surrounding document.
+    pub fn preview_expansion(&self, def_name: &str, args: &[&str]) -> Result<String, PreviewError> {
+        self.get(def_name)
+            .ok_or_else(|| PreviewError::UnknownDefinition(def_name.to_string()))?
+            .expand(args)
+    }
+}
+
+/// The command used to create a definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+pub enum DefinitionKind {
+    /// `\newcommand`, `\renewcommand` or `\providecommand`
+    Command,
+    /// TeX primitive `\def`
+    Def,
+    /// `\newenvironment` or `\renewenvironment`
+    Environment,
+}
+
+/// Argument signature of a definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExpansionArgs<'source> {
+    /// Total number of arguments, including the optional one
+    pub count: usize,
+    /// Default value of the optional first argument, if the definition has one
+    pub default: Option<&'source str>,
+}
+
+/// Parameter text of a `\def`, e.g. `(#1,#2)`.
+///
+/// Delimiters are compared token by token when collecting the arguments of a call, whitespace is
+/// ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DefPattern<'source> {
+    /// Text which has to follow the command before the first parameter, e.g. `(`
+    pub prefix: &'source str,
+    /// Delimiter following each parameter, e.g. `,` and `)`. Empty for undelimited parameters.
+    pub delimiters: Vec<&'source str>,
+}
+
+/// A single resolved definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpansionStoreItem<'source> {
+    /// Name of the definition
+    pub name: &'source str,
+    /// Command used to create the definition
+    pub kind: DefinitionKind,
+    /// Argument signature
+    pub args: ExpansionArgs<'source>,
+    /// Replacement text without the surrounding braces. For environments this is the begin code.
+    pub body: &'source str,
+    /// End code of an environment
+    pub end_body: Option<&'source str>,
+    /// Parameter text of a `\def`
+    pub pattern: Option<DefPattern<'source>>,
+    /// Whether the arguments may contain paragraph breaks, i.e. the definition is `\long`. This
+    /// holds for the unstarred forms of `\newcommand` and `\newenvironment`.
+    pub long: bool,
+    /// Whether the definition was made with the starred form of its command, e.g.
+    /// `\newcommand*`. Always `false` for a `\def`.
+    pub starred: bool,
+    /// Token range of the whole definition in the [`LexedStr`] it was resolved from
+    pub range: Range<usize>,
+}
+
+impl<'source> ExpansionStoreItem<'source> {
+    /// Substitutes the parameters of the body with `args`.
+    ///
+    /// If the definition has an optional argument, `args` may omit it and its default value is
+    /// used instead.
+    pub fn expand(&self, args: &[&str]) -> Result<String, PreviewError> {
+        self.substitute(self.body, args)
+    }
+
+    /// Like [`expand`](Self::expand), but substitutes the body of the starred or unstarred
+    /// variant, see [`star_variants`](Self::star_variants). Without variants the whole body is
+    /// used either way.
+    ///
+    /// ```
+    /// use parser::{expansion, LexedStr};
+    ///
+    /// let lexed = LexedStr::new(r"\newcommand{\name}[1]{\@ifstar{#1}{\textbf{#1}}}");
+    /// let name = expansion::resolve(&lexed).get(r"\name").cloned().unwrap();
+    ///
+    /// assert_eq!(name.star_variants(), Some(("#1", r"\textbf{#1}")));
+    /// assert_eq!(name.expand_variant(true, &["x"]).unwrap(), "x");
+    /// assert_eq!(name.expand_variant(false, &["x"]).unwrap(), r"\textbf{x}");
+    /// assert_eq!(name.expand_call("{x}*, y").unwrap(), ("x".to_string(), 4));
+    /// ```
+    pub fn expand_variant(&self, starred: bool, args: &[&str]) -> Result<String, PreviewError> {
+        let body = match self.star_variants() {
+            Some((star, _)) if starred => star,
+            Some((_, plain)) => plain,
+            None => self.body,
+        };
+        self.substitute(body, args)
+    }
+
+    /// The bodies of the starred and the unstarred variant of a command whose body dispatches
+    /// on a following star with `\@ifstar`, e.g. `\@ifstar{\section*}{\section}`. Each
+    /// variant is a brace group, whose braces are removed, or a single token.
+    ///
+    /// Like `\@ifstar` the star is looked for after the arguments of the command, spaces in
+    /// front of it are skipped.
+    pub fn star_variants(&self) -> Option<(&'source str, &'source str)> {
+        let rest = self.body.trim_start().strip_prefix("\\@ifstar")?;
+        // `\@ifstarred` is another command
+        if rest.starts_with(|c: char| c.is_alphabetic() || c == '@') {
+            return None;
+        }
+        let (starred, rest) = star_argument(rest)?;
+        let (plain, _) = star_argument(rest)?;
+        Some((starred, plain))
+    }
+
+    fn substitute(&self, body: &str, args: &[&str]) -> Result<String, PreviewError> {
+        let ExpansionArgs { count, default } = self.args;
+
+        let args: Vec<&str> = match default {
+            Some(default) if args.len() + 1 == count => {
+                std::iter::once(default).chain(args.iter().copied()).collect()
+            }
+            _ if args.len() == count => args.to_vec(),
+            _ => return Err(PreviewError::ArgumentCount { expected: count, found: args.len() }),
+        };
+
+        let lexed = LexedStr::new(body);
+        let mut tokens = lexed.spanned_tokens().peekable();
+        let mut expanded = String::with_capacity(body.len());
+
+        while let Some(token) = tokens.next() {
+            match token.kind {
+                SyntaxKind::SimpleMacroExpansionToken => {
+                    let arg = token.text[1..]
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|n| n.checked_sub(1))
+                        .and_then(|n| args.get(n))
+                        .ok_or_else(|| PreviewError::UndefinedParameter(token.text.to_string()))?;
+                    expanded.push_str(arg);
+                }
+                // '##1' is the parameter of a nested definition and loses one '#'
+                SyntaxKind::NumSign
+                    if tokens.peek().is_some_and(|next| {
+                        matches!(
+                            next.kind,
+                            SyntaxKind::SimpleMacroExpansionToken
+                                | SyntaxKind::ComplexMacroExpansionToken
+                        )
+                    }) =>
+                {
+                    expanded.push_str(tokens.next().unwrap().text);
+                }
+                SyntaxKind::ComplexMacroExpansionToken | SyntaxKind::Variable => {
+                    return Err(PreviewError::MissingContext(token.text.to_string()))
+                }
+                _ => expanded.push_str(token.text),
+            }
+        }
+
+        Ok(expanded)
+    }
+
+    /// Formats the signature of a command, e.g. `\vect[#1]{#2}` or `\pair(#1,#2)` for a `\def`.
+    pub fn signature(&self) -> String {
+        let mut signature = String::from(self.name);
+        let _ =
+            match &self.pattern {
+                Some(pattern) => {
+                    signature.push_str(pattern.prefix);
+                    pattern.delimiters.iter().enumerate().try_for_each(|(idx, delimiter)| {
+                        write!(signature, "#{}{delimiter}", idx + 1)
+                    })
+                }
+                None => {
+                    signature.clear();
+                    let optional = self.args.default.is_some();
+                    registry::write_signature(&mut signature, self.name, self.args.count, optional)
+                }
+            };
+        signature
+    }
+
+    /// Collects the arguments of a call from `input`, the text following the command name, and
+    /// expands the definition with them.
+    ///
+    /// Returns the expansion and the number of bytes of `input` consumed by the arguments. A
+    /// star following them is consumed as well if the command has
+    /// [`star_variants`](Self::star_variants).
+    pub fn expand_call(&self, input: &str) -> Result<(String, usize), PreviewError> {
+        let (args, starred, consumed) = arguments::collect(self, input)?;
+        Ok((self.expand_variant(starred, &args)?, consumed))
+    }
+}
+
+/// Splits an argument of `\@ifstar` off `text`: a brace group without its braces or a single
+/// token, the leading spaces skipped.
+fn star_argument(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    let mut chars = text.char_indices();
+    let end = match chars.next()? {
+        (_, '{') => {
+            let mut depth = 0usize;
+            let mut escaped = false;
+            let close = text.char_indices().find(|&(_, c)| {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            })?;
+            return Some((&text[1..close.0], &text[close.0 + 1..]));
+        }
+        (_, '}') => return None,
+        (_, '\\') => match chars.next()? {
+            (idx, c) if c.is_alphabetic() || c == '@' => chars
+                .find(|&(_, c)| !(c.is_alphabetic() || c == '@'))
+                .map_or(text.len(), |(end, _)| end)
+                .max(idx + c.len_utf8()),
+            (idx, c) => idx + c.len_utf8(),
+        },
+        (_, c) => c.len_utf8(),
+    };
+    Some(text.split_at(end))
+}
+
+/// Error returned when previewing the expansion of a single definition.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PreviewError {
+    /// No command with the given name is defined
+    #[error("no definition found for '{0}'")]
+    UnknownDefinition(String),
+    /// The number of provided arguments does not match the definition
+    #[error("expected {expected} arguments but {found} were provided")]
+    ArgumentCount {
+        /// Number of arguments of the definition
+        expected: usize,
+        /// Number of provided arguments
+        found: usize,
+    },
+    /// The body references a parameter the definition does not declare
+    #[error("the body references the undeclared parameter '{0}'")]
+    UndefinedParameter(String),
+    /// The body depends on document state which is not available in a preview
+    #[error("'{0}' can only be expanded in the context of a document")]
+    MissingContext(String),
+    /// A call provides fewer arguments than the definition requires
+    #[error("argument {0} is missing")]
+    MissingArgument(usize),
+    /// A call does not match the parameter text of a `\def`
+    #[error("use of the command does not match its definition, expected '{0}'")]
+    PatternMismatch(String),
+}
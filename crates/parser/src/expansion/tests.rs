@@ -0,0 +1,675 @@
+use super::{
+    conditionals, join_ranges, resolve, ConditionalFlags, DefPattern, DefinitionKind,
+    ExpansionCache, ExpansionTrace, PreviewError, RegisterKind, RenameError, ResolverErrorKind,
+    TextEdit, PARAGRAPH_IN_ARGUMENT, RECURSIVE_DEFINITION, SHADOWED_COMMAND,
+};
+use crate::{diagnostics::Severity, LexedStr};
+
+#[test]
+fn resolve_new_command() {
+    let lexed = LexedStr::new("\\newcommand{\\R}[1]{\\mathbb{#1}}\n\\newcommand*\\half{\\frac12}");
+    let store = resolve(&lexed);
+
+    let r = store.get("\\R").unwrap();
+    assert_eq!(r.kind, DefinitionKind::Command);
+    assert_eq!(r.args.count, 1);
+    assert_eq!(r.body, "\\mathbb{#1}");
+
+    let half = store.get("\\half").unwrap();
+    assert_eq!(half.args.count, 0);
+    assert_eq!(half.body, "\\frac12");
+    assert!(store.errors().is_empty());
+}
+
+#[test]
+fn resolve_optional_argument() {
+    let lexed = LexedStr::new("\\newcommand{\\vect}[2][n]{#2_1, \\dots, #2_#1}");
+    let store = resolve(&lexed);
+
+    let vect = store.get("\\vect").unwrap();
+    assert_eq!(vect.args.count, 2);
+    assert_eq!(vect.args.default, Some("n"));
+}
+
+#[test]
+fn resolve_def_and_environment() {
+    let lexed = LexedStr::new(
+        "\\def\\pair#1#2{(#1, #2)}\n\\newenvironment{note}[1]{\\textbf{#1}: }{\\par}",
+    );
+    let store = resolve(&lexed);
+
+    let pair = store.get("\\pair").unwrap();
+    assert_eq!(pair.kind, DefinitionKind::Def);
+    assert_eq!(pair.args.count, 2);
+
+    let note = store.get_environment("note").unwrap();
+    assert_eq!(note.body, "\\textbf{#1}: ");
+    assert_eq!(note.end_body, Some("\\par"));
+}
+
+#[test]
+fn resolve_errors() {
+    let lexed = LexedStr::new("\\newcommand{\\a}[x]{a} \\newcommand{b}{b} \\newcommand{\\c}{c");
+    let store = resolve(&lexed);
+
+    let errors: Vec<_> = store.errors().iter().map(|err| err.kind).collect();
+    assert_eq!(
+        errors,
+        vec![
+            ResolverErrorKind::InvalidArgCount,
+            ResolverErrorKind::MissingName,
+            ResolverErrorKind::UnclosedGroup
+        ]
+    );
+    assert_eq!(store.commands().count(), 0);
+}
+
+#[test]
+fn resolve_errors_recovery() {
+    // broken definitions do not swallow the next one
+    let lexed = LexedStr::new("\\def\\a#1 \\newcommand{\\b}{b} \\newcommand{\\c}{\\def\\d{d}");
+    let store = resolve(&lexed);
+
+    let errors: Vec<_> = store.errors().iter().map(|err| (err.kind, err.name)).collect();
+    assert_eq!(
+        errors,
+        [
+            (ResolverErrorKind::MissingBody, Some("\\a")),
+            (ResolverErrorKind::UnclosedGroup, Some("\\c"))
+        ]
+    );
+    let names: Vec<_> = store.commands().map(|item| item.name).collect();
+    assert_eq!(names, ["\\b", "\\d"]);
+}
+
+#[test]
+fn resolve_errors_context() {
+    let input = "\\newcommand{x}[y]{a} \\def\\pair#2#3{} \\newenvironment{proof}[0]{}";
+    let lexed = LexedStr::new(input);
+    let store = resolve(&lexed);
+
+    let errors: Vec<_> =
+        store.errors().iter().map(|err| (err.kind, err.name, err.definition)).collect();
+    assert_eq!(
+        errors,
+        [
+            (ResolverErrorKind::MissingName, None, Some(DefinitionKind::Command)),
+            (ResolverErrorKind::InvalidArgCount, None, Some(DefinitionKind::Command)),
+            (ResolverErrorKind::InvalidParameterNumber, Some("\\pair"), Some(DefinitionKind::Def)),
+            (ResolverErrorKind::InvalidParameterNumber, Some("\\pair"), Some(DefinitionKind::Def)),
+            (ResolverErrorKind::InvalidArgCount, Some("proof"), Some(DefinitionKind::Environment)),
+            (ResolverErrorKind::MissingBody, Some("proof"), Some(DefinitionKind::Environment)),
+        ]
+    );
+    assert_eq!(store.errors()[0].range, store.errors()[1].range);
+    assert!(store.get("\\pair").is_none() && store.environments().count() == 0);
+
+    let diagnostic = store.errors()[3].to_diagnostic(&lexed);
+    assert_eq!(&input[diagnostic.range.clone()], "#3");
+    assert_eq!(
+        diagnostic.message,
+        "parameters must be numbered consecutively in the definition of '\\pair'"
+    );
+    assert_eq!(&input[diagnostic.related[0].range.clone()], "\\def\\pair#2#3{}");
+    assert_eq!(store.errors()[3].span(), diagnostic.range);
+    assert_eq!(store.errors()[3].definition_span, diagnostic.related[0].range);
+}
+
+#[test]
+fn preview_expansion() {
+    let lexed = LexedStr::new(
+        "\\newcommand{\\R}[1]{\\mathbb{#1}}\n\\newcommand{\\vect}[2][n]{#2_1, \\dots, #2_#1}",
+    );
+    let store = resolve(&lexed);
+
+    assert_eq!(store.preview_expansion("\\R", &["N"]).unwrap(), "\\mathbb{N}");
+    assert_eq!(store.preview_expansion("\\vect", &["x"]).unwrap(), "x_1, \\dots, x_n");
+    assert_eq!(store.preview_expansion("\\vect", &["m", "y"]).unwrap(), "y_1, \\dots, y_m");
+}
+
+#[test]
+fn preview_expansion_errors() {
+    let lexed = LexedStr::new(
+        "\\newcommand{\\R}[1]{\\mathbb{#1}}\n\\def\\bad{#2}\n\\newcommand{\\var}{\\@year}",
+    );
+    let store = resolve(&lexed);
+
+    assert_eq!(
+        store.preview_expansion("\\Q", &[]),
+        Err(PreviewError::UnknownDefinition("\\Q".to_string()))
+    );
+    assert_eq!(
+        store.preview_expansion("\\R", &[]),
+        Err(PreviewError::ArgumentCount { expected: 1, found: 0 })
+    );
+    assert_eq!(
+        store.preview_expansion("\\bad", &[]),
+        Err(PreviewError::UndefinedParameter("#2".to_string()))
+    );
+    assert_eq!(
+        store.preview_expansion("\\var", &[]),
+        Err(PreviewError::MissingContext("\\@year".to_string()))
+    );
+}
+
+#[test]
+fn def_pattern() {
+    let lexed = LexedStr::new("\\def\\pair(#1,#2){(#2, #1)}\n\\def\\first#1#2{#1}");
+    let store = resolve(&lexed);
+
+    let pair = store.get("\\pair").unwrap();
+    assert_eq!(pair.args.count, 2);
+    assert_eq!(pair.pattern, Some(DefPattern { prefix: "(", delimiters: vec![",", ")"] }));
+    assert_eq!(pair.expand_call("(a, {b,c}) rest").unwrap(), ("(b,c, a)".to_string(), 10));
+
+    let first = store.get("\\first").unwrap();
+    assert_eq!(first.pattern, Some(DefPattern { prefix: "", delimiters: vec!["", ""] }));
+    assert_eq!(first.expand_call(" {ab} c d").unwrap(), ("ab".to_string(), 7));
+}
+
+#[test]
+fn def_pattern_errors() {
+    let lexed = LexedStr::new("\\def\\pair(#1,#2){}\n\\def\\bad#2{}\n\\newcommand{\\R}[2][x]{}");
+    let store = resolve(&lexed);
+
+    assert_eq!(store.errors().len(), 1);
+    assert_eq!(store.errors()[0].kind, ResolverErrorKind::InvalidParameterNumber);
+
+    let pair = store.get("\\pair").unwrap();
+    assert_eq!(pair.expand_call("a,b)"), Err(PreviewError::PatternMismatch("(".to_string())));
+    assert_eq!(pair.expand_call("(a b)"), Err(PreviewError::PatternMismatch(",".to_string())));
+
+    let r = store.get("\\R").unwrap();
+    assert_eq!(r.expand_call("[y]"), Err(PreviewError::MissingArgument(2)));
+    assert_eq!(r.expand_call("z"), Ok((String::new(), 1)));
+}
+
+#[test]
+fn starred_variants() {
+    let lexed = LexedStr::new(
+        "\\makeatletter\n\\newcommand*{\\name}{\\@ifstar\\name@star{\\textbf{\\name@plain}}}\n\
+         \\newcommand{\\pair}[2]{\\@ifstar {#2, #1}{(#1, #2)}}\n\
+         \\newcommand{\\other}{\\@ifstarred{a}{b}}\\newenvironment*{box}{}{}\n\
+         \\name* \\name, \\pair{a}{b} * \\pair{a}{b}, \\other*",
+    );
+    let store = resolve(&lexed);
+
+    let name = store.get("\\name").unwrap();
+    assert!(name.starred && !name.long);
+    assert_eq!(name.star_variants(), Some(("\\name@star", "\\textbf{\\name@plain}")));
+    assert!(!store.get("\\pair").unwrap().starred && store.get_environment("box").unwrap().starred);
+    assert_eq!(store.get("\\other").unwrap().star_variants(), None);
+
+    // the star follows the arguments, `\other` needs the context of `\@ifstarred` and is kept
+    let start = (0..lexed.len()).rposition(|idx| lexed.text(idx) == "\\name").unwrap() - 3;
+    assert_eq!(
+        lexed.text_with_expansion(start..lexed.len(), &store),
+        "\\name@star \\textbf{\\name@plain}, b, a (a, b), \\other*"
+    );
+}
+
+#[test]
+fn text_with_expansion() {
+    let lexed = LexedStr::new(
+        "\\newcommand{\\R}[1]{\\mathbb{#1}}\n\\def\\pair(#1,#2){(#2, #1)}\n$\\R{N} \\pair(a,b) \\pair x$",
+    );
+    let store = resolve(&lexed);
+
+    let start = lexed.spanned_tokens().position(|token| token.text == "$").unwrap();
+    assert_eq!(
+        lexed.text_with_expansion(start..lexed.len(), &store),
+        "$\\mathbb{N} (b, a) \\pair x$"
+    );
+    assert_eq!(
+        lexed.text_with_expansion(0..lexed.len(), &resolve(&LexedStr::new(""))),
+        lexed.as_str()
+    );
+
+    let limited = |max_len| lexed.text_with_expansion_limited(start..lexed.len(), &store, max_len);
+    assert_eq!(limited(27).as_deref(), Some("$\\mathbb{N} (b, a) \\pair x$"));
+    assert_eq!(limited(26), None);
+}
+
+#[test]
+fn traced_expansion() {
+    let lexed = LexedStr::new(
+        "\\newcommand{\\norm}[1]{\\abs{#1}^2}\n\\newcommand{\\abs}[1]{|#1|}\n\\def\\loop{a\\loop}\n$\\norm{\\abs{x}}$ \\loop",
+    );
+    let store = resolve(&lexed);
+    let start = lexed.spanned_tokens().position(|token| token.text == "$").unwrap();
+    let end = lexed.len() - 2;
+
+    let mut trace = ExpansionTrace::new();
+    assert_eq!(lexed.text_with_traced_expansion(start..end, &store, &mut trace), "$||x||^2$");
+    assert!(!trace.is_truncated());
+    let steps: Vec<_> = trace
+        .steps()
+        .iter()
+        .map(|step| (step.depth, step.call.as_str(), step.arguments.clone(), step.output.as_str()))
+        .collect();
+    assert_eq!(
+        steps,
+        [
+            (0, "\\norm{\\abs{x}}", vec!["\\abs{x}".to_string()], "\\abs{\\abs{x}}^2"),
+            (1, "\\abs{\\abs{x}}", vec!["\\abs{x}".to_string()], "|\\abs{x}|"),
+            (2, "\\abs{x}", vec!["x".to_string()], "|x|"),
+        ]
+    );
+    let first = &trace.steps()[0];
+    assert_eq!(
+        (first.name.as_str(), &lexed.as_str()[first.range.clone()]),
+        ("\\norm", first.call.as_str())
+    );
+
+    // recursive definitions stop at the maximal depth
+    let mut trace = ExpansionTrace::new();
+    trace.max_depth = 3;
+    let text = lexed.text_with_traced_expansion(end + 1..lexed.len(), &store, &mut trace);
+    assert_eq!((text.as_str(), trace.steps().len()), ("aaaa\\loop", 4));
+    assert!(trace.is_truncated());
+
+    // definitions are not expanded
+    let mut trace = ExpansionTrace::new();
+    lexed.text_with_traced_expansion(0..start, &store, &mut trace);
+    assert!(trace.steps().is_empty());
+}
+
+#[test]
+fn rename_command() {
+    let source = "\\newcommand{\\R}{\\mathbb{R}}\n$\\R \\Rn$ % \\R\n\\verb|\\R| \\verb*+\\R+\n\\begin{verbatim}\\R\\end{verbatim}\n{\\def\\local{x}\\local}\\local";
+    let lexed = LexedStr::new(source);
+    let store = resolve(&lexed);
+
+    let apply = |edits: Vec<TextEdit>| {
+        let mut text = source.to_string();
+        for edit in edits.into_iter().rev() {
+            text.replace_range(edit.range, &edit.new_text);
+        }
+        text
+    };
+
+    let edits = store.rename_command(&lexed, "\\R", "\\Reals").unwrap();
+    assert_eq!(
+        apply(edits),
+        source.replacen("{\\R}", "{\\Reals}", 1).replacen("$\\R ", "$\\Reals ", 1)
+    );
+
+    let edits = store.rename_command(&lexed, "\\local", "\\inner").unwrap();
+    assert_eq!(
+        apply(edits),
+        source.replace("{\\def\\local{x}\\local}", "{\\def\\inner{x}\\inner}")
+    );
+}
+
+#[test]
+fn rename_command_errors() {
+    let lexed = LexedStr::new("\\newcommand{\\R}{x}\\def\\N{y}");
+    let store = resolve(&lexed);
+
+    assert_eq!(
+        store.rename_command(&lexed, "\\Q", "\\P"),
+        Err(RenameError::UnknownDefinition("\\Q".to_string()))
+    );
+    for name in ["R", "\\a b", "\\def", ""] {
+        assert_eq!(
+            store.rename_command(&lexed, "\\R", name),
+            Err(RenameError::InvalidName(name.to_string()))
+        );
+    }
+    assert_eq!(
+        store.rename_command(&lexed, "\\R", "\\N"),
+        Err(RenameError::AlreadyDefined("\\N".to_string()))
+    );
+}
+
+#[test]
+fn export() {
+    let source = "\\newenvironment{box}{[}{]}\n\\newcommand{\\vect}[2][n]{#2_1, \\dots, #2_#1}\n\\def\\pair(#1,#2){}";
+    let lexed = LexedStr::new(source);
+    let definitions = resolve(&lexed).export(&lexed);
+
+    let names: Vec<_> = definitions.iter().map(|definition| definition.name).collect();
+    assert_eq!(names, ["box", "\\vect", "\\pair"]);
+
+    let vect = &definitions[1];
+    assert_eq!(&source[vect.span.clone()], "\\newcommand{\\vect}[2][n]{#2_1, \\dots, #2_#1}");
+    assert_eq!(
+        (vect.kind, vect.arg_count, vect.default_arg),
+        (DefinitionKind::Command, 2, Some("n"))
+    );
+    assert_eq!(definitions[0].end_body, Some("]"));
+    assert_eq!(definitions[2].pattern.as_ref().unwrap().delimiters, [",", ")"]);
+}
+
+#[test]
+fn stable_order() {
+    let preamble = LexedStr::new("\\def\\c{}\\def\\a{}\\newenvironment{z}{}{}\\def\\b{}");
+    let lexed = LexedStr::new("\\def\\y{}\\def\\x{}\\def\\y{y}");
+    let store = || {
+        let mut store = resolve(&lexed);
+        store.inherit(&resolve(&preamble));
+        store
+    };
+
+    let names: Vec<_> = store().all_commands().map(|item| item.name).collect();
+    assert_eq!(names, ["\\a", "\\b", "\\c", "\\x", "\\y", "\\y"]);
+    let names: Vec<_> = store().export(&lexed).iter().map(|definition| definition.name).collect();
+    assert_eq!(names, ["\\a", "\\b", "\\c", "z", "\\x", "\\y"]);
+    assert_eq!(format!("{:?}", store()), format!("{:?}", store()));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn export_json() {
+    let lexed = LexedStr::new("\\newcommand{\\R}[1]{\\mathbb{#1}}");
+    let json = serde_json::to_value(resolve(&lexed).export(&lexed)).unwrap();
+
+    assert_eq!(
+        json,
+        serde_json::json!([{
+            "name": "\\R",
+            "kind": "command",
+            "arg_count": 1,
+            "default_arg": null,
+            "pattern": null,
+            "body": "\\mathbb{#1}",
+            "end_body": null,
+            "long": true,
+            "starred": false,
+            "span": { "start": 0, "end": 31 }
+        }])
+    );
+}
+
+#[test]
+fn shadowed_commands() {
+    let lexed = LexedStr::new(
+        "$\\vec{v}$ \\renewcommand{\\vec}[1]{\\mathbf{#1}} $\\vec{a} + \\vec b$\n\
+         {\\def\\phi(#1){\\varphi_{#1}} \\phi(x)} \\phi \\newcommand{\\R}{\\mathbb{R}}",
+    );
+    let store = resolve(&lexed);
+    let shadowed = store.shadowed_commands(&lexed);
+
+    let names: Vec<_> = shadowed.iter().map(|shadowing| shadowing.name).collect();
+    assert_eq!(names, ["\\vec", "\\phi"]);
+
+    let vec = &shadowed[0];
+    assert_eq!(
+        (vec.builtin.to_string(), vec.signature.as_str()),
+        ("\\vec{#1}".into(), "\\vec{#1}")
+    );
+    assert_eq!(vec.call_sites.len(), 2);
+    assert_eq!(&lexed.as_str()[vec.range.clone()], "\\vec");
+
+    // the definition of \phi is only visible inside of its group
+    let phi = &shadowed[1];
+    assert_eq!(phi.signature, "\\phi(#1)");
+    assert_eq!(phi.call_sites.len(), 1);
+
+    let diagnostic = phi.to_diagnostic();
+    assert_eq!((diagnostic.rule, diagnostic.severity), (SHADOWED_COMMAND, Severity::Info));
+    assert_eq!(
+        diagnostic.message,
+        "redefines '\\phi' of the LaTeX kernel: \\phi becomes \\phi(#1), 1 use is affected"
+    );
+}
+
+#[test]
+fn recursive_definitions() {
+    let lexed = LexedStr::new(
+        "\\newcommand{\\loop}{\\loop} \\def\\even{\\odd}\\def\\odd{\\even}\n\
+         \\newcommand{\\step}{\\ifnum\\value{x}>3 \\step\\fi} \\newcommand{\\fine}{\\even}",
+    );
+    let recursions = resolve(&lexed).recursions(&lexed);
+    let cycles: Vec<_> = recursions.iter().map(|recursion| &recursion.cycle[..]).collect();
+    assert_eq!(cycles, [&["\\loop"][..], &["\\even", "\\odd"]]);
+    assert!(recursions[0].is_direct());
+    assert_eq!(&lexed.as_str()[recursions[1].range.clone()], "\\even");
+
+    let diagnostic = recursions[1].to_diagnostic();
+    assert_eq!((diagnostic.rule, diagnostic.severity), (RECURSIVE_DEFINITION, Severity::Warning));
+    assert_eq!(diagnostic.message, "'\\even' expands to itself through \\even -> \\odd -> \\even");
+    assert_eq!(recursions[0].to_diagnostic().message, "'\\loop' expands to itself");
+
+    // a redefinition calling the saved original is no recursion
+    let lexed = LexedStr::new("\\let\\old\\emph \\renewcommand{\\emph}[1]{\\old{#1}}");
+    assert!(resolve(&lexed).recursions(&lexed).is_empty());
+}
+
+#[test]
+fn resolve_registers() {
+    let lexed = LexedStr::new(
+        "\\newcounter{step}[section] \\setcounter{step}{3}\n\
+         \\newlength\\gap \\setlength{\\gap}{1.5em plus 2pt}\n\
+         \\newlength{\\half}\\setlength\\half{0.5\\textwidth}\n\
+         \\setlength{\\parindent}{0pt} \\newcounter{plain} \\setcounter{page}{\\value{step}}",
+    );
+    let store = resolve(&lexed);
+    let registers = store.registers();
+    assert!(store.errors().is_empty());
+
+    let counters: Vec<_> =
+        registers.declarations(RegisterKind::Counter).map(|item| item.name).collect();
+    assert_eq!(counters, ["step", "plain"]);
+    let step = registers.declaration(RegisterKind::Counter, "step").unwrap();
+    assert_eq!(lexed.text_start(step.range.end), 26);
+
+    assert_eq!(registers.initial_value(RegisterKind::Counter, "step"), Some("3"));
+    assert_eq!(registers.initial_value(RegisterKind::Counter, "plain"), Some("0"));
+    assert_eq!(registers.initial_value(RegisterKind::Length, "\\gap"), Some("1.5em plus 2pt"));
+    assert_eq!(registers.initial_value(RegisterKind::Length, "\\half"), None);
+    // not declared in the document
+    assert_eq!(registers.initial_value(RegisterKind::Length, "\\parindent"), None);
+
+    let page = registers.assignments(RegisterKind::Counter, "page").next().unwrap();
+    assert_eq!((page.value, page.integer()), (Some("\\value{step}"), None));
+    assert_eq!(registers.items().len(), 9);
+
+    let gap = registers.assignments(RegisterKind::Length, "\\gap").next().unwrap();
+    assert_eq!(gap.dimension().unwrap().to_string(), "1.5em plus 2pt");
+
+    let lexed = LexedStr::new("\\setlength{\\gap} x \\newcounter{}");
+    let errors: Vec<_> = resolve(&lexed).errors().iter().map(|err| err.kind).collect();
+    assert_eq!(errors, [ResolverErrorKind::MissingBody, ResolverErrorKind::MissingName]);
+}
+
+#[test]
+fn paragraphs_in_arguments() {
+    let input =
+        "\\newcommand*{\\short}[2][x]{#1#2}\\newcommand{\\full}[1]{#1}\\def\\pair(#1){#1}\n\
+                 \\long\\global\\def\\lpair(#1){#1}\n\
+                 \\short{a\n\nb} \\short[a\\par b]{c} \\short{a}\n\nb \\full{a\n\nb}\n\
+                 \\pair(a\n\nb) \\lpair(a\n\nb) \\short\n\n{a}";
+    let lexed = LexedStr::new(input);
+    let store = resolve(&lexed);
+
+    let long: Vec<_> = ["\\short", "\\full", "\\pair", "\\lpair"]
+        .into_iter()
+        .map(|name| store.get(name).unwrap().long)
+        .collect();
+    assert_eq!(long, [false, true, false, true]);
+
+    let found = store.paragraphs_in_arguments(&lexed);
+    let calls: Vec<_> =
+        found.iter().map(|paragraph| (paragraph.name, &input[paragraph.range.clone()])).collect();
+    assert_eq!(
+        calls,
+        [("\\short", "\n\n"), ("\\short", "\\par"), ("\\pair", "\n\n"), ("\\short", "\n\n")]
+    );
+
+    let diagnostic = found[0].to_diagnostic();
+    assert_eq!((diagnostic.rule, diagnostic.severity), (PARAGRAPH_IN_ARGUMENT, Severity::Error));
+    assert_eq!(&input[diagnostic.related[0].range.clone()], "\\short");
+    assert_eq!(&input[diagnostic.related[1].range.clone()], "\\newcommand*{\\short}[2][x]{#1#2}");
+}
+
+#[test]
+fn conditional_pairing() {
+    let input = "\\newif\\ifdraft\n\\ifdefined\\x a\\ifdraft b\\else c\\fi\\else d\\fi\n\
+                 \\ifcase\\value{page} e\\or f\\else g\\fi \\ifnum 1<2 \\or\\fi \\ifx\\a\\b";
+    let lexed = LexedStr::new(input);
+    let blocks = conditionals(&lexed, &ConditionalFlags::new());
+    let text = |range: std::ops::Range<usize>| {
+        &input[lexed.text_start(range.start)..lexed.text_start(range.end)]
+    };
+
+    let names: Vec<_> = blocks.iter().map(|block| lexed.text(block.range.start)).collect();
+    assert_eq!(names, ["\\ifdefined", "\\ifdraft", "\\ifcase", "\\ifnum", "\\ifx"]);
+    let tests: Vec<_> = blocks.iter().map(|block| text(block.test.clone()).trim()).collect();
+    assert_eq!(tests, ["\\x", "", "\\value{page}", "1<2", "\\a\\b"]);
+
+    let branches: Vec<Vec<_>> = blocks
+        .iter()
+        .map(|block| block.branches().into_iter().map(|branch| text(branch).trim()).collect())
+        .collect();
+    assert_eq!(branches[0], ["a\\ifdraft b\\else c\\fi", "d"]);
+    assert_eq!(branches[2], ["e", "f", "g"]);
+    // `\or` only separates the branches of `\ifcase`
+    assert_eq!(branches[3], ["\\or"]);
+    assert!(blocks[..4].iter().all(|block| block.closed));
+    assert!(!blocks[4].closed);
+}
+
+#[test]
+fn conditional_branches() {
+    let input = "\\newcommand{\\x}{X}\\newif\\ifdraft\n\
+                 \\ifdraft \\x\\else final\\fi|\\iffalse a\\fi|\\iftrue b\\else c\\fi|\\ifdefined\\x d\\fi";
+    let lexed = LexedStr::new(input);
+    let mut store = resolve(&lexed);
+    let start = lexed.spanned_tokens().position(|token| token.text == "\n").unwrap() + 1;
+
+    // the value of `\ifdraft` and `\ifdefined` is unknown, both branches are kept
+    assert_eq!(
+        lexed.text_with_expansion(start..lexed.len(), &store),
+        "\\ifdraft X\\else final\\fi|| b|\\ifdefined\\x d\\fi"
+    );
+
+    let mut flags = ConditionalFlags::new();
+    flags.set("draft", true);
+    store.set_flags(flags.clone());
+    assert_eq!(store.flags().get("draft"), Some(true));
+    assert_eq!(
+        lexed.text_with_expansion(start..lexed.len(), &store),
+        " X|| b|\\ifdefined\\x d\\fi"
+    );
+
+    let blocks = conditionals(&lexed, &flags);
+    let active = blocks[0].active_branch(&lexed, &flags).unwrap();
+    assert_eq!(lexed.text(active.start), " ");
+    flags.set("draft", false);
+    assert_eq!(blocks[0].value(&lexed, &flags), Some(false));
+    assert_eq!(blocks[3].active_branch(&lexed, &flags), None);
+}
+
+#[test]
+fn inherit_definitions() {
+    let preamble = LexedStr::new("\\newcommand{\\R}{\\mathbb{R}}\\newenvironment{note}{[}{]}");
+    let lexed = LexedStr::new("\\newcommand{\\R}{r}\\begin{note}$\\R$\\end{note}");
+    let mut store = resolve(&lexed);
+    store.inherit(&resolve(&preamble));
+
+    // definitions of the store itself win, inherited ones do not point into its source
+    assert_eq!(store.get("\\R").unwrap().body, "r");
+    assert_eq!(store.get_environment("note").unwrap().range, 0..0);
+    assert_eq!(
+        lexed.text_with_expansion(0..lexed.len(), &store),
+        "\\newcommand{\\R}{r}\\begin{note}$r$\\end{note}"
+    );
+}
+
+#[test]
+fn redefinitions() {
+    let lexed = LexedStr::new(
+        "\\R \\newcommand{\\R}{\\mathbb{R}}\\R \\renewcommand{\\R}[1]{#1}\\R{x} \\def\\y{\\R{y}}\\y",
+    );
+    let store = resolve(&lexed);
+    let uses: Vec<_> = (0..lexed.len()).filter(|&idx| lexed.text(idx) == "\\R").collect();
+
+    assert_eq!(store.definitions_of("\\R").len(), 2);
+    assert_eq!(store.get("\\R").unwrap().body, "#1");
+    let bodies: Vec<_> =
+        uses.iter().map(|&idx| store.get_at("\\R", idx).map(|item| item.body)).collect();
+    assert_eq!(bodies, [None, None, Some("\\mathbb{R}"), None, Some("#1"), Some("#1")]);
+
+    // every call is expanded with the definition in effect at its position
+    assert_eq!(
+        lexed.text_with_expansion(0..lexed.len(), &store),
+        "\\R \\newcommand{\\R}{\\mathbb{R}}\\mathbb{R} \\renewcommand{\\R}[1]{#1}x \\def\\y{\\R{y}}\\R{y}"
+    );
+    let mut trace = ExpansionTrace::new();
+    let text = lexed.text_with_traced_expansion(0..lexed.len(), &store, &mut trace);
+    assert!(text.ends_with("}x \\def\\y{\\R{y}}y"));
+
+    // thousands of redefinitions expand in linear time
+    let source: String =
+        (0..4000).map(|i| format!("\\renewcommand{{\\x}}[1]{{{i}#1}}\\x{{.}} ")).collect();
+    let lexed = LexedStr::new(&source);
+    let store = resolve(&lexed);
+    let expanded = lexed.text_with_expansion(0..lexed.len(), &store);
+    assert!(expanded.contains("}0. ") && expanded.ends_with("}3999. "));
+}
+
+#[test]
+fn joined_lines() {
+    let join = |source: &str, tokens: fn(usize) -> std::ops::Range<usize>| {
+        let lexed = LexedStr::new(source);
+        let mut edits = Vec::new();
+        join_ranges(&mut edits, &lexed, tokens(lexed.len()));
+        let mut joined = source.to_string();
+        for edit in edits.iter().rev() {
+            joined.replace_range(edit.range.clone(), &edit.new_text);
+        }
+        joined
+    };
+
+    // spaces on a single line stay, comments move in front of the one ending the line
+    assert_eq!(join("\\cite\n  [p. 3]\n  {knuth}", |len| 0..len), "\\cite[p. 3] {knuth}");
+    assert_eq!(
+        join("a % one\n% two\n  b % three\nc", |len| 0..len - 2),
+        "a b % one % two % three\nc"
+    );
+    assert_eq!(join("\\x\n\n{y}", |len| 0..len), "\\x{y}");
+    assert_eq!(join(" a  b ", |len| 0..len), " a  b ");
+}
+
+#[test]
+fn cached_expansion() {
+    let mut cache = ExpansionCache::new();
+    let expand = |cache: &mut ExpansionCache, source: &str| {
+        let lexed = LexedStr::new(source);
+        let store = resolve(&lexed);
+        let cached = lexed.text_with_cached_expansion(0..lexed.len(), &store, usize::MAX, cache);
+        assert_eq!(cached.as_deref(), Some(&*lexed.text_with_expansion(0..lexed.len(), &store)));
+        let stats = cache.stats();
+        cache.reset_stats();
+        (stats.hits, stats.misses)
+    };
+
+    let definitions = "\\newcommand{\\v}[1]{\\mathbf{#1}}\n\\newcommand{\\s}{\\@ifstar{a}{b}}\n";
+    assert_eq!(
+        expand(&mut cache, &format!("{definitions}$\\v{{x}} \\v{{y}} \\v{{x}}$ \\s* \\s")),
+        (1, 4)
+    );
+    // an edit far from the calls, and a moved definition keeps its fingerprint
+    assert_eq!(
+        expand(&mut cache, &format!("Intro\n{definitions}$\\v{{x}} \\v{{y}}$ \\s \\s*")),
+        (4, 0)
+    );
+    // a changed definition or argument is expanded again
+    let changed = "\\newcommand{\\v}[1]{\\vec{#1}}\n";
+    assert_eq!(expand(&mut cache, &format!("{changed}$\\v{{x}} \\v{{z}}$")), (0, 2));
+    assert_eq!(expand(&mut cache, &format!("{changed}$\\v{{ x}}$")), (0, 1));
+
+    let a = resolve(&LexedStr::new("\\newcommand{\\a}[1]{#1}"));
+    let b = resolve(&LexedStr::new("\\def\\b#1{#1}"));
+    let (a, b) = (a.get("\\a").unwrap(), b.get("\\b").unwrap());
+    assert_eq!(a.fingerprint(), a.clone().fingerprint());
+    assert_ne!(a.fingerprint(), b.fingerprint());
+
+    cache.max_entries = 2;
+    assert_eq!(cache.len(), 7);
+    expand(&mut cache, "\\newcommand{\\y}{y} \\y");
+    assert_eq!(cache.len(), 1);
+    cache.clear();
+    assert!(cache.is_empty());
+}
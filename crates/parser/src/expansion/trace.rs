@@ -0,0 +1,138 @@
+use std::ops::Range;
+
+use super::{arguments::collect_at, ExpansionStore};
+use crate::{LexedStr, SyntaxKind};
+
+/// A single substitution recorded in an [`ExpansionTrace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpansionStep {
+    /// Name of the expanded command including the leading backslash
+    pub name: String,
+    /// Text of the call, i.e. the command and its arguments
+    pub call: String,
+    /// Byte range of the call in the text it was expanded from: the source for calls at depth
+    /// zero, the [`output`](Self::output) of the enclosing step otherwise
+    pub range: Range<usize>,
+    /// The arguments substituted for `#1`, `#2` and so on, including the default of an omitted
+    /// optional argument
+    pub arguments: Vec<String>,
+    /// The body with the arguments substituted, before the calls inside of it are expanded
+    pub output: String,
+    /// Number of enclosing expansions, zero for a call in the expanded text itself
+    pub depth: usize,
+}
+
+/// Records every substitution of [`LexedStr::text_with_traced_expansion`], to find out why a
+/// macro expands differently than expected.
+///
+/// Steps are recorded in the order of the expansion: every step is followed by the steps of the
+/// calls in its output, which have a larger [`depth`](ExpansionStep::depth).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpansionTrace {
+    /// Calls nested deeper than this are not expanded, which stops recursive definitions
+    pub max_depth: usize,
+    /// Calls following this number of substitutions are not expanded
+    pub max_steps: usize,
+    steps: Vec<ExpansionStep>,
+    truncated: bool,
+}
+
+impl ExpansionTrace {
+    /// An empty trace with a maximal depth of 16 and at most 1000 steps.
+    pub fn new() -> ExpansionTrace {
+        ExpansionTrace { max_depth: 16, max_steps: 1000, steps: Vec::new(), truncated: false }
+    }
+
+    /// The recorded substitutions.
+    pub fn steps(&self) -> &[ExpansionStep] {
+        &self.steps
+    }
+
+    /// Returns `true` if a call was left unexpanded because of [`max_depth`](Self::max_depth)
+    /// or [`max_steps`](Self::max_steps).
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl Default for ExpansionTrace {
+    fn default() -> Self {
+        ExpansionTrace::new()
+    }
+}
+
+impl LexedStr<'_> {
+    /// Source text of the token range `range` with every call of a command defined in `store`
+    /// expanded, recording each substitution in `trace`.
+    ///
+    /// Unlike [`text_with_expansion`](Self::text_with_expansion) the calls in the output of an
+    /// expansion are expanded as well, up to the limits of the trace. The arguments of a call
+    /// starting in `range` may extend beyond it.
+    pub fn text_with_traced_expansion(
+        &self,
+        range: Range<usize>,
+        store: &ExpansionStore<'_>,
+        trace: &mut ExpansionTrace,
+    ) -> String {
+        let definitions: Vec<_> = store.all_commands().map(|item| item.range.clone()).collect();
+        expand(self, range, store, &definitions, None, 0, trace)
+    }
+}
+
+fn expand(
+    lexed: &LexedStr<'_>,
+    range: Range<usize>,
+    store: &ExpansionStore<'_>,
+    definitions: &[Range<usize>],
+    site: Option<usize>,
+    depth: usize,
+    trace: &mut ExpansionTrace,
+) -> String {
+    let mut text = String::new();
+    let mut idx = range.start;
+
+    while idx < range.end {
+        let item = match lexed.kind(idx) {
+            SyntaxKind::Command if !definitions.iter().any(|def| def.contains(&idx)) => {
+                // calls in the output of an expansion use the definitions in effect at its call
+                store.get_at(lexed.text(idx), site.unwrap_or(idx))
+            }
+            _ => None,
+        };
+        let call = item.and_then(|item| {
+            let (args, starred, end) = collect_at(item, lexed, idx + 1).ok()?;
+            Some((item, item.expand_variant(starred, &args).ok()?, args, end.min(lexed.len())))
+        });
+
+        let Some((item, output, args, end)) = call else {
+            text.push_str(lexed.text(idx));
+            idx += 1;
+            continue;
+        };
+        if depth > trace.max_depth || trace.steps.len() >= trace.max_steps {
+            trace.truncated = true;
+            text.push_str(lexed.text(idx));
+            idx += 1;
+            continue;
+        }
+
+        let range = lexed.text_start(idx)..lexed.text_start(end);
+        let default = item.args.default.filter(|_| args.len() < item.args.count);
+        trace.steps.push(ExpansionStep {
+            name: item.name.to_string(),
+            call: lexed.as_str()[range.clone()].to_string(),
+            range,
+            arguments: default.iter().chain(&args).map(|arg| arg.to_string()).collect(),
+            output: output.clone(),
+            depth,
+        });
+
+        // the output is new text, none of its tokens belong to a definition of the store
+        let nested = LexedStr::with_profile(&output, lexed.profile());
+        let site = Some(site.unwrap_or(idx));
+        text.push_str(&expand(&nested, 0..nested.len(), store, &[], site, depth + 1, trace));
+        idx = end;
+    }
+
+    text
+}
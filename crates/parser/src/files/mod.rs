@@ -0,0 +1,228 @@
+//! References between the files of a project.
+//!
+//! [`file_references`] lists every `\input`, `\include` and `\subfile` of a document and
+//! [`main_file`] the main document of a subfile.
+//! [`rename_file`] computes the edits which keep those references intact when a file or
+//! directory is moved, e.g. for the `workspace/willRenameFiles` request of an editor.
+
+use std::{
+    ops::Range,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{expansion::TextEdit, LexedStr, SyntaxKind};
+
+/// Commands whose only argument is the path of another source file.
+pub(crate) const FILE_COMMANDS: &[&str] = &["\\input", "\\include", "\\subfile"];
+
+/// Extensions of pictures exported as TeX code, e.g. by matplotlib or Inkscape.
+pub const GRAPHIC_EXTENSIONS: &[&str] = &["pdf_tex", "pgf", "pstex_t", "tikz"];
+
+/// Classes of documents which compile on their own but are usually included into a main document,
+/// e.g. a chapter with `\documentclass[../main.tex]{subfiles}`.
+pub const SUBFILE_CLASSES: &[&str] = &["standalone", "subfiles"];
+
+/// A reference to another file, e.g. `\input{chapters/intro}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileReference<'source> {
+    /// The referencing command including the backslash
+    pub command: &'source str,
+    /// The path as written in the source, without surrounding whitespace
+    pub path: &'source str,
+    /// Byte range of `path` in the source text
+    pub range: Range<usize>,
+}
+
+impl FileReference<'_> {
+    /// Whether the referenced file is a picture exported as TeX code, see [`GRAPHIC_EXTENSIONS`].
+    pub fn is_graphic(&self) -> bool {
+        is_graphic(self.path)
+    }
+
+    /// Resolves the path against `dir`, the directory of the referencing document. A missing
+    /// extension defaults to `.tex`.
+    pub fn resolve(&self, dir: &Path) -> PathBuf {
+        let mut path = normalize(&dir.join(self.path));
+        if path.extension().is_none() {
+            path.set_extension("tex");
+        }
+        path
+    }
+}
+
+/// An edit of the document at `path`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEdit {
+    /// Path of the edited document before the rename
+    pub path: PathBuf,
+    /// The edit itself
+    pub edit: TextEdit,
+}
+
+/// Collects all file references of `lexed`.
+pub fn file_references<'source>(lexed: &LexedStr<'source>) -> Vec<FileReference<'source>> {
+    let mut references = Vec::new();
+
+    for idx in 0..lexed.len() {
+        let command = lexed.text(idx);
+        if !matches!(lexed.kind(idx), SyntaxKind::FileInput | SyntaxKind::Command)
+            || !FILE_COMMANDS.contains(&command)
+        {
+            continue;
+        }
+
+        let Some(open) = lexed.next_non_trivia(idx + 1) else {
+            continue;
+        };
+        if lexed.kind(open) != SyntaxKind::OpenBrace {
+            continue;
+        }
+        let Some(close) =
+            (open..lexed.len()).find(|&next| lexed.kind(next) == SyntaxKind::CloseBrace)
+        else {
+            continue;
+        };
+
+        let start = lexed.text_start(open + 1);
+        let text = &lexed.as_str()[start..lexed.text_start(close)];
+        let path = text.trim();
+        let start = start + (text.len() - text.trim_start().len());
+
+        references.push(FileReference { command, path, range: start..start + path.len() });
+    }
+
+    references
+}
+
+/// The class of the `\documentclass` of `lexed`, e.g. `article`.
+pub fn document_class<'source>(lexed: &LexedStr<'source>) -> Option<&'source str> {
+    class_arguments(lexed).map(|(_, class)| class)
+}
+
+/// The main document of a subfile, named by the option of `\documentclass[../main.tex]{subfiles}`.
+/// The subfile is compiled with the preamble of the main document. The command of the reference
+/// is `\documentclass`.
+///
+/// ```
+/// use parser::{files::main_file, LexedStr};
+///
+/// let lexed = LexedStr::new("\\documentclass[ ../main.tex ]{subfiles}");
+/// assert_eq!(main_file(&lexed).unwrap().path, "../main.tex");
+/// assert!(main_file(&LexedStr::new("\\documentclass[a4paper]{article}")).is_none());
+/// ```
+pub fn main_file<'source>(lexed: &LexedStr<'source>) -> Option<FileReference<'source>> {
+    let (Some(options), "subfiles") = class_arguments(lexed)? else {
+        return None;
+    };
+    let text = &lexed.as_str()[options.clone()];
+    let path = text.trim();
+    let start = options.start + (text.len() - text.trim_start().len());
+    (!path.is_empty()).then(|| FileReference {
+        command: "\\documentclass",
+        path,
+        range: start..start + path.len(),
+    })
+}
+
+/// Byte range of the options and the class of the first `\documentclass[options]{class}`.
+fn class_arguments<'source>(
+    lexed: &LexedStr<'source>,
+) -> Option<(Option<Range<usize>>, &'source str)> {
+    let next = |from: usize| lexed.next_non_trivia(from);
+
+    let idx = (0..lexed.len()).find(|&idx| lexed.kind(idx) == SyntaxKind::DocClass)?;
+    let mut open = next(idx + 1)?;
+    let mut options = None;
+    if lexed.kind(open) == SyntaxKind::OpenBracket {
+        let close = (open..lexed.len()).find(|&idx| lexed.kind(idx) == SyntaxKind::CloseBracket)?;
+        options = Some(lexed.text_start(open + 1)..lexed.text_start(close));
+        open = next(close + 1)?;
+    }
+    if lexed.kind(open) != SyntaxKind::OpenBrace {
+        return None;
+    }
+    let close = (open..lexed.len()).find(|&idx| lexed.kind(idx) == SyntaxKind::CloseBrace)?;
+    Some((options, lexed.as_str()[lexed.text_start(open + 1)..lexed.text_start(close)].trim()))
+}
+
+/// Computes the edits required to rename the file or directory `old` to `new`.
+///
+/// `documents` are the paths and tokens of all documents of the project. References are
+/// resolved relative to the directory of the referencing document, references of a moved
+/// document are updated as well, including the [`main_file`] of a subfile. Edits refer to the documents by their path before the rename.
+pub fn rename_file<'a, 'source: 'a>(
+    documents: impl IntoIterator<Item = (&'a Path, &'a LexedStr<'source>)>,
+    old: &Path,
+    new: &Path,
+) -> Vec<FileEdit> {
+    let (old, new) = (normalize(old), normalize(new));
+    let renamed = |path: &Path| match path.strip_prefix(&old) {
+        Ok(rest) if rest.as_os_str().is_empty() => new.clone(),
+        Ok(rest) => new.join(rest),
+        Err(_) => path.to_path_buf(),
+    };
+
+    let mut edits = Vec::new();
+    for (document, lexed) in documents {
+        let document = normalize(document);
+        let dir = document.parent().unwrap_or(Path::new(""));
+        let moved = renamed(&document);
+        let new_dir = moved.parent().unwrap_or(Path::new(""));
+
+        for reference in file_references(lexed).into_iter().chain(main_file(lexed)) {
+            let target = reference.resolve(dir);
+            let new_target = renamed(&target);
+            if new_target == target && new_dir == dir {
+                continue;
+            }
+
+            let mut path = relative(new_dir, &new_target);
+            if Path::new(reference.path).extension().is_none() {
+                path = path.strip_suffix(".tex").map(str::to_string).unwrap_or(path);
+            }
+            if path != reference.path {
+                edits.push(FileEdit {
+                    path: document.clone(),
+                    edit: TextEdit { range: reference.range, new_text: path },
+                });
+            }
+        }
+    }
+
+    edits
+}
+
+pub(crate) fn is_graphic(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| GRAPHIC_EXTENSIONS.iter().any(|graphic| ext == *graphic))
+}
+
+/// Resolves `.` and `..` components without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Path of `to` relative to the directory `from`, always separated by `/` like in TeX sources.
+fn relative(from: &Path, to: &Path) -> String {
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let parents = std::iter::repeat("..").take(from.len() - common);
+    let rest = to[common..].iter().map(|component| component.as_os_str().to_str().unwrap_or(""));
+    parents.chain(rest).collect::<Vec<_>>().join("/")
+}
+
+#[cfg(test)]
+mod tests;
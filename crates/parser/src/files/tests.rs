@@ -0,0 +1,77 @@
+use std::path::Path;
+
+use super::{document_class, file_references, main_file, rename_file, FileEdit};
+use crate::{expansion::TextEdit, LexedStr};
+
+#[test]
+fn references() {
+    let lexed = LexedStr::new(
+        "\\input{ intro }\n\\include {chapters/one.tex}\\subfile{a}\\import{x}{y}\\input",
+    );
+    let references: Vec<_> = file_references(&lexed)
+        .into_iter()
+        .map(|reference| (reference.command, reference.path))
+        .collect();
+
+    assert_eq!(
+        references,
+        [("\\input", "intro"), ("\\include", "chapters/one.tex"), ("\\subfile", "a")]
+    );
+    assert_eq!(&lexed.as_str()[file_references(&lexed)[0].range.clone()], "intro");
+}
+
+#[test]
+fn rename_referenced_file() {
+    let main = LexedStr::new("\\input{chapters/intro}\\input{chapters/other}");
+    let chapter = LexedStr::new("\\include{../appendix.tex}");
+    let documents = [(Path::new("main.tex"), &main), (Path::new("chapters/one.tex"), &chapter)];
+
+    let edits =
+        rename_file(documents, Path::new("chapters/intro.tex"), Path::new("parts/start.tex"));
+    assert_eq!(
+        edits,
+        [FileEdit {
+            path: "main.tex".into(),
+            edit: TextEdit { range: 7..21, new_text: "parts/start".to_string() }
+        }]
+    );
+
+    // the moved document updates its own relative references
+    let edits = rename_file(documents, Path::new("chapters/one.tex"), Path::new("one.tex"));
+    assert_eq!(
+        edits,
+        [FileEdit {
+            path: "chapters/one.tex".into(),
+            edit: TextEdit { range: 9..24, new_text: "appendix.tex".to_string() }
+        }]
+    );
+}
+
+#[test]
+fn rename_directory() {
+    let main = LexedStr::new("\\input{./chapters/intro}\\input{appendix}");
+    let documents = [(Path::new("book/main.tex"), &main)];
+
+    let edits: Vec<_> = rename_file(documents, Path::new("book/chapters"), Path::new("book/parts"))
+        .into_iter()
+        .map(|edit| edit.edit.new_text)
+        .collect();
+    assert_eq!(edits, ["parts/intro"]);
+}
+
+#[test]
+fn subfiles() {
+    let lexed = LexedStr::new("% chapter\n\\documentclass [../main] {subfiles}\n\\input{a}");
+    assert_eq!(document_class(&lexed), Some("subfiles"));
+    let main = main_file(&lexed).unwrap();
+    assert_eq!((main.command, &lexed.as_str()[main.range]), ("\\documentclass", "../main"));
+    assert_eq!(document_class(&LexedStr::new("\\documentclass")), None);
+
+    // renaming the main document updates the option naming it
+    let documents = [(Path::new("chapters/one.tex"), &lexed)];
+    let edits: Vec<_> = rename_file(documents, Path::new("main.tex"), Path::new("book.tex"))
+        .into_iter()
+        .map(|edit| edit.edit.new_text)
+        .collect();
+    assert_eq!(edits, ["../book"]);
+}
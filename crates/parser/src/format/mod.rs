@@ -0,0 +1,633 @@
+//! Formatter for LaTeX and NeoTeX source.
+//!
+//! [`format`] reprints the lossless token stream of a [`LexedStr`]. Lines are indented by the
+//! environments and brace groups they are nested in, binary operators in math get a single space
+//! on both sides and long lines are wrapped at whitespace outside of math atoms like `x^{n + 1}`.
+//! Only whitespace is changed, so the typeset output stays the same. The content of verbatim
+//! environments, `\verb` and comments is kept byte for byte.
+//!
+//! An [`EnvironmentRule`] changes the layout of the content of a single environment, e.g. to line
+//! up the columns of an `align` or to indent the lines continuing an `\item`.
+//!
+//! [`organize_preamble`] orders the preamble of a document, which unlike [`format`] moves code.
+
+mod preamble;
+
+use std::{collections::BTreeMap, ops::Range};
+
+use crate::{
+    ast::{content_model, AstNode, Document, Environment, NodeKind},
+    expansion::VERBATIM_ENVIRONMENTS,
+    LexedStr, SyntaxKind, Trivia,
+};
+
+pub use preamble::{organize_preamble, PackageCategory, PreambleError, PreambleOptions};
+
+/// One level of indentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    /// The given number of spaces
+    Spaces(usize),
+    /// A single tab
+    Tab,
+}
+
+/// Options of the formatter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// One level of indentation of environments and multi-line brace groups
+    pub indent: Indent,
+    /// Column after which lines are wrapped at whitespace, `None` keeps long lines
+    pub line_width: Option<usize>,
+    /// Whether the content of the `document` environment is indented
+    pub indent_document: bool,
+    /// Whether binary operators in math get a single space on both sides, e.g. `a + b = c`
+    pub math_spacing: bool,
+    /// Rules of environments by name, see [`FormatOptions::environment`] for the rules of
+    /// environments which are not listed
+    pub environments: BTreeMap<String, EnvironmentRule>,
+}
+
+impl FormatOptions {
+    /// The rule of the environment `name`. Without a configured rule the content of verbatim
+    /// environments is kept as is and the content of all others is indented, except for the
+    /// `document` unless [`indent_document`](Self::indent_document) is set.
+    pub fn environment(&self, name: &str) -> EnvironmentRule {
+        self.environments.get(name).copied().unwrap_or(EnvironmentRule {
+            indent: name != "document" || self.indent_document,
+            verbatim: VERBATIM_ENVIRONMENTS.contains(&name),
+            ..EnvironmentRule::default()
+        })
+    }
+}
+
+/// How the content of an environment is formatted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvironmentRule {
+    /// Whether the content is indented by one level
+    pub indent: bool,
+    /// Whether the content is kept byte for byte, like the content of `verbatim`
+    pub verbatim: bool,
+    /// Whether the `&` of all rows line up, e.g. in `align` or `tabular`
+    pub align: bool,
+    /// Whether lines continuing an `\item` are indented one level deeper than the `\item`
+    pub hanging_items: bool,
+}
+
+impl Default for EnvironmentRule {
+    fn default() -> Self {
+        EnvironmentRule { indent: true, verbatim: false, align: false, hanging_items: false }
+    }
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            indent: Indent::Spaces(2),
+            line_width: None,
+            indent_document: false,
+            math_spacing: true,
+            environments: BTreeMap::new(),
+        }
+    }
+}
+
+/// Commands whose arguments are text even inside of math, e.g. `\text{well-known}`.
+const TEXT_ARGUMENT_COMMANDS: &[&str] = &[
+    "\\text",
+    "\\textrm",
+    "\\textit",
+    "\\textbf",
+    "\\textsf",
+    "\\texttt",
+    "\\textup",
+    "\\textnormal",
+    "\\emph",
+    "\\mbox",
+    "\\hbox",
+    "\\intertext",
+    "\\label",
+    "\\ref",
+    "\\eqref",
+    "\\tag",
+    "\\cite",
+    "\\url",
+    "\\href",
+];
+
+/// Formats the source text of `lexed`.
+pub fn format(lexed: &LexedStr<'_>, options: &FormatOptions) -> String {
+    let document = Document::new(lexed);
+    let layout = Layout::new(&document, options);
+    let mut printer = Printer::new(lexed.as_str().len(), options);
+
+    // byte ranges of the output whose columns are aligned once everything is printed
+    let mut aligned = Vec::new();
+    let mut idx = 0;
+    while idx < lexed.len() {
+        for range in &layout.aligned {
+            if range.start == idx {
+                aligned.push(printer.out.len()..usize::MAX);
+            } else if range.end == idx {
+                if let Some(region) =
+                    aligned.iter_mut().rev().find(|region| region.end == usize::MAX)
+                {
+                    region.end = printer.out.len();
+                }
+            }
+        }
+        printer.atom = layout.atom[idx];
+
+        if let Some(end) = layout.raw_end(idx) {
+            let text = &lexed.as_str()[lexed.text_start(idx)..lexed.text_start(end)];
+            printer.raw(text, layout.depth[idx]);
+            idx = end;
+            continue;
+        }
+
+        let text = lexed.text(idx);
+        let depth = layout.depth[idx];
+        match lexed.kind(idx) {
+            SyntaxKind::Newline => printer.newlines(1),
+            SyntaxKind::Break => printer.newlines(text.matches('\n').count().max(2)),
+            SyntaxKind::Whitespace => printer.space(text),
+            _ if options.math_spacing && layout.is_binary_operator(lexed, idx) => {
+                printer.operator(text, depth)
+            }
+            kind => {
+                let comment = matches!(kind, SyntaxKind::Comment | SyntaxKind::AComment);
+                printer.token(text, depth, comment);
+            }
+        }
+        idx += 1;
+    }
+
+    let mut out = printer.out;
+    for mut region in aligned.into_iter().rev().filter(|region| region.end != usize::MAX) {
+        // a row on the line of the `\begin` has no indentation to align from
+        if !out[..region.start].ends_with('\n') {
+            let first_line = out[region.clone()].find('\n');
+            region.start = first_line.map_or(region.end, |newline| region.start + newline + 1);
+        }
+        let columns = align_columns(&out[region.clone()]);
+        out.replace_range(region, &columns);
+    }
+    out
+}
+
+/// What the formatter needs to know about every token.
+struct Layout {
+    /// Indentation level of a line starting with the token
+    depth: Vec<usize>,
+    /// Whether the token is typeset in math mode
+    math: Vec<bool>,
+    /// Token ranges which are printed unchanged, ordered by start
+    raw: Vec<Range<usize>>,
+    /// Whether a line must not be wrapped at the token, because it is part of a math atom like
+    /// `\frac{a + b}{2}` or `x ^ 2` or of a row whose columns are aligned
+    atom: Vec<bool>,
+    /// Content token ranges of environments whose columns are aligned
+    aligned: Vec<Range<usize>>,
+}
+
+impl Layout {
+    fn new(document: &Document<'_, '_>, options: &FormatOptions) -> Layout {
+        let lexed = document.lexed();
+        let len = lexed.len();
+        let mut delta = vec![0isize; len + 1];
+        let mut math = vec![false; len];
+        let mut raw = verb_ranges(lexed);
+        let (mut aligned, mut hanging) = (Vec::new(), Vec::new());
+
+        for node in document.descendants() {
+            let content = node.content_token_range();
+            match node.kind() {
+                NodeKind::MathBlock => math[content].fill(true),
+                NodeKind::Environment => {
+                    let name = Environment::cast(node).unwrap().name();
+                    let rule = options.environment(name);
+                    if rule.verbatim {
+                        raw.push(content);
+                        continue;
+                    }
+                    if content_model(name).is_some_and(|model| model.math) {
+                        math[content.clone()].fill(true);
+                    }
+                    if rule.indent {
+                        delta[content.start] += 1;
+                        delta[content.end] -= 1;
+                    }
+                    if rule.align {
+                        aligned.push(content.clone());
+                    }
+                    if rule.hanging_items {
+                        hanging.push(content);
+                    }
+                }
+                _ => {}
+            }
+        }
+        raw.sort_by_key(|range| range.start);
+
+        // a closing brace belongs to the outer level, so that it lines up with its opening line
+        let mut text_groups = Vec::new();
+        let mut math_groups = Vec::new();
+        let mut atom = vec![false; len];
+        for idx in 0..len {
+            if raw.iter().any(|range| range.contains(&idx)) {
+                continue;
+            }
+            match lexed.kind(idx) {
+                SyntaxKind::OpenBrace => {
+                    delta[idx + 1] += 1;
+                    text_groups.push(math[idx] && is_text_argument(lexed, idx));
+                    math_groups.push(math[idx]);
+                }
+                SyntaxKind::CloseBrace => {
+                    delta[idx] -= 1;
+                    text_groups.pop();
+                    math_groups.pop();
+                }
+                _ => {}
+            }
+            if text_groups.iter().any(|&text| text) {
+                math[idx] = false;
+            }
+            atom[idx] = math_groups.iter().any(|&math| math);
+        }
+        // sub- and superscripts are bound to their base and their argument
+        let scripts = (0..len).filter(|&idx| {
+            math[idx] && matches!(lexed.kind(idx), SyntaxKind::Carret | SyntaxKind::Underscore)
+        });
+        let space = |idx: &usize| lexed.kind(*idx) == SyntaxKind::Whitespace;
+        for idx in scripts {
+            let before = (0..idx).rev().take_while(space);
+            let after = (idx + 1..len).take_while(space);
+            for idx in before.chain(after) {
+                atom[idx] = true;
+            }
+        }
+
+        // the padding of aligned columns would change where a wrapped row breaks
+        for range in &aligned {
+            atom[range.clone()].fill(true);
+        }
+
+        let mut level = 0isize;
+        let mut depth: Vec<usize> = delta[..len]
+            .iter()
+            .map(|delta| {
+                level += delta;
+                level.max(0) as usize
+            })
+            .collect();
+
+        // everything following an `\item` up to the next one is indented one level deeper
+        for content in hanging {
+            let Some(&base) = depth.get(content.start) else {
+                continue;
+            };
+            let mut in_item = false;
+            for idx in content {
+                let kind = lexed.kind(idx);
+                if depth[idx] == base && kind == SyntaxKind::Command && lexed.text(idx) == "\\item"
+                {
+                    in_item = true;
+                    continue;
+                }
+                if in_item {
+                    depth[idx] += 1;
+                }
+            }
+        }
+
+        Layout { depth, math, raw, atom, aligned }
+    }
+
+    /// The end of the raw range starting at the token `idx`.
+    fn raw_end(&self, idx: usize) -> Option<usize> {
+        self.raw.iter().find(|range| range.start == idx && !range.is_empty()).map(|range| range.end)
+    }
+
+    /// Whether the token `idx` is an operator in math with an operand on both sides, e.g. the
+    /// `-` in `a - b` but not in `-b` or `x^{-1}`.
+    fn is_binary_operator(&self, lexed: &LexedStr<'_>, idx: usize) -> bool {
+        if !self.math[idx] || !is_operator(lexed.kind(idx)) {
+            return false;
+        }
+        let is_math_space = |idx: usize| lexed.kind(idx).is_trivia(Trivia::PARSE);
+        let Some(prev) = (0..idx).rev().find(|&prev| !is_math_space(prev)) else {
+            return false;
+        };
+        let Some(next) = (idx + 1..lexed.len()).find(|&next| !is_math_space(next)) else {
+            return false;
+        };
+
+        let operand_before = match lexed.kind(prev) {
+            kind if is_operator(kind) => false,
+            SyntaxKind::OpenBrace
+            | SyntaxKind::OpenParen
+            | SyntaxKind::OpenBracket
+            | SyntaxKind::Carret
+            | SyntaxKind::Underscore
+            | SyntaxKind::Ampersand
+            | SyntaxKind::Comma => false,
+            SyntaxKind::Command => !matches!(lexed.text(prev), "\\\\" | "\\(" | "\\["),
+            _ => true,
+        };
+        let operand_after =
+            !matches!(lexed.kind(next), SyntaxKind::CloseBrace | SyntaxKind::CloseBracket);
+        operand_before && operand_after && self.math[prev] && self.math[next]
+    }
+}
+
+fn is_operator(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::Plus
+            | SyntaxKind::Minus
+            | SyntaxKind::Equal
+            | SyntaxKind::Less
+            | SyntaxKind::Greater
+            | SyntaxKind::LessEq
+            | SyntaxKind::GreaterEq
+            | SyntaxKind::NotEq
+    )
+}
+
+/// Whether the brace `open` starts a text argument or a sub- or superscript, which keep their
+/// spacing.
+fn is_text_argument(lexed: &LexedStr<'_>, open: usize) -> bool {
+    let mut prev = (0..open).rev().filter(|&prev| !lexed.kind(prev).is_resolver_trivia());
+    match prev.next().map(|prev| (lexed.kind(prev), prev)) {
+        Some((SyntaxKind::Carret | SyntaxKind::Underscore, _)) => true,
+        Some((SyntaxKind::Command, prev)) => TEXT_ARGUMENT_COMMANDS.contains(&lexed.text(prev)),
+        Some((SyntaxKind::Star, _)) => {
+            prev.next().is_some_and(|prev| TEXT_ARGUMENT_COMMANDS.contains(&lexed.text(prev)))
+        }
+        _ => false,
+    }
+}
+
+/// Lines up the `&` of the rows in `text`, the printed content of an environment.
+///
+/// Only rows on the indentation level of the environment content are aligned, those of nested
+/// environments keep their layout. Every cell is trimmed and padded to the width of its column.
+fn align_columns(text: &str) -> String {
+    let indent = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let base = text.lines().filter(|line| !line.trim().is_empty()).map(indent).min();
+
+    let rows: Vec<(&str, Option<Vec<&str>>)> = text
+        .split_inclusive('\n')
+        .map(|line| {
+            let content = line.strip_suffix('\n').unwrap_or(line);
+            let cells = (Some(indent(content)) == base)
+                .then(|| cells(&content[indent(content)..]))
+                .filter(|cells| cells.len() > 1);
+            (line, cells)
+        })
+        .collect();
+
+    let mut widths = Vec::new();
+    for cells in rows.iter().filter_map(|(_, cells)| cells.as_ref()) {
+        for (column, cell) in cells[..cells.len() - 1].iter().enumerate() {
+            let width = cell.chars().count();
+            match widths.get_mut(column) {
+                Some(max) => *max = width.max(*max),
+                None => widths.push(width),
+            }
+        }
+    }
+
+    let mut out = String::with_capacity(text.len());
+    for (line, cells) in rows {
+        let Some(cells) = cells else {
+            out.push_str(line);
+            continue;
+        };
+        let mut row = line[..indent(line)].to_string();
+        for (column, cell) in cells[..cells.len() - 1].iter().enumerate() {
+            let padding = widths[column] - cell.chars().count();
+            row.push_str(cell);
+            row.extend(std::iter::repeat(' ').take(padding));
+            row.push_str(" & ");
+        }
+        row.push_str(cells[cells.len() - 1]);
+        out.push_str(row.trim_end_matches([' ', '\t']));
+        if line.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Splits a row at every `&` outside of braces, up to a comment. The cells are trimmed, the last
+/// one keeps the comment.
+fn cells(row: &str) -> Vec<&str> {
+    let mut cells = Vec::new();
+    let (mut start, mut depth) = (0, 0usize);
+    let mut chars = row.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            '%' => break,
+            '&' if depth == 0 => {
+                cells.push(row[start..idx].trim());
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    cells.push(row[start..].trim());
+    cells
+}
+
+/// Token ranges of `\verb|...|` including the command.
+fn verb_ranges(lexed: &LexedStr<'_>) -> Vec<Range<usize>> {
+    let text = lexed.as_str();
+    let mut ranges = Vec::new();
+
+    let mut idx = 0;
+    while idx < lexed.len() {
+        idx += 1;
+        if lexed.kind(idx - 1) != SyntaxKind::Command || lexed.text(idx - 1) != "\\verb" {
+            continue;
+        }
+        let mut start = lexed.text_range(idx - 1).end;
+        if text[start..].starts_with('*') {
+            start += 1;
+        }
+        let Some(delimiter) = text[start..].chars().next().filter(|c| !c.is_whitespace()) else {
+            continue;
+        };
+        let argument = start + delimiter.len_utf8();
+        let Some(end) = text[argument..]
+            .find([delimiter, '\n'])
+            .filter(|&len| text[argument + len..].starts_with(delimiter))
+        else {
+            continue;
+        };
+
+        let end = argument + end + delimiter.len_utf8();
+        let end_token = (idx..lexed.len()).find(|&next| lexed.text_start(next) >= end);
+        let end_token = end_token.unwrap_or(lexed.len());
+        ranges.push(idx - 1..end_token);
+        idx = end_token;
+    }
+
+    ranges
+}
+
+struct Printer<'options> {
+    options: &'options FormatOptions,
+    out: String,
+    /// Start of the current line in `out`
+    line_start: usize,
+    /// Whether the current token belongs to a math atom, whose whitespace is no line break
+    atom: bool,
+    /// Whether nothing but the indentation was printed on the current line
+    at_line_start: bool,
+    /// Whitespace of the current line at which it may be wrapped, as ranges of `out`, with the
+    /// indentation level of the token following it once that is printed. A wrapped line is
+    /// indented like a line starting with that token, so formatting the output again keeps it.
+    breaks: Vec<(Range<usize>, Option<usize>)>,
+    /// A single space has to follow, e.g. after a binary operator
+    space_after: bool,
+    /// Trailing whitespace is not removed in front of this position, e.g. to keep comments
+    protected: usize,
+}
+
+impl<'options> Printer<'options> {
+    fn new(capacity: usize, options: &'options FormatOptions) -> Self {
+        Printer {
+            options,
+            out: String::with_capacity(capacity),
+            line_start: 0,
+            atom: false,
+            at_line_start: true,
+            breaks: Vec::new(),
+            space_after: false,
+            protected: 0,
+        }
+    }
+
+    fn token(&mut self, text: &str, depth: usize, protect: bool) {
+        self.start_line(depth);
+        if std::mem::take(&mut self.space_after) {
+            self.space(" ");
+        }
+        self.settle_breaks(depth);
+        self.out.push_str(text);
+        if protect {
+            self.protected = self.out.len();
+        }
+        if let Some(newline) = text.rfind('\n') {
+            self.line_start = self.out.len() - text.len() + newline + 1;
+            self.breaks.clear();
+        }
+        self.wrap();
+    }
+
+    fn operator(&mut self, text: &str, depth: usize) {
+        if self.at_line_start {
+            self.start_line(depth);
+        } else {
+            self.trim_trailing_whitespace();
+            self.space(" ");
+        }
+        self.settle_breaks(depth);
+        self.out.push_str(text);
+        self.space_after = true;
+        self.wrap();
+    }
+
+    fn space(&mut self, text: &str) {
+        if self.at_line_start || self.space_after {
+            return;
+        }
+        if !self.atom {
+            self.breaks.push((self.out.len()..self.out.len() + text.len(), None));
+        }
+        self.out.push_str(text);
+    }
+
+    fn newlines(&mut self, count: usize) {
+        self.trim_trailing_whitespace();
+        for _ in 0..count {
+            self.out.push('\n');
+        }
+        self.line_start = self.out.len();
+        self.at_line_start = true;
+        self.space_after = false;
+        self.breaks.clear();
+    }
+
+    /// Prints `text` unchanged.
+    fn raw(&mut self, text: &str, depth: usize) {
+        self.start_line(depth);
+        if std::mem::take(&mut self.space_after) {
+            self.space(" ");
+        }
+        self.out.push_str(text);
+        if let Some(newline) = text.rfind('\n') {
+            self.line_start = self.out.len() - text.len() + newline + 1;
+        }
+        self.breaks.clear();
+        self.protected = self.out.len();
+    }
+
+    fn start_line(&mut self, depth: usize) {
+        if !self.at_line_start {
+            return;
+        }
+        let indent = self.indentation(depth);
+        self.out.push_str(&indent);
+        self.at_line_start = false;
+    }
+
+    /// Sets the indentation level of the breaks in front of a token on the level `depth`.
+    fn settle_breaks(&mut self, depth: usize) {
+        for (_, level) in self.breaks.iter_mut().rev().take_while(|(_, level)| level.is_none()) {
+            *level = Some(depth);
+        }
+    }
+
+    fn indentation(&self, depth: usize) -> String {
+        match self.options.indent {
+            Indent::Spaces(width) => " ".repeat(width * depth),
+            Indent::Tab => "\t".repeat(depth),
+        }
+    }
+
+    fn trim_trailing_whitespace(&mut self) {
+        let min = self.protected.max(self.line_start);
+        let trimmed = self.out.trim_end_matches([' ', '\t']).len().max(min);
+        self.out.truncate(trimmed);
+        self.breaks.retain(|(range, _)| range.end <= trimmed);
+    }
+
+    /// Replaces the last whitespace of the current line by a line break if it is too long.
+    fn wrap(&mut self) {
+        let Some(width) = self.options.line_width else {
+            return;
+        };
+        if self.out[self.line_start..].chars().count() <= width {
+            return;
+        }
+        let Some((at, depth)) = self.breaks.pop() else {
+            return;
+        };
+
+        let newline = format!("\n{}", self.indentation(depth.unwrap_or(0)));
+        self.out.replace_range(at.clone(), &newline);
+        self.line_start = at.start + 1;
+        self.breaks.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests;
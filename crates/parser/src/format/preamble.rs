@@ -0,0 +1,265 @@
+use std::ops::Range;
+
+use thiserror::Error;
+
+use crate::{
+    ast::{AstNode, Document, Environment},
+    LexedStr, SyntaxKind, Trivia,
+};
+
+/// Commands which define something without being a definition keyword like `\newcommand`.
+const DEFINITION_COMMANDS: &[&str] =
+    &["\\DeclareMathOperator", "\\DeclareRobustCommand", "\\DeclarePairedDelimiter", "\\let"];
+
+/// A group of packages in an organized preamble.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PackageCategory {
+    /// Input and font encodings and fonts, e.g. `fontenc` or `lmodern`
+    Fonts,
+    /// Languages and quotes, e.g. `babel`
+    Language,
+    /// Math and units, e.g. `amsmath` or `siunitx`
+    Math,
+    /// Graphics, colors, floats and captions, e.g. `graphicx`
+    Graphics,
+    /// Tables, e.g. `booktabs`
+    Tables,
+    /// Page layout, headers and lists, e.g. `geometry`
+    Layout,
+    /// Every package not listed in another category
+    Other,
+    /// Bibliographies, e.g. `biblatex`
+    Bibliography,
+    /// Links and cross references, e.g. `hyperref`, which have to be loaded late
+    References,
+}
+
+impl PackageCategory {
+    /// All categories in the default order.
+    pub const ALL: [PackageCategory; 9] = [
+        PackageCategory::Fonts,
+        PackageCategory::Language,
+        PackageCategory::Math,
+        PackageCategory::Graphics,
+        PackageCategory::Tables,
+        PackageCategory::Layout,
+        PackageCategory::Other,
+        PackageCategory::Bibliography,
+        PackageCategory::References,
+    ];
+
+    /// The category of the package `name`.
+    pub fn of(name: &str) -> PackageCategory {
+        match name {
+            "inputenc" | "fontenc" | "fontspec" | "unicode-math" | "lmodern" | "textcomp"
+            | "microtype" | "mathptmx" | "mathpazo" | "newtxtext" | "newtxmath" | "libertine" => {
+                PackageCategory::Fonts
+            }
+            "babel" | "polyglossia" | "csquotes" => PackageCategory::Language,
+            "amsmath" | "amssymb" | "amsfonts" | "amsthm" | "mathtools" | "bm" | "thmtools"
+            | "siunitx" => PackageCategory::Math,
+            "graphicx" | "xcolor" | "color" | "tikz" | "pgfplots" | "float" | "caption"
+            | "subcaption" | "wrapfig" => PackageCategory::Graphics,
+            "booktabs" | "tabularx" | "longtable" | "array" | "multirow" => PackageCategory::Tables,
+            "geometry" | "fancyhdr" | "setspace" | "parskip" | "titlesec" | "enumitem"
+            | "multicol" => PackageCategory::Layout,
+            "biblatex" | "natbib" => PackageCategory::Bibliography,
+            "url" | "hyperref" | "cleveref" | "bookmark" => PackageCategory::References,
+            _ => PackageCategory::Other,
+        }
+    }
+
+    /// Name of the category in the configuration, e.g. `fonts`.
+    pub fn name(self) -> &'static str {
+        match self {
+            PackageCategory::Fonts => "fonts",
+            PackageCategory::Language => "language",
+            PackageCategory::Math => "math",
+            PackageCategory::Graphics => "graphics",
+            PackageCategory::Tables => "tables",
+            PackageCategory::Layout => "layout",
+            PackageCategory::Other => "other",
+            PackageCategory::Bibliography => "bibliography",
+            PackageCategory::References => "references",
+        }
+    }
+
+    /// The category with the given [`name`](Self::name).
+    pub fn from_name(name: &str) -> Option<PackageCategory> {
+        PackageCategory::ALL.into_iter().find(|category| category.name() == name)
+    }
+}
+
+/// Options of [`organize_preamble`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreambleOptions {
+    /// Order of the package groups. Categories which are not listed follow in their default
+    /// order.
+    pub order: Vec<PackageCategory>,
+}
+
+impl Default for PreambleOptions {
+    fn default() -> Self {
+        PreambleOptions { order: PackageCategory::ALL.to_vec() }
+    }
+}
+
+/// Error returned when a preamble cannot be reordered without changing its meaning.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PreambleError {
+    /// The preamble contains a TeX conditional, whose branches would be torn apart
+    #[error("the preamble contains a conditional")]
+    Conditional(Range<usize>),
+    /// The preamble contains text, which is typeset and has to stay in place
+    #[error("the preamble contains text")]
+    Text(Range<usize>),
+}
+
+/// The kind of an item of the preamble, in the order of an organized preamble.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Section {
+    Class,
+    /// Index of the category in the configured order
+    Packages(usize),
+    Settings,
+    Definitions,
+    /// Comments which are not followed by a statement
+    Trailing,
+}
+
+/// Orders the preamble of `lexed`: the `\documentclass` first, then the `\usepackage`s grouped
+/// by [`PackageCategory`], then all other settings and the user definitions last. Groups are
+/// separated by a blank line and items keep their order within a group.
+///
+/// Comments directly above an item move together with it, blocks between `\makeatletter` and
+/// `\makeatother` stay a single setting. Sources without `\begin{document}` are returned
+/// unchanged.
+pub fn organize_preamble(
+    lexed: &LexedStr<'_>,
+    options: &PreambleOptions,
+) -> Result<String, PreambleError> {
+    let text = lexed.as_str();
+    let document = Document::new(lexed);
+    let Some(body) = document
+        .descendants()
+        .filter_map(Environment::cast)
+        .find(|environment| environment.name() == "document")
+    else {
+        return Ok(text.to_string());
+    };
+
+    let items = items(lexed, body.syntax().token_range().start)?;
+    let (Some(first), Some(last)) = (items.first(), items.last()) else {
+        return Ok(text.to_string());
+    };
+    let range = first.range.start..last.range.end;
+
+    let defaults =
+        PackageCategory::ALL.into_iter().filter(|category| !options.order.contains(category));
+    let order: Vec<_> = options.order.iter().copied().chain(defaults).collect();
+    let packages = document.package_names();
+    let section = |item: &Item| match lexed.kind(item.command) {
+        SyntaxKind::DocClass => Section::Class,
+        SyntaxKind::UsePackage => {
+            let start = lexed.text_start(item.command);
+            let category = packages
+                .iter()
+                .find(|(_, range)| range.start > start && range.end <= item.range.end)
+                .map_or(PackageCategory::Other, |(name, _)| PackageCategory::of(name));
+            Section::Packages(order.iter().position(|&other| other == category).unwrap())
+        }
+        SyntaxKind::Comment | SyntaxKind::AComment => Section::Trailing,
+        kind if kind.is_definition_keyword() => Section::Definitions,
+        _ if DEFINITION_COMMANDS.contains(&lexed.text(item.command)) => Section::Definitions,
+        _ => Section::Settings,
+    };
+
+    let mut sorted: Vec<_> = items.iter().map(|item| (section(item), item)).collect();
+    sorted.sort_by_key(|(section, _)| *section);
+
+    let mut organized = String::with_capacity(range.len());
+    for (idx, (section, item)) in sorted.iter().enumerate() {
+        if idx > 0 {
+            organized.push_str(if sorted[idx - 1].0 == *section { "\n" } else { "\n\n" });
+        }
+        organized.push_str(&text[item.range.clone()]);
+    }
+
+    Ok(format!("{}{organized}{}", &text[..range.start], &text[range.end..]))
+}
+
+/// A statement of the preamble together with the comments above it.
+struct Item {
+    /// Byte range from the first comment to the end of the last line of the statement
+    range: Range<usize>,
+    /// The first token of the statement
+    command: usize,
+}
+
+/// Splits the tokens before `end` into items. A statement extends to the end of the line on which
+/// all of its groups are closed, or to the next line if that starts with another argument.
+fn items(lexed: &LexedStr<'_>, end: usize) -> Result<Vec<Item>, PreambleError> {
+    let blank = |idx: usize| lexed.kind(idx).is_trivia(Trivia::BLANK);
+    let comment =
+        |idx: usize| matches!(lexed.kind(idx), SyntaxKind::Comment | SyntaxKind::AComment);
+
+    let mut items = Vec::new();
+    let mut idx = 0;
+    loop {
+        while idx < end && blank(idx) {
+            idx += 1;
+        }
+        if idx == end {
+            break;
+        }
+        let start = idx;
+        while idx < end && (blank(idx) || comment(idx)) {
+            idx += 1;
+        }
+        if idx == end {
+            // trailing comments stay at the end of the preamble
+            let end = (start..end).rev().find(|&idx| !blank(idx)).unwrap() + 1;
+            let range = lexed.text_start(start)..lexed.text_start(end);
+            items.push(Item { range, command: start });
+            break;
+        }
+
+        let command = idx;
+        if matches!(lexed.kind(command), SyntaxKind::Word | SyntaxKind::AWord | SyntaxKind::Number)
+        {
+            return Err(PreambleError::Text(lexed.text_range(command)));
+        }
+        let mut depth = 0usize;
+        let mut makeat = false;
+        while idx < end {
+            match lexed.kind(idx) {
+                SyntaxKind::OpenBrace | SyntaxKind::OpenBracket => depth += 1,
+                SyntaxKind::CloseBrace | SyntaxKind::CloseBracket => {
+                    depth = depth.saturating_sub(1)
+                }
+                SyntaxKind::Command => match lexed.text(idx) {
+                    "\\fi" => return Err(PreambleError::Conditional(lexed.text_range(idx))),
+                    "\\makeatletter" => makeat = true,
+                    "\\makeatother" => makeat = false,
+                    _ => {}
+                },
+                SyntaxKind::Newline | SyntaxKind::Break if depth == 0 && !makeat => {
+                    let next = (idx..end).find(|&next| !blank(next));
+                    let continued = next.is_some_and(|next| {
+                        matches!(lexed.kind(next), SyntaxKind::OpenBrace | SyntaxKind::OpenBracket)
+                    });
+                    if !continued {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            idx += 1;
+        }
+
+        let last = (command..idx).rev().find(|&last| !blank(last)).unwrap();
+        let range = lexed.text_start(start)..lexed.text_range(last).end;
+        items.push(Item { range, command });
+    }
+    Ok(items)
+}
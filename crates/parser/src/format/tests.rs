@@ -0,0 +1,300 @@
+use super::{
+    format, organize_preamble, EnvironmentRule, FormatOptions, Indent, PackageCategory,
+    PreambleError, PreambleOptions,
+};
+use crate::{
+    ast::{Document, NodeKind},
+    LexedStr, SyntaxKind, Trivia,
+};
+
+fn fmt(input: &str, options: &FormatOptions) -> String {
+    let formatted = format(&LexedStr::new(input), options);
+    assert_eq!(format(&LexedStr::new(&formatted), options), formatted, "not idempotent");
+    formatted
+}
+
+#[test]
+fn indent_environments_and_groups() {
+    let input =
+        "\\begin{document}\n\\begin{itemize}\n\\item one\n      \\item two\n\\end{itemize}\n\
+                 \\newcommand{\\x}{\n\\textbf{x}\n}\n\\end{document}\n";
+    let expected =
+        "\\begin{document}\n\\begin{itemize}\n  \\item one\n  \\item two\n\\end{itemize}\n\
+                    \\newcommand{\\x}{\n  \\textbf{x}\n}\n\\end{document}\n";
+    assert_eq!(fmt(input, &FormatOptions::default()), expected);
+
+    let options =
+        FormatOptions { indent: Indent::Tab, indent_document: true, ..Default::default() };
+    let expected = "\\begin{document}\n\t\\begin{itemize}\n\t\t\\item one\n\t\t\\item two\n\
+                    \t\\end{itemize}\n\t\\newcommand{\\x}{\n\t\t\\textbf{x}\n\t}\n\\end{document}\n";
+    assert_eq!(fmt(input, &options), expected);
+}
+
+#[test]
+fn trailing_whitespace_and_blank_lines() {
+    let input = "a   \n\n\n\nb\t\n";
+    assert_eq!(fmt(input, &FormatOptions::default()), "a\n\n\n\nb\n");
+}
+
+#[test]
+fn math_spacing() {
+    let options = FormatOptions::default();
+    assert_eq!(fmt("$a+b=c$", &options), "$a + b = c$");
+    assert_eq!(fmt("$a  -b$ and $-b$", &options), "$a - b$ and $-b$");
+    assert_eq!(fmt("$x^{-1}+y_{i+1}$", &options), "$x^{-1} + y_{i+1}$");
+    assert_eq!(fmt("$\\text{well-known}=1$", &options), "$\\text{well-known} = 1$");
+    assert_eq!(fmt("a-b=c", &options), "a-b=c");
+    assert_eq!(
+        fmt("\\begin{align}\nx&=1+2\\\\\n-y&=3\n\\end{align}", &options),
+        "\\begin{align}\n  x&=1 + 2\\\\\n  -y&=3\n\\end{align}"
+    );
+
+    let options = FormatOptions { math_spacing: false, ..Default::default() };
+    assert_eq!(fmt("$a+b=c$", &options), "$a+b=c$");
+}
+
+#[test]
+fn wrap_lines() {
+    let options = FormatOptions { line_width: Some(20), ..Default::default() };
+    let input = "\\begin{quote}\nthe quick brown fox jumps over the lazy dog\n\\end{quote}";
+    assert_eq!(
+        fmt(input, &options),
+        "\\begin{quote}\n  the quick brown\n  fox jumps over the\n  lazy dog\n\\end{quote}"
+    );
+    assert_eq!(
+        fmt("averyveryverylongwordwithoutspaces", &options),
+        "averyveryverylongwordwithoutspaces"
+    );
+}
+
+#[test]
+fn preserve_verbatim_and_comments() {
+    let options = FormatOptions { line_width: Some(10), ..Default::default() };
+    let input = "\\begin{itemize}\n\\item \\verb|a+b   =c|\n% keep   this  comment   as is \n\
+                 \\end{itemize}\n\\begin{verbatim}\n  x+y   \n\t\\end\n\\end{verbatim}\n";
+    let expected =
+        "\\begin{itemize}\n  \\item \\verb|a+b   =c|\n  % keep   this  comment   as is \n\
+                    \\end{itemize}\n\\begin{verbatim}\n  x+y   \n\t\\end\n\\end{verbatim}\n";
+    assert_eq!(fmt(input, &options), expected);
+}
+
+#[test]
+fn environment_rules() {
+    let rule = |name: &str, rule| (name.to_string(), rule);
+    let mut options = FormatOptions::default();
+    options.environments.extend([
+        rule("align", EnvironmentRule { align: true, ..Default::default() }),
+        rule("itemize", EnvironmentRule { hanging_items: true, ..Default::default() }),
+        rule("code", EnvironmentRule { verbatim: true, ..Default::default() }),
+        rule("center", EnvironmentRule { indent: false, ..Default::default() }),
+    ]);
+
+    let input =
+        "\\begin{align}\nx&=1+2\\\\\n-y   &= 3 & \\text{a&b}\\\\ % c&d\n\\begin{cases}\n1&x\\\\\n\
+                 -1&y\n\\end{cases}\n\\end{align}";
+    let expected = "\\begin{align}\n  x  & =1 + 2\\\\\n  -y & = 3 & \\text{a&b}\\\\ % c&d\n  \
+                    \\begin{cases}\n    1&x\\\\\n    -1&y\n  \\end{cases}\n\\end{align}";
+    assert_eq!(fmt(input, &options), expected);
+
+    let input = "\\begin{itemize}\n\\item one\ncontinued\n\\begin{itemize}\n\\item nested\n\
+                 \\end{itemize}\n\\item two\n\\end{itemize}";
+    let expected = "\\begin{itemize}\n  \\item one\n    continued\n    \\begin{itemize}\n      \
+                    \\item nested\n    \\end{itemize}\n  \\item two\n\\end{itemize}";
+    assert_eq!(fmt(input, &options), expected);
+
+    let input = "\\begin{center}\nx\n\\end{center}\n\\begin{code}\n  a+b  \n\\end{code}";
+    assert_eq!(
+        fmt(input, &options),
+        "\\begin{center}\nx\n\\end{center}\n\\begin{code}\n  a+b  \n\\end{code}"
+    );
+    assert!(options.environment("verbatim").verbatim && !options.environment("document").indent);
+}
+
+#[test]
+fn wrap_between_atoms() {
+    let mut options = FormatOptions { line_width: Some(16), ..Default::default() };
+    let input = "$\\frac{a + b}{c + d} + x ^ {n + 1}$ and more words";
+    assert_eq!(fmt(input, &options), "$\\frac{a + b}{c + d}\n+ x ^ {n + 1}$\nand more words");
+
+    options.environments.insert(
+        "itemize".to_string(),
+        EnvironmentRule { hanging_items: true, ..Default::default() },
+    );
+    let input = "\\begin{itemize}\n\\item the quick brown fox jumps\n\\end{itemize}";
+    assert_eq!(
+        fmt(input, &options),
+        "\\begin{itemize}\n  \\item the\n    quick brown\n    fox jumps\n\\end{itemize}"
+    );
+}
+
+fn organize(input: &str, options: &PreambleOptions) -> Result<String, PreambleError> {
+    let organized = organize_preamble(&LexedStr::new(input), options)?;
+    let again = organize_preamble(&LexedStr::new(&organized), options);
+    assert_eq!(again.as_ref(), Ok(&organized), "not idempotent");
+    Ok(organized)
+}
+
+#[test]
+fn organize_preambles() {
+    let input = "% !TEX program = pdflatex\n\\newcommand{\\R}\n  {\\mathbb{R}}\n\
+                 \\usepackage{hyperref}\n\\documentclass[a4paper]{article}\n\n\
+                 % links need colors\n\\usepackage{xcolor} % for \\textcolor\n\
+                 \\title{Notes}\n\\usepackage[utf8]{inputenc}\n\
+                 \\makeatletter\n\\def\\@x{}\n\\makeatother\n\\usepackage{mypackage,amsmath}\n\
+                 % the end\n\n\\begin{document}\n\\R\n\\end{document}\n";
+
+    let organized = organize(input, &PreambleOptions::default()).unwrap();
+    assert_eq!(
+        organized,
+        "\\documentclass[a4paper]{article}\n\n\
+         \\usepackage[utf8]{inputenc}\n\n\
+         % links need colors\n\\usepackage{xcolor} % for \\textcolor\n\n\
+         \\usepackage{mypackage,amsmath}\n\n\
+         \\usepackage{hyperref}\n\n\
+         \\title{Notes}\n\\makeatletter\n\\def\\@x{}\n\\makeatother\n\n\
+         % !TEX program = pdflatex\n\\newcommand{\\R}\n  {\\mathbb{R}}\n\n\
+         % the end\n\n\\begin{document}\n\\R\n\\end{document}\n"
+    );
+
+    let options = PreambleOptions { order: vec![PackageCategory::References] };
+    let organized = organize(input, &options).unwrap();
+    assert!(organized.find("{hyperref}").unwrap() < organized.find("{inputenc}").unwrap());
+
+    let unchanged = "\\usepackage{b}\n\\usepackage{a}";
+    assert_eq!(organize(unchanged, &options).unwrap(), unchanged);
+    let conditional = "\\ifdefined\\x\n\\usepackage{a}\n\\fi\n\\begin{document}\\end{document}";
+    assert_eq!(organize(conditional, &options), Err(PreambleError::Conditional(28..31)));
+    let text = "\\documentclass{article}\nHello\n\\begin{document}\\end{document}";
+    assert_eq!(organize(text, &options), Err(PreambleError::Text(24..29)));
+    assert_eq!(PackageCategory::from_name("math"), Some(PackageCategory::Math));
+}
+
+/// Documents exercising every rule of the formatter, checked by the round-trip tests.
+const CORPUS: &[&str] = &[
+    "\\documentclass{article}\n\\usepackage{amsmath}\n\\newcommand{\\R}{\\mathbb{R}}\n\
+     \\begin{document}\n\\section{Intro}\\label{sec:intro}\nLet $x\\in\\R$ and   $a+b=c$.\n\n\n\
+     \\begin{itemize}\n\\item one\ncontinued\n      \\item two % note\n\\end{itemize}\n\\end{document}\n",
+    "\\begin{align}\nx&=1+2\\\\\n-y   &= 3 & \\text{a&b}\\\\ % c&d\n\\begin{cases}\n1&x\\\\\n\
+     -1&y\n\\end{cases}\n\\end{align}",
+    "\\begin{quote}\nthe quick brown fox jumps over the lazy dog\n\\end{quote}\t\n",
+    "$\\frac{a + b}{c + d} + x ^ {n + 1}$ and more words, $x^{-1}+y_{i+1}$ or $-b$",
+    "\\begin{verbatim}\n  x+y   \n\t\\end\n\\end{verbatim}\n\\verb|a+b   =c| {\n\\textbf{x}\n}",
+    "\\def\\pair#1#2{(#1,#2)}\n\\begin{tabular}{ll}\na & b \\\\\n\\hline\nc&d\\\\\n\\end{tabular}",
+    "\\begin{frame}{Title}\n\\begin{itemize}[<+->]\n\\item<2-> a\\\\[2pt] b\n\\end{itemize}\n\
+     \\end{frame}\n% trailing comment",
+    "unbalanced { group \\begin{center} $ math \\end{itemize}\n}}",
+];
+
+/// The nodes of a source with the tokens they span, and the texts of the tokens.
+type Structure<'source> = (Vec<(NodeKind, usize, usize)>, Vec<(SyntaxKind, &'source str)>);
+
+/// The structure of `source`, where only the tokens which are not [blank](Trivia::BLANK) count.
+fn structure(source: &str) -> Structure<'_> {
+    let lexed = LexedStr::new(source);
+    let significant: Vec<_> =
+        (0..lexed.len()).filter(|&idx| !lexed.kind(idx).is_trivia(Trivia::BLANK)).collect();
+    let position = |idx: usize| significant.partition_point(|&significant| significant < idx);
+    let nodes = Document::new(&lexed)
+        .descendants()
+        .map(|node| {
+            let range = node.token_range();
+            (node.kind(), position(range.start), position(range.end))
+        })
+        .collect();
+    let tokens = significant.iter().map(|&idx| (lexed.kind(idx), lexed.text(idx))).collect();
+    (nodes, tokens)
+}
+
+/// Formats `source` with each of a few option sets and checks that formatting again changes
+/// nothing and that the structure of the source is kept.
+fn assert_round_trip(source: &str) {
+    let mut aligned = FormatOptions { line_width: Some(16), ..Default::default() };
+    aligned.environments.extend([
+        ("align".to_string(), EnvironmentRule { align: true, ..Default::default() }),
+        ("itemize".to_string(), EnvironmentRule { hanging_items: true, ..Default::default() }),
+    ]);
+    let options = [
+        FormatOptions::default(),
+        FormatOptions { indent: Indent::Tab, indent_document: true, ..Default::default() },
+        FormatOptions { line_width: Some(10), math_spacing: false, ..Default::default() },
+        aligned,
+    ];
+    for options in &options {
+        let formatted = format(&LexedStr::new(source), options);
+        let again = format(&LexedStr::new(&formatted), options);
+        assert_eq!(again, formatted, "not idempotent for {source:?} with {options:?}");
+        assert!(
+            structure(&formatted) == structure(source),
+            "structure changed for {source:?} with {options:?}:\n{formatted}"
+        );
+    }
+}
+
+#[test]
+fn round_trip_corpus() {
+    for source in CORPUS {
+        assert_round_trip(source);
+    }
+}
+
+#[test]
+fn round_trip_generated() {
+    // xorshift, so failures are reproducible
+    let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+    let fragments = [
+        "word",
+        "more words",
+        " ",
+        "  ",
+        "\t",
+        "\n",
+        "\n\n",
+        "\n  ",
+        "% comment\n",
+        "{",
+        "}",
+        "[",
+        "]",
+        "$",
+        "$$",
+        "+",
+        "-",
+        "=",
+        "^",
+        "_",
+        "&",
+        "\\\\",
+        "\\item ",
+        "\\textbf{",
+        "x",
+        "\\section{Title}",
+        "\\begin{itemize}",
+        "\\end{itemize}",
+        "\\begin{align}",
+        "\\end{align}",
+        "\\begin{verbatim} a  + b \\end{verbatim}",
+        "\\verb|x  y|",
+        "\\newcommand{\\x}{",
+        "\\frac{a}{b}",
+        "\\text{a-b}",
+        "1",
+        "(",
+        ")",
+        ",",
+        ".",
+        "ä€",
+    ];
+
+    for _ in 0..1000 {
+        let len = (next() % 30) as usize;
+        let source: String =
+            (0..len).map(|_| fragments[(next() % fragments.len() as u64) as usize]).collect();
+        assert_round_trip(&source);
+    }
+}
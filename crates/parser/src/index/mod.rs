@@ -0,0 +1,153 @@
+//! Index of the citations, labels and references of a document.
+//!
+//! [`DocumentIndex`] collects every key of `\cite`, `\bibliography`, `\label` and `\ref` like
+//! commands together with its position. It is the basis for finding all references to a label
+//! and for reporting references to undefined labels.
+
+use std::ops::Range;
+
+use crate::{LexedStr, SyntaxKind};
+
+/// The kind of an [`IndexEntry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IndexKind {
+    /// A bibliography key cited with e.g. `\cite` or `\parencite`
+    Citation,
+    /// A bibliography database loaded with `\bibliography` or `\addbibresource`
+    Bibliography,
+    /// A label defined with `\label`
+    Label,
+    /// A label referenced with e.g. `\ref` or `\eqref`
+    Reference,
+}
+
+/// Commands whose mandatory argument is a comma separated list of keys.
+const INDEXED_COMMANDS: &[(&str, IndexKind)] = &[
+    ("\\cite", IndexKind::Citation),
+    ("\\citep", IndexKind::Citation),
+    ("\\citet", IndexKind::Citation),
+    ("\\citeauthor", IndexKind::Citation),
+    ("\\citeyear", IndexKind::Citation),
+    ("\\nocite", IndexKind::Citation),
+    ("\\parencite", IndexKind::Citation),
+    ("\\textcite", IndexKind::Citation),
+    ("\\autocite", IndexKind::Citation),
+    ("\\footcite", IndexKind::Citation),
+    ("\\bibliography", IndexKind::Bibliography),
+    ("\\addbibresource", IndexKind::Bibliography),
+    ("\\label", IndexKind::Label),
+    ("\\ref", IndexKind::Reference),
+    ("\\eqref", IndexKind::Reference),
+    ("\\pageref", IndexKind::Reference),
+    ("\\autoref", IndexKind::Reference),
+    ("\\nameref", IndexKind::Reference),
+    ("\\cref", IndexKind::Reference),
+    ("\\Cref", IndexKind::Reference),
+];
+
+/// A single key of an indexed command, e.g. `knuth84` in `\cite{knuth84}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry<'source> {
+    /// What the key refers to
+    pub kind: IndexKind,
+    /// The command including the backslash
+    pub command: &'source str,
+    /// The key without surrounding whitespace
+    pub key: &'source str,
+    /// Byte range of `key` in the source text
+    pub range: Range<usize>,
+}
+
+/// All citations, bibliographies, labels and references of a document in source order.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentIndex<'source> {
+    entries: Vec<IndexEntry<'source>>,
+}
+
+impl<'source> DocumentIndex<'source> {
+    /// Indexes `lexed`.
+    pub fn new(lexed: &LexedStr<'source>) -> DocumentIndex<'source> {
+        let mut entries = Vec::new();
+
+        for idx in 0..lexed.len() {
+            if lexed.kind(idx) != SyntaxKind::Command {
+                continue;
+            }
+            let command = lexed.text(idx);
+            let Some(&(_, kind)) = INDEXED_COMMANDS.iter().find(|(name, _)| *name == command)
+            else {
+                continue;
+            };
+            let Some(keys) = key_list(lexed, idx + 1) else {
+                continue;
+            };
+
+            let mut start = lexed.text_start(keys.start);
+            let text = &lexed.as_str()[start..lexed.text_start(keys.end)];
+            for part in text.split(',') {
+                let key = part.trim();
+                let offset = start + (part.len() - part.trim_start().len());
+                if !key.is_empty() {
+                    entries.push(IndexEntry {
+                        kind,
+                        command,
+                        key,
+                        range: offset..offset + key.len(),
+                    });
+                }
+                start += part.len() + 1;
+            }
+        }
+
+        DocumentIndex { entries }
+    }
+
+    /// All entries in source order.
+    pub fn entries(&self) -> &[IndexEntry<'source>] {
+        &self.entries
+    }
+
+    /// All entries of `kind` in source order.
+    pub fn entries_of(&self, kind: IndexKind) -> impl Iterator<Item = &IndexEntry<'source>> {
+        self.entries.iter().filter(move |entry| entry.kind == kind)
+    }
+
+    /// All entries of `kind` with the given `key`, e.g. every reference to a label.
+    pub fn find(
+        &self,
+        kind: IndexKind,
+        key: &str,
+    ) -> impl Iterator<Item = &IndexEntry<'source>> + '_ {
+        let key = key.to_string();
+        self.entries_of(kind).filter(move |entry| entry.key == key)
+    }
+
+    /// The `\label` defining `key`.
+    pub fn label(&self, key: &str) -> Option<&IndexEntry<'source>> {
+        self.find(IndexKind::Label, key).next()
+    }
+}
+
+/// Token range of the content of the key group following the token `idx`. A `*` and optional
+/// arguments in front of it, e.g. `\cite*[p.~4]{key}`, are skipped.
+fn key_list(lexed: &LexedStr<'_>, mut idx: usize) -> Option<Range<usize>> {
+    loop {
+        idx = lexed.next_non_trivia(idx)?;
+        match lexed.kind(idx) {
+            SyntaxKind::Star => idx += 1,
+            SyntaxKind::OpenBracket => {
+                let close =
+                    (idx..lexed.len()).find(|&idx| lexed.kind(idx) == SyntaxKind::CloseBracket)?;
+                idx = close + 1;
+            }
+            SyntaxKind::OpenBrace => break,
+            _ => return None,
+        }
+    }
+
+    let close = (idx..lexed.len()).find(|&close| lexed.kind(close) == SyntaxKind::CloseBrace)?;
+    Some(idx + 1..close)
+}
+
+#[cfg(test)]
+mod tests;
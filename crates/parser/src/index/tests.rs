@@ -0,0 +1,30 @@
+use super::{DocumentIndex, IndexKind};
+use crate::LexedStr;
+
+#[test]
+fn index_entries() {
+    let input = "\\section{Intro}\\label{sec:intro}\nsee \\cite[p.~4]{ knuth84 ,lamport94}, \
+                 \\ref{sec:intro} and \\cref {sec:intro,eq:1}\n\\nocite*{}\\bibliography{refs}";
+    let lexed = LexedStr::new(input);
+    let index = DocumentIndex::new(&lexed);
+
+    let entries: Vec<_> =
+        index.entries().iter().map(|entry| (entry.kind, entry.command, entry.key)).collect();
+    assert_eq!(
+        entries,
+        [
+            (IndexKind::Label, "\\label", "sec:intro"),
+            (IndexKind::Citation, "\\cite", "knuth84"),
+            (IndexKind::Citation, "\\cite", "lamport94"),
+            (IndexKind::Reference, "\\ref", "sec:intro"),
+            (IndexKind::Reference, "\\cref", "sec:intro"),
+            (IndexKind::Reference, "\\cref", "eq:1"),
+            (IndexKind::Bibliography, "\\bibliography", "refs"),
+        ]
+    );
+    assert!(index.entries().iter().all(|entry| input[entry.range.clone()] == *entry.key));
+
+    assert_eq!(index.find(IndexKind::Reference, "sec:intro").count(), 2);
+    assert!(index.label("sec:intro").is_some() && index.label("eq:1").is_none());
+    assert_eq!(index.entries_of(IndexKind::Citation).count(), 2);
+}
@@ -0,0 +1,160 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use super::{LexedStr, SpannedToken};
+use crate::{SyntaxKind, Trivia};
+
+/// A [`LexedStr`] which stores runs of whitespace, newlines and paragraph breaks only once.
+///
+/// In prose almost every word is followed by a single space or newline, so about half of the
+/// tokens of a [`LexedStr`] are identical trivia. Here every run of such trivia is interned as a
+/// pattern and the following significant token references it by id. Comments are kept as
+/// regular tokens.
+///
+/// A token takes 8 instead of 6 bytes, which pays off as soon as more than a quarter of the
+/// tokens are blank trivia. Once [`u16::MAX`] distinct runs have been interned, further new runs
+/// are stored as regular tokens.
+///
+/// The tokens are reconstructed on the fly, [`CompactLexedStr::spanned_tokens`] yields exactly
+/// the tokens of the [`LexedStr`] it was created from.
+#[derive(Debug)]
+pub struct CompactLexedStr<'source> {
+    text: &'source str,
+    /// Kind of every significant token followed by [`SyntaxKind::Eof`]
+    kind: Vec<SyntaxKind>,
+    /// Byte offset of every significant token followed by the length of the text
+    start: Vec<u32>,
+    /// Id of the trivia pattern in front of every significant token and in front of the end
+    trivia: Vec<u16>,
+    patterns: Vec<TriviaPattern>,
+}
+
+/// Kinds and lengths of the tokens of an interned trivia run. Id 0 is the empty run.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct TriviaPattern {
+    tokens: Vec<(SyntaxKind, u32)>,
+    len: u32,
+}
+
+impl<'source> CompactLexedStr<'source> {
+    /// Interns the trivia of `lexed`.
+    pub fn new(lexed: &LexedStr<'source>) -> CompactLexedStr<'source> {
+        let empty = TriviaPattern { tokens: Vec::new(), len: 0 };
+        let mut ids = BTreeMap::from([(empty.clone(), 0)]);
+        let mut compact = CompactLexedStr {
+            text: lexed.as_str(),
+            kind: Vec::new(),
+            start: Vec::new(),
+            trivia: Vec::new(),
+            patterns: Vec::from([empty]),
+        };
+
+        let mut run = TriviaPattern { tokens: Vec::new(), len: 0 };
+        for token in lexed.spanned_tokens().chain([eof(lexed)]) {
+            if token.kind.is_trivia(Trivia::BLANK) {
+                run.tokens.push((token.kind, token.text_len() as u32));
+                run.len += token.text_len() as u32;
+                continue;
+            }
+
+            let run = core::mem::replace(&mut run, TriviaPattern { tokens: Vec::new(), len: 0 });
+            let id = match ids.get(&run) {
+                Some(&id) => id,
+                None => match u16::try_from(compact.patterns.len()) {
+                    Ok(id) => {
+                        ids.insert(run.clone(), id);
+                        compact.patterns.push(run);
+                        id
+                    }
+                    Err(_) => {
+                        let mut start = token.range.start - run.len as usize;
+                        for (kind, len) in run.tokens {
+                            compact.push(kind, start, 0);
+                            start += len as usize;
+                        }
+                        0
+                    }
+                },
+            };
+
+            compact.push(token.kind, token.range.start, id);
+        }
+
+        compact
+    }
+
+    fn push(&mut self, kind: SyntaxKind, start: usize, trivia: u16) {
+        self.kind.push(kind);
+        self.start.push(start as u32);
+        self.trivia.push(trivia);
+    }
+
+    /// The complete source text.
+    pub fn as_str(&self) -> &'source str {
+        self.text
+    }
+
+    /// Number of significant tokens, excluding the trailing [`SyntaxKind::Eof`].
+    pub fn len(&self) -> usize {
+        self.kind.len() - 1
+    }
+
+    /// Returns `true` if the source contains nothing but blank trivia.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of distinct trivia runs, including the empty one.
+    pub fn trivia_patterns(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Kind of the significant token at `idx`.
+    pub fn kind(&self, idx: usize) -> SyntaxKind {
+        assert!(idx < self.len());
+        self.kind[idx]
+    }
+
+    /// Source text of the significant token at `idx`.
+    pub fn text(&self, idx: usize) -> &'source str {
+        assert!(idx < self.len());
+        let end = self.start[idx + 1] - self.patterns[self.trivia[idx + 1] as usize].len;
+        &self.text[self.start[idx] as usize..end as usize]
+    }
+
+    /// Iterator over all tokens, including the interned trivia.
+    pub fn spanned_tokens(&self) -> impl Iterator<Item = SpannedToken<'source>> + '_ {
+        (0..self.kind.len()).flat_map(move |idx| {
+            let pattern = &self.patterns[self.trivia[idx] as usize];
+            let mut offset = (self.start[idx] - pattern.len) as usize;
+
+            let trivia = pattern.tokens.iter().map(move |&(kind, len)| {
+                let range = offset..offset + len as usize;
+                offset = range.end;
+                SpannedToken { kind, text: &self.text[range.clone()], range }
+            });
+            let significant = (idx < self.len()).then(|| {
+                let text = self.text(idx);
+                let start = self.start[idx] as usize;
+                SpannedToken { kind: self.kind[idx], text, range: start..start + text.len() }
+            });
+
+            trivia.chain(significant)
+        })
+    }
+
+    /// Regenerates the source text from the tokens.
+    pub fn reconstruct(&self) -> String {
+        self.spanned_tokens().map(|token| token.text).collect()
+    }
+}
+
+impl<'source> From<&LexedStr<'source>> for CompactLexedStr<'source> {
+    fn from(lexed: &LexedStr<'source>) -> CompactLexedStr<'source> {
+        CompactLexedStr::new(lexed)
+    }
+}
+
+fn eof<'source>(lexed: &LexedStr<'source>) -> SpannedToken<'source> {
+    let end = lexed.as_str().len();
+    SpannedToken { kind: SyntaxKind::Eof, text: "", range: end..end }
+}
@@ -1,11 +1,15 @@
-use std::ops::Deref;
-
-use unicode_ident::{is_xid_continue, is_xid_start};
-
-use crate::lexer::{
-    infra::{consume, consume_str, reconsume, reset, LexerDelegate, LexerNext},
-    tables::{COMPOSITE_SYMBOL_TABLE, SYMBOL_TABLE, UNIT_TABLE},
-    token::LexToken,
+use core::ops::Deref;
+
+use unicode_ident::is_xid_start;
+
+use crate::{
+    chars::{is_newline, is_whitespace},
+    lexer::{
+        infra::{consume, consume_str, reconsume, reset, LexerDelegate, LexerNext},
+        tables::{COMPOSITE_SYMBOL_TABLE, SYMBOL_TABLE, UNIT_TABLE},
+        token::LexToken,
+        LexerProfile,
+    },
 };
 
 use super::infra::TokenizerItemDelegate;
@@ -25,6 +29,7 @@ pub(crate) enum LexerState {
     VariableName,
     VariableNameStart,
     VariableNameContinue,
+    PathSeparator { variable: bool },
     Comment,
     Number,
     Float,
@@ -55,7 +60,12 @@ impl LexerDelegate for LexerState {
         LexerState::Top { unit: false }
     }
 
-    fn next<'src>(&self, c: Option<char>, rest: &'src str) -> LexerNext<Self> {
+    fn next<'src>(
+        &self,
+        c: Option<char>,
+        rest: &'src str,
+        profile: LexerProfile,
+    ) -> LexerNext<Self> {
         use LexerState::*;
 
         match self {
@@ -70,11 +80,11 @@ impl LexerDelegate for LexerState {
                     c if is_multichar_newline(c, rest) => {
                         consume_str("\r\n").and_transition(Newline { unit })
                     }
-                    c if is_newline(c, rest) => consume(c).and_transition(Newline { unit }),
+                    c if is_newline(c) => consume(c).and_transition(Newline { unit }),
 
                     // numbers
                     '0'..='9' => LexerNext::begin(Number),
-                    '.' if is_continue_numeric(rest) => LexerNext::begin(Float),
+                    '.' if is_continue_numeric(rest) => consume('.').and_transition(Float),
                     // all units are ascii
                     c if (unit && c.is_ascii()) => reconsume().and_transition(Unit),
 
@@ -83,7 +93,7 @@ impl LexerDelegate for LexerState {
                         .and_emit(LexToken::CommandIdent)
                         .and_transition(CommandNameBegin),
 
-                    '#' => LexerNext::begin(MacroParameter),
+                    '#' => consume('#').and_transition(MacroParameter),
 
                     '%' => LexerNext::begin(Comment),
                     c if !c.is_alphabetic() => LexerNext::begin(Symbol),
@@ -93,7 +103,7 @@ impl LexerDelegate for LexerState {
 
             &Whitespace { unit, from, len } => match c {
                 None => reconsume().and_emit(LexToken::Whitespace).and_transition(top()),
-                Some(c) if is_newline(c, rest) && from != FromSeperator::None => {
+                Some(c) if is_newline(c) && from != FromSeperator::None => {
                     reconsume().and_transition(Break)
                 }
                 Some(c) if is_whitespace(c) => {
@@ -116,7 +126,7 @@ impl LexerDelegate for LexerState {
                     len: 0,
                 }),
                 Some(c) if is_multichar_newline(c, rest) => consume_str("\r\n").and_remain(),
-                Some(c) if is_newline(c, rest) => consume(c).and_remain(),
+                Some(c) if is_newline(c) => consume(c).and_remain(),
                 Some(_) => reconsume().and_emit(LexToken::Break).and_transition(top()),
             },
 
@@ -130,7 +140,7 @@ impl LexerDelegate for LexerState {
                 Some(c) if is_multichar_newline(c, rest) => {
                     consume_str("\r\n").and_transition(Break)
                 }
-                Some(c) if is_newline(c, rest) => consume(c).and_transition(Break),
+                Some(c) if is_newline(c) => consume(c).and_transition(Break),
                 Some(_) => reconsume().and_emit(LexToken::Newline).and_transition(Top { unit }),
             },
 
@@ -192,15 +202,17 @@ impl LexerDelegate for LexerState {
             CommandNameBegin => match c {
                 None => reconsume().and_discard().and_transition(top()),
                 Some(c) if is_whitespace(c) => reconsume().and_discard().and_transition(top()),
-                Some(c) if is_newline(c, rest) => reconsume().and_discard().and_transition(top()),
-                Some('u') => consume('u').and_transition(UnicodeEscape),
-                Some('@') => {
+                Some(c) if is_newline(c) => reconsume().and_discard().and_transition(top()),
+                Some('u') if profile.extensions() => consume('u').and_transition(UnicodeEscape),
+                Some('@') if profile.extensions() => {
                     consume('@').and_emit(LexToken::VariableIdent).and_transition(VariableName)
                 }
-                Some(':') if rest.starts_with(':') => consume_str("::")
+                Some(':') if profile.extensions() && rest.starts_with(':') => consume_str("::")
                     .and_emit(LexToken::PathSeparator)
                     .and_transition(CommandNameContinueBegin),
-                Some(c) if is_xid_start(c) => consume(c).and_transition(CommandNameContinue),
+                Some(c) if profile.is_command_start(c) => {
+                    consume(c).and_transition(CommandNameContinue)
+                }
                 Some(c) if c.is_ascii() => {
                     consume(c).and_emit(LexToken::Command).and_transition(top())
                 }
@@ -218,10 +230,10 @@ impl LexerDelegate for LexerState {
 
             CommandNameContinue => match c {
                 None => reconsume().and_emit(LexToken::Command).and_transition(top()),
-                Some(':') if rest.starts_with(':') => consume_str("::")
-                    .and_emit(LexToken::PathSeparator)
-                    .and_transition(CommandNameContinueBegin),
-                Some(c) if is_xid_continue(c) => consume(c).and_remain(),
+                Some(':') if profile.extensions() && rest.starts_with(':') => reconsume()
+                    .and_emit(LexToken::Command)
+                    .and_transition(PathSeparator { variable: false }),
+                Some(c) if profile.is_command_continue(c) => consume(c).and_remain(),
                 Some(_) => reconsume().and_emit(LexToken::Command).and_transition(top()),
             },
 
@@ -243,17 +255,23 @@ impl LexerDelegate for LexerState {
 
             VariableNameContinue => match c {
                 None => reconsume().and_emit(LexToken::Variable).and_transition(top()),
-                Some(':') if rest.starts_with(':') => consume_str("::")
-                    .and_emit(LexToken::PathSeparator)
-                    .and_transition(VariableNameStart),
-                Some(c) if is_xid_continue(c) => consume(c).and_remain(),
+                Some(':') if rest.starts_with(':') => reconsume()
+                    .and_emit(LexToken::Variable)
+                    .and_transition(PathSeparator { variable: true }),
+                Some(c) if profile.is_command_continue(c) => consume(c).and_remain(),
                 Some(_) => reconsume().and_emit(LexToken::Variable).and_transition(top()),
             },
 
+            // only called in front of a `::`
+            &PathSeparator { variable } => {
+                let next = if variable { VariableNameStart } else { CommandNameContinueBegin };
+                consume_str("::").and_emit(LexToken::PathSeparator).and_transition(next)
+            }
+
             UnicodeEscape => match c {
                 None => reconsume().and_emit(LexToken::UnicodeEscape).and_transition(top()),
                 Some('{') => consume('{').and_transition(UnicodeEscapeValue),
-                Some(c) if is_whitespace(c) || is_newline(c, rest) => {
+                Some(c) if is_whitespace(c) || is_newline(c) => {
                     reconsume().and_emit(LexToken::UnicodeEscape).and_transition(top())
                 }
                 Some(_) => reconsume().and_transition(CommandNameContinue),
@@ -267,6 +285,13 @@ impl LexerDelegate for LexerState {
 
             MacroParameter => match c {
                 None => reconsume().and_emit(LexToken::NumSign).and_transition(top()),
+                // LaTeX only knows the parameters '#1' to '#9'
+                Some(c @ '1'..='9') if !profile.extensions() => {
+                    consume(c).and_emit(LexToken::MacroParameter).and_transition(top())
+                }
+                Some(_) if !profile.extensions() => {
+                    reconsume().and_emit(LexToken::NumSign).and_transition(top())
+                }
                 Some(c @ '0'..='9') => consume(c).and_transition(MacroParameterContinue),
                 Some(c) if is_xid_start(c) => consume(c).and_transition(MacroParameterContinue),
                 Some(_) => reconsume().and_emit(LexToken::NumSign).and_transition(top()),
@@ -275,13 +300,13 @@ impl LexerDelegate for LexerState {
             MacroParameterContinue => match c {
                 None => reconsume().and_emit(LexToken::MacroParameter).and_transition(top()),
                 Some('_') => reconsume().and_emit(LexToken::MacroParameter).and_transition(top()),
-                Some(c) if is_xid_continue(c) => consume(c).and_remain(),
+                Some(c) if profile.is_command_continue(c) => consume(c).and_remain(),
                 Some(_) => reconsume().and_emit(LexToken::MacroParameter).and_transition(top()),
             },
 
             Comment => match c {
                 None => reconsume().and_emit(LexToken::Comment).and_transition(top()),
-                Some(c) if is_newline(c, rest) => {
+                Some(c) if is_newline(c) => {
                     reconsume().and_emit(LexToken::Comment).and_transition(top())
                 }
                 Some(c) => consume(c).and_remain(),
@@ -291,9 +316,12 @@ impl LexerDelegate for LexerState {
                 None => reconsume().and_discard().and_transition(top()),
                 Some(first) => {
                     if let Some(second) = rest.chars().next() {
-                        if let Some((.., token)) = COMPOSITE_SYMBOL_TABLE
-                            .iter()
-                            .find(|&(f, s, _)| f == &first && s == &second)
+                        if let Some((.., token)) =
+                            COMPOSITE_SYMBOL_TABLE.iter().find(|&(f, s, token)| {
+                                f == &first
+                                    && s == &second
+                                    && (profile.extensions() || *token == LexToken::MathDisplay)
+                            })
                         {
                             return consume_str("$$").and_emit(*token).and_transition(top());
                         }
@@ -310,8 +338,10 @@ impl LexerDelegate for LexerState {
 
             Word => match c {
                 None => reconsume().and_transition(top()),
-                Some(c) if !c.is_ascii_alphabetic() => reconsume().and_transition(AWord),
-                Some(c) => reconsume().and_transition(UWord),
+                Some(c) if is_ascii_word(c, rest) => reconsume().and_transition(AWord),
+                // e.g. a symbol following a backslash, which would otherwise become an empty word
+                Some(c) if !c.is_alphabetic() => reconsume().and_transition(top()),
+                Some(_) => reconsume().and_transition(UWord),
             },
 
             AWord => match c {
@@ -337,44 +367,15 @@ fn top() -> LexerState {
     LexerState::top()
 }
 
-fn is_whitespace(c: char) -> bool {
-    matches!(
-        c,
-        '\u{0009}'   // \t
-           | '\u{0020}' // space
-           | '\u{00A0}' // no-break space
-           | '\u{1680}' // ogham space mark
-           | '\u{202F}' // narrow no-break space
-           | '\u{3000}' // ideographic space
-
-           // Bidi markers
-           | '\u{200E}' // LEFT-TO-RIGHT MARK
-           | '\u{200F}' // RIGHT-TO-LEFT MARK
-    )
-}
-
-fn is_newline(c: char, rest: &str) -> bool {
-    if c == '\r' && rest.starts_with('\n') {
-        return true;
-    }
-    matches!(
-        c,
-        '\u{000A}' // \n
-           | '\u{000B}' // vertical tab
-           | '\u{000C}' // form feed
-           | '\u{000D}' // \r
-           | '\u{0085}' // NEXT LINE from latin1
-
-           // Dedicated whitespace characters from Unicode
-           | '\u{2028}' // LINE SEPARATOR
-           | '\u{2029}' // PARAGRAPH SEPARATOR
-    )
-}
-
 fn is_multichar_newline(c: char, rest: &str) -> bool {
     c == '\r' && rest.starts_with('\n')
 }
 
+fn is_ascii_word(c: char, rest: &str) -> bool {
+    c.is_ascii_alphabetic()
+        && rest.chars().take_while(|c| c.is_alphabetic()).all(|c| c.is_ascii_alphabetic())
+}
+
 fn is_break(c: char) -> bool {
     matches!(c, '\u{2029}') // PARAGRAPH SEPARATOR
 }
@@ -0,0 +1,81 @@
+use alloc::{collections::BTreeMap, string::String};
+use core::fmt;
+
+use super::LexedStr;
+
+/// Writes the tokens of a [`LexedStr`] back out after modifying single tokens.
+///
+/// Tokens without an edit are written unchanged, so an emitter without any edits reproduces the
+/// source text byte for byte:
+///
+/// ```
+/// # use parser::{LexedStr, SyntaxKind, TokenEmitter};
+/// let lexed = LexedStr::new(r"\foo{x} \foo");
+/// let mut emitter = TokenEmitter::new(&lexed);
+/// for (idx, token) in lexed.spanned_tokens().enumerate() {
+///     if token.kind == SyntaxKind::Command && token.text == r"\foo" {
+///         emitter.replace(idx, r"\bar");
+///     }
+/// }
+/// assert_eq!(emitter.to_string(), r"\bar{x} \bar");
+/// ```
+#[derive(Debug)]
+pub struct TokenEmitter<'lexed, 'source> {
+    lexed: &'lexed LexedStr<'source>,
+    replacements: BTreeMap<usize, String>,
+    insertions: BTreeMap<usize, String>,
+}
+
+impl<'lexed, 'source> TokenEmitter<'lexed, 'source> {
+    /// Creates an emitter without any edits.
+    pub fn new(lexed: &'lexed LexedStr<'source>) -> TokenEmitter<'lexed, 'source> {
+        TokenEmitter { lexed, replacements: BTreeMap::new(), insertions: BTreeMap::new() }
+    }
+
+    /// Replaces the text of the token at `idx`. A later replacement of the same token wins.
+    pub fn replace(&mut self, idx: usize, text: impl Into<String>) -> &mut Self {
+        assert!(idx < self.lexed.len());
+        self.replacements.insert(idx, text.into());
+        self
+    }
+
+    /// Drops the token at `idx` from the output.
+    pub fn remove(&mut self, idx: usize) -> &mut Self {
+        self.replace(idx, String::new())
+    }
+
+    /// Inserts `text` in front of the token at `idx`. Passing [`LexedStr::len`] appends to the end.
+    pub fn insert_before(&mut self, idx: usize, text: &str) -> &mut Self {
+        assert!(idx <= self.lexed.len());
+        self.insertions.entry(idx).or_default().push_str(text);
+        self
+    }
+
+    /// Returns `true` if no token has been modified.
+    pub fn is_unchanged(&self) -> bool {
+        self.replacements.is_empty() && self.insertions.is_empty()
+    }
+
+    /// Writes all tokens with their edits applied to `out`.
+    pub fn write_to(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        for idx in 0..=self.lexed.len() {
+            if let Some(inserted) = self.insertions.get(&idx) {
+                out.write_str(inserted)?;
+            }
+            if idx == self.lexed.len() {
+                break;
+            }
+            match self.replacements.get(&idx) {
+                Some(replacement) => out.write_str(replacement)?,
+                None => out.write_str(self.lexed.text(idx))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TokenEmitter<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_to(f)
+    }
+}
@@ -1,14 +1,21 @@
-use std::fmt;
+use core::fmt;
 
 use span::{CurrentFile, Span, Spanned};
 use tracing::trace;
 
+use super::LexerProfile;
+
 pub(crate) trait LexerDelegate: fmt::Debug + Clone + Sized {
-    type Token: fmt::Debug + Clone + Sized + Eq + Attachable + Tombstone;
+    type Token: fmt::Debug + Clone + Sized + Eq + Attachable + Tombstone + Recover;
 
     fn top() -> Self;
 
-    fn next<'src>(&self, c: Option<char>, rest: &'src str) -> LexerNext<Self>;
+    fn next<'src>(
+        &self,
+        c: Option<char>,
+        rest: &'src str,
+        profile: LexerProfile,
+    ) -> LexerNext<Self>;
 }
 
 #[derive(Debug)]
@@ -92,9 +99,12 @@ pub fn reset(len: usize) -> LexerAction {
 pub(crate) struct Tokenizer<'table, Delegate: LexerDelegate> {
     state: Delegate,
     input: &'table str,
+    profile: LexerProfile,
     start: usize,
     token_len: usize,
     exhausted: bool,
+    /// Iterations since the last consumed character
+    stalled: usize,
 }
 
 pub(super) type TokenizerItemDelegate<'table, Delegate: LexerDelegate> =
@@ -104,24 +114,29 @@ impl<'table, Delegate: LexerDelegate + fmt::Debug> Iterator for Tokenizer<'table
     type Item = TokenizerItemDelegate<'table, Delegate>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        const MAX_ITERATIONS: usize = 1000;
+        // no state transitions or emits empty tokens more than a few times without consuming a
+        // character
+        const MAX_STALLED_ITERATIONS: usize = 64;
 
-        for _ in 0..MAX_ITERATIONS {
-            let Tokenizer { state, input, start, token_len, .. } = &self;
+        loop {
+            let Tokenizer { state, input, profile, start, token_len, stalled, .. } = &self;
 
             let pos = start + token_len;
-            let c = input[pos..].chars().next();
-            let rest = &input[pos + c.map(|c| c.len_utf8()).unwrap_or(0)..];
+            let Some(remaining) = input.get(pos..).filter(|_| *stalled <= MAX_STALLED_ITERATIONS)
+            else {
+                return self.recover();
+            };
+            let mut chars = remaining.chars();
+            let c = chars.next();
+            let rest = chars.as_str();
 
-            let next = state.next(c, rest);
+            let next = state.next(c, rest, *profile);
 
             match self.step(next) {
                 LoopCompletion::Return(item) => return self.emit(item),
                 LoopCompletion::Continue => {}
             }
         }
-
-        None
     }
 }
 
@@ -132,13 +147,53 @@ enum LoopCompletion<T> {
 
 impl<'table, Delegate: LexerDelegate> Tokenizer<'table, Delegate> {
     pub fn new(input: &'table str) -> Self {
-        Self { state: Delegate::top(), input, start: 0, token_len: 0, exhausted: false }
+        Self::with_profile(input, LexerProfile::default())
+    }
+
+    pub fn with_profile(input: &'table str, profile: LexerProfile) -> Self {
+        Self {
+            state: Delegate::top(),
+            input,
+            profile,
+            start: 0,
+            token_len: 0,
+            exhausted: false,
+            stalled: 0,
+        }
+    }
+
+    /// Emits everything up to the next character as an error token and restarts in the top
+    /// state. Called if a state got stuck or consumed past a character boundary, so a bug in a
+    /// state never ends in a panic or an endless loop.
+    fn recover(&mut self) -> Option<TokenizerItemDelegate<'table, Delegate>> {
+        let pos = (self.start + self.token_len).min(self.input.len());
+        let end = (pos + 1..=self.input.len())
+            .find(|&end| self.input.is_char_boundary(end))
+            .unwrap_or(self.input.len());
+
+        trace!("recover {:?} at {}", self.state, pos);
+        self.state = Delegate::top();
+        self.stalled = 0;
+        self.start = self.start.min(end);
+        self.token_len = end - self.start;
+
+        match self.token_len {
+            0 => self.emit(Delegate::Token::tombstone().attach_span(self.start, 0)),
+            len => {
+                let token = Delegate::Token::error().attach_span(self.start, len);
+                self.start = end;
+                self.token_len = 0;
+                self.emit(token)
+            }
+        }
     }
 
     fn step(
         &mut self,
         next: LexerNext<Delegate>,
     ) -> LoopCompletion<Spanned<Delegate::Token, CurrentFile>> {
+        self.stalled += 1;
+
         match next {
             LexerNext::EOF => {
                 trace!("EOF");
@@ -161,14 +216,8 @@ impl<'table, Delegate: LexerDelegate> Tokenizer<'table, Delegate> {
         use self::LexerAccumulate::*;
 
         match accum {
-            Begin => {
-                assert!(
-                    self.token_len == 0,
-                    "Cannot begin a new token when there are already accumulated characters"
-                );
-
-                LoopCompletion::Continue
-            }
+            // accumulated characters are kept and become part of the next token
+            Begin => LoopCompletion::Continue,
             Continue(action) => {
                 self.action(action);
                 LoopCompletion::Continue
@@ -200,10 +249,11 @@ impl<'table, Delegate: LexerDelegate> Tokenizer<'table, Delegate> {
         match action {
             LexerAction::Consume(n) => {
                 self.token_len += n;
+                self.stalled = 0;
             }
             LexerAction::Reconsume => {}
             LexerAction::Reset(n) => {
-                self.token_len -= n;
+                self.token_len = self.token_len.saturating_sub(n);
             }
         }
     }
@@ -240,4 +290,9 @@ pub trait Tombstone {
     fn tombstone() -> Self;
 }
 
+/// Token emitted for input the lexer could not make progress on.
+pub trait Recover {
+    fn error() -> Self;
+}
+
 impl<T: Sized> Attachable for T {}
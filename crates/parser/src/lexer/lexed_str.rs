@@ -0,0 +1,656 @@
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::ops::Range;
+
+use crate::{
+    lexer::{definition::LexerState, infra::Tokenizer, token::LexToken, LexerProfile, SourceKind},
+    syntax::Trivia,
+    SyntaxKind,
+};
+
+/// The source text together with its lexed [`SyntaxKind`] tokens.
+///
+/// Tokens are stored in two parallel vectors: `kind` holds the kind of every token and `start`
+/// its byte offset into the source. Both contain a trailing [`SyntaxKind::Eof`] entry, so the
+/// text of token `idx` always spans `start[idx]..start[idx + 1]`.
+///
+/// Offsets are stored as `u32` to keep a token at 6 bytes instead of the 10 of a `usize` offset,
+/// which limits the source text to 4 GiB. The `repr(u16)` kind is the only other state of a
+/// token, there are no flags to pack next to it, and expansions are kept apart in an
+/// [`ExpansionStore`](crate::expansion::ExpansionStore) rather than per token.
+///
+/// The vectors are borrowed if the tokens belong to a [`LexedBuffer`], see
+/// [`LexedBuffer::as_lexed`].
+#[derive(Debug)]
+pub struct LexedStr<'source> {
+    text: &'source str,
+    profile: LexerProfile,
+    kind: Cow<'source, [SyntaxKind]>,
+    start: Cow<'source, [u32]>,
+}
+
+impl<'source> LexedStr<'source> {
+    /// Lexes `text` into syntax tokens using the default [`LexerProfile`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` is larger than `u32::MAX` bytes.
+    pub fn new(text: &'source str) -> LexedStr<'source> {
+        LexedStr::with_profile(text, LexerProfile::default())
+    }
+
+    /// Lexes `text` into syntax tokens of the given dialect.
+    ///
+    /// In between `\makeatletter` and `\makeatother` `@` is a letter of command names, so
+    /// internals like `\@ifnextchar` or `\foo@bar` are single [`SyntaxKind::Command`] tokens.
+    /// The [`SourceKind`] is [detected](SourceKind::detect) from the text.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` is larger than `u32::MAX` bytes.
+    pub fn with_profile(text: &'source str, profile: LexerProfile) -> LexedStr<'source> {
+        LexedStr::with_source_kind(text, profile, SourceKind::detect(text))
+    }
+
+    /// Like [`LexedStr::with_profile`], but lexes `text` as a file of the given kind, e.g. as a
+    /// package with `@` being a letter from the start.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` is larger than `u32::MAX` bytes.
+    pub fn with_source_kind(
+        text: &'source str,
+        profile: LexerProfile,
+        kind: SourceKind,
+    ) -> LexedStr<'source> {
+        assert!(u32::try_from(text.len()).is_ok(), "source text exceeds 4 GiB");
+
+        let mut lexed =
+            LexedStr { text, profile, kind: Cow::Owned(Vec::new()), start: Cow::Owned(Vec::new()) };
+
+        // `@` is a letter in command names in between `\makeatletter` and `\makeatother`
+        let mut at_letter = kind.at_letter();
+        let mut tokens = Tokenizer::<LexerState>::with_profile(text, profile).peekable();
+        while let Some(token) = tokens.next() {
+            let range = token.span.span();
+
+            let end = match token.value {
+                LexToken::CommandIdent if at_letter => at_command_end(text, range.end, profile),
+                _ => range.end,
+            };
+            if text[range.end..end].contains('@') {
+                // the tokenizer splits the name at every `@`, the pieces are skipped and the rest
+                // of a piece extending past the name becomes a token of its own
+                let mut rest = None;
+                while let Some(piece) = tokens.next_if(|piece| piece.span.span().start < end) {
+                    rest = (piece.span.span().end > end).then_some(piece.value);
+                }
+                lexed.push(SyntaxKind::Command, range.start);
+                if let Some(rest) = rest {
+                    lexed.push(rest.into(), end);
+                }
+                continue;
+            }
+
+            let kind = match token.value {
+                LexToken::Eof => break,
+                // merge the backslash with the following command name
+                LexToken::CommandIdent => match tokens.peek().map(|next| next.value) {
+                    Some(LexToken::Command) => {
+                        let name = tokens.next().unwrap();
+                        match &text[name.span.span()] {
+                            "makeatletter" => at_letter = true,
+                            "makeatother" => at_letter = false,
+                            _ => {}
+                        }
+                        command_kind(&text[name.span.span()], profile)
+                    }
+                    // merge '\@' with the following variable name
+                    Some(LexToken::VariableIdent) => {
+                        tokens.next();
+                        tokens.next_if(|next| next.value == LexToken::Variable);
+                        SyntaxKind::Variable
+                    }
+                    _ => SyntaxKind::Command,
+                },
+                LexToken::Comment if text[range.clone()].starts_with("%%") => SyntaxKind::AComment,
+                LexToken::MacroParameter
+                    if text[range.clone()][1..].bytes().all(|b| b.is_ascii_digit()) =>
+                {
+                    SyntaxKind::SimpleMacroExpansionToken
+                }
+                token => token.into(),
+            };
+
+            lexed.push(kind, range.start);
+        }
+
+        lexed.push(SyntaxKind::Eof, text.len());
+        lexed
+    }
+
+    fn push(&mut self, kind: SyntaxKind, start: usize) {
+        // token lookup by offset relies on sorted starts
+        debug_assert!(self.start.last().map_or(true, |&last| last as usize <= start));
+        self.kind.to_mut().push(kind);
+        self.start.to_mut().push(start as u32);
+    }
+
+    /// The dialect the source text was lexed with.
+    pub fn profile(&self) -> LexerProfile {
+        self.profile
+    }
+
+    /// The complete source text.
+    pub fn as_str(&self) -> &'source str {
+        self.text
+    }
+
+    /// Number of tokens, excluding the trailing [`SyntaxKind::Eof`].
+    pub fn len(&self) -> usize {
+        self.kind.len() - 1
+    }
+
+    /// Returns `true` if the source produced no tokens.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Kind of the token at `idx`.
+    pub fn kind(&self, idx: usize) -> SyntaxKind {
+        assert!(idx < self.len());
+        self.kind[idx]
+    }
+
+    /// Source text of the token at `idx`.
+    pub fn text(&self, idx: usize) -> &'source str {
+        &self.text[self.text_range(idx)]
+    }
+
+    /// Byte range of the token at `idx` in the source text.
+    pub fn text_range(&self, idx: usize) -> Range<usize> {
+        assert!(idx < self.len());
+        self.start[idx] as usize..self.start[idx + 1] as usize
+    }
+
+    /// Byte offset at which the token at `idx` starts.
+    pub fn text_start(&self, idx: usize) -> usize {
+        assert!(idx <= self.len());
+        self.start[idx] as usize
+    }
+
+    /// Regenerates the source text from the tokens.
+    ///
+    /// Whitespace, newlines and comments are tokens as well, so the result is always equal to
+    /// [`LexedStr::as_str`]. Use a [`TokenEmitter`](crate::TokenEmitter) to write the tokens with
+    /// modifications.
+    pub fn reconstruct(&self) -> String {
+        self.spanned_tokens().map(|token| token.text).collect()
+    }
+
+    /// Finds the token covering the byte `offset`.
+    ///
+    /// At the boundary of two tokens both are returned, just like `rowan::TokenAtOffset`. An
+    /// offset at the end of the text belongs to the last token.
+    pub fn token_at_offset(&self, offset: usize) -> TokenAtOffset<usize> {
+        if self.is_empty() || offset > self.text.len() {
+            return TokenAtOffset::None;
+        }
+
+        // the first token always starts at 0
+        let idx = self.start[..self.len()].partition_point(|&start| start as usize <= offset) - 1;
+        match self.start[idx] as usize == offset && idx > 0 {
+            true => TokenAtOffset::Between(idx - 1, idx),
+            false => TokenAtOffset::Single(idx),
+        }
+    }
+
+    /// Index of the first token at or after `idx` which is no trivia, i.e. no whitespace,
+    /// newline, paragraph break or comment, see [`SyntaxKind::is_resolver_trivia`].
+    pub fn next_non_trivia(&self, idx: usize) -> Option<usize> {
+        (idx..self.len()).find(|&idx| !self.kind[idx].is_resolver_trivia())
+    }
+
+    /// Index of the last token before `idx` which is no trivia.
+    pub fn prev_non_trivia(&self, idx: usize) -> Option<usize> {
+        (0..idx.min(self.len())).rev().find(|&idx| !self.kind[idx].is_resolver_trivia())
+    }
+
+    /// Token range of the trivia in front of the token at `idx` which is not
+    /// [trailing trivia](Self::trailing_trivia) of the previous token. Like in rust-analyzer, a
+    /// comment on a line of its own belongs to the following token.
+    ///
+    /// ```
+    /// use parser::LexedStr;
+    ///
+    /// let lexed = LexedStr::new("\\a % a\n\n% b\n\\b");
+    /// let text = |range: std::ops::Range<usize>| {
+    ///     &lexed.as_str()[lexed.text_start(range.start)..lexed.text_start(range.end)]
+    /// };
+    /// let b = lexed.len() - 1;
+    /// assert_eq!(text(lexed.trailing_trivia(0)), " % a");
+    /// assert_eq!(text(lexed.leading_trivia(b)), "\n\n% b\n");
+    /// ```
+    pub fn leading_trivia(&self, idx: usize) -> Range<usize> {
+        let start = self.prev_non_trivia(idx).map_or(0, |prev| self.trailing_trivia(prev).end);
+        start..idx
+    }
+
+    /// Token range of the trivia following the token at `idx` on the same line, i.e. whitespace
+    /// and comments up to the next newline.
+    pub fn trailing_trivia(&self, idx: usize) -> Range<usize> {
+        let end = (idx + 1..self.len())
+            .find(|&idx| {
+                !matches!(
+                    self.kind[idx],
+                    SyntaxKind::Whitespace | SyntaxKind::Comment | SyntaxKind::AComment
+                )
+            })
+            .unwrap_or(self.len());
+        idx + 1..end
+    }
+
+    /// Iterator over the kinds of all tokens, excluding the trailing [`SyntaxKind::Eof`].
+    pub fn syntax_tokens(&self) -> impl Iterator<Item = SyntaxKind> + '_ {
+        self.kind[..self.len()].iter().copied()
+    }
+
+    /// Iterator over all tokens yielding their kind, text and byte range together.
+    pub fn spanned_tokens(&self) -> impl Iterator<Item = SpannedToken<'source>> + '_ {
+        (0..self.len()).map(|idx| SpannedToken {
+            kind: self.kind[idx],
+            text: self.text(idx),
+            range: self.text_range(idx),
+        })
+    }
+
+    /// Copies the tokens into a form which does not borrow the source text.
+    pub fn to_tokens(&self) -> LexedTokens {
+        LexedTokens { profile: self.profile, kind: self.kind.to_vec(), start: self.start.to_vec() }
+    }
+
+    /// Like [`LexedStr::to_tokens`], but does not copy tokens which are not borrowed.
+    pub fn into_tokens(self) -> LexedTokens {
+        let LexedStr { profile, kind, start, .. } = self;
+        LexedTokens { profile, kind: kind.into_owned(), start: start.into_owned() }
+    }
+
+    /// Copies the source text into a [`LexedBuffer`], which owns text and tokens.
+    pub fn to_buffer(&self) -> LexedBuffer {
+        LexedBuffer { text: self.text.into(), tokens: self.to_tokens() }
+    }
+
+    /// Attaches `tokens` to the source text they were lexed from without lexing it again.
+    ///
+    /// Returns `None` if the tokens do not cover `text`, e.g. because it has changed since.
+    pub fn from_tokens(text: &'source str, tokens: LexedTokens) -> Option<LexedStr<'source>> {
+        let LexedTokens { profile, kind, start } = tokens.covering(text)?;
+        Some(LexedStr { text, profile, kind: Cow::Owned(kind), start: Cow::Owned(start) })
+    }
+}
+
+impl From<LexedStr<'_>> for LexedBuffer {
+    fn from(lexed: LexedStr<'_>) -> LexedBuffer {
+        LexedBuffer { text: lexed.text.into(), tokens: lexed.into_tokens() }
+    }
+}
+
+/// A [`LexedStr`] which owns its source text, so it can be kept in a cache or sent to another
+/// thread independently of the source.
+///
+/// [`LexedBuffer::as_lexed`] borrows it as a [`LexedStr`] without copying, the accessors and
+/// iterators are available on the buffer directly as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexedBuffer {
+    text: String,
+    tokens: LexedTokens,
+}
+
+impl LexedBuffer {
+    /// Lexes `text` into syntax tokens using the default [`LexerProfile`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` is larger than `u32::MAX` bytes.
+    pub fn new(text: impl Into<String>) -> LexedBuffer {
+        LexedBuffer::with_profile(text, LexerProfile::default())
+    }
+
+    /// Lexes `text` into syntax tokens of the given dialect.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` is larger than `u32::MAX` bytes.
+    pub fn with_profile(text: impl Into<String>, profile: LexerProfile) -> LexedBuffer {
+        let text = text.into();
+        let tokens = LexedStr::with_profile(&text, profile).into_tokens();
+        LexedBuffer { text, tokens }
+    }
+
+    /// Attaches `tokens` to the source text they were lexed from, see [`LexedStr::from_tokens`].
+    pub fn from_tokens(text: String, tokens: LexedTokens) -> Option<LexedBuffer> {
+        let tokens = tokens.covering(&text)?;
+        Some(LexedBuffer { text, tokens })
+    }
+
+    /// Borrows the buffer as a [`LexedStr`] without copying the text or the tokens.
+    pub fn as_lexed(&self) -> LexedStr<'_> {
+        let LexedTokens { profile, kind, start } = &self.tokens;
+        LexedStr {
+            text: &self.text,
+            profile: *profile,
+            kind: Cow::Borrowed(kind),
+            start: Cow::Borrowed(start),
+        }
+    }
+
+    /// Splits the buffer into the source text and the tokens.
+    pub fn into_parts(self) -> (String, LexedTokens) {
+        (self.text, self.tokens)
+    }
+
+    /// The dialect the source text was lexed with.
+    pub fn profile(&self) -> LexerProfile {
+        self.tokens.profile
+    }
+
+    /// The complete source text.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Number of tokens, excluding the trailing [`SyntaxKind::Eof`].
+    pub fn len(&self) -> usize {
+        self.tokens.kind.len() - 1
+    }
+
+    /// Returns `true` if the source produced no tokens.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Kind of the token at `idx`.
+    pub fn kind(&self, idx: usize) -> SyntaxKind {
+        self.as_lexed().kind(idx)
+    }
+
+    /// Source text of the token at `idx`.
+    pub fn text(&self, idx: usize) -> &str {
+        self.as_lexed().text(idx)
+    }
+
+    /// Byte range of the token at `idx` in the source text.
+    pub fn text_range(&self, idx: usize) -> Range<usize> {
+        self.as_lexed().text_range(idx)
+    }
+
+    /// Byte offset at which the token at `idx` starts.
+    pub fn text_start(&self, idx: usize) -> usize {
+        self.as_lexed().text_start(idx)
+    }
+
+    /// Regenerates the source text from the tokens, see [`LexedStr::reconstruct`].
+    pub fn reconstruct(&self) -> String {
+        self.as_lexed().reconstruct()
+    }
+
+    /// Finds the token covering the byte `offset`, see [`LexedStr::token_at_offset`].
+    pub fn token_at_offset(&self, offset: usize) -> TokenAtOffset<usize> {
+        self.as_lexed().token_at_offset(offset)
+    }
+
+    /// Iterator over the kinds of all tokens, excluding the trailing [`SyntaxKind::Eof`].
+    pub fn syntax_tokens(&self) -> impl Iterator<Item = SyntaxKind> + '_ {
+        self.tokens.kind[..self.len()].iter().copied()
+    }
+
+    /// Iterator over all tokens yielding their kind, text and byte range together.
+    pub fn spanned_tokens(&self) -> impl Iterator<Item = SpannedToken<'_>> + '_ {
+        (0..self.len()).map(|idx| SpannedToken {
+            kind: self.tokens.kind[idx],
+            text: self.text(idx),
+            range: self.text_range(idx),
+        })
+    }
+}
+
+impl<'buffer> From<&'buffer LexedBuffer> for LexedStr<'buffer> {
+    fn from(buffer: &'buffer LexedBuffer) -> LexedStr<'buffer> {
+        buffer.as_lexed()
+    }
+}
+
+/// The tokens of a [`LexedStr`] without the source text, e.g. to keep them in a cache.
+///
+/// [`LexedTokens::to_bytes`] encodes them in a compact binary format: a version byte, the
+/// profile, the token count and then every kind as `u16` followed by every offset as `u32`, all
+/// little endian.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexedTokens {
+    profile: LexerProfile,
+    kind: Vec<SyntaxKind>,
+    start: Vec<u32>,
+}
+
+impl LexedTokens {
+    /// Version of the binary format, bumped whenever the format or the lexer output changes.
+    const VERSION: u8 = 3;
+
+    /// The dialect the tokens were lexed with.
+    pub fn profile(&self) -> LexerProfile {
+        self.profile
+    }
+
+    /// Returns the tokens if they cover `text`, i.e. were lexed from it.
+    fn covering(self, text: &str) -> Option<LexedTokens> {
+        let LexedTokens { kind, start, .. } = &self;
+        let valid = kind.len() == start.len()
+            && kind.last() == Some(&SyntaxKind::Eof)
+            && start.first() == Some(&0)
+            && start.last().map(|&end| end as usize) == Some(text.len())
+            && start.windows(2).all(|pair| pair[0] <= pair[1])
+            && start.iter().all(|&start| text.is_char_boundary(start as usize));
+        valid.then_some(self)
+    }
+
+    /// Encodes the tokens, see [`LexedTokens::from_bytes`] for the inverse.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(6 + self.kind.len() * 6);
+        bytes.push(LexedTokens::VERSION);
+        bytes.push(match self.profile {
+            LexerProfile::Latex => 0,
+            LexerProfile::NeoTex => 1,
+            LexerProfile::ExpL3 => 2,
+        });
+        bytes.extend((self.kind.len() as u32).to_le_bytes());
+        bytes.extend(self.kind.iter().flat_map(|&kind| (kind as u16).to_le_bytes()));
+        bytes.extend(self.start.iter().flat_map(|start| start.to_le_bytes()));
+        bytes
+    }
+
+    /// Decodes tokens encoded by [`LexedTokens::to_bytes`]. Returns `None` for malformed input
+    /// or input of a different format version.
+    pub fn from_bytes(bytes: &[u8]) -> Option<LexedTokens> {
+        let [version, profile, count @ ..] = bytes else {
+            return None;
+        };
+        let profile = match (*version, profile) {
+            (LexedTokens::VERSION, 0) => LexerProfile::Latex,
+            (LexedTokens::VERSION, 1) => LexerProfile::NeoTex,
+            (LexedTokens::VERSION, 2) => LexerProfile::ExpL3,
+            _ => return None,
+        };
+        let (count, rest) = count.split_at(count.len().min(4));
+        let count = u32::from_le_bytes(count.try_into().ok()?) as usize;
+        if rest.len() != count.checked_mul(6)? {
+            return None;
+        }
+
+        let (kind, start) = rest.split_at(count * 2);
+        let kind = kind
+            .chunks_exact(2)
+            .map(|raw| SyntaxKind::from_raw(u16::from_le_bytes([raw[0], raw[1]])))
+            .collect::<Option<_>>()?;
+        let start = start
+            .chunks_exact(4)
+            .map(|raw| u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]))
+            .collect();
+        Some(LexedTokens { profile, kind, start })
+    }
+}
+
+/// Result of [`LexedStr::token_at_offset`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenAtOffset<T> {
+    /// The offset lies outside of the text or the text has no tokens
+    None,
+    /// The offset lies inside of a token
+    Single(T),
+    /// The offset lies between two tokens, the left one is first
+    Between(T, T),
+}
+
+impl<T> TokenAtOffset<T> {
+    /// The token covering the offset, preferring the left one at a boundary.
+    pub fn left_biased(self) -> Option<T> {
+        match self {
+            TokenAtOffset::None => None,
+            TokenAtOffset::Single(token) | TokenAtOffset::Between(token, _) => Some(token),
+        }
+    }
+
+    /// The token covering the offset, preferring the right one at a boundary.
+    pub fn right_biased(self) -> Option<T> {
+        match self {
+            TokenAtOffset::None => None,
+            TokenAtOffset::Single(token) | TokenAtOffset::Between(_, token) => Some(token),
+        }
+    }
+
+    /// Applies `f` to every token.
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> TokenAtOffset<U> {
+        match self {
+            TokenAtOffset::None => TokenAtOffset::None,
+            TokenAtOffset::Single(token) => TokenAtOffset::Single(f(token)),
+            TokenAtOffset::Between(left, right) => TokenAtOffset::Between(f(left), f(right)),
+        }
+    }
+}
+
+/// A single token of a [`LexedStr`] with its kind, source text and byte range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpannedToken<'source> {
+    /// Kind of the token
+    pub kind: SyntaxKind,
+    /// Source text covered by the token
+    pub text: &'source str,
+    /// Byte range of the token in the source text
+    pub range: Range<usize>,
+}
+
+impl<'source> SpannedToken<'source> {
+    /// Returns `true` for whitespace, newlines and comments, the kinds of [`Trivia::LEX`].
+    pub fn is_trivia(&self) -> bool {
+        self.kind.is_trivia(Trivia::LEX)
+    }
+
+    /// Length of the token text in bytes.
+    pub fn text_len(&self) -> usize {
+        self.range.len()
+    }
+}
+
+fn command_kind(name: &str, profile: LexerProfile) -> SyntaxKind {
+    use SyntaxKind::*;
+
+    match name {
+        "fn" if profile.extensions() => FunctionIdent,
+        "mod" if profile.extensions() => Module,
+        "pub" if profile.extensions() => Pub,
+        "let" if profile.extensions() => Let,
+        "use" if profile.extensions() => Use,
+        "def" => Def,
+        "newcommand" | "renewcommand" | "providecommand" => NewCommand,
+        "newenvironment" | "renewenvironment" => NewEnv,
+        "usepackage" | "RequirePackage" => UsePackage,
+        "begin" => BeginGroup,
+        "end" => EndGroup,
+        "documentclass" => DocClass,
+        "input" | "include" | "import" => FileInput,
+        _ => Command,
+    }
+}
+
+/// End of the name of a command starting at `start` when `@` is a letter.
+fn at_command_end(text: &str, start: usize, profile: LexerProfile) -> usize {
+    let mut chars = text[start..].char_indices();
+    match chars.next() {
+        Some((_, c)) if c == '@' || profile.is_command_start(c) => {}
+        _ => return start,
+    }
+    let end = chars.find(|&(_, c)| c != '@' && !profile.is_command_continue(c));
+    start + end.map_or(text.len() - start, |(offset, _)| offset)
+}
+
+impl From<LexToken> for SyntaxKind {
+    fn from(token: LexToken) -> SyntaxKind {
+        use SyntaxKind::*;
+
+        match token {
+            LexToken::CommandIdent | LexToken::Command => Command,
+            LexToken::VariableIdent | LexToken::Variable => Variable,
+            LexToken::Whitespace => Whitespace,
+            LexToken::Newline => Newline,
+            LexToken::Break => Break,
+            LexToken::Comment => Comment,
+            LexToken::Integer => Number,
+            LexToken::Float => Float,
+            LexToken::Unit | LexToken::AWord => AWord,
+            LexToken::UWord => Word,
+            LexToken::OpenBrace => OpenBrace,
+            LexToken::CloseBrace => CloseBrace,
+            LexToken::OpenBracket => OpenBracket,
+            LexToken::CloseBracket => CloseBracket,
+            LexToken::OpenParen => OpenParen,
+            LexToken::CloseParen => CloseParen,
+            LexToken::Star => Star,
+            LexToken::NumSign => NumSign,
+            LexToken::Carret => Carret,
+            LexToken::Less => Less,
+            LexToken::Greater => Greater,
+            LexToken::Underscore => Underscore,
+            LexToken::SingleApostrophe => SingleApostrophe,
+            LexToken::DoubleApostrophe => DoubleApostrophe,
+            LexToken::Slash => Slash,
+            LexToken::Tilde => Tilde,
+            LexToken::Comma => Comma,
+            LexToken::Semicolon => Semicolon,
+            LexToken::Ampersand => Ampersand,
+            LexToken::Equal => Equal,
+            LexToken::Pipe => Pipe,
+            LexToken::Colon => Colon,
+            LexToken::Dollar | LexToken::MathDisplay => MathDelimiter,
+            LexToken::Minus => Minus,
+            LexToken::Plus => Plus,
+            LexToken::Period => Dot,
+            LexToken::At => At,
+            LexToken::Question => Question,
+            LexToken::Bang => Bang,
+            LexToken::PathSeparator => PathSeperator,
+            LexToken::RightArrow => RightArrow,
+            LexToken::LeftArrow => LeftArrow,
+            LexToken::LessEqual => LessEq,
+            LexToken::GreaterEqual => GreaterEq,
+            LexToken::NotEqual => NotEq,
+            LexToken::DoubleEqual => Comparison,
+            LexToken::PlusEqual => PlusEq,
+            LexToken::MinusEqual => MinusEq,
+            LexToken::MulEqual => StarEq,
+            LexToken::DivEqual => SlashEq,
+            LexToken::Symbol => Symbol,
+            LexToken::UnicodeEscape => UnicodeEscape,
+            LexToken::MacroParameter => ComplexMacroExpansionToken,
+            LexToken::Error => Error,
+            LexToken::Eof => Eof,
+        }
+    }
+}
@@ -1,11 +1,21 @@
+mod compact;
 mod definition;
+mod emitter;
 mod infra;
+mod lexed_str;
+mod profile;
+mod source_kind;
 mod tables;
 mod token;
 
+pub use compact::CompactLexedStr;
 pub(crate) use definition::TokenizerItem;
+pub use emitter::TokenEmitter;
 pub(crate) use infra::LexerDelegate;
 pub(crate) use infra::Tokenizer;
+pub use lexed_str::{LexedBuffer, LexedStr, LexedTokens, SpannedToken, TokenAtOffset};
+pub use profile::LexerProfile;
+pub use source_kind::SourceKind;
 pub(crate) use token::LexToken;
 
 #[cfg(test)]
@@ -0,0 +1,41 @@
+use unicode_ident::{is_xid_continue, is_xid_start};
+
+/// Dialect the lexer recognizes.
+///
+/// NeoTeX extends LaTeX with additional syntax like `\@variables`, `::` paths, `\u{..}` escapes,
+/// named macro parameters and keywords such as `\fn`. These extensions change the meaning of
+/// valid LaTeX, e.g. `\u` is the breve accent in LaTeX, so they are only enabled for
+/// [`LexerProfile::NeoTex`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LexerProfile {
+    /// Plain LaTeX: command names consist of letters only
+    Latex,
+    /// LaTeX with all NeoTeX extensions
+    #[default]
+    NeoTex,
+    /// LaTeX3 programming layer: `_` and `:` are letters in command names
+    ExpL3,
+}
+
+impl LexerProfile {
+    /// Returns `true` if the NeoTeX syntax extensions are enabled.
+    pub fn extensions(&self) -> bool {
+        *self == LexerProfile::NeoTex
+    }
+
+    pub(crate) fn is_command_start(&self, c: char) -> bool {
+        match self {
+            LexerProfile::Latex => c.is_alphabetic(),
+            LexerProfile::NeoTex => is_xid_start(c),
+            LexerProfile::ExpL3 => c.is_alphabetic() || c == '_' || c == ':',
+        }
+    }
+
+    pub(crate) fn is_command_continue(&self, c: char) -> bool {
+        match self {
+            LexerProfile::Latex => c.is_alphabetic(),
+            LexerProfile::NeoTex => is_xid_continue(c),
+            LexerProfile::ExpL3 => c.is_alphabetic() || c == '_' || c == ':',
+        }
+    }
+}
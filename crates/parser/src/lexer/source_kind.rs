@@ -0,0 +1,55 @@
+/// The kind of file a source text is, which changes how it is lexed.
+///
+/// In package and class files `@` is a letter of command names from the start, as if the file
+/// began with `\makeatletter`, so internals like `\foo@bar` are single commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SourceKind {
+    /// A document or a part of one, e.g. a chapter
+    #[default]
+    Document,
+    /// A package, e.g. a `.sty` file
+    Package,
+    /// A document class, e.g. a `.cls` file
+    Class,
+}
+
+impl SourceKind {
+    /// The kind declared by a `\ProvidesPackage` or `\ProvidesClass` at the start of a line of
+    /// `text`, [`SourceKind::Document`] without one.
+    ///
+    /// ```
+    /// use parser::SourceKind;
+    ///
+    /// let text = "\\NeedsTeXFormat{LaTeX2e}\n\\ProvidesPackage{mymacros}[2024/01/01]";
+    /// assert_eq!(SourceKind::detect(text), SourceKind::Package);
+    /// assert_eq!(SourceKind::detect("% \\ProvidesClass{x}"), SourceKind::Document);
+    /// ```
+    pub fn detect(text: &str) -> SourceKind {
+        if !text.contains("\\Provides") {
+            return SourceKind::Document;
+        }
+        for line in text.lines().map(str::trim_start) {
+            if ["\\ProvidesPackage", "\\ProvidesExplPackage"].iter().any(|d| line.starts_with(d)) {
+                return SourceKind::Package;
+            }
+            if ["\\ProvidesClass", "\\ProvidesExplClass"].iter().any(|d| line.starts_with(d)) {
+                return SourceKind::Class;
+            }
+        }
+        SourceKind::Document
+    }
+
+    /// The kind of files with the extension `ext`, `None` if the extension does not tell.
+    pub fn from_extension(ext: &str) -> Option<SourceKind> {
+        match ext {
+            "sty" => Some(SourceKind::Package),
+            "cls" => Some(SourceKind::Class),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if `@` is a letter of command names from the start of the source.
+    pub fn at_letter(&self) -> bool {
+        *self != SourceKind::Document
+    }
+}
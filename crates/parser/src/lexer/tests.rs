@@ -1,6 +1,14 @@
-use super::{definition::LexerState, infra::Tokenizer};
+use super::{
+    CompactLexedStr, LexedBuffer, LexedStr, LexedTokens, LexerProfile, SourceKind, TokenAtOffset,
+    TokenEmitter,
+};
+use crate::SyntaxKind::{self, *};
+use alloc::{format, string::String, string::ToString, vec, vec::Vec};
 
+// printing and the subscriber need `std`, the other tests run without it as well
+#[cfg(feature = "std")]
 fn check(input: &str) -> Result<(), ()> {
+    use super::{definition::LexerState, infra::Tokenizer};
     use tracing_subscriber::FmtSubscriber;
     let subscriber = FmtSubscriber::builder().with_max_level(tracing::Level::TRACE).finish();
 
@@ -13,13 +21,343 @@ fn check(input: &str) -> Result<(), ()> {
         println!("{:?} - '{}'", token, &input[token.span.span()]);
     }
 
-    Err(())
+    Ok(())
 }
 
+fn kinds(input: &str) -> Vec<(SyntaxKind, &str)> {
+    LexedStr::new(input).spanned_tokens().map(|token| (token.kind, token.text)).collect()
+}
+
+#[cfg(feature = "std")]
 #[test]
 fn simple_lex() {
-    let input = include_str!(
-        "/Users/leon/Documents/02_university/05_semester/numerical_calculus/notes/main.tex"
-    );
+    let input = include_str!("../../test.ntex");
     check(input).unwrap();
 }
+
+#[test]
+fn spanned_tokens_cover_source() {
+    let input = include_str!("../../test.ntex");
+    let lexed = LexedStr::new(input);
+
+    let mut end = 0;
+    for token in lexed.spanned_tokens() {
+        assert_eq!(token.range.start, end);
+        assert_eq!(token.text, &input[token.range.clone()]);
+        assert_eq!(token.text_len(), token.text.len());
+        end = token.range.end;
+    }
+    assert_eq!(end, input.len());
+}
+
+#[test]
+fn commands_are_merged() {
+    assert_eq!(
+        kinds("\\newcommand{\\R} \\@var"),
+        vec![
+            (NewCommand, "\\newcommand"),
+            (OpenBrace, "{"),
+            (Command, "\\R"),
+            (CloseBrace, "}"),
+            (Whitespace, " "),
+            (Variable, "\\@var"),
+        ]
+    );
+}
+
+#[test]
+fn trivia_tokens() {
+    let lexed = LexedStr::new("a % comment\nb");
+    let trivia: Vec<_> =
+        lexed.spanned_tokens().filter(|token| token.is_trivia()).map(|token| token.kind).collect();
+    assert_eq!(trivia, vec![Whitespace, Comment, Newline]);
+}
+
+#[test]
+fn compact_token_storage() {
+    // a token is stored as a `SyntaxKind` plus a `u32` start offset
+    assert_eq!(core::mem::size_of::<SyntaxKind>(), 2);
+
+    let lexed = LexedStr::new("a\u{00e9}b c");
+    assert_eq!(lexed.text_start(lexed.len()), "a\u{00e9}b c".len());
+
+    // the encoding copies the storage: a header, then 6 bytes for every token including `Eof`
+    let bytes = lexed.to_tokens().to_bytes();
+    assert_eq!(bytes.len(), 6 + (lexed.len() + 1) * 6);
+}
+
+#[test]
+fn delimiter_classification() {
+    let lexed = LexedStr::new("{[(x)]}");
+    let (open, close): (Vec<_>, Vec<_>) = lexed
+        .syntax_tokens()
+        .filter(|kind| kind.matching_delimiter().is_some())
+        .partition(|kind| kind.is_open_delimiter());
+
+    assert!(close.iter().all(|kind| kind.is_closing_delimiter()));
+    let matched: Vec<_> = close.iter().rev().filter_map(|kind| kind.matching_delimiter()).collect();
+    assert_eq!(open, matched);
+    assert!(NewCommand.is_definition_keyword() && !Command.is_definition_keyword());
+    assert!(LessEq.is_math_operator() && !Comma.is_math_operator());
+}
+
+fn profile_kinds(input: &str, profile: LexerProfile) -> Vec<(SyntaxKind, &str)> {
+    let lexed = LexedStr::with_profile(input, profile);
+    lexed.spanned_tokens().map(|token| (token.kind, token.text)).collect()
+}
+
+#[test]
+fn latex_profile_disables_extensions() {
+    let input = "\\@ifnextchar\\u{o}\\fn a::b #1 #x";
+    let kinds = profile_kinds(input, LexerProfile::Latex);
+
+    assert!(kinds.iter().all(|(kind, _)| !matches!(
+        kind,
+        Variable | UnicodeEscape | FunctionIdent | PathSeperator | ComplexMacroExpansionToken
+    )));
+    assert_eq!(&kinds[..3], &[(Command, "\\@"), (AWord, "ifnextchar"), (Command, "\\u")]);
+    assert!(kinds.contains(&(SimpleMacroExpansionToken, "#1")));
+    assert!(kinds.contains(&(NumSign, "#")));
+}
+
+#[test]
+fn neotex_profile_enables_extensions() {
+    let kinds = profile_kinds("\\@year \\fn \\a::b", LexerProfile::NeoTex);
+    assert_eq!(kinds[0], (Variable, "\\@year"));
+    assert_eq!(kinds[2], (FunctionIdent, "\\fn"));
+    assert!(kinds.contains(&(PathSeperator, "::")));
+}
+
+#[test]
+fn expl3_profile_command_names() {
+    let kinds = profile_kinds("\\cs_new:Npn \\__mod_aux:n", LexerProfile::ExpL3);
+    assert_eq!(
+        kinds,
+        vec![(Command, "\\cs_new:Npn"), (Whitespace, " "), (Command, "\\__mod_aux:n")]
+    );
+}
+
+#[test]
+fn makeatletter_regions() {
+    let input = "\\@year\\makeatletter\\@ifnextchar*\\foo@bar \\@@x* \\@\\makeatother\\@year";
+    for profile in [LexerProfile::Latex, LexerProfile::NeoTex] {
+        let kinds = profile_kinds(input, profile);
+        let start = kinds.iter().position(|token| *token == (Command, "\\makeatletter")).unwrap();
+        let commands: Vec<_> = kinds[start..].iter().filter(|(kind, _)| *kind == Command).collect();
+        assert_eq!(
+            &commands[1..6],
+            [
+                &(Command, "\\@ifnextchar"),
+                &(Command, "\\foo@bar"),
+                &(Command, "\\@@x"),
+                &(Command, "\\@"),
+                &(Command, "\\makeatother")
+            ]
+        );
+        assert_eq!(LexedStr::with_profile(input, profile).reconstruct(), input);
+    }
+
+    // outside of the region `\@` keeps its meaning
+    let kinds = profile_kinds(input, LexerProfile::NeoTex);
+    assert_eq!((kinds[0], kinds[kinds.len() - 1]), ((Variable, "\\@year"), (Variable, "\\@year")));
+    let kinds = profile_kinds(input, LexerProfile::Latex);
+    assert_eq!(kinds[kinds.len() - 2..], [(Command, "\\@"), (AWord, "year")]);
+}
+
+#[test]
+fn package_files() {
+    let input = "\\ProvidesPackage{mymacros}\n\\RequirePackage{xcolor}\\newcommand\\foo@bar{}";
+    let kinds = profile_kinds(input, LexerProfile::Latex);
+    assert!(kinds.contains(&(Command, "\\foo@bar")));
+    assert!(kinds.contains(&(UsePackage, "\\RequirePackage")));
+
+    // the kind can be given instead of being detected
+    let input = "\\def\\foo@bar{}";
+    let lexed = LexedStr::with_source_kind(input, LexerProfile::Latex, SourceKind::Class);
+    assert_eq!(lexed.spanned_tokens().nth(1).map(|token| token.text), Some("\\foo@bar"));
+    let lexed = LexedStr::with_profile(input, LexerProfile::Latex);
+    assert_eq!(lexed.spanned_tokens().nth(1).map(|token| token.text), Some("\\foo"));
+    assert_eq!(SourceKind::from_extension("cls"), Some(SourceKind::Class));
+}
+
+#[test]
+fn reconstruct_is_lossless() {
+    let sources = [
+        include_str!("../../test.ntex"),
+        "",
+        "\\\n% comment\r\n\\a::b \\@var::x #name ## 1.5e3\t\u{00e4}",
+        "\\",
+        "\\begin{document}%%\n\\end",
+    ];
+    for source in sources {
+        for profile in [LexerProfile::Latex, LexerProfile::NeoTex, LexerProfile::ExpL3] {
+            assert_eq!(LexedStr::with_profile(source, profile).reconstruct(), source);
+        }
+    }
+}
+
+#[test]
+fn token_emitter() {
+    let lexed = LexedStr::new("\\newcommand{\\old}{x} \\old \\older");
+    assert_eq!(TokenEmitter::new(&lexed).to_string(), lexed.as_str());
+
+    let mut emitter = TokenEmitter::new(&lexed);
+    for (idx, token) in lexed.spanned_tokens().enumerate() {
+        if token.kind == Command && token.text == "\\old" {
+            emitter.replace(idx, "\\new");
+        }
+    }
+    emitter.insert_before(0, "% renamed\n").insert_before(lexed.len(), "\n");
+    emitter.remove(lexed.len() - 1);
+
+    assert_eq!(emitter.to_string(), "% renamed\n\\newcommand{\\new}{x} \\new \n");
+}
+
+#[test]
+fn char_classes() {
+    use crate::chars::{is_newline, is_whitespace};
+
+    for c in ['\t', ' ', '\u{00A0}', '\u{1680}', '\u{202F}', '\u{3000}', '\u{200E}', '\u{200F}'] {
+        assert!(is_whitespace(c) && !is_newline(c), "{c:?}");
+    }
+    for c in ['\n', '\u{000B}', '\u{000C}', '\r', '\u{0085}', '\u{2028}', '\u{2029}'] {
+        assert!(is_newline(c) && !is_whitespace(c), "{c:?}");
+    }
+    for c in ['a', '\0', '\u{00FF}', '\u{0100}', '\u{2000}', 'ä'] {
+        assert!(!is_whitespace(c) && !is_newline(c), "{c:?}");
+    }
+}
+
+#[test]
+fn compact_trivia() {
+    let sources = [include_str!("../../test.ntex"), "", "  \n", "a b\n\nc  % comment\n\\d "];
+    for source in sources {
+        let lexed = LexedStr::new(source);
+        let compact = CompactLexedStr::new(&lexed);
+
+        assert!(compact.spanned_tokens().eq(lexed.spanned_tokens()), "{source:?}");
+        assert_eq!(compact.reconstruct(), source);
+    }
+
+    let prose = "Lorem ipsum dolor sit amet,\nconsectetur adipiscing elit.\n\n".repeat(100);
+    let lexed = LexedStr::new(&prose);
+    let compact = CompactLexedStr::new(&lexed);
+
+    // 8 bytes per compact token instead of 6
+    assert!(compact.len() * 8 < lexed.len() * 6);
+    assert_eq!(compact.trivia_patterns(), 4);
+    assert_eq!((compact.kind(0), compact.text(0)), (AWord, "Lorem"));
+}
+
+#[test]
+fn owned_tokens() {
+    for source in [include_str!("../../test.ntex"), "", "\\@year \\u{1F600} ä"] {
+        let lexed = LexedStr::with_profile(source, LexerProfile::ExpL3);
+        let bytes = lexed.to_tokens().to_bytes();
+        let tokens = LexedTokens::from_bytes(&bytes).unwrap();
+        assert_eq!(tokens.profile(), LexerProfile::ExpL3);
+
+        let restored = LexedStr::from_tokens(source, tokens).unwrap();
+        assert!(restored.spanned_tokens().eq(lexed.spanned_tokens()), "{source:?}");
+        assert!(LexedTokens::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+    }
+
+    let tokens = LexedStr::new("a b").to_tokens();
+    assert!(LexedStr::from_tokens("a bc", tokens.clone()).is_none());
+    // the offset of `b` is not a char boundary
+    assert!(LexedStr::from_tokens("ä b", tokens).is_none());
+    assert_eq!(SyntaxKind::from_raw(Error as u16), Some(Error));
+    assert_eq!(SyntaxKind::from_raw(Error as u16 + 1), None);
+}
+
+#[test]
+fn owned_buffer() {
+    let source = include_str!("../../test.ntex");
+    let lexed = LexedStr::with_profile(source, LexerProfile::NeoTex);
+    let buffer = LexedBuffer::from(LexedStr::with_profile(source, LexerProfile::NeoTex));
+    // the buffer does not borrow the source and can be sent to another thread
+    #[cfg(feature = "std")]
+    let buffer = std::thread::spawn(move || buffer).join().unwrap();
+
+    assert_eq!(buffer.as_str(), source);
+    assert_eq!((buffer.len(), buffer.profile()), (lexed.len(), LexerProfile::NeoTex));
+    assert!(buffer.spanned_tokens().eq(lexed.spanned_tokens()));
+    assert!(buffer.syntax_tokens().eq(lexed.syntax_tokens()));
+    assert!(buffer.as_lexed().spanned_tokens().eq(lexed.spanned_tokens()));
+    assert_eq!(buffer.token_at_offset(10), lexed.token_at_offset(10));
+    assert_eq!(lexed.to_buffer(), buffer);
+    assert_eq!(buffer.reconstruct(), source);
+
+    let (text, tokens) = buffer.into_parts();
+    assert!(LexedBuffer::from_tokens("a".into(), tokens.clone()).is_none());
+    let buffer = LexedBuffer::from_tokens(text, tokens).unwrap();
+    assert!(LexedBuffer::new("").is_empty());
+    assert_eq!(LexedBuffer::new("\\x").kind(0), LexedStr::new("\\x").kind(0));
+    assert_eq!(buffer.text(0), lexed.text(0));
+}
+
+#[test]
+fn long_tokens_are_not_truncated() {
+    let word = "a".repeat(5000);
+    let source = format!("{word} \\{word}");
+    assert_eq!(
+        kinds(&source),
+        [(AWord, word.as_str()), (Whitespace, " "), (Command, &source[5001..])]
+    );
+}
+
+#[test]
+fn arbitrary_input_never_panics() {
+    // xorshift, so failures are reproducible
+    let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+    let alphabet: Vec<char> =
+        "\\@#%{}[]()$:_-.=<>!*+/ \t\r\n0123456789eamu\u{00A0}\u{2029}äß€🦀".chars().collect();
+
+    for _ in 0..2000 {
+        let len = (next() % 40) as usize;
+        let source: String =
+            (0..len).map(|_| alphabet[(next() % alphabet.len() as u64) as usize]).collect();
+
+        for profile in [LexerProfile::Latex, LexerProfile::NeoTex, LexerProfile::ExpL3] {
+            let lexed = LexedStr::with_profile(&source, profile);
+            assert_eq!(lexed.reconstruct(), source);
+            assert!(lexed.spanned_tokens().all(|token| !token.text.is_empty()), "{source:?}");
+        }
+    }
+}
+
+#[test]
+fn token_at_offset() {
+    let lexed = LexedStr::new("\\foo{ä}");
+    // \foo 0..4, { 4..5, ä 5..7, } 7..8
+    assert_eq!(lexed.token_at_offset(0), TokenAtOffset::Single(0));
+    assert_eq!(lexed.token_at_offset(2), TokenAtOffset::Single(0));
+    assert_eq!(lexed.token_at_offset(4), TokenAtOffset::Between(0, 1));
+    assert_eq!(lexed.token_at_offset(6), TokenAtOffset::Single(2));
+    assert_eq!(lexed.token_at_offset(8), TokenAtOffset::Single(3));
+    assert_eq!(lexed.token_at_offset(9), TokenAtOffset::None);
+    assert_eq!(LexedStr::new("").token_at_offset(0), TokenAtOffset::None);
+
+    let at = lexed.token_at_offset(5);
+    assert_eq!((at.left_biased(), at.right_biased()), (Some(1), Some(2)));
+    assert_eq!(at.map(|idx| lexed.kind(idx)), TokenAtOffset::Between(OpenBrace, Word));
+}
+
+#[test]
+fn trivia_ranges() {
+    // \a 0, ' ' 1, % x\n 2, \n 3, ' ' 4, \b 5, ' ' 6
+    let lexed = LexedStr::new("\\a % x\n\n \\b ");
+    assert_eq!((lexed.next_non_trivia(1), lexed.prev_non_trivia(5)), (Some(5), Some(0)));
+    assert_eq!((lexed.next_non_trivia(6), lexed.prev_non_trivia(0)), (None, None));
+
+    assert_eq!(lexed.trailing_trivia(0), 1..3);
+    assert_eq!(lexed.leading_trivia(5), 3..5);
+    assert_eq!(lexed.trailing_trivia(5), 6..7);
+    assert_eq!(lexed.leading_trivia(0), 0..0);
+}
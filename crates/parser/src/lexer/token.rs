@@ -1,4 +1,4 @@
-use super::infra::Tombstone;
+use super::infra::{Recover, Tombstone};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
@@ -126,6 +126,9 @@ pub(crate) enum LexToken {
     /// A hash ('#') character followed by Unicode xid_start and xid_continue characters except the underscore.
     MacroParameter,
 
+    /// Input the lexer could not make progress on.
+    Error,
+
     /// A token representing the end of the input stream.
     Eof,
 }
@@ -135,3 +138,9 @@ impl Tombstone for LexToken {
         Self::Eof
     }
 }
+
+impl Recover for LexToken {
+    fn error() -> LexToken {
+        Self::Error
+    }
+}
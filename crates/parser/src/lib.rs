@@ -5,6 +5,7 @@
     html_favicon_url = "https://gist.githubusercontent.com/leonfuss/fa44f11267796b352edaa121675cd6f2/raw/ae237aa701a5d087c89af313299858f5e1c1144f/favicon.svg"
 )]
 #![warn(missing_docs)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
 //! **Part of [NeoTeX](../neotex/index.html)**
 /// The `preparse` module serves as the initial step in the parsing pipeline for LaTeX source code.
@@ -30,8 +31,46 @@
 /// LaTeX Syntax Tokens
 pub mod syntax;
 
+pub mod chars;
+pub mod registry;
+
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "std")]
+pub mod anchors;
+#[cfg(feature = "std")]
+pub mod ast;
+#[cfg(feature = "std")]
+pub mod charcode;
+#[cfg(feature = "std")]
+pub mod diagnostics;
+#[cfg(feature = "std")]
+pub mod dimension;
+#[cfg(feature = "std")]
+pub mod expansion;
+#[cfg(feature = "std")]
+pub mod files;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(feature = "std")]
+pub mod index;
+#[cfg(feature = "std")]
+pub mod outline;
+#[cfg(feature = "std")]
+pub mod semantic;
+
 mod lexer;
+#[cfg(feature = "std")]
 mod parser;
+mod source;
+#[cfg(feature = "std")]
 mod utils;
 
-pub use syntax::SyntaxKind;
+extern crate alloc;
+
+pub use lexer::{
+    CompactLexedStr, LexedBuffer, LexedStr, LexedTokens, LexerProfile, SourceKind, SpannedToken,
+    TokenAtOffset, TokenEmitter,
+};
+pub use source::SourceText;
+pub use syntax::{SyntaxKind, SyntaxKindSet, Trivia};
@@ -0,0 +1,299 @@
+//! Outline of the sectioning commands of a document.
+//!
+//! [`Outline`] collects every `\part`, `\chapter`, `\section` … `\subparagraph` together with its
+//! title and nests them by level. A section extends up to the next heading of the same or a higher
+//! level, which is what editors show as the range of a document symbol. Beamer `frame`
+//! environments are part of the outline as well, nested in the innermost section. Headings in the
+//! body of a definition only appear once the definition is used and are skipped.
+
+use std::ops::Range;
+
+use crate::{
+    ast::{environment_name, overlay_spec, Document, NodeKind},
+    LexedStr, SyntaxKind,
+};
+
+/// The level of a sectioning command, from the outermost `\part` to the innermost
+/// `\subparagraph`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SectionLevel {
+    /// `\part`
+    Part,
+    /// `\chapter`
+    Chapter,
+    /// `\section`
+    Section,
+    /// `\subsection`
+    Subsection,
+    /// `\subsubsection`
+    Subsubsection,
+    /// `\paragraph`
+    Paragraph,
+    /// `\subparagraph`
+    Subparagraph,
+    /// A beamer `frame` environment
+    Frame,
+}
+
+impl SectionLevel {
+    /// The level of the sectioning command `name`, including the backslash.
+    pub fn from_command(name: &str) -> Option<SectionLevel> {
+        let level = match name {
+            "\\part" => SectionLevel::Part,
+            "\\chapter" => SectionLevel::Chapter,
+            "\\section" => SectionLevel::Section,
+            "\\subsection" => SectionLevel::Subsection,
+            "\\subsubsection" => SectionLevel::Subsubsection,
+            "\\paragraph" => SectionLevel::Paragraph,
+            "\\subparagraph" => SectionLevel::Subparagraph,
+            _ => return None,
+        };
+        Some(level)
+    }
+
+    /// The sectioning command of this level, including the backslash, or `\begin{frame}`.
+    pub fn command(self) -> &'static str {
+        match self {
+            SectionLevel::Part => "\\part",
+            SectionLevel::Chapter => "\\chapter",
+            SectionLevel::Section => "\\section",
+            SectionLevel::Subsection => "\\subsection",
+            SectionLevel::Subsubsection => "\\subsubsection",
+            SectionLevel::Paragraph => "\\paragraph",
+            SectionLevel::Subparagraph => "\\subparagraph",
+            SectionLevel::Frame => "\\begin{frame}",
+        }
+    }
+}
+
+/// A heading together with the sections nested in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section<'source> {
+    /// The level of the sectioning command
+    pub level: SectionLevel,
+    /// Whether the starred form, e.g. `\section*`, was used
+    pub starred: bool,
+    /// The source text of the title argument without surrounding whitespace, empty for frames
+    /// without a title
+    pub title: &'source str,
+    /// Byte range of `title` in the source text
+    pub title_range: Range<usize>,
+    /// Byte range of the heading, from the command up to the closing brace of the title
+    pub heading: Range<usize>,
+    /// Byte range of the whole section, up to the next heading of the same or a higher level or
+    /// up to `\end{frame}` for frames
+    pub range: Range<usize>,
+    /// The sections of a lower level in source order
+    pub children: Vec<Section<'source>>,
+}
+
+/// The hierarchical outline of a document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Outline<'source> {
+    sections: Vec<Section<'source>>,
+}
+
+impl<'source> Outline<'source> {
+    /// Builds the outline of `lexed`. Sectioning commands without a title argument are skipped.
+    pub fn new(lexed: &LexedStr<'source>) -> Outline<'source> {
+        let mut definition = vec![false; lexed.len()];
+        for node in Document::new(lexed).descendants() {
+            if matches!(node.kind(), NodeKind::CommandDefinition | NodeKind::EnvironmentDefinition)
+            {
+                definition[node.token_range()].fill(true);
+            }
+        }
+
+        let mut headings = Vec::new();
+        for idx in (0..lexed.len()).filter(|&idx| !definition[idx]) {
+            let heading = match lexed.kind(idx) {
+                SyntaxKind::Command => SectionLevel::from_command(lexed.text(idx))
+                    .and_then(|level| heading(lexed, idx, level)),
+                SyntaxKind::BeginGroup => frame(lexed, idx),
+                _ => None,
+            };
+            headings.extend(heading);
+        }
+
+        // a section ends where the next heading of the same or a higher level starts
+        let end = lexed.as_str().len();
+        for i in 0..headings.len() {
+            let level = headings[i].level;
+            if level == SectionLevel::Frame {
+                continue;
+            }
+            headings[i].range.end = headings[i + 1..]
+                .iter()
+                .find(|next| next.level <= level)
+                .map_or(end, |next| next.heading.start);
+        }
+
+        let mut sections = Vec::new();
+        let mut open: Vec<Section<'source>> = Vec::new();
+        for heading in headings {
+            while open.last().is_some_and(|last| last.level >= heading.level) {
+                close(&mut open, &mut sections);
+            }
+            open.push(heading);
+        }
+        while !open.is_empty() {
+            close(&mut open, &mut sections);
+        }
+
+        Outline { sections }
+    }
+
+    /// The outermost sections in source order.
+    pub fn sections(&self) -> &[Section<'source>] {
+        &self.sections
+    }
+
+    /// All sections in source order, each followed by its children.
+    pub fn iter(&self) -> impl Iterator<Item = &Section<'source>> {
+        let mut stack: Vec<&Section<'source>> = self.sections.iter().rev().collect();
+        std::iter::from_fn(move || {
+            let section = stack.pop()?;
+            stack.extend(section.children.iter().rev());
+            Some(section)
+        })
+    }
+
+    /// Whether the document has no headings.
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+}
+
+/// Moves the innermost open section into its parent, or into `sections` if it is outermost.
+fn close<'source>(open: &mut Vec<Section<'source>>, sections: &mut Vec<Section<'source>>) {
+    let Some(section) = open.pop() else {
+        return;
+    };
+    match open.last_mut() {
+        Some(parent) => parent.children.push(section),
+        None => sections.push(section),
+    }
+}
+
+/// The heading of the sectioning command `idx`.
+fn heading<'source>(
+    lexed: &LexedStr<'source>,
+    idx: usize,
+    level: SectionLevel,
+) -> Option<Section<'source>> {
+    let (starred, open, close) = argument(lexed, idx + 1)?;
+    let heading = lexed.text_start(idx)..lexed.text_range(close).end;
+    Some(section(lexed, level, starred, heading, open..close))
+}
+
+/// The frame starting with the `\begin` at `idx`. The title is either the first mandatory
+/// argument, e.g. `\begin{frame}<2>[fragile]{Title}`, or the argument of a `\frametitle` in the
+/// frame.
+fn frame<'source>(lexed: &LexedStr<'source>, idx: usize) -> Option<Section<'source>> {
+    let (name, start) = environment_name(lexed, idx + 1).filter(|(name, _)| *name == "frame")?;
+    let end = (start..lexed.len())
+        .filter(|&end| lexed.kind(end) == SyntaxKind::EndGroup)
+        .find_map(|end| environment_name(lexed, end + 1).filter(|(end_name, _)| *end_name == name))
+        .map_or(lexed.len(), |(_, after)| after);
+
+    let begin = lexed.text_start(idx);
+    let title = match argument(lexed, start) {
+        Some((_, open, close)) => Some((begin..lexed.text_range(close).end, open..close)),
+        None => (start..end)
+            .filter(|&idx| lexed.kind(idx) == SyntaxKind::Command)
+            .find(|&idx| lexed.text(idx) == "\\frametitle")
+            .and_then(|idx| argument(lexed, idx + 1))
+            .map(|(_, open, close)| (begin..lexed.text_start(start), open..close)),
+    };
+
+    let mut frame = match title {
+        Some((heading, braces)) => section(lexed, SectionLevel::Frame, false, heading, braces),
+        None => {
+            let heading = begin..lexed.text_start(start);
+            Section {
+                level: SectionLevel::Frame,
+                starred: false,
+                title: "",
+                title_range: heading.end..heading.end,
+                range: heading.clone(),
+                heading,
+                children: Vec::new(),
+            }
+        }
+    };
+    frame.range.end = lexed.text_start(end);
+    Some(frame)
+}
+
+/// The braces of the first mandatory argument at or after the token `next` and whether a `*`
+/// preceded it. A `*`, overlay specifications like `<2->` and optional arguments are skipped,
+/// e.g. `\section*[Intro]{Introduction}`.
+fn argument(lexed: &LexedStr<'_>, mut next: usize) -> Option<(bool, usize, usize)> {
+    let mut starred = false;
+    let open = loop {
+        next = lexed.next_non_trivia(next)?;
+        match lexed.kind(next) {
+            SyntaxKind::Star if !starred => {
+                starred = true;
+                next += 1;
+            }
+            SyntaxKind::OpenBracket => {
+                next = matching(lexed, next, SyntaxKind::OpenBracket, SyntaxKind::CloseBracket)? + 1
+            }
+            SyntaxKind::OpenBrace => break next,
+            _ => next = overlay_spec(lexed, next)?.end,
+        }
+    };
+    let close = matching(lexed, open, SyntaxKind::OpenBrace, SyntaxKind::CloseBrace)?;
+    Some((starred, open, close))
+}
+
+/// A section with the title in between the braces `open` and `close` of `braces`.
+fn section<'source>(
+    lexed: &LexedStr<'source>,
+    level: SectionLevel,
+    starred: bool,
+    heading: Range<usize>,
+    braces: Range<usize>,
+) -> Section<'source> {
+    let inner = lexed.text_start(braces.start + 1)..lexed.text_start(braces.end);
+    let text = &lexed.as_str()[inner.clone()];
+    let title = text.trim();
+    let start = inner.start + (text.len() - text.trim_start().len());
+
+    Section {
+        level,
+        starred,
+        title,
+        title_range: start..start + title.len(),
+        range: heading.clone(),
+        heading,
+        children: Vec::new(),
+    }
+}
+
+/// The token closing the group opened by the token `open`, taking nested groups into account.
+fn matching(
+    lexed: &LexedStr<'_>,
+    open: usize,
+    left: SyntaxKind,
+    right: SyntaxKind,
+) -> Option<usize> {
+    let mut depth = 0usize;
+    for idx in open..lexed.len() {
+        match lexed.kind(idx) {
+            kind if kind == left => depth += 1,
+            kind if kind == right => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests;
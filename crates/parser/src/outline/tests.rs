@@ -0,0 +1,81 @@
+use super::{Outline, SectionLevel};
+use crate::LexedStr;
+
+#[test]
+fn outline() {
+    let input = "\\chapter{Intro}\ntext\n\\section*[Short]{ A \\emph{long} title }\n\
+                 \\subsection{Details}\n\\paragraph{Note}\n\\section{Next}\n\\chapter{End}\n\\section";
+    let lexed = LexedStr::new(input);
+    let outline = Outline::new(&lexed);
+
+    let sections: Vec<_> = outline
+        .iter()
+        .map(|section| (section.level, section.starred, section.title, section.children.len()))
+        .collect();
+    assert_eq!(
+        sections,
+        [
+            (SectionLevel::Chapter, false, "Intro", 2),
+            (SectionLevel::Section, true, "A \\emph{long} title", 1),
+            (SectionLevel::Subsection, false, "Details", 1),
+            (SectionLevel::Paragraph, false, "Note", 0),
+            (SectionLevel::Section, false, "Next", 0),
+            (SectionLevel::Chapter, false, "End", 0),
+        ]
+    );
+    assert!(outline.iter().all(|section| input[section.title_range.clone()] == *section.title));
+
+    let [intro, end] = outline.sections() else { panic!("expected two chapters") };
+    assert_eq!(&input[intro.heading.clone()], "\\chapter{Intro}");
+    assert_eq!(intro.range, 0..input.find("\\chapter{End}").unwrap());
+    assert_eq!(intro.children[0].range.end, input.find("\\section{Next}").unwrap());
+    assert_eq!(end.range.end, input.len());
+}
+
+#[test]
+fn skipped_levels() {
+    let lexed = LexedStr::new("\\subsection{A}\\section{B}\\subsubsection{C}");
+    let outline = Outline::new(&lexed);
+
+    let titles: Vec<_> = outline.sections().iter().map(|section| section.title).collect();
+    assert_eq!(titles, ["A", "B"]);
+    assert_eq!(outline.sections()[1].children[0].title, "C");
+    assert!(Outline::new(&LexedStr::new("no headings")).is_empty());
+
+    let lexed = LexedStr::new("\\newcommand{\\thm}[1]{\\paragraph{#1}}\\section{A}");
+    let titles: Vec<_> = Outline::new(&lexed).iter().map(|section| section.title).collect();
+    assert_eq!(titles, ["A"]);
+}
+
+#[test]
+fn frames() {
+    let input = "\\section{Intro}\n\\begin{frame}<1-2>[fragile]{First}\na\n\\end{frame}\n\
+                 \\begin{frame}\n\\frametitle<2>{Second}\n\\end{frame}\n\\section{Next}\n\
+                 \\begin{frame}\n\\end{frame}";
+    let lexed = LexedStr::new(input);
+    let outline = Outline::new(&lexed);
+
+    let sections: Vec<_> = outline
+        .iter()
+        .map(|section| (section.level, section.title, &input[section.heading.clone()]))
+        .collect();
+    assert_eq!(
+        sections,
+        [
+            (SectionLevel::Section, "Intro", "\\section{Intro}"),
+            (SectionLevel::Frame, "First", "\\begin{frame}<1-2>[fragile]{First}"),
+            (SectionLevel::Frame, "Second", "\\begin{frame}"),
+            (SectionLevel::Section, "Next", "\\section{Next}"),
+            (SectionLevel::Frame, "", "\\begin{frame}"),
+        ]
+    );
+
+    let [intro, next] = outline.sections() else { panic!("expected two sections") };
+    assert_eq!(intro.children.len(), 2);
+    assert_eq!(
+        &input[intro.children[1].range.clone()],
+        "\\begin{frame}\n\\frametitle<2>{Second}\n\\end{frame}"
+    );
+    assert_eq!(intro.range.end, input.find("\\section{Next}").unwrap());
+    assert_eq!(next.children[0].range.end, input.len());
+}
@@ -24,9 +24,17 @@ pub(super) enum ParserEvent {
     Advance,
 }
 
+/// Tokens the parser can look ahead. A lookahead configured per parser, e.g. for the argument
+/// specifications of `xparse`, is deferred like speculative parsing, see [`Parser`].
 const MAX_LOOKAHEAD: usize = 8;
 const MAX_FUEL: usize = 256;
 
+/// A parser turning the tokens of the lexer into events of a syntax tree.
+///
+/// Not used yet, the syntax tree is built by [`Document`](crate::ast::Document) from the tokens of
+/// a [`LexedStr`](crate::LexedStr), which looks back and ahead freely. Speculative parsing, i.e.
+/// checkpoints which roll back the events and tokens of an abandoned attempt like a `[` which turns
+/// out to be no optional argument, is deferred until this parser gets a grammar.
 struct Parser<'source, I>
 where
     I: Iterator<Item = TokenizerItem<'source>> + 'source,
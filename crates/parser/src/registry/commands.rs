@@ -0,0 +1,258 @@
+use super::BuiltinCommand;
+
+/// Standard commands ordered by name, so that they can be found with a binary search.
+pub(super) const COMMANDS: &[BuiltinCommand] = &[
+    BuiltinCommand::new("\\Big", 0),
+    BuiltinCommand::new("\\Bigg", 0),
+    BuiltinCommand::new("\\DeclareOption", 2),
+    BuiltinCommand::new("\\Delta", 0),
+    BuiltinCommand::new("\\ExecuteOptions", 1),
+    BuiltinCommand::new("\\Gamma", 0),
+    BuiltinCommand::new("\\Im", 0),
+    BuiltinCommand::new("\\LaTeX", 0),
+    BuiltinCommand::new("\\Lambda", 0),
+    BuiltinCommand::new("\\Leftarrow", 0),
+    BuiltinCommand::new("\\Leftrightarrow", 0),
+    BuiltinCommand::new("\\LoadClass", 2).optional(),
+    BuiltinCommand::new("\\NeedsTeXFormat", 1),
+    BuiltinCommand::new("\\Omega", 0),
+    BuiltinCommand::new("\\PassOptionsToClass", 2),
+    BuiltinCommand::new("\\PassOptionsToPackage", 2),
+    BuiltinCommand::new("\\Phi", 0),
+    BuiltinCommand::new("\\Pi", 0),
+    BuiltinCommand::new("\\ProcessOptions", 0),
+    BuiltinCommand::new("\\ProvidesClass", 1),
+    BuiltinCommand::new("\\ProvidesPackage", 1),
+    BuiltinCommand::new("\\Psi", 0),
+    BuiltinCommand::new("\\Re", 0),
+    BuiltinCommand::new("\\RequirePackage", 2).optional(),
+    BuiltinCommand::new("\\Rightarrow", 0),
+    BuiltinCommand::new("\\Sigma", 0),
+    BuiltinCommand::new("\\TeX", 0),
+    BuiltinCommand::new("\\Theta", 0),
+    BuiltinCommand::new("\\Upsilon", 0),
+    BuiltinCommand::new("\\Xi", 0),
+    BuiltinCommand::new("\\acute", 1),
+    BuiltinCommand::new("\\aleph", 0),
+    BuiltinCommand::new("\\alpha", 0),
+    BuiltinCommand::new("\\approx", 0),
+    BuiltinCommand::new("\\arccos", 0),
+    BuiltinCommand::new("\\arcsin", 0),
+    BuiltinCommand::new("\\arctan", 0),
+    BuiltinCommand::new("\\author", 1),
+    BuiltinCommand::new("\\bar", 1),
+    BuiltinCommand::new("\\beta", 0),
+    BuiltinCommand::new("\\big", 0),
+    BuiltinCommand::new("\\bigcap", 0),
+    BuiltinCommand::new("\\bigcup", 0),
+    BuiltinCommand::new("\\bigg", 0),
+    BuiltinCommand::new("\\bigoplus", 0),
+    BuiltinCommand::new("\\bigotimes", 0),
+    BuiltinCommand::new("\\binom", 2).package("amsmath"),
+    BuiltinCommand::new("\\boldsymbol", 1).package("amsmath"),
+    BuiltinCommand::new("\\breve", 1),
+    BuiltinCommand::new("\\bullet", 0),
+    BuiltinCommand::new("\\cap", 0),
+    BuiltinCommand::new("\\caption", 2).optional(),
+    BuiltinCommand::new("\\cdot", 0),
+    BuiltinCommand::new("\\cdots", 0),
+    BuiltinCommand::new("\\centering", 0),
+    BuiltinCommand::new("\\chapter", 2).optional(),
+    BuiltinCommand::new("\\check", 1),
+    BuiltinCommand::new("\\chi", 0),
+    BuiltinCommand::new("\\circ", 0),
+    BuiltinCommand::new("\\cite", 2).optional(),
+    BuiltinCommand::new("\\clearpage", 0),
+    BuiltinCommand::new("\\color", 1).package("xcolor"),
+    BuiltinCommand::new("\\cong", 0),
+    BuiltinCommand::new("\\coprod", 0),
+    BuiltinCommand::new("\\cos", 0),
+    BuiltinCommand::new("\\cosh", 0),
+    BuiltinCommand::new("\\cup", 0),
+    BuiltinCommand::new("\\dagger", 0),
+    BuiltinCommand::new("\\date", 1),
+    BuiltinCommand::new("\\ddot", 1),
+    BuiltinCommand::new("\\ddots", 0),
+    BuiltinCommand::new("\\delta", 0),
+    BuiltinCommand::new("\\det", 0),
+    BuiltinCommand::new("\\dfrac", 2).package("amsmath"),
+    BuiltinCommand::new("\\displaystyle", 0),
+    BuiltinCommand::new("\\div", 0),
+    BuiltinCommand::new("\\documentclass", 2).optional(),
+    BuiltinCommand::new("\\dot", 1),
+    BuiltinCommand::new("\\dots", 0),
+    BuiltinCommand::new("\\ell", 0),
+    BuiltinCommand::new("\\emph", 1),
+    BuiltinCommand::new("\\emptyset", 0),
+    BuiltinCommand::new("\\epsilon", 0),
+    BuiltinCommand::new("\\eqref", 1).package("amsmath"),
+    BuiltinCommand::new("\\equiv", 0),
+    BuiltinCommand::new("\\eta", 0),
+    BuiltinCommand::new("\\exists", 0),
+    BuiltinCommand::new("\\exp", 0),
+    BuiltinCommand::new("\\footnote", 2).optional(),
+    BuiltinCommand::new("\\forall", 0),
+    BuiltinCommand::new("\\frac", 2),
+    BuiltinCommand::new("\\gamma", 0),
+    BuiltinCommand::new("\\ge", 0),
+    BuiltinCommand::new("\\geq", 0),
+    BuiltinCommand::new("\\geqslant", 0).package("amssymb"),
+    BuiltinCommand::new("\\grave", 1),
+    BuiltinCommand::new("\\gtrsim", 0).package("amssymb"),
+    BuiltinCommand::new("\\hat", 1),
+    BuiltinCommand::new("\\hbar", 0),
+    BuiltinCommand::new("\\href", 2).package("hyperref"),
+    BuiltinCommand::new("\\hspace", 1),
+    BuiltinCommand::new("\\iiint", 0).package("amsmath"),
+    BuiltinCommand::new("\\iint", 0).package("amsmath"),
+    BuiltinCommand::new("\\impliedby", 0).package("amsmath"),
+    BuiltinCommand::new("\\implies", 0).package("amsmath"),
+    BuiltinCommand::new("\\in", 0),
+    BuiltinCommand::new("\\include", 1),
+    BuiltinCommand::new("\\includegraphics", 2).optional().package("graphicx"),
+    BuiltinCommand::new("\\inf", 0),
+    BuiltinCommand::new("\\infty", 0),
+    BuiltinCommand::new("\\input", 1),
+    BuiltinCommand::new("\\int", 0),
+    BuiltinCommand::new("\\iota", 0),
+    BuiltinCommand::new("\\item", 1).optional(),
+    BuiltinCommand::new("\\kappa", 0),
+    BuiltinCommand::new("\\label", 1),
+    BuiltinCommand::new("\\lambda", 0),
+    BuiltinCommand::new("\\land", 0),
+    BuiltinCommand::new("\\langle", 0),
+    BuiltinCommand::new("\\lceil", 0),
+    BuiltinCommand::new("\\ldots", 0),
+    BuiltinCommand::new("\\le", 0),
+    BuiltinCommand::new("\\left", 0),
+    BuiltinCommand::new("\\leftarrow", 0),
+    BuiltinCommand::new("\\leftrightarrow", 0),
+    BuiltinCommand::new("\\leq", 0),
+    BuiltinCommand::new("\\leqslant", 0).package("amssymb"),
+    BuiltinCommand::new("\\lesssim", 0).package("amssymb"),
+    BuiltinCommand::new("\\lfloor", 0),
+    BuiltinCommand::new("\\lim", 0),
+    BuiltinCommand::new("\\liminf", 0),
+    BuiltinCommand::new("\\limsup", 0),
+    BuiltinCommand::new("\\linebreak", 0),
+    BuiltinCommand::new("\\ln", 0),
+    BuiltinCommand::new("\\log", 0),
+    BuiltinCommand::new("\\lor", 0),
+    BuiltinCommand::new("\\maketitle", 0),
+    BuiltinCommand::new("\\mapsto", 0),
+    BuiltinCommand::new("\\mathbb", 1).package("amssymb"),
+    BuiltinCommand::new("\\mathbf", 1),
+    BuiltinCommand::new("\\mathcal", 1),
+    BuiltinCommand::new("\\mathfrak", 1).package("amssymb"),
+    BuiltinCommand::new("\\mathit", 1),
+    BuiltinCommand::new("\\mathrm", 1),
+    BuiltinCommand::new("\\mathscr", 1).package("mathrsfs"),
+    BuiltinCommand::new("\\mathsf", 1),
+    BuiltinCommand::new("\\mathtt", 1),
+    BuiltinCommand::new("\\max", 0),
+    BuiltinCommand::new("\\mid", 0),
+    BuiltinCommand::new("\\min", 0),
+    BuiltinCommand::new("\\mp", 0),
+    BuiltinCommand::new("\\mu", 0),
+    BuiltinCommand::new("\\nabla", 0),
+    BuiltinCommand::new("\\ne", 0),
+    BuiltinCommand::new("\\neg", 0),
+    BuiltinCommand::new("\\neq", 0),
+    BuiltinCommand::new("\\newline", 0),
+    BuiltinCommand::new("\\newpage", 0),
+    BuiltinCommand::new("\\ni", 0),
+    BuiltinCommand::new("\\noindent", 0),
+    BuiltinCommand::new("\\notin", 0),
+    BuiltinCommand::new("\\nu", 0),
+    BuiltinCommand::new("\\oint", 0),
+    BuiltinCommand::new("\\omega", 0),
+    BuiltinCommand::new("\\operatorname", 1).package("amsmath"),
+    BuiltinCommand::new("\\oplus", 0),
+    BuiltinCommand::new("\\otimes", 0),
+    BuiltinCommand::new("\\overbrace", 1),
+    BuiltinCommand::new("\\overline", 1),
+    BuiltinCommand::new("\\overset", 2).package("amsmath"),
+    BuiltinCommand::new("\\pagebreak", 0),
+    BuiltinCommand::new("\\pageref", 1),
+    BuiltinCommand::new("\\paragraph", 2).optional(),
+    BuiltinCommand::new("\\parallel", 0),
+    BuiltinCommand::new("\\part", 2).optional(),
+    BuiltinCommand::new("\\partial", 0),
+    BuiltinCommand::new("\\perp", 0),
+    BuiltinCommand::new("\\phi", 0),
+    BuiltinCommand::new("\\pi", 0),
+    BuiltinCommand::new("\\pm", 0),
+    BuiltinCommand::new("\\prod", 0),
+    BuiltinCommand::new("\\propto", 0),
+    BuiltinCommand::new("\\psi", 0),
+    BuiltinCommand::new("\\qquad", 0),
+    BuiltinCommand::new("\\quad", 0),
+    BuiltinCommand::new("\\rangle", 0),
+    BuiltinCommand::new("\\rceil", 0),
+    BuiltinCommand::new("\\ref", 1),
+    BuiltinCommand::new("\\rfloor", 0),
+    BuiltinCommand::new("\\rho", 0),
+    BuiltinCommand::new("\\right", 0),
+    BuiltinCommand::new("\\rightarrow", 0),
+    BuiltinCommand::new("\\section", 2).optional(),
+    BuiltinCommand::new("\\setminus", 0),
+    BuiltinCommand::new("\\sigma", 0),
+    BuiltinCommand::new("\\sim", 0),
+    BuiltinCommand::new("\\simeq", 0),
+    BuiltinCommand::new("\\sin", 0),
+    BuiltinCommand::new("\\sinh", 0),
+    BuiltinCommand::new("\\sqrt", 2).optional(),
+    BuiltinCommand::new("\\stackrel", 2),
+    BuiltinCommand::new("\\star", 0),
+    BuiltinCommand::new("\\subsection", 2).optional(),
+    BuiltinCommand::new("\\subset", 0),
+    BuiltinCommand::new("\\subseteq", 0),
+    BuiltinCommand::new("\\subsubsection", 2).optional(),
+    BuiltinCommand::new("\\sum", 0),
+    BuiltinCommand::new("\\sup", 0),
+    BuiltinCommand::new("\\supset", 0),
+    BuiltinCommand::new("\\supseteq", 0),
+    BuiltinCommand::new("\\tableofcontents", 0),
+    BuiltinCommand::new("\\tag", 1).package("amsmath"),
+    BuiltinCommand::new("\\tan", 0),
+    BuiltinCommand::new("\\tanh", 0),
+    BuiltinCommand::new("\\tau", 0),
+    BuiltinCommand::new("\\text", 1).package("amsmath"),
+    BuiltinCommand::new("\\textbf", 1),
+    BuiltinCommand::new("\\textcolor", 2).package("xcolor"),
+    BuiltinCommand::new("\\textit", 1),
+    BuiltinCommand::new("\\textrm", 1),
+    BuiltinCommand::new("\\textsc", 1),
+    BuiltinCommand::new("\\textsf", 1),
+    BuiltinCommand::new("\\textstyle", 0),
+    BuiltinCommand::new("\\texttt", 1),
+    BuiltinCommand::new("\\tfrac", 2).package("amsmath"),
+    BuiltinCommand::new("\\theta", 0),
+    BuiltinCommand::new("\\tilde", 1),
+    BuiltinCommand::new("\\times", 0),
+    BuiltinCommand::new("\\title", 1),
+    BuiltinCommand::new("\\to", 0),
+    BuiltinCommand::new("\\today", 0),
+    BuiltinCommand::new("\\underbrace", 1),
+    BuiltinCommand::new("\\underline", 1),
+    BuiltinCommand::new("\\underset", 2).package("amsmath"),
+    BuiltinCommand::new("\\upsilon", 0),
+    BuiltinCommand::new("\\url", 1).package("url"),
+    BuiltinCommand::new("\\usepackage", 2).optional(),
+    BuiltinCommand::new("\\varepsilon", 0),
+    BuiltinCommand::new("\\varnothing", 0).package("amssymb"),
+    BuiltinCommand::new("\\varphi", 0),
+    BuiltinCommand::new("\\varpi", 0),
+    BuiltinCommand::new("\\varrho", 0),
+    BuiltinCommand::new("\\varsigma", 0),
+    BuiltinCommand::new("\\vartheta", 0),
+    BuiltinCommand::new("\\vdots", 0),
+    BuiltinCommand::new("\\vec", 1),
+    BuiltinCommand::new("\\vee", 0),
+    BuiltinCommand::new("\\vspace", 1),
+    BuiltinCommand::new("\\wedge", 0),
+    BuiltinCommand::new("\\widehat", 1),
+    BuiltinCommand::new("\\widetilde", 1),
+    BuiltinCommand::new("\\xi", 0),
+    BuiltinCommand::new("\\zeta", 0),
+];
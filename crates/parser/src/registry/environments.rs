@@ -0,0 +1,50 @@
+use super::BuiltinEnvironment;
+
+/// Standard environments ordered by name, so that they can be found with a binary search.
+pub(super) const ENVIRONMENTS: &[BuiltinEnvironment] = &[
+    BuiltinEnvironment::new("Bmatrix", 0).package("amsmath"),
+    BuiltinEnvironment::new("Vmatrix", 0).package("amsmath"),
+    BuiltinEnvironment::new("abstract", 0),
+    BuiltinEnvironment::new("align", 0).package("amsmath"),
+    BuiltinEnvironment::new("align*", 0).package("amsmath"),
+    BuiltinEnvironment::new("alignat", 1).package("amsmath"),
+    BuiltinEnvironment::new("alignat*", 1).package("amsmath"),
+    BuiltinEnvironment::new("array", 1),
+    BuiltinEnvironment::new("bmatrix", 0).package("amsmath"),
+    BuiltinEnvironment::new("cases", 0).package("amsmath"),
+    BuiltinEnvironment::new("center", 0),
+    BuiltinEnvironment::new("description", 0),
+    BuiltinEnvironment::new("document", 0),
+    BuiltinEnvironment::new("enumerate", 0),
+    BuiltinEnvironment::new("eqnarray", 0),
+    BuiltinEnvironment::new("equation", 0),
+    BuiltinEnvironment::new("equation*", 0).package("amsmath"),
+    BuiltinEnvironment::new("figure", 1).optional(),
+    BuiltinEnvironment::new("figure*", 1).optional(),
+    BuiltinEnvironment::new("flushleft", 0),
+    BuiltinEnvironment::new("flushright", 0),
+    BuiltinEnvironment::new("gather", 0).package("amsmath"),
+    BuiltinEnvironment::new("gather*", 0).package("amsmath"),
+    BuiltinEnvironment::new("itemize", 0),
+    BuiltinEnvironment::new("list", 2),
+    BuiltinEnvironment::new("longtable", 1).package("longtable"),
+    BuiltinEnvironment::new("matrix", 0).package("amsmath"),
+    BuiltinEnvironment::new("minipage", 2).optional(),
+    BuiltinEnvironment::new("multline", 0).package("amsmath"),
+    BuiltinEnvironment::new("multline*", 0).package("amsmath"),
+    BuiltinEnvironment::new("pmatrix", 0).package("amsmath"),
+    BuiltinEnvironment::new("proof", 1).optional().package("amsthm"),
+    BuiltinEnvironment::new("quotation", 0),
+    BuiltinEnvironment::new("quote", 0),
+    BuiltinEnvironment::new("split", 0).package("amsmath"),
+    BuiltinEnvironment::new("table", 1).optional(),
+    BuiltinEnvironment::new("table*", 1).optional(),
+    BuiltinEnvironment::new("tabular", 1),
+    BuiltinEnvironment::new("tabular*", 2),
+    BuiltinEnvironment::new("tabularx", 2).package("tabularx"),
+    BuiltinEnvironment::new("thebibliography", 1),
+    BuiltinEnvironment::new("titlepage", 0),
+    BuiltinEnvironment::new("verbatim", 0),
+    BuiltinEnvironment::new("verse", 0),
+    BuiltinEnvironment::new("vmatrix", 0).package("amsmath"),
+];
@@ -0,0 +1,128 @@
+//! Signatures of the standard commands and environments of LaTeX and common packages.
+//!
+//! The registry knows which commands exist without being defined in the document, e.g. `\frac`
+//! from the kernel or `\mathbb` from `amssymb`. It is used to detect redefinitions of standard
+//! commands, to find out which package provides a command and to offer completions.
+
+mod commands;
+mod environments;
+
+use core::fmt;
+
+use commands::COMMANDS;
+use environments::ENVIRONMENTS;
+
+/// A command provided by the LaTeX kernel or a package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuiltinCommand {
+    /// Name including the leading backslash
+    pub name: &'static str,
+    /// Total number of arguments, including the optional one
+    pub arg_count: usize,
+    /// Whether the first argument is optional
+    pub optional: bool,
+    /// Package providing the command, `None` for the kernel
+    pub package: Option<&'static str>,
+}
+
+impl BuiltinCommand {
+    const fn new(name: &'static str, arg_count: usize) -> BuiltinCommand {
+        BuiltinCommand { name, arg_count, optional: false, package: None }
+    }
+
+    const fn optional(mut self) -> BuiltinCommand {
+        self.optional = true;
+        self
+    }
+
+    const fn package(mut self, package: &'static str) -> BuiltinCommand {
+        self.package = Some(package);
+        self
+    }
+}
+
+/// Formats the signature, e.g. `\sqrt[#1]{#2}`.
+impl fmt::Display for BuiltinCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_signature(f, self.name, self.arg_count, self.optional)
+    }
+}
+
+/// Returns the standard command `name`, e.g. `\vec`.
+pub fn command(name: &str) -> Option<&'static BuiltinCommand> {
+    COMMANDS.binary_search_by(|command| command.name.cmp(name)).ok().map(|idx| &COMMANDS[idx])
+}
+
+/// All standard commands ordered by name.
+pub fn commands() -> &'static [BuiltinCommand] {
+    COMMANDS
+}
+
+/// An environment provided by the LaTeX kernel or a package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuiltinEnvironment {
+    /// Name without `\begin`, e.g. `tabular`
+    pub name: &'static str,
+    /// Total number of arguments following `\begin{name}`, including the optional one
+    pub arg_count: usize,
+    /// Whether the first argument is optional
+    pub optional: bool,
+    /// Package providing the environment, `None` for the kernel
+    pub package: Option<&'static str>,
+}
+
+impl BuiltinEnvironment {
+    const fn new(name: &'static str, arg_count: usize) -> BuiltinEnvironment {
+        BuiltinEnvironment { name, arg_count, optional: false, package: None }
+    }
+
+    const fn optional(mut self) -> BuiltinEnvironment {
+        self.optional = true;
+        self
+    }
+
+    const fn package(mut self, package: &'static str) -> BuiltinEnvironment {
+        self.package = Some(package);
+        self
+    }
+}
+
+/// Formats the signature, e.g. `minipage[#1]{#2}`.
+impl fmt::Display for BuiltinEnvironment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_signature(f, self.name, self.arg_count, self.optional)
+    }
+}
+
+/// Returns the standard environment `name`, e.g. `itemize`.
+pub fn environment(name: &str) -> Option<&'static BuiltinEnvironment> {
+    ENVIRONMENTS
+        .binary_search_by(|environment| environment.name.cmp(name))
+        .ok()
+        .map(|idx| &ENVIRONMENTS[idx])
+}
+
+/// All standard environments ordered by name.
+pub fn environments() -> &'static [BuiltinEnvironment] {
+    ENVIRONMENTS
+}
+
+/// Writes `name` followed by one parameter for every argument, e.g. `\vect[#1]{#2}`.
+pub(crate) fn write_signature(
+    out: &mut impl fmt::Write,
+    name: &str,
+    arg_count: usize,
+    optional: bool,
+) -> fmt::Result {
+    out.write_str(name)?;
+    for arg in 1..=arg_count {
+        match optional && arg == 1 {
+            true => write!(out, "[#{arg}]")?,
+            false => write!(out, "{{#{arg}}}")?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,26 @@
+use super::{command, commands, environment, environments};
+use alloc::string::ToString;
+
+#[test]
+fn commands_are_sorted() {
+    assert!(commands().windows(2).all(|pair| pair[0].name < pair[1].name));
+    assert!(environments().windows(2).all(|pair| pair[0].name < pair[1].name));
+}
+
+#[test]
+fn lookup() {
+    let sqrt = command("\\sqrt").unwrap();
+    assert_eq!(sqrt.to_string(), "\\sqrt[#1]{#2}");
+    assert_eq!(command("\\phi").unwrap().to_string(), "\\phi");
+    assert_eq!(command("\\mathbb").unwrap().package, Some("amssymb"));
+    assert_eq!(command("\\R"), None);
+    assert_eq!(command("\\RequirePackage").unwrap().to_string(), "\\RequirePackage[#1]{#2}");
+}
+
+#[test]
+fn lookup_environment() {
+    assert_eq!(environment("minipage").unwrap().to_string(), "minipage[#1]{#2}");
+    assert_eq!(environment("align*").unwrap().package, Some("amsmath"));
+    assert_eq!(environment("proof").unwrap().package, Some("amsthm"));
+    assert_eq!(environment("theorem"), None);
+}
@@ -0,0 +1,265 @@
+//! Classification of tokens for semantic highlighting.
+//!
+//! Unlike a highlighting grammar, [`semantic_tokens`] knows the resolved definitions of the
+//! document, so it can tell the name a definition introduces from its uses and the parts of a
+//! NeoTeX path like `\geo::area` apart. [`encode`] turns the tokens into the relative encoding of
+//! the language server protocol.
+
+use std::ops::Range;
+
+use rustc_hash::FxHashSet;
+
+use crate::{expansion::ExpansionStore, LexedStr, SyntaxKind};
+
+/// The type of a [`SemanticToken`]. The discriminants are the indices into
+/// [`SemanticTokenType::ALL`], the legend of the encoded tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SemanticTokenType {
+    /// A command, e.g. `\section`
+    Macro,
+    /// A NeoTeX function, the last segment of a path like `\geo::area` or the name after `\fn`
+    Function,
+    /// A NeoTeX variable like `\@x` or a macro parameter like `#1`
+    Variable,
+    /// A NeoTeX module, every segment of a path but the last or the name after `\mod`
+    Namespace,
+    /// A keyword like `\newcommand`, `\begin` or `\fn`
+    Keyword,
+    /// A number, e.g. `12` or `.5`
+    Number,
+    /// A comment, including annotated `%%` comments
+    Comment,
+    /// `$`, `$$`, `\(`, `\)`, `\[` or `\]`
+    MathDelimiter,
+}
+
+impl SemanticTokenType {
+    /// All types in the order of their discriminants.
+    pub const ALL: [SemanticTokenType; 8] = [
+        SemanticTokenType::Macro,
+        SemanticTokenType::Function,
+        SemanticTokenType::Variable,
+        SemanticTokenType::Namespace,
+        SemanticTokenType::Keyword,
+        SemanticTokenType::Number,
+        SemanticTokenType::Comment,
+        SemanticTokenType::MathDelimiter,
+    ];
+
+    /// Name of the type in the protocol, e.g. `macro` or `mathDelimiter`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SemanticTokenType::Macro => "macro",
+            SemanticTokenType::Function => "function",
+            SemanticTokenType::Variable => "variable",
+            SemanticTokenType::Namespace => "namespace",
+            SemanticTokenType::Keyword => "keyword",
+            SemanticTokenType::Number => "number",
+            SemanticTokenType::Comment => "comment",
+            SemanticTokenType::MathDelimiter => "mathDelimiter",
+        }
+    }
+}
+
+/// A modifier of a [`SemanticToken`]. The discriminants are the bit positions in
+/// [`SemanticToken::modifiers`] and the indices into [`SemanticModifier::ALL`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SemanticModifier {
+    /// The name a definition introduces. Names without it refer to a definition.
+    Definition,
+}
+
+impl SemanticModifier {
+    /// All modifiers in the order of their discriminants.
+    pub const ALL: [SemanticModifier; 1] = [SemanticModifier::Definition];
+
+    /// Name of the modifier in the protocol, e.g. `definition`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SemanticModifier::Definition => "definition",
+        }
+    }
+
+    /// The bit of the modifier in [`SemanticToken::modifiers`].
+    pub fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// A classified token of the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemanticToken {
+    /// Byte range of the token
+    pub range: Range<usize>,
+    /// The type of the token
+    pub kind: SemanticTokenType,
+    /// Bit set of the [`SemanticModifier`]s of the token
+    pub modifiers: u32,
+}
+
+impl SemanticToken {
+    /// Returns `true` if the token has `modifier`.
+    pub fn has(&self, modifier: SemanticModifier) -> bool {
+        self.modifiers & modifier.bit() != 0
+    }
+}
+
+/// The classified tokens of `lexed` in source order. `definitions` are the resolved definitions
+/// of `lexed`, the names of command definitions get [`SemanticModifier::Definition`].
+///
+/// Text, whitespace and punctuation are not classified and left to the highlighting grammar.
+///
+/// ```
+/// use parser::{
+///     expansion,
+///     semantic::{semantic_tokens, SemanticModifier, SemanticTokenType},
+///     LexedStr,
+/// };
+///
+/// let source = r"\newcommand{\R}{\mathbb{R}} $\R^2$ % reals";
+/// let lexed = LexedStr::new(source);
+/// let tokens = semantic_tokens(&lexed, &expansion::resolve(&lexed));
+/// let classified: Vec<_> = tokens
+///     .iter()
+///     .map(|token| (&source[token.range.clone()], token.kind, token.has(SemanticModifier::Definition)))
+///     .collect();
+///
+/// assert_eq!(classified[..3], [
+///     (r"\newcommand", SemanticTokenType::Keyword, false),
+///     (r"\R", SemanticTokenType::Macro, true),
+///     (r"\mathbb", SemanticTokenType::Macro, false),
+/// ]);
+/// assert_eq!(classified[4], (r"\R", SemanticTokenType::Macro, false));
+/// assert_eq!(classified.last(), Some(&("% reals", SemanticTokenType::Comment, false)));
+/// ```
+pub fn semantic_tokens(
+    lexed: &LexedStr<'_>,
+    definitions: &ExpansionStore<'_>,
+) -> Vec<SemanticToken> {
+    // the first occurrence of the name in a definition is the name it introduces
+    let defined: FxHashSet<usize> = definitions
+        .all_commands()
+        .filter_map(|item| {
+            item.range
+                .clone()
+                .find(|&idx| lexed.kind(idx) == SyntaxKind::Command && lexed.text(idx) == item.name)
+        })
+        .collect();
+
+    let kind = |idx: usize| (idx < lexed.len()).then(|| lexed.kind(idx));
+    let previous = |idx: usize| {
+        (0..idx).rev().map(|idx| lexed.kind(idx)).find(|kind| !kind.is_resolver_trivia())
+    };
+
+    let mut tokens = Vec::new();
+    for idx in 0..lexed.len() {
+        let path = (
+            idx.checked_sub(1).and_then(kind) == Some(SyntaxKind::PathSeperator),
+            kind(idx + 1) == Some(SyntaxKind::PathSeperator),
+        );
+        let classified = match lexed.kind(idx) {
+            SyntaxKind::Comment | SyntaxKind::AComment => Some((SemanticTokenType::Comment, false)),
+            SyntaxKind::Number | SyntaxKind::Float => Some((SemanticTokenType::Number, false)),
+            SyntaxKind::MathDelimiter => Some((SemanticTokenType::MathDelimiter, false)),
+            SyntaxKind::SimpleMacroExpansionToken | SyntaxKind::ComplexMacroExpansionToken => {
+                Some((SemanticTokenType::Variable, false))
+            }
+            SyntaxKind::Variable => {
+                Some((SemanticTokenType::Variable, previous(idx) == Some(SyntaxKind::Let)))
+            }
+            SyntaxKind::FunctionIdent
+            | SyntaxKind::Module
+            | SyntaxKind::Pub
+            | SyntaxKind::Let
+            | SyntaxKind::Use
+            | SyntaxKind::Def
+            | SyntaxKind::NewCommand
+            | SyntaxKind::NewEnv
+            | SyntaxKind::UsePackage
+            | SyntaxKind::BeginGroup
+            | SyntaxKind::EndGroup
+            | SyntaxKind::DocClass
+            | SyntaxKind::FileInput => Some((SemanticTokenType::Keyword, false)),
+            SyntaxKind::Command | SyntaxKind::AWord if path.1 => {
+                Some((SemanticTokenType::Namespace, false))
+            }
+            SyntaxKind::Command | SyntaxKind::AWord if path.0 => {
+                Some((SemanticTokenType::Function, false))
+            }
+            SyntaxKind::AWord => match previous(idx) {
+                Some(SyntaxKind::Module) => Some((SemanticTokenType::Namespace, true)),
+                Some(SyntaxKind::FunctionIdent) => Some((SemanticTokenType::Function, true)),
+                _ => None,
+            },
+            SyntaxKind::Command => match lexed.text(idx) {
+                "\\(" | "\\)" | "\\[" | "\\]" => Some((SemanticTokenType::MathDelimiter, false)),
+                _ => Some((SemanticTokenType::Macro, defined.contains(&idx))),
+            },
+            _ => None,
+        };
+
+        if let Some((kind, definition)) = classified {
+            let modifiers = if definition { SemanticModifier::Definition.bit() } else { 0 };
+            tokens.push(SemanticToken { range: lexed.text_range(idx), kind, modifiers });
+        }
+    }
+    tokens
+}
+
+/// Encodes `tokens` of `source` relative to each other as in the language server protocol: five
+/// numbers per token, the line relative to the previous token, the start column relative to the
+/// previous token on the same line or the start of the line, the length, the type and the
+/// modifiers. Columns and lengths are counted in UTF-16 code units.
+///
+/// `tokens` have to be sorted and must not overlap. A token spanning several lines is split into
+/// one per line, since not every client supports multiline tokens.
+///
+/// ```
+/// use parser::semantic::{encode, SemanticToken, SemanticTokenType};
+///
+/// let token = |range, kind| SemanticToken { range, kind, modifiers: 0 };
+/// let tokens = [token(0..3, SemanticTokenType::Macro), token(7..8, SemanticTokenType::Number)];
+///
+/// assert_eq!(encode("\\é x\n 1", &tokens), [0, 0, 2, 0, 0, 1, 1, 1, 5, 0]);
+/// ```
+pub fn encode(source: &str, tokens: &[SemanticToken]) -> Vec<u32> {
+    let utf16 = |text: &str| text.encode_utf16().count() as u32;
+    let mut data = Vec::with_capacity(tokens.len() * 5);
+    let (mut line, mut line_start, mut pos) = (0, 0, 0);
+    let (mut previous_line, mut previous_column) = (0, 0);
+
+    for token in tokens {
+        let mut start = token.range.start;
+        while start < token.range.end {
+            for (offset, _) in source[pos..start].match_indices('\n') {
+                line += 1;
+                line_start = pos + offset + 1;
+            }
+            pos = start;
+
+            let end =
+                source[start..token.range.end].find('\n').map_or(token.range.end, |n| start + n);
+            let column = utf16(&source[line_start..start]);
+            if end > start {
+                let delta = if line == previous_line { column - previous_column } else { column };
+                data.extend([
+                    line - previous_line,
+                    delta,
+                    utf16(&source[start..end]),
+                    token.kind as u32,
+                    token.modifiers,
+                ]);
+                (previous_line, previous_column) = (line, column);
+            }
+            if end == token.range.end {
+                break;
+            }
+            line += 1;
+            (line_start, pos, start) = (end + 1, end + 1, end + 1);
+        }
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests;
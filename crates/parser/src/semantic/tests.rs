@@ -0,0 +1,91 @@
+use super::{encode, semantic_tokens, SemanticModifier, SemanticToken, SemanticTokenType};
+use crate::{expansion, LexedStr};
+
+fn classify(input: &str) -> Vec<(&str, SemanticTokenType, bool)> {
+    let lexed = LexedStr::new(input);
+    semantic_tokens(&lexed, &expansion::resolve(&lexed))
+        .into_iter()
+        .map(|token| {
+            (&input[token.range.clone()], token.kind, token.has(SemanticModifier::Definition))
+        })
+        .collect()
+}
+
+#[test]
+fn definitions_and_references() {
+    use SemanticTokenType::*;
+
+    assert_eq!(
+        classify("\\def\\a#1{#1} \\a{2} \\begin{x} \\[ \\] %% doc"),
+        [
+            ("\\def", Keyword, false),
+            ("\\a", Macro, true),
+            ("#1", Variable, false),
+            ("#1", Variable, false),
+            ("\\a", Macro, false),
+            ("2", Number, false),
+            ("\\begin", Keyword, false),
+            ("\\[", MathDelimiter, false),
+            ("\\]", MathDelimiter, false),
+            ("%% doc", Comment, false),
+        ]
+    );
+    // a redefinition introduces the name again
+    assert_eq!(
+        classify("\\newcommand{\\b}{} \\renewcommand{\\b}{\\b}")
+            .into_iter()
+            .filter(|(text, ..)| *text == "\\b")
+            .map(|(.., definition)| definition)
+            .collect::<Vec<_>>(),
+        [true, true, false]
+    );
+}
+
+#[test]
+fn neotex_paths() {
+    use SemanticTokenType::*;
+
+    assert_eq!(
+        classify("\\mod geo \\fn area \\let\\@x = \\geo::area{1} \\use ::geo::area"),
+        [
+            ("\\mod", Keyword, false),
+            ("geo", Namespace, true),
+            ("\\fn", Keyword, false),
+            ("area", Function, true),
+            ("\\let", Keyword, false),
+            ("\\@x", Variable, true),
+            ("\\geo", Namespace, false),
+            ("area", Function, false),
+            ("1", Number, false),
+            ("\\use", Keyword, false),
+            ("geo", Namespace, false),
+            ("area", Function, false),
+        ]
+    );
+}
+
+#[test]
+fn encode_lines() {
+    let source = "% a\n\\x % multi\n\n$\\alpha$";
+    let lexed = LexedStr::new(source);
+    let tokens = semantic_tokens(&lexed, &expansion::resolve(&lexed));
+
+    #[rustfmt::skip]
+    assert_eq!(
+        encode(source, &tokens),
+        [
+            0, 0, 3, 6, 0,
+            1, 0, 2, 0, 0,
+            0, 3, 7, 6, 0,
+            2, 0, 1, 7, 0,
+            0, 1, 6, 0, 0,
+            0, 6, 1, 7, 0,
+        ]
+    );
+}
+
+#[test]
+fn encode_multiline_token() {
+    let token = SemanticToken { range: 2..11, kind: SemanticTokenType::Comment, modifiers: 1 };
+    assert_eq!(encode("x abc\n\u{1F600}d", &[token]), [0, 2, 3, 6, 1, 1, 0, 3, 6, 1]);
+}
@@ -0,0 +1,71 @@
+use alloc::{string::String, sync::Arc};
+use core::{fmt, ops::Deref};
+
+/// Shared, immutable source text.
+///
+/// Cloning only increments a reference count, so the text can be handed to other threads and kept
+/// alive by every result derived from it, independent of the scope which loaded it.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct SourceText(Arc<str>);
+
+impl SourceText {
+    /// Wraps `text`.
+    pub fn new(text: impl Into<Arc<str>>) -> SourceText {
+        SourceText(text.into())
+    }
+
+    /// The text itself.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns `true` if both handles share the same allocation, which is cheaper than comparing
+    /// the texts.
+    pub fn ptr_eq(this: &SourceText, other: &SourceText) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+impl Deref for SourceText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for SourceText {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SourceText {
+    fn from(text: String) -> SourceText {
+        SourceText::new(text)
+    }
+}
+
+impl From<&str> for SourceText {
+    fn from(text: &str) -> SourceText {
+        SourceText::new(text)
+    }
+}
+
+impl From<Arc<str>> for SourceText {
+    fn from(text: Arc<str>) -> SourceText {
+        SourceText(text)
+    }
+}
+
+impl fmt::Debug for SourceText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for SourceText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
@@ -68,6 +68,14 @@ pub enum SyntaxKind {
     GreaterEq,
     /// Comparison (e.g. '==')
     Comparison,
+    /// Plus equal token (e.g., '+=')
+    PlusEq,
+    /// Minus equal token (e.g., '-=')
+    MinusEq,
+    /// Star equal token (e.g., '*=')
+    StarEq,
+    /// Slash equal token (e.g., '/=')
+    SlashEq,
     /// Underscore token (e.g., '_')
     Underscore,
     /// Apostrophe token (e.g., '\'')
@@ -108,6 +116,10 @@ pub enum SyntaxKind {
     LeftArrow, // <-
     /// Right arrow token (e.g., '->')
     RightArrow, // ->
+    /// Any other non alphabetic character (e.g., '`')
+    Symbol,
+    /// Unicode escape sequence (e.g., '\u{1F4A9}')
+    UnicodeEscape,
 
     /// macro expansion token (eg. #name_9_)
     ComplexMacroExpansionToken,
@@ -189,16 +201,221 @@ pub enum SyntaxKind {
     Error,
 }
 
+/// Opening delimiters: `{`, `[` and `(`
+pub const OPEN_DELIMITERS: &[SyntaxKind] = &[OpenBrace, OpenBracket, OpenParen];
+
+/// Closing delimiters: `}`, `]` and `)`
+pub const CLOSING_DELIMITERS: &[SyntaxKind] = &[CloseBrace, CloseBracket, CloseParen];
+
+/// Keywords introducing a macro or environment definition
+pub const DEFINITION_KEYWORDS: &[SyntaxKind] = &[Def, NewCommand, NewEnv];
+
+/// Arithmetic, comparison and arrow operators
+pub const MATH_OPERATORS: &[SyntaxKind] = &[
+    Plus, Minus, Star, Slash, Equal, Less, LessEq, Greater, GreaterEq, Comparison, NotEq, PlusEq,
+    MinusEq, StarEq, SlashEq, LeftArrow, RightArrow,
+];
+
+/// A set of [`SyntaxKind`]s as a bitset, which can be built in constants, e.g. the tokens a
+/// construct can start with or which may follow it.
+///
+/// ```
+/// use parser::{syntax::SyntaxKindSet, SyntaxKind};
+///
+/// const OPEN: SyntaxKindSet = SyntaxKindSet::from_slice(&[SyntaxKind::OpenBrace, SyntaxKind::BeginGroup]);
+/// const GROUPS: SyntaxKindSet = OPEN.union(SyntaxKindSet::new().insert(SyntaxKind::CloseBrace));
+///
+/// assert!(GROUPS.contains(SyntaxKind::BeginGroup) && !GROUPS.contains(SyntaxKind::EndGroup));
+/// assert!(OPEN.intersection(SyntaxKindSet::new().insert(SyntaxKind::Error)).is_empty());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct SyntaxKindSet([u128; 2]);
+
+// every kind has to fit into the two words of a set
+const _: () = assert!((Error as u16) < 256);
+
+impl SyntaxKindSet {
+    const fn word_and_mask(kind: SyntaxKind) -> (usize, u128) {
+        let raw = kind as u16;
+        ((raw / 128) as usize, 1 << (raw % 128))
+    }
+
+    /// The empty set.
+    pub const fn new() -> SyntaxKindSet {
+        SyntaxKindSet([0; 2])
+    }
+
+    /// The set of the `kinds`.
+    pub const fn from_slice(kinds: &[SyntaxKind]) -> SyntaxKindSet {
+        let mut set = SyntaxKindSet::new();
+        let mut i = 0;
+        while i < kinds.len() {
+            set = set.insert(kinds[i]);
+            i += 1;
+        }
+        set
+    }
+
+    /// The set with `kind` added.
+    pub const fn insert(self, kind: SyntaxKind) -> SyntaxKindSet {
+        let (word, mask) = SyntaxKindSet::word_and_mask(kind);
+        let mut words = self.0;
+        words[word] |= mask;
+        SyntaxKindSet(words)
+    }
+
+    /// The kinds in either set.
+    pub const fn union(self, other: SyntaxKindSet) -> SyntaxKindSet {
+        SyntaxKindSet([self.0[0] | other.0[0], self.0[1] | other.0[1]])
+    }
+
+    /// The set with `kind` removed.
+    pub const fn remove(self, kind: SyntaxKind) -> SyntaxKindSet {
+        let (word, mask) = SyntaxKindSet::word_and_mask(kind);
+        let mut words = self.0;
+        words[word] &= !mask;
+        SyntaxKindSet(words)
+    }
+
+    /// The kinds in both sets.
+    pub const fn intersection(self, other: SyntaxKindSet) -> SyntaxKindSet {
+        SyntaxKindSet([self.0[0] & other.0[0], self.0[1] & other.0[1]])
+    }
+
+    /// Returns `true` if `kind` is in the set.
+    pub const fn contains(&self, kind: SyntaxKind) -> bool {
+        let (word, mask) = SyntaxKindSet::word_and_mask(kind);
+        self.0[word] & mask != 0
+    }
+
+    /// Returns `true` if the set has no kinds.
+    pub const fn is_empty(&self) -> bool {
+        self.0[0] == 0 && self.0[1] == 0
+    }
+}
+
+/// The kinds a pass skips as trivia, without meaning for what it looks for.
+///
+/// Every pass uses one of the classes below instead of matching the kinds itself, so passes
+/// working on the same tokens agree on what is skippable. The classes nest: [`PARSE`] is part of
+/// [`LEX`], which is part of [`RESOLVER`], and [`BLANK`] is [`RESOLVER`] without the comments. A
+/// tool with different needs derives its own class with [`with`] and [`without`].
+///
+/// ```
+/// use parser::{syntax::Trivia, SyntaxKind};
+///
+/// assert!(Trivia::RESOLVER.contains(SyntaxKind::Break) && !Trivia::LEX.contains(SyntaxKind::Break));
+/// assert!(Trivia::LEX.contains(SyntaxKind::Comment) && !Trivia::PARSE.contains(SyntaxKind::Comment));
+///
+/// const WORDS: Trivia = Trivia::BLANK.without(SyntaxKind::Break);
+/// assert_eq!(WORDS, Trivia::PARSE);
+/// ```
+///
+/// [`PARSE`]: Trivia::PARSE
+/// [`LEX`]: Trivia::LEX
+/// [`RESOLVER`]: Trivia::RESOLVER
+/// [`BLANK`]: Trivia::BLANK
+/// [`with`]: Trivia::with
+/// [`without`]: Trivia::without
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Trivia(SyntaxKindSet);
+
+impl Trivia {
+    /// Whitespace and single newlines, which separate the arguments of a call. Comments and
+    /// paragraph breaks end an argument list.
+    pub const PARSE: Trivia = Trivia::new(&[Whitespace, Newline]);
+
+    /// Whitespace, single newlines and `%` comments, see [`SpannedToken::is_trivia`].
+    ///
+    /// [`SpannedToken::is_trivia`]: crate::SpannedToken::is_trivia
+    pub const LEX: Trivia = Trivia::PARSE.with(Comment);
+
+    /// Everything without meaning for definitions and the structure of a document: [`LEX`] with
+    /// annotated comments and paragraph breaks, see [`SyntaxKind::is_resolver_trivia`].
+    ///
+    /// [`LEX`]: Trivia::LEX
+    pub const RESOLVER: Trivia = Trivia::LEX.with(AComment).with(Break);
+
+    /// Whitespace, newlines and paragraph breaks, the text between tokens which the formatter
+    /// and [`CompactLexedStr`](crate::CompactLexedStr) may rewrite.
+    pub const BLANK: Trivia = Trivia::PARSE.with(Break);
+
+    /// The class of the `kinds`.
+    pub const fn new(kinds: &[SyntaxKind]) -> Trivia {
+        Trivia(SyntaxKindSet::from_slice(kinds))
+    }
+
+    /// The class with `kind` added.
+    pub const fn with(self, kind: SyntaxKind) -> Trivia {
+        Trivia(self.0.insert(kind))
+    }
+
+    /// The class with `kind` removed.
+    pub const fn without(self, kind: SyntaxKind) -> Trivia {
+        Trivia(self.0.remove(kind))
+    }
+
+    /// Returns `true` if `kind` is trivia of the class.
+    pub const fn contains(&self, kind: SyntaxKind) -> bool {
+        self.0.contains(kind)
+    }
+
+    /// The kinds of the class.
+    pub const fn kinds(&self) -> SyntaxKindSet {
+        self.0
+    }
+}
+
 impl SyntaxKind {
+    /// The kind whose discriminant is `raw`, the inverse of `kind as u16`.
+    pub fn from_raw(raw: u16) -> Option<SyntaxKind> {
+        // SAFETY: `SyntaxKind` is a fieldless `repr(u16)` enum without explicit discriminants, so
+        // every value from 0 up to the last variant `Error` is a valid kind
+        (raw <= Error as u16).then(|| unsafe { core::mem::transmute::<u16, SyntaxKind>(raw) })
+    }
+
+    /// Returns `true` for `{`, `[` and `(`.
+    pub fn is_open_delimiter(&self) -> bool {
+        OPEN_DELIMITERS.contains(self)
+    }
+
+    /// Returns `true` for `}`, `]` and `)`.
+    pub fn is_closing_delimiter(&self) -> bool {
+        CLOSING_DELIMITERS.contains(self)
+    }
+
+    /// Returns the counterpart of an opening or closing delimiter.
+    pub fn matching_delimiter(&self) -> Option<SyntaxKind> {
+        match self {
+            OpenBrace => Some(CloseBrace),
+            OpenBracket => Some(CloseBracket),
+            OpenParen => Some(CloseParen),
+            CloseBrace => Some(OpenBrace),
+            CloseBracket => Some(OpenBracket),
+            CloseParen => Some(OpenParen),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` for keywords introducing a definition, e.g. `\newcommand`.
+    pub fn is_definition_keyword(&self) -> bool {
+        DEFINITION_KEYWORDS.contains(self)
+    }
+
+    /// Returns `true` for arithmetic, comparison and arrow operators.
+    pub fn is_math_operator(&self) -> bool {
+        MATH_OPERATORS.contains(self)
+    }
+
+    /// Returns `true` for the kinds of [`Trivia::RESOLVER`], which the resolver and the AST
+    /// skip: whitespace, newlines, paragraph breaks and comments.
     pub fn is_resolver_trivia(&self) -> bool {
-        matches!(
-            self,
-            SyntaxKind::Whitespace
-                | SyntaxKind::Newline
-                | SyntaxKind::Comment
-                | SyntaxKind::AComment
-                | SyntaxKind::Break
-        )
+        Trivia::RESOLVER.contains(*self)
+    }
+
+    /// Returns `true` if the kind is trivia of `class`.
+    pub fn is_trivia(&self, class: Trivia) -> bool {
+        class.contains(*self)
     }
 
     pub fn is_special(&self) -> bool {
@@ -221,19 +438,8 @@ impl SyntaxKind {
     }
 }
 
-pub(crate) trait Trivia {
-    fn is_trivia(&self) -> bool;
-}
-
-impl Trivia for SyntaxKind {
-    #[inline(always)]
-    fn is_trivia(&self) -> bool {
-        matches!(self, SyntaxKind::Whitespace | SyntaxKind::Newline | SyntaxKind::Comment)
-    }
-}
-
-impl std::fmt::Display for SyntaxKind {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", format!("{self:?}"))
+impl core::fmt::Display for SyntaxKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{self:?}")
     }
 }
@@ -0,0 +1,20 @@
+//! The lexer has to stay usable without the standard library.
+
+use std::process::Command;
+
+#[test]
+fn builds_without_std() {
+    // the unit tests of the lexer run without the standard library as well
+    for profile in ["dev", "test"] {
+        let output = Command::new(env!("CARGO"))
+            .args(["check", "--package", "parser", "--lib", "--no-default-features", "--quiet"])
+            .args(["--profile", profile])
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            // a separate target directory avoids waiting for the lock held by the running build
+            .env("CARGO_TARGET_DIR", concat!(env!("CARGO_TARGET_TMPDIR"), "/no-std"))
+            .output()
+            .expect("failed to run cargo");
+
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+}